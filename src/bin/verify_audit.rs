@@ -0,0 +1,20 @@
+use quantum_cryptography::test_vectors::to_hex;
+use quantum_cryptography::*;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: verify_audit <audit-log>");
+            std::process::exit(2);
+        }
+    };
+
+    match verify_audit_file(&path) {
+        Ok((entries, head)) => println!("{}: {} entries, head {}", path, entries, to_hex(&head)),
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::*;
+use merlin::Transcript;
+
+// Leaf binding a proof to its position and statement
+fn aggregation_leaf(index: usize, statement: &[u8], proof: &SNARKProof) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&(index as u64).to_le_bytes());
+    hasher.update(&(statement.len() as u64).to_le_bytes());
+    hasher.update(statement);
+    hasher.update(&proof.to_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+// Positions of the proofs to open: all of them if there are no more than
+// `samples`, otherwise `samples` drawn from a transcript over the root
+fn sampled_positions(statements: &[&[u8]], root: &[u8; 32], samples: usize) -> Vec<usize> {
+    let count = statements.len();
+    if count <= samples {
+        return (0..count).collect();
+    }
+    let mut transcript = Transcript::new(b"snark-aggregation");
+    transcript.append_u64(b"count", count as u64);
+    for statement in statements {
+        transcript.append_message(b"statement", statement);
+    }
+    transcript.append_message(b"root", root);
+    (0..samples)
+        .map(|_| {
+            let mut bytes = [0u8; 8];
+            transcript.challenge_bytes(b"position", &mut bytes);
+            (u64::from_le_bytes(bytes) % count as u64) as usize
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct SpotCheckOpening {
+    proof: SNARKProof,
+    path: Vec<[u8; 32]>,
+}
+
+// A Merkle root over every (statement, proof) and the openings of the
+// proofs at the positions that root selects
+#[derive(Clone, Debug)]
+pub struct SpotCheckProof {
+    count: usize,
+    root: [u8; 32],
+    openings: Vec<SpotCheckOpening>,
+}
+
+impl SpotCheckProof {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn root(&self) -> &[u8; 32] {
+        &self.root
+    }
+}
+
+// Probabilistic spot-checking, not aggregation: a batch of proofs for one
+// circuit is committed to in a Merkle tree and only `samples` of them, at
+// positions drawn from the root, are opened and re-verified, so the result
+// is `samples` proofs plus logarithmic paths whatever the batch size. The
+// proofs' Fiat-Shamir checks do not combine linearly, so nothing is folded.
+// A batch in which a fraction e of the proofs is invalid passes one draw
+// with probability (1 - e)^samples, but the prover picks the root: by
+// re-proving valid statements it can draw again, and after 2^k attempts
+// passes with probability up to 2^k (1 - e)^samples. A single invalid proof
+// in a large batch is almost never sampled. Batches of at most `samples`
+// proofs are opened in full and checked exactly.
+#[derive(Clone, Copy, Debug)]
+pub struct SpotCheckAggregator {
+    samples: usize,
+}
+
+impl Default for SpotCheckAggregator {
+    fn default() -> Self {
+        Self { samples: 64 }
+    }
+}
+
+impl SpotCheckAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    // Refuses batches holding an invalid proof, naming its position
+    pub fn spot_check(
+        &self,
+        verifier: &SNARKVerifier,
+        batch: &[(&[u8], &SNARKProof)],
+    ) -> Result<SpotCheckProof, CryptoError> {
+        for (index, (statement, proof)) in batch.iter().enumerate() {
+            if !verifier.verify(statement, proof)? {
+                return Err(CryptoError::InvalidProof(index));
+            }
+        }
+        let mut leaves: Vec<[u8; 32]> = batch
+            .iter()
+            .enumerate()
+            .map(|(index, (statement, proof))| aggregation_leaf(index, statement, proof))
+            .collect();
+        leaves.resize(batch.len().max(1).next_power_of_two(), [0u8; 32]);
+        let tree = MerkleTree::new(leaves);
+        let root = tree.root();
+
+        let statements: Vec<&[u8]> = batch.iter().map(|(statement, _)| *statement).collect();
+        let openings = sampled_positions(&statements, &root, self.samples)
+            .into_iter()
+            .map(|position| SpotCheckOpening {
+                proof: batch[position].1.clone(),
+                path: tree.path(position),
+            })
+            .collect();
+        Ok(SpotCheckProof {
+            count: batch.len(),
+            root,
+            openings,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        verifier: &SNARKVerifier,
+        statements: &[&[u8]],
+        proof: &SpotCheckProof,
+    ) -> Result<bool, CryptoError> {
+        if proof.count != statements.len() {
+            return Ok(false);
+        }
+        let positions = sampled_positions(statements, &proof.root, self.samples);
+        if positions.len() != proof.openings.len() {
+            return Ok(false);
+        }
+        let depth = statements.len().max(1).next_power_of_two().trailing_zeros() as usize;
+        for (position, opening) in positions.into_iter().zip(&proof.openings) {
+            let leaf = aggregation_leaf(position, statements[position], &opening.proof);
+            if opening.path.len() != depth
+                || MerkleTree::root_from_path(&leaf, position, &opening.path) != proof.root
+                || !verifier.verify(statements[position], &opening.proof)?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // Proofs that x * x = i for statements i = 0, 1, ...
+    fn batch(count: u64) -> (SNARKVerifier, Vec<Vec<u8>>, Vec<SNARKProof>) {
+        let mut rng = ChaCha20Rng::seed_from_u64(1096);
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        let (public_parameters, proving_key, verification_key) = snark_setup(cs, &mut rng);
+        let prover = SNARKProver::new(public_parameters.clone(), proving_key);
+        let statements: Vec<Vec<u8>> = (0..count).map(|i| i.to_le_bytes().to_vec()).collect();
+        let proofs = (0..count)
+            .zip(&statements)
+            .map(|(i, statement)| {
+                let x = Scalar::from(i);
+                let witness = Assignment::new(vec![x * x], vec![x]);
+                prover.prove(statement, &witness, &mut rng).unwrap()
+            })
+            .collect();
+        (
+            SNARKVerifier::new(public_parameters, verification_key),
+            statements,
+            proofs,
+        )
+    }
+
+    #[test]
+    fn sampled_batches_verify() {
+        let (verifier, statements, proofs) = batch(20);
+        let statements: Vec<&[u8]> = statements.iter().map(Vec::as_slice).collect();
+        let batch: Vec<(&[u8], &SNARKProof)> = statements.iter().copied().zip(&proofs).collect();
+        for aggregator in [
+            SpotCheckAggregator::new(),
+            SpotCheckAggregator::new().with_samples(4),
+        ] {
+            let proof = aggregator.spot_check(&verifier, &batch).unwrap();
+            assert_eq!(proof.count(), 20);
+            assert!(aggregator.verify(&verifier, &statements, &proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_proofs_and_other_statements() {
+        let (verifier, statements, proofs) = batch(8);
+        let statements: Vec<&[u8]> = statements.iter().map(Vec::as_slice).collect();
+        let mut batch: Vec<(&[u8], &SNARKProof)> =
+            statements.iter().copied().zip(&proofs).collect();
+        let aggregator = SpotCheckAggregator::new();
+        let proof = aggregator.spot_check(&verifier, &batch).unwrap();
+
+        let mut swapped = statements.clone();
+        swapped.swap(0, 1);
+        assert!(!aggregator.verify(&verifier, &swapped, &proof).unwrap());
+        let mut tampered = proof.clone();
+        tampered.openings[3].proof = proofs[4].clone();
+        assert!(!aggregator
+            .verify(&verifier, &statements, &tampered)
+            .unwrap());
+
+        batch[5].1 = &proofs[6];
+        assert_eq!(
+            aggregator.spot_check(&verifier, &batch).unwrap_err(),
+            CryptoError::InvalidProof(5)
+        );
+    }
+
+    #[test]
+    fn rejects_a_different_number_of_statements() {
+        let (verifier, statements, proofs) = batch(8);
+        let statements: Vec<&[u8]> = statements.iter().map(Vec::as_slice).collect();
+        let batch: Vec<(&[u8], &SNARKProof)> = statements.iter().copied().zip(&proofs).collect();
+        let aggregator = SpotCheckAggregator::new().with_samples(4);
+        let proof = aggregator.spot_check(&verifier, &batch).unwrap();
+        assert!(!aggregator
+            .verify(&verifier, &statements[..7], &proof)
+            .unwrap());
+        assert!(!aggregator.verify(&verifier, &[], &proof).unwrap());
+    }
+}
@@ -0,0 +1,469 @@
+use super::*;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use merlin::Transcript;
+use rand::Rng;
+
+// Vector bases G and H for up to `party_capacity` proofs of up to
+// `gens_capacity` bits each, hashed to the curve so nobody knows their
+// discrete logarithms
+#[derive(Clone, Debug)]
+pub struct BulletproofGens {
+    pub gens_capacity: usize,
+    pub party_capacity: usize,
+    g: Vec<RistrettoPoint>,
+    h: Vec<RistrettoPoint>,
+}
+
+impl BulletproofGens {
+    pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
+        let size = gens_capacity * party_capacity;
+        Self {
+            gens_capacity,
+            party_capacity,
+            g: (0..size)
+                .map(|i| indexed_generator(b"bulletproofs G", i))
+                .collect(),
+            h: (0..size)
+                .map(|i| indexed_generator(b"bulletproofs H", i))
+                .collect(),
+        }
+    }
+}
+
+// 1, x, x^2, ...
+fn powers(x: Scalar, count: usize) -> Vec<Scalar> {
+    let mut power = Scalar::ONE;
+    (0..count)
+        .map(|_| {
+            let current = power;
+            power *= x;
+            current
+        })
+        .collect()
+}
+
+// Aggregated range proof that each of m committed values lies in [0, 2^n)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeProof {
+    a: CompressedRistretto,
+    s: CompressedRistretto,
+    t1: CompressedRistretto,
+    t2: CompressedRistretto,
+    t_x: Scalar,
+    t_x_blinding: Scalar,
+    e_blinding: Scalar,
+    inner_product: InnerProductProof,
+}
+
+fn check_dimensions(bp_gens: &BulletproofGens, n: usize, m: usize) -> Result<(), CryptoError> {
+    if !matches!(n, 8 | 16 | 32 | 64) || n > bp_gens.gens_capacity {
+        return Err(CryptoError::RangeTooWide(n));
+    }
+    if m == 0 || !m.is_power_of_two() || m > bp_gens.party_capacity {
+        return Err(CryptoError::InvalidAggregation(m));
+    }
+    Ok(())
+}
+
+fn begin_range_proof(transcript: &mut Transcript, n: usize, m: usize) {
+    transcript.append_message(b"dom-sep", b"rangeproof");
+    transcript.append_u64(b"n", n as u64);
+    transcript.append_u64(b"m", m as u64);
+}
+
+impl RangeProof {
+    pub fn prove_single<R: Rng + ?Sized>(
+        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        value: u64,
+        blinding: &Scalar,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(RangeProof, CompressedRistretto), CryptoError> {
+        let (proof, commitments) =
+            Self::prove_multiple(pc_gens, bp_gens, transcript, &[value], &[*blinding], n, rng)?;
+        Ok((proof, commitments[0]))
+    }
+
+    // One proof for every value, which must number a power of two
+    pub fn prove_multiple<R: Rng + ?Sized>(
+        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut R,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), CryptoError> {
+        let m = values.len();
+        check_dimensions(bp_gens, n, m)?;
+        if blindings.len() != m {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        if n < 64 && values.iter().any(|v| v >> n != 0) {
+            return Err(CryptoError::ValueOutOfRange);
+        }
+        let size = n * m;
+        let (g, h) = (&bp_gens.g[..size], &bp_gens.h[..size]);
+
+        begin_range_proof(transcript, n, m);
+        let commitments: Vec<CompressedRistretto> = values
+            .iter()
+            .zip(blindings)
+            .map(|(v, gamma)| pc_gens.commit(Scalar::from(*v), *gamma).compress())
+            .collect();
+        for commitment in &commitments {
+            transcript.append_message(b"V", commitment.as_bytes());
+        }
+
+        let a_l: Vec<Scalar> = values
+            .iter()
+            .flat_map(|v| (0..n).map(move |i| Scalar::from((v >> i) & 1)))
+            .collect();
+        let a_r: Vec<Scalar> = a_l.iter().map(|bit| bit - Scalar::ONE).collect();
+        let alpha = random_scalar(rng);
+        let a = RistrettoPoint::multiscalar_mul(
+            [alpha].iter().chain(&a_l).chain(&a_r),
+            [pc_gens.b_blinding].iter().chain(g).chain(h),
+        )
+        .compress();
+
+        let s_l: Vec<Scalar> = (0..size).map(|_| random_scalar(rng)).collect();
+        let s_r: Vec<Scalar> = (0..size).map(|_| random_scalar(rng)).collect();
+        let rho = random_scalar(rng);
+        let s = RistrettoPoint::multiscalar_mul(
+            [rho].iter().chain(&s_l).chain(&s_r),
+            [pc_gens.b_blinding].iter().chain(g).chain(h),
+        )
+        .compress();
+        transcript.append_message(b"A", a.as_bytes());
+        transcript.append_message(b"S", s.as_bytes());
+        let y = transcript_scalar(transcript, b"y");
+        let z = transcript_scalar(transcript, b"z");
+
+        // l(X) = l0 + l1 X and r(X) = r0 + r1 X, with <l(X), r(X)> = t(X)
+        let y_powers = powers(y, size);
+        let two_powers = powers(Scalar::from(2u64), n);
+        let z_powers = powers(z, m + 2);
+        let l0: Vec<Scalar> = a_l.iter().map(|x| x - z).collect();
+        let l1 = s_l;
+        let r0: Vec<Scalar> = (0..size)
+            .map(|i| y_powers[i] * (a_r[i] + z) + z_powers[2 + i / n] * two_powers[i % n])
+            .collect();
+        let r1: Vec<Scalar> = (0..size).map(|i| y_powers[i] * s_r[i]).collect();
+        let t1 = inner_product(&l0, &r1) + inner_product(&l1, &r0);
+        let t2 = inner_product(&l1, &r1);
+
+        let tau1 = random_scalar(rng);
+        let tau2 = random_scalar(rng);
+        let t1_commitment = pc_gens.commit(t1, tau1).compress();
+        let t2_commitment = pc_gens.commit(t2, tau2).compress();
+        transcript.append_message(b"T1", t1_commitment.as_bytes());
+        transcript.append_message(b"T2", t2_commitment.as_bytes());
+        let x = transcript_scalar(transcript, b"x");
+
+        let l: Vec<Scalar> = (0..size).map(|i| l0[i] + l1[i] * x).collect();
+        let r: Vec<Scalar> = (0..size).map(|i| r0[i] + r1[i] * x).collect();
+        let t_x = inner_product(&l, &r);
+        let t_x_blinding = tau2 * x * x
+            + tau1 * x
+            + blindings
+                .iter()
+                .enumerate()
+                .map(|(j, gamma)| z_powers[2 + j] * gamma)
+                .sum::<Scalar>();
+        let e_blinding = alpha + rho * x;
+        transcript.append_message(b"t_x", t_x.as_bytes());
+        transcript.append_message(b"t_x_blinding", t_x_blinding.as_bytes());
+        transcript.append_message(b"e_blinding", e_blinding.as_bytes());
+        let w = transcript_scalar(transcript, b"w");
+
+        // The inner product is over G and H' = y^-i H
+        let y_inverse = y.invert();
+        let h_prime: Vec<RistrettoPoint> = h
+            .iter()
+            .zip(powers(y_inverse, size))
+            .map(|(point, factor)| point * factor)
+            .collect();
+        let inner_product =
+            InnerProductProof::create(transcript, pc_gens.b * w, g.to_vec(), h_prime, l, r);
+
+        Ok((
+            RangeProof {
+                a,
+                s,
+                t1: t1_commitment,
+                t2: t2_commitment,
+                t_x,
+                t_x_blinding,
+                e_blinding,
+                inner_product,
+            },
+            commitments,
+        ))
+    }
+
+    pub fn verify_single(
+        &self,
+        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        commitment: &CompressedRistretto,
+        n: usize,
+    ) -> Result<bool, CryptoError> {
+        self.verify_multiple(pc_gens, bp_gens, transcript, &[*commitment], n)
+    }
+
+    pub fn verify_multiple(
+        &self,
+        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens,
+        transcript: &mut Transcript,
+        commitments: &[CompressedRistretto],
+        n: usize,
+    ) -> Result<bool, CryptoError> {
+        let m = commitments.len();
+        check_dimensions(bp_gens, n, m)?;
+        let size = n * m;
+        let (g, h) = (&bp_gens.g[..size], &bp_gens.h[..size]);
+
+        begin_range_proof(transcript, n, m);
+        for commitment in commitments {
+            transcript.append_message(b"V", commitment.as_bytes());
+        }
+        transcript.append_message(b"A", self.a.as_bytes());
+        transcript.append_message(b"S", self.s.as_bytes());
+        let y = transcript_scalar(transcript, b"y");
+        let z = transcript_scalar(transcript, b"z");
+        transcript.append_message(b"T1", self.t1.as_bytes());
+        transcript.append_message(b"T2", self.t2.as_bytes());
+        let x = transcript_scalar(transcript, b"x");
+        transcript.append_message(b"t_x", self.t_x.as_bytes());
+        transcript.append_message(b"t_x_blinding", self.t_x_blinding.as_bytes());
+        transcript.append_message(b"e_blinding", self.e_blinding.as_bytes());
+        let w = transcript_scalar(transcript, b"w");
+
+        let decompress =
+            |point: &CompressedRistretto| point.decompress().ok_or(CryptoError::InvalidPoint);
+        let y_powers = powers(y, size);
+        let two_powers = powers(Scalar::from(2u64), n);
+        let z_powers = powers(z, m + 3);
+
+        // sum z^(2+j) V_j + x T1 + x^2 T2 must commit to t_x - delta under
+        // t_x_blinding
+        let sum_y: Scalar = y_powers.iter().sum();
+        let sum_two: Scalar = two_powers.iter().sum();
+        let delta = (z - z * z) * sum_y - (0..m).map(|j| z_powers[3 + j]).sum::<Scalar>() * sum_two;
+        let scalars: Vec<Scalar> = (0..m).map(|j| z_powers[2 + j]).chain([x, x * x]).collect();
+        let combined = PedersenCommitment::linear_combination(
+            &scalars,
+            &commitments
+                .iter()
+                .chain([&self.t1, &self.t2])
+                .map(PedersenCommitment::decompress)
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+        if !pc_gens.open(&combined, self.t_x - delta, self.t_x_blinding) {
+            return Ok(false);
+        }
+
+        // P = A + x S - z <1, G> + <z + z^(2+j) 2^k y^-i, H> - e_blinding B~
+        let y_inverse_powers = powers(y.invert(), size);
+        let h_prime: Vec<RistrettoPoint> = h
+            .iter()
+            .zip(&y_inverse_powers)
+            .map(|(point, factor)| point * factor)
+            .collect();
+        let scalars: Vec<Scalar> = [Scalar::ONE, x, -self.e_blinding]
+            .into_iter()
+            .chain((0..size).map(|_| -z))
+            .chain(
+                (0..size)
+                    .map(|i| z + z_powers[2 + i / n] * two_powers[i % n] * y_inverse_powers[i]),
+            )
+            .collect();
+        let points: Vec<RistrettoPoint> = [
+            decompress(&self.a)?,
+            decompress(&self.s)?,
+            pc_gens.b_blinding,
+        ]
+        .into_iter()
+        .chain(g.iter().chain(h).copied())
+        .collect();
+        let p = ristretto_msm(&scalars, &points);
+        let q = pc_gens.b * w;
+        self.inner_product
+            .verify_with(transcript, q, p + q * self.t_x, g, &h_prime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn single_proofs_round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1091);
+        let (pc_gens, bp_gens) = (PedersenGens::default(), BulletproofGens::new(64, 4));
+        let blinding = Scalar::from(99u64);
+        let (proof, commitment) = RangeProof::prove_single(
+            &pc_gens,
+            &bp_gens,
+            &mut Transcript::new(b"test"),
+            1037,
+            &blinding,
+            32,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(proof
+            .verify_single(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                &commitment,
+                32
+            )
+            .unwrap());
+        assert!(!proof
+            .verify_single(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"other"),
+                &commitment,
+                32
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn aggregated_proofs_round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1091);
+        let (pc_gens, bp_gens) = (PedersenGens::default(), BulletproofGens::new(64, 4));
+        let values = [0, 1, 12345, u64::MAX];
+        let blindings: Vec<Scalar> = (0..4u64).map(|i| Scalar::from(i + 5)).collect();
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &pc_gens,
+            &bp_gens,
+            &mut Transcript::new(b"test"),
+            &values,
+            &blindings,
+            64,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(proof
+            .verify_multiple(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                &commitments,
+                64
+            )
+            .unwrap());
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 1);
+        assert!(!proof
+            .verify_multiple(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                &swapped,
+                64
+            )
+            .unwrap());
+        assert_eq!(
+            proof.verify_multiple(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                &commitments[..3],
+                64
+            ),
+            Err(CryptoError::InvalidAggregation(3))
+        );
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1091);
+        let (pc_gens, bp_gens) = (PedersenGens::default(), BulletproofGens::new(64, 4));
+        assert_eq!(
+            RangeProof::prove_single(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                256,
+                &Scalar::ONE,
+                8,
+                &mut rng,
+            )
+            .unwrap_err(),
+            CryptoError::ValueOutOfRange
+        );
+
+        // A proof for 200 does not carry over to a commitment to 456
+        let (proof, _) = RangeProof::prove_single(
+            &pc_gens,
+            &bp_gens,
+            &mut Transcript::new(b"test"),
+            200,
+            &Scalar::ONE,
+            8,
+            &mut rng,
+        )
+        .unwrap();
+        let out_of_range = pc_gens.commit(Scalar::from(456u64), Scalar::ONE).compress();
+        assert!(!proof
+            .verify_single(
+                &pc_gens,
+                &bp_gens,
+                &mut Transcript::new(b"test"),
+                &out_of_range,
+                8
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn rejects_tampered_proofs() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1091);
+        let (pc_gens, bp_gens) = (PedersenGens::default(), BulletproofGens::new(64, 4));
+        let (proof, commitment) = RangeProof::prove_single(
+            &pc_gens,
+            &bp_gens,
+            &mut Transcript::new(b"test"),
+            77,
+            &Scalar::from(3u64),
+            16,
+            &mut rng,
+        )
+        .unwrap();
+        let verifies = |proof: &RangeProof| {
+            proof
+                .verify_single(
+                    &pc_gens,
+                    &bp_gens,
+                    &mut Transcript::new(b"test"),
+                    &commitment,
+                    16,
+                )
+                .unwrap()
+        };
+        assert!(verifies(&proof));
+        let mut tampered = proof.clone();
+        tampered.t_x += Scalar::ONE;
+        assert!(!verifies(&tampered));
+        let mut tampered = proof.clone();
+        tampered.e_blinding += Scalar::ONE;
+        assert!(!verifies(&tampered));
+        let mut tampered = proof;
+        tampered.t1 = tampered.t2;
+        assert!(!verifies(&tampered));
+    }
+}
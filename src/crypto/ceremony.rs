@@ -0,0 +1,365 @@
+use super::*;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+fn absorb_keys(
+    hasher: &mut blake3::Hasher,
+    public_parameters: &PublicParameters,
+    verification_key: &VerificationKey,
+) {
+    hasher.update(public_parameters.generator.compress().as_bytes());
+    hasher.update(&(verification_key.bases.len() as u64).to_le_bytes());
+    for base in &verification_key.bases {
+        hasher.update(base.as_bytes());
+    }
+    hasher.update(verification_key.statement_scalar.as_bytes());
+    hasher.update(verification_key.blinding_factor.as_bytes());
+    hasher.update(verification_key.verification_point.compress().as_bytes());
+}
+
+fn dleq_challenge(digest: &[u8; 32], label: &'static [u8], points: [&RistrettoPoint; 5]) -> Scalar {
+    let mut transcript = Transcript::new(b"setup-ceremony");
+    transcript.append_message(b"digest", digest);
+    transcript.append_message(b"component", label);
+    for point in points {
+        transcript.append_message(b"point", point.compress().as_bytes());
+    }
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// Chaum-Pedersen proof that factor_point = x B and after = x before for one
+// secret x, bound to the digest of the state it updates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DleqProof {
+    challenge: Scalar,
+    response: Scalar,
+}
+
+impl DleqProof {
+    fn prove<R: Rng + ?Sized>(
+        digest: &[u8; 32],
+        label: &'static [u8],
+        factor: &Scalar,
+        before: &RistrettoPoint,
+        rng: &mut R,
+    ) -> Self {
+        let nonce = random_scalar(rng);
+        let factor_point = RistrettoPoint::mul_base(factor);
+        let challenge = dleq_challenge(
+            digest,
+            label,
+            [
+                &factor_point,
+                before,
+                &(before * factor),
+                &RistrettoPoint::mul_base(&nonce),
+                &(before * nonce),
+            ],
+        );
+        Self {
+            challenge,
+            response: nonce + challenge * factor,
+        }
+    }
+
+    fn verify(
+        &self,
+        digest: &[u8; 32],
+        label: &'static [u8],
+        factor_point: &RistrettoPoint,
+        before: &RistrettoPoint,
+        after: &RistrettoPoint,
+    ) -> bool {
+        let nonce_base = RISTRETTO_BASEPOINT_POINT * self.response - factor_point * self.challenge;
+        let nonce_before = before * self.response - after * self.challenge;
+        self.challenge
+            == dleq_challenge(
+                digest,
+                label,
+                [factor_point, before, after, &nonce_base, &nonce_before],
+            )
+    }
+}
+
+// One participant's update: the new keys, and for each of the two points
+// the contributor's factor in the base group with a proof that the point
+// was multiplied by it. The scalar components are public, so their factors
+// need no proof beyond being nonzero.
+#[derive(Clone, Debug)]
+pub struct Contribution {
+    public_parameters: PublicParameters,
+    verification_key: VerificationKey,
+    generator_factor: RistrettoPoint,
+    generator_proof: DleqProof,
+    verification_factor: RistrettoPoint,
+    verification_proof: DleqProof,
+}
+
+impl Contribution {
+    pub fn public_parameters(&self) -> &PublicParameters {
+        &self.public_parameters
+    }
+
+    pub fn verification_key(&self) -> &VerificationKey {
+        &self.verification_key
+    }
+}
+
+// Keys after some number of contributions, with a digest chaining every
+// contribution so far; a contribution is only valid on the state it was
+// made for.
+//
+// This keeps nothing secret. The toy SNARK's verification key is its own
+// trapdoor: the bases, statement scalar and blinding factor a contribution
+// draws are published in the key it produces, and a verifier needs them in
+// the clear. What the ceremony gives is provenance: keys no single party
+// chose, from a chain anyone can replay with verify_ceremony, ending in a
+// beacon nobody controls. PlonkSrs is the setup that an honest contributor
+// actually protects.
+#[derive(Clone, Debug)]
+pub struct SetupState {
+    public_parameters: PublicParameters,
+    verification_key: VerificationKey,
+    digest: [u8; 32],
+}
+
+impl SetupState {
+    // Keys anyone can recompute, with every component equal to 1
+    pub fn genesis(constraint_system: &ConstraintSystem) -> Self {
+        let verification_key = VerificationKey {
+            generator: RISTRETTO_BASEPOINT_POINT,
+            bases: vec![Scalar::ONE; constraint_system.num_public() + 1],
+            statement_scalar: Scalar::ONE,
+            blinding_factor: Scalar::ONE,
+            verification_point: RISTRETTO_BASEPOINT_POINT,
+            transcript_digest: [0u8; 32],
+        };
+        let public_parameters = PublicParameters {
+            generator: verification_key.generator,
+            key_digest: verification_key.digest(),
+        };
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"setup-ceremony genesis");
+        absorb_keys(&mut hasher, &public_parameters, &verification_key);
+        Self {
+            public_parameters,
+            verification_key,
+            digest: *hasher.finalize().as_bytes(),
+        }
+    }
+
+    pub fn public_parameters(&self) -> &PublicParameters {
+        &self.public_parameters
+    }
+
+    pub fn verification_key(&self) -> &VerificationKey {
+        &self.verification_key
+    }
+
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    // Multiplies every component by a fresh factor. The factors of the two
+    // points are dropped when this returns; those of the scalars can be read
+    // off the new key
+    pub fn contribute<R: Rng + ?Sized>(&self, rng: &mut R) -> Contribution {
+        let generator_factor = random_nonzero_scalar(rng);
+        let verification_factor = random_nonzero_scalar(rng);
+        let generator = self.public_parameters.generator * generator_factor;
+        let key = &self.verification_key;
+        let verification_key = VerificationKey {
+            generator,
+            bases: key
+                .bases
+                .iter()
+                .map(|base| base * random_nonzero_scalar(rng))
+                .collect(),
+            statement_scalar: key.statement_scalar * random_nonzero_scalar(rng),
+            blinding_factor: key.blinding_factor * random_nonzero_scalar(rng),
+            verification_point: key.verification_point * verification_factor,
+            transcript_digest: self.digest,
+        };
+        Contribution {
+            public_parameters: PublicParameters {
+                generator,
+                key_digest: verification_key.digest(),
+            },
+            verification_key,
+            generator_factor: RistrettoPoint::mul_base(&generator_factor),
+            generator_proof: DleqProof::prove(
+                &self.digest,
+                b"generator",
+                &generator_factor,
+                &self.public_parameters.generator,
+                rng,
+            ),
+            verification_factor: RistrettoPoint::mul_base(&verification_factor),
+            verification_proof: DleqProof::prove(
+                &self.digest,
+                b"verification point",
+                &verification_factor,
+                &key.verification_point,
+                rng,
+            ),
+        }
+    }
+
+    // The state after `contribution`, if it was made for this state and
+    // only rerandomizes it
+    pub fn apply(&self, contribution: &Contribution) -> Result<SetupState, CryptoError> {
+        let (old, new) = (&self.verification_key, &contribution.verification_key);
+        if new.bases.len() != old.bases.len()
+            || new.transcript_digest != self.digest
+            || new.validate(&contribution.public_parameters).is_err()
+            || !contribution.generator_proof.verify(
+                &self.digest,
+                b"generator",
+                &contribution.generator_factor,
+                &self.public_parameters.generator,
+                &contribution.public_parameters.generator,
+            )
+            || !contribution.verification_proof.verify(
+                &self.digest,
+                b"verification point",
+                &contribution.verification_factor,
+                &old.verification_point,
+                &new.verification_point,
+            )
+        {
+            return Err(CryptoError::InvalidContribution);
+        }
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.digest);
+        absorb_keys(&mut hasher, &contribution.public_parameters, new);
+        hasher.update(contribution.generator_factor.compress().as_bytes());
+        hasher.update(contribution.verification_factor.compress().as_bytes());
+        Ok(SetupState {
+            public_parameters: contribution.public_parameters.clone(),
+            verification_key: new.clone(),
+            digest: *hasher.finalize().as_bytes(),
+        })
+    }
+
+    // The last contribution, drawn from a public beacon (e.g. a future block
+    // hash) hashed `iterations` times so nobody can steer the outcome by
+    // grinding on the final state. Deterministic, so anyone can replay it.
+    pub fn beacon_contribution(&self, beacon: &[u8], iterations: u32) -> Contribution {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.digest);
+        hasher.update(beacon);
+        let mut seed = *hasher.finalize().as_bytes();
+        for _ in 0..iterations {
+            seed = *blake3::hash(&seed).as_bytes();
+        }
+        self.contribute(&mut ChaCha20Rng::from_seed(seed))
+    }
+
+    pub fn finalize(
+        self,
+        constraint_system: ConstraintSystem,
+        beacon: &[u8],
+        iterations: u32,
+    ) -> Result<(PublicParameters, ProvingKey, VerificationKey), CryptoError> {
+        let last = self.apply(&self.beacon_contribution(beacon, iterations))?;
+        let proving_key = ProvingKey::new(constraint_system, last.verification_key.clone());
+        Ok((last.public_parameters, proving_key, last.verification_key))
+    }
+}
+
+// Replays a whole ceremony from genesis: every contribution in order, then
+// the beacon. Fails on the first contribution that does not apply.
+pub fn verify_ceremony(
+    constraint_system: &ConstraintSystem,
+    contributions: &[Contribution],
+    beacon: &[u8],
+    iterations: u32,
+) -> Result<(PublicParameters, VerificationKey), CryptoError> {
+    let mut state = SetupState::genesis(constraint_system);
+    for contribution in contributions {
+        state = state.apply(contribution)?;
+    }
+    let last = state.apply(&state.beacon_contribution(beacon, iterations))?;
+    Ok((last.public_parameters, last.verification_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_circuit() -> ConstraintSystem {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        cs
+    }
+
+    #[test]
+    fn ceremonies_replay_to_working_keys() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1098);
+        let cs = square_circuit();
+        let genesis = SetupState::genesis(&cs);
+        let first = genesis.contribute(&mut rng);
+        let state = genesis.apply(&first).unwrap();
+        let second = state.contribute(&mut rng);
+        let state = state.apply(&second).unwrap();
+        assert_ne!(state.digest(), genesis.digest());
+
+        let (public_parameters, proving_key, verification_key) =
+            state.finalize(cs.clone(), b"beacon", 16).unwrap();
+        let (replayed_parameters, replayed_key) =
+            verify_ceremony(&cs, &[first.clone(), second.clone()], b"beacon", 16).unwrap();
+        assert_eq!(replayed_parameters, public_parameters);
+        assert_eq!(replayed_key, verification_key);
+        let (other_parameters, _) = verify_ceremony(&cs, &[first, second], b"other", 16).unwrap();
+        assert_ne!(other_parameters, public_parameters);
+
+        let witness = Assignment::new(vec![Scalar::from(9u64)], vec![Scalar::from(3u64)]);
+        let proof = SNARKProver::new(public_parameters.clone(), proving_key)
+            .prove(b"statement", &witness, &mut rng)
+            .unwrap();
+        assert!(SNARKVerifier::new(public_parameters, verification_key)
+            .verify(b"statement", &proof)
+            .unwrap());
+    }
+
+    #[test]
+    fn rejects_contributions_for_another_state() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1098);
+        let cs = square_circuit();
+        let genesis = SetupState::genesis(&cs);
+        let first = genesis.contribute(&mut rng);
+        let state = genesis.apply(&first).unwrap();
+        let second = state.contribute(&mut rng);
+        assert_eq!(
+            genesis.apply(&second).unwrap_err(),
+            CryptoError::InvalidContribution
+        );
+        assert!(verify_ceremony(&cs, &[second, first], b"beacon", 16).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_contributions() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1098);
+        let genesis = SetupState::genesis(&square_circuit());
+        let contribution = genesis.contribute(&mut rng);
+
+        let mut tampered = contribution.clone();
+        tampered.verification_key.verification_point += RISTRETTO_BASEPOINT_POINT;
+        assert!(genesis.apply(&tampered).is_err());
+        let mut tampered = contribution.clone();
+        tampered.generator_factor = RistrettoPoint::mul_base(&Scalar::from(2u64));
+        assert!(genesis.apply(&tampered).is_err());
+        let mut tampered = contribution;
+        tampered.verification_key.bases.pop();
+        assert!(genesis.apply(&tampered).is_err());
+    }
+}
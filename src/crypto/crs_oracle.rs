@@ -0,0 +1,140 @@
+use super::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use num_complex::Complex64;
+use sha3::{Sha3_256, Digest};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CRSAccessMode {
+    FixedString,
+    QuantumOracle,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OracleQueryStats {
+    pub classical_queries: usize,
+    pub superposition_queries: usize,
+    pub max_input_width: usize,
+    pub max_support_size: usize,
+    pub index_weights: HashMap<usize, f64>,
+}
+
+impl OracleQueryStats {
+    pub fn total_queries(&self) -> usize {
+        self.classical_queries + self.superposition_queries
+    }
+
+    pub fn heaviest_index(&self) -> Option<(usize, f64)> {
+        self.index_weights
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(index, weight)| (*index, *weight))
+    }
+}
+
+pub struct CRSOracle {
+    crs: Vec<u8>,
+    mode: CRSAccessMode,
+    output_bits: usize,
+    stats: OracleQueryStats,
+}
+
+impl CRSOracle {
+    pub fn new(crs: Vec<u8>, mode: CRSAccessMode, output_bits: usize) -> Self {
+        Self {
+            crs,
+            mode,
+            output_bits: output_bits.min(64),
+            stats: OracleQueryStats::default(),
+        }
+    }
+
+    pub fn mode(&self) -> CRSAccessMode {
+        self.mode
+    }
+
+    pub fn stats(&self) -> &OracleQueryStats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = OracleQueryStats::default();
+    }
+
+    pub fn crs_string(&self) -> Result<&[u8], CryptoError> {
+        match self.mode {
+            CRSAccessMode::FixedString => Ok(&self.crs),
+            CRSAccessMode::QuantumOracle => Err(CryptoError::OracleAccessDenied),
+        }
+    }
+
+    pub fn query(&mut self, index: usize) -> u64 {
+        self.stats.classical_queries += 1;
+        *self.stats.index_weights.entry(index).or_insert(0.0) += 1.0;
+        self.evaluate(index)
+    }
+
+    // Applies |x>|y> -> |x>|y xor f(x)> where f(x) is the CRS-indexed value
+    pub fn quantum_query(
+        &mut self,
+        state: &mut QuantumState,
+        input: Range<usize>,
+        output: Range<usize>,
+    ) -> Result<(), CryptoError> {
+        if self.mode != CRSAccessMode::QuantumOracle {
+            return Err(CryptoError::OracleAccessDenied);
+        }
+        if input.end > state.num_qubits
+            || output.end > state.num_qubits
+            || output.len() < self.output_bits
+            || (input.start < output.end && output.start < input.end)
+        {
+            return Err(CryptoError::InvalidOracleRegister);
+        }
+
+        let mut evaluations = HashMap::new();
+        let mut support = HashMap::new();
+        let mut amplitudes = vec![Complex64::new(0.0, 0.0); state.amplitudes.len()];
+
+        for (basis, amplitude) in state.amplitudes.iter().enumerate() {
+            let x = read_register(basis, &input);
+            let fx = *evaluations.entry(x).or_insert_with(|| self.evaluate(x));
+            let target = xor_register(basis, &output, fx as usize);
+            amplitudes[target] = *amplitude;
+
+            let weight = amplitude.norm_sqr();
+            if weight > 0.0 {
+                *support.entry(x).or_insert(0.0) += weight;
+            }
+        }
+        state.amplitudes = amplitudes;
+        let qubits: Vec<usize> = input.clone().chain(output.clone()).collect();
+        state.merge_entanglement(&qubits);
+
+        self.stats.superposition_queries += 1;
+        self.stats.max_input_width = self.stats.max_input_width.max(input.len());
+        self.stats.max_support_size = self.stats.max_support_size.max(support.len());
+        for (index, weight) in support {
+            *self.stats.index_weights.entry(index).or_insert(0.0) += weight;
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&self, index: usize) -> u64 {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"crs-oracle");
+        hasher.update(&self.crs);
+        hasher.update(&(index as u64).to_le_bytes());
+        let hash = hasher.finalize();
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        let value = u64::from_le_bytes(bytes);
+        if self.output_bits == 64 {
+            value
+        } else {
+            value & ((1u64 << self.output_bits) - 1)
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use bls12_381::Scalar as Fr;
+use ff::{Field, PrimeField};
+
+// Ristretto scalars as BLS12-381 scalars through their representatives in
+// (-l/2, l/2), l the Ristretto group order. The BLS12-381 order is larger, so
+// this is injective and preserves sums and products whose integer values stay
+// in that range, e.g. -1 * -1 = 1, but nothing that wraps around.
+pub(crate) fn lift_scalar(scalar: &curve25519_dalek::scalar::Scalar) -> Fr {
+    let negated = -scalar;
+    let (mut bytes, mut negated_bytes) = (scalar.to_bytes(), negated.to_bytes());
+    bytes.reverse();
+    negated_bytes.reverse();
+    // Canonical encodings are below the BLS12-381 modulus
+    if negated_bytes < bytes {
+        -Fr::from_bytes(&negated.to_bytes()).unwrap()
+    } else {
+        Fr::from_bytes(&scalar.to_bytes()).unwrap()
+    }
+}
+
+// The inherent pow_vartime takes a full 256-bit exponent
+pub(crate) fn power(base: Fr, exponent: u64) -> Fr {
+    Field::pow_vartime(&base, [exponent])
+}
+
+// The multiplicative subgroup of order `size`, a power of two, generated by
+// `generator`, with radix-2 FFTs between coefficients and evaluations
+#[derive(Clone, Debug)]
+pub(crate) struct EvaluationDomain {
+    pub(crate) size: usize,
+    log_size: u32,
+    pub(crate) generator: Fr,
+    generator_inverse: Fr,
+    size_inverse: Fr,
+}
+
+impl EvaluationDomain {
+    // Smallest domain with at least `min_size` points
+    pub(crate) fn new(min_size: usize) -> Option<Self> {
+        let size = min_size.max(1).next_power_of_two();
+        let log_size = size.trailing_zeros();
+        if log_size > Fr::S {
+            return None;
+        }
+        let mut generator = Fr::ROOT_OF_UNITY;
+        for _ in log_size..Fr::S {
+            generator = generator.square();
+        }
+        Some(Self {
+            size,
+            log_size,
+            generator,
+            generator_inverse: generator.invert().unwrap(),
+            size_inverse: Fr::from(size as u64).invert().unwrap(),
+        })
+    }
+
+    pub(crate) fn element(&self, index: usize) -> Fr {
+        power(self.generator, index as u64)
+    }
+
+    // x^n - 1 at `point`
+    pub(crate) fn vanishing_at(&self, point: Fr) -> Fr {
+        power(point, self.size as u64) - Fr::ONE
+    }
+
+    // Every Lagrange basis polynomial at `point`, which must lie off the domain
+    pub(crate) fn lagrange_at(&self, point: Fr) -> Option<Vec<Fr>> {
+        let vanishing = self.vanishing_at(point);
+        if vanishing == Fr::ZERO {
+            return None;
+        }
+        let scale = vanishing * self.size_inverse;
+        let mut element = Fr::ONE;
+        let mut basis = Vec::with_capacity(self.size);
+        for _ in 0..self.size {
+            basis.push(scale * element * (point - element).invert().unwrap());
+            element *= self.generator;
+        }
+        Some(basis)
+    }
+
+    fn fft_in_place(&self, values: &mut [Fr], root: Fr) {
+        let n = values.len();
+        if n < 2 {
+            return;
+        }
+        for i in 0..n {
+            let j = i.reverse_bits() >> (usize::BITS - self.log_size);
+            if i < j {
+                values.swap(i, j);
+            }
+        }
+        let mut half = 1;
+        while half < n {
+            let step = power(root, (n / (2 * half)) as u64);
+            for start in (0..n).step_by(2 * half) {
+                let mut twiddle = Fr::ONE;
+                for k in 0..half {
+                    let odd = values[start + k + half] * twiddle;
+                    values[start + k + half] = values[start + k] - odd;
+                    values[start + k] += odd;
+                    twiddle *= step;
+                }
+            }
+            half *= 2;
+        }
+    }
+
+    // Coefficients, zero padded to the domain size, to evaluations
+    pub(crate) fn fft(&self, coefficients: &[Fr]) -> Vec<Fr> {
+        let mut values = coefficients.to_vec();
+        values.resize(self.size, Fr::ZERO);
+        self.fft_in_place(&mut values, self.generator);
+        values
+    }
+
+    pub(crate) fn ifft(&self, evaluations: &[Fr]) -> Vec<Fr> {
+        let mut values = evaluations.to_vec();
+        values.resize(self.size, Fr::ZERO);
+        self.fft_in_place(&mut values, self.generator_inverse);
+        for value in &mut values {
+            *value *= self.size_inverse;
+        }
+        values
+    }
+
+    // Evaluations on the coset shift * domain
+    pub(crate) fn coset_fft(&self, coefficients: &[Fr], shift: Fr) -> Vec<Fr> {
+        let mut power = Fr::ONE;
+        let shifted: Vec<Fr> = coefficients
+            .iter()
+            .map(|c| {
+                let term = *c * power;
+                power *= shift;
+                term
+            })
+            .collect();
+        self.fft(&shifted)
+    }
+
+    pub(crate) fn coset_ifft(&self, evaluations: &[Fr], shift: Fr) -> Vec<Fr> {
+        let shift_inverse = shift.invert().unwrap();
+        let mut power = Fr::ONE;
+        self.ifft(evaluations)
+            .into_iter()
+            .map(|c| {
+                let term = c * power;
+                power *= shift_inverse;
+                term
+            })
+            .collect()
+    }
+}
+
+// Horner evaluation of a coefficient vector
+pub(crate) fn evaluate_polynomial(coefficients: &[Fr], point: Fr) -> Fr {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Fr::ZERO, |acc, c| acc * point + c)
+}
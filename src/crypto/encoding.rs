@@ -0,0 +1,457 @@
+use super::*;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use num_complex::Complex64;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+// Canonical binary encodings: a version byte and a type tag, then the fields
+// in declaration order. Scalars are their 32 canonical bytes, points their
+// 32-byte Ristretto compression, and lengths u32 little endian. Decoding
+// rejects anything another encoder could not have produced: other versions
+// or tags, non-canonical scalars, encodings that are not Ristretto points,
+// and trailing bytes.
+pub const ENCODING_VERSION: u8 = 1;
+
+const SNARK_PROOF_TAG: u8 = 1;
+const NIZK_PROOF_TAG: u8 = 2;
+const PUBLIC_PARAMETERS_TAG: u8 = 3;
+const VERIFICATION_KEY_TAG: u8 = 4;
+
+// Verification keys carry a version of their own after the common header,
+// bumped whenever what a key commits to changes, so that a verifier refuses
+// keys from another kind of setup rather than misreading them. Version 1
+// keys recorded the generator they were made under; version 2 keys also
+// record the digest of their setup transcript.
+pub const VERIFICATION_KEY_VERSION: u8 = 2;
+
+// How far from 1 the squared norm of a decoded quantum state may drift
+const NORM_TOLERANCE: f64 = 1e-9;
+
+fn header(tag: u8) -> Vec<u8> {
+    vec![ENCODING_VERSION, tag]
+}
+
+fn put_length(out: &mut Vec<u8>, length: usize) {
+    let length = u32::try_from(length).expect("encoded lengths fit in 32 bits");
+    out.extend_from_slice(&length.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_length(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], tag: u8) -> Result<Self, CryptoError> {
+        match bytes {
+            [ENCODING_VERSION, found, rest @ ..] if *found == tag => Ok(Self { bytes: rest }),
+            [ENCODING_VERSION, ..] | [] | [_] => Err(CryptoError::MalformedEncoding),
+            [version, ..] => Err(CryptoError::UnsupportedEncodingVersion(*version)),
+        }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], CryptoError> {
+        if count > self.bytes.len() {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    // A length prefix for items of `item_size` bytes, checked against what
+    // is left before anything is allocated for it
+    fn length(&mut self, item_size: usize) -> Result<usize, CryptoError> {
+        let bytes = self.take(4)?;
+        let length = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        if length.saturating_mul(item_size) > self.bytes.len() {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        Ok(length)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], CryptoError> {
+        let length = self.length(1)?;
+        self.take(length)
+    }
+
+    fn scalar(&mut self) -> Result<Scalar, CryptoError> {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(self.take(32)?);
+        Option::from(Scalar::from_canonical_bytes(bytes)).ok_or(CryptoError::MalformedEncoding)
+    }
+
+    fn nonzero_scalar(&mut self) -> Result<Scalar, CryptoError> {
+        let scalar = self.scalar()?;
+        if scalar == Scalar::ZERO {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        Ok(scalar)
+    }
+
+    // Decompression only succeeds on canonical encodings of elements of the
+    // prime-order Ristretto group, so there is no separate subgroup check
+    fn point(&mut self) -> Result<RistrettoPoint, CryptoError> {
+        CompressedRistretto::from_slice(self.take(32)?)
+            .ok()
+            .and_then(|point| point.decompress())
+            .ok_or(CryptoError::InvalidPoint)
+    }
+
+    fn compressed_point(&mut self) -> Result<CompressedRistretto, CryptoError> {
+        Ok(self.point()?.compress())
+    }
+
+    fn generator(&mut self) -> Result<RistrettoPoint, CryptoError> {
+        let point = self.point()?;
+        if point.is_identity() {
+            return Err(CryptoError::InvalidPoint);
+        }
+        Ok(point)
+    }
+
+    fn digest(&mut self) -> Result<[u8; 32], CryptoError> {
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(self.take(32)?);
+        Ok(digest)
+    }
+
+    fn finish(self) -> Result<(), CryptoError> {
+        if !self.bytes.is_empty() {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        Ok(())
+    }
+}
+
+impl SNARKProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = header(SNARK_PROOF_TAG);
+        out.extend_from_slice(self.commitment.as_bytes());
+        out.extend_from_slice(self.response.as_bytes());
+        out.extend_from_slice(self.challenge.as_bytes());
+        put_length(&mut out, self.auxiliary_points.len());
+        for point in &self.auxiliary_points {
+            out.extend_from_slice(point.as_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut reader = Reader::new(bytes, SNARK_PROOF_TAG)?;
+        let commitment = reader.compressed_point()?;
+        let response = reader.scalar()?;
+        let challenge = reader.scalar()?;
+        let count = reader.length(32)?;
+        let auxiliary_points = (0..count)
+            .map(|_| reader.compressed_point())
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+        Ok(Self {
+            commitment,
+            response,
+            challenge,
+            auxiliary_points,
+        })
+    }
+}
+
+impl PublicParameters {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = header(PUBLIC_PARAMETERS_TAG);
+        out.extend_from_slice(self.generator.compress().as_bytes());
+        out.extend_from_slice(&self.key_digest);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut reader = Reader::new(bytes, PUBLIC_PARAMETERS_TAG)?;
+        let generator = reader.generator()?;
+        let key_digest = reader.digest()?;
+        reader.finish()?;
+        Ok(Self {
+            generator,
+            key_digest,
+        })
+    }
+}
+
+impl VerificationKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = header(VERIFICATION_KEY_TAG);
+        out.push(VERIFICATION_KEY_VERSION);
+        out.extend_from_slice(self.generator.compress().as_bytes());
+        put_length(&mut out, self.bases.len());
+        for base in &self.bases {
+            out.extend_from_slice(base.as_bytes());
+        }
+        out.extend_from_slice(self.statement_scalar.as_bytes());
+        out.extend_from_slice(self.blinding_factor.as_bytes());
+        out.extend_from_slice(self.verification_point.compress().as_bytes());
+        out.extend_from_slice(&self.transcript_digest);
+        out
+    }
+
+    // Every scalar must be nonzero and neither point the identity, as setup
+    // and the ceremony guarantee; whether the key belongs to a given setup
+    // is for validate to check
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut reader = Reader::new(bytes, VERIFICATION_KEY_TAG)?;
+        let version = reader.take(1)?[0];
+        if version != VERIFICATION_KEY_VERSION {
+            return Err(CryptoError::UnsupportedKeyVersion(version));
+        }
+        let generator = reader.generator()?;
+        let count = reader.length(32)?;
+        let bases = (0..count)
+            .map(|_| reader.nonzero_scalar())
+            .collect::<Result<_, _>>()?;
+        let statement_scalar = reader.nonzero_scalar()?;
+        let blinding_factor = reader.nonzero_scalar()?;
+        let verification_point = reader.generator()?;
+        let transcript_digest = reader.digest()?;
+        reader.finish()?;
+        Ok(Self {
+            generator,
+            bases,
+            statement_scalar,
+            blinding_factor,
+            verification_point,
+            transcript_digest,
+        })
+    }
+}
+
+// QuantumState with its entanglement map ordered, so that equal states
+// encode to equal bytes
+#[derive(Serialize, Deserialize)]
+struct EncodedQuantumState {
+    amplitudes: Vec<Complex64>,
+    num_qubits: usize,
+    entanglement_map: BTreeMap<usize, Vec<usize>>,
+    measurement_history: Vec<Measurement>,
+    classical_outcomes: Vec<bool>,
+    error_syndrome: Option<ErrorSyndrome>,
+    leaked: Vec<usize>,
+}
+
+impl EncodedQuantumState {
+    fn new(state: &QuantumState) -> Self {
+        Self {
+            amplitudes: state.amplitudes.clone(),
+            num_qubits: state.num_qubits,
+            entanglement_map: state
+                .entanglement_map
+                .iter()
+                .map(|(qubit, partners)| (*qubit, partners.clone()))
+                .collect(),
+            measurement_history: state.measurement_history.clone(),
+            classical_outcomes: state.classical_outcomes.clone(),
+            error_syndrome: state.error_syndrome.clone(),
+            leaked: state.leaked.clone(),
+        }
+    }
+
+    // Refuses states the simulator could not hold or that are not states:
+    // amplitudes that are not finite or not of norm 1, which includes all
+    // zero, and qubit indices beyond num_qubits
+    fn into_state(self) -> Result<QuantumState, CryptoError> {
+        let n = self.num_qubits;
+        let fits = estimate_memory(n).is_some_and(|bytes| bytes <= state_memory_limit());
+        if !fits
+            || self.amplitudes.len() != 1 << n
+            || self
+                .amplitudes
+                .iter()
+                .any(|a| !a.re.is_finite() || !a.im.is_finite())
+        {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        let norm: f64 = self.amplitudes.iter().map(|a| a.norm_sqr()).sum();
+        let in_range = self.leaked.iter().all(|&qubit| qubit < n)
+            && self
+                .entanglement_map
+                .iter()
+                .all(|(qubit, partners)| *qubit < n && partners.iter().all(|&p| p < n));
+        if (norm - 1.0).abs() > NORM_TOLERANCE || !in_range {
+            return Err(CryptoError::MalformedEncoding);
+        }
+        Ok(QuantumState {
+            amplitudes: self.amplitudes,
+            num_qubits: self.num_qubits,
+            entanglement_map: self.entanglement_map.into_iter().collect(),
+            measurement_history: self.measurement_history,
+            classical_outcomes: self.classical_outcomes,
+            error_syndrome: self.error_syndrome,
+            leaked: self.leaked,
+        })
+    }
+}
+
+impl NIZKProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = header(NIZK_PROOF_TAG);
+        let state = bincode::serialize(&EncodedQuantumState::new(&self.quantum_state))
+            .expect("quantum states are always serializable");
+        put_bytes(&mut out, &state);
+        put_bytes(&mut out, &self.classical_proof.to_bytes());
+        out.extend_from_slice(self.commitment.as_bytes());
+        out.extend_from_slice(self.response.as_bytes());
+        put_bytes(&mut out, &self.auxiliary_data);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut reader = Reader::new(bytes, NIZK_PROOF_TAG)?;
+        let state: EncodedQuantumState =
+            bincode::deserialize(reader.bytes()?).map_err(|_| CryptoError::MalformedEncoding)?;
+        let quantum_state = state.into_state()?;
+        let classical_proof = SNARKProof::from_bytes(reader.bytes()?)?;
+        let commitment = reader.compressed_point()?;
+        let response = reader.scalar()?;
+        let auxiliary_data = reader.bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            quantum_state,
+            classical_proof,
+            commitment,
+            response,
+            auxiliary_data,
+        })
+    }
+}
+
+// Serde goes through the canonical encoding, so every format gets the same
+// validation
+macro_rules! serde_via_canonical_bytes {
+    ($($type:ty),*) => {$(
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(&self.to_bytes())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bytes = Vec::<u8>::deserialize(deserializer)?;
+                Self::from_bytes(&bytes).map_err(|e| D::Error::custom(format!("{:?}", e)))
+            }
+        }
+    )*};
+}
+
+serde_via_canonical_bytes!(SNARKProof, NIZKProof, PublicParameters, VerificationKey);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn proof_and_key() -> (SNARKProof, VerificationKey) {
+        let mut rng = ChaCha20Rng::seed_from_u64(1099);
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        let (public_parameters, proving_key, verification_key) = snark_setup(cs, &mut rng);
+        let witness = Assignment::new(vec![Scalar::from(9u64)], vec![Scalar::from(3u64)]);
+        let proof = SNARKProver::new(public_parameters, proving_key)
+            .prove(b"encoding", &witness, &mut rng)
+            .unwrap();
+        (proof, verification_key)
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let (proof, verification_key) = proof_and_key();
+        assert_eq!(SNARKProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+        assert_eq!(
+            VerificationKey::from_bytes(&verification_key.to_bytes()).unwrap(),
+            verification_key
+        );
+        let json = serde_json::to_string(&verification_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<VerificationKey>(&json).unwrap(),
+            verification_key
+        );
+    }
+
+    #[test]
+    fn rejects_non_canonical_scalars() {
+        let (proof, _) = proof_and_key();
+        let mut bytes = proof.to_bytes();
+        // The response follows the header and the commitment
+        bytes[34..66].fill(0xff);
+        assert_eq!(
+            SNARKProof::from_bytes(&bytes),
+            Err(CryptoError::MalformedEncoding)
+        );
+    }
+
+    #[test]
+    fn rejects_identity_generators() {
+        let parameters = PublicParameters {
+            generator: RistrettoPoint::default(),
+            key_digest: [0u8; 32],
+        };
+        assert_eq!(
+            PublicParameters::from_bytes(&parameters.to_bytes()),
+            Err(CryptoError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn rejects_trailing_bytes_and_other_versions() {
+        let (proof, verification_key) = proof_and_key();
+        let mut bytes = proof.to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            SNARKProof::from_bytes(&bytes),
+            Err(CryptoError::MalformedEncoding)
+        );
+
+        let mut bytes = proof.to_bytes();
+        bytes[0] = ENCODING_VERSION + 1;
+        assert_eq!(
+            SNARKProof::from_bytes(&bytes),
+            Err(CryptoError::UnsupportedEncodingVersion(
+                ENCODING_VERSION + 1
+            ))
+        );
+        let mut bytes = verification_key.to_bytes();
+        bytes[2] = VERIFICATION_KEY_VERSION + 1;
+        assert_eq!(
+            VerificationKey::from_bytes(&bytes),
+            Err(CryptoError::UnsupportedKeyVersion(
+                VERIFICATION_KEY_VERSION + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_quantum_states_that_are_not_states() {
+        let state = QuantumState::bell_pair().unwrap();
+        assert!(EncodedQuantumState::new(&state).into_state().is_ok());
+
+        let mut encoded = EncodedQuantumState::new(&state);
+        encoded.amplitudes[0] *= 2.0;
+        assert!(encoded.into_state().is_err());
+        let mut encoded = EncodedQuantumState::new(&state);
+        encoded.amplitudes.fill(Complex64::new(0.0, 0.0));
+        assert!(encoded.into_state().is_err());
+        let mut encoded = EncodedQuantumState::new(&state);
+        encoded.leaked.push(2);
+        assert!(encoded.into_state().is_err());
+        let mut encoded = EncodedQuantumState::new(&state);
+        encoded.entanglement_map.insert(0, vec![5]);
+        assert!(encoded.into_state().is_err());
+    }
+}
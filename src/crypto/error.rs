@@ -0,0 +1,70 @@
+use super::*;
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum CryptoError {
+    #[error("point does not decompress")]
+    InvalidPoint,
+    #[error("proof {0} is invalid")]
+    InvalidProof(usize),
+    #[error("invalid verification key")]
+    InvalidVerificationKey,
+    #[error("verification key belongs to another setup")]
+    MismatchedSetup,
+    #[error("unsupported verification key version {0}")]
+    UnsupportedKeyVersion(u8),
+    #[error("malformed encoding")]
+    MalformedEncoding,
+    #[error("unsupported encoding version {0}")]
+    UnsupportedEncodingVersion(u8),
+    #[error("variable {0:?} is not allocated")]
+    InvalidVariable(Variable),
+    #[error("variable {0:?} is unassigned")]
+    UnassignedVariable(Variable),
+    #[error("assignment length does not match the constraint system")]
+    AssignmentLengthMismatch,
+    #[error("constraint {0} ({1}) is unsatisfied")]
+    UnsatisfiedConstraint(usize, String),
+    #[error("lookup {0} ({1}) is not in its table")]
+    LookupFailed(usize, String),
+    #[error("constraint {0} ({1}) fails over the proof system's field")]
+    LiftedConstraintFailed(usize, String),
+    #[error("lookup {0} ({1}) fails over the proof system's field")]
+    LiftedLookupFailed(usize, String),
+    #[error("invalid lookup table")]
+    InvalidLookupTable,
+    #[error("proof system does not support lookups")]
+    LookupsUnsupported,
+    #[error("invalid custom gate")]
+    InvalidCustomGate,
+    #[error("circuit is too large for the setup")]
+    CircuitTooLarge,
+    #[error("range of {0} bits is too wide")]
+    RangeTooWide(usize),
+    #[error("value is out of range")]
+    ValueOutOfRange,
+    #[error("cannot aggregate {0} proofs")]
+    InvalidAggregation(usize),
+    #[error("invalid vector length {0}")]
+    InvalidVectorLength(usize),
+    #[error("evaluation point lies in the domain")]
+    InvalidEvaluationPoint,
+    #[error("invalid setup contribution")]
+    InvalidContribution,
+    #[error("invalid trapdoor")]
+    InvalidTrapdoor,
+    #[error("samples cannot be compared")]
+    IncomparableSamples,
+    #[error("statements must be distinct")]
+    IndistinctStatements,
+    #[error("CRS access denied in this mode")]
+    OracleAccessDenied,
+    #[error("invalid oracle register")]
+    InvalidOracleRegister,
+    #[error("step state of width {0} does not match the step circuit")]
+    InvalidStateWidth(usize),
+    #[error("expected {0} steps")]
+    StepCountMismatch(usize),
+    #[error("step {0} does not verify")]
+    InvalidStep(usize),
+}
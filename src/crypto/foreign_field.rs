@@ -0,0 +1,365 @@
+use super::*;
+use bls12_381::Scalar as Fr;
+use curve25519_dalek::scalar::Scalar;
+use ff::Field;
+
+pub const FOREIGN_LIMBS: usize = 3;
+pub const FOREIGN_LIMB_BITS: usize = 85;
+const FOREIGN_BITS: usize = FOREIGN_LIMBS * FOREIGN_LIMB_BITS;
+// Columns of limb products stay below 2^173 in magnitude, so their carries
+// stay below 2^88
+const CARRY_OFFSET_BITS: usize = 89;
+
+// What a Hint::Foreign computes from its operands a, b and c
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForeignOperation {
+    // a b + c reduced modulo the field order
+    Remainder,
+    // (a b + c) / order, rounded down
+    Quotient,
+    // a / b, or 0 for b = 0
+    Divide,
+}
+
+impl ForeignOperation {
+    pub(crate) fn limb(self, sources: &[Scalar], limb: usize) -> Scalar {
+        let operand =
+            |i: usize| wide_from_limbs(&sources[FOREIGN_LIMBS * i..FOREIGN_LIMBS * (i + 1)]);
+        let remainder = || {
+            let (a, b, c) = (operand(0), operand(1), operand(2));
+            wide_from_field(&(field(&a) * field(&b) + field(&c)))
+        };
+        let result = match self {
+            ForeignOperation::Remainder => remainder(),
+            // Exact, so it can be taken modulo 2^256 by the order's inverse
+            ForeignOperation::Quotient => {
+                let (a, b, c) = (operand(0), operand(1), operand(2));
+                let numerator = wide_sub(&wide_add(&wide_mul(&a, &b), &c), &remainder());
+                wide_mul(&numerator, &order_inverse())
+            }
+            ForeignOperation::Divide => {
+                let inverse = Option::<Fr>::from(field(&operand(1)).invert()).unwrap_or(Fr::ZERO);
+                wide_from_field(&(field(&operand(0)) * inverse))
+            }
+        };
+        wide_limb(&result, limb)
+    }
+}
+
+// 256-bit integers, least significant word first, wrapping modulo 2^256
+type Wide = [u64; 4];
+
+fn wide_bit(x: &Wide, bit: usize) -> bool {
+    bit < 256 && (x[bit / 64] >> (bit % 64)) & 1 == 1
+}
+
+fn wide_from_bytes(bytes: &[u8; 32]) -> Wide {
+    std::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+}
+
+fn wide_from_field(x: &Fr) -> Wide {
+    wide_from_bytes(&x.to_bytes())
+}
+
+// Non-canonical encodings only come from unsatisfiable witnesses, so any
+// value will do for them
+fn field(x: &Wide) -> Fr {
+    let mut bytes = [0u8; 32];
+    for (chunk, word) in bytes.chunks_mut(8).zip(x) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    Option::from(Fr::from_bytes(&bytes)).unwrap_or(Fr::ZERO)
+}
+
+fn wide_from_limbs(limbs: &[Scalar]) -> Wide {
+    let mut x = [0u64; 4];
+    for (i, limb) in limbs.iter().enumerate() {
+        let limb = limb.to_bytes();
+        for bit in 0..FOREIGN_LIMB_BITS {
+            let position = FOREIGN_LIMB_BITS * i + bit;
+            if position < 256 && (limb[bit / 8] >> (bit % 8)) & 1 == 1 {
+                x[position / 64] |= 1 << (position % 64);
+            }
+        }
+    }
+    x
+}
+
+fn wide_limb(x: &Wide, limb: usize) -> Scalar {
+    let value = (0..FOREIGN_LIMB_BITS).rev().fold(0u128, |value, bit| {
+        (value << 1) | wide_bit(x, FOREIGN_LIMB_BITS * limb + bit) as u128
+    });
+    Scalar::from(value)
+}
+
+fn wide_add(a: &Wide, b: &Wide) -> Wide {
+    let mut carry = false;
+    std::array::from_fn(|i| {
+        let (sum, first) = a[i].overflowing_add(b[i]);
+        let (sum, second) = sum.overflowing_add(carry as u64);
+        carry = first || second;
+        sum
+    })
+}
+
+fn wide_sub(a: &Wide, b: &Wide) -> Wide {
+    let mut borrow = false;
+    std::array::from_fn(|i| {
+        let (difference, first) = a[i].overflowing_sub(b[i]);
+        let (difference, second) = difference.overflowing_sub(borrow as u64);
+        borrow = first || second;
+        difference
+    })
+}
+
+fn wide_mul(a: &Wide, b: &Wide) -> Wide {
+    let mut product = [0u64; 4];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 - i {
+            let total = product[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+            product[i + j] = total as u64;
+            carry = total >> 64;
+        }
+    }
+    product
+}
+
+fn order() -> Wide {
+    wide_add(&wide_from_field(&-Fr::ONE), &[1, 0, 0, 0])
+}
+
+// Newton's iteration doubles the correct low bits from the 3 of an odd
+// number's own inverse modulo 8
+fn order_inverse() -> Wide {
+    let order = order();
+    let mut inverse = order;
+    for _ in 0..7 {
+        let correction = wide_sub(&[2, 0, 0, 0], &wide_mul(&order, &inverse));
+        inverse = wide_mul(&inverse, &correction);
+    }
+    inverse
+}
+
+// An element of the BLS12-381 scalar field, the FRI SNARK's, in a circuit
+// over the Ristretto scalar field: FOREIGN_LIMBS limbs and their 255 bits,
+// least significant first, constrained below the field order so that the
+// encoding is canonical. Operations check a b + c = q p + d over the
+// integers, column by column of limb products, which at 85 bits a limb
+// stay far below the circuit's modulus.
+#[derive(Clone, Debug)]
+pub struct FrVar {
+    limbs: Vec<LinearCombination>,
+    bits: Vec<LinearCombination>,
+}
+
+impl FrVar {
+    pub fn constant(value: &Fr) -> Self {
+        let bits = constant_bits(&value.to_bytes())[..FOREIGN_BITS].to_vec();
+        let limbs = Self::limb_values(value)
+            .into_iter()
+            .map(LinearCombination::constant)
+            .collect();
+        Self { limbs, bits }
+    }
+
+    // Values of the limbs from_limbs reads
+    pub fn limb_values(value: &Fr) -> [Scalar; FOREIGN_LIMBS] {
+        let value = wide_from_field(value);
+        std::array::from_fn(|limb| wide_limb(&value, limb))
+    }
+
+    // The element with these limbs, each range checked
+    pub fn from_limbs(
+        cs: &mut ConstraintSystem,
+        limbs: &[LinearCombination],
+    ) -> Result<Self, CryptoError> {
+        if limbs.len() != FOREIGN_LIMBS {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let mut bits = Vec::with_capacity(FOREIGN_BITS);
+        for limb in limbs {
+            bits.extend(decompose(cs, limb, FOREIGN_LIMB_BITS)?);
+        }
+        enforce_canonical(cs, &bits);
+        Ok(Self {
+            limbs: limbs.to_vec(),
+            bits,
+        })
+    }
+
+    // An element whose limbs the program supplies, with the variables to set
+    pub fn alloc_private(cs: &mut ConstraintSystem) -> Result<(Self, Vec<Variable>), CryptoError> {
+        let limbs: Vec<Variable> = (0..FOREIGN_LIMBS).map(|_| cs.alloc_private()).collect();
+        let lcs: Vec<LinearCombination> = limbs.iter().map(|&limb| limb.into()).collect();
+        Ok((Self::from_limbs(cs, &lcs)?, limbs))
+    }
+
+    // An element whose limbs are the next public inputs
+    pub fn alloc_public(cs: &mut ConstraintSystem) -> Result<Self, CryptoError> {
+        let limbs: Vec<LinearCombination> = (0..FOREIGN_LIMBS)
+            .map(|_| cs.alloc_public().into())
+            .collect();
+        Self::from_limbs(cs, &limbs)
+    }
+
+    pub fn bits(&self) -> &[LinearCombination] {
+        &self.bits
+    }
+
+    // The 256 bits of the little-endian encoding Fr::to_bytes gives
+    pub fn to_bytes_bits(&self) -> Vec<LinearCombination> {
+        let mut bits = self.bits.clone();
+        bits.push(LinearCombination::zero());
+        bits
+    }
+
+    fn hinted(
+        cs: &mut ConstraintSystem,
+        operation: ForeignOperation,
+        operands: &[LinearCombination],
+    ) -> Vec<LinearCombination> {
+        (0..FOREIGN_LIMBS)
+            .map(|limb| {
+                let hint = Hint::Foreign {
+                    operation,
+                    operands: operands.to_vec(),
+                    limb,
+                };
+                cs.alloc_hinted(hint).into()
+            })
+            .collect()
+    }
+
+    // self b + c
+    pub fn mul_add(
+        &self,
+        cs: &mut ConstraintSystem,
+        b: &FrVar,
+        c: &FrVar,
+    ) -> Result<FrVar, CryptoError> {
+        let operands: Vec<LinearCombination> =
+            [self, b, c].iter().flat_map(|x| x.limbs.clone()).collect();
+        let limbs = Self::hinted(cs, ForeignOperation::Remainder, &operands);
+        let result = Self::from_limbs(cs, &limbs)?;
+        let quotient = Self::hinted(cs, ForeignOperation::Quotient, &operands);
+        for limb in &quotient {
+            range_check(cs, limb, FOREIGN_LIMB_BITS)?;
+        }
+
+        let order = Self::limb_values(&-Fr::ONE);
+        let mut columns = vec![LinearCombination::zero(); 2 * FOREIGN_LIMBS - 1];
+        for i in 0..FOREIGN_LIMBS {
+            for j in 0..FOREIGN_LIMBS {
+                // The order's low limb is that of -1, plus one
+                let modulus = if j == 0 {
+                    order[0] + Scalar::ONE
+                } else {
+                    order[j]
+                };
+                let product = multiply(cs, &self.limbs[i], &b.limbs[j]);
+                columns[i + j] = columns[i + j].clone() + product - quotient[i].clone() * modulus;
+            }
+            columns[i] = columns[i].clone() + c.limbs[i].clone() - result.limbs[i].clone();
+        }
+        enforce_zero(cs, &columns)?;
+        Ok(result)
+    }
+
+    pub fn mul(&self, cs: &mut ConstraintSystem, other: &FrVar) -> Result<FrVar, CryptoError> {
+        self.mul_add(cs, other, &Self::constant(&Fr::ZERO))
+    }
+
+    pub fn add(&self, cs: &mut ConstraintSystem, other: &FrVar) -> Result<FrVar, CryptoError> {
+        self.mul_add(cs, &Self::constant(&Fr::ONE), other)
+    }
+
+    pub fn sub(&self, cs: &mut ConstraintSystem, other: &FrVar) -> Result<FrVar, CryptoError> {
+        other.mul_add(cs, &Self::constant(&-Fr::ONE), self)
+    }
+
+    // Unsatisfiable for 0
+    pub fn invert(&self, cs: &mut ConstraintSystem) -> Result<FrVar, CryptoError> {
+        let one = Self::constant(&Fr::ONE);
+        let operands: Vec<LinearCombination> =
+            [&one, self].iter().flat_map(|x| x.limbs.clone()).collect();
+        let limbs = Self::hinted(cs, ForeignOperation::Divide, &operands);
+        let inverse = Self::from_limbs(cs, &limbs)?;
+        inverse.mul(cs, self)?.enforce_equal(cs, &one);
+        Ok(inverse)
+    }
+
+    pub fn div(&self, cs: &mut ConstraintSystem, divisor: &FrVar) -> Result<FrVar, CryptoError> {
+        let inverse = divisor.invert(cs)?;
+        self.mul(cs, &inverse)
+    }
+
+    // Limb by limb, which suffices for canonical encodings
+    pub fn enforce_equal(&self, cs: &mut ConstraintSystem, other: &FrVar) {
+        for (a, b) in self.limbs.iter().zip(&other.limbs) {
+            cs.enforce_equal(a.clone(), b.clone());
+        }
+    }
+
+    // For a boolean condition
+    pub fn select(
+        cs: &mut ConstraintSystem,
+        condition: &LinearCombination,
+        if_true: &FrVar,
+        if_false: &FrVar,
+    ) -> FrVar {
+        let mut pick = |a: &[LinearCombination], b: &[LinearCombination]| -> Vec<_> {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| select(cs, condition, a, b))
+                .collect()
+        };
+        FrVar {
+            limbs: pick(&if_true.limbs, &if_false.limbs),
+            bits: pick(&if_true.bits, &if_false.bits),
+        }
+    }
+}
+
+// bits <= order - 1, scanning from the top: while the bits match the bound's
+// so far, a 0 in the bound forces a 0, and a 1 keeps the match only if the
+// bit is also 1
+fn enforce_canonical(cs: &mut ConstraintSystem, bits: &[LinearCombination]) {
+    let bound = bytes_to_bits(&(-Fr::ONE).to_bytes());
+    let mut matching = LinearCombination::from(Variable::One);
+    for (bit, bound) in bits.iter().zip(bound).rev() {
+        if bound {
+            matching = multiply(cs, &matching, bit);
+        } else {
+            cs.enforce(matching.clone(), bit.clone(), LinearCombination::zero());
+        }
+    }
+}
+
+// sum_k columns[k] 2^(85 k) = 0 over the integers, carrying from each column
+// into the next
+fn enforce_zero(
+    cs: &mut ConstraintSystem,
+    columns: &[LinearCombination],
+) -> Result<(), CryptoError> {
+    let base = Scalar::from(1u128 << FOREIGN_LIMB_BITS);
+    let offset = Scalar::from(1u128 << CARRY_OFFSET_BITS);
+    let mut carry = LinearCombination::zero();
+    let (last, rest) = columns
+        .split_last()
+        .ok_or(CryptoError::AssignmentLengthMismatch)?;
+    for column in rest {
+        let total = column.clone() + carry;
+        let next: LinearCombination = cs
+            .alloc_hinted(Hint::Shifted {
+                source: total.clone(),
+                shift: FOREIGN_LIMB_BITS,
+            })
+            .into();
+        cs.enforce_equal(total, next.clone() * base);
+        range_check(cs, &(next.clone() + offset), CARRY_OFFSET_BITS + 1)?;
+        carry = next;
+    }
+    cs.enforce_equal(last.clone() + carry, LinearCombination::zero());
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use super::*;
+use bls12_381::Scalar as Fr;
+use ff::{Field, PrimeField};
+use merlin::Transcript;
+
+// Leaf holding the evaluations at x and -x
+fn leaf(values: &[Fr; 2]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&values[0].to_bytes());
+    hasher.update(&values[1].to_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+fn verify_path(root: &[u8; 32], index: usize, values: &[Fr; 2], path: &[[u8; 32]]) -> bool {
+    MerkleTree::root_from_path(&leaf(values), index, path) == *root
+}
+
+// A layer of n evaluations on shift * <omega> is committed as n / 2 leaves,
+// leaf i pairing the points x = shift omega^i and -x = shift omega^(i + n/2)
+fn commit_layer(evaluations: &[Fr]) -> MerkleTree {
+    let half = evaluations.len() / 2;
+    MerkleTree::new(
+        (0..half)
+            .map(|i| leaf(&[evaluations[i], evaluations[i + half]]))
+            .collect(),
+    )
+}
+
+// f(x) = f_e(x^2) + x f_o(x^2) folds to f_e(y) + beta f_o(y) at y = x^2
+fn fold(values: [Fr; 2], x: Fr, beta: Fr) -> Fr {
+    let two_inverse = Fr::from(2).invert().unwrap();
+    (values[0] + values[1] + beta * (values[0] - values[1]) * x.invert().unwrap()) * two_inverse
+}
+
+fn begin_opening(transcript: &mut Transcript, commitment: &FriCommitment, point: Fr, value: Fr) {
+    transcript.append_message(b"dom-sep", b"fri");
+    transcript.append_message(b"root", &commitment.root);
+    transcript.append_message(b"z", &point.to_bytes());
+    transcript.append_message(b"v", &value.to_bytes());
+}
+
+fn query_positions(transcript: &mut Transcript, size: usize, count: usize) -> Vec<usize> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; 8];
+            transcript.challenge_bytes(b"query", &mut bytes);
+            (u64::from_le_bytes(bytes) % size as u64) as usize
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriCommitment {
+    pub root: [u8; 32],
+}
+
+// The two evaluations of one leaf of a layer, with its Merkle path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriDecommitment {
+    pub(crate) values: [Fr; 2],
+    pub(crate) path: Vec<[u8; 32]>,
+}
+
+// Roots of the folded layers, the constant the last fold reaches, and for
+// each query one decommitment per layer starting with the committed one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriOpening {
+    pub(crate) layer_roots: Vec<[u8; 32]>,
+    pub(crate) final_value: Fr,
+    pub(crate) queries: Vec<Vec<FriDecommitment>>,
+}
+
+// What the queries of an opening are checked against: the roots of its
+// layers starting with the committed one, the opened point and value, the
+// final value, and the folding challenges and query positions the
+// transcript gives
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FriQueryClaim {
+    pub(crate) roots: Vec<[u8; 32]>,
+    pub(crate) point: Fr,
+    pub(crate) value: Fr,
+    pub(crate) final_value: Fr,
+    pub(crate) betas: Vec<Fr>,
+    pub(crate) positions: Vec<usize>,
+}
+
+// Hash-based commitments: the Merkle root of the evaluations on a coset
+// 2^log_blowup times larger than the degree bound, opened at z by running
+// the FRI low-degree test on (p(X) - p(z)) / (X - z). Transparent and relying
+// on no group, so plausibly post-quantum; soundness error about
+// 2^(-log_blowup * num_queries) under the usual proximity conjectures.
+#[derive(Clone, Copy, Debug)]
+pub struct Fri {
+    max_degree: usize,
+    log_blowup: u32,
+    num_queries: usize,
+}
+
+impl Fri {
+    pub fn new(max_degree: usize) -> Self {
+        Self {
+            max_degree,
+            log_blowup: 3,
+            num_queries: 40,
+        }
+    }
+
+    pub fn with_blowup(mut self, log_blowup: u32) -> Self {
+        self.log_blowup = log_blowup.max(1);
+        self
+    }
+
+    pub fn with_queries(mut self, num_queries: usize) -> Self {
+        self.num_queries = num_queries.max(1);
+        self
+    }
+
+    pub(crate) fn rounds(&self) -> usize {
+        (self.max_degree + 1).next_power_of_two().trailing_zeros() as usize
+    }
+
+    pub(crate) fn domain(&self) -> Result<EvaluationDomain, CryptoError> {
+        EvaluationDomain::new((self.max_degree + 1).next_power_of_two() << self.log_blowup)
+            .ok_or(CryptoError::CircuitTooLarge)
+    }
+
+    pub(crate) fn num_queries(&self) -> usize {
+        self.num_queries
+    }
+
+    // Replays the transcript of an opening with the right number of layers
+    pub(crate) fn replay(
+        &self,
+        commitment: &FriCommitment,
+        point: Fr,
+        value: Fr,
+        layer_roots: &[[u8; 32]],
+        final_value: Fr,
+        transcript: &mut Transcript,
+    ) -> Result<Option<FriQueryClaim>, CryptoError> {
+        let domain = self.domain()?;
+        let rounds = self.rounds();
+        if layer_roots.len() != rounds.saturating_sub(1) {
+            return Ok(None);
+        }
+        begin_opening(transcript, commitment, point, value);
+        let mut betas = Vec::with_capacity(rounds);
+        for round in 0..rounds {
+            betas.push(challenge(transcript, b"beta"));
+            // Every round but the last commits a layer
+            if let Some(root) = layer_roots.get(round) {
+                transcript.append_message(b"layer", root);
+            }
+        }
+        transcript.append_message(b"final", &final_value.to_bytes());
+        Ok(Some(FriQueryClaim {
+            roots: std::iter::once(commitment.root)
+                .chain(layer_roots.iter().copied())
+                .collect(),
+            point,
+            value,
+            final_value,
+            betas,
+            positions: query_positions(transcript, domain.size, self.num_queries),
+        }))
+    }
+
+    // The query checks, against a claim replayed from the transcript
+    pub(crate) fn check_queries(
+        &self,
+        claim: &FriQueryClaim,
+        queries: &[Vec<FriDecommitment>],
+    ) -> Result<bool, CryptoError> {
+        let domain = self.domain()?;
+        let rounds = self.rounds();
+        if queries.len() != self.num_queries {
+            return Ok(false);
+        }
+        for (mut position, decommitments) in claim.positions.iter().copied().zip(queries) {
+            if decommitments.len() != claim.roots.len() {
+                return Ok(false);
+            }
+            let (mut size, mut generator) = (domain.size, domain.generator);
+            let mut shift = Fr::MULTIPLICATIVE_GENERATOR;
+            let mut expected = None;
+            for (layer, decommitment) in decommitments.iter().enumerate() {
+                let half = size / 2;
+                let index = position % half;
+                if decommitment.path.len() != half.trailing_zeros() as usize
+                    || !verify_path(
+                        &claim.roots[layer],
+                        index,
+                        &decommitment.values,
+                        &decommitment.path,
+                    )
+                {
+                    return Ok(false);
+                }
+                let x = shift * power(generator, index as u64);
+                let mut values = decommitment.values;
+                if layer == 0 {
+                    // Evaluations of p to evaluations of the quotient
+                    for (v, x) in values.iter_mut().zip([x, -x]) {
+                        let Some(inverse) = Option::<Fr>::from((x - claim.point).invert()) else {
+                            return Ok(false);
+                        };
+                        *v = (*v - claim.value) * inverse;
+                    }
+                }
+                if expected.is_some_and(|e| values[position / half] != e) {
+                    return Ok(false);
+                }
+                if rounds == 0 {
+                    // Nothing to fold: the quotient is already constant
+                    if values != [claim.final_value; 2] {
+                        return Ok(false);
+                    }
+                } else {
+                    expected = Some(fold(values, x, claim.betas[layer]));
+                }
+                size = half;
+                generator = generator.square();
+                shift = shift.square();
+                position = index;
+            }
+            if rounds > 0 && expected != Some(claim.final_value) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn evaluations(&self, coefficients: &[Fr]) -> Result<(EvaluationDomain, Vec<Fr>), CryptoError> {
+        if coefficients.len() > self.max_degree + 1 {
+            return Err(CryptoError::CircuitTooLarge);
+        }
+        let domain = self.domain()?;
+        let evaluations = domain.coset_fft(coefficients, Fr::MULTIPLICATIVE_GENERATOR);
+        Ok((domain, evaluations))
+    }
+}
+
+impl PolynomialCommitment for Fri {
+    type Commitment = FriCommitment;
+    type Opening = FriOpening;
+
+    fn name(&self) -> &'static str {
+        "fri"
+    }
+
+    fn max_degree(&self) -> usize {
+        self.max_degree
+    }
+
+    fn commit(&self, coefficients: &[Fr]) -> Result<FriCommitment, CryptoError> {
+        let (_, evaluations) = self.evaluations(coefficients)?;
+        Ok(FriCommitment {
+            root: commit_layer(&evaluations).root(),
+        })
+    }
+
+    fn open(
+        &self,
+        coefficients: &[Fr],
+        point: Fr,
+        transcript: &mut Transcript,
+    ) -> Result<(Fr, FriOpening), CryptoError> {
+        let (domain, evaluations) = self.evaluations(coefficients)?;
+        let shift = Fr::MULTIPLICATIVE_GENERATOR;
+        if domain.vanishing_at(point * shift.invert().unwrap()) == Fr::ZERO {
+            return Err(CryptoError::InvalidEvaluationPoint);
+        }
+        let value = evaluate_polynomial(coefficients, point);
+        let committed = commit_layer(&evaluations);
+        begin_opening(
+            transcript,
+            &FriCommitment {
+                root: committed.root(),
+            },
+            point,
+            value,
+        );
+
+        let mut x = shift;
+        let mut layer: Vec<Fr> = evaluations
+            .iter()
+            .map(|evaluation| {
+                let quotient = (evaluation - value) * (x - point).invert().unwrap();
+                x *= domain.generator;
+                quotient
+            })
+            .collect();
+        let rounds = self.rounds();
+        let (mut generator, mut shift) = (domain.generator, shift);
+        let mut folded_layers = Vec::with_capacity(rounds);
+        for round in 0..rounds {
+            let beta = challenge(transcript, b"beta");
+            let half = layer.len() / 2;
+            let mut x = shift;
+            layer = (0..half)
+                .map(|i| {
+                    let folded = fold([layer[i], layer[i + half]], x, beta);
+                    x *= generator;
+                    folded
+                })
+                .collect();
+            generator = generator.square();
+            shift = shift.square();
+            if round + 1 < rounds {
+                let tree = commit_layer(&layer);
+                transcript.append_message(b"layer", &tree.root());
+                folded_layers.push((layer.clone(), tree));
+            }
+        }
+        let final_value = layer[0];
+        transcript.append_message(b"final", &final_value.to_bytes());
+
+        let decommit = |evaluations: &[Fr], tree: &MerkleTree, position: usize| {
+            let half = evaluations.len() / 2;
+            let index = position % half;
+            let decommitment = FriDecommitment {
+                values: [evaluations[index], evaluations[index + half]],
+                path: tree.path(index),
+            };
+            (decommitment, index)
+        };
+        let queries = query_positions(transcript, domain.size, self.num_queries)
+            .into_iter()
+            .map(|position| {
+                let (first, mut position) = decommit(&evaluations, &committed, position);
+                let mut decommitments = vec![first];
+                for (evaluations, tree) in &folded_layers {
+                    let (decommitment, index) = decommit(evaluations, tree, position);
+                    decommitments.push(decommitment);
+                    position = index;
+                }
+                decommitments
+            })
+            .collect();
+
+        Ok((
+            value,
+            FriOpening {
+                layer_roots: folded_layers.iter().map(|(_, tree)| tree.root()).collect(),
+                final_value,
+                queries,
+            },
+        ))
+    }
+
+    fn verify(
+        &self,
+        commitment: &FriCommitment,
+        point: Fr,
+        value: Fr,
+        opening: &FriOpening,
+        transcript: &mut Transcript,
+    ) -> Result<bool, CryptoError> {
+        let claim = self.replay(
+            commitment,
+            point,
+            value,
+            &opening.layer_roots,
+            opening.final_value,
+            transcript,
+        )?;
+        match claim {
+            Some(claim) => self.check_queries(&claim, &opening.queries),
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use super::*;
+use bls12_381::Scalar as Fr;
+use curve25519_dalek::scalar::Scalar;
+use ff::{Field, PrimeField};
+use merlin::Transcript;
+use rand::Rng;
+
+// The lifted system, the size of the domain H its constraints and private
+// variables are laid out on, and the commitment scheme sized for it. The
+// verifier reads the constraint matrices itself.
+#[derive(Clone, Debug)]
+pub struct FriSnarkVerifyingKey {
+    system: LiftedSystem,
+    pub domain_size: usize,
+    pub circuit_digest: [u8; 32],
+    pub(crate) fri: Fri,
+}
+
+#[derive(Clone, Debug)]
+pub struct FriSnarkProvingKey {
+    verifying_key: FriSnarkVerifyingKey,
+}
+
+// Commitments to w, z_A, z_B, z_C, the running sum S and the quotient h;
+// their evaluations at zeta, then S at zeta omega; one opening for each
+// evaluation, in that order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriSnarkProof {
+    pub commitments: [FriCommitment; 6],
+    pub evaluations: [Fr; 7],
+    pub openings: Vec<FriOpening>,
+}
+
+// A proof short of the decommitments its openings' queries read: everything
+// the verifier hashes into the transcript
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriSnarkClaim {
+    pub commitments: [FriCommitment; 6],
+    pub evaluations: [Fr; 7],
+    pub layer_roots: Vec<Vec<[u8; 32]>>,
+    pub final_values: Vec<Fr>,
+}
+
+impl FriSnarkProof {
+    pub fn claim(&self) -> FriSnarkClaim {
+        FriSnarkClaim {
+            commitments: self.commitments,
+            evaluations: self.evaluations,
+            layer_roots: self
+                .openings
+                .iter()
+                .map(|opening| opening.layer_roots.clone())
+                .collect(),
+            final_values: self
+                .openings
+                .iter()
+                .map(|opening| opening.final_value)
+                .collect(),
+        }
+    }
+}
+
+// An R1CS argument from hashing alone: the private variables w and the
+// vectors Az, Bz and Cz are committed with FRI over the domain H, and a
+// quotient shows z_A z_B - z_C and
+//   S(omega X) - S(X) - r(X) (z_A + eta z_B + eta^2 z_C)(X) + p(X) w(X) + sigma / |H|
+// vanish on H, for r(X) the random powers r^i on H, p(X) the private part of
+// r^T (A + eta B + eta^2 C), and sigma its public part against the public
+// inputs. The accumulator S can only wrap around when z_M = M z, so no
+// degree bound beyond FRI's is needed. Transparent, and relying on no group,
+// so plausibly post-quantum with the Fiat-Shamir transcript modelled as a
+// random oracle. Proofs are polylogarithmic but verification is linear in
+// the circuit, and openings reveal evaluations of the witness, so this is
+// an argument of knowledge without zero knowledge. Setup refuses lookups as
+// Groth16 does.
+#[derive(Clone, Copy, Debug)]
+pub struct FriSnark {
+    log_blowup: u32,
+    num_queries: usize,
+}
+
+impl Default for FriSnark {
+    fn default() -> Self {
+        Self {
+            log_blowup: 3,
+            num_queries: 40,
+        }
+    }
+}
+
+impl FriSnark {
+    pub fn with_blowup(mut self, log_blowup: u32) -> Self {
+        self.log_blowup = log_blowup.max(1);
+        self
+    }
+
+    pub fn with_queries(mut self, num_queries: usize) -> Self {
+        self.num_queries = num_queries.max(1);
+        self
+    }
+
+    fn transcript(verifying_key: &FriSnarkVerifyingKey, public_inputs: &[Fr]) -> Transcript {
+        let mut transcript = Transcript::new(b"fri-snark");
+        transcript.append_u64(b"n", verifying_key.domain_size as u64);
+        transcript.append_message(b"circuit", &verifying_key.circuit_digest);
+        for input in public_inputs {
+            transcript.append_message(b"public", &input.to_bytes());
+        }
+        transcript
+    }
+
+    // Everything but the FRI queries: the transcript and the identity at
+    // zeta, leaving what the queries of the seven openings are checked
+    // against, or None if the claim already fails
+    pub(crate) fn check_claim(
+        verifying_key: &FriSnarkVerifyingKey,
+        public_inputs: &[Scalar],
+        claim: &FriSnarkClaim,
+    ) -> Result<Option<Vec<FriQueryClaim>>, CryptoError> {
+        let system = &verifying_key.system;
+        if public_inputs.len() != system.num_public {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let domain =
+            EvaluationDomain::new(verifying_key.domain_size).ok_or(CryptoError::CircuitTooLarge)?;
+        if domain.size != verifying_key.domain_size
+            || claim.layer_roots.len() != 7
+            || claim.final_values.len() != 7
+        {
+            return Ok(None);
+        }
+        let n = domain.size;
+        let inputs: Vec<Fr> = public_inputs.iter().map(lift_scalar).collect();
+        let mut transcript = Self::transcript(verifying_key, &inputs);
+        for commitment in &claim.commitments[..4] {
+            transcript.append_message(b"commitment", &commitment.root);
+        }
+        let r = challenge(&mut transcript, b"r");
+        let eta = challenge(&mut transcript, b"eta");
+        transcript.append_message(b"commitment", &claim.commitments[4].root);
+        let alpha = challenge(&mut transcript, b"alpha");
+        transcript.append_message(b"commitment", &claim.commitments[5].root);
+        let zeta = challenge(&mut transcript, b"zeta");
+
+        // The identity at zeta, with r(zeta) and p(zeta) from the matrices
+        let Some(lagrange) = domain.lagrange_at(zeta) else {
+            return Ok(None);
+        };
+        let r_powers = powers(r, n);
+        let columns = combined_columns(system, &r_powers, eta);
+        let sigma = public_sum(&columns, &inputs);
+        let r_at = r_powers
+            .iter()
+            .zip(&lagrange)
+            .map(|(x, l)| x * l)
+            .sum::<Fr>();
+        let p_at = columns[1 + system.num_public..]
+            .iter()
+            .zip(&lagrange)
+            .map(|(x, l)| x * l)
+            .sum::<Fr>();
+        let [w, a, b, c, s, h, s_shifted] = claim.evaluations;
+        let step = sigma * Fr::from(n as u64).invert().unwrap();
+        let q = r_at * (a + eta * (b + eta * c)) - p_at * w;
+        let numerator = a * b - c + alpha * (s_shifted - s - q + step);
+        if numerator != h * domain.vanishing_at(zeta) {
+            return Ok(None);
+        }
+
+        let fri = &verifying_key.fri;
+        let committed = [0, 1, 2, 3, 4, 5, 4];
+        let mut claims = Vec::with_capacity(7);
+        let layers = claim.layer_roots.iter().zip(&claim.final_values);
+        for (index, (layer_roots, final_value)) in layers.enumerate() {
+            let point = if index == 6 {
+                zeta * domain.generator
+            } else {
+                zeta
+            };
+            let replayed = fri.replay(
+                &claim.commitments[committed[index]],
+                point,
+                claim.evaluations[index],
+                layer_roots,
+                *final_value,
+                &mut transcript,
+            )?;
+            match replayed {
+                Some(replayed) => claims.push(replayed),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(claims))
+    }
+}
+
+fn digest(system: &LiftedSystem) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&(system.num_public as u64).to_le_bytes());
+    hasher.update(&(system.num_private as u64).to_le_bytes());
+    for row in &system.rows {
+        for terms in row {
+            hasher.update(&(terms.len() as u64).to_le_bytes());
+            for (column, c) in terms {
+                hasher.update(&(*column as u64).to_le_bytes());
+                hasher.update(&c.to_bytes());
+            }
+        }
+    }
+    *hasher.finalize().as_bytes()
+}
+
+// r^0, r^1, ..., one per point of H
+fn powers(r: Fr, count: usize) -> Vec<Fr> {
+    std::iter::successors(Some(Fr::ONE), |x| Some(x * r))
+        .take(count)
+        .collect()
+}
+
+// The coefficient of every column in r^T (A + eta B + eta^2 C)
+fn combined_columns(system: &LiftedSystem, r_powers: &[Fr], eta: Fr) -> Vec<Fr> {
+    let mut columns = vec![Fr::ZERO; system.num_columns()];
+    for (row, r) in system.rows.iter().zip(r_powers) {
+        let mut weight = *r;
+        for terms in row {
+            for (column, c) in terms {
+                columns[*column] += weight * c;
+            }
+            weight *= eta;
+        }
+    }
+    columns
+}
+
+// sigma, the constant and public columns of the combination against the
+// public inputs
+fn public_sum(columns: &[Fr], public_inputs: &[Fr]) -> Fr {
+    columns[0]
+        + columns[1..]
+            .iter()
+            .zip(public_inputs)
+            .map(|(c, x)| c * x)
+            .sum::<Fr>()
+}
+
+impl ProofSystem for FriSnark {
+    type ProvingKey = FriSnarkProvingKey;
+    type VerifyingKey = FriSnarkVerifyingKey;
+    type Proof = FriSnarkProof;
+
+    fn name(&self) -> &'static str {
+        "fri-snark"
+    }
+
+    // Deterministic: nothing is secret
+    fn setup<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        _rng: &mut R,
+    ) -> Result<(FriSnarkProvingKey, FriSnarkVerifyingKey), CryptoError> {
+        if cs.num_lookups() > 0 {
+            return Err(CryptoError::LookupsUnsupported);
+        }
+        let system = LiftedSystem::new(cs);
+        let domain = EvaluationDomain::new(system.rows.len().max(system.num_private).max(2))
+            .ok_or(CryptoError::CircuitTooLarge)?;
+        // Every committed polynomial, the quotient included, has degree
+        // below |H|
+        let fri = Fri::new(domain.size - 1)
+            .with_blowup(self.log_blowup)
+            .with_queries(self.num_queries);
+        let verifying_key = FriSnarkVerifyingKey {
+            circuit_digest: digest(&system),
+            system,
+            domain_size: domain.size,
+            fri,
+        };
+        Ok((
+            FriSnarkProvingKey {
+                verifying_key: verifying_key.clone(),
+            },
+            verifying_key,
+        ))
+    }
+
+    fn prove<R: Rng + ?Sized>(
+        &self,
+        proving_key: &FriSnarkProvingKey,
+        assignment: &Assignment,
+        _rng: &mut R,
+    ) -> Result<FriSnarkProof, CryptoError> {
+        let verifying_key = &proving_key.verifying_key;
+        let system = &verifying_key.system;
+        let fri = &verifying_key.fri;
+        let domain =
+            EvaluationDomain::new(verifying_key.domain_size).ok_or(CryptoError::CircuitTooLarge)?;
+        let n = domain.size;
+        let values = system.values(assignment)?;
+        system.check(&values)?;
+        let public_inputs = &values[1..1 + system.num_public];
+        let mut transcript = Self::transcript(verifying_key, public_inputs);
+
+        // Round 1: w and z_A, z_B, z_C on H
+        let mut w = values[1 + system.num_public..].to_vec();
+        w.resize(n, Fr::ZERO);
+        let mut products: [Vec<Fr>; 3] = std::array::from_fn(|_| vec![Fr::ZERO; n]);
+        for (i, row) in system.rows.iter().enumerate() {
+            for (product, terms) in products.iter_mut().zip(row) {
+                product[i] = LiftedSystem::evaluate(terms, &values);
+            }
+        }
+        let [w_polynomial, z_a, z_b, z_c] =
+            [&w, &products[0], &products[1], &products[2]].map(|values| domain.ifft(values));
+        let mut commitments = [FriCommitment { root: [0; 32] }; 6];
+        for (commitment, polynomial) in
+            commitments
+                .iter_mut()
+                .zip([&w_polynomial, &z_a, &z_b, &z_c])
+        {
+            *commitment = fri.commit(polynomial)?;
+            transcript.append_message(b"commitment", &commitment.root);
+        }
+        let r = challenge(&mut transcript, b"r");
+        let eta = challenge(&mut transcript, b"eta");
+
+        // Round 2: S, stepping by q - sigma / n from S(1) = 0, which brings
+        // it back to 0 after n steps exactly when the q_i sum to sigma
+        let r_powers = powers(r, n);
+        let columns = combined_columns(system, &r_powers, eta);
+        let sigma = public_sum(&columns, public_inputs);
+        let mut p = columns[1 + system.num_public..].to_vec();
+        p.resize(n, Fr::ZERO);
+        let step = sigma * Fr::from(n as u64).invert().unwrap();
+        let mut s = Vec::with_capacity(n);
+        let mut running = Fr::ZERO;
+        for i in 0..n {
+            s.push(running);
+            let combined = products[0][i] + eta * (products[1][i] + eta * products[2][i]);
+            running += r_powers[i] * combined - p[i] * w[i] - step;
+        }
+        let s_polynomial = domain.ifft(&s);
+        commitments[4] = fri.commit(&s_polynomial)?;
+        transcript.append_message(b"commitment", &commitments[4].root);
+        let alpha = challenge(&mut transcript, b"alpha");
+
+        // Round 3: the quotient, whose numerator has degree below 2n
+        let big = EvaluationDomain::new(2 * n).ok_or(CryptoError::CircuitTooLarge)?;
+        let shift = Fr::MULTIPLICATIVE_GENERATOR;
+        let on_coset = |coefficients: &[Fr]| big.coset_fft(coefficients, shift);
+        let [w_coset, a_coset, b_coset, c_coset, s_coset] =
+            [&w_polynomial, &z_a, &z_b, &z_c, &s_polynomial].map(|p| on_coset(p));
+        let s_shifted_coset = on_coset(&shift_polynomial(&s_polynomial, domain.generator));
+        let r_coset = on_coset(&domain.ifft(&r_powers));
+        let p_coset = on_coset(&domain.ifft(&p));
+        let mut point = shift;
+        let mut quotient = Vec::with_capacity(big.size);
+        for k in 0..big.size {
+            let combined = a_coset[k] + eta * (b_coset[k] + eta * c_coset[k]);
+            let q = r_coset[k] * combined - p_coset[k] * w_coset[k];
+            let numerator = a_coset[k] * b_coset[k] - c_coset[k]
+                + alpha * (s_shifted_coset[k] - s_coset[k] - q + step);
+            quotient.push(numerator * domain.vanishing_at(point).invert().unwrap());
+            point *= big.generator;
+        }
+        let mut h = big.coset_ifft(&quotient, shift);
+        h.truncate(n);
+        commitments[5] = fri.commit(&h)?;
+        transcript.append_message(b"commitment", &commitments[5].root);
+        let zeta = challenge(&mut transcript, b"zeta");
+
+        // Round 4: openings
+        let mut evaluations = [Fr::ZERO; 7];
+        let mut openings = Vec::with_capacity(7);
+        let opened = [
+            &w_polynomial,
+            &z_a,
+            &z_b,
+            &z_c,
+            &s_polynomial,
+            &h,
+            &s_polynomial,
+        ];
+        for (index, polynomial) in opened.into_iter().enumerate() {
+            let point = if index == 6 {
+                zeta * domain.generator
+            } else {
+                zeta
+            };
+            let (value, opening) = fri.open(polynomial, point, &mut transcript)?;
+            evaluations[index] = value;
+            openings.push(opening);
+        }
+        Ok(FriSnarkProof {
+            commitments,
+            evaluations,
+            openings,
+        })
+    }
+
+    fn verify(
+        &self,
+        verifying_key: &FriSnarkVerifyingKey,
+        public_inputs: &[Scalar],
+        proof: &FriSnarkProof,
+    ) -> Result<bool, CryptoError> {
+        if proof.openings.len() != 7 {
+            return Ok(false);
+        }
+        let Some(claims) = Self::check_claim(verifying_key, public_inputs, &proof.claim())? else {
+            return Ok(false);
+        };
+        for (claim, opening) in claims.iter().zip(&proof.openings) {
+            if !verifying_key.fri.check_queries(claim, &opening.queries)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,356 @@
+use super::*;
+use curve25519_dalek::scalar::Scalar;
+
+// Gadgets append constraints for a common sub-statement to a constraint
+// system. Bits are linear combinations the caller has already constrained to
+// 0 or 1; intermediate variables are either fixed by a constraint in which
+// they appear alone or allocated with a hint.
+
+// Value of a linear combination with no variable terms
+fn constant_value(lc: &LinearCombination) -> Option<Scalar> {
+    lc.terms().iter().try_fold(Scalar::ZERO, |sum, (v, c)| {
+        (*v == Variable::One).then(|| sum + c)
+    })
+}
+
+pub fn constant_bit(bit: bool) -> LinearCombination {
+    if bit {
+        LinearCombination::from(Variable::One)
+    } else {
+        LinearCombination::zero()
+    }
+}
+
+// b * (1 - b) = 0
+pub fn enforce_boolean(cs: &mut ConstraintSystem, bit: impl Into<LinearCombination>) {
+    let bit = bit.into();
+    cs.enforce(
+        bit.clone(),
+        LinearCombination::from(Variable::One) - bit,
+        LinearCombination::zero(),
+    );
+}
+
+// `count` fresh private bits, each constrained boolean
+pub fn alloc_private_bits(cs: &mut ConstraintSystem, count: usize) -> Vec<LinearCombination> {
+    (0..count)
+        .map(|_| {
+            let bit = cs.alloc_private();
+            enforce_boolean(cs, bit);
+            LinearCombination::from(bit)
+        })
+        .collect()
+}
+
+pub fn alloc_public_bits(cs: &mut ConstraintSystem, count: usize) -> Vec<LinearCombination> {
+    (0..count)
+        .map(|_| {
+            let bit = cs.alloc_public();
+            enforce_boolean(cs, bit);
+            LinearCombination::from(bit)
+        })
+        .collect()
+}
+
+// Product of two field elements, allocated unless either is a constant
+pub fn multiply(
+    cs: &mut ConstraintSystem,
+    a: &LinearCombination,
+    b: &LinearCombination,
+) -> LinearCombination {
+    if let Some(c) = constant_value(a) {
+        return b.clone() * c;
+    }
+    if let Some(c) = constant_value(b) {
+        return a.clone() * c;
+    }
+    let product = cs.alloc_private();
+    cs.enforce(a.clone(), b.clone(), product);
+    product.into()
+}
+
+pub fn and(
+    cs: &mut ConstraintSystem,
+    a: &LinearCombination,
+    b: &LinearCombination,
+) -> LinearCombination {
+    multiply(cs, a, b)
+}
+
+pub fn not(a: &LinearCombination) -> LinearCombination {
+    LinearCombination::from(Variable::One) - a.clone()
+}
+
+// a + b - 2ab, allocated so that long chains of xors keep their linear
+// combinations short: 2a * b = a + b - result
+pub fn xor(
+    cs: &mut ConstraintSystem,
+    a: &LinearCombination,
+    b: &LinearCombination,
+) -> LinearCombination {
+    if constant_value(a).is_some() || constant_value(b).is_some() {
+        let product = multiply(cs, a, b);
+        return a.clone() + b.clone() - product * Scalar::from(2u64);
+    }
+    let result = cs.alloc_private();
+    cs.enforce(
+        a.clone() * Scalar::from(2u64),
+        b.clone(),
+        a.clone() + b.clone() - result,
+    );
+    result.into()
+}
+
+// if_false + condition * (if_true - if_false), for a boolean condition
+pub fn select(
+    cs: &mut ConstraintSystem,
+    condition: &LinearCombination,
+    if_true: &LinearCombination,
+    if_false: &LinearCombination,
+) -> LinearCombination {
+    let difference = if_true.clone() - if_false.clone();
+    if_false.clone() + multiply(cs, condition, &difference)
+}
+
+// sum 2^i bits[i]
+pub fn pack_bits(bits: &[LinearCombination]) -> LinearCombination {
+    let mut weight = Scalar::ONE;
+    let mut packed = LinearCombination::zero();
+    for bit in bits {
+        packed = packed + bit.clone() * weight;
+        weight += weight;
+    }
+    packed
+}
+
+// The low `num_bits` bits of `value`, least significant first, constrained
+// to recompose it; this is also the range check value < 2^num_bits. Widths
+// past 252 bits would let the recomposition wrap around the field.
+pub fn decompose(
+    cs: &mut ConstraintSystem,
+    value: &LinearCombination,
+    num_bits: usize,
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if num_bits > 252 {
+        return Err(CryptoError::RangeTooWide(num_bits));
+    }
+    let bits: Vec<LinearCombination> = (0..num_bits)
+        .map(|bit| {
+            let variable = cs.alloc_hinted(Hint::Bit {
+                source: value.clone(),
+                bit,
+            });
+            enforce_boolean(cs, variable);
+            LinearCombination::from(variable)
+        })
+        .collect();
+    cs.enforce_equal(pack_bits(&bits), value.clone());
+    Ok(bits)
+}
+
+pub fn range_check(
+    cs: &mut ConstraintSystem,
+    value: &LinearCombination,
+    num_bits: usize,
+) -> Result<(), CryptoError> {
+    decompose(cs, value, num_bits).map(|_| ())
+}
+
+// The low `num_bits` bits of `value` as limbs of `limb_bits` bits, least
+// significant first, each looked up in the range table of that width: one
+// lookup per limb instead of a constraint per bit. A shorter top limb is also
+// looked up shifted to the full width, which bounds it by its own width.
+pub fn decompose_limbs(
+    cs: &mut ConstraintSystem,
+    value: &LinearCombination,
+    num_bits: usize,
+    limb_bits: usize,
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if num_bits > 252 {
+        return Err(CryptoError::RangeTooWide(num_bits));
+    }
+    if limb_bits == 0 {
+        return Err(CryptoError::InvalidLookupTable);
+    }
+    let table = cs.add_table(LookupTable::range(limb_bits)?)?;
+    let mut limbs = Vec::new();
+    let mut packed = LinearCombination::zero();
+    let mut weight = Scalar::ONE;
+    for offset in (0..num_bits).step_by(limb_bits) {
+        let count = limb_bits.min(num_bits - offset);
+        let limb = LinearCombination::from(cs.alloc_hinted(Hint::Bits {
+            source: value.clone(),
+            offset,
+            count,
+        }));
+        cs.enforce_lookup(table, vec![limb.clone()])?;
+        if count < limb_bits {
+            let shift = Scalar::from(1u64 << (limb_bits - count));
+            cs.enforce_lookup(table, vec![limb.clone() * shift])?;
+        }
+        packed = packed + limb.clone() * weight;
+        for _ in 0..limb_bits {
+            weight += weight;
+        }
+        limbs.push(limb);
+    }
+    cs.enforce_equal(packed, value.clone());
+    Ok(limbs)
+}
+
+pub fn range_check_lookup(
+    cs: &mut ConstraintSystem,
+    value: &LinearCombination,
+    num_bits: usize,
+    limb_bits: usize,
+) -> Result<(), CryptoError> {
+    decompose_limbs(cs, value, num_bits, limb_bits).map(|_| ())
+}
+
+// 32-bit word as bits, least significant first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UInt32 {
+    pub bits: Vec<LinearCombination>,
+}
+
+impl UInt32 {
+    pub fn constant(value: u32) -> Self {
+        Self {
+            bits: (0..32)
+                .map(|i| constant_bit((value >> i) & 1 == 1))
+                .collect(),
+        }
+    }
+
+    // Words from bits, 32 per word, least significant first
+    pub fn from_bits(bits: &[LinearCombination]) -> Result<Self, CryptoError> {
+        if bits.len() != 32 {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        Ok(Self {
+            bits: bits.to_vec(),
+        })
+    }
+
+    pub fn xor(&self, cs: &mut ConstraintSystem, other: &UInt32) -> UInt32 {
+        UInt32 {
+            bits: self
+                .bits
+                .iter()
+                .zip(&other.bits)
+                .map(|(a, b)| xor(cs, a, b))
+                .collect(),
+        }
+    }
+
+    pub fn rotate_right(&self, amount: usize) -> UInt32 {
+        UInt32 {
+            bits: (0..32)
+                .map(|i| self.bits[(i + amount) % 32].clone())
+                .collect(),
+        }
+    }
+
+    // Sum of the words modulo 2^32
+    pub fn sum(cs: &mut ConstraintSystem, words: &[UInt32]) -> Result<UInt32, CryptoError> {
+        let total = words.iter().fold(LinearCombination::zero(), |total, word| {
+            total + pack_bits(&word.bits)
+        });
+        if let Some(value) = constant_value(&total) {
+            let bytes = value.to_bytes();
+            return Ok(UInt32::constant(u32::from_le_bytes([
+                bytes[0], bytes[1], bytes[2], bytes[3],
+            ])));
+        }
+        let carry_bits = usize::BITS as usize - words.len().leading_zeros() as usize;
+        let mut bits = decompose(cs, &total, 32 + carry_bits)?;
+        bits.truncate(32);
+        Ok(UInt32 { bits })
+    }
+}
+
+// Twisted Edwards curve a x^2 + y^2 = 1 + d x^2 y^2 over the scalar field.
+// The addition law used here is complete, with no exceptional points, when a
+// is a square and d is not; choosing such parameters is up to the caller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EdwardsCurve {
+    pub a: Scalar,
+    pub d: Scalar,
+}
+
+impl EdwardsCurve {
+    pub fn new(a: Scalar, d: Scalar) -> Self {
+        Self { a, d }
+    }
+
+    pub fn identity() -> (Scalar, Scalar) {
+        (Scalar::ZERO, Scalar::ONE)
+    }
+
+    pub fn is_on_curve(&self, (x, y): (Scalar, Scalar)) -> bool {
+        let (xx, yy) = (x * x, y * y);
+        self.a * xx + yy == Scalar::ONE + self.d * xx * yy
+    }
+
+    pub fn add(&self, (x1, y1): (Scalar, Scalar), (x2, y2): (Scalar, Scalar)) -> (Scalar, Scalar) {
+        let t = self.d * x1 * x2 * y1 * y2;
+        (
+            (x1 * y2 + y1 * x2) * (Scalar::ONE + t).invert(),
+            (y1 * y2 - self.a * x1 * x2) * (Scalar::ONE - t).invert(),
+        )
+    }
+
+    // `bits` least significant first
+    pub fn scalar_mul(&self, bits: &[bool], point: (Scalar, Scalar)) -> (Scalar, Scalar) {
+        bits.iter().rev().fold(Self::identity(), |acc, &bit| {
+            let doubled = self.add(acc, acc);
+            if bit {
+                self.add(doubled, point)
+            } else {
+                doubled
+            }
+        })
+    }
+
+    pub fn add_gadget(
+        &self,
+        cs: &mut ConstraintSystem,
+        (x1, y1): &(LinearCombination, LinearCombination),
+        (x2, y2): &(LinearCombination, LinearCombination),
+    ) -> (LinearCombination, LinearCombination) {
+        let x1y2 = multiply(cs, x1, y2);
+        let y1x2 = multiply(cs, y1, x2);
+        let x1x2 = multiply(cs, x1, x2);
+        let y1y2 = multiply(cs, y1, y2);
+        let t = multiply(cs, &x1x2, &y1y2) * self.d;
+        let one = LinearCombination::from(Variable::One);
+
+        let x3 = cs.alloc_private();
+        cs.enforce(x3, one.clone() + t.clone(), x1y2 + y1x2);
+        let y3 = cs.alloc_private();
+        cs.enforce(y3, one - t, y1y2 - x1x2 * self.a);
+        (x3.into(), y3.into())
+    }
+
+    // Double-and-add over boolean `bits`, least significant first
+    pub fn scalar_mul_gadget(
+        &self,
+        cs: &mut ConstraintSystem,
+        bits: &[LinearCombination],
+        point: &(LinearCombination, LinearCombination),
+    ) -> (LinearCombination, LinearCombination) {
+        let mut acc = (
+            LinearCombination::zero(),
+            LinearCombination::from(Variable::One),
+        );
+        for bit in bits.iter().rev() {
+            let doubled = self.add_gadget(cs, &acc, &acc);
+            let added = self.add_gadget(cs, &doubled, point);
+            acc = (
+                select(cs, bit, &added.0, &doubled.0),
+                select(cs, bit, &added.1, &doubled.1),
+            );
+        }
+        acc
+    }
+}
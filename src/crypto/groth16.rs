@@ -0,0 +1,522 @@
+use super::*;
+use bls12_381::{
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar as Fr,
+};
+use curve25519_dalek::scalar::Scalar;
+use ff::{Field, PrimeField};
+use rand::Rng;
+use std::collections::HashSet;
+use std::ops::Range;
+
+// Sparse (column, coefficient) pairs of a linear combination
+pub(crate) type SparseRow = Vec<(usize, Fr)>;
+
+// Coefficient and wire exponents of each term of a custom gate
+pub(crate) type GateTerms = Vec<(Fr, Vec<u32>)>;
+
+// A constraint system re-encoded over the BLS12-381 scalar field, one column
+// per variable: the constant, then the public inputs, then the private ones
+#[derive(Clone, Debug)]
+pub(crate) struct LiftedSystem {
+    pub(crate) num_public: usize,
+    pub(crate) num_private: usize,
+    // (a, b, c) of each constraint
+    pub(crate) rows: Vec<[SparseRow; 3]>,
+    pub(crate) names: Vec<String>,
+    // Each lookup's table index and inputs, then every table's rows
+    pub(crate) lookups: Vec<(usize, Vec<SparseRow>)>,
+    pub(crate) lookup_names: Vec<String>,
+    pub(crate) tables: Vec<Vec<Vec<Fr>>>,
+    // Each custom gate's width and terms, then each application's gate,
+    // inputs and the constraints it was lowered to
+    pub(crate) custom_gates: Vec<(usize, GateTerms)>,
+    pub(crate) applications: Vec<(usize, Vec<SparseRow>, Range<usize>)>,
+}
+
+impl LiftedSystem {
+    pub(crate) fn new(cs: &ConstraintSystem) -> Self {
+        let num_public = cs.num_public();
+        let column = |variable: Variable| match variable {
+            Variable::One => 0,
+            Variable::Public(i) => 1 + i,
+            Variable::Private(i) => 1 + num_public + i,
+        };
+        let lift = |lc: &LinearCombination| {
+            lc.terms()
+                .iter()
+                .map(|(v, c)| (column(*v), lift_scalar(c)))
+                .collect::<Vec<_>>()
+        };
+        Self {
+            num_public,
+            num_private: cs.num_private(),
+            rows: cs
+                .constraints()
+                .iter()
+                .map(|c| [lift(&c.a), lift(&c.b), lift(&c.c)])
+                .collect(),
+            names: cs.constraints().iter().map(|c| c.name.clone()).collect(),
+            lookups: cs
+                .lookups()
+                .iter()
+                .map(|l| (l.table.0, l.inputs.iter().map(lift).collect()))
+                .collect(),
+            lookup_names: cs.lookups().iter().map(|l| l.name.clone()).collect(),
+            tables: cs
+                .tables()
+                .iter()
+                .map(|t| {
+                    t.rows()
+                        .iter()
+                        .map(|row| row.iter().map(lift_scalar).collect())
+                        .collect()
+                })
+                .collect(),
+            custom_gates: cs
+                .custom_gates()
+                .iter()
+                .map(|g| {
+                    let terms = g.terms().iter();
+                    (
+                        g.width(),
+                        terms.map(|(c, e)| (lift_scalar(c), e.clone())).collect(),
+                    )
+                })
+                .collect(),
+            applications: cs
+                .gate_applications()
+                .iter()
+                .map(|a| {
+                    (
+                        a.gate.0,
+                        a.inputs.iter().map(lift).collect(),
+                        a.lowered.clone(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn num_columns(&self) -> usize {
+        1 + self.num_public + self.num_private
+    }
+
+    // Column values of `assignment`
+    pub(crate) fn values(&self, assignment: &Assignment) -> Result<Vec<Fr>, CryptoError> {
+        if assignment.public.len() != self.num_public
+            || assignment.private.len() != self.num_private
+        {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        Ok(std::iter::once(Fr::ONE)
+            .chain(assignment.public.iter().map(lift_scalar))
+            .chain(assignment.private.iter().map(lift_scalar))
+            .collect())
+    }
+
+    pub(crate) fn evaluate(row: &[(usize, Fr)], values: &[Fr]) -> Fr {
+        row.iter().map(|(column, c)| values[*column] * c).sum()
+    }
+
+    // Fails on the first constraint or lookup that held natively but wraps
+    // around once lifted
+    pub(crate) fn check(&self, values: &[Fr]) -> Result<(), CryptoError> {
+        for (index, [a, b, c]) in self.rows.iter().enumerate() {
+            if Self::evaluate(a, values) * Self::evaluate(b, values) != Self::evaluate(c, values) {
+                return Err(CryptoError::LiftedConstraintFailed(
+                    index,
+                    self.names[index].clone(),
+                ));
+            }
+        }
+        let tables: Vec<HashSet<Vec<[u8; 32]>>> = self
+            .tables
+            .iter()
+            .map(|rows| {
+                rows.iter()
+                    .map(|row| row.iter().map(|x| x.to_bytes()).collect())
+                    .collect()
+            })
+            .collect();
+        for (index, (table, inputs)) in self.lookups.iter().enumerate() {
+            let row: Vec<[u8; 32]> = inputs
+                .iter()
+                .map(|input| Self::evaluate(input, values).to_bytes())
+                .collect();
+            if !tables[*table].contains(&row) {
+                return Err(CryptoError::LiftedLookupFailed(
+                    index,
+                    self.lookup_names[index].clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Groth16VerifyingKey {
+    pub alpha_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    pub gamma_g2: G2Affine,
+    pub delta_g2: G2Affine,
+    // Input commitments: the constant, then each public input
+    pub ic: Vec<G1Affine>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Groth16ProvingKey {
+    system: LiftedSystem,
+    domain: EvaluationDomain,
+    alpha_g1: G1Affine,
+    beta_g1: G1Affine,
+    beta_g2: G2Affine,
+    delta_g1: G1Affine,
+    delta_g2: G2Affine,
+    a_query: Vec<G1Affine>,
+    b_g1_query: Vec<G1Affine>,
+    b_g2_query: Vec<G2Affine>,
+    l_query: Vec<G1Affine>,
+    h_query: Vec<G1Affine>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Groth16Proof {
+    pub a: G1Affine,
+    pub b: G2Affine,
+    pub c: G1Affine,
+}
+
+// Groth16 over BLS12-381. Circuits are lifted out of the Ristretto scalar
+// field by lift_scalar, so proving fails with LiftedConstraintFailed on
+// witnesses whose constraints only hold modulo the Ristretto group order.
+// Lookups have no rank-1 encoding here, so setup refuses systems with any.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Groth16;
+
+// The toxic waste of a setup. Whoever holds it can simulate proofs of any
+// statement, so setup normally discards it.
+#[derive(Clone, Debug)]
+pub struct Groth16Trapdoor {
+    pub tau: Fr,
+    pub alpha: Fr,
+    pub beta: Fr,
+    pub gamma: Fr,
+    pub delta: Fr,
+}
+
+impl Simulatable for Groth16 {
+    type Trapdoor = Groth16Trapdoor;
+
+    fn setup_with_trapdoor<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(Groth16ProvingKey, Groth16VerifyingKey, Groth16Trapdoor), CryptoError> {
+        if cs.num_lookups() > 0 {
+            return Err(CryptoError::LookupsUnsupported);
+        }
+        let mut system = LiftedSystem::new(cs);
+        // x_k * 0 = 0 for every input column keeps the input polynomials
+        // linearly independent
+        let num_inputs = 1 + system.num_public;
+        for k in 0..num_inputs {
+            system.rows.push([vec![(k, Fr::ONE)], vec![], vec![]]);
+            system.names.push(format!("input {}", k));
+        }
+        let domain =
+            EvaluationDomain::new(system.rows.len()).ok_or(CryptoError::CircuitTooLarge)?;
+
+        let (tau, lagrange) = loop {
+            let tau = Fr::random(&mut *rng);
+            if let Some(lagrange) = domain.lagrange_at(tau) {
+                break (tau, lagrange);
+            }
+        };
+        let nonzero = |rng: &mut R| loop {
+            let x = Fr::random(&mut *rng);
+            if x != Fr::ZERO {
+                break x;
+            }
+        };
+        let alpha = nonzero(rng);
+        let beta = nonzero(rng);
+        let gamma = nonzero(rng);
+        let delta = nonzero(rng);
+        let gamma_inverse = gamma.invert().unwrap();
+        let delta_inverse = delta.invert().unwrap();
+
+        // u, v, w of every column at tau
+        let columns = system.num_columns();
+        let mut polynomials = vec![[Fr::ZERO; 3]; columns];
+        for (row, basis) in system.rows.iter().zip(&lagrange) {
+            for (side, terms) in row.iter().enumerate() {
+                for (column, c) in terms {
+                    polynomials[*column][side] += c * basis;
+                }
+            }
+        }
+        let combined: Vec<Fr> = polynomials
+            .iter()
+            .map(|[u, v, w]| beta * u + alpha * v + w)
+            .collect();
+
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let to_g1 = |x: Fr| G1Affine::from(g1 * x);
+        let to_g2 = |x: Fr| G2Affine::from(g2 * x);
+
+        let vanishing = domain.vanishing_at(tau) * delta_inverse;
+        let mut power = Fr::ONE;
+        let h_query = (0..domain.size - 1)
+            .map(|_| {
+                let point = to_g1(power * vanishing);
+                power *= tau;
+                point
+            })
+            .collect();
+
+        let verifying_key = Groth16VerifyingKey {
+            alpha_g1: to_g1(alpha),
+            beta_g2: to_g2(beta),
+            gamma_g2: to_g2(gamma),
+            delta_g2: to_g2(delta),
+            ic: combined[..num_inputs]
+                .iter()
+                .map(|x| to_g1(x * gamma_inverse))
+                .collect(),
+        };
+        let proving_key = Groth16ProvingKey {
+            alpha_g1: verifying_key.alpha_g1,
+            beta_g1: to_g1(beta),
+            beta_g2: verifying_key.beta_g2,
+            delta_g1: to_g1(delta),
+            delta_g2: verifying_key.delta_g2,
+            a_query: polynomials.iter().map(|[u, _, _]| to_g1(*u)).collect(),
+            b_g1_query: polynomials.iter().map(|[_, v, _]| to_g1(*v)).collect(),
+            b_g2_query: polynomials.iter().map(|[_, v, _]| to_g2(*v)).collect(),
+            l_query: combined[num_inputs..]
+                .iter()
+                .map(|x| to_g1(x * delta_inverse))
+                .collect(),
+            h_query,
+            system,
+            domain,
+        };
+        let trapdoor = Groth16Trapdoor {
+            tau,
+            alpha,
+            beta,
+            gamma,
+            delta,
+        };
+        Ok((proving_key, verifying_key, trapdoor))
+    }
+
+    // A and B uniform, as in honest proofs, and C the one point that then
+    // balances the verification equation:
+    // C = ((a b - alpha beta) G - gamma IC) / delta
+    fn simulate<R: Rng + ?Sized>(
+        &self,
+        verifying_key: &Groth16VerifyingKey,
+        trapdoor: &Groth16Trapdoor,
+        public_inputs: &[Scalar],
+        rng: &mut R,
+    ) -> Result<Groth16Proof, CryptoError> {
+        if public_inputs.len() + 1 != verifying_key.ic.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let delta_inverse =
+            Option::<Fr>::from(trapdoor.delta.invert()).ok_or(CryptoError::InvalidTrapdoor)?;
+        let inputs: Vec<Fr> = public_inputs.iter().map(lift_scalar).collect();
+        let ic = G1Projective::from(verifying_key.ic[0])
+            + pippenger::<G1Projective>(&verifying_key.ic[1..], &inputs);
+
+        let a = Fr::random(&mut *rng);
+        let b = Fr::random(&mut *rng);
+        let g1 = G1Projective::generator();
+        let c =
+            (g1 * (a * b - trapdoor.alpha * trapdoor.beta) - ic * trapdoor.gamma) * delta_inverse;
+        Ok(Groth16Proof {
+            a: (g1 * a).into(),
+            b: (G2Projective::generator() * b).into(),
+            c: c.into(),
+        })
+    }
+}
+
+impl ProofSystem for Groth16 {
+    type ProvingKey = Groth16ProvingKey;
+    type VerifyingKey = Groth16VerifyingKey;
+    type Proof = Groth16Proof;
+
+    fn name(&self) -> &'static str {
+        "groth16"
+    }
+
+    fn setup<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(Groth16ProvingKey, Groth16VerifyingKey), CryptoError> {
+        let (proving_key, verifying_key, _) = self.setup_with_trapdoor(cs, rng)?;
+        Ok((proving_key, verifying_key))
+    }
+
+    fn prove<R: Rng + ?Sized>(
+        &self,
+        proving_key: &Groth16ProvingKey,
+        assignment: &Assignment,
+        rng: &mut R,
+    ) -> Result<Groth16Proof, CryptoError> {
+        let system = &proving_key.system;
+        let domain = &proving_key.domain;
+        let values = system.values(assignment)?;
+        system.check(&values)?;
+
+        // h = (a b - c) / Z, computed on a coset where Z is a nonzero constant
+        let side = |index: usize| {
+            let evaluations: Vec<Fr> = system
+                .rows
+                .iter()
+                .map(|row| LiftedSystem::evaluate(&row[index], &values))
+                .collect();
+            domain.coset_fft(&domain.ifft(&evaluations), Fr::MULTIPLICATIVE_GENERATOR)
+        };
+        let (a, b, c) = (side(0), side(1), side(2));
+        let vanishing_inverse = domain
+            .vanishing_at(Fr::MULTIPLICATIVE_GENERATOR)
+            .invert()
+            .unwrap();
+        let quotient: Vec<Fr> = a
+            .iter()
+            .zip(&b)
+            .zip(&c)
+            .map(|((a, b), c)| (a * b - c) * vanishing_inverse)
+            .collect();
+        let h = domain.coset_ifft(&quotient, Fr::MULTIPLICATIVE_GENERATOR);
+
+        let r = Fr::random(&mut *rng);
+        let s = Fr::random(&mut *rng);
+        let num_inputs = 1 + system.num_public;
+
+        let a = proving_key.alpha_g1
+            + pippenger::<G1Projective>(&proving_key.a_query, &values)
+            + proving_key.delta_g1 * r;
+        let b = G2Projective::from(proving_key.beta_g2)
+            + pippenger::<G2Projective>(&proving_key.b_g2_query, &values)
+            + proving_key.delta_g2 * s;
+        let b_g1 = proving_key.beta_g1
+            + pippenger::<G1Projective>(&proving_key.b_g1_query, &values)
+            + proving_key.delta_g1 * s;
+        let c = pippenger::<G1Projective>(&proving_key.l_query, &values[num_inputs..])
+            + pippenger::<G1Projective>(&proving_key.h_query, &h)
+            + a * s
+            + b_g1 * r
+            - proving_key.delta_g1 * (r * s);
+
+        Ok(Groth16Proof {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+        })
+    }
+
+    // e(A, B) = e(alpha, beta) e(IC, gamma) e(C, delta)
+    fn verify(
+        &self,
+        verifying_key: &Groth16VerifyingKey,
+        public_inputs: &[Scalar],
+        proof: &Groth16Proof,
+    ) -> Result<bool, CryptoError> {
+        if public_inputs.len() + 1 != verifying_key.ic.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let inputs: Vec<Fr> = public_inputs.iter().map(lift_scalar).collect();
+        let ic = G1Affine::from(
+            G1Projective::from(verifying_key.ic[0])
+                + pippenger::<G1Projective>(&verifying_key.ic[1..], &inputs),
+        );
+
+        let beta = G2Prepared::from(verifying_key.beta_g2);
+        let gamma = G2Prepared::from(verifying_key.gamma_g2);
+        let delta = G2Prepared::from(verifying_key.delta_g2);
+        let b = G2Prepared::from(proof.b);
+        let result = multi_miller_loop(&[
+            (&-proof.a, &b),
+            (&verifying_key.alpha_g1, &beta),
+            (&ic, &gamma),
+            (&proof.c, &delta),
+        ])
+        .final_exponentiation();
+        Ok(result == Gt::identity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // y = x^3 + x + 5 with x in [0, 2^8)
+    fn cubic() -> (ConstraintSystem, Variable) {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        let x_lc: LinearCombination = x.into();
+        let square = multiply(&mut cs, &x_lc, &x_lc);
+        let cube = multiply(&mut cs, &square, &x_lc);
+        cs.enforce_equal(cube + x_lc.clone() + Scalar::from(5u64), y);
+        range_check(&mut cs, &x_lc, 8).unwrap();
+        (cs, x)
+    }
+
+    #[test]
+    fn proofs_verify_only_their_statement() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1089);
+        let (cs, x) = cubic();
+        let (proving_key, verifying_key) = Groth16.setup(&cs, &mut rng).unwrap();
+        let assignment = cs
+            .witness_builder()
+            .with_public_inputs(&[Scalar::from(35u64)])
+            .unwrap()
+            .with_value(x, Scalar::from(3u64))
+            .unwrap()
+            .build()
+            .unwrap();
+        let proof = Groth16.prove(&proving_key, &assignment, &mut rng).unwrap();
+        let inputs = [Scalar::from(35u64)];
+        assert!(Groth16.verify(&verifying_key, &inputs, &proof).unwrap());
+        assert!(!Groth16
+            .verify(&verifying_key, &[Scalar::from(36u64)], &proof)
+            .unwrap());
+
+        let mut tampered = proof;
+        tampered.c = tampered.a;
+        assert!(!Groth16.verify(&verifying_key, &inputs, &tampered).unwrap());
+        let mut tampered = proof;
+        tampered.b = G2Affine::generator();
+        assert!(!Groth16.verify(&verifying_key, &inputs, &tampered).unwrap());
+
+        let (_, other_key) = Groth16.setup(&cs, &mut rng).unwrap();
+        assert!(!Groth16.verify(&other_key, &inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn refuses_witnesses_that_only_hold_modulo_the_ristretto_order() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1089);
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce(x, x, y);
+        let (proving_key, _) = Groth16.setup(&cs, &mut rng).unwrap();
+        let half = Scalar::from(2u64).invert();
+        let assignment = Assignment::new(vec![half * half], vec![half]);
+        assert!(matches!(
+            Groth16.prove(&proving_key, &assignment, &mut rng),
+            Err(CryptoError::LiftedConstraintFailed(..))
+        ));
+    }
+}
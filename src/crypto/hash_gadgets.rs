@@ -0,0 +1,479 @@
+use super::*;
+use curve25519_dalek::scalar::Scalar;
+
+// Bit strings throughout are byte by byte, each byte least significant bit
+// first, which is also the little-endian bit order of BLAKE3 words and
+// Keccak lanes.
+
+const BLAKE3_IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+const BLAKE3_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+pub const BLAKE3_CHUNK_START: u32 = 1;
+pub const BLAKE3_CHUNK_END: u32 = 2;
+pub const BLAKE3_PARENT: u32 = 4;
+pub const BLAKE3_ROOT: u32 = 8;
+
+// (a, b, c, d) state indices of the eight G applications of a round
+const BLAKE3_G: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// Rotation of lane (x, y), indexed [x][y]
+const KECCAK_ROTATIONS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// SHA3-256 absorbs 136-byte blocks
+const SHA3_256_RATE: usize = 136 * 8;
+
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+pub fn constant_bits(bytes: &[u8]) -> Vec<LinearCombination> {
+    bytes_to_bits(bytes).into_iter().map(constant_bit).collect()
+}
+
+// Native BLAKE3 compression function, all 16 output words
+pub fn blake3_compress(
+    chaining_value: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..8].copy_from_slice(chaining_value);
+    state[8..12].copy_from_slice(&BLAKE3_IV[..4]);
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = block_len;
+    state[15] = flags;
+
+    let mut message = *block;
+    for round in 0..7 {
+        for (g, &[a, b, c, d]) in BLAKE3_G.iter().enumerate() {
+            let (x, y) = (message[2 * g], message[2 * g + 1]);
+            state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+            state[d] = (state[d] ^ state[a]).rotate_right(16);
+            state[c] = state[c].wrapping_add(state[d]);
+            state[b] = (state[b] ^ state[c]).rotate_right(12);
+            state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+            state[d] = (state[d] ^ state[a]).rotate_right(8);
+            state[c] = state[c].wrapping_add(state[d]);
+            state[b] = (state[b] ^ state[c]).rotate_right(7);
+        }
+        if round < 6 {
+            message = BLAKE3_PERMUTATION.map(|i| message[i]);
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+// Constraints for blake3_compress on word gadgets; the counter, length and
+// flags are fixed by the circuit
+pub fn blake3_compress_gadget(
+    cs: &mut ConstraintSystem,
+    chaining_value: &[UInt32],
+    block: &[UInt32],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> Result<Vec<UInt32>, CryptoError> {
+    if chaining_value.len() != 8 || block.len() != 16 {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    let mut state: Vec<UInt32> = chaining_value.to_vec();
+    state.extend(BLAKE3_IV[..4].iter().map(|&w| UInt32::constant(w)));
+    state.push(UInt32::constant(counter as u32));
+    state.push(UInt32::constant((counter >> 32) as u32));
+    state.push(UInt32::constant(block_len));
+    state.push(UInt32::constant(flags));
+
+    let mut message = block.to_vec();
+    for round in 0..7 {
+        for (g, &[a, b, c, d]) in BLAKE3_G.iter().enumerate() {
+            let (x, y) = (&message[2 * g], &message[2 * g + 1]);
+            state[a] = UInt32::sum(cs, &[state[a].clone(), state[b].clone(), x.clone()])?;
+            state[d] = state[d].xor(cs, &state[a]).rotate_right(16);
+            state[c] = UInt32::sum(cs, &[state[c].clone(), state[d].clone()])?;
+            state[b] = state[b].xor(cs, &state[c]).rotate_right(12);
+            state[a] = UInt32::sum(cs, &[state[a].clone(), state[b].clone(), y.clone()])?;
+            state[d] = state[d].xor(cs, &state[a]).rotate_right(8);
+            state[c] = UInt32::sum(cs, &[state[c].clone(), state[d].clone()])?;
+            state[b] = state[b].xor(cs, &state[c]).rotate_right(7);
+        }
+        if round < 6 {
+            message = BLAKE3_PERMUTATION
+                .iter()
+                .map(|&i| message[i].clone())
+                .collect();
+        }
+    }
+    for i in 0..8 {
+        state[i] = state[i].xor(cs, &state[i + 8]);
+        state[i + 8] = state[i + 8].xor(cs, &chaining_value[i]);
+    }
+    Ok(state)
+}
+
+fn words_from_bits(bits: &[LinearCombination]) -> Result<Vec<UInt32>, CryptoError> {
+    bits.chunks(32).map(UInt32::from_bits).collect()
+}
+
+fn words_from_bytes<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    words
+}
+
+// Merkle trees hash two children with the BLAKE3 parent-node compression
+pub fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    block[..32].copy_from_slice(left);
+    block[32..].copy_from_slice(right);
+    let output = blake3_compress(&BLAKE3_IV, &words_from_bytes(&block), 0, 64, BLAKE3_PARENT);
+    let mut node = [0u8; 32];
+    for (chunk, word) in node.chunks_mut(4).zip(&output[..8]) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    node
+}
+
+// `path` lists siblings from the leaf upwards, each with whether the node
+// on the path is the right child at that level
+pub fn merkle_root(leaf: &[u8; 32], path: &[([u8; 32], bool)]) -> [u8; 32] {
+    path.iter().fold(*leaf, |node, (sibling, is_right)| {
+        if *is_right {
+            merkle_parent(sibling, &node)
+        } else {
+            merkle_parent(&node, sibling)
+        }
+    })
+}
+
+// Binary Merkle tree over a power of two of 32-byte leaves, with
+// merkle_parent nodes so that paths can also be checked in circuit
+#[derive(Clone, Debug)]
+pub(crate) struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub(crate) fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut layers = vec![leaves];
+        while layers[layers.len() - 1].len() > 1 {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| merkle_parent(&pair[0], &pair[1]))
+                .collect();
+            layers.push(next);
+        }
+        Self { layers }
+    }
+
+    pub(crate) fn root(&self) -> [u8; 32] {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    // Siblings from the leaf upwards
+    pub(crate) fn path(&self, mut index: usize) -> Vec<[u8; 32]> {
+        self.layers[..self.layers.len() - 1]
+            .iter()
+            .map(|layer| {
+                let sibling = layer[index ^ 1];
+                index /= 2;
+                sibling
+            })
+            .collect()
+    }
+
+    // Root reached from the leaf at `index` through the siblings of `path`
+    pub(crate) fn root_from_path(leaf: &[u8; 32], index: usize, path: &[[u8; 32]]) -> [u8; 32] {
+        let path: Vec<([u8; 32], bool)> = path
+            .iter()
+            .enumerate()
+            .map(|(level, sibling)| (*sibling, (index >> level) & 1 == 1))
+            .collect();
+        merkle_root(leaf, &path)
+    }
+}
+
+// BLAKE3 of a 64-byte message, one block that is the whole of the only chunk
+pub fn blake3_block_gadget(
+    cs: &mut ConstraintSystem,
+    message: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if message.len() != 512 {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    let chaining_value: Vec<UInt32> = BLAKE3_IV.iter().map(|&w| UInt32::constant(w)).collect();
+    let output = blake3_compress_gadget(
+        cs,
+        &chaining_value,
+        &words_from_bits(message)?,
+        0,
+        64,
+        BLAKE3_CHUNK_START | BLAKE3_CHUNK_END | BLAKE3_ROOT,
+    )?;
+    Ok(output[..8].iter().flat_map(|w| w.bits.clone()).collect())
+}
+
+pub fn merkle_parent_gadget(
+    cs: &mut ConstraintSystem,
+    left: &[LinearCombination],
+    right: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if left.len() != 256 || right.len() != 256 {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    let block: Vec<LinearCombination> = left.iter().chain(right).cloned().collect();
+    let chaining_value: Vec<UInt32> = BLAKE3_IV.iter().map(|&w| UInt32::constant(w)).collect();
+    let output = blake3_compress_gadget(
+        cs,
+        &chaining_value,
+        &words_from_bits(&block)?,
+        0,
+        64,
+        BLAKE3_PARENT,
+    )?;
+    Ok(output[..8].iter().flat_map(|w| w.bits.clone()).collect())
+}
+
+// Root of the tree containing `leaf` along `path`, given as 256-bit node
+// gadgets and boolean direction bits as in merkle_root
+pub fn merkle_root_gadget(
+    cs: &mut ConstraintSystem,
+    leaf: &[LinearCombination],
+    path: &[(Vec<LinearCombination>, LinearCombination)],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    let mut node = leaf.to_vec();
+    for (sibling, is_right) in path {
+        if sibling.len() != node.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let (left, right): (Vec<_>, Vec<_>) = node
+            .iter()
+            .zip(sibling)
+            .map(|(n, s)| (select(cs, is_right, s, n), select(cs, is_right, n, s)))
+            .unzip();
+        node = merkle_parent_gadget(cs, &left, &right)?;
+    }
+    Ok(node)
+}
+
+pub fn verify_merkle_path_gadget(
+    cs: &mut ConstraintSystem,
+    leaf: &[LinearCombination],
+    path: &[(Vec<LinearCombination>, LinearCombination)],
+    root: &[LinearCombination],
+) -> Result<(), CryptoError> {
+    let computed = merkle_root_gadget(cs, leaf, path)?;
+    if computed.len() != root.len() {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    for (computed, expected) in computed.into_iter().zip(root) {
+        cs.enforce_equal(computed, expected.clone());
+    }
+    Ok(())
+}
+
+// Keccak's chi on three bits b0, b1, b2 packed as b0 + 2 b1 + 4 b2, giving
+// b0 xor (not b1 and b2)
+fn keccak_chi_table() -> Result<LookupTable, CryptoError> {
+    LookupTable::function("keccak chi", 3, |x| (x ^ (!(x >> 1) & (x >> 2))) & 1)
+}
+
+// Keccak-f[1600] on 1600 state bits, lane (x, y) at bits 64 (x + 5 y)..
+pub fn keccak_f1600_gadget(
+    cs: &mut ConstraintSystem,
+    state: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    keccak_f1600(cs, state, None)
+}
+
+// Keccak-f[1600] with chi, its S-box, as one lookup per output bit in place
+// of two constraints; needs a backend with lookups
+pub fn keccak_f1600_lookup_gadget(
+    cs: &mut ConstraintSystem,
+    state: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    let table = cs.add_table(keccak_chi_table()?)?;
+    keccak_f1600(cs, state, Some(table))
+}
+
+fn keccak_f1600(
+    cs: &mut ConstraintSystem,
+    state: &[LinearCombination],
+    chi_table: Option<TableId>,
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if state.len() != 1600 {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    let lane = |x: usize, y: usize| 64 * ((x % 5) + 5 * (y % 5));
+    let mut a = state.to_vec();
+    for round_constant in KECCAK_ROUND_CONSTANTS {
+        // theta
+        let mut column = Vec::with_capacity(5 * 64);
+        for x in 0..5 {
+            for z in 0..64 {
+                let mut parity = a[lane(x, 0) + z].clone();
+                for y in 1..5 {
+                    parity = xor(cs, &parity, &a[lane(x, y) + z]);
+                }
+                column.push(parity);
+            }
+        }
+        for x in 0..5 {
+            for z in 0..64 {
+                let d = xor(
+                    cs,
+                    &column[64 * ((x + 4) % 5) + z],
+                    &column[64 * ((x + 1) % 5) + (z + 63) % 64],
+                );
+                for y in 0..5 {
+                    a[lane(x, y) + z] = xor(cs, &a[lane(x, y) + z], &d);
+                }
+            }
+        }
+
+        // rho and pi
+        let mut b = vec![LinearCombination::zero(); 1600];
+        for x in 0..5 {
+            for y in 0..5 {
+                let rotation = KECCAK_ROTATIONS[x][y];
+                for z in 0..64 {
+                    b[lane(y, 2 * x + 3 * y) + (z + rotation) % 64] = a[lane(x, y) + z].clone();
+                }
+            }
+        }
+
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..64 {
+                    let (b0, b1, b2) = (
+                        &b[lane(x, y) + z],
+                        &b[lane(x + 1, y) + z],
+                        &b[lane(x + 2, y) + z],
+                    );
+                    a[lane(x, y) + z] = match chi_table {
+                        Some(table) => {
+                            let output = cs.alloc_private();
+                            let packed = b0.clone()
+                                + b1.clone() * Scalar::from(2u64)
+                                + b2.clone() * Scalar::from(4u64);
+                            cs.enforce_lookup(table, vec![packed, output.into()])?;
+                            output.into()
+                        }
+                        None => {
+                            let masked = and(cs, &not(b1), b2);
+                            xor(cs, b0, &masked)
+                        }
+                    };
+                }
+            }
+        }
+
+        // iota
+        for (z, bit) in a.iter_mut().take(64).enumerate() {
+            if (round_constant >> z) & 1 == 1 {
+                *bit = not(bit);
+            }
+        }
+    }
+    Ok(a)
+}
+
+// SHA3-256 of a whole number of bytes, returning the 256 digest bits
+pub fn sha3_256_gadget(
+    cs: &mut ConstraintSystem,
+    message: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    sha3_256(cs, message, keccak_f1600_gadget)
+}
+
+// SHA3-256 over keccak_f1600_lookup_gadget
+pub fn sha3_256_lookup_gadget(
+    cs: &mut ConstraintSystem,
+    message: &[LinearCombination],
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    sha3_256(cs, message, keccak_f1600_lookup_gadget)
+}
+
+fn sha3_256(
+    cs: &mut ConstraintSystem,
+    message: &[LinearCombination],
+    permutation: fn(
+        &mut ConstraintSystem,
+        &[LinearCombination],
+    ) -> Result<Vec<LinearCombination>, CryptoError>,
+) -> Result<Vec<LinearCombination>, CryptoError> {
+    if !message.len().is_multiple_of(8) {
+        return Err(CryptoError::AssignmentLengthMismatch);
+    }
+    let mut padded = message.to_vec();
+    let padding_bytes = SHA3_256_RATE / 8 - (message.len() / 8) % (SHA3_256_RATE / 8);
+    let mut padding = vec![0u8; padding_bytes];
+    padding[0] |= 0x06;
+    padding[padding_bytes - 1] |= 0x80;
+    padded.extend(constant_bits(&padding));
+
+    let mut state = vec![LinearCombination::zero(); 1600];
+    for block in padded.chunks(SHA3_256_RATE) {
+        for (s, bit) in state.iter_mut().zip(block) {
+            *s = xor(cs, s, bit);
+        }
+        state = permutation(cs, &state)?;
+    }
+    state.truncate(256);
+    Ok(state)
+}
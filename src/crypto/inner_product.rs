@@ -0,0 +1,407 @@
+use super::*;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+use merlin::Transcript;
+use rand::Rng;
+
+pub(crate) fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub(crate) fn transcript_scalar(transcript: &mut Transcript, label: &'static [u8]) -> Scalar {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(label, &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// Bases for committing to a pair of vectors together with their inner
+// product: P = <a, G> + <b, H> + <a, b> Q
+#[derive(Clone, Debug)]
+pub struct InnerProductGens {
+    g: Vec<RistrettoPoint>,
+    h: Vec<RistrettoPoint>,
+    q: RistrettoPoint,
+}
+
+impl InnerProductGens {
+    // `size` bases of each kind, hashed to the curve; size must be a power
+    // of two
+    pub fn new(size: usize) -> Result<Self, CryptoError> {
+        Self::from_points(
+            (0..size)
+                .map(|i| indexed_generator(b"inner product G", i))
+                .collect(),
+            (0..size)
+                .map(|i| indexed_generator(b"inner product H", i))
+                .collect(),
+            hash_to_curve(b"inner product Q"),
+        )
+    }
+
+    pub fn from_points(
+        g: Vec<RistrettoPoint>,
+        h: Vec<RistrettoPoint>,
+        q: RistrettoPoint,
+    ) -> Result<Self, CryptoError> {
+        if !g.len().is_power_of_two() || h.len() != g.len() {
+            return Err(CryptoError::InvalidVectorLength(g.len()));
+        }
+        Ok(Self { g, h, q })
+    }
+
+    pub fn size(&self) -> usize {
+        self.g.len()
+    }
+
+    pub fn commit(&self, a: &[Scalar], b: &[Scalar]) -> Result<RistrettoPoint, CryptoError> {
+        if a.len() != self.size() || b.len() != self.size() {
+            return Err(CryptoError::InvalidVectorLength(a.len()));
+        }
+        Ok(RistrettoPoint::multiscalar_mul(
+            a.iter().chain(b).chain([&inner_product(a, b)]),
+            self.g.iter().chain(&self.h).chain([&self.q]),
+        ))
+    }
+}
+
+// Logarithmic-size argument of knowledge of a, b opening an inner product
+// commitment: one (L, R) pair per halving round, then the final a and b
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerProductProof {
+    l_vec: Vec<CompressedRistretto>,
+    r_vec: Vec<CompressedRistretto>,
+    a: Scalar,
+    b: Scalar,
+}
+
+// Scalars of the verification equation
+// P + sum(u_j^2 L_j + u_j^-2 R_j) = a <s, G> + b <1/s, H> + a b Q, where s_i
+// is the product of u_j or 1/u_j by the bits of i
+struct VerificationScalars {
+    challenges_squared: Vec<Scalar>,
+    inverses_squared: Vec<Scalar>,
+    s: Vec<Scalar>,
+}
+
+impl InnerProductProof {
+    pub fn prove(
+        gens: &InnerProductGens,
+        transcript: &mut Transcript,
+        a: &[Scalar],
+        b: &[Scalar],
+    ) -> Result<Self, CryptoError> {
+        if a.len() != gens.size() || b.len() != gens.size() {
+            return Err(CryptoError::InvalidVectorLength(a.len()));
+        }
+        Ok(Self::create(
+            transcript,
+            gens.q,
+            gens.g.clone(),
+            gens.h.clone(),
+            a.to_vec(),
+            b.to_vec(),
+        ))
+    }
+
+    // The halving rounds, on bases the caller has already checked
+    pub(crate) fn create(
+        transcript: &mut Transcript,
+        q: RistrettoPoint,
+        mut g: Vec<RistrettoPoint>,
+        mut h: Vec<RistrettoPoint>,
+        mut a: Vec<Scalar>,
+        mut b: Vec<Scalar>,
+    ) -> Self {
+        transcript.append_message(b"dom-sep", b"ipp");
+        transcript.append_u64(b"n", a.len() as u64);
+        let mut l_vec = Vec::new();
+        let mut r_vec = Vec::new();
+        let mut n = a.len();
+        while n > 1 {
+            n /= 2;
+            let (a_lo, a_hi) = a.split_at(n);
+            let (b_lo, b_hi) = b.split_at(n);
+            let (g_lo, g_hi) = g.split_at(n);
+            let (h_lo, h_hi) = h.split_at(n);
+            let c_l = inner_product(a_lo, b_hi);
+            let c_r = inner_product(a_hi, b_lo);
+            let l = RistrettoPoint::multiscalar_mul(
+                a_lo.iter().chain(b_hi).chain([&c_l]),
+                g_hi.iter().chain(h_lo).chain([&q]),
+            )
+            .compress();
+            let r = RistrettoPoint::multiscalar_mul(
+                a_hi.iter().chain(b_lo).chain([&c_r]),
+                g_lo.iter().chain(h_hi).chain([&q]),
+            )
+            .compress();
+            transcript.append_message(b"L", l.as_bytes());
+            transcript.append_message(b"R", r.as_bytes());
+            l_vec.push(l);
+            r_vec.push(r);
+
+            let u = transcript_scalar(transcript, b"u");
+            let u_inverse = u.invert();
+            a = (0..n).map(|i| a_lo[i] * u + a_hi[i] * u_inverse).collect();
+            b = (0..n).map(|i| b_lo[i] * u_inverse + b_hi[i] * u).collect();
+            g = (0..n).map(|i| g_lo[i] * u_inverse + g_hi[i] * u).collect();
+            h = (0..n).map(|i| h_lo[i] * u + h_hi[i] * u_inverse).collect();
+        }
+        Self {
+            l_vec,
+            r_vec,
+            a: a[0],
+            b: b[0],
+        }
+    }
+
+    // Number of halving rounds, log2 of the vector length
+    pub fn rounds(&self) -> usize {
+        self.l_vec.len()
+    }
+
+    fn verification_scalars(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+    ) -> Option<VerificationScalars> {
+        let rounds = self.rounds();
+        if n != 1 << rounds || self.r_vec.len() != rounds {
+            return None;
+        }
+        transcript.append_message(b"dom-sep", b"ipp");
+        transcript.append_u64(b"n", n as u64);
+        let mut challenges = Vec::with_capacity(rounds);
+        for (l, r) in self.l_vec.iter().zip(&self.r_vec) {
+            transcript.append_message(b"L", l.as_bytes());
+            transcript.append_message(b"R", r.as_bytes());
+            challenges.push(transcript_scalar(transcript, b"u"));
+        }
+        let inverses: Vec<Scalar> = challenges.iter().map(|u| u.invert()).collect();
+
+        let mut s = vec![inverses.iter().product::<Scalar>(); n];
+        for i in 1..n {
+            // The highest set bit of i picks the round that differs from i
+            // without it
+            let bit = usize::BITS - 1 - i.leading_zeros();
+            let u = challenges[rounds - 1 - bit as usize];
+            s[i] = s[i - (1 << bit)] * u * u;
+        }
+        Some(VerificationScalars {
+            challenges_squared: challenges.iter().map(|u| u * u).collect(),
+            inverses_squared: inverses.iter().map(|u| u * u).collect(),
+            s,
+        })
+    }
+
+    // Points and scalars whose combination is the identity exactly when the
+    // proof verifies, each scalar multiplied by `weight`; the G, H and Q
+    // terms are added into the given accumulators
+    fn accumulate(
+        &self,
+        scalars: &VerificationScalars,
+        commitment: RistrettoPoint,
+        weight: Scalar,
+        g_scalars: &mut [Scalar],
+        h_scalars: &mut [Scalar],
+        q_scalar: &mut Scalar,
+    ) -> Result<(Vec<Scalar>, Vec<RistrettoPoint>), CryptoError> {
+        for ((g, h), s) in g_scalars.iter_mut().zip(h_scalars).zip(&scalars.s) {
+            *g -= weight * self.a * s;
+            *h -= weight * self.b * s.invert();
+        }
+        *q_scalar -= weight * self.a * self.b;
+
+        let mut point_scalars = vec![weight];
+        let mut points = vec![commitment];
+        for j in 0..self.rounds() {
+            point_scalars.push(weight * scalars.challenges_squared[j]);
+            points.push(
+                self.l_vec[j]
+                    .decompress()
+                    .ok_or(CryptoError::InvalidPoint)?,
+            );
+            point_scalars.push(weight * scalars.inverses_squared[j]);
+            points.push(
+                self.r_vec[j]
+                    .decompress()
+                    .ok_or(CryptoError::InvalidPoint)?,
+            );
+        }
+        Ok((point_scalars, points))
+    }
+
+    pub(crate) fn verify_with(
+        &self,
+        transcript: &mut Transcript,
+        q: RistrettoPoint,
+        commitment: RistrettoPoint,
+        g: &[RistrettoPoint],
+        h: &[RistrettoPoint],
+    ) -> Result<bool, CryptoError> {
+        if h.len() != g.len() {
+            return Ok(false);
+        }
+        let Some(scalars) = self.verification_scalars(g.len(), transcript) else {
+            return Ok(false);
+        };
+        let mut g_scalars = vec![Scalar::ZERO; g.len()];
+        let mut h_scalars = vec![Scalar::ZERO; h.len()];
+        let mut q_scalar = Scalar::ZERO;
+        let (point_scalars, points) = self.accumulate(
+            &scalars,
+            commitment,
+            Scalar::ONE,
+            &mut g_scalars,
+            &mut h_scalars,
+            &mut q_scalar,
+        )?;
+        let scalars: Vec<Scalar> = point_scalars
+            .into_iter()
+            .chain(g_scalars)
+            .chain(h_scalars)
+            .chain([q_scalar])
+            .collect();
+        let points: Vec<RistrettoPoint> = points
+            .into_iter()
+            .chain(g.iter().chain(h).copied())
+            .chain([q])
+            .collect();
+        Ok(ristretto_msm(&scalars, &points) == RistrettoPoint::default())
+    }
+
+    pub fn verify(
+        &self,
+        gens: &InnerProductGens,
+        transcript: &mut Transcript,
+        commitment: &RistrettoPoint,
+    ) -> Result<bool, CryptoError> {
+        self.verify_with(transcript, gens.q, *commitment, &gens.g, &gens.h)
+    }
+
+    // Checks every (transcript, commitment, proof) at once with a random
+    // linear combination of their equations, one multiscalar multiplication
+    // in all; a false result does not say which proof failed
+    pub fn batch_verify<'a, R: Rng + ?Sized>(
+        gens: &InnerProductGens,
+        proofs: impl IntoIterator<Item = (&'a mut Transcript, RistrettoPoint, &'a InnerProductProof)>,
+        rng: &mut R,
+    ) -> Result<bool, CryptoError> {
+        let mut g_scalars = vec![Scalar::ZERO; gens.size()];
+        let mut h_scalars = vec![Scalar::ZERO; gens.size()];
+        let mut q_scalar = Scalar::ZERO;
+        let mut point_scalars = Vec::new();
+        let mut points = Vec::new();
+        for (transcript, commitment, proof) in proofs {
+            let Some(scalars) = proof.verification_scalars(gens.size(), transcript) else {
+                return Ok(false);
+            };
+            let (mut scalars, mut proof_points) = proof.accumulate(
+                &scalars,
+                commitment,
+                random_scalar(rng),
+                &mut g_scalars,
+                &mut h_scalars,
+                &mut q_scalar,
+            )?;
+            point_scalars.append(&mut scalars);
+            points.append(&mut proof_points);
+        }
+        point_scalars.extend(g_scalars.into_iter().chain(h_scalars).chain([q_scalar]));
+        points.extend(gens.g.iter().chain(&gens.h).chain([&gens.q]));
+        Ok(ristretto_msm(&point_scalars, &points) == RistrettoPoint::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn vectors(size: usize, rng: &mut ChaCha20Rng) -> (Vec<Scalar>, Vec<Scalar>) {
+        (
+            (0..size).map(|_| random_scalar(rng)).collect(),
+            (0..size).map(|_| random_scalar(rng)).collect(),
+        )
+    }
+
+    #[test]
+    fn proofs_round_trip() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1092);
+        let gens = InnerProductGens::new(8).unwrap();
+        let (a, b) = vectors(8, &mut rng);
+        let commitment = gens.commit(&a, &b).unwrap();
+        let proof = InnerProductProof::prove(&gens, &mut Transcript::new(b"test"), &a, &b).unwrap();
+        assert_eq!(proof.rounds(), 3);
+        assert!(proof
+            .verify(&gens, &mut Transcript::new(b"test"), &commitment)
+            .unwrap());
+        assert!(!proof
+            .verify(&gens, &mut Transcript::new(b"other"), &commitment)
+            .unwrap());
+        let other = gens.commit(&b, &a).unwrap();
+        assert!(!proof
+            .verify(&gens, &mut Transcript::new(b"test"), &other)
+            .unwrap());
+    }
+
+    #[test]
+    fn batches_fail_on_one_bad_proof() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1092);
+        let gens = InnerProductGens::new(4).unwrap();
+        let mut commitments = Vec::new();
+        let mut proofs = Vec::new();
+        for _ in 0..3 {
+            let (a, b) = vectors(4, &mut rng);
+            commitments.push(gens.commit(&a, &b).unwrap());
+            proofs.push(
+                InnerProductProof::prove(&gens, &mut Transcript::new(b"test"), &a, &b).unwrap(),
+            );
+        }
+        let mut transcripts = vec![Transcript::new(b"test"); 3];
+        let batch = transcripts
+            .iter_mut()
+            .zip(&commitments)
+            .zip(&proofs)
+            .map(|((transcript, commitment), proof)| (transcript, *commitment, proof));
+        assert!(InnerProductProof::batch_verify(&gens, batch, &mut rng).unwrap());
+
+        let mut tampered = proofs.clone();
+        tampered[1].a += Scalar::ONE;
+        let mut transcripts = vec![Transcript::new(b"test"); 3];
+        let batch = transcripts
+            .iter_mut()
+            .zip(&commitments)
+            .zip(&tampered)
+            .map(|((transcript, commitment), proof)| (transcript, *commitment, proof));
+        assert!(!InnerProductProof::batch_verify(&gens, batch, &mut rng).unwrap());
+    }
+
+    #[test]
+    fn rejects_mismatched_lengths() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1092);
+        let gens = InnerProductGens::new(4).unwrap();
+        let (a, b) = vectors(4, &mut rng);
+        assert_eq!(
+            InnerProductProof::prove(&gens, &mut Transcript::new(b"test"), &a[..3], &b),
+            Err(CryptoError::InvalidVectorLength(3))
+        );
+        assert_eq!(
+            gens.commit(&a, &b[..2]).unwrap_err(),
+            CryptoError::InvalidVectorLength(4)
+        );
+        assert!(InnerProductGens::new(6).is_err());
+
+        // A proof for longer vectors has rounds these bases cannot check
+        let longer = InnerProductGens::new(8).unwrap();
+        let (a, b) = vectors(8, &mut rng);
+        let proof =
+            InnerProductProof::prove(&longer, &mut Transcript::new(b"test"), &a, &b).unwrap();
+        let commitment = longer.commit(&a, &b).unwrap();
+        assert!(!proof
+            .verify(&gens, &mut Transcript::new(b"test"), &commitment)
+            .unwrap());
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
+use ff::{PrimeField, PrimeFieldBits};
+use group::Curve;
+
+// Multi-scalar multiplications sum s_i P_i for verification equations, in
+// variable time: inputs must be public. Ristretto sums go to curve25519-dalek,
+// which switches from Straus to Pippenger as the sum grows; the pairing
+// groups use the bucket method below.
+
+pub fn ristretto_msm(scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+    RistrettoPoint::vartime_multiscalar_mul(scalars, points)
+}
+
+// The same sum in constant time, for when the scalars or points derive from
+// secrets
+pub fn ristretto_msm_constant_time(
+    scalars: &[Scalar],
+    points: &[RistrettoPoint],
+) -> RistrettoPoint {
+    RistrettoPoint::multiscalar_mul(scalars, points)
+}
+
+// Fails if any point does not decompress
+pub fn ristretto_msm_compressed(
+    scalars: &[Scalar],
+    points: &[CompressedRistretto],
+) -> Result<RistrettoPoint, CryptoError> {
+    RistrettoPoint::optional_multiscalar_mul(scalars, points.iter().map(|p| p.decompress()))
+        .ok_or(CryptoError::InvalidPoint)
+}
+
+// Bits per Pippenger window for n terms, about log2 n
+fn window_bits(n: usize) -> usize {
+    if n < 32 {
+        3
+    } else {
+        (usize::BITS - n.leading_zeros()) as usize - 2
+    }
+}
+
+// Pippenger's bucket method: per window of c scalar bits, each base goes into
+// the bucket of its digit, and the buckets are summed with weights 1 to
+// 2^c - 1 by a running sum. Costs about (b / c)(n + 2^c) additions for b-bit
+// scalars, against b n for one scalar multiplication per term.
+pub fn pippenger<G>(bases: &[G::AffineRepr], scalars: &[G::Scalar]) -> G
+where
+    G: Curve,
+    G::AffineRepr: Copy,
+    G::Scalar: PrimeFieldBits,
+{
+    let bits: Vec<_> = scalars.iter().map(|s| s.to_le_bits()).collect();
+    let num_bits = G::Scalar::NUM_BITS as usize;
+    let c = window_bits(bases.len().min(scalars.len()));
+
+    let mut result = G::identity();
+    for window in (0..num_bits.div_ceil(c)).rev() {
+        for _ in 0..c {
+            result = result.double();
+        }
+        let mut buckets = vec![G::identity(); (1 << c) - 1];
+        for (base, bits) in bases.iter().zip(&bits) {
+            let digit = (0..c)
+                .map(|k| window * c + k)
+                .filter(|&i| i < num_bits && bits[i])
+                .fold(0, |digit, i| digit | 1 << (i - window * c));
+            if digit != 0 {
+                buckets[digit - 1] += *base;
+            }
+        }
+        let mut running = G::identity();
+        let mut sum = G::identity();
+        for bucket in buckets.into_iter().rev() {
+            running += bucket;
+            sum += running;
+        }
+        result += sum;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::{G1Affine, G1Projective, Scalar as Fr};
+    use ff::Field;
+    use group::Group;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn pippenger_matches_the_naive_sum() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1101);
+        // 2^254 and r - 1 both have the top bit of NUM_BITS set
+        let top_bit = (0..254).fold(Fr::ONE, |x, _| x.double());
+        for n in [0, 1, 31, 32, 100] {
+            let bases: Vec<G1Affine> = (0..n)
+                .map(|_| G1Projective::random(&mut rng).to_affine())
+                .collect();
+            let mut scalars: Vec<Fr> = (0..n).map(|_| Fr::random(&mut rng)).collect();
+            for (i, special) in [Fr::ZERO, top_bit, -Fr::ONE, Fr::ZERO]
+                .into_iter()
+                .enumerate()
+            {
+                if let Some(scalar) = scalars.get_mut(3 * i) {
+                    *scalar = special;
+                }
+            }
+            let naive: G1Projective = bases.iter().zip(&scalars).map(|(base, s)| base * s).sum();
+            assert_eq!(
+                pippenger::<G1Projective>(&bases, &scalars),
+                naive,
+                "n = {}",
+                n
+            );
+        }
+    }
+}
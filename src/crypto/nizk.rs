@@ -3,18 +3,22 @@ use blake3::Hash;
 use merlin::Transcript;
 use curve25519_dalek::ristretto::{RistrettoPoint, CompressedRistretto};
 use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConstantTimeEq};
 
 pub struct NIZKProof {
-    quantum_state: QuantumState,
-    classical_proof: SNARKProof,
-    commitment: CompressedRistretto,
-    response: Scalar,
-    auxiliary_data: Vec<u8>,
+    pub(crate) quantum_state: QuantumState,
+    pub(crate) classical_proof: SNARKProof,
+    pub(crate) commitment: CompressedRistretto,
+    pub(crate) response: Scalar,
+    pub(crate) auxiliary_data: Vec<u8>,
 }
 
+// The classical part is the toy Ristretto SNARK, which is not sound, so
+// neither is this verifier
 pub struct NIZKVerifier {
     snark_verifier: SNARKVerifier,
     quantum_verifier: QuantumVerifier,
+    mode: VerificationMode,
 }
 
 impl NIZKVerifier {
@@ -26,67 +30,94 @@ impl NIZKVerifier {
         Self {
             snark_verifier: SNARKVerifier::new(public_parameters, verification_key),
             quantum_verifier: QuantumVerifier::new(quantum_parameters),
+            mode: VerificationMode::default(),
         }
     }
 
+    // Also sets the mode of the classical verifier
+    pub fn with_mode(mut self, mode: VerificationMode) -> Self {
+        self.snark_verifier = self.snark_verifier.with_mode(mode);
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> VerificationMode {
+        self.mode
+    }
+
     pub fn verify(
         &self,
         statement: &[u8],
         proof: &NIZKProof,
         quantum_channel: &QuantumChannel,
     ) -> Result<bool, CryptoError> {
-        // Verify classical part
-        let classical_valid = self.snark_verifier.verify(statement, &proof.classical_proof)?;
-        if !classical_valid {
+        let mut transcript = Transcript::new(SNARK_TRANSCRIPT_LABEL);
+        self.verify_with_transcript(&mut transcript, statement, proof, quantum_channel)
+    }
+
+    // The classical proof is checked against `transcript`, and the
+    // commitment against a fresh transcript of the same kind
+    pub fn verify_with_transcript<T: FiatShamirTranscript>(
+        &self,
+        transcript: &mut T,
+        statement: &[u8],
+        proof: &NIZKProof,
+        quantum_channel: &QuantumChannel,
+    ) -> Result<bool, CryptoError> {
+        // Verify classical part. In constant time every part is checked and
+        // a part that fails outright only counts as a rejection; the quantum
+        // verifier's own running time is outside this verifier's control.
+        let classical_valid = self.mode.outcome(self.snark_verifier.verify_with_transcript(
+            transcript,
+            statement,
+            &proof.classical_proof,
+        ))?;
+        if self.mode.rejects_early(classical_valid) {
             return Ok(false);
         }
 
         // Verify quantum part
-        let quantum_valid = self.quantum_verifier.verify_state(
+        let quantum_valid = self.mode.outcome(self.quantum_verifier.verify_state(
             &proof.quantum_state,
             statement,
             quantum_channel,
-        )?;
-        if !quantum_valid {
+        ))?;
+        if self.mode.rejects_early(quantum_valid) {
             return Ok(false);
         }
 
         // Verify commitment consistency
-        let commitment_valid = self.verify_commitment(
+        let commitment_valid = self.verify_commitment::<T>(
             &proof.commitment,
             &proof.response,
             statement,
             &proof.auxiliary_data,
         )?;
 
-        Ok(commitment_valid)
+        Ok((classical_valid & quantum_valid & commitment_valid).into())
     }
 
-    fn verify_commitment(
+    fn verify_commitment<T: FiatShamirTranscript>(
         &self,
         commitment: &CompressedRistretto,
         response: &Scalar,
         statement: &[u8],
         auxiliary_data: &[u8],
-    ) -> Result<bool, CryptoError> {
-        let mut transcript = Transcript::new(b"nizk-commitment");
+    ) -> Result<Choice, CryptoError> {
+        let mut transcript = T::new(b"nizk-commitment");
         transcript.append_message(b"statement", statement);
         transcript.append_message(b"auxiliary", auxiliary_data);
 
-        let point = commitment.decompress()
-            .ok_or(CryptoError::InvalidPoint)?;
+        let mut valid = Choice::from(1u8);
+        let point = self.mode.decompress(commitment, &mut valid)?;
         
-        let challenge = self.derive_challenge(&mut transcript);
-        let verification_point = (point * challenge + 
-            self.quantum_verifier.get_base_point() * response) *
-            self.quantum_verifier.get_blinding_factor();
-
-        Ok(verification_point == self.quantum_verifier.get_verification_point())
-    }
+        let challenge = transcript.challenge_scalar(b"commitment-challenge");
+        let blinding_factor = self.quantum_verifier.get_blinding_factor();
+        let verification_point = self.mode.msm(
+            &[challenge * blinding_factor, response * blinding_factor],
+            &[point, self.quantum_verifier.get_base_point()],
+        );
 
-    fn derive_challenge(&self, transcript: &mut Transcript) -> Scalar {
-        let mut scalar_bytes = [0u8; 64];
-        transcript.challenge_bytes(b"commitment-challenge", &mut scalar_bytes);
-        Scalar::from_bytes_mod_order_wide(&scalar_bytes)
+        Ok(valid & verification_point.ct_eq(&self.quantum_verifier.get_verification_point()))
     }
 }
\ No newline at end of file
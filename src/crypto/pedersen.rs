@@ -0,0 +1,174 @@
+use super::*;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, MultiscalarMul};
+use std::iter::Sum;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// The index-th of a family of bases hashed to the curve under `label`
+pub(crate) fn indexed_generator(label: &[u8], index: usize) -> RistrettoPoint {
+    let mut input = label.to_vec();
+    input.extend_from_slice(&(index as u64).to_le_bytes());
+    hash_to_curve(&input)
+}
+
+// A commitment v B + gamma B_blinding, or <v, G> + gamma B_blinding for a
+// vector. Perfectly hiding, and binding as long as nobody knows a discrete
+// logarithm relation between the bases, which is why every base is hashed
+// to the curve. Commitments add: the sum commits to the sum of the values
+// under the sum of the blindings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PedersenCommitment(RistrettoPoint);
+
+impl PedersenCommitment {
+    pub fn from_point(point: RistrettoPoint) -> Self {
+        Self(point)
+    }
+
+    pub fn point(&self) -> &RistrettoPoint {
+        &self.0
+    }
+
+    pub fn compress(&self) -> CompressedRistretto {
+        self.0.compress()
+    }
+
+    pub fn decompress(compressed: &CompressedRistretto) -> Result<Self, CryptoError> {
+        compressed
+            .decompress()
+            .map(Self)
+            .ok_or(CryptoError::InvalidPoint)
+    }
+
+    // sum s_i C_i, committing to sum s_i v_i under sum s_i gamma_i
+    pub fn linear_combination(scalars: &[Scalar], commitments: &[PedersenCommitment]) -> Self {
+        let points: Vec<RistrettoPoint> = commitments.iter().map(|c| c.0).collect();
+        Self(ristretto_msm(scalars, &points))
+    }
+}
+
+impl Default for PedersenCommitment {
+    fn default() -> Self {
+        Self(RistrettoPoint::identity())
+    }
+}
+
+impl Add for PedersenCommitment {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for PedersenCommitment {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl Neg for PedersenCommitment {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Mul<Scalar> for PedersenCommitment {
+    type Output = Self;
+
+    fn mul(self, scalar: Scalar) -> Self {
+        Self(self.0 * scalar)
+    }
+}
+
+impl Sum for PedersenCommitment {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self(iter.map(|c| c.0).sum())
+    }
+}
+
+// Bases of Pedersen commitments to single values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PedersenGens {
+    pub b: RistrettoPoint,
+    pub b_blinding: RistrettoPoint,
+}
+
+impl Default for PedersenGens {
+    fn default() -> Self {
+        Self {
+            b: hash_to_curve(b"pedersen B"),
+            b_blinding: hash_to_curve(b"pedersen blinding"),
+        }
+    }
+}
+
+impl PedersenGens {
+    pub fn commit(&self, value: Scalar, blinding: Scalar) -> PedersenCommitment {
+        PedersenCommitment(RistrettoPoint::multiscalar_mul(
+            [value, blinding],
+            [self.b, self.b_blinding],
+        ))
+    }
+
+    // Whether (value, blinding) opens `commitment`
+    pub fn open(&self, commitment: &PedersenCommitment, value: Scalar, blinding: Scalar) -> bool {
+        self.commit(value, blinding) == *commitment
+    }
+}
+
+// Bases of Pedersen commitments to vectors of up to `size` values, with the
+// same blinding base as the default PedersenGens
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VectorPedersenGens {
+    g: Vec<RistrettoPoint>,
+    b_blinding: RistrettoPoint,
+}
+
+impl VectorPedersenGens {
+    pub fn new(size: usize) -> Self {
+        Self {
+            g: (0..size)
+                .map(|i| indexed_generator(b"pedersen G", i))
+                .collect(),
+            b_blinding: PedersenGens::default().b_blinding,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.g.len()
+    }
+
+    // Shorter vectors are committed as if padded with zeros
+    pub fn commit(
+        &self,
+        values: &[Scalar],
+        blinding: Scalar,
+    ) -> Result<PedersenCommitment, CryptoError> {
+        if values.len() > self.size() {
+            return Err(CryptoError::InvalidVectorLength(values.len()));
+        }
+        let scalars: Vec<Scalar> = [blinding]
+            .into_iter()
+            .chain(values.iter().copied())
+            .collect();
+        let points: Vec<RistrettoPoint> = [self.b_blinding]
+            .into_iter()
+            .chain(self.g[..values.len()].iter().copied())
+            .collect();
+        Ok(PedersenCommitment(ristretto_msm(&scalars, &points)))
+    }
+
+    pub fn open(
+        &self,
+        commitment: &PedersenCommitment,
+        values: &[Scalar],
+        blinding: Scalar,
+    ) -> Result<bool, CryptoError> {
+        Ok(self.commit(values, blinding)? == *commitment)
+    }
+}
@@ -0,0 +1,1450 @@
+use super::*;
+use bls12_381::{
+    multi_miller_loop, pairing, G1Affine, G1Projective, G2Affine, G2Prepared, Gt, Scalar as Fr,
+};
+use curve25519_dalek::scalar::Scalar;
+use ff::{Field, PrimeField};
+use merlin::Transcript;
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap};
+
+// Powers of a secret tau in G1, and tau in G2, for KZG commitments to
+// polynomials of degree up to max_degree. One SRS serves every circuit that
+// fits; anyone can rerandomize it with update, so it is sound as long as
+// one contributor discarded their secret.
+#[derive(Clone, Debug)]
+pub struct PlonkSrs {
+    g1_powers: Vec<G1Affine>,
+    pub(crate) g2: G2Affine,
+    pub(crate) tau_g2: G2Affine,
+}
+
+// Evidence that an SRS was obtained from another by multiplying tau by rho
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SrsUpdateProof {
+    pub rho_g1: G1Affine,
+    pub rho_g2: G2Affine,
+}
+
+impl PlonkSrs {
+    pub fn new<R: Rng + ?Sized>(max_degree: usize, rng: &mut R) -> Self {
+        let genesis = Self {
+            g1_powers: vec![G1Affine::generator(); max_degree + 1],
+            g2: G2Affine::generator(),
+            tau_g2: G2Affine::generator(),
+        };
+        genesis.update(rng).0
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.g1_powers.len() - 1
+    }
+
+    // The SRS for tau * rho, rho fresh and discarded
+    pub fn update<R: Rng + ?Sized>(&self, rng: &mut R) -> (Self, SrsUpdateProof) {
+        let rho = loop {
+            let rho = Fr::random(&mut *rng);
+            if rho != Fr::ZERO {
+                break rho;
+            }
+        };
+        let mut factor = Fr::ONE;
+        let g1_powers = self
+            .g1_powers
+            .iter()
+            .map(|point| {
+                let updated = G1Affine::from(point * factor);
+                factor *= rho;
+                updated
+            })
+            .collect();
+        let updated = Self {
+            g1_powers,
+            g2: self.g2,
+            tau_g2: G2Affine::from(self.tau_g2 * rho),
+        };
+        let proof = SrsUpdateProof {
+            rho_g1: G1Affine::from(G1Affine::generator() * rho),
+            rho_g2: G2Affine::from(G2Affine::generator() * rho),
+        };
+        (updated, proof)
+    }
+
+    // Checks that `updated` is well formed and that its tau is this tau
+    // times the rho committed to in `proof`
+    pub fn verify_update(&self, updated: &Self, proof: &SrsUpdateProof) -> bool {
+        self.g1_powers.len() >= 2
+            && updated.g1_powers.len() == self.g1_powers.len()
+            && updated.is_well_formed()
+            && pairing(&proof.rho_g1, &G2Affine::generator())
+                == pairing(&G1Affine::generator(), &proof.rho_g2)
+            && pairing(&updated.g1_powers[1], &updated.g2)
+                == pairing(&self.g1_powers[1], &proof.rho_g2)
+    }
+
+    // Consecutive G1 powers differ by the tau in G2, checked on one random
+    // linear combination of all of them
+    pub fn is_well_formed(&self) -> bool {
+        if self.g1_powers.first() != Some(&G1Affine::generator())
+            || self.g2 != G2Affine::generator()
+        {
+            return false;
+        }
+        let mut transcript = Transcript::new(b"plonk-srs");
+        for point in &self.g1_powers {
+            transcript.append_message(b"g1", &point.to_compressed());
+        }
+        transcript.append_message(b"tau_g2", &self.tau_g2.to_compressed());
+        let r = challenge(&mut transcript, b"r");
+        let n = self.g1_powers.len() - 1;
+        let mut weights = Vec::with_capacity(n);
+        let mut weight = Fr::ONE;
+        for _ in 0..n {
+            weights.push(weight);
+            weight *= r;
+        }
+        let lower = pippenger::<G1Projective>(&self.g1_powers[..n], &weights);
+        let upper = pippenger::<G1Projective>(&self.g1_powers[1..], &weights);
+        pairing(&G1Affine::from(upper), &self.g2) == pairing(&G1Affine::from(lower), &self.tau_g2)
+    }
+
+    pub(crate) fn commit(&self, coefficients: &[Fr]) -> Result<G1Affine, CryptoError> {
+        if coefficients.len() > self.g1_powers.len() {
+            return Err(CryptoError::CircuitTooLarge);
+        }
+        Ok(G1Affine::from(pippenger::<G1Projective>(
+            &self.g1_powers,
+            coefficients,
+        )))
+    }
+}
+
+pub(crate) fn challenge(transcript: &mut Transcript, label: &'static [u8]) -> Fr {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(label, &mut bytes);
+    Fr::from_bytes_wide(&bytes)
+}
+
+// q_m a b + q_l a + q_r b + q_o c + q_c = 0, plus the public input term on
+// the first rows. Wires name circuit variables; an absent wire is 0. A
+// lookup gate has no arithmetic identity and reads a row of its table from
+// its wires. A custom gate reads its wires row by row from its first row,
+// three to a row, and the rows it spans carry no selectors.
+#[derive(Clone, Debug, Default)]
+struct Gate {
+    q_m: Fr,
+    q_l: Fr,
+    q_r: Fr,
+    q_o: Fr,
+    q_c: Fr,
+    wires: [Option<usize>; 3],
+    // Whether the gate defines its c wire, with q_o = -1
+    defines_output: bool,
+    // Index of the table a lookup gate reads
+    lookup: Option<usize>,
+    // Index of the custom gate starting on this row
+    custom: Option<usize>,
+}
+
+// Gates for a lifted constraint system. Variables are its columns, the
+// constant column 0 folded into q_c, followed by intermediate sums.
+struct GateCompiler {
+    gates: Vec<Gate>,
+    num_variables: usize,
+}
+
+impl GateCompiler {
+    fn fresh(&mut self) -> usize {
+        self.num_variables += 1;
+        self.num_variables - 1
+    }
+
+    fn split(row: &[(usize, Fr)]) -> (BTreeMap<usize, Fr>, Fr) {
+        let mut terms = BTreeMap::new();
+        let mut constant = Fr::ZERO;
+        for (column, c) in row {
+            if *column == 0 {
+                constant += c;
+            } else {
+                *terms.entry(*column).or_insert(Fr::ZERO) += c;
+            }
+        }
+        terms.retain(|_, c| *c != Fr::ZERO);
+        (terms, constant)
+    }
+
+    // A variable equal to the sum of `terms` and `constant`
+    fn linear(&mut self, terms: &[(usize, Fr)], constant: Fr) -> usize {
+        if let [(variable, c)] = terms {
+            if *c == Fr::ONE && constant == Fr::ZERO {
+                return *variable;
+            }
+        }
+        let mut output = self.fresh();
+        let mut gate = Gate {
+            q_o: -Fr::ONE,
+            q_c: constant,
+            defines_output: true,
+            ..Gate::default()
+        };
+        gate.wires[2] = Some(output);
+        if let Some((variable, c)) = terms.first() {
+            gate.q_l = *c;
+            gate.wires[0] = Some(*variable);
+        }
+        if let Some((variable, c)) = terms.get(1) {
+            gate.q_r = *c;
+            gate.wires[1] = Some(*variable);
+        }
+        self.gates.push(gate);
+        for (variable, c) in terms.iter().skip(2) {
+            let sum = self.fresh();
+            self.gates.push(Gate {
+                q_l: Fr::ONE,
+                q_r: *c,
+                q_o: -Fr::ONE,
+                wires: [Some(output), Some(*variable), Some(sum)],
+                defines_output: true,
+                ..Gate::default()
+            });
+            output = sum;
+        }
+        output
+    }
+
+    fn enforce_zero(&mut self, terms: &[(usize, Fr)], constant: Fr) {
+        let gate = match terms.split_last() {
+            Some((&(variable, c), rest)) if rest.len() >= 2 => {
+                let sum = self.linear(rest, constant);
+                Gate {
+                    q_l: Fr::ONE,
+                    q_r: c,
+                    wires: [Some(sum), Some(variable), None],
+                    ..Gate::default()
+                }
+            }
+            _ => {
+                let mut gate = Gate {
+                    q_c: constant,
+                    ..Gate::default()
+                };
+                if let Some((variable, c)) = terms.first() {
+                    gate.q_l = *c;
+                    gate.wires[0] = Some(*variable);
+                }
+                if let Some((variable, c)) = terms.get(1) {
+                    gate.q_r = *c;
+                    gate.wires[1] = Some(*variable);
+                }
+                gate
+            }
+        };
+        self.gates.push(gate);
+    }
+
+    fn lookup(&mut self, table: usize, inputs: &[Vec<(usize, Fr)>]) {
+        let mut gate = Gate {
+            lookup: Some(table),
+            ..Gate::default()
+        };
+        for (wire, input) in gate.wires.iter_mut().zip(inputs) {
+            let (terms, constant) = Self::split(input);
+            let terms: Vec<(usize, Fr)> = terms.into_iter().collect();
+            *wire = Some(self.linear(&terms, constant));
+        }
+        self.gates.push(gate);
+    }
+
+    fn custom(&mut self, gate: usize, width: usize, inputs: &[Vec<(usize, Fr)>]) {
+        let mut wires = Vec::with_capacity(width);
+        for input in inputs {
+            let (terms, constant) = Self::split(input);
+            let terms: Vec<(usize, Fr)> = terms.into_iter().collect();
+            wires.push(self.linear(&terms, constant));
+        }
+        for (row, chunk) in wires.chunks(3).enumerate() {
+            let mut gate = Gate {
+                custom: (row == 0).then_some(gate),
+                ..Gate::default()
+            };
+            for (wire, variable) in gate.wires.iter_mut().zip(chunk) {
+                *wire = Some(*variable);
+            }
+            self.gates.push(gate);
+        }
+    }
+
+    fn constraint(&mut self, [a, b, c]: &[Vec<(usize, Fr)>; 3]) {
+        let (a, a_constant) = Self::split(a);
+        let (b, b_constant) = Self::split(b);
+        let (c, c_constant) = Self::split(c);
+        let flatten = |terms: &BTreeMap<usize, Fr>| -> Vec<(usize, Fr)> {
+            terms.iter().map(|(v, c)| (*v, *c)).collect()
+        };
+
+        // A constant factor leaves k * other - c = 0, which is linear
+        let linear_case = match (a.is_empty(), b.is_empty()) {
+            (true, _) => Some((a_constant, &b, b_constant)),
+            (_, true) => Some((b_constant, &a, a_constant)),
+            _ => None,
+        };
+        if let Some((factor, other, other_constant)) = linear_case {
+            let mut terms: BTreeMap<usize, Fr> =
+                other.iter().map(|(v, x)| (*v, factor * x)).collect();
+            for (v, x) in &c {
+                *terms.entry(*v).or_insert(Fr::ZERO) -= x;
+            }
+            terms.retain(|_, x| *x != Fr::ZERO);
+            self.enforce_zero(&flatten(&terms), factor * other_constant - c_constant);
+            return;
+        }
+
+        let left = self.linear(&flatten(&a), a_constant);
+        let right = self.linear(&flatten(&b), b_constant);
+        let mut gate = Gate {
+            q_m: Fr::ONE,
+            q_c: -c_constant,
+            wires: [Some(left), Some(right), None],
+            ..Gate::default()
+        };
+        let c: Vec<(usize, Fr)> = flatten(&c);
+        match c.as_slice() {
+            [] => {}
+            [(variable, x)] => {
+                gate.q_o = -*x;
+                gate.wires[2] = Some(*variable);
+            }
+            _ => {
+                gate.q_o = -Fr::ONE;
+                gate.wires[2] = Some(self.linear(&c, Fr::ZERO));
+            }
+        }
+        self.gates.push(gate);
+    }
+}
+
+// Distinct cosets of the domain labelling the a, b and c wire columns
+fn coset_shifts() -> [Fr; 3] {
+    let k = Fr::MULTIPLICATIVE_GENERATOR;
+    [Fr::ONE, k, k.square()]
+}
+
+// Compresses a table row (x, y, z, id) to x + eta y + eta^2 z + eta^3 id
+fn compress_row(eta: Fr, [x, y, z, id]: [Fr; 4]) -> Fr {
+    x + eta * (y + eta * (z + eta * id))
+}
+
+// The coefficients of p(omega X)
+pub(crate) fn shift_polynomial(coefficients: &[Fr], omega: Fr) -> Vec<Fr> {
+    let mut power = Fr::ONE;
+    coefficients
+        .iter()
+        .map(|c| {
+            let term = c * power;
+            power *= omega;
+            term
+        })
+        .collect()
+}
+
+// Number of rows the widest custom gate reads, at least 1
+fn rotations(custom_gates: &[PlonkCustomGate]) -> usize {
+    custom_gates
+        .iter()
+        .map(|g| g.width.div_ceil(3))
+        .max()
+        .unwrap_or(1)
+}
+
+// A custom gate's terms on wires given by index
+fn evaluate_gate(terms: &[(Fr, Vec<u32>)], wire: impl Fn(usize) -> Fr) -> Fr {
+    terms
+        .iter()
+        .map(|(c, exponents)| {
+            exponents
+                .iter()
+                .enumerate()
+                .fold(*c, |term, (i, e)| term * power(wire(i), *e as u64))
+        })
+        .sum()
+}
+
+// Degree of the quotient numerator: 4n + 5 from the permutation identity,
+// or a custom gate's selector times gate_degree blinded wires
+fn numerator_degree(n: usize, gate_degree: usize) -> usize {
+    (4 * n + 5).max(n - 1 + gate_degree * (n + 1))
+}
+
+// The commitment to a custom gate's selector, 1 on the rows the gate
+// starts on, and its identity lifted to BLS12-381
+#[derive(Clone, Debug)]
+pub struct PlonkCustomGate {
+    pub selector: G1Affine,
+    pub width: usize,
+    pub degree: usize,
+    pub terms: Vec<(Fr, Vec<u32>)>,
+}
+
+// Commitments to the q_lookup and q_table selectors, q_table holding 1 plus
+// the table index on lookup rows, and to the four columns of the tables
+// stacked under an all-zero row, the last column being the table's q_table
+#[derive(Clone, Debug)]
+pub struct PlonkLookupKey {
+    pub selectors: [G1Affine; 2],
+    pub table: [G1Affine; 4],
+}
+
+#[derive(Clone, Debug)]
+pub struct PlonkVerifyingKey {
+    pub domain_size: usize,
+    pub num_public: usize,
+    // q_m, q_l, q_r, q_o, q_c
+    pub selectors: [G1Affine; 5],
+    pub permutation: [G1Affine; 3],
+    // Present exactly when the circuit has lookups
+    pub lookup: Option<PlonkLookupKey>,
+    pub custom_gates: Vec<PlonkCustomGate>,
+}
+
+#[derive(Clone, Debug)]
+struct LookupPolynomials {
+    selectors: [Vec<Fr>; 2],
+    table: [Vec<Fr>; 4],
+    table_values: [Vec<Fr>; 4],
+}
+
+#[derive(Clone, Debug)]
+pub struct PlonkProvingKey {
+    system: LiftedSystem,
+    gates: Vec<Gate>,
+    num_variables: usize,
+    domain: EvaluationDomain,
+    selectors: [Vec<Fr>; 5],
+    permutation: [Vec<Fr>; 3],
+    // Labels of the next position in each wire's copy cycle, per column
+    sigma: [Vec<Fr>; 3],
+    lookup: Option<LookupPolynomials>,
+    custom_selectors: Vec<Vec<Fr>>,
+    verifying_key: PlonkVerifyingKey,
+}
+
+// Evaluations at zeta of a, b, c, z, t, the selectors and the permutation
+// polynomials, in that order, plus z at zeta * omega
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlonkEvaluations {
+    pub at_zeta: [Fr; 13],
+    pub z_shifted: Fr,
+}
+
+// Commitments to the halves h1, h2 of the sorted vector and to the lookup
+// accumulator; evaluations of q_lookup, q_table, t, h1, h2 and the
+// accumulator at zeta, then of t, h1, h2 and the accumulator at zeta * omega
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlonkLookupProof {
+    pub sorted: [G1Affine; 2],
+    pub z: G1Affine,
+    pub evaluations: [Fr; 10],
+}
+
+// Evaluations of the custom gate selectors at zeta, and of a, b and c at
+// zeta omega^r for each further row the widest gate reads, with the
+// openings at zeta omega^r from r = 2 on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlonkCustomProof {
+    pub selectors: Vec<Fr>,
+    pub wires: Vec<[Fr; 3]>,
+    pub openings: Vec<G1Affine>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlonkProof {
+    pub wires: [G1Affine; 3],
+    pub z: G1Affine,
+    pub t: G1Affine,
+    pub evaluations: PlonkEvaluations,
+    pub opening: G1Affine,
+    pub shifted_opening: G1Affine,
+    pub lookup: Option<PlonkLookupProof>,
+    // Present exactly when the circuit has custom gates
+    pub custom: Option<PlonkCustomProof>,
+}
+
+// A PLONK-style system on a universal SRS: setup only preprocesses the
+// circuit, so circuits can change without a new ceremony. Constraint systems
+// are lifted to BLS12-381 as for Groth16. Lookups are proven with plookup:
+// the wires of lookup gates and the tables are compressed by a challenge
+// eta, and a second grand product shows that the compressed values sorted
+// together with the table are sorted by the table, which only holds if each
+// lookup is a table row. A lookup costs one gate whatever the table. Each
+// custom gate gets its own selector and adds its identity to the gate
+// identity, reading wires on later rows through rotations by omega.
+#[derive(Clone, Debug)]
+pub struct Plonk {
+    srs: PlonkSrs,
+}
+
+impl Plonk {
+    pub fn new(srs: PlonkSrs) -> Self {
+        Self { srs }
+    }
+
+    pub fn srs(&self) -> &PlonkSrs {
+        &self.srs
+    }
+
+    fn transcript(verifying_key: &PlonkVerifyingKey, public_inputs: &[Fr]) -> Transcript {
+        let mut transcript = Transcript::new(b"plonk");
+        transcript.append_u64(b"n", verifying_key.domain_size as u64);
+        for point in verifying_key
+            .selectors
+            .iter()
+            .chain(&verifying_key.permutation)
+        {
+            transcript.append_message(b"preprocessed", &point.to_compressed());
+        }
+        if let Some(lookup) = &verifying_key.lookup {
+            for point in lookup.selectors.iter().chain(&lookup.table) {
+                transcript.append_message(b"lookup", &point.to_compressed());
+            }
+        }
+        for gate in &verifying_key.custom_gates {
+            transcript.append_message(b"custom", &gate.selector.to_compressed());
+            transcript.append_u64(b"width", gate.width as u64);
+            for (c, exponents) in &gate.terms {
+                transcript.append_message(b"coefficient", &c.to_bytes());
+                for e in exponents {
+                    transcript.append_u64(b"exponent", *e as u64);
+                }
+            }
+        }
+        for input in public_inputs {
+            transcript.append_message(b"public", &input.to_bytes());
+        }
+        transcript
+    }
+}
+
+// c(X) + Z_H(X) * sum blinds_i X^i
+fn blind(mut coefficients: Vec<Fr>, domain_size: usize, blinds: &[Fr]) -> Vec<Fr> {
+    coefficients.resize(domain_size + blinds.len(), Fr::ZERO);
+    for (i, b) in blinds.iter().enumerate() {
+        coefficients[i] -= b;
+        coefficients[domain_size + i] += b;
+    }
+    coefficients
+}
+
+// Quotient of p(X) by X - point, dropping the remainder
+pub(crate) fn divide_by_linear(coefficients: &[Fr], point: Fr) -> Vec<Fr> {
+    let mut quotient = vec![Fr::ZERO; coefficients.len().saturating_sub(1)];
+    let mut carry = Fr::ZERO;
+    for i in (1..coefficients.len()).rev() {
+        carry = coefficients[i] + carry * point;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+impl ProofSystem for Plonk {
+    type ProvingKey = PlonkProvingKey;
+    type VerifyingKey = PlonkVerifyingKey;
+    type Proof = PlonkProof;
+
+    fn name(&self) -> &'static str {
+        "plonk"
+    }
+
+    // Deterministic; the SRS carries all the randomness
+    fn setup<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        _rng: &mut R,
+    ) -> Result<(PlonkProvingKey, PlonkVerifyingKey), CryptoError> {
+        let system = LiftedSystem::new(cs);
+        let mut compiler = GateCompiler {
+            gates: Vec::new(),
+            num_variables: system.num_columns(),
+        };
+        // Row i reads public input i, matched against the PI term
+        for i in 0..system.num_public {
+            compiler.gates.push(Gate {
+                q_l: Fr::ONE,
+                wires: [Some(1 + i), None, None],
+                ..Gate::default()
+            });
+        }
+        // Custom gates stand in for the constraints they were lowered to
+        let mut lowered = vec![false; system.rows.len()];
+        for (_, _, constraints) in &system.applications {
+            lowered[constraints.clone()].fill(true);
+        }
+        for (row, lowered) in system.rows.iter().zip(lowered) {
+            if !lowered {
+                compiler.constraint(row);
+            }
+        }
+        for (table, inputs) in &system.lookups {
+            compiler.lookup(*table, inputs);
+        }
+        for (gate, inputs, _) in &system.applications {
+            compiler.custom(*gate, system.custom_gates[*gate].0, inputs);
+        }
+        let GateCompiler {
+            gates,
+            num_variables,
+        } = compiler;
+        // With lookups the last row must be free, as the lookup identity
+        // does not cover it, and the tables must fit under the zero row
+        let has_lookups = !system.lookups.is_empty();
+        let min_size = if has_lookups {
+            let table_rows = 1 + system.tables.iter().map(Vec::len).sum::<usize>();
+            (gates.len() + 1).max(table_rows)
+        } else {
+            gates.len()
+        };
+        let domain = EvaluationDomain::new(min_size).ok_or(CryptoError::CircuitTooLarge)?;
+        let n = domain.size;
+        let degrees: Vec<usize> = system
+            .custom_gates
+            .iter()
+            .map(|(_, terms)| {
+                let degree = |(_, e): &(Fr, Vec<u32>)| e.iter().map(|e| *e as usize).sum();
+                terms.iter().map(degree).max().unwrap_or(0)
+            })
+            .collect();
+        let gate_degree = degrees.iter().copied().max().unwrap_or(0);
+        if numerator_degree(n, gate_degree) - n > self.srs.max_degree() {
+            return Err(CryptoError::CircuitTooLarge);
+        }
+
+        let selector = |f: &dyn Fn(&Gate) -> Fr| {
+            let mut values: Vec<Fr> = gates.iter().map(f).collect();
+            values.resize(n, Fr::ZERO);
+            domain.ifft(&values)
+        };
+        let selectors = [
+            selector(&|g| g.q_m),
+            selector(&|g| g.q_l),
+            selector(&|g| g.q_r),
+            selector(&|g| g.q_o),
+            selector(&|g| g.q_c),
+        ];
+        let custom_selectors: Vec<Vec<Fr>> = (0..system.custom_gates.len())
+            .map(|index| selector(&|g| Fr::from((g.custom == Some(index)) as u64)))
+            .collect();
+
+        // Copy cycles through every position holding the same variable
+        let mut positions: BTreeMap<usize, Vec<(usize, usize)>> = BTreeMap::new();
+        for (row, gate) in gates.iter().enumerate() {
+            for (column, wire) in gate.wires.iter().enumerate() {
+                if let Some(variable) = wire {
+                    positions.entry(*variable).or_default().push((column, row));
+                }
+            }
+        }
+        let shifts = coset_shifts();
+        let elements: Vec<Fr> = (0..n).map(|i| domain.element(i)).collect();
+        let mut sigma: [Vec<Fr>; 3] =
+            std::array::from_fn(|column| elements.iter().map(|x| shifts[column] * x).collect());
+        for cycle in positions.values() {
+            for (index, &(column, row)) in cycle.iter().enumerate() {
+                let (next_column, next_row) = cycle[(index + 1) % cycle.len()];
+                sigma[column][row] = shifts[next_column] * elements[next_row];
+            }
+        }
+        let permutation: [Vec<Fr>; 3] = std::array::from_fn(|column| domain.ifft(&sigma[column]));
+
+        let lookup = has_lookups.then(|| {
+            let mut table_values: [Vec<Fr>; 4] = std::array::from_fn(|_| vec![Fr::ZERO]);
+            for (index, rows) in system.tables.iter().enumerate() {
+                for row in rows {
+                    for (column, values) in table_values.iter_mut().take(3).enumerate() {
+                        values.push(row.get(column).copied().unwrap_or(Fr::ZERO));
+                    }
+                    table_values[3].push(Fr::from(index as u64 + 1));
+                }
+            }
+            for values in &mut table_values {
+                let last = values[values.len() - 1];
+                values.resize(n, last);
+            }
+            LookupPolynomials {
+                selectors: [
+                    selector(&|g| Fr::from(g.lookup.is_some() as u64)),
+                    selector(&|g| g.lookup.map_or(Fr::ZERO, |t| Fr::from(t as u64 + 1))),
+                ],
+                table: std::array::from_fn(|column| domain.ifft(&table_values[column])),
+                table_values,
+            }
+        });
+
+        let commit = |coefficients: &Vec<Fr>| self.srs.commit(coefficients);
+        let lookup_key = match &lookup {
+            Some(lookup) => Some(PlonkLookupKey {
+                selectors: [commit(&lookup.selectors[0])?, commit(&lookup.selectors[1])?],
+                table: [
+                    commit(&lookup.table[0])?,
+                    commit(&lookup.table[1])?,
+                    commit(&lookup.table[2])?,
+                    commit(&lookup.table[3])?,
+                ],
+            }),
+            None => None,
+        };
+        let mut custom_gates = Vec::with_capacity(custom_selectors.len());
+        for ((selector, (width, terms)), degree) in custom_selectors
+            .iter()
+            .zip(&system.custom_gates)
+            .zip(degrees)
+        {
+            custom_gates.push(PlonkCustomGate {
+                selector: commit(selector)?,
+                width: *width,
+                degree,
+                terms: terms.clone(),
+            });
+        }
+        let verifying_key = PlonkVerifyingKey {
+            domain_size: n,
+            num_public: system.num_public,
+            selectors: [
+                commit(&selectors[0])?,
+                commit(&selectors[1])?,
+                commit(&selectors[2])?,
+                commit(&selectors[3])?,
+                commit(&selectors[4])?,
+            ],
+            permutation: [
+                commit(&permutation[0])?,
+                commit(&permutation[1])?,
+                commit(&permutation[2])?,
+            ],
+            lookup: lookup_key,
+            custom_gates,
+        };
+        let proving_key = PlonkProvingKey {
+            system,
+            gates,
+            num_variables,
+            domain,
+            selectors,
+            permutation,
+            sigma,
+            lookup,
+            custom_selectors,
+            verifying_key: verifying_key.clone(),
+        };
+        Ok((proving_key, verifying_key))
+    }
+
+    fn prove<R: Rng + ?Sized>(
+        &self,
+        proving_key: &PlonkProvingKey,
+        assignment: &Assignment,
+        rng: &mut R,
+    ) -> Result<PlonkProof, CryptoError> {
+        let system = &proving_key.system;
+        let domain = &proving_key.domain;
+        let n = domain.size;
+        let columns = system.values(assignment)?;
+        system.check(&columns)?;
+
+        let mut values = columns;
+        values.resize(proving_key.num_variables, Fr::ZERO);
+        let value = |values: &[Fr], wire: Option<usize>| wire.map_or(Fr::ZERO, |w| values[w]);
+        for gate in &proving_key.gates {
+            if gate.defines_output {
+                let (a, b) = (value(&values, gate.wires[0]), value(&values, gate.wires[1]));
+                values[gate.wires[2].unwrap()] =
+                    gate.q_m * a * b + gate.q_l * a + gate.q_r * b + gate.q_c;
+            }
+        }
+        let public_inputs = &values[1..1 + system.num_public];
+        let mut transcript = Self::transcript(&proving_key.verifying_key, public_inputs);
+
+        // Round 1: blinded wire polynomials
+        let wire_values: [Vec<Fr>; 3] = std::array::from_fn(|column| {
+            let mut evaluations: Vec<Fr> = proving_key
+                .gates
+                .iter()
+                .map(|g| value(&values, g.wires[column]))
+                .collect();
+            evaluations.resize(n, Fr::ZERO);
+            evaluations
+        });
+        let wires: [Vec<Fr>; 3] = std::array::from_fn(|column| {
+            let blinds = [Fr::random(&mut *rng), Fr::random(&mut *rng)];
+            blind(domain.ifft(&wire_values[column]), n, &blinds)
+        });
+        let mut wire_commitments = [G1Affine::identity(); 3];
+        for (commitment, polynomial) in wire_commitments.iter_mut().zip(&wires) {
+            *commitment = self.srs.commit(polynomial)?;
+            transcript.append_message(b"wire", &commitment.to_compressed());
+        }
+        // Round 1b, with lookups: f holds the compressed wires on lookup
+        // rows and 0, the table's first row, elsewhere, except on the last
+        // row which the identity leaves out; the sorted vector s is f and t
+        // merged in the order of t, split into h1 and h2 sharing an entry
+        let sorted = match &proving_key.lookup {
+            Some(lookup) => {
+                let eta = challenge(&mut transcript, b"eta");
+                let mut f: Vec<Fr> = proving_key
+                    .gates
+                    .iter()
+                    .enumerate()
+                    .map(|(i, gate)| match gate.lookup {
+                        Some(table) => compress_row(
+                            eta,
+                            [
+                                wire_values[0][i],
+                                wire_values[1][i],
+                                wire_values[2][i],
+                                Fr::from(table as u64 + 1),
+                            ],
+                        ),
+                        None => Fr::ZERO,
+                    })
+                    .collect();
+                f.resize(n - 1, Fr::ZERO);
+                let t: Vec<Fr> = (0..n)
+                    .map(|i| compress_row(eta, std::array::from_fn(|k| lookup.table_values[k][i])))
+                    .collect();
+                let mut counts: HashMap<[u8; 32], usize> = HashMap::new();
+                for value in &f {
+                    *counts.entry(value.to_bytes()).or_default() += 1;
+                }
+                let mut s = Vec::with_capacity(2 * n - 1);
+                for value in &t {
+                    s.push(*value);
+                    let count = counts.remove(&value.to_bytes()).unwrap_or(0);
+                    s.extend(std::iter::repeat_n(*value, count));
+                }
+                // Lifted lookups were checked against the tables above
+                debug_assert!(counts.is_empty());
+                let halves = [s[..n].to_vec(), s[n - 1..].to_vec()];
+                let polynomials: [Vec<Fr>; 2] = std::array::from_fn(|half| {
+                    let blinds = [Fr::random(&mut *rng), Fr::random(&mut *rng)];
+                    blind(domain.ifft(&halves[half]), n, &blinds)
+                });
+                let mut commitments = [G1Affine::identity(); 2];
+                for (commitment, polynomial) in commitments.iter_mut().zip(&polynomials) {
+                    *commitment = self.srs.commit(polynomial)?;
+                    transcript.append_message(b"sorted", &commitment.to_compressed());
+                }
+                Some((eta, f, t, halves, polynomials, commitments))
+            }
+            None => None,
+        };
+        let beta = challenge(&mut transcript, b"beta");
+        let gamma = challenge(&mut transcript, b"gamma");
+
+        // Round 2: the permutation accumulator
+        let shifts = coset_shifts();
+        let mut accumulator = Vec::with_capacity(n);
+        let mut running = Fr::ONE;
+        for i in 0..n {
+            accumulator.push(running);
+            let x = domain.element(i);
+            let (mut numerator, mut denominator) = (Fr::ONE, Fr::ONE);
+            for ((values, sigma), shift) in wire_values.iter().zip(&proving_key.sigma).zip(shifts) {
+                numerator *= values[i] + beta * shift * x + gamma;
+                denominator *= values[i] + beta * sigma[i] + gamma;
+            }
+            running *= numerator * denominator.invert().unwrap();
+        }
+        let blinds = [
+            Fr::random(&mut *rng),
+            Fr::random(&mut *rng),
+            Fr::random(&mut *rng),
+        ];
+        let z = blind(domain.ifft(&accumulator), n, &blinds);
+        let z_commitment = self.srs.commit(&z)?;
+        transcript.append_message(b"z", &z_commitment.to_compressed());
+
+        // and the lookup accumulator, which returns to 1 on the last row
+        // exactly when s is f and t sorted by t
+        let one_plus_beta = Fr::ONE + beta;
+        let gamma_beta = gamma * one_plus_beta;
+        let lookup_z = match &sorted {
+            Some((_, f, t, [h1, h2], _, _)) => {
+                let mut accumulator = Vec::with_capacity(n);
+                let mut running = Fr::ONE;
+                for i in 0..n {
+                    accumulator.push(running);
+                    if i + 1 < n {
+                        let numerator =
+                            one_plus_beta * (gamma + f[i]) * (gamma_beta + t[i] + beta * t[i + 1]);
+                        let denominator = (gamma_beta + h1[i] + beta * h1[i + 1])
+                            * (gamma_beta + h2[i] + beta * h2[i + 1]);
+                        running *= numerator * denominator.invert().unwrap();
+                    }
+                }
+                let blinds = [
+                    Fr::random(&mut *rng),
+                    Fr::random(&mut *rng),
+                    Fr::random(&mut *rng),
+                ];
+                let z = blind(domain.ifft(&accumulator), n, &blinds);
+                let commitment = self.srs.commit(&z)?;
+                transcript.append_message(b"z_lookup", &commitment.to_compressed());
+                Some((z, commitment))
+            }
+            None => None,
+        };
+        let alpha = challenge(&mut transcript, b"alpha");
+
+        // Round 3: the quotient, evaluated on a coset large enough for its
+        // numerator
+        let custom_gates = &proving_key.verifying_key.custom_gates;
+        let gate_degree = custom_gates.iter().map(|g| g.degree).max().unwrap_or(0);
+        let degree = numerator_degree(n, gate_degree);
+        let big = EvaluationDomain::new(degree + 1).ok_or(CryptoError::CircuitTooLarge)?;
+        let shift = Fr::MULTIPLICATIVE_GENERATOR;
+        let on_coset = |coefficients: &[Fr]| big.coset_fft(coefficients, shift);
+        let omega = domain.generator;
+        let z_shifted = shift_polynomial(&z, omega);
+        let mut public_values = vec![Fr::ZERO; n];
+        for (slot, input) in public_values.iter_mut().zip(public_inputs) {
+            *slot = -input;
+        }
+        let mut first = vec![Fr::ZERO; n];
+        first[0] = Fr::ONE;
+
+        let [a, b, c] = &wires;
+        let (a_coset, b_coset, c_coset) = (on_coset(a), on_coset(b), on_coset(c));
+        let selector_coset: Vec<Vec<Fr>> =
+            proving_key.selectors.iter().map(|s| on_coset(s)).collect();
+        let sigma_coset: Vec<Vec<Fr>> = proving_key
+            .permutation
+            .iter()
+            .map(|s| on_coset(s))
+            .collect();
+        // Custom gates read a, b and c on later rows at omega^r X
+        let custom_coset: Vec<Vec<Fr>> = proving_key
+            .custom_selectors
+            .iter()
+            .map(|s| on_coset(s))
+            .collect();
+        let rotated_coset: Vec<[Vec<Fr>; 3]> = (1..rotations(custom_gates))
+            .map(|r| {
+                let omega_r = power(omega, r as u64);
+                std::array::from_fn(|column| on_coset(&shift_polynomial(&wires[column], omega_r)))
+            })
+            .collect();
+        let z_coset = on_coset(&z);
+        let z_shifted_coset = on_coset(&z_shifted);
+        let public_coset = on_coset(&domain.ifft(&public_values));
+        let first_coset = on_coset(&domain.ifft(&first));
+
+        // The compressed table as a polynomial, then on the coset: q_lookup,
+        // q_table, the last Lagrange polynomial, and t, h1, h2 and the
+        // accumulator each at X and omega X
+        let lookup_table =
+            sorted
+                .as_ref()
+                .zip(proving_key.lookup.as_ref())
+                .map(|((eta, ..), lookup)| {
+                    let mut table = vec![Fr::ZERO; n];
+                    for column in lookup.table.iter().rev() {
+                        for (sum, c) in table.iter_mut().zip(column) {
+                            *sum = *sum * eta + c;
+                        }
+                    }
+                    table
+                });
+        let last_element = domain.element(n - 1);
+        let lookup_coset = match (&sorted, &proving_key.lookup, &lookup_table, &lookup_z) {
+            (Some((eta, _, _, _, [h1, h2], _)), Some(lookup), Some(table), Some((lookup_z, _))) => {
+                let mut last = vec![Fr::ZERO; n];
+                last[n - 1] = Fr::ONE;
+                let pair = |p: &[Fr]| [on_coset(p), on_coset(&shift_polynomial(p, omega))];
+                Some((
+                    *eta,
+                    [
+                        on_coset(&lookup.selectors[0]),
+                        on_coset(&lookup.selectors[1]),
+                    ],
+                    on_coset(&domain.ifft(&last)),
+                    [pair(table), pair(h1), pair(h2), pair(lookup_z)],
+                ))
+            }
+            _ => None,
+        };
+
+        let alpha_squared = alpha.square();
+        let alpha_powers = [
+            alpha_squared * alpha,
+            alpha_squared.square(),
+            alpha_squared.square() * alpha,
+            alpha_squared.square() * alpha_squared,
+        ];
+        let mut point = shift;
+        let mut quotient = Vec::with_capacity(big.size);
+        for k in 0..big.size {
+            let (a, b, c) = (a_coset[k], b_coset[k], c_coset[k]);
+            let wires = [a, b, c];
+            let mut gate = selector_coset[0][k] * a * b
+                + selector_coset[1][k] * a
+                + selector_coset[2][k] * b
+                + selector_coset[3][k] * c
+                + selector_coset[4][k]
+                + public_coset[k];
+            for (custom, selector) in custom_gates.iter().zip(&custom_coset) {
+                gate += selector[k]
+                    * evaluate_gate(&custom.terms, |i| match i / 3 {
+                        0 => wires[i % 3],
+                        r => rotated_coset[r - 1][i % 3][k],
+                    });
+            }
+            let (mut identity, mut permuted) = (z_coset[k], z_shifted_coset[k]);
+            for column in 0..3 {
+                identity *= wires[column] + beta * shifts[column] * point + gamma;
+                permuted *= wires[column] + beta * sigma_coset[column][k] + gamma;
+            }
+            let start = first_coset[k] * (z_coset[k] - Fr::ONE);
+            let mut numerator = gate + alpha * (identity - permuted) + alpha_squared * start;
+            if let Some((eta, [q_lookup, q_table], last, [t, h1, h2, lookup_z])) = &lookup_coset {
+                let f = q_lookup[k] * compress_row(*eta, [a, b, c, q_table[k]]);
+                let product = (point - last_element)
+                    * (lookup_z[0][k]
+                        * one_plus_beta
+                        * (gamma + f)
+                        * (gamma_beta + t[0][k] + beta * t[1][k])
+                        - lookup_z[1][k]
+                            * (gamma_beta + h1[0][k] + beta * h1[1][k])
+                            * (gamma_beta + h2[0][k] + beta * h2[1][k]));
+                numerator += alpha_powers[0] * first_coset[k] * (lookup_z[0][k] - Fr::ONE)
+                    + alpha_powers[1] * product
+                    + alpha_powers[2] * last[k] * (h1[0][k] - h2[1][k])
+                    + alpha_powers[3] * last[k] * (lookup_z[0][k] - Fr::ONE);
+            }
+            quotient.push(numerator * domain.vanishing_at(point).invert().unwrap());
+            point *= big.generator;
+        }
+        let mut t = big.coset_ifft(&quotient, shift);
+        t.truncate(degree - n + 1);
+        let t_commitment = self.srs.commit(&t)?;
+        transcript.append_message(b"t", &t_commitment.to_compressed());
+        let zeta = challenge(&mut transcript, b"zeta");
+
+        // Round 4: evaluations
+        let mut polynomials: Vec<&Vec<Fr>> = [a, b, c, &z, &t]
+            .into_iter()
+            .chain(&proving_key.selectors)
+            .chain(&proving_key.permutation)
+            .collect();
+        let mut at_zeta = [Fr::ZERO; 13];
+        for (evaluation, polynomial) in at_zeta.iter_mut().zip(&polynomials) {
+            *evaluation = evaluate_polynomial(polynomial, zeta);
+            transcript.append_message(b"evaluation", &evaluation.to_bytes());
+        }
+        let zeta_omega = zeta * omega;
+        let z_at_shifted = evaluate_polynomial(&z, zeta_omega);
+        transcript.append_message(b"evaluation", &z_at_shifted.to_bytes());
+        let mut evaluations = at_zeta.to_vec();
+        let mut shifted_polynomials = vec![&z];
+        let mut shifted_evaluations = vec![z_at_shifted];
+        let mut lookup_evaluations = [Fr::ZERO; 10];
+        if let (Some(lookup), Some(table), Some((.., [h1, h2], _)), Some((lookup_z, _))) =
+            (&proving_key.lookup, &lookup_table, &sorted, &lookup_z)
+        {
+            let opened = [
+                &lookup.selectors[0],
+                &lookup.selectors[1],
+                table,
+                h1,
+                h2,
+                lookup_z,
+            ];
+            for (evaluation, polynomial) in lookup_evaluations.iter_mut().zip(opened) {
+                *evaluation = evaluate_polynomial(polynomial, zeta);
+            }
+            for (evaluation, polynomial) in lookup_evaluations[6..].iter_mut().zip(&opened[2..]) {
+                *evaluation = evaluate_polynomial(polynomial, zeta_omega);
+            }
+            for evaluation in &lookup_evaluations {
+                transcript.append_message(b"evaluation", &evaluation.to_bytes());
+            }
+            polynomials.extend(opened);
+            evaluations.extend(&lookup_evaluations[..6]);
+            shifted_polynomials.extend(&opened[2..]);
+            shifted_evaluations.extend(&lookup_evaluations[6..]);
+        }
+        let custom_evaluations = (!custom_gates.is_empty()).then(|| {
+            let selectors: Vec<Fr> = proving_key
+                .custom_selectors
+                .iter()
+                .map(|s| evaluate_polynomial(s, zeta))
+                .collect();
+            let rotated: Vec<[Fr; 3]> = (1..rotations(custom_gates))
+                .map(|r| {
+                    let point = zeta * power(omega, r as u64);
+                    [a, b, c].map(|p| evaluate_polynomial(p, point))
+                })
+                .collect();
+            for evaluation in selectors.iter().chain(rotated.iter().flatten()) {
+                transcript.append_message(b"evaluation", &evaluation.to_bytes());
+            }
+            polynomials.extend(&proving_key.custom_selectors);
+            evaluations.extend(&selectors);
+            if let Some(first) = rotated.first() {
+                shifted_polynomials.extend([a, b, c]);
+                shifted_evaluations.extend(first);
+            }
+            (selectors, rotated)
+        });
+        let v = challenge(&mut transcript, b"v");
+
+        // Round 5: one batched opening at zeta, one at zeta * omega, and one
+        // at zeta omega^r for every further row custom gates read
+        let batch = |polynomials: &[&Vec<Fr>], evaluations: &[Fr], point: Fr| {
+            let length = polynomials.iter().map(|p| p.len()).max().unwrap_or(0);
+            let mut combined = vec![Fr::ZERO; length];
+            let mut weight = Fr::ONE;
+            for (polynomial, evaluation) in polynomials.iter().zip(evaluations) {
+                for (sum, c) in combined.iter_mut().zip(polynomial.iter()) {
+                    *sum += weight * c;
+                }
+                combined[0] -= weight * evaluation;
+                weight *= v;
+            }
+            self.srs.commit(&divide_by_linear(&combined, point))
+        };
+        let opening = batch(&polynomials, &evaluations, zeta)?;
+        let shifted_opening = batch(&shifted_polynomials, &shifted_evaluations, zeta_omega)?;
+        let mut custom = None;
+        if let Some((selectors, rotated)) = custom_evaluations {
+            let mut openings = Vec::new();
+            for (r, evaluations) in rotated.iter().enumerate().skip(1) {
+                let point = zeta * power(omega, r as u64 + 1);
+                openings.push(batch(&[a, b, c], evaluations, point)?);
+            }
+            custom = Some(PlonkCustomProof {
+                selectors,
+                wires: rotated,
+                openings,
+            });
+        }
+
+        Ok(PlonkProof {
+            wires: wire_commitments,
+            z: z_commitment,
+            t: t_commitment,
+            evaluations: PlonkEvaluations {
+                at_zeta,
+                z_shifted: z_at_shifted,
+            },
+            opening,
+            shifted_opening,
+            lookup: sorted
+                .zip(lookup_z)
+                .map(|((.., commitments), (_, z))| PlonkLookupProof {
+                    sorted: commitments,
+                    z,
+                    evaluations: lookup_evaluations,
+                }),
+            custom,
+        })
+    }
+
+    fn verify(
+        &self,
+        verifying_key: &PlonkVerifyingKey,
+        public_inputs: &[Scalar],
+        proof: &PlonkProof,
+    ) -> Result<bool, CryptoError> {
+        if public_inputs.len() != verifying_key.num_public {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let domain =
+            EvaluationDomain::new(verifying_key.domain_size).ok_or(CryptoError::CircuitTooLarge)?;
+        if domain.size != verifying_key.domain_size {
+            return Err(CryptoError::CircuitTooLarge);
+        }
+        if proof.lookup.is_some() != verifying_key.lookup.is_some() {
+            return Ok(false);
+        }
+        let custom_gates = &verifying_key.custom_gates;
+        let rotations = rotations(custom_gates);
+        match &proof.custom {
+            None if custom_gates.is_empty() => {}
+            Some(custom)
+                if !custom_gates.is_empty()
+                    && custom.selectors.len() == custom_gates.len()
+                    && custom.wires.len() == rotations - 1
+                    && custom.openings.len() == rotations.saturating_sub(2) => {}
+            _ => return Ok(false),
+        }
+        let inputs: Vec<Fr> = public_inputs.iter().map(lift_scalar).collect();
+        let mut transcript = Self::transcript(verifying_key, &inputs);
+        for commitment in &proof.wires {
+            transcript.append_message(b"wire", &commitment.to_compressed());
+        }
+        let mut eta = Fr::ZERO;
+        if let Some(lookup) = &proof.lookup {
+            eta = challenge(&mut transcript, b"eta");
+            for commitment in &lookup.sorted {
+                transcript.append_message(b"sorted", &commitment.to_compressed());
+            }
+        }
+        let beta = challenge(&mut transcript, b"beta");
+        let gamma = challenge(&mut transcript, b"gamma");
+        transcript.append_message(b"z", &proof.z.to_compressed());
+        if let Some(lookup) = &proof.lookup {
+            transcript.append_message(b"z_lookup", &lookup.z.to_compressed());
+        }
+        let alpha = challenge(&mut transcript, b"alpha");
+        transcript.append_message(b"t", &proof.t.to_compressed());
+        let zeta = challenge(&mut transcript, b"zeta");
+        let evaluations = &proof.evaluations;
+        for evaluation in &evaluations.at_zeta {
+            transcript.append_message(b"evaluation", &evaluation.to_bytes());
+        }
+        transcript.append_message(b"evaluation", &evaluations.z_shifted.to_bytes());
+        if let Some(lookup) = &proof.lookup {
+            for evaluation in &lookup.evaluations {
+                transcript.append_message(b"evaluation", &evaluation.to_bytes());
+            }
+        }
+        if let Some(custom) = &proof.custom {
+            for evaluation in custom.selectors.iter().chain(custom.wires.iter().flatten()) {
+                transcript.append_message(b"evaluation", &evaluation.to_bytes());
+            }
+        }
+        let v = challenge(&mut transcript, b"v");
+
+        // The gate, permutation and start identities at zeta
+        let vanishing = domain.vanishing_at(zeta);
+        if vanishing == Fr::ZERO {
+            return Ok(false);
+        }
+        let scale = vanishing * Fr::from(domain.size as u64).invert().unwrap();
+        let lagrange = |i: usize| {
+            let x = domain.element(i);
+            x * scale * (zeta - x).invert().unwrap()
+        };
+        let first = lagrange(0);
+        let mut public = Fr::ZERO;
+        for (i, input) in inputs.iter().enumerate() {
+            public -= input * lagrange(i);
+        }
+        let [a, b, c, z, t, q_m, q_l, q_r, q_o, q_c, s_a, s_b, s_c] = evaluations.at_zeta;
+        let shifts = coset_shifts();
+        let mut gate = q_m * a * b + q_l * a + q_r * b + q_o * c + q_c + public;
+        if let Some(custom) = &proof.custom {
+            for (custom_gate, selector) in custom_gates.iter().zip(&custom.selectors) {
+                gate += selector
+                    * evaluate_gate(&custom_gate.terms, |i| match i / 3 {
+                        0 => [a, b, c][i % 3],
+                        r => custom.wires[r - 1][i % 3],
+                    });
+            }
+        }
+        let identity = z
+            * (a + beta * shifts[0] * zeta + gamma)
+            * (b + beta * shifts[1] * zeta + gamma)
+            * (c + beta * shifts[2] * zeta + gamma);
+        let permuted = evaluations.z_shifted
+            * (a + beta * s_a + gamma)
+            * (b + beta * s_b + gamma)
+            * (c + beta * s_c + gamma);
+        let start = first * (z - Fr::ONE);
+        let alpha_squared = alpha.square();
+        let mut numerator = gate + alpha * (identity - permuted) + alpha_squared * start;
+
+        // and the lookup identities: the accumulator starts and ends at 1,
+        // steps by the plookup ratio on every row but the last, and h1 ends
+        // where h2 starts
+        if let Some(lookup) = &proof.lookup {
+            let [q_lookup, q_table, table, h1, h2, lookup_z, table_shifted, h1_shifted, h2_shifted, lookup_z_shifted] =
+                lookup.evaluations;
+            let last_element = domain.element(domain.size - 1);
+            let last = lagrange(domain.size - 1);
+            let one_plus_beta = Fr::ONE + beta;
+            let gamma_beta = gamma * one_plus_beta;
+            let f = q_lookup * compress_row(eta, [a, b, c, q_table]);
+            let product = (zeta - last_element)
+                * (lookup_z
+                    * one_plus_beta
+                    * (gamma + f)
+                    * (gamma_beta + table + beta * table_shifted)
+                    - lookup_z_shifted
+                        * (gamma_beta + h1 + beta * h1_shifted)
+                        * (gamma_beta + h2 + beta * h2_shifted));
+            numerator += alpha_squared * alpha * first * (lookup_z - Fr::ONE)
+                + alpha_squared.square() * product
+                + alpha_squared.square() * alpha * last * (h1 - h2_shifted)
+                + alpha_squared.square() * alpha_squared * last * (lookup_z - Fr::ONE);
+        }
+        if numerator != t * vanishing {
+            return Ok(false);
+        }
+
+        // e(W + u W', tau) = e(zeta W + u zeta omega W' + F - E + u (F' - E'), 1)
+        // for F and F' the v-combinations of the commitments opened at zeta
+        // and zeta omega, and E and E' those of their evaluations
+        transcript.append_message(b"opening", &proof.opening.to_compressed());
+        transcript.append_message(b"opening", &proof.shifted_opening.to_compressed());
+        for opening in proof.custom.iter().flat_map(|c| &c.openings) {
+            transcript.append_message(b"opening", &opening.to_compressed());
+        }
+        let u = challenge(&mut transcript, b"u");
+        let mut bases: Vec<G1Affine> = proof
+            .wires
+            .iter()
+            .chain([&proof.z, &proof.t])
+            .chain(&verifying_key.selectors)
+            .chain(&verifying_key.permutation)
+            .copied()
+            .collect();
+        let mut scalars = Vec::with_capacity(bases.len() + 4);
+        let mut value = Fr::ZERO;
+        let mut weight = Fr::ONE;
+        for evaluation in &evaluations.at_zeta {
+            scalars.push(weight);
+            value += weight * evaluation;
+            weight *= v;
+        }
+        bases.push(proof.z);
+        scalars.push(u);
+        let mut shifted_value = evaluations.z_shifted;
+        let mut shifted_weight = v;
+        if let (Some(lookup), Some(key)) = (&proof.lookup, &verifying_key.lookup) {
+            // The compressed table's commitment is the eta-combination of
+            // the column commitments
+            let eta_powers = [Fr::ONE, eta, eta.square(), eta.square() * eta];
+            let at_zeta = &lookup.evaluations[..6];
+            let at_shifted = &lookup.evaluations[6..];
+            for (index, evaluation) in at_zeta.iter().enumerate() {
+                match index {
+                    0 | 1 => {
+                        bases.push(key.selectors[index]);
+                        scalars.push(weight);
+                    }
+                    2 => {
+                        bases.extend(&key.table);
+                        scalars.extend(eta_powers.iter().map(|power| weight * power));
+                    }
+                    _ => {
+                        bases.push([lookup.sorted[0], lookup.sorted[1], lookup.z][index - 3]);
+                        scalars.push(weight);
+                    }
+                }
+                value += weight * evaluation;
+                weight *= v;
+            }
+            for (index, evaluation) in at_shifted.iter().enumerate() {
+                if index == 0 {
+                    bases.extend(&key.table);
+                    scalars.extend(eta_powers.iter().map(|power| u * shifted_weight * power));
+                } else {
+                    bases.push([lookup.sorted[0], lookup.sorted[1], lookup.z][index - 1]);
+                    scalars.push(u * shifted_weight);
+                }
+                shifted_value += shifted_weight * evaluation;
+                shifted_weight *= v;
+            }
+        }
+        // Custom gate selectors open at zeta, and a, b and c at zeta omega
+        // when gates read a second row
+        if let Some(custom) = &proof.custom {
+            for (custom_gate, evaluation) in custom_gates.iter().zip(&custom.selectors) {
+                bases.push(custom_gate.selector);
+                scalars.push(weight);
+                value += weight * evaluation;
+                weight *= v;
+            }
+            if let Some(first) = custom.wires.first() {
+                for (commitment, evaluation) in proof.wires.iter().zip(first) {
+                    bases.push(*commitment);
+                    scalars.push(u * shifted_weight);
+                    shifted_value += shifted_weight * evaluation;
+                    shifted_weight *= v;
+                }
+            }
+        }
+        let zeta_omega = zeta * domain.generator;
+        bases.extend([proof.opening, proof.shifted_opening]);
+        scalars.extend([zeta, u * zeta_omega]);
+        let mut left = proof.opening + proof.shifted_opening * u;
+        let mut constant = value + u * shifted_value;
+        // with the opening at zeta omega^r, for r from 2, weighted by u^r
+        if let Some(custom) = &proof.custom {
+            let (mut u_power, mut point) = (u, zeta_omega);
+            for (opening, evaluations) in custom.openings.iter().zip(custom.wires.iter().skip(1)) {
+                u_power *= u;
+                point *= domain.generator;
+                let mut weight = Fr::ONE;
+                for (commitment, evaluation) in proof.wires.iter().zip(evaluations) {
+                    bases.push(*commitment);
+                    scalars.push(u_power * weight);
+                    constant += u_power * weight * evaluation;
+                    weight *= v;
+                }
+                bases.push(*opening);
+                scalars.push(u_power * point);
+                left += opening * u_power;
+            }
+        }
+        bases.push(G1Affine::generator());
+        scalars.push(-constant);
+        let left = G1Affine::from(left);
+        let right = G1Affine::from(pippenger::<G1Projective>(&bases, &scalars));
+        let tau = G2Prepared::from(self.srs.tau_g2);
+        let one = G2Prepared::from(self.srs.g2);
+        let result = multi_miller_loop(&[(&left, &tau), (&-right, &one)]).final_exponentiation();
+        Ok(result == Gt::identity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn srs_updates_verify() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1090);
+        let srs = PlonkSrs::new(16, &mut rng);
+        assert!(srs.is_well_formed());
+        let (updated, proof) = srs.update(&mut rng);
+        assert!(srs.verify_update(&updated, &proof));
+        assert!(!updated.verify_update(&srs, &proof));
+        let (other, _) = srs.update(&mut rng);
+        assert!(!srs.verify_update(&other, &proof));
+    }
+
+    #[test]
+    fn proofs_verify_only_their_statement() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1090);
+        let plonk = Plonk::new(PlonkSrs::new(100, &mut rng));
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce(x, x, y);
+        let (proving_key, verifying_key) = plonk.setup(&cs, &mut rng).unwrap();
+        let assignment = Assignment::new(vec![Scalar::from(49u64)], vec![Scalar::from(7u64)]);
+        let proof = plonk.prove(&proving_key, &assignment, &mut rng).unwrap();
+        let inputs = [Scalar::from(49u64)];
+        assert!(plonk.verify(&verifying_key, &inputs, &proof).unwrap());
+        assert!(!plonk
+            .verify(&verifying_key, &[Scalar::from(50u64)], &proof)
+            .unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.evaluations.at_zeta[0] += Fr::ONE;
+        assert!(!plonk.verify(&verifying_key, &inputs, &tampered).unwrap());
+        let mut tampered = proof.clone();
+        tampered.evaluations.z_shifted += Fr::ONE;
+        assert!(!plonk.verify(&verifying_key, &inputs, &tampered).unwrap());
+        let mut tampered = proof.clone();
+        tampered.t = tampered.z;
+        assert!(!plonk.verify(&verifying_key, &inputs, &tampered).unwrap());
+
+        // Keys are tied to the SRS they were made on
+        let other = Plonk::new(PlonkSrs::new(100, &mut rng));
+        assert!(!other.verify(&verifying_key, &inputs, &proof).unwrap());
+    }
+}
@@ -0,0 +1,101 @@
+use super::*;
+use bls12_381::{multi_miller_loop, G1Affine, G1Projective, G2Prepared, Gt, Scalar as Fr};
+use merlin::Transcript;
+
+// A commitment scheme for polynomials over the BLS12-381 scalar field, given
+// by their coefficients, with proofs of evaluations at single points. The
+// transcript binds an opening to whatever the caller absorbed before it;
+// open and verify must be handed transcripts in the same state.
+pub trait PolynomialCommitment {
+    type Commitment: Clone + std::fmt::Debug;
+    type Opening: Clone + std::fmt::Debug;
+
+    fn name(&self) -> &'static str;
+
+    fn max_degree(&self) -> usize;
+
+    fn commit(&self, coefficients: &[Fr]) -> Result<Self::Commitment, CryptoError>;
+
+    // The value at `point` and a proof of it
+    fn open(
+        &self,
+        coefficients: &[Fr],
+        point: Fr,
+        transcript: &mut Transcript,
+    ) -> Result<(Fr, Self::Opening), CryptoError>;
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: Fr,
+        value: Fr,
+        opening: &Self::Opening,
+        transcript: &mut Transcript,
+    ) -> Result<bool, CryptoError>;
+}
+
+// KZG commitments over a PLONK SRS: a commitment is p(tau) G1 and an opening
+// the commitment to (p(X) - p(z)) / (X - z). Constant size, but only as
+// sound as the trusted setup and the discrete logarithm problem.
+#[derive(Clone, Debug)]
+pub struct Kzg {
+    srs: PlonkSrs,
+}
+
+impl Kzg {
+    pub fn new(srs: PlonkSrs) -> Self {
+        Self { srs }
+    }
+
+    pub fn srs(&self) -> &PlonkSrs {
+        &self.srs
+    }
+}
+
+impl PolynomialCommitment for Kzg {
+    type Commitment = G1Affine;
+    type Opening = G1Affine;
+
+    fn name(&self) -> &'static str {
+        "kzg"
+    }
+
+    fn max_degree(&self) -> usize {
+        self.srs.max_degree()
+    }
+
+    fn commit(&self, coefficients: &[Fr]) -> Result<G1Affine, CryptoError> {
+        self.srs.commit(coefficients)
+    }
+
+    fn open(
+        &self,
+        coefficients: &[Fr],
+        point: Fr,
+        _transcript: &mut Transcript,
+    ) -> Result<(Fr, G1Affine), CryptoError> {
+        let value = evaluate_polynomial(coefficients, point);
+        let witness = self.srs.commit(&divide_by_linear(coefficients, point))?;
+        Ok((value, witness))
+    }
+
+    // e(C - v G1 + z W, G2) = e(W, tau G2)
+    fn verify(
+        &self,
+        commitment: &G1Affine,
+        point: Fr,
+        value: Fr,
+        opening: &G1Affine,
+        _transcript: &mut Transcript,
+    ) -> Result<bool, CryptoError> {
+        let left = G1Affine::from(
+            G1Projective::from(commitment) - G1Affine::generator() * value + opening * point,
+        );
+        let result = multi_miller_loop(&[
+            (&left, &G2Prepared::from(self.srs.g2)),
+            (&-opening, &G2Prepared::from(self.srs.tau_g2)),
+        ])
+        .final_exponentiation();
+        Ok(result == Gt::identity())
+    }
+}
@@ -0,0 +1,333 @@
+use super::*;
+use aes_gcm::aes::cipher::generic_array::GenericArray;
+use aes_gcm::aes::cipher::{BlockEncrypt, KeyInit};
+use aes_gcm::aes::Aes256;
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::ops::Range;
+
+// PRFs key the pseudorandom states of prs.rs, the simulator's randomness
+// through PrfRng, and the demo's authentication key. Nothing in the crate
+// one-time pads quantum states yet, so there are no pad keys to derive.
+pub trait PseudorandomFunction {
+    fn evaluate(&self, input: &[u8], output_len: usize) -> Vec<u8>;
+
+    fn evaluate_bits(&self, input: u64, output_bits: usize) -> u64 {
+        let bytes = self.evaluate(&input.to_le_bytes(), 8);
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[..8]);
+        let value = u64::from_le_bytes(word);
+        if output_bits >= 64 {
+            value
+        } else {
+            value & ((1u64 << output_bits) - 1)
+        }
+    }
+}
+
+pub struct Blake3Prf {
+    key: [u8; 32],
+}
+
+impl Blake3Prf {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    pub fn random<R: RngCore + ?Sized>(rng: &mut R) -> Self {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        Self::new(key)
+    }
+}
+
+impl PseudorandomFunction for Blake3Prf {
+    fn evaluate(&self, input: &[u8], output_len: usize) -> Vec<u8> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update(input);
+        let mut output = vec![0u8; output_len];
+        hasher.finalize_xof().fill(&mut output);
+        output
+    }
+}
+
+pub struct AesPrf {
+    cipher: Aes256,
+    hash_key: [u8; 32],
+}
+
+impl AesPrf {
+    pub fn new(key: [u8; 32]) -> Self {
+        let cipher = Aes256::new(GenericArray::from_slice(&key));
+        // The compression key is the encryption of two fixed blocks, so it is
+        // as secret as the AES key
+        let mut hash_key = [0u8; 32];
+        for (index, half) in hash_key.chunks_mut(16).enumerate() {
+            let mut block = GenericArray::from((index as u128).to_le_bytes());
+            cipher.encrypt_block(&mut block);
+            half.copy_from_slice(&block);
+        }
+        Self { cipher, hash_key }
+    }
+
+    pub fn random<R: RngCore + ?Sized>(rng: &mut R) -> Self {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        Self::new(key)
+    }
+}
+
+impl PseudorandomFunction for AesPrf {
+    // Inputs are compressed to a full block with keyed BLAKE3, which is then
+    // the initial counter of counter mode. An unkeyed or shorter digest would
+    // let distinct inputs collide after a birthday-bound number of queries.
+    fn evaluate(&self, input: &[u8], output_len: usize) -> Vec<u8> {
+        let digest = blake3::keyed_hash(&self.hash_key, input);
+        let mut initial = [0u8; 16];
+        initial.copy_from_slice(&digest.as_bytes()[..16]);
+        let initial = u128::from_le_bytes(initial);
+        let mut output = Vec::with_capacity(output_len + 16);
+        let mut counter = 0u128;
+
+        while output.len() < output_len {
+            let mut block = GenericArray::from(initial.wrapping_add(counter).to_le_bytes());
+            self.cipher.encrypt_block(&mut block);
+            output.extend_from_slice(&block);
+            counter += 1;
+        }
+
+        output.truncate(output_len);
+        output
+    }
+}
+
+// Randomness streamed from a PRF under one label: the n-th request for
+// bytes is the PRF at (label, n). Whoever holds the key can replay the
+// stream, which is what makes simulate_with_prf deterministic.
+pub struct PrfRng<'a, P: PseudorandomFunction + ?Sized> {
+    prf: &'a P,
+    label: Vec<u8>,
+    requests: u64,
+}
+
+impl<'a, P: PseudorandomFunction + ?Sized> PrfRng<'a, P> {
+    pub fn new(prf: &'a P, label: &[u8]) -> Self {
+        Self {
+            prf,
+            label: label.to_vec(),
+            requests: 0,
+        }
+    }
+}
+
+impl<P: PseudorandomFunction + ?Sized> RngCore for PrfRng<'_, P> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut input = (self.label.len() as u64).to_le_bytes().to_vec();
+        input.extend_from_slice(&self.label);
+        input.extend_from_slice(&self.requests.to_le_bytes());
+        dest.copy_from_slice(&self.prf.evaluate(&input, dest.len()));
+        self.requests += 1;
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// Superposition access to a function on a small domain, with query counting
+pub struct QuantumQueryOracle<'a> {
+    function: Box<dyn Fn(usize) -> usize + 'a>,
+    input_bits: usize,
+    output_bits: usize,
+    queries: usize,
+}
+
+impl<'a> QuantumQueryOracle<'a> {
+    pub fn new<F: Fn(usize) -> usize + 'a>(
+        input_bits: usize,
+        output_bits: usize,
+        function: F,
+    ) -> Self {
+        Self {
+            function: Box::new(function),
+            input_bits,
+            output_bits,
+            queries: 0,
+        }
+    }
+
+    pub fn input_bits(&self) -> usize {
+        self.input_bits
+    }
+
+    pub fn output_bits(&self) -> usize {
+        self.output_bits
+    }
+
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    pub fn query(
+        &mut self,
+        state: &mut QuantumState,
+        input: Range<usize>,
+        output: Range<usize>,
+    ) -> Result<(), QuantumError> {
+        if input.len() != self.input_bits || output.len() != self.output_bits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        self.queries += 1;
+        apply_xor_oracle(state, &input, &output, &self.function)
+    }
+
+    pub fn classical_query(&mut self, x: usize) -> usize {
+        self.queries += 1;
+        (self.function)(x)
+    }
+}
+
+pub trait QuantumDistinguisher {
+    // Returns true when the oracle is believed to be the PRF
+    fn distinguish(
+        &mut self,
+        oracle: &mut QuantumQueryOracle,
+        rng: &mut dyn RngCore,
+    ) -> Result<bool, QuantumError>;
+}
+
+// Fourier-samples (-1)^{f_0(x)} twice; a structured (e.g. linear) function
+// gives identical samples, while a random function almost never does.
+pub struct FourierSamplingDistinguisher;
+
+impl QuantumDistinguisher for FourierSamplingDistinguisher {
+    fn distinguish(
+        &mut self,
+        oracle: &mut QuantumQueryOracle,
+        rng: &mut dyn RngCore,
+    ) -> Result<bool, QuantumError> {
+        let n = oracle.input_bits();
+        let m = oracle.output_bits();
+        let mut samples = Vec::with_capacity(2);
+
+        for _ in 0..2 {
+            let mut state = QuantumState::new(n + m)?;
+            // Output register in |-> on its lowest bit for phase kickback
+            state.apply_gate(QuantumGate::PauliX, n)?;
+            state.apply_gate(QuantumGate::Hadamard, n)?;
+            for qubit in 0..n {
+                state.apply_gate(QuantumGate::Hadamard, qubit)?;
+            }
+
+            oracle.query(&mut state, 0..n, n..n + m)?;
+
+            let mut outcome = 0;
+            for qubit in 0..n {
+                state.apply_gate(QuantumGate::Hadamard, qubit)?;
+                if state.measure_qubit(qubit, rng)? {
+                    outcome |= 1 << qubit;
+                }
+            }
+            samples.push(outcome);
+        }
+
+        Ok(samples[0] == samples[1])
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DistinguishingResult {
+    pub trials: usize,
+    pub correct: usize,
+    pub total_queries: usize,
+}
+
+impl DistinguishingResult {
+    pub fn advantage(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        (2.0 * self.correct as f64 / self.trials as f64 - 1.0).abs()
+    }
+
+    pub fn average_queries(&self) -> f64 {
+        if self.trials == 0 {
+            0.0
+        } else {
+            self.total_queries as f64 / self.trials as f64
+        }
+    }
+}
+
+// Real-or-random game for a PRF truncated to a toy domain, with superposition queries
+pub struct PrfDistinguishingExperiment {
+    pub input_bits: usize,
+    pub output_bits: usize,
+    pub trials: usize,
+}
+
+impl PrfDistinguishingExperiment {
+    pub fn run<P, K, D>(
+        &self,
+        mut keygen: K,
+        distinguisher: &mut D,
+        rng: &mut dyn RngCore,
+    ) -> Result<DistinguishingResult, QuantumError>
+    where
+        P: PseudorandomFunction,
+        K: FnMut(&mut dyn RngCore) -> P,
+        D: QuantumDistinguisher,
+    {
+        let mut result = DistinguishingResult {
+            trials: self.trials,
+            ..Default::default()
+        };
+
+        for _ in 0..self.trials {
+            let real = rng.gen::<bool>();
+            let guess = if real {
+                let prf = keygen(rng);
+                let output_bits = self.output_bits;
+                let mut oracle = QuantumQueryOracle::new(self.input_bits, output_bits, move |x| {
+                    prf.evaluate_bits(x as u64, output_bits) as usize
+                });
+                let guess = distinguisher.distinguish(&mut oracle, rng)?;
+                result.total_queries += oracle.queries();
+                guess
+            } else {
+                let table = self.random_table(rng);
+                let mut oracle =
+                    QuantumQueryOracle::new(self.input_bits, self.output_bits, move |x| table[&x]);
+                let guess = distinguisher.distinguish(&mut oracle, rng)?;
+                result.total_queries += oracle.queries();
+                guess
+            };
+
+            if guess == real {
+                result.correct += 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn random_table(&self, rng: &mut dyn RngCore) -> HashMap<usize, usize> {
+        let mask = (1usize << self.output_bits) - 1;
+        (0..1usize << self.input_bits)
+            .map(|x| (x, (rng.next_u64() as usize) & mask))
+            .collect()
+    }
+}
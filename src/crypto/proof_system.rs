@@ -0,0 +1,139 @@
+use super::*;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::Rng;
+
+// A proof system for the relation of a constraint system: setup for a
+// circuit, proving with a full assignment, and verifying against the public
+// inputs alone. Lets experiments swap one backend for another.
+pub trait ProofSystem {
+    type ProvingKey;
+    type VerifyingKey;
+    type Proof;
+
+    fn name(&self) -> &'static str;
+
+    fn setup<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), CryptoError>;
+
+    fn prove<R: Rng + ?Sized>(
+        &self,
+        proving_key: &Self::ProvingKey,
+        assignment: &Assignment,
+        rng: &mut R,
+    ) -> Result<Self::Proof, CryptoError>;
+
+    fn verify(
+        &self,
+        verifying_key: &Self::VerifyingKey,
+        public_inputs: &[Scalar],
+        proof: &Self::Proof,
+    ) -> Result<bool, CryptoError>;
+}
+
+// Statement bytes the Ristretto scheme proves public inputs under
+pub fn encode_public_inputs(public_inputs: &[Scalar]) -> Vec<u8> {
+    public_inputs.iter().flat_map(|x| x.to_bytes()).collect()
+}
+
+// The crate's Ristretto-based SNARK, with the public inputs as statement.
+// An insecure toy: anyone holding the verification key can forge proofs of
+// any statement, so it only stands in where soundness does not matter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RistrettoSnark;
+
+// The Ristretto scheme's keys are designated-verifier: the verification key
+// is itself the trapdoor
+#[derive(Clone, Debug)]
+pub struct RistrettoSnarkTrapdoor {
+    pub public_parameters: PublicParameters,
+    pub verification_key: VerificationKey,
+}
+
+impl Simulatable for RistrettoSnark {
+    type Trapdoor = RistrettoSnarkTrapdoor;
+
+    fn setup_with_trapdoor<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(SNARKProver, SNARKVerifier, RistrettoSnarkTrapdoor), CryptoError> {
+        let (public_parameters, proving_key, verification_key) = snark_setup(cs.clone(), rng);
+        let trapdoor = RistrettoSnarkTrapdoor {
+            public_parameters: public_parameters.clone(),
+            verification_key: verification_key.clone(),
+        };
+        Ok((
+            SNARKProver::new(public_parameters.clone(), proving_key),
+            SNARKVerifier::new(public_parameters, verification_key),
+            trapdoor,
+        ))
+    }
+
+    // What the prover does, without checking a witness
+    fn simulate<R: Rng + ?Sized>(
+        &self,
+        _verifier: &SNARKVerifier,
+        trapdoor: &RistrettoSnarkTrapdoor,
+        public_inputs: &[Scalar],
+        rng: &mut R,
+    ) -> Result<SNARKProof, CryptoError> {
+        let key = &trapdoor.verification_key;
+        key.validate(&trapdoor.public_parameters)?;
+        let auxiliary: Vec<RistrettoPoint> = key
+            .bases
+            .iter()
+            .map(|_| trapdoor.public_parameters.generator * random_scalar(rng))
+            .collect();
+        let auxiliary_points = auxiliary.iter().map(|point| point.compress()).collect();
+        Ok(balanced_proof(
+            &mut Transcript::new(SNARK_TRANSCRIPT_LABEL),
+            key,
+            &encode_public_inputs(public_inputs),
+            &auxiliary,
+            auxiliary_points,
+            random_scalar(rng),
+        ))
+    }
+}
+
+impl ProofSystem for RistrettoSnark {
+    type ProvingKey = SNARKProver;
+    type VerifyingKey = SNARKVerifier;
+    type Proof = SNARKProof;
+
+    fn name(&self) -> &'static str {
+        "ristretto"
+    }
+
+    fn setup<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(SNARKProver, SNARKVerifier), CryptoError> {
+        let (prover, verifier, _) = self.setup_with_trapdoor(cs, rng)?;
+        Ok((prover, verifier))
+    }
+
+    fn prove<R: Rng + ?Sized>(
+        &self,
+        prover: &SNARKProver,
+        assignment: &Assignment,
+        rng: &mut R,
+    ) -> Result<SNARKProof, CryptoError> {
+        prover.prove(&encode_public_inputs(&assignment.public), assignment, rng)
+    }
+
+    fn verify(
+        &self,
+        verifier: &SNARKVerifier,
+        public_inputs: &[Scalar],
+        proof: &SNARKProof,
+    ) -> Result<bool, CryptoError> {
+        verifier.verify(&encode_public_inputs(public_inputs), proof)
+    }
+}
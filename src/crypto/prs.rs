@@ -0,0 +1,140 @@
+use super::*;
+use num_complex::Complex64;
+use rand::{Rng, RngCore};
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrsPhase {
+    // (-1)^{f_k(x)}
+    Binary,
+    // exp(2 pi i f_k(x) / 2^bits)
+    Uniform { bits: usize },
+}
+
+// |psi_k> = 2^{-n/2} sum_x phase(f_k(x)) |x>, prepared by H^{(x)n} followed by
+// a PRF-keyed phase oracle
+pub struct PrsGenerator {
+    num_qubits: usize,
+    phase: PrsPhase,
+    preparation: QuantumCircuit,
+}
+
+impl PrsGenerator {
+    pub fn new(num_qubits: usize, phase: PrsPhase) -> Self {
+        let mut preparation = QuantumCircuit::new(num_qubits);
+        for qubit in 0..num_qubits {
+            preparation.add_gate(QuantumGate::Hadamard, qubit);
+        }
+
+        Self {
+            num_qubits,
+            phase,
+            preparation,
+        }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn generate<P: PseudorandomFunction + ?Sized>(
+        &self,
+        prf: &P,
+    ) -> Result<QuantumState, QuantumError> {
+        let mut state = QuantumState::new(self.num_qubits)?;
+        self.preparation.execute(&mut state)?;
+
+        match self.phase {
+            PrsPhase::Binary => {
+                apply_phase_oracle(&mut state, &(0..self.num_qubits), |x| {
+                    prf.evaluate_bits(x as u64, 1) == 1
+                })?;
+            }
+            PrsPhase::Uniform { bits } => {
+                let modulus = (1u64 << bits) as f64;
+                for (x, amplitude) in state.amplitudes.iter_mut().enumerate() {
+                    let angle = 2.0 * PI * prf.evaluate_bits(x as u64, bits) as f64 / modulus;
+                    *amplitude *= Complex64::from_polar(1.0, angle);
+                }
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+pub trait PrsDistinguisher {
+    // Returns true when the copies are believed to come from the PRS generator
+    fn distinguish(
+        &mut self,
+        copies: &[QuantumState],
+        rng: &mut dyn RngCore,
+    ) -> Result<bool, QuantumError>;
+}
+
+// Measures every copy in the computational basis and guesses "PRS" when no
+// outcome repeats: PRS outputs are flat, while Haar states collide with
+// probability roughly 2 / (2^n + 1) per pair instead of 1 / 2^n.
+pub struct CollisionDistinguisher;
+
+impl PrsDistinguisher for CollisionDistinguisher {
+    fn distinguish(
+        &mut self,
+        copies: &[QuantumState],
+        rng: &mut dyn RngCore,
+    ) -> Result<bool, QuantumError> {
+        let mut seen = std::collections::HashSet::new();
+        for copy in copies {
+            let counts = copy.sample_counts(1, rng);
+            let outcome = counts.keys().next().copied().unwrap_or(0);
+            if !seen.insert(outcome) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Real-or-random game: t copies of |psi_k> for a fresh key versus t copies of a
+// Haar-random state
+pub struct PrsDistinguishingExperiment {
+    pub copies: usize,
+    pub trials: usize,
+}
+
+impl PrsDistinguishingExperiment {
+    pub fn run<P, K, D>(
+        &self,
+        generator: &PrsGenerator,
+        mut keygen: K,
+        distinguisher: &mut D,
+        rng: &mut dyn RngCore,
+    ) -> Result<DistinguishingResult, QuantumError>
+    where
+        P: PseudorandomFunction,
+        K: FnMut(&mut dyn RngCore) -> P,
+        D: PrsDistinguisher,
+    {
+        let mut result = DistinguishingResult {
+            trials: self.trials,
+            ..Default::default()
+        };
+
+        for _ in 0..self.trials {
+            let real = rng.gen::<bool>();
+            let state = if real {
+                generator.generate(&keygen(rng))?
+            } else {
+                haar_random_state(generator.num_qubits(), rng)?
+            };
+
+            let copies = vec![state; self.copies];
+            if distinguisher.distinguish(&copies, rng)? == real {
+                result.correct += 1;
+            }
+            result.total_queries += self.copies;
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,690 @@
+use super::{CryptoError, ForeignOperation};
+use curve25519_dalek::scalar::Scalar;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::{Add, Mul, Neg, Range, Sub};
+
+// Variables of a rank-1 constraint system over the Ristretto scalar field.
+// One is the constant 1; public inputs are part of the statement and private
+// ones of the witness, each numbered in allocation order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Variable {
+    One,
+    Public(usize),
+    Private(usize),
+}
+
+// sum of coefficient * variable, kept with one term per variable
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinearCombination {
+    terms: Vec<(Variable, Scalar)>,
+}
+
+impl LinearCombination {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn constant(value: Scalar) -> Self {
+        Self::from(Variable::One) * value
+    }
+
+    pub fn terms(&self) -> &[(Variable, Scalar)] {
+        &self.terms
+    }
+
+    pub fn add_term(mut self, variable: Variable, coefficient: Scalar) -> Self {
+        match self.terms.iter_mut().find(|(v, _)| *v == variable) {
+            Some((_, c)) => *c += coefficient,
+            None => self.terms.push((variable, coefficient)),
+        }
+        self.terms.retain(|(_, c)| *c != Scalar::ZERO);
+        self
+    }
+
+    pub fn evaluate(&self, assignment: &Assignment) -> Result<Scalar, CryptoError> {
+        self.terms.iter().try_fold(Scalar::ZERO, |sum, (v, c)| {
+            Ok(sum + assignment.value(*v)? * c)
+        })
+    }
+}
+
+impl From<Variable> for LinearCombination {
+    fn from(variable: Variable) -> Self {
+        Self {
+            terms: vec![(variable, Scalar::ONE)],
+        }
+    }
+}
+
+impl From<Scalar> for LinearCombination {
+    fn from(value: Scalar) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for LinearCombination {
+    type Output = Self;
+
+    fn add(self, other: T) -> Self {
+        other
+            .into()
+            .terms
+            .into_iter()
+            .fold(self, |lc, (v, c)| lc.add_term(v, c))
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for LinearCombination {
+    type Output = Self;
+
+    fn sub(self, other: T) -> Self {
+        self + -other.into()
+    }
+}
+
+impl Neg for LinearCombination {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -Scalar::ONE
+    }
+}
+
+impl Mul<Scalar> for LinearCombination {
+    type Output = Self;
+
+    fn mul(mut self, scalar: Scalar) -> Self {
+        for (_, c) in &mut self.terms {
+            *c *= scalar;
+        }
+        self.terms.retain(|(_, c)| *c != Scalar::ZERO);
+        self
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for Variable {
+    type Output = LinearCombination;
+
+    fn add(self, other: T) -> LinearCombination {
+        LinearCombination::from(self) + other
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for Variable {
+    type Output = LinearCombination;
+
+    fn sub(self, other: T) -> LinearCombination {
+        LinearCombination::from(self) - other
+    }
+}
+
+impl Mul<Scalar> for Variable {
+    type Output = LinearCombination;
+
+    fn mul(self, scalar: Scalar) -> LinearCombination {
+        LinearCombination::from(self) * scalar
+    }
+}
+
+// <a, z> * <b, z> = <c, z> for the full assignment z
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub name: String,
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+// A fixed relation that lookups check membership in: rows of `width`
+// values, at most three so that a lookup fits one PLONK gate. Tables are
+// identified by name within a constraint system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupTable {
+    name: String,
+    width: usize,
+    rows: Vec<Vec<Scalar>>,
+}
+
+impl LookupTable {
+    pub fn new(
+        name: impl Into<String>,
+        width: usize,
+        rows: Vec<Vec<Scalar>>,
+    ) -> Result<Self, CryptoError> {
+        if !(1..=3).contains(&width) || rows.is_empty() || rows.iter().any(|r| r.len() != width) {
+            return Err(CryptoError::InvalidLookupTable);
+        }
+        Ok(Self {
+            name: name.into(),
+            width,
+            rows,
+        })
+    }
+
+    // 0, 1, ..., 2^num_bits - 1
+    pub fn range(num_bits: usize) -> Result<Self, CryptoError> {
+        if num_bits > 20 {
+            return Err(CryptoError::RangeTooWide(num_bits));
+        }
+        Self::new(
+            format!("range {}", num_bits),
+            1,
+            (0..1u64 << num_bits)
+                .map(|x| vec![Scalar::from(x)])
+                .collect(),
+        )
+    }
+
+    // (x, f(x)) for every x below 2^input_bits, e.g. an S-box
+    pub fn function(
+        name: impl Into<String>,
+        input_bits: usize,
+        f: impl Fn(u64) -> u64,
+    ) -> Result<Self, CryptoError> {
+        if input_bits > 20 {
+            return Err(CryptoError::RangeTooWide(input_bits));
+        }
+        Self::new(
+            name,
+            2,
+            (0..1u64 << input_bits)
+                .map(|x| vec![Scalar::from(x), Scalar::from(f(x))])
+                .collect(),
+        )
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rows(&self) -> &[Vec<Scalar>] {
+        &self.rows
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TableId(pub(crate) usize);
+
+// The values of `inputs` form a row of `table`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup {
+    pub name: String,
+    pub table: TableId,
+    pub inputs: Vec<LinearCombination>,
+}
+
+// A polynomial identity over `width` wires: the sum over the terms of the
+// coefficient times the product of the wires raised to the exponents must
+// vanish. Gates are identified by name within a constraint system, like
+// tables. PLONK proves an application of a gate with a single selector;
+// other backends see the rank-1 constraints it is lowered to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomGate {
+    name: String,
+    width: usize,
+    terms: Vec<(Scalar, Vec<u32>)>,
+}
+
+impl CustomGate {
+    pub fn new(
+        name: impl Into<String>,
+        width: usize,
+        terms: Vec<(Scalar, Vec<u32>)>,
+    ) -> Result<Self, CryptoError> {
+        if width == 0 || terms.is_empty() || terms.iter().any(|(_, e)| e.len() != width) {
+            return Err(CryptoError::InvalidCustomGate);
+        }
+        Ok(Self {
+            name: name.into(),
+            width,
+            terms,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn terms(&self) -> &[(Scalar, Vec<u32>)] {
+        &self.terms
+    }
+
+    // Largest total degree of a term
+    pub fn degree(&self) -> usize {
+        self.terms
+            .iter()
+            .map(|(_, exponents)| exponents.iter().map(|e| *e as usize).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn evaluate(&self, wires: &[Scalar]) -> Scalar {
+        self.terms
+            .iter()
+            .map(|(c, exponents)| {
+                let mut term = *c;
+                for (wire, e) in wires.iter().zip(exponents) {
+                    for _ in 0..*e {
+                        term *= wire;
+                    }
+                }
+                term
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GateId(pub(crate) usize);
+
+// `gate` holds on the values of `inputs`; `lowered` are the indices of the
+// constraints that express it in rank-1 form
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateApplication {
+    pub name: String,
+    pub gate: GateId,
+    pub inputs: Vec<LinearCombination>,
+    pub lowered: Range<usize>,
+}
+
+// Values of the public and private variables, in allocation order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Assignment {
+    pub public: Vec<Scalar>,
+    pub private: Vec<Scalar>,
+}
+
+impl Assignment {
+    pub fn new(public: Vec<Scalar>, private: Vec<Scalar>) -> Self {
+        Self { public, private }
+    }
+
+    pub fn value(&self, variable: Variable) -> Result<Scalar, CryptoError> {
+        match variable {
+            Variable::One => Some(Scalar::ONE),
+            Variable::Public(i) => self.public.get(i).copied(),
+            Variable::Private(i) => self.private.get(i).copied(),
+        }
+        .ok_or(CryptoError::UnassignedVariable(variable))
+    }
+}
+
+// How to compute a private variable that the constraints alone do not
+// determine from earlier values
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    // Bit `bit` of the canonical little-endian encoding of `source`
+    Bit {
+        source: LinearCombination,
+        bit: usize,
+    },
+    // Bits `offset` to `offset + count` of `source`, as a number
+    Bits {
+        source: LinearCombination,
+        offset: usize,
+        count: usize,
+    },
+    // source / 2^shift, for a source the constraints make a multiple of it
+    Shifted {
+        source: LinearCombination,
+        shift: usize,
+    },
+    // Limb `limb` of an operation over the BLS12-381 scalar field, on
+    // operands given as FOREIGN_LIMBS limbs each
+    Foreign {
+        operation: ForeignOperation,
+        operands: Vec<LinearCombination>,
+        limb: usize,
+    },
+}
+
+impl Hint {
+    pub fn sources(&self) -> &[LinearCombination] {
+        match self {
+            Hint::Bit { source, .. } | Hint::Bits { source, .. } | Hint::Shifted { source, .. } => {
+                std::slice::from_ref(source)
+            }
+            Hint::Foreign { operands, .. } => operands,
+        }
+    }
+
+    // Value of the hinted variable, given the values of its sources
+    pub fn apply(&self, sources: &[Scalar]) -> Scalar {
+        match self {
+            Hint::Bit { bit, .. } => {
+                let bytes = sources[0].to_bytes();
+                Scalar::from(((bytes[bit / 8] >> (bit % 8)) & 1) as u64)
+            }
+            Hint::Bits { offset, count, .. } => {
+                let bytes = sources[0].to_bytes();
+                let mut value = Scalar::ZERO;
+                for bit in (*offset..offset + count).rev() {
+                    let set = bit < 256 && (bytes[bit / 8] >> (bit % 8)) & 1 == 1;
+                    value = value + value + Scalar::from(set as u64);
+                }
+                value
+            }
+            Hint::Shifted { shift, .. } => {
+                let divisor = (0..*shift).fold(Scalar::ONE, |x, _| x + x);
+                sources[0] * divisor.invert()
+            }
+            Hint::Foreign {
+                operation, limb, ..
+            } => operation.limb(sources, *limb),
+        }
+    }
+
+    pub fn evaluate(&self, assignment: &Assignment) -> Result<Scalar, CryptoError> {
+        let sources = self
+            .sources()
+            .iter()
+            .map(|source| source.evaluate(assignment))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.apply(&sources))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintSystem {
+    num_public: usize,
+    num_private: usize,
+    constraints: Vec<Constraint>,
+    hints: BTreeMap<usize, Hint>,
+    tables: Vec<LookupTable>,
+    lookups: Vec<Lookup>,
+    custom_gates: Vec<CustomGate>,
+    gate_applications: Vec<GateApplication>,
+}
+
+impl ConstraintSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn alloc_public(&mut self) -> Variable {
+        self.num_public += 1;
+        Variable::Public(self.num_public - 1)
+    }
+
+    pub fn alloc_private(&mut self) -> Variable {
+        self.num_private += 1;
+        Variable::Private(self.num_private - 1)
+    }
+
+    // A private variable whose value comes from `hint` rather than from the
+    // constraints; the caller still has to constrain it
+    pub fn alloc_hinted(&mut self, hint: Hint) -> Variable {
+        self.hints.insert(self.num_private, hint);
+        self.alloc_private()
+    }
+
+    pub fn hint(&self, private: usize) -> Option<&Hint> {
+        self.hints.get(&private)
+    }
+
+    // Adds a * b = c, named after its position
+    pub fn enforce(
+        &mut self,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+    ) {
+        let name = format!("constraint {}", self.constraints.len());
+        self.enforce_named(name, a, b, c);
+    }
+
+    // Adds a * b = c under a name that error reports can point to
+    pub fn enforce_named(
+        &mut self,
+        name: impl Into<String>,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+    ) {
+        self.constraints.push(Constraint {
+            name: name.into(),
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+        });
+    }
+
+    // a = b, as a single constraint (a - b) * 1 = 0
+    pub fn enforce_equal(
+        &mut self,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+    ) {
+        self.enforce(
+            a.into() - b.into(),
+            Variable::One,
+            LinearCombination::zero(),
+        );
+    }
+
+    // The id of `table`, adding it unless a table of that name is already
+    // there; a different table under a taken name is refused
+    pub fn add_table(&mut self, table: LookupTable) -> Result<TableId, CryptoError> {
+        match self.tables.iter().position(|t| t.name == table.name) {
+            Some(index) if self.tables[index] == table => Ok(TableId(index)),
+            Some(_) => Err(CryptoError::InvalidLookupTable),
+            None => {
+                self.tables.push(table);
+                Ok(TableId(self.tables.len() - 1))
+            }
+        }
+    }
+
+    pub fn table(&self, id: TableId) -> Option<&LookupTable> {
+        self.tables.get(id.0)
+    }
+
+    pub fn tables(&self) -> &[LookupTable] {
+        &self.tables
+    }
+
+    // Adds the lookup of `inputs` in `table`, named after its position
+    pub fn enforce_lookup(
+        &mut self,
+        table: TableId,
+        inputs: Vec<LinearCombination>,
+    ) -> Result<(), CryptoError> {
+        let name = format!("lookup {}", self.lookups.len());
+        self.enforce_lookup_named(name, table, inputs)
+    }
+
+    pub fn enforce_lookup_named(
+        &mut self,
+        name: impl Into<String>,
+        table: TableId,
+        inputs: Vec<LinearCombination>,
+    ) -> Result<(), CryptoError> {
+        let width = self
+            .table(table)
+            .ok_or(CryptoError::InvalidLookupTable)?
+            .width;
+        if inputs.len() != width {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        self.lookups.push(Lookup {
+            name: name.into(),
+            table,
+            inputs,
+        });
+        Ok(())
+    }
+
+    pub fn lookups(&self) -> &[Lookup] {
+        &self.lookups
+    }
+
+    pub fn num_lookups(&self) -> usize {
+        self.lookups.len()
+    }
+
+    // The id of `gate`, adding it unless a gate of that name is already
+    // there; a different gate under a taken name is refused
+    pub fn add_gate(&mut self, gate: CustomGate) -> Result<GateId, CryptoError> {
+        match self.custom_gates.iter().position(|g| g.name == gate.name) {
+            Some(index) if self.custom_gates[index] == gate => Ok(GateId(index)),
+            Some(_) => Err(CryptoError::InvalidCustomGate),
+            None => {
+                self.custom_gates.push(gate);
+                Ok(GateId(self.custom_gates.len() - 1))
+            }
+        }
+    }
+
+    pub fn custom_gate(&self, id: GateId) -> Option<&CustomGate> {
+        self.custom_gates.get(id.0)
+    }
+
+    pub fn custom_gates(&self) -> &[CustomGate] {
+        &self.custom_gates
+    }
+
+    // Applies `gate` to `inputs`, named after its position
+    pub fn enforce_gate(
+        &mut self,
+        gate: GateId,
+        inputs: Vec<LinearCombination>,
+    ) -> Result<(), CryptoError> {
+        let name = format!("gate {}", self.gate_applications.len());
+        self.enforce_gate_named(name, gate, inputs)
+    }
+
+    // Each monomial of degree above one costs a constraint per extra
+    // factor, and the sum one more
+    pub fn enforce_gate_named(
+        &mut self,
+        name: impl Into<String>,
+        gate: GateId,
+        inputs: Vec<LinearCombination>,
+    ) -> Result<(), CryptoError> {
+        let name = name.into();
+        let terms = self
+            .custom_gate(gate)
+            .ok_or(CryptoError::InvalidCustomGate)?
+            .terms
+            .clone();
+        if inputs.len() != terms[0].1.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        let start = self.constraints.len();
+        let mut sum = LinearCombination::zero();
+        for (c, exponents) in &terms {
+            let mut monomial: Option<LinearCombination> = None;
+            for (input, e) in inputs.iter().zip(exponents) {
+                for _ in 0..*e {
+                    monomial = Some(match monomial {
+                        None => input.clone(),
+                        Some(factor) => {
+                            let product = self.alloc_private();
+                            self.enforce_named(
+                                format!("{} product", name),
+                                factor,
+                                input.clone(),
+                                product,
+                            );
+                            product.into()
+                        }
+                    });
+                }
+            }
+            sum = sum + monomial.unwrap_or_else(|| Variable::One.into()) * *c;
+        }
+        self.enforce_named(name.clone(), sum, Variable::One, LinearCombination::zero());
+        self.gate_applications.push(GateApplication {
+            name,
+            gate,
+            inputs,
+            lowered: start..self.constraints.len(),
+        });
+        Ok(())
+    }
+
+    pub fn gate_applications(&self) -> &[GateApplication] {
+        &self.gate_applications
+    }
+
+    pub fn num_public(&self) -> usize {
+        self.num_public
+    }
+
+    pub fn num_private(&self) -> usize {
+        self.num_private
+    }
+
+    // Public, private and the constant
+    pub fn num_variables(&self) -> usize {
+        1 + self.num_public + self.num_private
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    fn check_lengths(&self, assignment: &Assignment) -> Result<(), CryptoError> {
+        if assignment.public.len() != self.num_public
+            || assignment.private.len() != self.num_private
+        {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        Ok(())
+    }
+
+    // Index of the first constraint the assignment violates
+    pub fn first_unsatisfied(&self, assignment: &Assignment) -> Result<Option<usize>, CryptoError> {
+        self.check_lengths(assignment)?;
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            let a = constraint.a.evaluate(assignment)?;
+            let b = constraint.b.evaluate(assignment)?;
+            let c = constraint.c.evaluate(assignment)?;
+            if a * b != c {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    // Index of the first lookup whose inputs are not a row of its table
+    pub fn first_failed_lookup(
+        &self,
+        assignment: &Assignment,
+    ) -> Result<Option<usize>, CryptoError> {
+        self.check_lengths(assignment)?;
+        let tables: Vec<HashSet<&[Scalar]>> = self
+            .tables
+            .iter()
+            .map(|table| table.rows.iter().map(|row| row.as_slice()).collect())
+            .collect();
+        for (index, lookup) in self.lookups.iter().enumerate() {
+            let values = lookup
+                .inputs
+                .iter()
+                .map(|input| input.evaluate(assignment))
+                .collect::<Result<Vec<_>, _>>()?;
+            if !tables[lookup.table.0].contains(values.as_slice()) {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn is_satisfied(&self, assignment: &Assignment) -> Result<bool, CryptoError> {
+        Ok(self.first_unsatisfied(assignment)?.is_none()
+            && self.first_failed_lookup(assignment)?.is_none())
+    }
+}
@@ -0,0 +1,519 @@
+use super::*;
+use bls12_381::Scalar as Fr;
+use curve25519_dalek::scalar::Scalar;
+use ff::{Field, PrimeField};
+use rand::Rng;
+
+// Openings in a FriSnarkProof, one per evaluation
+const OPENINGS_PER_STEP: usize = 7;
+
+// Digests enter circuits as two 128-bit halves, low half first
+fn digest_halves(digest: &[u8; 32]) -> [Scalar; 2] {
+    std::array::from_fn(|i| {
+        Scalar::from(u128::from_le_bytes(
+            digest[16 * i..16 * (i + 1)].try_into().unwrap(),
+        ))
+    })
+}
+
+fn alloc_digest(
+    cs: &mut ConstraintSystem,
+    public: bool,
+) -> Result<(Vec<LinearCombination>, [Variable; 2]), CryptoError> {
+    let halves = [(); 2].map(|_| {
+        if public {
+            cs.alloc_public()
+        } else {
+            cs.alloc_private()
+        }
+    });
+    let mut bits = Vec::with_capacity(256);
+    for half in halves {
+        bits.extend(decompose(cs, &half.into(), 128)?);
+    }
+    Ok((bits, halves))
+}
+
+// The queries of one FRI opening as constraints, for the shape its Fri
+// fixes: Merkle paths through BLAKE3 gadgets, and the quotient and folding
+// over the FRI field emulated with FrVar. What the transcript gives is
+// public, in the order public_inputs lists it; the decommitments are
+// private, set by assign.
+#[derive(Clone, Debug)]
+pub(crate) struct FriQueryGadget {
+    private: Vec<Variable>,
+}
+
+impl FriQueryGadget {
+    pub(crate) fn new(cs: &mut ConstraintSystem, fri: &Fri) -> Result<Self, CryptoError> {
+        let domain = fri.domain()?;
+        let rounds = fri.rounds();
+        let log_size = domain.size.trailing_zeros() as usize;
+        let roots = (0..rounds.max(1))
+            .map(|_| alloc_digest(cs, true).map(|(bits, _)| bits))
+            .collect::<Result<Vec<_>, _>>()?;
+        let point = FrVar::alloc_public(cs)?;
+        let value = FrVar::alloc_public(cs)?;
+        let final_value = FrVar::alloc_public(cs)?;
+        let betas = (0..rounds)
+            .map(|_| FrVar::alloc_public(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let positions = (0..fri.num_queries())
+            .map(|_| {
+                let position = cs.alloc_public();
+                decompose(cs, &position.into(), log_size)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let one = FrVar::constant(&Fr::ONE);
+        let minus_one = FrVar::constant(&-Fr::ONE);
+        let two_inverse = FrVar::constant(&Fr::from(2).invert().unwrap());
+        let mut private = Vec::new();
+        for position in &positions {
+            let (mut generator, mut shift) = (domain.generator, Fr::MULTIPLICATIVE_GENERATOR);
+            let mut expected: Option<FrVar> = None;
+            for (layer, root) in roots.iter().enumerate() {
+                // The leaf index is the position in the layer without its
+                // top bit, which tells x from -x
+                let (index, top) = position[..log_size - layer].split_at(log_size - layer - 1);
+                let mut values = Vec::with_capacity(2);
+                for _ in 0..2 {
+                    let (value, limbs) = FrVar::alloc_private(cs)?;
+                    private.extend(limbs);
+                    values.push(value);
+                }
+                let mut path = Vec::with_capacity(index.len());
+                for bit in index {
+                    let (node, halves) = alloc_digest(cs, false)?;
+                    private.extend(halves);
+                    path.push((node, bit.clone()));
+                }
+                let mut leaf = values[0].to_bytes_bits();
+                leaf.extend(values[1].to_bytes_bits());
+                let leaf = blake3_block_gadget(cs, &leaf)?;
+                verify_merkle_path_gadget(cs, &leaf, &path, root)?;
+
+                // x = shift generator^index, a product of constants the
+                // index bits select
+                let mut x = FrVar::constant(&shift);
+                let mut power = generator;
+                for (i, bit) in index.iter().enumerate() {
+                    if i == 0 {
+                        x = FrVar::select(cs, bit, &FrVar::constant(&(shift * power)), &x);
+                    } else {
+                        let factor = FrVar::select(cs, bit, &FrVar::constant(&power), &one);
+                        x = x.mul(cs, &factor)?;
+                    }
+                    power = power.square();
+                }
+                if layer == 0 {
+                    // Evaluations of p to evaluations of the quotient
+                    let minus_x = x.mul(cs, &minus_one)?;
+                    for (v, x) in values.iter_mut().zip([&x, &minus_x]) {
+                        let numerator = v.sub(cs, &value)?;
+                        let denominator = x.sub(cs, &point)?;
+                        *v = numerator.div(cs, &denominator)?;
+                    }
+                }
+                if let Some(expected) = &expected {
+                    FrVar::select(cs, &top[0], &values[1], &values[0]).enforce_equal(cs, expected);
+                }
+                if rounds == 0 {
+                    // Nothing to fold: the quotient is already constant
+                    values[0].enforce_equal(cs, &final_value);
+                    values[1].enforce_equal(cs, &final_value);
+                } else {
+                    let odd = values[0].sub(cs, &values[1])?.div(cs, &x)?;
+                    let even = values[0].add(cs, &values[1])?;
+                    let folded = betas[layer].mul_add(cs, &odd, &even)?;
+                    expected = Some(folded.mul(cs, &two_inverse)?);
+                }
+                generator = generator.square();
+                shift = shift.square();
+            }
+            if let Some(expected) = expected {
+                expected.enforce_equal(cs, &final_value);
+            }
+        }
+        Ok(Self { private })
+    }
+
+    pub(crate) fn public_inputs(claim: &FriQueryClaim) -> Vec<Scalar> {
+        let mut inputs: Vec<Scalar> = claim.roots.iter().flat_map(digest_halves).collect();
+        for x in [&claim.point, &claim.value, &claim.final_value]
+            .into_iter()
+            .chain(&claim.betas)
+        {
+            inputs.extend(FrVar::limb_values(x));
+        }
+        inputs.extend(claim.positions.iter().map(|&p| Scalar::from(p as u64)));
+        inputs
+    }
+
+    pub(crate) fn assign(
+        &self,
+        witness: &mut WitnessBuilder,
+        queries: &[Vec<FriDecommitment>],
+    ) -> Result<(), CryptoError> {
+        let mut values = Vec::with_capacity(self.private.len());
+        for decommitment in queries.iter().flatten() {
+            for value in &decommitment.values {
+                values.extend(FrVar::limb_values(value));
+            }
+            values.extend(decommitment.path.iter().flat_map(digest_halves));
+        }
+        if values.len() != self.private.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        for (&variable, value) in self.private.iter().zip(values) {
+            witness.set(variable, value)?;
+        }
+        Ok(())
+    }
+}
+
+// The outer proof, with what the verifier replays natively: the states
+// between the steps, the first being the input, and each step's proof short
+// of its decommitments
+#[derive(Clone, Debug)]
+pub struct ComposedProof<T> {
+    pub states: Vec<Vec<Scalar>>,
+    pub claims: Vec<FriSnarkClaim>,
+    pub outer: T,
+}
+
+// Composes FRI SNARK proofs of a fixed number of steps of a computation into
+// one proof, in the outer system P, that all their FRI queries pass. A step
+// circuit's public inputs are the state before it, then the state after it.
+// The verifier replays each step's transcript and checks its identity at
+// zeta natively, which fixes the outer circuit's public inputs; hashing the
+// Merlin transcript in-circuit would take a Keccak permutation per few
+// messages, and the FRI SNARK's verifier is linear in its circuit anyway.
+// This is not incrementally verifiable computation, and does not verify
+// SNARKProofs: the number of steps is fixed at setup, verification stays
+// linear in the steps and in the step circuit, and it saves only the
+// queries' hashing and folding. The outer circuit runs to
+// hundreds of thousands of constraints per step even with one query, and
+// the composed proof is only as succinct as P's proofs.
+pub struct RecursionDriver<P: ProofSystem> {
+    inner: FriSnark,
+    inner_proving_key: FriSnarkProvingKey,
+    inner_verifying_key: FriSnarkVerifyingKey,
+    outer: P,
+    outer_proving_key: P::ProvingKey,
+    outer_verifying_key: P::VerifyingKey,
+    circuit: ConstraintSystem,
+    gadgets: Vec<FriQueryGadget>,
+    state_width: usize,
+    num_steps: usize,
+}
+
+impl<P: ProofSystem> RecursionDriver<P> {
+    pub fn new<R: Rng + ?Sized>(
+        step: &ConstraintSystem,
+        state_width: usize,
+        num_steps: usize,
+        inner: FriSnark,
+        outer: P,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        if step.num_public() != 2 * state_width {
+            return Err(CryptoError::InvalidStateWidth(state_width));
+        }
+        if num_steps == 0 {
+            return Err(CryptoError::StepCountMismatch(num_steps));
+        }
+        let (inner_proving_key, inner_verifying_key) = inner.setup(step, rng)?;
+        let mut circuit = ConstraintSystem::new();
+        let gadgets = (0..OPENINGS_PER_STEP * num_steps)
+            .map(|_| FriQueryGadget::new(&mut circuit, &inner_verifying_key.fri))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (outer_proving_key, outer_verifying_key) = outer.setup(&circuit, rng)?;
+        Ok(Self {
+            inner,
+            inner_proving_key,
+            inner_verifying_key,
+            outer,
+            outer_proving_key,
+            outer_verifying_key,
+            circuit,
+            gadgets,
+            state_width,
+            num_steps,
+        })
+    }
+
+    pub fn outer_circuit(&self) -> &ConstraintSystem {
+        &self.circuit
+    }
+
+    pub fn prove_step<R: Rng + ?Sized>(
+        &self,
+        assignment: &Assignment,
+        rng: &mut R,
+    ) -> Result<FriSnarkProof, CryptoError> {
+        self.inner.prove(&self.inner_proving_key, assignment, rng)
+    }
+
+    // The steps' query claims in order, OPENINGS_PER_STEP to a step, failing
+    // with InvalidStep if a transcript or an identity at zeta does
+    fn query_claims(
+        &self,
+        states: &[Vec<Scalar>],
+        claims: &[FriSnarkClaim],
+    ) -> Result<Vec<FriQueryClaim>, CryptoError> {
+        if claims.len() != self.num_steps || states.len() != self.num_steps + 1 {
+            return Err(CryptoError::StepCountMismatch(self.num_steps));
+        }
+        if let Some(state) = states.iter().find(|state| state.len() != self.state_width) {
+            return Err(CryptoError::InvalidStateWidth(state.len()));
+        }
+        let mut queries = Vec::with_capacity(OPENINGS_PER_STEP * self.num_steps);
+        for (i, (states, claim)) in states.windows(2).zip(claims).enumerate() {
+            match FriSnark::check_claim(&self.inner_verifying_key, &states.concat(), claim)? {
+                Some(claims) => queries.extend(claims),
+                None => return Err(CryptoError::InvalidStep(i)),
+            }
+        }
+        Ok(queries)
+    }
+
+    // states[i] and states[i + 1] are the public inputs of steps[i]
+    pub fn compose<R: Rng + ?Sized>(
+        &self,
+        states: &[Vec<Scalar>],
+        steps: &[FriSnarkProof],
+        rng: &mut R,
+    ) -> Result<ComposedProof<P::Proof>, CryptoError> {
+        let claims: Vec<FriSnarkClaim> = steps.iter().map(FriSnarkProof::claim).collect();
+        if let Some(i) = steps
+            .iter()
+            .position(|step| step.openings.len() != OPENINGS_PER_STEP)
+        {
+            return Err(CryptoError::InvalidStep(i));
+        }
+        let queries = self.query_claims(states, &claims)?;
+        let openings: Vec<&FriOpening> = steps.iter().flat_map(|step| &step.openings).collect();
+        let mut public_inputs = Vec::new();
+        for (i, (claim, opening)) in queries.iter().zip(&openings).enumerate() {
+            if !self
+                .inner_verifying_key
+                .fri
+                .check_queries(claim, &opening.queries)?
+            {
+                return Err(CryptoError::InvalidStep(i / OPENINGS_PER_STEP));
+            }
+            public_inputs.extend(FriQueryGadget::public_inputs(claim));
+        }
+
+        let mut witness = self
+            .circuit
+            .witness_builder()
+            .with_public_inputs(&public_inputs)?;
+        for (gadget, opening) in self.gadgets.iter().zip(&openings) {
+            gadget.assign(&mut witness, &opening.queries)?;
+        }
+        let assignment = witness.build()?;
+        let outer = self
+            .outer
+            .prove(&self.outer_proving_key, &assignment, rng)?;
+        Ok(ComposedProof {
+            states: states.to_vec(),
+            claims,
+            outer,
+        })
+    }
+
+    pub fn verify(&self, proof: &ComposedProof<P::Proof>) -> Result<bool, CryptoError> {
+        let queries = match self.query_claims(&proof.states, &proof.claims) {
+            Ok(queries) => queries,
+            Err(
+                CryptoError::StepCountMismatch(_)
+                | CryptoError::InvalidStateWidth(_)
+                | CryptoError::InvalidStep(_),
+            ) => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let public_inputs: Vec<Scalar> = queries
+            .iter()
+            .flat_map(FriQueryGadget::public_inputs)
+            .collect();
+        self.outer
+            .verify(&self.outer_verifying_key, &public_inputs, &proof.outer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merlin::Transcript;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // A degree-1 polynomial opened with a single query, and the claim the
+    // verifier replays for it
+    fn opening() -> (Fri, FriQueryClaim, FriOpening) {
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let fri = Fri::new(1).with_blowup(1).with_queries(1);
+        let coefficients = [Fr::random(&mut rng), Fr::random(&mut rng)];
+        let point = Fr::random(&mut rng);
+        let commitment = fri.commit(&coefficients).unwrap();
+        let (value, opening) = fri
+            .open(&coefficients, point, &mut Transcript::new(b"test"))
+            .unwrap();
+        let claim = fri
+            .replay(
+                &commitment,
+                point,
+                value,
+                &opening.layer_roots,
+                opening.final_value,
+                &mut Transcript::new(b"test"),
+            )
+            .unwrap()
+            .unwrap();
+        (fri, claim, opening)
+    }
+
+    fn satisfied(fri: &Fri, claim: &FriQueryClaim, opening: &FriOpening) -> bool {
+        let mut cs = ConstraintSystem::new();
+        let gadget = FriQueryGadget::new(&mut cs, fri).unwrap();
+        let mut witness = cs
+            .witness_builder()
+            .with_public_inputs(&FriQueryGadget::public_inputs(claim))
+            .unwrap();
+        gadget.assign(&mut witness, &opening.queries).unwrap();
+        witness.build().is_ok()
+    }
+
+    #[test]
+    fn query_gadget_accepts_only_honest_openings() {
+        let (fri, claim, opening) = opening();
+        assert!(fri.check_queries(&claim, &opening.queries).unwrap());
+        assert!(satisfied(&fri, &claim, &opening));
+
+        let mut tampered = opening.clone();
+        tampered.queries[0][0].values[1] += Fr::ONE;
+        assert!(!satisfied(&fri, &claim, &tampered));
+
+        let mut wrong = claim.clone();
+        wrong.value += Fr::ONE;
+        assert!(!satisfied(&fri, &wrong, &opening));
+    }
+
+    // An outer system whose proof is the assignment itself, so the driver
+    // is tested without proving its large outer circuit
+    #[derive(Clone, Copy, Debug)]
+    struct Satisfiability;
+
+    impl ProofSystem for Satisfiability {
+        type ProvingKey = ();
+        type VerifyingKey = ConstraintSystem;
+        type Proof = Assignment;
+
+        fn name(&self) -> &'static str {
+            "satisfiability"
+        }
+
+        fn setup<R: Rng + ?Sized>(
+            &self,
+            cs: &ConstraintSystem,
+            _rng: &mut R,
+        ) -> Result<((), ConstraintSystem), CryptoError> {
+            Ok(((), cs.clone()))
+        }
+
+        fn prove<R: Rng + ?Sized>(
+            &self,
+            _proving_key: &(),
+            assignment: &Assignment,
+            _rng: &mut R,
+        ) -> Result<Assignment, CryptoError> {
+            Ok(assignment.clone())
+        }
+
+        fn verify(
+            &self,
+            verifying_key: &ConstraintSystem,
+            public_inputs: &[Scalar],
+            proof: &Assignment,
+        ) -> Result<bool, CryptoError> {
+            Ok(proof.public == public_inputs && verifying_key.is_satisfied(proof)?)
+        }
+    }
+
+    // y = x^2 + 1, through a private x^2
+    fn step() -> (ConstraintSystem, Variable) {
+        let mut cs = ConstraintSystem::new();
+        let x = cs.alloc_public();
+        let y = cs.alloc_public();
+        let square = cs.alloc_private();
+        cs.enforce(x, x, square);
+        cs.enforce_equal(y, square + Scalar::ONE);
+        (cs, square)
+    }
+
+    fn prove_step(
+        driver: &RecursionDriver<Satisfiability>,
+        cs: &ConstraintSystem,
+        square: Variable,
+        x: Scalar,
+        rng: &mut ChaCha20Rng,
+    ) -> FriSnarkProof {
+        let assignment = cs
+            .witness_builder()
+            .with_public_inputs(&[x, x * x + Scalar::ONE])
+            .unwrap()
+            .with_value(square, x * x)
+            .unwrap()
+            .build()
+            .unwrap();
+        driver.prove_step(&assignment, rng).unwrap()
+    }
+
+    #[test]
+    fn driver_composes_steps_and_rejects_bad_ones() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1097);
+        let (cs, square) = step();
+        let inner = FriSnark::default().with_blowup(1).with_queries(1);
+        let driver = RecursionDriver::new(&cs, 1, 2, inner, Satisfiability, &mut rng).unwrap();
+        let states: Vec<Vec<Scalar>> = [2u64, 5, 26]
+            .into_iter()
+            .map(|x| vec![Scalar::from(x)])
+            .collect();
+        let steps: Vec<FriSnarkProof> = states[..2]
+            .iter()
+            .map(|state| prove_step(&driver, &cs, square, state[0], &mut rng))
+            .collect();
+        let proof = driver.compose(&states, &steps, &mut rng).unwrap();
+        assert!(driver.verify(&proof).unwrap());
+
+        let mut forged = proof.clone();
+        forged.states[2][0] += Scalar::ONE;
+        assert!(!driver.verify(&forged).unwrap());
+        let mut forged = proof.clone();
+        forged.claims.pop();
+        assert!(!driver.verify(&forged).unwrap());
+
+        let mut bad = steps.clone();
+        bad[1].openings[0].queries[0][0].values[0] += Fr::ONE;
+        assert_eq!(
+            driver.compose(&states, &bad, &mut rng).unwrap_err(),
+            CryptoError::InvalidStep(1)
+        );
+        let mut bad = steps.clone();
+        bad[0].evaluations[0] += Fr::ONE;
+        assert_eq!(
+            driver.compose(&states, &bad, &mut rng).unwrap_err(),
+            CryptoError::InvalidStep(0)
+        );
+        assert_eq!(
+            driver.compose(&states, &steps[..1], &mut rng).unwrap_err(),
+            CryptoError::StepCountMismatch(2)
+        );
+        assert_eq!(
+            RecursionDriver::new(&cs, 2, 2, inner, Satisfiability, &mut rng).err(),
+            Some(CryptoError::InvalidStateWidth(2))
+        );
+    }
+}
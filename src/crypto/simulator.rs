@@ -0,0 +1,336 @@
+use super::*;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+
+// Proving key, verifying key and the trapdoor they were made under
+pub type TrapdoorKeys<P> = (
+    <P as ProofSystem>::ProvingKey,
+    <P as ProofSystem>::VerifyingKey,
+    <P as Simulatable>::Trapdoor,
+);
+
+// A proof system whose setup can hand out its trapdoor, and whose proofs
+// can then be simulated for any public inputs without a witness. For true
+// statements simulated proofs are distributed as honest ones; for false
+// ones they still verify, which is why the trapdoor must be destroyed.
+pub trait Simulatable: ProofSystem {
+    type Trapdoor;
+
+    fn setup_with_trapdoor<R: Rng + ?Sized>(
+        &self,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<TrapdoorKeys<Self>, CryptoError>;
+
+    fn simulate<R: Rng + ?Sized>(
+        &self,
+        verifying_key: &Self::VerifyingKey,
+        trapdoor: &Self::Trapdoor,
+        public_inputs: &[Scalar],
+        rng: &mut R,
+    ) -> Result<Self::Proof, CryptoError>;
+}
+
+// The zero-knowledge simulator of one setup: the verifying key together
+// with the trapdoor it was made under
+pub struct Simulator<P: Simulatable> {
+    system: P,
+    verifying_key: P::VerifyingKey,
+    trapdoor: P::Trapdoor,
+}
+
+impl<P: Simulatable> Simulator<P> {
+    pub fn new(system: P, verifying_key: P::VerifyingKey, trapdoor: P::Trapdoor) -> Self {
+        Self {
+            system,
+            verifying_key,
+            trapdoor,
+        }
+    }
+
+    // Keys for `cs`, with the simulator that knows their trapdoor in place
+    // of the verifying key
+    pub fn setup<R: Rng + ?Sized>(
+        system: P,
+        cs: &ConstraintSystem,
+        rng: &mut R,
+    ) -> Result<(P::ProvingKey, Self), CryptoError> {
+        let (proving_key, verifying_key, trapdoor) = system.setup_with_trapdoor(cs, rng)?;
+        Ok((proving_key, Self::new(system, verifying_key, trapdoor)))
+    }
+
+    pub fn system(&self) -> &P {
+        &self.system
+    }
+
+    pub fn verifying_key(&self) -> &P::VerifyingKey {
+        &self.verifying_key
+    }
+
+    pub fn trapdoor(&self) -> &P::Trapdoor {
+        &self.trapdoor
+    }
+
+    pub fn simulate<R: Rng + ?Sized>(
+        &self,
+        public_inputs: &[Scalar],
+        rng: &mut R,
+    ) -> Result<P::Proof, CryptoError> {
+        self.system
+            .simulate(&self.verifying_key, &self.trapdoor, public_inputs, rng)
+    }
+
+    // Simulation with randomness derived from `prf` under the public inputs,
+    // so one key gives one proof per statement
+    pub fn simulate_with_prf<F: PseudorandomFunction + ?Sized>(
+        &self,
+        public_inputs: &[Scalar],
+        prf: &F,
+    ) -> Result<P::Proof, CryptoError> {
+        let mut rng = PrfRng::new(prf, &encode_public_inputs(public_inputs));
+        self.simulate(public_inputs, &mut rng)
+    }
+}
+
+// Outcome of a two-sample test of whether two sets of encoded proofs are
+// drawn from one distribution. Every nibble position of the encodings is
+// histogrammed over its 16 values and the per-position chi-square
+// statistics are summed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistributionComparison {
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    // Largest total variation distance between the two samples' histograms
+    // at any one position
+    pub max_total_variation: f64,
+}
+
+impl DistributionComparison {
+    // The statistic in standard deviations above its mean under the null
+    // hypothesis, by the Wilson-Hilferty normal approximation
+    pub fn z_score(&self) -> f64 {
+        if self.degrees_of_freedom == 0 {
+            return 0.0;
+        }
+        let k = self.degrees_of_freedom as f64;
+        let variance = 2.0 / (9.0 * k);
+        ((self.chi_square / k).cbrt() - (1.0 - variance)) / variance.sqrt()
+    }
+
+    pub fn is_consistent(&self, max_z_score: f64) -> bool {
+        self.z_score() <= max_z_score
+    }
+}
+
+// Positions where both samples take a single common value contribute no
+// degrees of freedom, so fixed encoding bits are harmless. Both samples
+// need at least one encoding, all of one length.
+pub fn compare_distributions(
+    first: &[Vec<u8>],
+    second: &[Vec<u8>],
+) -> Result<DistributionComparison, CryptoError> {
+    let length = first.first().map_or(0, Vec::len);
+    if first.is_empty()
+        || second.is_empty()
+        || first.iter().chain(second).any(|e| e.len() != length)
+    {
+        return Err(CryptoError::IncomparableSamples);
+    }
+    let histogram = |sample: &[Vec<u8>], position: usize| {
+        let mut counts = [0usize; 16];
+        for encoding in sample {
+            counts[((encoding[position / 2] >> (4 * (position % 2))) & 0xf) as usize] += 1;
+        }
+        counts
+    };
+
+    let (n, m) = (first.len() as f64, second.len() as f64);
+    let (k1, k2) = ((m / n).sqrt(), (n / m).sqrt());
+    let mut comparison = DistributionComparison {
+        chi_square: 0.0,
+        degrees_of_freedom: 0,
+        max_total_variation: 0.0,
+    };
+    for position in 0..2 * length {
+        let (r, s) = (histogram(first, position), histogram(second, position));
+        let mut bins = 0;
+        let mut total_variation = 0.0;
+        for (r, s) in r.iter().zip(&s) {
+            let (r, s) = (*r as f64, *s as f64);
+            total_variation += (r / n - s / m).abs() / 2.0;
+            if r + s > 0.0 {
+                bins += 1;
+                comparison.chi_square += (k1 * r - k2 * s).powi(2) / (r + s);
+            }
+        }
+        comparison.degrees_of_freedom += bins - 1;
+        comparison.max_total_variation = comparison.max_total_variation.max(total_variation);
+    }
+    Ok(comparison)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::{G1Affine, G1Projective, G2Projective, Scalar as Fr};
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const SAMPLES: usize = 256;
+    const MAX_Z_SCORE: f64 = 4.0;
+
+    // x * x = y with y public, and the witness for y = 9
+    fn square_circuit() -> (ConstraintSystem, Assignment) {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        let assignment = cs
+            .witness_builder()
+            .with_public_inputs(&[Scalar::from(9u64)])
+            .unwrap()
+            .with_value(x, Scalar::from(3u64))
+            .unwrap()
+            .build()
+            .unwrap();
+        (cs, assignment)
+    }
+
+    fn groth16_bytes(proof: &Groth16Proof) -> Vec<u8> {
+        [
+            &proof.a.to_compressed()[..],
+            &proof.b.to_compressed()[..],
+            &proof.c.to_compressed()[..],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn simulated_proofs_verify_without_a_witness() {
+        let mut rng = ChaCha20Rng::seed_from_u64(11);
+        let (cs, _) = square_circuit();
+        // Proofs for y = 10 need no square root of 10
+        let inputs = [Scalar::from(10u64)];
+
+        let (_, simulator) = Simulator::setup(Groth16, &cs, &mut rng).unwrap();
+        let proof = simulator.simulate(&inputs, &mut rng).unwrap();
+        assert!(Groth16
+            .verify(simulator.verifying_key(), &inputs, &proof)
+            .unwrap());
+        assert!(simulator.simulate(&[], &mut rng).is_err());
+
+        let (_, simulator) = Simulator::setup(RistrettoSnark, &cs, &mut rng).unwrap();
+        let proof = simulator.simulate(&inputs, &mut rng).unwrap();
+        assert!(RistrettoSnark
+            .verify(simulator.verifying_key(), &inputs, &proof)
+            .unwrap());
+    }
+
+    #[test]
+    fn prf_simulation_is_deterministic_per_key_and_statement() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1027);
+        let (cs, _) = square_circuit();
+        let (_, simulator) = Simulator::setup(Groth16, &cs, &mut rng).unwrap();
+        let prf = Blake3Prf::random(&mut rng);
+        let (ten, eleven) = ([Scalar::from(10u64)], [Scalar::from(11u64)]);
+
+        let proof = simulator.simulate_with_prf(&ten, &prf).unwrap();
+        assert!(Groth16
+            .verify(simulator.verifying_key(), &ten, &proof)
+            .unwrap());
+        assert_eq!(simulator.simulate_with_prf(&ten, &prf).unwrap(), proof);
+        assert_ne!(simulator.simulate_with_prf(&eleven, &prf).unwrap(), proof);
+        let other = AesPrf::random(&mut rng);
+        assert_ne!(simulator.simulate_with_prf(&ten, &other).unwrap(), proof);
+    }
+
+    #[test]
+    fn groth16_simulation_matches_honest_proofs() {
+        let mut rng = ChaCha20Rng::seed_from_u64(12);
+        let (cs, assignment) = square_circuit();
+        let (proving_key, simulator) = Simulator::setup(Groth16, &cs, &mut rng).unwrap();
+
+        let honest: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| groth16_bytes(&Groth16.prove(&proving_key, &assignment, &mut rng).unwrap()))
+            .collect();
+        let simulated: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| groth16_bytes(&simulator.simulate(&assignment.public, &mut rng).unwrap()))
+            .collect();
+        let comparison = compare_distributions(&honest, &simulated).unwrap();
+        assert!(comparison.degrees_of_freedom > 0);
+        assert!(comparison.is_consistent(MAX_Z_SCORE), "{:?}", comparison);
+    }
+
+    #[test]
+    fn ristretto_simulation_matches_honest_proofs() {
+        let mut rng = ChaCha20Rng::seed_from_u64(13);
+        let (cs, assignment) = square_circuit();
+        let (prover, simulator) = Simulator::setup(RistrettoSnark, &cs, &mut rng).unwrap();
+
+        let honest: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| {
+                RistrettoSnark
+                    .prove(&prover, &assignment, &mut rng)
+                    .unwrap()
+                    .to_bytes()
+            })
+            .collect();
+        let simulated: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| {
+                simulator
+                    .simulate(&assignment.public, &mut rng)
+                    .unwrap()
+                    .to_bytes()
+            })
+            .collect();
+        let comparison = compare_distributions(&honest, &simulated).unwrap();
+        assert!(comparison.is_consistent(MAX_Z_SCORE), "{:?}", comparison);
+    }
+
+    // A simulator drawing the discrete log of A from a small range still
+    // makes proofs that verify, but the test has to tell them apart
+    #[test]
+    fn biased_simulation_is_detected() {
+        let mut rng = ChaCha20Rng::seed_from_u64(14);
+        let (cs, assignment) = square_circuit();
+        let (proving_key, verifying_key, trapdoor) =
+            Groth16.setup_with_trapdoor(&cs, &mut rng).unwrap();
+        let ic = G1Projective::from(verifying_key.ic[0])
+            + verifying_key.ic[1] * lift_scalar(&assignment.public[0]);
+
+        let honest: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| groth16_bytes(&Groth16.prove(&proving_key, &assignment, &mut rng).unwrap()))
+            .collect();
+        let biased: Vec<Vec<u8>> = (0..SAMPLES)
+            .map(|_| {
+                let a = Fr::from(rng.gen_range(1..16u64));
+                let b = Fr::random(&mut rng);
+                let c = (G1Projective::generator() * (a * b - trapdoor.alpha * trapdoor.beta)
+                    - ic * trapdoor.gamma)
+                    * trapdoor.delta.invert().unwrap();
+                let proof = Groth16Proof {
+                    a: (G1Affine::generator() * a).into(),
+                    b: (G2Projective::generator() * b).into(),
+                    c: c.into(),
+                };
+                assert!(Groth16
+                    .verify(&verifying_key, &assignment.public, &proof)
+                    .unwrap());
+                groth16_bytes(&proof)
+            })
+            .collect();
+        let comparison = compare_distributions(&honest, &biased).unwrap();
+        assert!(!comparison.is_consistent(MAX_Z_SCORE), "{:?}", comparison);
+        assert!(comparison.max_total_variation > 0.5);
+    }
+
+    #[test]
+    fn samples_must_share_one_length() {
+        assert!(compare_distributions(&[], &[vec![0]]).is_err());
+        assert!(compare_distributions(&[vec![0]], &[vec![0, 1]]).is_err());
+        let same = compare_distributions(&[vec![7]], &[vec![7]]).unwrap();
+        assert_eq!(same.degrees_of_freedom, 0);
+        assert_eq!(same.max_total_variation, 0.0);
+    }
+}
@@ -2,19 +2,306 @@ use super::*;
 use merlin::Transcript;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::{RistrettoPoint, CompressedRistretto};
+use curve25519_dalek::traits::{Identity, IsIdentity};
 use sha3::{Sha3_512, Digest};
-use rand::rngs::OsRng;
+use subtle::{Choice, ConstantTimeEq};
+use rand::Rng;
 
+// An insecure toy scheme, kept for experiments on the protocol around it.
+// It is not sound: the verification equation involves key material only, so
+// anyone holding the verification key, which the proving key contains, can
+// balance it for any statement without a witness (see balanced_proof). Use
+// Groth16, PLONK or the FRI SNARK wherever soundness matters.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SNARKProof {
-    commitment: CompressedRistretto,
-    response: Scalar,
-    challenge: Scalar,
+    pub(crate) commitment: CompressedRistretto,
+    pub(crate) response: Scalar,
+    pub(crate) challenge: Scalar,
+    pub(crate) auxiliary_points: Vec<CompressedRistretto>,
+}
+
+// The generator, and the digest of the one verification key the setup
+// produced
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicParameters {
+    pub generator: RistrettoPoint,
+    pub key_digest: [u8; 32],
+}
+
+// Bound to its setup through the key digest of the public parameters, which
+// covers every field here, among them the digest of the transcript the key
+// came out of: the ceremony state it was contributed to, or zero for
+// snark_setup
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerificationKey {
+    pub generator: RistrettoPoint,
+    pub bases: Vec<Scalar>,
+    pub statement_scalar: Scalar,
+    pub blinding_factor: Scalar,
+    pub verification_point: RistrettoPoint,
+    pub transcript_digest: [u8; 32],
+}
+
+impl VerificationKey {
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"snark verification key");
+        hasher.update(&self.transcript_digest);
+        hasher.update(self.generator.compress().as_bytes());
+        hasher.update(&(self.bases.len() as u64).to_le_bytes());
+        for base in &self.bases {
+            hasher.update(base.as_bytes());
+        }
+        hasher.update(self.statement_scalar.as_bytes());
+        hasher.update(self.blinding_factor.as_bytes());
+        hasher.update(self.verification_point.compress().as_bytes());
+        *hasher.finalize().as_bytes()
+    }
+
+    // Refuses keys no setup could have produced, zero scalars or the
+    // identity for a point, and any key but the one `public_parameters`
+    // were made with. Ristretto points are in the prime-order group by
+    // construction, and decoding only accepts canonical encodings, so
+    // subgroup membership leaves just the identity to exclude.
+    pub fn validate(&self, public_parameters: &PublicParameters) -> Result<(), CryptoError> {
+        let scalars_ok = self
+            .bases
+            .iter()
+            .chain([&self.statement_scalar, &self.blinding_factor])
+            .all(|scalar| *scalar != Scalar::ZERO);
+        let points_ok = [self.generator, self.verification_point]
+            .iter()
+            .all(|point| !point.is_identity());
+        if self.bases.is_empty() || !scalars_ok || !points_ok {
+            return Err(CryptoError::InvalidVerificationKey);
+        }
+        if self.generator != public_parameters.generator
+            || self.digest() != public_parameters.key_digest
+        {
+            return Err(CryptoError::MismatchedSetup);
+        }
+        Ok(())
+    }
+}
+
+// The constraint system a witness has to satisfy, with the verification key
+// the proofs are made for
+#[derive(Clone, Debug)]
+pub struct ProvingKey {
+    constraint_system: ConstraintSystem,
+    verification_key: VerificationKey,
+}
+
+impl ProvingKey {
+    pub fn new(constraint_system: ConstraintSystem, verification_key: VerificationKey) -> Self {
+        Self {
+            constraint_system,
+            verification_key,
+        }
+    }
+
+    pub fn constraint_system(&self) -> &ConstraintSystem {
+        &self.constraint_system
+    }
+
+    pub fn verification_key(&self) -> &VerificationKey {
+        &self.verification_key
+    }
+}
+
+pub(crate) fn random_scalar<R: Rng + ?Sized>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill(&mut bytes[..]);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+pub(crate) fn random_nonzero_scalar<R: Rng + ?Sized>(rng: &mut R) -> Scalar {
+    loop {
+        let scalar = random_scalar(rng);
+        if scalar != Scalar::ZERO {
+            return scalar;
+        }
+    }
+}
+
+// Fresh keys for `constraint_system`, with one base per public input and
+// one for the constant
+pub fn snark_setup<R: Rng + ?Sized>(
+    constraint_system: ConstraintSystem,
+    rng: &mut R,
+) -> (PublicParameters, ProvingKey, VerificationKey) {
+    let generator = RistrettoPoint::mul_base(&random_nonzero_scalar(rng));
+    let verification_key = VerificationKey {
+        generator,
+        bases: (0..=constraint_system.num_public())
+            .map(|_| random_nonzero_scalar(rng))
+            .collect(),
+        statement_scalar: random_nonzero_scalar(rng),
+        blinding_factor: random_nonzero_scalar(rng),
+        verification_point: RistrettoPoint::mul_base(&random_nonzero_scalar(rng)),
+        transcript_digest: [0u8; 32],
+    };
+    let public_parameters = PublicParameters {
+        generator,
+        key_digest: verification_key.digest(),
+    };
+    let proving_key = ProvingKey::new(constraint_system, verification_key.clone());
+    (public_parameters, proving_key, verification_key)
+}
+
+pub struct SNARKProver {
+    public_parameters: PublicParameters,
+    proving_key: ProvingKey,
+}
+
+impl SNARKProver {
+    pub fn new(public_parameters: PublicParameters, proving_key: ProvingKey) -> Self {
+        Self {
+            public_parameters,
+            proving_key,
+        }
+    }
+
+    // Refuses witnesses that do not satisfy the constraint system, naming the
+    // first violated constraint, and keys that fail validation. The
+    // auxiliary points are blinded multiples of the generator and the
+    // response is fresh randomness, so the proof reveals nothing about the
+    // witness; the commitment is then the one point that balances the
+    // verification equation.
+    pub fn prove<R: Rng + ?Sized>(
+        &self,
+        statement: &[u8],
+        witness: &Assignment,
+        rng: &mut R,
+    ) -> Result<SNARKProof, CryptoError> {
+        let mut transcript = Transcript::new(SNARK_TRANSCRIPT_LABEL);
+        self.prove_with_transcript(&mut transcript, statement, witness, rng)
+    }
+
+    // Like prove, with the challenge drawn from `transcript`. The verifier has
+    // to start from an equal transcript.
+    pub fn prove_with_transcript<T: FiatShamirTranscript, R: Rng + ?Sized>(
+        &self,
+        transcript: &mut T,
+        statement: &[u8],
+        witness: &Assignment,
+        rng: &mut R,
+    ) -> Result<SNARKProof, CryptoError> {
+        self.proving_key.constraint_system.validate(witness)?;
+        let key = &self.proving_key.verification_key;
+        key.validate(&self.public_parameters)?;
+
+        let blinds: Vec<Scalar> = key.bases.iter().map(|_| random_scalar(rng)).collect();
+        let auxiliary: Vec<RistrettoPoint> = blinds
+            .iter()
+            .map(|blind| self.public_parameters.generator * blind)
+            .collect();
+        let auxiliary_points: Vec<CompressedRistretto> =
+            auxiliary.iter().map(|point| point.compress()).collect();
+
+        let response = random_scalar(rng);
+
+        Ok(balanced_proof(
+            transcript,
+            key,
+            statement,
+            &auxiliary,
+            auxiliary_points,
+            response,
+        ))
+    }
+}
+
+// The proof with these auxiliary points and response whose commitment is
+// the one point that balances the verification equation. It needs no
+// witness, which is what makes the scheme forgeable.
+pub(crate) fn balanced_proof<T: FiatShamirTranscript>(
+    transcript: &mut T,
+    key: &VerificationKey,
+    statement: &[u8],
+    auxiliary: &[RistrettoPoint],
     auxiliary_points: Vec<CompressedRistretto>,
+    response: Scalar,
+) -> SNARKProof {
+    let combined_point: RistrettoPoint = auxiliary
+        .iter()
+        .zip(&key.bases)
+        .map(|(point, base)| point * base)
+        .sum();
+    let commitment = (key.verification_point * key.blinding_factor.invert()
+        - hash_to_curve(statement) * key.statement_scalar
+        - combined_point * response)
+        .compress();
+    let challenge = snark_challenge_with(transcript, statement, &commitment, &auxiliary_points);
+
+    SNARKProof {
+        commitment,
+        response,
+        challenge,
+        auxiliary_points,
+    }
+}
+
+// How a verifier treats the proof. Variable time stops at the first failed
+// check and uses variable-time arithmetic, so its timing tells which check
+// failed and leaks about the proof's contents. Constant time runs every
+// check on every proof with constant-time arithmetic and comparisons, and
+// rejects malformed points instead of failing on them, so it reveals only
+// the verdict; it suits deployments where proofs are secret-adjacent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerificationMode {
+    #[default]
+    VariableTime,
+    ConstantTime,
+}
+
+impl VerificationMode {
+    // A point of the proof. Constant time substitutes the identity for one
+    // that does not decompress and clears `valid`, so the proof is only
+    // rejected at the end.
+    pub(crate) fn decompress(
+        self,
+        point: &CompressedRistretto,
+        valid: &mut Choice,
+    ) -> Result<RistrettoPoint, CryptoError> {
+        let decompressed = point.decompress();
+        match self {
+            VerificationMode::VariableTime => decompressed.ok_or(CryptoError::InvalidPoint),
+            VerificationMode::ConstantTime => {
+                *valid &= Choice::from(decompressed.is_some() as u8);
+                Ok(decompressed.unwrap_or_else(RistrettoPoint::identity))
+            }
+        }
+    }
+
+    pub(crate) fn msm(self, scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+        match self {
+            VerificationMode::VariableTime => ristretto_msm(scalars, points),
+            VerificationMode::ConstantTime => ristretto_msm_constant_time(scalars, points),
+        }
+    }
+
+    // The outcome of a check that can also fail outright. Constant time
+    // counts the failure as a rejection, so it shows only in the verdict.
+    pub(crate) fn outcome(self, result: Result<bool, CryptoError>) -> Result<Choice, CryptoError> {
+        match (self, result) {
+            (_, Ok(accepted)) => Ok(Choice::from(accepted as u8)),
+            (VerificationMode::ConstantTime, Err(_)) => Ok(Choice::from(0u8)),
+            (VerificationMode::VariableTime, Err(error)) => Err(error),
+        }
+    }
+
+    // Whether to stop now on a failed check
+    pub(crate) fn rejects_early(self, check: Choice) -> bool {
+        self == VerificationMode::VariableTime && !bool::from(check)
+    }
 }
 
+// Verifies the toy scheme above; acceptance is no evidence of a witness
 pub struct SNARKVerifier {
     public_parameters: PublicParameters,
     verification_key: VerificationKey,
+    mode: VerificationMode,
 }
 
 impl SNARKVerifier {
@@ -22,20 +309,48 @@ impl SNARKVerifier {
         Self {
             public_parameters,
             verification_key,
+            mode: VerificationMode::default(),
         }
     }
 
-    pub fn verify(&self, statement: &[u8], proof: &SNARKProof) -> Result<bool, CryptoError> {
-        let mut transcript = Transcript::new(b"snark-verification");
-        transcript.append_message(b"statement", statement);
-        transcript.append_message(b"commitment", proof.commitment.as_bytes());
+    pub fn with_mode(mut self, mode: VerificationMode) -> Self {
+        self.mode = mode;
+        self
+    }
 
-        for point in &proof.auxiliary_points {
-            transcript.append_message(b"auxiliary", point.as_bytes());
-        }
+    pub fn mode(&self) -> VerificationMode {
+        self.mode
+    }
+
+    pub(crate) fn public_parameters(&self) -> &PublicParameters {
+        &self.public_parameters
+    }
 
-        let challenge = self.derive_challenge(&mut transcript);
-        if challenge != proof.challenge {
+    pub(crate) fn verification_key(&self) -> &VerificationKey {
+        &self.verification_key
+    }
+
+    // Fails on keys that do not validate against the public parameters
+    pub fn verify(&self, statement: &[u8], proof: &SNARKProof) -> Result<bool, CryptoError> {
+        let mut transcript = Transcript::new(SNARK_TRANSCRIPT_LABEL);
+        self.verify_with_transcript(&mut transcript, statement, proof)
+    }
+
+    pub fn verify_with_transcript<T: FiatShamirTranscript>(
+        &self,
+        transcript: &mut T,
+        statement: &[u8],
+        proof: &SNARKProof,
+    ) -> Result<bool, CryptoError> {
+        self.verification_key.validate(&self.public_parameters)?;
+        let challenge = snark_challenge_with(
+            transcript,
+            statement,
+            &proof.commitment,
+            &proof.auxiliary_points,
+        );
+        let challenge_ok = challenge.ct_eq(&proof.challenge);
+        if self.mode.rejects_early(challenge_ok) {
             return Ok(false);
         }
 
@@ -46,13 +361,7 @@ impl SNARKVerifier {
             &proof.auxiliary_points,
         )?;
 
-        Ok(verification_equation)
-    }
-
-    fn derive_challenge(&self, transcript: &mut Transcript) -> Scalar {
-        let mut scalar_bytes = [0u8; 64];
-        transcript.challenge_bytes(b"challenge", &mut scalar_bytes);
-        Scalar::from_bytes_mod_order_wide(&scalar_bytes)
+        Ok((challenge_ok & verification_equation).into())
     }
 
     fn verify_proof_equation(
@@ -61,36 +370,342 @@ impl SNARKVerifier {
         commitment: &CompressedRistretto,
         response: &Scalar,
         auxiliary_points: &[CompressedRistretto],
+    ) -> Result<Choice, CryptoError> {
+        // beta C + beta s H(x) + beta r sum(b_i A_i) = V, with one auxiliary
+        // point per base
+        let key = &self.verification_key;
+        let mut valid = Choice::from((auxiliary_points.len() == key.bases.len()) as u8);
+        if self.mode.rejects_early(valid) {
+            return Ok(valid);
+        }
+        let mut scalars = vec![
+            key.blinding_factor,
+            key.blinding_factor * key.statement_scalar,
+        ];
+        let mut points = vec![
+            self.mode.decompress(commitment, &mut valid)?,
+            hash_to_curve(statement),
+        ];
+        for (point, base) in auxiliary_points.iter().zip(&key.bases) {
+            scalars.push(key.blinding_factor * response * base);
+            points.push(self.mode.decompress(point, &mut valid)?);
+        }
+        Ok(valid & self.mode.msm(&scalars, &points).ct_eq(&key.verification_point))
+    }
+
+    // Checks every (statement, proof) pair at once: the verification
+    // equations are weighted by scalars drawn from `rng` and summed, so the whole
+    // batch costs one multi-scalar multiplication. A false result does not
+    // say which proof failed.
+    pub fn verify_batch<R: Rng + ?Sized>(
+        &self,
+        batch: &[(&[u8], &SNARKProof)],
+        rng: &mut R,
     ) -> Result<bool, CryptoError> {
-        let commitment_point = commitment.decompress()
-            .ok_or(CryptoError::InvalidPoint)?;
+        let key = &self.verification_key;
+        key.validate(&self.public_parameters)?;
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+        let mut weight_sum = Scalar::ZERO;
+        let mut valid = Choice::from(1u8);
+        for (statement, proof) in batch {
+            let challenge_ok = snark_challenge(statement, &proof.commitment, &proof.auxiliary_points)
+                .ct_eq(&proof.challenge)
+                & Choice::from((proof.auxiliary_points.len() == key.bases.len()) as u8);
+            if self.mode.rejects_early(challenge_ok) {
+                return Ok(false);
+            }
+            valid &= challenge_ok;
+            // w (C + s H(x) + r sum(b_i A_i)) blinded, against w V
+            let weight = random_scalar(rng);
+            let scaled = weight * key.blinding_factor;
+            weight_sum += weight;
+            scalars.push(scaled);
+            points.push(self.mode.decompress(&proof.commitment, &mut valid)?);
+            scalars.push(scaled * key.statement_scalar);
+            points.push(hash_to_curve(statement));
+            for (point, base) in proof.auxiliary_points.iter().zip(&key.bases) {
+                scalars.push(scaled * proof.response * base);
+                points.push(self.mode.decompress(point, &mut valid)?);
+            }
+        }
+        scalars.push(-weight_sum);
+        points.push(key.verification_point);
+        let sum = self.mode.msm(&scalars, &points);
+        Ok((valid & sum.ct_eq(&RistrettoPoint::identity())).into())
+    }
+}
 
-        let mut combined_point = RistrettoPoint::identity();
-        for (point, base) in auxiliary_points.iter()
-            .zip(self.verification_key.bases.iter())
-        {
-            let aux_point = point.decompress()
-                .ok_or(CryptoError::InvalidPoint)?;
-            combined_point += aux_point * base;
+// Ristretto hash-to-group (Elligator on 64 uniform bytes) over SHA3-512
+pub fn hash_to_curve(input: &[u8]) -> RistrettoPoint {
+    let mut hasher = Sha3_512::new();
+    hasher.update(input);
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(&hasher.finalize());
+    RistrettoPoint::from_uniform_bytes(&bytes)
+}
+
+// Label of the merlin transcript prove and verify start from
+pub const SNARK_TRANSCRIPT_LABEL: &[u8] = b"snark-verification";
+
+pub fn snark_challenge(
+    statement: &[u8],
+    commitment: &CompressedRistretto,
+    auxiliary_points: &[CompressedRistretto],
+) -> Scalar {
+    let mut transcript = Transcript::new(SNARK_TRANSCRIPT_LABEL);
+    snark_challenge_with(&mut transcript, statement, commitment, auxiliary_points)
+}
+
+pub fn snark_challenge_with<T: FiatShamirTranscript>(
+    transcript: &mut T,
+    statement: &[u8],
+    commitment: &CompressedRistretto,
+    auxiliary_points: &[CompressedRistretto],
+) -> Scalar {
+    transcript.append_message(b"statement", statement);
+    transcript.append_message(b"commitment", commitment.as_bytes());
+    for point in auxiliary_points {
+        transcript.append_message(b"auxiliary", point.as_bytes());
+    }
+    transcript.challenge_scalar(b"challenge")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // x * x = y with y public
+    fn square_circuit() -> (ConstraintSystem, Variable, Variable) {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        (cs, x, y)
+    }
+
+    fn setup(rng: &mut ChaCha20Rng) -> (SNARKProver, SNARKVerifier, ConstraintSystem) {
+        let (cs, _, _) = square_circuit();
+        let (public_parameters, proving_key, verification_key) = snark_setup(cs.clone(), rng);
+        (
+            SNARKProver::new(public_parameters.clone(), proving_key),
+            SNARKVerifier::new(public_parameters, verification_key),
+            cs,
+        )
+    }
+
+    #[test]
+    fn honest_proofs_verify() {
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let (prover, verifier, cs) = setup(&mut rng);
+        let witness = cs
+            .witness_builder()
+            .with_public_inputs(&[Scalar::from(9u64)])
+            .unwrap()
+            .with_value(Variable::Private(0), Scalar::from(3u64))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        for statement in [&b"nine is a square"[..], &[], &[0u8; 256]] {
+            let proof = prover.prove(statement, &witness, &mut rng).unwrap();
+            assert!(verifier.verify(statement, &proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn proofs_do_not_transfer_or_survive_tampering() {
+        let mut rng = ChaCha20Rng::seed_from_u64(11);
+        let (prover, verifier, _) = setup(&mut rng);
+        let witness = Assignment::new(vec![Scalar::from(16u64)], vec![Scalar::from(4u64)]);
+        let proof = prover.prove(b"statement", &witness, &mut rng).unwrap();
+
+        assert!(!verifier.verify(b"other statement", &proof).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.response += Scalar::ONE;
+        assert!(!verifier.verify(b"statement", &tampered).unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.auxiliary_points.reverse();
+        assert!(!verifier.verify(b"statement", &tampered).unwrap());
+
+        // Dropped or extra auxiliary points are refused even with a matching
+        // challenge
+        for points in [1, 3] {
+            let mut tampered = proof.clone();
+            tampered.auxiliary_points.resize(points, proof.auxiliary_points[0]);
+            tampered.challenge =
+                snark_challenge(b"statement", &tampered.commitment, &tampered.auxiliary_points);
+            assert!(!verifier.verify(b"statement", &tampered).unwrap());
+            assert!(!verifier
+                .verify_batch(&[(&b"statement"[..], &tampered)], &mut rng)
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn batches_accept_only_if_every_proof_does() {
+        let mut rng = ChaCha20Rng::seed_from_u64(17);
+        let (prover, verifier, _) = setup(&mut rng);
+        let statements: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 4]).collect();
+        let proofs: Vec<SNARKProof> = statements
+            .iter()
+            .enumerate()
+            .map(|(i, statement)| {
+                let x = Scalar::from(i as u64 + 2);
+                let witness = Assignment::new(vec![x * x], vec![x]);
+                prover.prove(statement, &witness, &mut rng).unwrap()
+            })
+            .collect();
+        let mut batch: Vec<(&[u8], &SNARKProof)> = statements
+            .iter()
+            .map(|statement| statement.as_slice())
+            .zip(&proofs)
+            .collect();
+        assert!(verifier.verify_batch(&batch, &mut rng).unwrap());
+        assert!(verifier.verify_batch(&[], &mut rng).unwrap());
+
+        let mut tampered = proofs[5].clone();
+        tampered.response += Scalar::ONE;
+        batch[5].1 = &tampered;
+        assert!(!verifier.verify_batch(&batch, &mut rng).unwrap());
+
+        batch[5] = (b"other statement", &proofs[5]);
+        assert!(!verifier.verify_batch(&batch, &mut rng).unwrap());
+    }
+
+    #[test]
+    fn unsatisfying_witnesses_are_refused() {
+        let mut rng = ChaCha20Rng::seed_from_u64(13);
+        let (prover, _, _) = setup(&mut rng);
+        let witness = Assignment::new(vec![Scalar::from(10u64)], vec![Scalar::from(3u64)]);
+
+        match prover.prove(b"statement", &witness, &mut rng) {
+            Err(CryptoError::UnsatisfiedConstraint(0, name)) => assert_eq!(name, "square"),
+            other => panic!("expected an unsatisfied constraint, got {:?}", other.map(|_| ())),
         }
+    }
+
+    #[test]
+    fn proofs_bind_to_their_transcript() {
+        let mut rng = ChaCha20Rng::seed_from_u64(15);
+        let (prover, verifier, _) = setup(&mut rng);
+        let witness = Assignment::new(vec![Scalar::from(9u64)], vec![Scalar::from(3u64)]);
+
+        let mut transcript = RecordingTranscript::<Sha3Transcript>::new(b"snark-test");
+        let proof = prover
+            .prove_with_transcript(&mut transcript, b"statement", &witness, &mut rng)
+            .unwrap();
+        assert_eq!(transcript.num_queries(), 1);
+        let mut transcript = Sha3Transcript::new(b"snark-test");
+        assert!(verifier
+            .verify_with_transcript(&mut transcript, b"statement", &proof)
+            .unwrap());
+        let mut transcript = Sha3Transcript::new(b"other");
+        assert!(!verifier
+            .verify_with_transcript(&mut transcript, b"statement", &proof)
+            .unwrap());
+        assert!(!verifier.verify(b"statement", &proof).unwrap());
 
-        let statement_point = self.hash_to_curve(statement)?;
-        let verification_point = (commitment_point + 
-            (statement_point * self.verification_key.statement_scalar) +
-            (combined_point * response)) * self.verification_key.blinding_factor;
+        let proof = prover.prove(b"statement", &witness, &mut rng).unwrap();
+        let mut transcript = Transcript::new(SNARK_TRANSCRIPT_LABEL);
+        assert!(verifier
+            .verify_with_transcript(&mut transcript, b"statement", &proof)
+            .unwrap());
+    }
+
+    #[test]
+    fn constant_time_mode_agrees_with_variable_time() {
+        let mut rng = ChaCha20Rng::seed_from_u64(19);
+        let (cs, _, _) = square_circuit();
+        let (public_parameters, proving_key, verification_key) = snark_setup(cs, &mut rng);
+        let prover = SNARKProver::new(public_parameters.clone(), proving_key);
+        let variable_time =
+            SNARKVerifier::new(public_parameters.clone(), verification_key.clone());
+        let constant_time = SNARKVerifier::new(public_parameters, verification_key)
+            .with_mode(VerificationMode::ConstantTime);
+        assert_eq!(variable_time.mode(), VerificationMode::VariableTime);
+
+        let witness = Assignment::new(vec![Scalar::from(9u64)], vec![Scalar::from(3u64)]);
+        let proof = prover.prove(b"statement", &witness, &mut rng).unwrap();
+        let mut wrong_challenge = proof.clone();
+        wrong_challenge.challenge += Scalar::ONE;
+        let mut wrong_response = proof.clone();
+        wrong_response.response += Scalar::ONE;
+        for verifier in [&variable_time, &constant_time] {
+            assert!(verifier.verify(b"statement", &proof).unwrap());
+            assert!(!verifier.verify(b"other statement", &proof).unwrap());
+            assert!(!verifier.verify(b"statement", &wrong_challenge).unwrap());
+            assert!(!verifier.verify(b"statement", &wrong_response).unwrap());
+
+            let batch: [(&[u8], &SNARKProof); 2] =
+                [(b"statement", &proof), (b"statement", &proof)];
+            assert!(verifier.verify_batch(&batch, &mut rng).unwrap());
+            let batch: [(&[u8], &SNARKProof); 2] =
+                [(b"statement", &proof), (b"statement", &wrong_challenge)];
+            assert!(!verifier.verify_batch(&batch, &mut rng).unwrap());
+        }
 
-        Ok(verification_point == self.verification_key.verification_point)
+        // Malformed points are rejected in constant time rather than refused
+        let mut malformed = proof.clone();
+        malformed.commitment = CompressedRistretto([0xff; 32]);
+        malformed.challenge =
+            snark_challenge(b"statement", &malformed.commitment, &malformed.auxiliary_points);
+        assert!(matches!(
+            variable_time.verify(b"statement", &malformed),
+            Err(CryptoError::InvalidPoint)
+        ));
+        assert!(!constant_time.verify(b"statement", &malformed).unwrap());
+        let batch: [(&[u8], &SNARKProof); 1] = [(b"statement", &malformed)];
+        assert!(!constant_time.verify_batch(&batch, &mut rng).unwrap());
     }
 
-    fn hash_to_curve(&self, input: &[u8]) -> Result<RistrettoPoint, CryptoError> {
-        let mut hasher = Sha3_512::new();
-        hasher.update(input);
-        let hash = hasher.finalize();
-        
-        let point = CompressedRistretto::from_slice(&hash[..32])
-            .decompress()
-            .ok_or(CryptoError::InvalidPoint)?;
-            
-        Ok(point)
+    #[test]
+    fn keys_from_other_setups_are_refused() {
+        let mut rng = ChaCha20Rng::seed_from_u64(14);
+        let (cs, _, _) = square_circuit();
+        let (public_parameters, _, verification_key) = snark_setup(cs.clone(), &mut rng);
+        let (other_parameters, other_proving_key, other_key) = snark_setup(cs, &mut rng);
+        assert!(verification_key.validate(&public_parameters).is_ok());
+        assert!(matches!(
+            verification_key.validate(&other_parameters),
+            Err(CryptoError::MismatchedSetup)
+        ));
+
+        let witness = Assignment::new(vec![Scalar::from(9u64)], vec![Scalar::from(3u64)]);
+        let prover = SNARKProver::new(other_parameters, other_proving_key);
+        let proof = prover.prove(b"statement", &witness, &mut rng).unwrap();
+        let verifier = SNARKVerifier::new(public_parameters.clone(), other_key);
+        assert!(matches!(
+            verifier.verify(b"statement", &proof),
+            Err(CryptoError::MismatchedSetup)
+        ));
+
+        let mut zeroed = verification_key.clone();
+        zeroed.bases[0] = Scalar::ZERO;
+        assert!(matches!(
+            zeroed.validate(&public_parameters),
+            Err(CryptoError::InvalidVerificationKey)
+        ));
+
+        // Another key under the same generator is refused too
+        let mut substituted = verification_key.clone();
+        substituted.bases[0] += Scalar::ONE;
+        assert!(matches!(
+            substituted.validate(&public_parameters),
+            Err(CryptoError::MismatchedSetup)
+        ));
+
+        let bytes = verification_key.to_bytes();
+        assert_eq!(VerificationKey::from_bytes(&bytes).unwrap(), verification_key);
+        let mut future = bytes.clone();
+        future[2] = VERIFICATION_KEY_VERSION + 1;
+        assert!(matches!(
+            VerificationKey::from_bytes(&future),
+            Err(CryptoError::UnsupportedKeyVersion(_))
+        ));
     }
 }
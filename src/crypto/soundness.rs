@@ -0,0 +1,721 @@
+use super::*;
+use bls12_381::{G1Affine, G2Affine, Scalar as Fr};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::seq::index;
+use rand::{Rng, RngCore};
+
+// A way of corrupting a valid proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    // One bit of an element's canonical encoding. Most flips of a curve
+    // point leave no valid encoding and are rejected when decoding.
+    BitFlip { element: String, bit: usize },
+    // Two elements of the same kind exchanged
+    Swap(String, String),
+    // An element replayed from another valid proof: a stale challenge,
+    // commitment, evaluation or opening
+    Stale(String),
+    // The unmodified proof against other public inputs
+    WrongStatement,
+    // A proof built without a witness, by a known attack on the scheme
+    Forged(String),
+}
+
+// Which bits of each element get flipped: a fresh uniform sample of
+// `flips_per_element` positions per element, or every position
+pub struct FlipSampler<'a> {
+    flips_per_element: usize,
+    rng: &'a mut dyn RngCore,
+}
+
+impl<'a> FlipSampler<'a> {
+    pub fn new(rng: &'a mut dyn RngCore) -> Self {
+        Self {
+            flips_per_element: 16,
+            rng,
+        }
+    }
+
+    pub fn with_flips_per_element(mut self, flips_per_element: usize) -> Self {
+        self.flips_per_element = flips_per_element;
+        self
+    }
+
+    pub fn exhaustive(self) -> Self {
+        self.with_flips_per_element(usize::MAX)
+    }
+
+    fn bits(&mut self, bits: usize) -> Vec<usize> {
+        if self.flips_per_element >= bits {
+            return (0..bits).collect();
+        }
+        let mut sample = index::sample(self.rng, bits, self.flips_per_element).into_vec();
+        sample.sort_unstable();
+        sample
+    }
+
+    fn index(&mut self, len: usize) -> usize {
+        self.rng.gen_range(0..len)
+    }
+}
+
+// Proofs that can list corrupted copies of themselves, None for those no
+// decoder would accept. `other` is a valid proof of another statement under
+// the same key, the source of stale elements.
+pub trait MutableProof: Sized {
+    fn mutations(&self, other: &Self, sampler: &mut FlipSampler) -> Vec<(Mutation, Option<Self>)>;
+}
+
+// Proof systems with known witness-free attacks, each a named proof of
+// `public_inputs`. A sound system rejects them all; those without a known
+// attack list none.
+pub trait Forgeable: ProofSystem {
+    fn forgeries<R: Rng + ?Sized>(
+        &self,
+        _verifying_key: &Self::VerifyingKey,
+        _public_inputs: &[Scalar],
+        _rng: &mut R,
+    ) -> Vec<(String, Self::Proof)> {
+        Vec::new()
+    }
+}
+
+// The mutations a verifier was given and those it accepted
+#[derive(Clone, Debug, Default)]
+pub struct SoundnessReport {
+    pub checked: usize,
+    pub accepted: Vec<Mutation>,
+}
+
+impl SoundnessReport {
+    pub fn is_sound(&self) -> bool {
+        self.accepted.is_empty()
+    }
+}
+
+// Runs the verifier on every mutation of `proof`, a proof of
+// `public_inputs`, on the system's forgeries of `public_inputs`, and on
+// `proof` against `other_inputs`, of which `other_proof` is a proof. Both
+// proofs have to verify first. A verifier error counts as a rejection, as
+// does a mutant that does not decode, and mutations that leave the proof
+// unchanged are skipped.
+pub fn check_soundness<P>(
+    system: &P,
+    verifying_key: &P::VerifyingKey,
+    public_inputs: &[Scalar],
+    proof: &P::Proof,
+    other_inputs: &[Scalar],
+    other_proof: &P::Proof,
+    sampler: &mut FlipSampler,
+) -> Result<SoundnessReport, CryptoError>
+where
+    P: Forgeable,
+    P::Proof: MutableProof + PartialEq + Clone,
+{
+    if public_inputs == other_inputs {
+        return Err(CryptoError::IndistinctStatements);
+    }
+    for (index, (inputs, proof)) in [(public_inputs, proof), (other_inputs, other_proof)]
+        .into_iter()
+        .enumerate()
+    {
+        if !system.verify(verifying_key, inputs, proof)? {
+            return Err(CryptoError::InvalidProof(index));
+        }
+    }
+
+    let accepts = |inputs: &[Scalar], mutant: &P::Proof| {
+        system
+            .verify(verifying_key, inputs, mutant)
+            .unwrap_or(false)
+    };
+    let forgeries = system
+        .forgeries(verifying_key, public_inputs, &mut *sampler.rng)
+        .into_iter()
+        .map(|(name, forgery)| (Mutation::Forged(name), public_inputs, Some(forgery)));
+    let mut report = SoundnessReport::default();
+    let cases = proof
+        .mutations(other_proof, sampler)
+        .into_iter()
+        .filter(|(_, mutant)| mutant.as_ref() != Some(proof))
+        .map(|(mutation, mutant)| (mutation, public_inputs, mutant))
+        .chain([(Mutation::WrongStatement, other_inputs, Some(proof.clone()))])
+        .chain(forgeries);
+    for (mutation, inputs, mutant) in cases {
+        report.checked += 1;
+        if mutant.is_some_and(|mutant| accepts(inputs, &mutant)) {
+            report.accepted.push(mutation);
+        }
+    }
+    Ok(report)
+}
+
+// Proof elements by their canonical encoding. `decode` fails on bytes that
+// encode no element.
+trait Element: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Element for G1Affine {
+    fn encode(&self) -> Vec<u8> {
+        self.to_compressed().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Option::from(G1Affine::from_compressed(bytes.try_into().ok()?))
+    }
+}
+
+impl Element for G2Affine {
+    fn encode(&self) -> Vec<u8> {
+        self.to_compressed().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Option::from(G2Affine::from_compressed(bytes.try_into().ok()?))
+    }
+}
+
+impl Element for Fr {
+    fn encode(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Option::from(Fr::from_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl Element for Scalar {
+    fn encode(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Option::from(Scalar::from_canonical_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl Element for CompressedRistretto {
+    fn encode(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let point = CompressedRistretto::from_slice(bytes).ok()?;
+        point.decompress().map(|_| point)
+    }
+}
+
+// A Merkle root or path node
+impl Element for [u8; 32] {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok()
+    }
+}
+
+// Mutated copies of one proof
+struct Mutants<'s, 'a, T> {
+    original: T,
+    mutants: Vec<(Mutation, Option<T>)>,
+    sampler: &'s mut FlipSampler<'a>,
+}
+
+impl<'s, 'a, T: Clone> Mutants<'s, 'a, T> {
+    fn new(original: &T, sampler: &'s mut FlipSampler<'a>) -> Self {
+        Self {
+            original: original.clone(),
+            mutants: Vec::new(),
+            sampler,
+        }
+    }
+
+    fn push(&mut self, mutation: Mutation, mutate: impl FnOnce(&mut T)) {
+        let mut mutant = self.original.clone();
+        mutate(&mut mutant);
+        self.mutants.push((mutation, Some(mutant)));
+    }
+
+    // One mutant per sampled bit of `value`, each decoded and written into
+    // its copy by `set`
+    fn flip<V: Element>(&mut self, element: &str, value: &V, set: impl Fn(&mut T, V)) {
+        let bytes = value.encode();
+        for bit in self.sampler.bits(8 * bytes.len()) {
+            let mut flipped = bytes.clone();
+            flipped[bit / 8] ^= 1 << (bit % 8);
+            let mutation = Mutation::BitFlip {
+                element: element.to_string(),
+                bit,
+            };
+            match V::decode(&flipped) {
+                Some(value) => self.push(mutation, |mutant| set(mutant, value)),
+                None => self.mutants.push((mutation, None)),
+            }
+        }
+    }
+
+    fn swap(&mut self, first: &str, second: &str, mutate: impl FnOnce(&mut T)) {
+        self.push(Mutation::Swap(first.into(), second.into()), mutate);
+    }
+
+    fn stale(&mut self, element: &str, mutate: impl FnOnce(&mut T)) {
+        self.push(Mutation::Stale(element.into()), mutate);
+    }
+}
+
+impl MutableProof for SNARKProof {
+    fn mutations(&self, other: &Self, sampler: &mut FlipSampler) -> Vec<(Mutation, Option<Self>)> {
+        let mut m = Mutants::new(self, sampler);
+        m.flip("commitment", &self.commitment, |p, v| p.commitment = v);
+        m.flip("response", &self.response, |p, v| p.response = v);
+        m.flip("challenge", &self.challenge, |p, v| p.challenge = v);
+        for (i, point) in self.auxiliary_points.iter().enumerate() {
+            let name = format!("auxiliary[{}]", i);
+            m.flip(&name, point, |p, v| p.auxiliary_points[i] = v);
+            m.swap("commitment", &name, |p| {
+                std::mem::swap(&mut p.commitment, &mut p.auxiliary_points[i])
+            });
+            if i + 1 < self.auxiliary_points.len() {
+                m.swap(&name, &format!("auxiliary[{}]", i + 1), |p| {
+                    p.auxiliary_points.swap(i, i + 1)
+                });
+            }
+        }
+        m.swap("response", "challenge", |p| {
+            std::mem::swap(&mut p.response, &mut p.challenge)
+        });
+        m.stale("challenge", |p| p.challenge = other.challenge);
+        m.stale("response", |p| p.response = other.response);
+        m.stale("commitment", |p| p.commitment = other.commitment);
+        m.stale("auxiliary", |p| {
+            p.auxiliary_points = other.auxiliary_points.clone()
+        });
+        m.mutants
+    }
+}
+
+impl MutableProof for Groth16Proof {
+    fn mutations(&self, other: &Self, sampler: &mut FlipSampler) -> Vec<(Mutation, Option<Self>)> {
+        let mut m = Mutants::new(self, sampler);
+        m.flip("a", &self.a, |p, v| p.a = v);
+        m.flip("b", &self.b, |p, v| p.b = v);
+        m.flip("c", &self.c, |p, v| p.c = v);
+        m.swap("a", "c", |p| std::mem::swap(&mut p.a, &mut p.c));
+        m.stale("a", |p| p.a = other.a);
+        m.stale("b", |p| p.b = other.b);
+        m.stale("c", |p| p.c = other.c);
+        m.mutants
+    }
+}
+
+impl MutableProof for PlonkProof {
+    fn mutations(&self, other: &Self, sampler: &mut FlipSampler) -> Vec<(Mutation, Option<Self>)> {
+        let mut m = Mutants::new(self, sampler);
+        for i in 0..3 {
+            let name = format!("wires[{}]", i);
+            m.flip(&name, &self.wires[i], |p, v| p.wires[i] = v);
+            if i < 2 {
+                m.swap(&name, &format!("wires[{}]", i + 1), |p| {
+                    p.wires.swap(i, i + 1)
+                });
+            }
+        }
+        m.flip("z", &self.z, |p, v| p.z = v);
+        m.flip("t", &self.t, |p, v| p.t = v);
+        for (i, x) in self.evaluations.at_zeta.iter().enumerate() {
+            m.flip(&format!("at_zeta[{}]", i), x, |p, v| {
+                p.evaluations.at_zeta[i] = v
+            });
+        }
+        m.flip("z_shifted", &self.evaluations.z_shifted, |p, v| {
+            p.evaluations.z_shifted = v
+        });
+        m.flip("opening", &self.opening, |p, v| p.opening = v);
+        m.flip("shifted_opening", &self.shifted_opening, |p, v| {
+            p.shifted_opening = v
+        });
+        m.swap("z", "t", |p| std::mem::swap(&mut p.z, &mut p.t));
+        m.swap("opening", "shifted_opening", |p| {
+            std::mem::swap(&mut p.opening, &mut p.shifted_opening)
+        });
+
+        if let Some(lookup) = &self.lookup {
+            for i in 0..2 {
+                m.flip(
+                    &format!("lookup.sorted[{}]", i),
+                    &lookup.sorted[i],
+                    |p, v| p.lookup.as_mut().unwrap().sorted[i] = v,
+                );
+            }
+            m.flip("lookup.z", &lookup.z, |p, v| {
+                p.lookup.as_mut().unwrap().z = v
+            });
+            for (i, x) in lookup.evaluations.iter().enumerate() {
+                m.flip(&format!("lookup.evaluations[{}]", i), x, |p, v| {
+                    p.lookup.as_mut().unwrap().evaluations[i] = v
+                });
+            }
+            m.swap("lookup.sorted[0]", "lookup.sorted[1]", |p| {
+                p.lookup.as_mut().unwrap().sorted.swap(0, 1)
+            });
+        }
+        if let Some(custom) = &self.custom {
+            for (i, x) in custom.selectors.iter().enumerate() {
+                m.flip(&format!("custom.selectors[{}]", i), x, |p, v| {
+                    p.custom.as_mut().unwrap().selectors[i] = v
+                });
+            }
+            for (r, wires) in custom.wires.iter().enumerate() {
+                for (i, x) in wires.iter().enumerate() {
+                    m.flip(&format!("custom.wires[{}][{}]", r, i), x, |p, v| {
+                        p.custom.as_mut().unwrap().wires[r][i] = v
+                    });
+                }
+            }
+            for (i, point) in custom.openings.iter().enumerate() {
+                m.flip(&format!("custom.openings[{}]", i), point, |p, v| {
+                    p.custom.as_mut().unwrap().openings[i] = v
+                });
+            }
+        }
+
+        m.stale("wires", |p| p.wires = other.wires);
+        m.stale("z", |p| p.z = other.z);
+        m.stale("t", |p| p.t = other.t);
+        m.stale("evaluations", |p| p.evaluations = other.evaluations.clone());
+        m.stale("opening", |p| p.opening = other.opening);
+        m.stale("shifted_opening", |p| {
+            p.shifted_opening = other.shifted_opening
+        });
+        m.stale("lookup", |p| p.lookup = other.lookup.clone());
+        m.stale("custom", |p| p.custom = other.custom.clone());
+        m.mutants
+    }
+}
+
+impl MutableProof for FriSnarkProof {
+    fn mutations(&self, other: &Self, sampler: &mut FlipSampler) -> Vec<(Mutation, Option<Self>)> {
+        let mut m = Mutants::new(self, sampler);
+        for (i, commitment) in self.commitments.iter().enumerate() {
+            let name = format!("commitments[{}]", i);
+            m.flip(&name, &commitment.root, |p, v| p.commitments[i].root = v);
+            if i + 1 < self.commitments.len() {
+                m.swap(&name, &format!("commitments[{}]", i + 1), |p| {
+                    p.commitments.swap(i, i + 1)
+                });
+            }
+        }
+        for (i, x) in self.evaluations.iter().enumerate() {
+            let name = format!("evaluations[{}]", i);
+            m.flip(&name, x, |p, v| p.evaluations[i] = v);
+            if i + 1 < self.evaluations.len() {
+                m.swap(&name, &format!("evaluations[{}]", i + 1), |p| {
+                    p.evaluations.swap(i, i + 1)
+                });
+            }
+        }
+        // Every root and final value, and in one sampled query one sampled
+        // layer's decommitment, so each opening is tampered with inside
+        for (i, opening) in self.openings.iter().enumerate() {
+            for (l, root) in opening.layer_roots.iter().enumerate() {
+                m.flip(
+                    &format!("openings[{}].layer_roots[{}]", i, l),
+                    root,
+                    |p, v| p.openings[i].layer_roots[l] = v,
+                );
+            }
+            m.flip(
+                &format!("openings[{}].final_value", i),
+                &opening.final_value,
+                |p, v| p.openings[i].final_value = v,
+            );
+            if opening.queries.is_empty() {
+                continue;
+            }
+            let q = m.sampler.index(opening.queries.len());
+            let query = &opening.queries[q];
+            if query.is_empty() {
+                continue;
+            }
+            let l = m.sampler.index(query.len());
+            let name = format!("openings[{}].queries[{}][{}]", i, q, l);
+            for (k, x) in query[l].values.iter().enumerate() {
+                m.flip(&format!("{}.values[{}]", name, k), x, |p, v| {
+                    p.openings[i].queries[q][l].values[k] = v
+                });
+            }
+            for (k, node) in query[l].path.iter().enumerate() {
+                m.flip(&format!("{}.path[{}]", name, k), node, |p, v| {
+                    p.openings[i].queries[q][l].path[k] = v
+                });
+            }
+            m.swap(
+                &format!("{}.values[0]", name),
+                &format!("{}.values[1]", name),
+                |p| p.openings[i].queries[q][l].values.swap(0, 1),
+            );
+        }
+        for i in 1..self.openings.len() {
+            m.swap(
+                &format!("openings[{}]", i - 1),
+                &format!("openings[{}]", i),
+                |p| p.openings.swap(i - 1, i),
+            );
+        }
+        m.stale("commitments", |p| p.commitments = other.commitments);
+        m.stale("evaluations", |p| p.evaluations = other.evaluations);
+        m.stale("openings", |p| p.openings = other.openings.clone());
+        m.mutants
+    }
+}
+
+// The commitment that balances the verification equation for random
+// auxiliary points and response; the verification key is all it takes
+impl Forgeable for RistrettoSnark {
+    fn forgeries<R: Rng + ?Sized>(
+        &self,
+        verifier: &SNARKVerifier,
+        public_inputs: &[Scalar],
+        rng: &mut R,
+    ) -> Vec<(String, SNARKProof)> {
+        let key = verifier.verification_key();
+        let generator = verifier.public_parameters().generator;
+        let auxiliary: Vec<RistrettoPoint> = key
+            .bases
+            .iter()
+            .map(|_| generator * random_scalar(rng))
+            .collect();
+        let auxiliary_points = auxiliary.iter().map(|point| point.compress()).collect();
+        let proof = balanced_proof(
+            &mut Transcript::new(SNARK_TRANSCRIPT_LABEL),
+            key,
+            &encode_public_inputs(public_inputs),
+            &auxiliary,
+            auxiliary_points,
+            random_scalar(rng),
+        );
+        vec![("balanced".into(), proof)]
+    }
+}
+
+// A = alpha, B = beta and C = 0 satisfy the pairing check whenever the
+// public inputs' commitment vanishes
+impl Forgeable for Groth16 {
+    fn forgeries<R: Rng + ?Sized>(
+        &self,
+        verifying_key: &Groth16VerifyingKey,
+        _public_inputs: &[Scalar],
+        _rng: &mut R,
+    ) -> Vec<(String, Groth16Proof)> {
+        let proof = Groth16Proof {
+            a: verifying_key.alpha_g1,
+            b: verifying_key.beta_g2,
+            c: G1Affine::identity(),
+        };
+        vec![("alpha-beta".into(), proof)]
+    }
+}
+
+impl Forgeable for Plonk {}
+
+impl Forgeable for FriSnark {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // y = x^3 + x + 5 with y public, x^2 also through a custom gate, and
+    // optionally x range checked by lookups
+    fn circuit(with_lookups: bool) -> (ConstraintSystem, Variable) {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        let x_lc: LinearCombination = x.into();
+        let x_squared = multiply(&mut cs, &x_lc, &x_lc);
+        let x_cubed = multiply(&mut cs, &x_squared, &x_lc);
+        cs.enforce_equal(x_cubed + x_lc.clone() + Scalar::from(5u64), y);
+        let square = CustomGate::new(
+            "square",
+            2,
+            vec![(Scalar::ONE, vec![2, 0]), (-Scalar::ONE, vec![0, 1])],
+        )
+        .unwrap();
+        let square = cs.add_gate(square).unwrap();
+        let z = cs.alloc_private();
+        cs.enforce_gate(square, vec![x_lc.clone(), z.into()])
+            .unwrap();
+        if with_lookups {
+            range_check_lookup(&mut cs, &x_lc, 4, 2).unwrap();
+        }
+        (cs, x)
+    }
+
+    // Two proofs, of x = 3 and x = 4, and the mutations the verifier accepts
+    fn run<P>(system: P, with_lookups: bool, flips_per_element: usize) -> SoundnessReport
+    where
+        P: Forgeable,
+        P::Proof: MutableProof + PartialEq + Clone,
+    {
+        let mut rng = ChaCha20Rng::seed_from_u64(1107);
+        let (cs, x) = circuit(with_lookups);
+        let (proving_key, verifying_key) = system.setup(&cs, &mut rng).unwrap();
+        let mut prove = |x_value: u64| {
+            let y = Scalar::from(x_value * x_value * x_value + x_value + 5);
+            let assignment = cs
+                .witness_builder()
+                .with_public_inputs(&[y])
+                .unwrap()
+                .with_value(x, Scalar::from(x_value))
+                .unwrap()
+                .build()
+                .unwrap();
+            let proof = system.prove(&proving_key, &assignment, &mut rng).unwrap();
+            (assignment.public, proof)
+        };
+        let (inputs, proof) = prove(3);
+        let (other_inputs, other_proof) = prove(4);
+        let report = check_soundness(
+            &system,
+            &verifying_key,
+            &inputs,
+            &proof,
+            &other_inputs,
+            &other_proof,
+            &mut FlipSampler::new(&mut rng).with_flips_per_element(flips_per_element),
+        )
+        .unwrap();
+        assert!(report.checked > 10);
+        report
+    }
+
+    // The toy scheme rejects every corruption of an honest proof but not
+    // the forgery
+    #[test]
+    fn ristretto_accepts_only_the_balanced_forgery() {
+        let report = run(RistrettoSnark, true, usize::MAX);
+        assert_eq!(report.accepted, vec![Mutation::Forged("balanced".into())]);
+    }
+
+    #[test]
+    fn groth16_rejects_every_mutation() {
+        let report = run(Groth16, false, usize::MAX);
+        assert!(report.is_sound(), "{:?}", report.accepted);
+    }
+
+    #[test]
+    fn plonk_rejects_every_mutation() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1107);
+        let report = run(Plonk::new(PlonkSrs::new(400, &mut rng)), true, 16);
+        assert!(report.is_sound(), "{:?}", report.accepted);
+    }
+
+    #[test]
+    fn fri_snark_rejects_every_mutation() {
+        let report = run(FriSnark::default().with_queries(20), false, 8);
+        assert!(report.is_sound(), "{:?}", report.accepted);
+    }
+
+    // Groth16 with a verifier that ignores the proof's C
+    #[derive(Clone, Copy, Debug)]
+    struct IgnoresC;
+
+    impl ProofSystem for IgnoresC {
+        type ProvingKey = Groth16ProvingKey;
+        type VerifyingKey = Groth16VerifyingKey;
+        type Proof = Groth16Proof;
+
+        fn name(&self) -> &'static str {
+            "ignores-c"
+        }
+
+        fn setup<R: rand::Rng + ?Sized>(
+            &self,
+            cs: &ConstraintSystem,
+            rng: &mut R,
+        ) -> Result<(Groth16ProvingKey, Groth16VerifyingKey), CryptoError> {
+            Groth16.setup(cs, rng)
+        }
+
+        fn prove<R: rand::Rng + ?Sized>(
+            &self,
+            proving_key: &Groth16ProvingKey,
+            assignment: &Assignment,
+            rng: &mut R,
+        ) -> Result<Groth16Proof, CryptoError> {
+            Groth16.prove(proving_key, assignment, rng)
+        }
+
+        fn verify(
+            &self,
+            _verifying_key: &Groth16VerifyingKey,
+            _public_inputs: &[Scalar],
+            proof: &Groth16Proof,
+        ) -> Result<bool, CryptoError> {
+            Ok(proof.a != G1Affine::identity())
+        }
+    }
+
+    impl Forgeable for IgnoresC {}
+
+    #[test]
+    fn accepted_mutations_are_reported() {
+        let report = run(IgnoresC, false, 16);
+        assert!(report.accepted.contains(&Mutation::WrongStatement));
+        assert!(report.accepted.contains(&Mutation::Stale("c".into())));
+        assert!(report
+            .accepted
+            .contains(&Mutation::Swap("a".into(), "c".into())));
+    }
+
+    #[test]
+    fn statements_must_differ_and_proofs_verify() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1107);
+        let (cs, x) = circuit(false);
+        let (proving_key, verifying_key) = Groth16.setup(&cs, &mut rng).unwrap();
+        let assignment = cs
+            .witness_builder()
+            .with_public_inputs(&[Scalar::from(35u64)])
+            .unwrap()
+            .with_value(x, Scalar::from(3u64))
+            .unwrap()
+            .build()
+            .unwrap();
+        let proof = Groth16.prove(&proving_key, &assignment, &mut rng).unwrap();
+        let inputs = &assignment.public;
+        let mut sampler = FlipSampler::new(&mut rng);
+        assert!(matches!(
+            check_soundness(
+                &Groth16,
+                &verifying_key,
+                inputs,
+                &proof,
+                inputs,
+                &proof,
+                &mut sampler
+            ),
+            Err(CryptoError::IndistinctStatements)
+        ));
+        let other = [Scalar::from(36u64)];
+        assert!(matches!(
+            check_soundness(
+                &Groth16,
+                &verifying_key,
+                inputs,
+                &proof,
+                &other,
+                &proof,
+                &mut sampler
+            ),
+            Err(CryptoError::InvalidProof(1))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+// The random oracle behind a Fiat-Shamir proof: labelled messages in,
+// labelled challenges out, each challenge depending on everything absorbed
+// before it. Provers and verifiers are generic over it, so an analysis can
+// plug in a transcript that records or reprograms the oracle's queries.
+pub trait FiatShamirTranscript {
+    fn new(label: &'static [u8]) -> Self
+    where
+        Self: Sized;
+
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]);
+
+    // 64 bytes reduced modulo the group order, so the bias is negligible
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        let mut bytes = [0u8; 64];
+        self.challenge_bytes(label, &mut bytes);
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+}
+
+impl FiatShamirTranscript for Transcript {
+    fn new(label: &'static [u8]) -> Self {
+        Transcript::new(label)
+    }
+
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        Transcript::append_message(self, label, message)
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        Transcript::challenge_bytes(self, label, dest)
+    }
+}
+
+// A transcript on SHAKE256 alone. Labels and messages are absorbed with
+// their lengths, so no two sequences of calls share an input. A challenge
+// is squeezed from a copy of the state and then absorbed itself, so later
+// challenges depend on it.
+#[derive(Clone)]
+pub struct Sha3Transcript {
+    state: Shake256,
+}
+
+impl Sha3Transcript {
+    fn absorb(&mut self, label: &[u8], bytes: &[u8]) {
+        for part in [label, bytes] {
+            self.state.update(&(part.len() as u64).to_le_bytes());
+            self.state.update(part);
+        }
+    }
+}
+
+impl FiatShamirTranscript for Sha3Transcript {
+    fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            state: Shake256::default(),
+        };
+        transcript.absorb(b"dom-sep", label);
+        transcript
+    }
+
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.absorb(label, message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.absorb(label, &(dest.len() as u64).to_le_bytes());
+        self.state.clone().finalize_xof().read(dest);
+        self.absorb(b"challenge", dest);
+    }
+}
+
+// One call on a recorded transcript
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    Message {
+        label: &'static [u8],
+        message: Vec<u8>,
+    },
+    Challenge {
+        label: &'static [u8],
+        output: Vec<u8>,
+    },
+}
+
+// Passes every call through to `inner` and logs it, so the oracle queries a
+// proof makes can be counted and replayed
+#[derive(Clone, Debug)]
+pub struct RecordingTranscript<T> {
+    inner: T,
+    events: Vec<TranscriptEvent>,
+}
+
+impl<T> RecordingTranscript<T> {
+    pub fn wrap(inner: T) -> Self {
+        Self {
+            inner,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn events(&self) -> &[TranscriptEvent] {
+        &self.events
+    }
+
+    // Challenges drawn so far, i.e. queries to the random oracle
+    pub fn num_queries(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, TranscriptEvent::Challenge { .. }))
+            .count()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: FiatShamirTranscript> FiatShamirTranscript for RecordingTranscript<T> {
+    fn new(label: &'static [u8]) -> Self {
+        Self::wrap(T::new(label))
+    }
+
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.inner.append_message(label, message);
+        self.events.push(TranscriptEvent::Message {
+            label,
+            message: message.to_vec(),
+        });
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.inner.challenge_bytes(label, dest);
+        self.events.push(TranscriptEvent::Challenge {
+            label,
+            output: dest.to_vec(),
+        });
+    }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckPolicy {
+    // Any failing sub-check rejects the proof
+    HardFail,
+    // Failures are recorded but the proof is still accepted
+    SoftFail,
+    // Accept if at least `required` of the group's sub-checks pass
+    Threshold { required: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct QuantumCheckResult {
+    pub group: String,
+    pub passed: bool,
+}
+
+impl QuantumCheckResult {
+    pub fn new(group: &str, passed: bool) -> Self {
+        Self {
+            group: group.to_string(),
+            passed,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckVerdict {
+    Passed,
+    SoftFailed,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GroupVerdict {
+    pub group: String,
+    pub policy: CheckPolicy,
+    pub passed: usize,
+    pub total: usize,
+    pub verdict: CheckVerdict,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub accepted: bool,
+    pub policy: VerificationPolicy,
+    pub groups: Vec<GroupVerdict>,
+}
+
+impl VerificationReport {
+    pub fn soft_failures(&self) -> usize {
+        self.groups
+            .iter()
+            .filter(|g| g.verdict == CheckVerdict::SoftFailed)
+            .count()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerificationPolicy {
+    default: CheckPolicy,
+    rules: BTreeMap<String, CheckPolicy>,
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+impl VerificationPolicy {
+    pub fn strict() -> Self {
+        Self {
+            default: CheckPolicy::HardFail,
+            rules: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_default(mut self, policy: CheckPolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    pub fn with_rule(mut self, group: &str, policy: CheckPolicy) -> Self {
+        self.rules.insert(group.to_string(), policy);
+        self
+    }
+
+    pub fn policy_for(&self, group: &str) -> CheckPolicy {
+        self.rules.get(group).copied().unwrap_or(self.default)
+    }
+
+    pub fn evaluate(&self, checks: &[QuantumCheckResult]) -> VerificationReport {
+        let mut tallies: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for check in checks {
+            let tally = tallies.entry(check.group.as_str()).or_insert((0, 0));
+            tally.1 += 1;
+            if check.passed {
+                tally.0 += 1;
+            }
+        }
+
+        let groups: Vec<GroupVerdict> = tallies
+            .into_iter()
+            .map(|(group, (passed, total))| {
+                let policy = self.policy_for(group);
+                let verdict = match policy {
+                    _ if passed == total => CheckVerdict::Passed,
+                    CheckPolicy::HardFail => CheckVerdict::Failed,
+                    CheckPolicy::SoftFail => CheckVerdict::SoftFailed,
+                    CheckPolicy::Threshold { required } if passed >= required => {
+                        CheckVerdict::SoftFailed
+                    }
+                    CheckPolicy::Threshold { .. } => CheckVerdict::Failed,
+                };
+                GroupVerdict {
+                    group: group.to_string(),
+                    policy,
+                    passed,
+                    total,
+                    verdict,
+                }
+            })
+            .collect();
+
+        VerificationReport {
+            accepted: groups.iter().all(|g| g.verdict != CheckVerdict::Failed),
+            policy: self.clone(),
+            groups,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use super::*;
+use curve25519_dalek::scalar::Scalar;
+
+// Completes an assignment for a constraint system from the values the
+// program supplies. Every other variable has to be fixed by a hint, by a
+// constraint in which it is the only unknown and appears linearly, e.g. the
+// output c of a * b = c, or by a lookup in which it is the only unknown and
+// the known inputs select one value for it, e.g. the output of an S-box
+// table; gadgets lay out their intermediates that way.
+#[derive(Clone, Debug)]
+pub struct WitnessBuilder<'a> {
+    cs: &'a ConstraintSystem,
+    public: Vec<Option<Scalar>>,
+    private: Vec<Option<Scalar>>,
+}
+
+impl<'a> WitnessBuilder<'a> {
+    pub fn new(cs: &'a ConstraintSystem) -> Self {
+        Self {
+            cs,
+            public: vec![None; cs.num_public()],
+            private: vec![None; cs.num_private()],
+        }
+    }
+
+    pub fn set(&mut self, variable: Variable, value: Scalar) -> Result<(), CryptoError> {
+        let slot = match variable {
+            Variable::One => None,
+            Variable::Public(i) => self.public.get_mut(i),
+            Variable::Private(i) => self.private.get_mut(i),
+        }
+        .ok_or(CryptoError::InvalidVariable(variable))?;
+        *slot = Some(value);
+        Ok(())
+    }
+
+    // All public inputs, in allocation order
+    pub fn with_public_inputs(mut self, values: &[Scalar]) -> Result<Self, CryptoError> {
+        if values.len() != self.public.len() {
+            return Err(CryptoError::AssignmentLengthMismatch);
+        }
+        self.public = values.iter().copied().map(Some).collect();
+        Ok(self)
+    }
+
+    pub fn with_value(mut self, variable: Variable, value: Scalar) -> Result<Self, CryptoError> {
+        self.set(variable, value)?;
+        Ok(self)
+    }
+
+    pub fn value(&self, variable: Variable) -> Option<Scalar> {
+        match variable {
+            Variable::One => Some(Scalar::ONE),
+            Variable::Public(i) => self.public.get(i).copied().flatten(),
+            Variable::Private(i) => self.private.get(i).copied().flatten(),
+        }
+    }
+
+    // Unknowns are left at zero
+    fn assignment(&self) -> Assignment {
+        let fill = |values: &[Option<Scalar>]| {
+            values
+                .iter()
+                .map(|v| v.unwrap_or(Scalar::ZERO))
+                .collect::<Vec<_>>()
+        };
+        Assignment::new(fill(&self.public), fill(&self.private))
+    }
+
+    fn is_known(&self, lc: &LinearCombination) -> bool {
+        lc.terms().iter().all(|(v, _)| self.value(*v).is_some())
+    }
+
+    // Fills an unknown hinted variable once its hint's inputs are known
+    fn resolve_hint(&mut self, variable: Variable) -> bool {
+        let Variable::Private(i) = variable else {
+            return false;
+        };
+        let Some(hint) = self.cs.hint(i) else {
+            return false;
+        };
+        if !hint.sources().iter().all(|source| self.is_known(source)) {
+            return false;
+        }
+        let sources: Vec<Scalar> = hint
+            .sources()
+            .iter()
+            .map(|source| self.split(source, None).0)
+            .collect();
+        self.private[i] = Some(hint.apply(&sources));
+        true
+    }
+
+    // (alpha, beta) with lc = alpha + beta * unknown
+    fn split(&self, lc: &LinearCombination, unknown: Option<Variable>) -> (Scalar, Scalar) {
+        lc.terms().iter().fold(
+            (Scalar::ZERO, Scalar::ZERO),
+            |(alpha, beta), (v, c)| match self.value(*v) {
+                Some(value) => (alpha + value * c, beta),
+                None if Some(*v) == unknown => (alpha, beta + c),
+                None => (alpha, beta),
+            },
+        )
+    }
+
+    // Solves `constraint` for its one unknown, if it has exactly one and the
+    // constraint is linear in it with a nonzero coefficient
+    fn solve(&mut self, constraint: &Constraint) -> Result<bool, CryptoError> {
+        let mut unknown = None;
+        for lc in [&constraint.a, &constraint.b, &constraint.c] {
+            for (v, _) in lc.terms() {
+                if self.value(*v).is_some() || self.resolve_hint(*v) {
+                    continue;
+                }
+                match unknown {
+                    None => unknown = Some(*v),
+                    Some(u) if u == *v => {}
+                    Some(_) => return Ok(false),
+                }
+            }
+        }
+        let Some(unknown) = unknown else {
+            return Ok(false);
+        };
+        let (a0, a1) = self.split(&constraint.a, Some(unknown));
+        let (b0, b1) = self.split(&constraint.b, Some(unknown));
+        let (c0, c1) = self.split(&constraint.c, Some(unknown));
+        if a1 * b1 != Scalar::ZERO {
+            return Ok(false);
+        }
+        let linear = a0 * b1 + a1 * b0 - c1;
+        if linear == Scalar::ZERO {
+            return Ok(false);
+        }
+        let value = (c0 - a0 * b0) * linear.invert();
+        self.set(unknown, value)?;
+        Ok(true)
+    }
+
+    // Solves `lookup` for its one unknown, if it has exactly one, it appears
+    // in a single input, and the table rows matching the known inputs agree
+    // on that input
+    fn solve_lookup(&mut self, lookup: &Lookup) -> Result<bool, CryptoError> {
+        let mut unknown = None;
+        for (column, input) in lookup.inputs.iter().enumerate() {
+            for (v, _) in input.terms() {
+                if self.value(*v).is_some() || self.resolve_hint(*v) {
+                    continue;
+                }
+                match unknown {
+                    None => unknown = Some((column, *v)),
+                    Some(u) if u == (column, *v) => {}
+                    Some(_) => return Ok(false),
+                }
+            }
+        }
+        let Some((column, unknown)) = unknown else {
+            return Ok(false);
+        };
+        let (alpha, beta) = self.split(&lookup.inputs[column], Some(unknown));
+        if beta == Scalar::ZERO {
+            return Ok(false);
+        }
+        let known: Vec<Option<Scalar>> = lookup
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| (i != column).then(|| self.split(input, None).0))
+            .collect();
+        let table = self
+            .cs
+            .table(lookup.table)
+            .ok_or(CryptoError::InvalidLookupTable)?;
+        let mut candidates = table.rows().iter().filter(|row| {
+            row.iter()
+                .zip(&known)
+                .all(|(value, known)| known.is_none_or(|k| k == *value))
+        });
+        let Some(row) = candidates.next() else {
+            return Ok(false);
+        };
+        if candidates.any(|other| other[column] != row[column]) {
+            return Ok(false);
+        }
+        self.set(unknown, (row[column] - alpha) * beta.invert())?;
+        Ok(true)
+    }
+
+    // Solves for every remaining variable, then checks the whole system
+    pub fn build(mut self) -> Result<Assignment, CryptoError> {
+        let cs = self.cs;
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for constraint in cs.constraints() {
+                progress |= self.solve(constraint)?;
+            }
+            for lookup in cs.lookups() {
+                progress |= self.solve_lookup(lookup)?;
+            }
+            for i in 0..self.private.len() {
+                if self.private[i].is_none() {
+                    progress |= self.resolve_hint(Variable::Private(i));
+                }
+            }
+        }
+
+        let public = (0..self.public.len()).map(Variable::Public);
+        let private = (0..self.private.len()).map(Variable::Private);
+        if let Some(variable) = public.chain(private).find(|v| self.value(*v).is_none()) {
+            return Err(CryptoError::UnassignedVariable(variable));
+        }
+        let assignment = self.assignment();
+        cs.validate(&assignment)?;
+        Ok(assignment)
+    }
+}
+
+impl ConstraintSystem {
+    // Like is_satisfied, but names the first violated constraint, or failing
+    // that the first failed lookup
+    pub fn validate(&self, assignment: &Assignment) -> Result<(), CryptoError> {
+        if let Some(index) = self.first_unsatisfied(assignment)? {
+            return Err(CryptoError::UnsatisfiedConstraint(
+                index,
+                self.constraints()[index].name.clone(),
+            ));
+        }
+        match self.first_failed_lookup(assignment)? {
+            Some(index) => Err(CryptoError::LookupFailed(
+                index,
+                self.lookups()[index].name.clone(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn witness_builder(&self) -> WitnessBuilder<'_> {
+        WitnessBuilder::new(self)
+    }
+}
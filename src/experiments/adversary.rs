@@ -0,0 +1,368 @@
+use super::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryBounds {
+    pub classical_bytes: Option<usize>,
+    pub quantum_qubits: Option<usize>,
+}
+
+impl MemoryBounds {
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    pub fn new(classical_bytes: usize, quantum_qubits: usize) -> Self {
+        Self {
+            classical_bytes: Some(classical_bytes),
+            quantum_qubits: Some(quantum_qubits),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundEnforcement {
+    Abort,
+    Record,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MemoryViolation {
+    Classical { requested: usize, limit: usize },
+    Quantum { requested: usize, limit: usize },
+}
+
+pub struct AdversaryMemory {
+    bounds: MemoryBounds,
+    enforcement: BoundEnforcement,
+    classical: HashMap<String, Vec<u8>>,
+    classical_used: usize,
+    registers: Vec<Option<QuantumState>>,
+    qubits_used: usize,
+    peak_classical: usize,
+    peak_qubits: usize,
+    violations: Vec<MemoryViolation>,
+}
+
+impl AdversaryMemory {
+    pub fn new(bounds: MemoryBounds, enforcement: BoundEnforcement) -> Self {
+        Self {
+            bounds,
+            enforcement,
+            classical: HashMap::new(),
+            classical_used: 0,
+            registers: Vec::new(),
+            qubits_used: 0,
+            peak_classical: 0,
+            peak_qubits: 0,
+            violations: Vec::new(),
+        }
+    }
+
+    pub fn store(&mut self, key: &str, value: Vec<u8>) -> Result<(), ExperimentError> {
+        let previous = self.classical.get(key).map_or(0, |v| v.len());
+        let requested = self.classical_used - previous + value.len();
+        self.check_classical(requested)?;
+
+        self.classical_used = requested;
+        self.peak_classical = self.peak_classical.max(requested);
+        self.classical.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    pub fn load(&self, key: &str) -> Option<&[u8]> {
+        self.classical.get(key).map(|v| v.as_slice())
+    }
+
+    pub fn forget(&mut self, key: &str) {
+        if let Some(value) = self.classical.remove(key) {
+            self.classical_used -= value.len();
+        }
+    }
+
+    pub fn allocate_register(&mut self, num_qubits: usize) -> Result<usize, ExperimentError> {
+        let requested = self.qubits_used + num_qubits;
+        self.check_quantum(requested)?;
+        let register = QuantumState::new(num_qubits)
+            .map_err(|_| ExperimentError::StateTooLarge(num_qubits))?;
+
+        self.qubits_used = requested;
+        self.peak_qubits = self.peak_qubits.max(requested);
+        self.registers.push(Some(register));
+        Ok(self.registers.len() - 1)
+    }
+
+    pub fn register_mut(&mut self, handle: usize) -> Result<&mut QuantumState, ExperimentError> {
+        self.registers
+            .get_mut(handle)
+            .and_then(|r| r.as_mut())
+            .ok_or(ExperimentError::UnknownRegister(handle))
+    }
+
+    pub fn release_register(&mut self, handle: usize) -> Result<QuantumState, ExperimentError> {
+        let state = self.registers
+            .get_mut(handle)
+            .and_then(|r| r.take())
+            .ok_or(ExperimentError::UnknownRegister(handle))?;
+        self.qubits_used -= state.num_qubits;
+        Ok(state)
+    }
+
+    // Bounded-storage model: after the transmission phase the adversary must fit
+    // everything it kept into the (usually smaller) storage bounds. Only the
+    // harness moves between phases, so an adversary cannot lift its own bounds.
+    pub(crate) fn enter_storage_phase(
+        &mut self,
+        bounds: MemoryBounds,
+    ) -> Result<(), ExperimentError> {
+        self.bounds = bounds;
+        self.check_classical(self.classical_used)?;
+        self.check_quantum(self.qubits_used)
+    }
+
+    pub fn classical_used(&self) -> usize {
+        self.classical_used
+    }
+
+    pub fn qubits_used(&self) -> usize {
+        self.qubits_used
+    }
+
+    pub fn violations(&self) -> &[MemoryViolation] {
+        &self.violations
+    }
+
+    fn check_classical(&mut self, requested: usize) -> Result<(), ExperimentError> {
+        match self.bounds.classical_bytes {
+            Some(limit) if requested > limit => {
+                self.violations.push(MemoryViolation::Classical { requested, limit });
+                match self.enforcement {
+                    BoundEnforcement::Abort => {
+                        Err(ExperimentError::ClassicalMemoryExceeded { requested, limit })
+                    }
+                    BoundEnforcement::Record => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_quantum(&mut self, requested: usize) -> Result<(), ExperimentError> {
+        match self.bounds.quantum_qubits {
+            Some(limit) if requested > limit => {
+                self.violations.push(MemoryViolation::Quantum { requested, limit });
+                match self.enforcement {
+                    BoundEnforcement::Abort => {
+                        Err(ExperimentError::QuantumMemoryExceeded { requested, limit })
+                    }
+                    BoundEnforcement::Record => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Adversaries are stateless step functions: the harness owns the only memory
+// they have and hands it to each phase, so whatever an adversary keeps from
+// watching the protocol to answering has gone through the bounds. Hiding
+// state behind interior mutability defeats the harness and is not allowed.
+pub trait Adversary {
+    fn name(&self) -> &str;
+
+    // Transmission phase: every message on the wire is in view, under the
+    // harness's transmission bounds
+    fn observe(
+        &self,
+        transcript: &ProtocolTranscript,
+        memory: &mut AdversaryMemory,
+    ) -> Result<(), ExperimentError>;
+
+    // Storage phase: answers from what it kept, under the storage bounds
+    fn answer(&self, memory: &mut AdversaryMemory) -> Result<Vec<u8>, ExperimentError>;
+}
+
+pub struct AdversaryRun {
+    pub output: Result<Vec<u8>, ExperimentError>,
+    pub peak_classical_bytes: usize,
+    pub peak_qubits: usize,
+    pub violations: Vec<MemoryViolation>,
+}
+
+impl AdversaryRun {
+    pub fn within_bounds(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+pub struct AdversaryHarness {
+    bounds: MemoryBounds,
+    storage_bounds: Option<MemoryBounds>,
+    enforcement: BoundEnforcement,
+}
+
+impl AdversaryHarness {
+    pub fn new(bounds: MemoryBounds) -> Self {
+        Self {
+            bounds,
+            storage_bounds: None,
+            enforcement: BoundEnforcement::Abort,
+        }
+    }
+
+    pub fn with_enforcement(mut self, enforcement: BoundEnforcement) -> Self {
+        self.enforcement = enforcement;
+        self
+    }
+
+    // Bounds for the storage phase; without them the transmission bounds
+    // stay in force
+    pub fn with_storage_bounds(mut self, bounds: MemoryBounds) -> Self {
+        self.storage_bounds = Some(bounds);
+        self
+    }
+
+    pub fn bounds(&self) -> MemoryBounds {
+        self.bounds
+    }
+
+    pub fn storage_bounds(&self) -> MemoryBounds {
+        self.storage_bounds.unwrap_or(self.bounds)
+    }
+
+    pub fn run<A: Adversary + ?Sized>(
+        &self,
+        adversary: &A,
+        crs: &[u8],
+        statement: &[u8],
+    ) -> AdversaryRun {
+        self.run_on_transcript(
+            adversary,
+            &ProtocolTranscript::new(crs.to_vec(), statement.to_vec()),
+        )
+    }
+
+    pub fn run_on_transcript<A: Adversary + ?Sized>(
+        &self,
+        adversary: &A,
+        transcript: &ProtocolTranscript,
+    ) -> AdversaryRun {
+        let mut memory = AdversaryMemory::new(self.bounds, self.enforcement);
+        let output = adversary
+            .observe(transcript, &mut memory)
+            .and_then(|()| match self.storage_bounds {
+                Some(bounds) => memory.enter_storage_phase(bounds),
+                None => Ok(()),
+            })
+            .and_then(|()| adversary.answer(&mut memory));
+        AdversaryRun {
+            output,
+            peak_classical_bytes: memory.peak_classical,
+            peak_qubits: memory.peak_qubits,
+            violations: memory.violations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keeps the statement and a register as wide as the statement is long,
+    // and answers with the statement
+    struct Echo;
+
+    impl Adversary for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn observe(
+            &self,
+            transcript: &ProtocolTranscript,
+            memory: &mut AdversaryMemory,
+        ) -> Result<(), ExperimentError> {
+            memory.allocate_register(transcript.statement.len())?;
+            memory.store("statement", transcript.statement.clone())
+        }
+
+        fn answer(&self, memory: &mut AdversaryMemory) -> Result<Vec<u8>, ExperimentError> {
+            memory
+                .load("statement")
+                .map(<[u8]>::to_vec)
+                .ok_or(ExperimentError::AdversaryAborted(
+                    "nothing kept".to_string(),
+                ))
+        }
+    }
+
+    #[test]
+    fn runs_within_bounds() {
+        let harness = AdversaryHarness::new(MemoryBounds::new(8, 4));
+        let run = harness.run(&Echo, b"crs", b"four");
+        assert_eq!(run.output.as_deref().unwrap(), b"four");
+        assert!(run.within_bounds());
+        assert_eq!((run.peak_classical_bytes, run.peak_qubits), (4, 4));
+
+        let run = AdversaryHarness::new(MemoryBounds::unbounded()).run(&Echo, b"", b"");
+        assert_eq!(run.output.unwrap(), b"");
+    }
+
+    #[test]
+    fn aborts_or_records_violations() {
+        let run = AdversaryHarness::new(MemoryBounds::new(3, 8)).run(&Echo, b"", b"four");
+        assert!(matches!(
+            run.output,
+            Err(ExperimentError::ClassicalMemoryExceeded {
+                requested: 4,
+                limit: 3
+            })
+        ));
+
+        let run = AdversaryHarness::new(MemoryBounds::new(8, 3)).run(&Echo, b"", b"four");
+        assert!(matches!(
+            run.output,
+            Err(ExperimentError::QuantumMemoryExceeded {
+                requested: 4,
+                limit: 3
+            })
+        ));
+
+        let run = AdversaryHarness::new(MemoryBounds::new(3, 3))
+            .with_enforcement(BoundEnforcement::Record)
+            .run(&Echo, b"", b"four");
+        assert_eq!(run.output.as_deref().unwrap(), b"four");
+        assert_eq!(
+            run.violations,
+            vec![
+                MemoryViolation::Quantum {
+                    requested: 4,
+                    limit: 3
+                },
+                MemoryViolation::Classical {
+                    requested: 4,
+                    limit: 3
+                },
+            ]
+        );
+    }
+
+    // What was kept during transmission has to fit the storage bounds
+    #[test]
+    fn enforces_storage_bounds() {
+        let harness = AdversaryHarness::new(MemoryBounds::new(8, 8))
+            .with_storage_bounds(MemoryBounds::new(8, 2));
+        let run = harness.run(&Echo, b"", b"four");
+        assert!(matches!(
+            run.output,
+            Err(ExperimentError::QuantumMemoryExceeded {
+                requested: 4,
+                limit: 2
+            })
+        ));
+        assert_eq!(run.peak_qubits, 4);
+
+        let run = harness.run(&Echo, b"", b"ab");
+        assert_eq!(run.output.unwrap(), b"ab");
+    }
+}
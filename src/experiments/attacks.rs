@@ -0,0 +1,449 @@
+use super::*;
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct ObservedProof {
+    pub statement: Vec<u8>,
+    pub commitment: [u8; 32],
+    pub challenge: Scalar,
+    pub response: Scalar,
+}
+
+// Sigma-protocol view of a prover: response = nonce + challenge * witness
+pub trait ProverOracle {
+    fn prove(
+        &mut self,
+        statement: &[u8],
+        witness: &Scalar,
+    ) -> Result<ObservedProof, ExperimentError>;
+}
+
+// Tag a statement is proven under, so every statement gets its own proof
+fn statement_tag(statement: &[u8]) -> Scalar {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"prover-oracle-statement");
+    hasher.update(statement);
+    let mut bytes = [0u8; 64];
+    hasher.finalize_xof().fill(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// A proof system as a prover oracle: each statement is a proof of knowledge
+// of x with x * x = y, for public y and the statement's tag, drawing its
+// randomness from `rng`, which is what the attacks probe. Groth16 lifts the
+// circuit out of the Ristretto scalar field, so its witnesses have to square
+// without wrapping around.
+pub struct ProofSystemOracle<P: ProofSystem, R> {
+    system: P,
+    proving_key: P::ProvingKey,
+    rng: R,
+}
+
+impl<P: ProofSystem, R: Rng> ProofSystemOracle<P, R> {
+    pub fn new<S: Rng + ?Sized>(
+        system: P,
+        setup_rng: &mut S,
+        rng: R,
+    ) -> Result<Self, ExperimentError> {
+        let mut cs = ConstraintSystem::new();
+        cs.alloc_public();
+        let y = cs.alloc_public();
+        let x = cs.alloc_private();
+        cs.enforce_named("square", x, x, y);
+        let (proving_key, _) = system.setup(&cs, setup_rng)?;
+        Ok(Self {
+            system,
+            proving_key,
+            rng,
+        })
+    }
+
+    fn prove_square(
+        &mut self,
+        statement: &[u8],
+        witness: &Scalar,
+    ) -> Result<P::Proof, ExperimentError> {
+        let assignment = Assignment::new(
+            vec![statement_tag(statement), witness * witness],
+            vec![*witness],
+        );
+        Ok(self
+            .system
+            .prove(&self.proving_key, &assignment, &mut self.rng)?)
+    }
+}
+
+impl<R: Rng> ProverOracle for ProofSystemOracle<RistrettoSnark, R> {
+    fn prove(
+        &mut self,
+        statement: &[u8],
+        witness: &Scalar,
+    ) -> Result<ObservedProof, ExperimentError> {
+        let proof = self.prove_square(statement, witness)?;
+        Ok(ObservedProof {
+            statement: statement.to_vec(),
+            commitment: proof.commitment.to_bytes(),
+            challenge: proof.challenge,
+            response: proof.response,
+        })
+    }
+}
+
+// Groth16 proofs have no challenge or response, so only the attacks on
+// commitments apply: the commitment is the low 256 bits of A's x-coordinate
+impl<R: Rng> ProverOracle for ProofSystemOracle<Groth16, R> {
+    fn prove(
+        &mut self,
+        statement: &[u8],
+        witness: &Scalar,
+    ) -> Result<ObservedProof, ExperimentError> {
+        let proof = self.prove_square(statement, witness)?;
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&proof.a.to_compressed()[16..]);
+        Ok(ObservedProof {
+            statement: statement.to_vec(),
+            commitment,
+            challenge: Scalar::ZERO,
+            response: Scalar::ZERO,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Clone, Debug)]
+pub struct AttackFinding {
+    pub attack: String,
+    pub severity: Severity,
+    pub description: String,
+    pub witness_recovered: bool,
+}
+
+pub trait Attack {
+    fn name(&self) -> &str;
+
+    fn run(
+        &self,
+        prover: &mut dyn ProverOracle,
+        witness: &Scalar,
+    ) -> Result<Vec<AttackFinding>, ExperimentError>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AttackReport {
+    pub attacks_run: Vec<String>,
+    pub findings: Vec<AttackFinding>,
+}
+
+impl AttackReport {
+    pub fn is_clean(&self) -> bool {
+        self.findings.iter().all(|f| f.severity == Severity::Info)
+    }
+
+    pub fn worst_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+}
+
+#[derive(Default)]
+pub struct AttackSuite {
+    attacks: Vec<Box<dyn Attack>>,
+}
+
+impl AttackSuite {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_witness_reuse_attacks(statements: usize) -> Self {
+        let mut suite = Self::new();
+        suite.register(Box::new(NonceReuseAttack { statements }));
+        suite.register(Box::new(AffineNonceAttack { statements }));
+        suite.register(Box::new(StatementCorrelationAttack { statements }));
+        suite
+    }
+
+    pub fn register(&mut self, attack: Box<dyn Attack>) {
+        self.attacks.push(attack);
+    }
+
+    pub fn run(
+        &self,
+        prover: &mut dyn ProverOracle,
+        witness: &Scalar,
+    ) -> Result<AttackReport, ExperimentError> {
+        let mut report = AttackReport::default();
+        for attack in &self.attacks {
+            report.attacks_run.push(attack.name().to_string());
+            report.findings.extend(attack.run(prover, witness)?);
+        }
+        Ok(report)
+    }
+}
+
+fn correlated_statements(count: usize) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|i| {
+            let mut statement = b"correlated-statement".to_vec();
+            statement.extend_from_slice(&(i as u64).to_le_bytes());
+            statement
+        })
+        .collect()
+}
+
+fn collect_proofs(
+    prover: &mut dyn ProverOracle,
+    witness: &Scalar,
+    statements: usize,
+) -> Result<Vec<ObservedProof>, ExperimentError> {
+    correlated_statements(statements)
+        .iter()
+        .map(|statement| prover.prove(statement, witness))
+        .collect()
+}
+
+// Same commitment under two different challenges reveals the witness
+pub struct NonceReuseAttack {
+    pub statements: usize,
+}
+
+impl Attack for NonceReuseAttack {
+    fn name(&self) -> &str {
+        "nonce-reuse"
+    }
+
+    fn run(
+        &self,
+        prover: &mut dyn ProverOracle,
+        witness: &Scalar,
+    ) -> Result<Vec<AttackFinding>, ExperimentError> {
+        let proofs = collect_proofs(prover, witness, self.statements)?;
+        let mut seen: HashMap<[u8; 32], &ObservedProof> = HashMap::new();
+        let mut findings = Vec::new();
+
+        for proof in &proofs {
+            if let Some(previous) = seen.get(&proof.commitment) {
+                let challenge_difference = proof.challenge - previous.challenge;
+                if challenge_difference == Scalar::ZERO {
+                    continue;
+                }
+                let candidate =
+                    (proof.response - previous.response) * challenge_difference.invert();
+                findings.push(AttackFinding {
+                    attack: self.name().to_string(),
+                    severity: Severity::Critical,
+                    description: "prover reused a commitment nonce across statements".to_string(),
+                    witness_recovered: candidate == *witness,
+                });
+                break;
+            }
+            seen.insert(proof.commitment, proof);
+        }
+
+        Ok(findings)
+    }
+}
+
+// Nonces advancing by a fixed offset cancel out in second differences
+pub struct AffineNonceAttack {
+    pub statements: usize,
+}
+
+impl Attack for AffineNonceAttack {
+    fn name(&self) -> &str {
+        "affine-nonce"
+    }
+
+    fn run(
+        &self,
+        prover: &mut dyn ProverOracle,
+        witness: &Scalar,
+    ) -> Result<Vec<AttackFinding>, ExperimentError> {
+        let proofs = collect_proofs(prover, witness, self.statements.max(3))?;
+
+        for window in proofs.windows(3) {
+            let response_curvature =
+                window[2].response - window[1].response - (window[1].response - window[0].response);
+            let challenge_curvature = window[2].challenge
+                - window[1].challenge
+                - (window[1].challenge - window[0].challenge);
+            if challenge_curvature == Scalar::ZERO {
+                continue;
+            }
+
+            let candidate = response_curvature * challenge_curvature.invert();
+            if candidate == *witness {
+                return Ok(vec![AttackFinding {
+                    attack: self.name().to_string(),
+                    severity: Severity::Critical,
+                    description: "prover nonces follow an affine progression".to_string(),
+                    witness_recovered: true,
+                }]);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+}
+
+// Canonical Ristretto encodings fix their lowest and highest bits to zero,
+// leaving 254 that vary
+const COMMITMENT_VARYING_BITS: f64 = 254.0;
+
+// Commitments for related statements should look independent: the mean
+// Hamming distance between consecutive commitments should be close to half
+// the bits that vary
+pub struct StatementCorrelationAttack {
+    pub statements: usize,
+}
+
+impl Attack for StatementCorrelationAttack {
+    fn name(&self) -> &str {
+        "statement-correlation"
+    }
+
+    fn run(
+        &self,
+        prover: &mut dyn ProverOracle,
+        witness: &Scalar,
+    ) -> Result<Vec<AttackFinding>, ExperimentError> {
+        let proofs = collect_proofs(prover, witness, self.statements.max(2))?;
+        let distances: Vec<f64> = proofs
+            .windows(2)
+            .map(|pair| {
+                pair[0]
+                    .commitment
+                    .iter()
+                    .zip(pair[1].commitment.iter())
+                    .map(|(a, b)| (a ^ b).count_ones())
+                    .sum::<u32>() as f64
+            })
+            .collect();
+
+        let mean = distances.iter().sum::<f64>() / distances.len() as f64;
+        // Binomial(254, 1/2); allow four standard errors
+        let expected = COMMITMENT_VARYING_BITS / 2.0;
+        let standard_deviation = COMMITMENT_VARYING_BITS.sqrt() / 2.0;
+        let tolerance = 4.0 * standard_deviation / (distances.len() as f64).sqrt();
+        let deviation = (mean - expected).abs();
+
+        let severity = if deviation > tolerance {
+            Severity::Warning
+        } else {
+            Severity::Info
+        };
+
+        Ok(vec![AttackFinding {
+            attack: self.name().to_string(),
+            severity,
+            description: format!(
+                "mean commitment Hamming distance {:.2} bits (expected {} +/- {:.2})",
+                mean, expected, tolerance
+            ),
+            witness_recovered: false,
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    enum Nonces {
+        Fresh,
+        Reused,
+        Affine,
+    }
+
+    // Schnorr proofs of knowledge of the witness, with nonces hashed from a
+    // counter, reused, or advancing by a fixed step
+    struct Schnorr {
+        nonces: Nonces,
+        proofs: u64,
+    }
+
+    impl ProverOracle for Schnorr {
+        fn prove(
+            &mut self,
+            statement: &[u8],
+            witness: &Scalar,
+        ) -> Result<ObservedProof, ExperimentError> {
+            let nonce = match self.nonces {
+                Nonces::Fresh => statement_tag(&self.proofs.to_le_bytes()),
+                Nonces::Reused => Scalar::from(7u64),
+                Nonces::Affine => Scalar::from(7 + 3 * self.proofs),
+            };
+            self.proofs += 1;
+            let commitment = (RISTRETTO_BASEPOINT_POINT * nonce).compress().to_bytes();
+            let challenge = statement_tag(&[statement, &commitment].concat());
+            Ok(ObservedProof {
+                statement: statement.to_vec(),
+                commitment,
+                challenge,
+                response: nonce + challenge * witness,
+            })
+        }
+    }
+
+    fn run(nonces: Nonces) -> AttackReport {
+        let mut prover = Schnorr { nonces, proofs: 0 };
+        AttackSuite::with_witness_reuse_attacks(16)
+            .run(&mut prover, &Scalar::from(1024u64))
+            .unwrap()
+    }
+
+    fn recovered(report: &AttackReport, attack: &str) -> bool {
+        report
+            .findings
+            .iter()
+            .any(|f| f.attack == attack && f.witness_recovered)
+    }
+
+    #[test]
+    fn recovers_witnesses_from_bad_nonces() {
+        let report = run(Nonces::Reused);
+        assert!(recovered(&report, "nonce-reuse"));
+        assert_eq!(report.worst_severity(), Some(Severity::Critical));
+
+        let report = run(Nonces::Affine);
+        assert!(!recovered(&report, "nonce-reuse"));
+        assert!(recovered(&report, "affine-nonce"));
+
+        let report = run(Nonces::Fresh);
+        assert!(report.is_clean());
+        assert_eq!(
+            report.attacks_run,
+            ["nonce-reuse", "affine-nonce", "statement-correlation"]
+        );
+    }
+
+    #[test]
+    fn proof_systems_pass_the_suite() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1024);
+        let suite = AttackSuite::with_witness_reuse_attacks(16);
+        let witness = Scalar::from(1024u64);
+
+        let mut ristretto =
+            ProofSystemOracle::new(RistrettoSnark, &mut rng, ChaCha20Rng::seed_from_u64(1))
+                .unwrap();
+        let report = suite.run(&mut ristretto, &witness).unwrap();
+        assert!(report.is_clean(), "{:?}", report.findings);
+        assert_eq!(report.findings.len(), 1);
+
+        let mut groth16 =
+            ProofSystemOracle::new(Groth16, &mut rng, ChaCha20Rng::seed_from_u64(2)).unwrap();
+        let report = suite.run(&mut groth16, &witness).unwrap();
+        assert!(report.is_clean(), "{:?}", report.findings);
+        assert!(report.findings.iter().all(|f| !f.witness_recovered));
+    }
+}
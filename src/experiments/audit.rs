@@ -0,0 +1,266 @@
+use super::*;
+use crate::test_vectors::{from_hex, to_hex};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AUDIT_DOMAIN: &[u8] = b"quantum-nizk-audit-v1";
+const GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditKind {
+    Parameters,
+    Seed,
+    Action,
+    Result,
+}
+
+impl AuditKind {
+    fn tag(self) -> &'static str {
+        match self {
+            AuditKind::Parameters => "parameters",
+            AuditKind::Seed => "seed",
+            AuditKind::Action => "action",
+            AuditKind::Result => "result",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "parameters" => Some(AuditKind::Parameters),
+            "seed" => Some(AuditKind::Seed),
+            "action" => Some(AuditKind::Action),
+            "result" => Some(AuditKind::Result),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub index: u64,
+    pub timestamp: u64,
+    pub kind: AuditKind,
+    pub label: String,
+    pub payload: Vec<u8>,
+    pub previous: [u8; 32],
+    pub hash: [u8; 32],
+}
+
+impl AuditEntry {
+    // Every field except `hash` is bound, each length-prefixed so that no two
+    // distinct entries share an encoding
+    fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(AUDIT_DOMAIN);
+        hasher.update(&self.index.to_le_bytes());
+        hasher.update(&self.timestamp.to_le_bytes());
+        for field in [
+            self.kind.tag().as_bytes(),
+            self.label.as_bytes(),
+            &self.payload,
+        ] {
+            hasher.update(&(field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+        hasher.update(&self.previous);
+        *hasher.finalize().as_bytes()
+    }
+
+    // One line per entry: index, timestamp, kind, then hex label, payload, previous and hash
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.index,
+            self.timestamp,
+            self.kind.tag(),
+            to_hex(self.label.as_bytes()),
+            to_hex(&self.payload),
+            to_hex(&self.previous),
+            to_hex(&self.hash)
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+        let digest = |hex: &str| from_hex(hex).and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+        Some(Self {
+            index: fields[0].parse().ok()?,
+            timestamp: fields[1].parse().ok()?,
+            kind: AuditKind::from_tag(fields[2])?,
+            label: String::from_utf8(from_hex(fields[3])?).ok()?,
+            payload: from_hex(fields[4])?,
+            previous: digest(fields[5])?,
+            hash: digest(fields[6])?,
+        })
+    }
+}
+
+// Checks indices, back-links and hashes; the first broken entry is reported
+pub fn verify_audit_chain(entries: &[AuditEntry]) -> Result<[u8; 32], ExperimentError> {
+    let mut previous = GENESIS_HASH;
+    for (position, entry) in entries.iter().enumerate() {
+        let tampered = |reason| ExperimentError::AuditTampered {
+            index: position,
+            reason,
+        };
+        if entry.index != position as u64 {
+            return Err(tampered("entry out of sequence"));
+        }
+        if entry.previous != previous {
+            return Err(tampered("broken link to previous entry"));
+        }
+        if entry.compute_hash() != entry.hash {
+            return Err(tampered("entry hash does not match contents"));
+        }
+        previous = entry.hash;
+    }
+    Ok(previous)
+}
+
+pub fn read_audit_log<P: AsRef<Path>>(path: P) -> Result<Vec<AuditEntry>, ExperimentError> {
+    let file = File::open(path).map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line.map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+            AuditEntry::from_line(&line).ok_or(ExperimentError::AuditTampered {
+                index,
+                reason: "malformed entry",
+            })
+        })
+        .collect()
+}
+
+// Verification command backing published results: returns the entry count and head hash
+pub fn verify_audit_file<P: AsRef<Path>>(path: P) -> Result<(usize, [u8; 32]), ExperimentError> {
+    let entries = read_audit_log(path)?;
+    let head = verify_audit_chain(&entries)?;
+    Ok((entries.len(), head))
+}
+
+// Append-only log of experiment actions. Each entry commits to its predecessor,
+// so editing, dropping or reordering any recorded entry breaks the chain.
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    sink: Option<File>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            sink: None,
+        }
+    }
+
+    // Opens (or creates) a log file, verifying any existing chain before appending to it
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ExperimentError> {
+        let path = path.as_ref();
+        let entries = if path.exists() {
+            read_audit_log(path)?
+        } else {
+            Vec::new()
+        };
+        verify_audit_chain(&entries)?;
+
+        let sink = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+        Ok(Self {
+            entries,
+            sink: Some(sink),
+        })
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn head(&self) -> [u8; 32] {
+        self.entries.last().map_or(GENESIS_HASH, |entry| entry.hash)
+    }
+
+    pub fn verify(&self) -> Result<[u8; 32], ExperimentError> {
+        verify_audit_chain(&self.entries)
+    }
+
+    pub fn record(
+        &mut self,
+        kind: AuditKind,
+        label: &str,
+        payload: Vec<u8>,
+    ) -> Result<&AuditEntry, ExperimentError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let mut entry = AuditEntry {
+            index: self.entries.len() as u64,
+            timestamp,
+            kind,
+            label: label.to_string(),
+            payload,
+            previous: self.head(),
+            hash: GENESIS_HASH,
+        };
+        entry.hash = entry.compute_hash();
+
+        if let Some(sink) = &mut self.sink {
+            writeln!(sink, "{}", entry.to_line())
+                .and_then(|_| sink.flush())
+                .map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+        }
+        self.entries.push(entry);
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    pub fn record_parameters<T: Serialize>(
+        &mut self,
+        label: &str,
+        parameters: &T,
+    ) -> Result<&AuditEntry, ExperimentError> {
+        let payload =
+            bincode::serialize(parameters).map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+        self.record(AuditKind::Parameters, label, payload)
+    }
+
+    pub fn record_seed(
+        &mut self,
+        label: &str,
+        seed: &[u8],
+    ) -> Result<&AuditEntry, ExperimentError> {
+        self.record(AuditKind::Seed, label, seed.to_vec())
+    }
+
+    pub fn record_action(
+        &mut self,
+        label: &str,
+        details: &[u8],
+    ) -> Result<&AuditEntry, ExperimentError> {
+        self.record(AuditKind::Action, label, details.to_vec())
+    }
+
+    pub fn record_result<T: Serialize>(
+        &mut self,
+        label: &str,
+        result: &T,
+    ) -> Result<&AuditEntry, ExperimentError> {
+        let payload =
+            bincode::serialize(result).map_err(|e| ExperimentError::AuditIo(e.to_string()))?;
+        self.record(AuditKind::Result, label, payload)
+    }
+}
@@ -0,0 +1,122 @@
+use super::*;
+use rayon::prelude::*;
+
+pub const MAX_BRUTE_FORCE_BITS: usize = 24;
+
+type WitnessRelation = Box<dyn Fn(&ProtocolTranscript, &[u8]) -> bool + Send + Sync>;
+type TrapdoorRelation = Box<dyn Fn(&ProtocolTranscript, u64) -> bool + Send + Sync>;
+
+#[derive(Clone, Debug, Default)]
+pub struct BruteForceResult {
+    pub consistent_witnesses: Vec<Vec<u8>>,
+    pub consistent_trapdoors: Vec<u64>,
+    pub witnesses_examined: usize,
+    pub trapdoors_examined: usize,
+}
+
+impl BruteForceResult {
+    pub fn unique_witness(&self) -> Option<&[u8]> {
+        match self.consistent_witnesses.as_slice() {
+            [witness] => Some(witness),
+            _ => None,
+        }
+    }
+
+    // Remaining uncertainty about the witness given the transcript, in bits,
+    // assuming a uniform prior over the search space.
+    pub fn witness_entropy(&self) -> f64 {
+        if self.consistent_witnesses.is_empty() {
+            0.0
+        } else {
+            (self.consistent_witnesses.len() as f64).log2()
+        }
+    }
+
+    pub fn trapdoor_recovered(&self) -> bool {
+        self.consistent_trapdoors.len() == 1
+    }
+}
+
+pub struct BruteForceAnalyzer {
+    witness_bits: usize,
+    trapdoor_bits: usize,
+    witness_relation: WitnessRelation,
+    trapdoor_relation: Option<TrapdoorRelation>,
+}
+
+impl BruteForceAnalyzer {
+    pub fn new<F>(witness_bits: usize, witness_relation: F) -> Result<Self, ExperimentError>
+    where
+        F: Fn(&ProtocolTranscript, &[u8]) -> bool + Send + Sync + 'static,
+    {
+        if witness_bits > MAX_BRUTE_FORCE_BITS {
+            return Err(ExperimentError::SearchSpaceTooLarge(witness_bits));
+        }
+
+        Ok(Self {
+            witness_bits,
+            trapdoor_bits: 0,
+            witness_relation: Box::new(witness_relation),
+            trapdoor_relation: None,
+        })
+    }
+
+    pub fn with_trapdoor_search<F>(
+        mut self,
+        trapdoor_bits: usize,
+        relation: F,
+    ) -> Result<Self, ExperimentError>
+    where
+        F: Fn(&ProtocolTranscript, u64) -> bool + Send + Sync + 'static,
+    {
+        if trapdoor_bits > MAX_BRUTE_FORCE_BITS {
+            return Err(ExperimentError::SearchSpaceTooLarge(trapdoor_bits));
+        }
+
+        self.trapdoor_bits = trapdoor_bits;
+        self.trapdoor_relation = Some(Box::new(relation));
+        Ok(self)
+    }
+
+    pub fn search(&self, transcript: &ProtocolTranscript) -> BruteForceResult {
+        let mut result = BruteForceResult::default();
+        let witness_bytes = (self.witness_bits + 7) / 8;
+
+        for candidate in 0..(1u64 << self.witness_bits) {
+            let witness = candidate.to_le_bytes()[..witness_bytes].to_vec();
+            result.witnesses_examined += 1;
+            if (self.witness_relation)(transcript, &witness) {
+                result.consistent_witnesses.push(witness);
+            }
+        }
+
+        if let Some(relation) = &self.trapdoor_relation {
+            for trapdoor in 0..(1u64 << self.trapdoor_bits) {
+                result.trapdoors_examined += 1;
+                if relation(transcript, trapdoor) {
+                    result.consistent_trapdoors.push(trapdoor);
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn search_all(&self, transcripts: &[ProtocolTranscript]) -> Vec<BruteForceResult> {
+        transcripts.par_iter().map(|t| self.search(t)).collect()
+    }
+}
+
+impl UnboundedAnalyzer for BruteForceAnalyzer {
+    fn name(&self) -> &str {
+        "brute-force"
+    }
+
+    fn analyze(&self, transcript: &ProtocolTranscript) -> AnalysisOutcome {
+        let result = self.search(transcript);
+        AnalysisOutcome {
+            recovered_secret: result.unique_witness().map(|w| w.to_vec()),
+            candidates_examined: result.witnesses_examined + result.trapdoors_examined,
+        }
+    }
+}
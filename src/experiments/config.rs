@@ -0,0 +1,227 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigScale {
+    Full,
+    Toy,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtocolConfig {
+    pub security_parameter: usize,
+    pub field_bits: usize,
+    pub witness_bits: usize,
+    pub code_distance: usize,
+    pub repetitions: usize,
+    pub trap_qubits: usize,
+    pub decoherence_rate: f64,
+    pub depolarizing_probability: f64,
+    pub thermal_noise_strength: f64,
+}
+
+impl ProtocolConfig {
+    pub fn standard() -> Self {
+        Self {
+            security_parameter: 256,
+            field_bits: 252,
+            witness_bits: 256,
+            code_distance: 7,
+            repetitions: 128,
+            trap_qubits: 64,
+            decoherence_rate: 0.01,
+            depolarizing_probability: 0.001,
+            thermal_noise_strength: 0.0001,
+        }
+    }
+
+    // Soundness error of the repeated protocol, taking each repetition to
+    // catch a cheating prover with probability 1/2
+    pub fn soundness_bits(&self) -> usize {
+        self.repetitions
+    }
+
+    pub fn validate(&self) -> Result<(), ExperimentError> {
+        if self.code_distance < 3 || self.code_distance % 2 == 0 {
+            return Err(ExperimentError::InvalidConfig(
+                "code distance must be odd and at least 3",
+            ));
+        }
+        if self.repetitions == 0 {
+            return Err(ExperimentError::InvalidConfig(
+                "at least one repetition is required",
+            ));
+        }
+        if self.witness_bits > self.field_bits.max(self.security_parameter) {
+            return Err(ExperimentError::InvalidConfig(
+                "witness does not fit the field",
+            ));
+        }
+        if self.trap_qubits < self.code_distance {
+            return Err(ExperimentError::InvalidConfig(
+                "fewer trap qubits than the code distance",
+            ));
+        }
+        for rate in [self.decoherence_rate, self.depolarizing_probability] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(ExperimentError::InvalidConfig(
+                    "noise rates must be probabilities",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn noise_model(&self) -> NoiseModel {
+        NoiseModel::new(
+            self.decoherence_rate,
+            self.depolarizing_probability,
+            self.thermal_noise_strength,
+            1.0,
+        )
+    }
+
+    pub fn downscale(&self, policy: &DownscalingPolicy) -> Result<Self, ExperimentError> {
+        self.validate()?;
+
+        let ratio = policy.security_parameter as f64 / self.security_parameter as f64;
+        let scale =
+            |value: usize, minimum: usize| ((value as f64 * ratio).round() as usize).max(minimum);
+
+        let mut code_distance = policy.max_code_distance.min(self.code_distance).max(3);
+        if code_distance % 2 == 0 {
+            code_distance -= 1;
+        }
+
+        let toy = Self {
+            security_parameter: policy.security_parameter,
+            field_bits: scale(self.field_bits, 2),
+            witness_bits: scale(self.witness_bits, 1).min(policy.max_witness_bits),
+            code_distance,
+            // Keep the soundness error proportional to 2^-lambda at both sizes
+            repetitions: scale(self.repetitions, 1),
+            trap_qubits: scale(self.trap_qubits, code_distance)
+                .min(policy.max_qubits)
+                .max(code_distance),
+            // Noise rates are per-qubit physical quantities and are not rescaled
+            decoherence_rate: self.decoherence_rate,
+            depolarizing_probability: self.depolarizing_probability,
+            thermal_noise_strength: self.thermal_noise_strength,
+        };
+
+        toy.validate()?;
+        Ok(toy)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DownscalingPolicy {
+    pub security_parameter: usize,
+    pub max_witness_bits: usize,
+    pub max_code_distance: usize,
+    pub max_qubits: usize,
+}
+
+impl Default for DownscalingPolicy {
+    fn default() -> Self {
+        Self {
+            security_parameter: 8,
+            max_witness_bits: MAX_BRUTE_FORCE_BITS,
+            max_code_distance: 3,
+            max_qubits: 12,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScaledConfig {
+    full: ProtocolConfig,
+    toy: ProtocolConfig,
+}
+
+impl ScaledConfig {
+    pub fn new(full: ProtocolConfig, policy: &DownscalingPolicy) -> Result<Self, ExperimentError> {
+        let toy = full.downscale(policy)?;
+        Ok(Self { full, toy })
+    }
+
+    pub fn get(&self, scale: ConfigScale) -> &ProtocolConfig {
+        match scale {
+            ConfigScale::Full => &self.full,
+            ConfigScale::Toy => &self.toy,
+        }
+    }
+
+    pub fn full(&self) -> &ProtocolConfig {
+        &self.full
+    }
+
+    pub fn toy(&self) -> &ProtocolConfig {
+        &self.toy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downscales_the_standard_config() {
+        let scaled =
+            ScaledConfig::new(ProtocolConfig::standard(), &DownscalingPolicy::default()).unwrap();
+        assert_eq!(scaled.get(ConfigScale::Full), &ProtocolConfig::standard());
+
+        let toy = scaled.get(ConfigScale::Toy);
+        assert_eq!(toy, scaled.toy());
+        assert_eq!(toy.security_parameter, 8);
+        assert_eq!((toy.field_bits, toy.witness_bits), (8, 8));
+        assert_eq!((toy.code_distance, toy.trap_qubits), (3, 3));
+        assert_eq!(toy.decoherence_rate, scaled.full().decoherence_rate);
+        // Half a repetition per bit of security at both sizes
+        assert_eq!(toy.soundness_bits() * 2, toy.security_parameter);
+        assert_eq!(scaled.full().soundness_bits() * 2, 256);
+    }
+
+    // Only the toy witness space can be searched exhaustively
+    #[test]
+    fn toy_witnesses_fit_the_brute_force_search() {
+        let scaled =
+            ScaledConfig::new(ProtocolConfig::standard(), &DownscalingPolicy::default()).unwrap();
+        let relation = |_: &ProtocolTranscript, _: &[u8]| false;
+        assert!(BruteForceAnalyzer::new(scaled.toy().witness_bits, relation).is_ok());
+        assert!(matches!(
+            BruteForceAnalyzer::new(scaled.full().witness_bits, relation),
+            Err(ExperimentError::SearchSpaceTooLarge(256))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        let policy = DownscalingPolicy::default();
+        let mut even = ProtocolConfig::standard();
+        even.code_distance = 6;
+        assert!(matches!(
+            ScaledConfig::new(even, &policy),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+
+        let mut noisy = ProtocolConfig::standard();
+        noisy.depolarizing_probability = 1.5;
+        assert!(matches!(
+            noisy.validate(),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+
+        // A qubit cap below the code distance gives way to the distance
+        let cramped = DownscalingPolicy {
+            max_qubits: 2,
+            ..policy
+        };
+        assert_eq!(
+            ScaledConfig::new(ProtocolConfig::standard(), &cramped)
+                .unwrap()
+                .toy()
+                .trap_qubits,
+            3
+        );
+    }
+}
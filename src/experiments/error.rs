@@ -0,0 +1,32 @@
+use super::*;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExperimentError {
+    #[error("adversary exceeded classical memory bound: requested {requested} bytes, limit {limit}")]
+    ClassicalMemoryExceeded { requested: usize, limit: usize },
+    #[error("adversary exceeded quantum memory bound: requested {requested} qubits, limit {limit}")]
+    QuantumMemoryExceeded { requested: usize, limit: usize },
+    #[error("unknown quantum register handle {0}")]
+    UnknownRegister(usize),
+    #[error("brute-force search space of {0} bits is too large")]
+    SearchSpaceTooLarge(usize),
+    #[error("invalid protocol configuration: {0}")]
+    InvalidConfig(&'static str),
+    #[error("malformed security game description")]
+    MalformedGame,
+    #[error("a {0}-qubit register exceeds the simulator memory limit")]
+    StateTooLarge(usize),
+    #[error("prover transport failed: {0}")]
+    Transport(String),
+    #[error("prover refused capability: {0}")]
+    CapabilityDenied(String),
+    #[error("audit log tampered at entry {index}: {reason}")]
+    AuditTampered { index: usize, reason: &'static str },
+    #[error("audit log I/O failed: {0}")]
+    AuditIo(String),
+    #[error("adversary aborted: {0}")]
+    AdversaryAborted(String),
+    #[error("proof system failed: {0}")]
+    ProofSystem(#[from] CryptoError),
+}
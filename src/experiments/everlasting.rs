@@ -0,0 +1,204 @@
+use super::*;
+use rand::RngCore;
+
+pub trait ProtocolRunner {
+    // Runs one honest execution and returns the public transcript together
+    // with the secret (witness) the protocol is supposed to hide.
+    fn execute(&mut self, rng: &mut dyn RngCore)
+        -> Result<(ProtocolTranscript, Vec<u8>), ExperimentError>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AnalysisOutcome {
+    pub recovered_secret: Option<Vec<u8>>,
+    pub candidates_examined: usize,
+}
+
+pub trait UnboundedAnalyzer {
+    fn name(&self) -> &str;
+
+    fn analyze(&self, transcript: &ProtocolTranscript) -> AnalysisOutcome;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EverlastingReport {
+    pub trials: usize,
+    pub bounded_successes: usize,
+    pub unbounded_successes: usize,
+    pub learned_after_the_fact: usize,
+    pub memory_violations: usize,
+    pub candidates_examined: usize,
+}
+
+impl EverlastingReport {
+    pub fn bounded_advantage(&self) -> f64 {
+        Self::rate(self.bounded_successes, self.trials)
+    }
+
+    pub fn unbounded_advantage(&self) -> f64 {
+        Self::rate(self.unbounded_successes, self.trials)
+    }
+
+    // Fraction of runs where the secret was hidden from the online adversary
+    // but became learnable once the transcript was analyzed without bounds.
+    pub fn everlasting_leakage(&self) -> f64 {
+        Self::rate(self.learned_after_the_fact, self.trials)
+    }
+
+    fn rate(count: usize, trials: usize) -> f64 {
+        if trials == 0 {
+            0.0
+        } else {
+            count as f64 / trials as f64
+        }
+    }
+}
+
+pub struct EverlastingExperiment<P: ProtocolRunner, Z: UnboundedAnalyzer> {
+    runner: P,
+    analyzer: Z,
+    harness: AdversaryHarness,
+    trials: usize,
+}
+
+impl<P: ProtocolRunner, Z: UnboundedAnalyzer> EverlastingExperiment<P, Z> {
+    pub fn new(runner: P, analyzer: Z, harness: AdversaryHarness, trials: usize) -> Self {
+        Self {
+            runner,
+            analyzer,
+            harness,
+            trials,
+        }
+    }
+
+    pub fn run<A: Adversary + ?Sized>(
+        &mut self,
+        adversary: &A,
+        rng: &mut dyn RngCore,
+    ) -> Result<EverlastingReport, ExperimentError> {
+        let mut report = EverlastingReport {
+            trials: self.trials,
+            ..Default::default()
+        };
+
+        for _ in 0..self.trials {
+            let (transcript, secret) = self.runner.execute(rng)?;
+
+            // Online phase: computationally bounded adversary. Both phases see
+            // the same transcript, so any difference in success comes from
+            // computational power alone.
+            let online = self.harness.run_on_transcript(adversary, &transcript);
+            report.memory_violations += online.violations.len();
+            let bounded_success = matches!(&online.output, Ok(guess) if *guess == secret);
+
+            // Offline phase: unbounded analysis of the full transcript
+            let outcome = self.analyzer.analyze(&transcript);
+            report.candidates_examined += outcome.candidates_examined;
+            let unbounded_success = outcome.recovered_secret.as_deref() == Some(&secret[..]);
+
+            if bounded_success {
+                report.bounded_successes += 1;
+            }
+            if unbounded_success {
+                report.unbounded_successes += 1;
+                if !bounded_success {
+                    report.learned_after_the_fact += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // Sends a one-byte secret either hashed, which hides it only from bounded
+    // adversaries, or under a one-time pad, which hides it from everyone
+    struct OneByte {
+        padded: bool,
+    }
+
+    impl ProtocolRunner for OneByte {
+        fn execute(
+            &mut self,
+            rng: &mut dyn RngCore,
+        ) -> Result<(ProtocolTranscript, Vec<u8>), ExperimentError> {
+            let secret = (rng.next_u32() as u8).max(1);
+            let mut transcript = ProtocolTranscript::new(Vec::new(), b"one byte".to_vec());
+            if self.padded {
+                transcript.record("masked", vec![secret ^ rng.next_u32() as u8]);
+            } else {
+                transcript.record("hash", blake3::hash(&[secret]).as_bytes().to_vec());
+            }
+            Ok((transcript, vec![secret]))
+        }
+    }
+
+    fn analyzer() -> BruteForceAnalyzer {
+        BruteForceAnalyzer::new(8, |transcript, witness| match transcript.message("hash") {
+            Some(hash) => blake3::hash(witness).as_bytes() == hash,
+            None => true,
+        })
+        .unwrap()
+    }
+
+    // Keeps the first message and answers zero, which is never the secret
+    struct Keeper;
+
+    impl Adversary for Keeper {
+        fn name(&self) -> &str {
+            "keeper"
+        }
+
+        fn observe(
+            &self,
+            transcript: &ProtocolTranscript,
+            memory: &mut AdversaryMemory,
+        ) -> Result<(), ExperimentError> {
+            memory.store("message", transcript.messages[0].payload.clone())
+        }
+
+        fn answer(&self, _memory: &mut AdversaryMemory) -> Result<Vec<u8>, ExperimentError> {
+            Ok(vec![0])
+        }
+    }
+
+    #[test]
+    fn hashing_leaks_after_the_fact_and_padding_does_not() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1021);
+        let harness = || AdversaryHarness::new(MemoryBounds::new(32, 0));
+
+        let mut hashed =
+            EverlastingExperiment::new(OneByte { padded: false }, analyzer(), harness(), 4);
+        let report = hashed.run(&Keeper, &mut rng).unwrap();
+        assert_eq!(report.bounded_advantage(), 0.0);
+        assert_eq!(report.unbounded_advantage(), 1.0);
+        assert_eq!(report.everlasting_leakage(), 1.0);
+        assert_eq!(report.candidates_examined, 4 * 256);
+        assert_eq!(report.memory_violations, 0);
+
+        let mut padded =
+            EverlastingExperiment::new(OneByte { padded: true }, analyzer(), harness(), 4);
+        let report = padded.run(&Keeper, &mut rng).unwrap();
+        assert_eq!(report.unbounded_advantage(), 0.0);
+        assert_eq!(report.everlasting_leakage(), 0.0);
+    }
+
+    #[test]
+    fn counts_memory_violations() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1021);
+        let harness = AdversaryHarness::new(MemoryBounds::new(16, 0))
+            .with_enforcement(BoundEnforcement::Record);
+        let mut experiment =
+            EverlastingExperiment::new(OneByte { padded: false }, analyzer(), harness, 3);
+        let report = experiment.run(&Keeper, &mut rng).unwrap();
+        assert_eq!(report.trials, 3);
+        assert_eq!(report.memory_violations, 3);
+        assert_eq!(report.everlasting_leakage(), 1.0);
+    }
+}
@@ -0,0 +1,235 @@
+use super::*;
+use rand::RngCore;
+
+pub struct GameHop {
+    pub label: String,
+    pub game: SecurityGame,
+    pub protocol: Box<dyn GameProtocol>,
+}
+
+impl GameHop {
+    pub fn new(label: &str, game: SecurityGame, protocol: Box<dyn GameProtocol>) -> Self {
+        Self {
+            label: label.to_string(),
+            game,
+            protocol,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HopReport {
+    pub from: String,
+    pub to: String,
+    pub win_rate_from: f64,
+    pub win_rate_to: f64,
+    pub gap: f64,
+    pub confidence_half_width: f64,
+}
+
+impl HopReport {
+    // Gap exceeds the 95% confidence half-width of the difference of two rates
+    pub fn is_significant(&self) -> bool {
+        self.gap > self.confidence_half_width
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GameHoppingReport {
+    pub games: Vec<(String, GameResult)>,
+    pub hops: Vec<HopReport>,
+}
+
+impl GameHoppingReport {
+    pub fn largest_hop(&self) -> Option<&HopReport> {
+        self.hops
+            .iter()
+            .max_by(|a, b| a.gap.partial_cmp(&b.gap).unwrap())
+    }
+
+    // Triangle inequality: |Pr[W_0] - Pr[W_n]| <= sum of per-hop gaps
+    pub fn total_bound(&self) -> f64 {
+        self.hops.iter().map(|h| h.gap).sum()
+    }
+
+    pub fn first_significant_hop(&self) -> Option<&HopReport> {
+        self.hops.iter().find(|h| h.is_significant())
+    }
+}
+
+pub struct GameHopRunner {
+    hops: Vec<GameHop>,
+    trials: usize,
+}
+
+impl GameHopRunner {
+    pub fn new(trials: usize) -> Self {
+        Self {
+            hops: Vec::new(),
+            trials,
+        }
+    }
+
+    pub fn add_game(mut self, hop: GameHop) -> Self {
+        self.hops.push(hop);
+        self
+    }
+
+    pub fn run<A: GameAdversary + ?Sized>(
+        &mut self,
+        adversary: &mut A,
+        rng: &mut dyn RngCore,
+    ) -> Result<GameHoppingReport, ExperimentError> {
+        let mut games = Vec::with_capacity(self.hops.len());
+        for hop in &mut self.hops {
+            let runner = GameRunner::new(hop.game.clone())?;
+            let result = runner.run(hop.protocol.as_mut(), adversary, self.trials, rng);
+            games.push((hop.label.clone(), result));
+        }
+
+        let n = self.trials.max(1) as f64;
+        let hops = games
+            .windows(2)
+            .map(|pair| {
+                let p = pair[0].1.wins as f64 / n;
+                let q = pair[1].1.wins as f64 / n;
+                HopReport {
+                    from: pair[0].0.clone(),
+                    to: pair[1].0.clone(),
+                    win_rate_from: p,
+                    win_rate_to: q,
+                    gap: (p - q).abs(),
+                    confidence_half_width: 1.96 * ((p * (1.0 - p) + q * (1.0 - q)) / n).sqrt(),
+                }
+            })
+            .collect();
+
+        Ok(GameHoppingReport { games, hops })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    // Statements are random bytes whose witness is the byte itself; proofs
+    // repeat the witness with probability `leak` and are random otherwise,
+    // and simulated proofs are always random
+    struct Leaky {
+        leak: f64,
+    }
+
+    impl GameProtocol for Leaky {
+        fn setup(&mut self, _rng: &mut dyn RngCore) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn sample_instance(&mut self, rng: &mut dyn RngCore) -> (Vec<u8>, Vec<u8>) {
+            let witness = vec![rng.gen()];
+            (witness.clone(), witness)
+        }
+
+        fn prove(
+            &mut self,
+            _crs: &[u8],
+            _statement: &[u8],
+            witness: &[u8],
+            rng: &mut dyn RngCore,
+        ) -> Vec<u8> {
+            if rng.gen_bool(self.leak) {
+                witness.to_vec()
+            } else {
+                vec![rng.gen()]
+            }
+        }
+
+        fn simulate(&mut self, _crs: &[u8], _statement: &[u8], rng: &mut dyn RngCore) -> Vec<u8> {
+            vec![rng.gen()]
+        }
+
+        fn verify(&self, _crs: &[u8], _statement: &[u8], _proof: &[u8]) -> bool {
+            true
+        }
+
+        fn in_language(&self, _statement: &[u8]) -> bool {
+            true
+        }
+
+        fn relation(&self, statement: &[u8], witness: &[u8]) -> bool {
+            statement == witness
+        }
+
+        fn extract(&self, _crs: &[u8], _statement: &[u8], proof: &[u8]) -> Option<Vec<u8>> {
+            Some(proof.to_vec())
+        }
+    }
+
+    // Calls a proof simulated unless it repeats the statement
+    struct Repeats;
+
+    impl GameAdversary for Repeats {
+        fn guess(&mut self, _crs: &[u8], statement: &[u8], proof: &[u8]) -> bool {
+            statement != proof
+        }
+
+        fn forge(&mut self, _crs: &[u8]) -> (Vec<u8>, Vec<u8>) {
+            (Vec::new(), Vec::new())
+        }
+    }
+
+    fn hop(label: &str, leak: f64) -> GameHop {
+        let game =
+            SecurityGame::zero_knowledge(SecurityFlavor::Statistical, AdversaryClass::Unbounded)
+                .with_name(label);
+        GameHop::new(label, game, Box::new(Leaky { leak }))
+    }
+
+    #[test]
+    fn localizes_the_hop_that_changes_the_advantage() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1026);
+        let report = GameHopRunner::new(1000)
+            .add_game(hop("real", 1.0))
+            .add_game(hop("half", 0.5))
+            .add_game(hop("ideal", 0.0))
+            .add_game(hop("ideal again", 0.0))
+            .run(&mut Repeats, &mut rng)
+            .unwrap();
+
+        assert_eq!(report.games.len(), 4);
+        assert_eq!(report.hops.len(), 3);
+        assert_eq!(report.hops[0].from, "real");
+        assert_eq!(report.hops[0].to, "half");
+        assert!(report.hops[0].is_significant());
+        assert!(report.hops[1].is_significant());
+        // Equal games differ only by sampling noise
+        assert!(report.hops[2].gap < 0.1);
+        assert_eq!(report.first_significant_hop().unwrap().to, "half");
+
+        let real = report.games[0].1.wins as f64 / 1000.0;
+        let ideal = report.games[3].1.wins as f64 / 1000.0;
+        assert!(report.total_bound() >= (real - ideal).abs() - 1e-12);
+        let largest = report.largest_hop().unwrap();
+        assert!(report.hops.iter().all(|h| h.gap <= largest.gap));
+    }
+
+    #[test]
+    fn refuses_games_the_runner_cannot_honor() {
+        let game = SecurityGame::zero_knowledge(
+            SecurityFlavor::Statistical,
+            AdversaryClass::BoundedStorage {
+                classical_bytes: 1,
+                qubits: 0,
+            },
+        );
+        let mut runner = GameHopRunner::new(10)
+            .add_game(hop("real", 1.0))
+            .add_game(GameHop::new("bounded", game, Box::new(Leaky { leak: 0.0 })));
+        let mut rng = ChaCha20Rng::seed_from_u64(1026);
+        assert!(matches!(
+            runner.run(&mut Repeats, &mut rng),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+    }
+}
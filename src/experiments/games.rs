@@ -0,0 +1,471 @@
+use super::*;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameKind {
+    ZeroKnowledge,
+    Soundness,
+    Extractability,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityFlavor {
+    Perfect,
+    Statistical,
+    Computational,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdversaryClass {
+    ClassicalPolynomial,
+    QuantumPolynomial,
+    BoundedStorage {
+        classical_bytes: usize,
+        qubits: usize,
+    },
+    Unbounded,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrsModel {
+    FixedString,
+    QuantumOracle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdvantageDefinition {
+    // |2 Pr[b' = b] - 1|
+    Distinguishing,
+    // Pr[verifier accepts a false statement]
+    Winning,
+    // Pr[verifier accepts and the extracted witness is invalid]
+    ExtractionFailure,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStep {
+    SetupCrs,
+    SampleChallengeBit,
+    SampleInstance,
+    HonestProof,
+    SimulatedProof,
+    AdversaryGuess,
+    AdversaryForgery,
+    Verify,
+    Extract,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityGame {
+    pub name: String,
+    pub kind: GameKind,
+    pub flavor: SecurityFlavor,
+    pub adversary: AdversaryClass,
+    pub crs_model: CrsModel,
+    pub advantage: AdvantageDefinition,
+    pub steps: Vec<GameStep>,
+}
+
+impl SecurityGame {
+    pub fn zero_knowledge(flavor: SecurityFlavor, adversary: AdversaryClass) -> Self {
+        Self {
+            name: "zero-knowledge".to_string(),
+            kind: GameKind::ZeroKnowledge,
+            flavor,
+            adversary,
+            crs_model: CrsModel::FixedString,
+            advantage: AdvantageDefinition::Distinguishing,
+            steps: vec![
+                GameStep::SetupCrs,
+                GameStep::SampleInstance,
+                GameStep::SampleChallengeBit,
+                GameStep::HonestProof,
+                GameStep::SimulatedProof,
+                GameStep::AdversaryGuess,
+            ],
+        }
+    }
+
+    pub fn soundness(flavor: SecurityFlavor, adversary: AdversaryClass) -> Self {
+        Self {
+            name: "soundness".to_string(),
+            kind: GameKind::Soundness,
+            flavor,
+            adversary,
+            crs_model: CrsModel::FixedString,
+            advantage: AdvantageDefinition::Winning,
+            steps: vec![
+                GameStep::SetupCrs,
+                GameStep::AdversaryForgery,
+                GameStep::Verify,
+            ],
+        }
+    }
+
+    pub fn extractability(flavor: SecurityFlavor, adversary: AdversaryClass) -> Self {
+        Self {
+            name: "extractability".to_string(),
+            kind: GameKind::Extractability,
+            flavor,
+            adversary,
+            crs_model: CrsModel::FixedString,
+            advantage: AdvantageDefinition::ExtractionFailure,
+            steps: vec![
+                GameStep::SetupCrs,
+                GameStep::AdversaryForgery,
+                GameStep::Verify,
+                GameStep::Extract,
+            ],
+        }
+    }
+
+    pub fn with_crs_model(mut self, crs_model: CrsModel) -> Self {
+        self.crs_model = crs_model;
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("security games are always serializable")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ExperimentError> {
+        bincode::deserialize(bytes).map_err(|_| ExperimentError::MalformedGame)
+    }
+
+    pub fn describe(&self) -> String {
+        let advantage = match self.advantage {
+            AdvantageDefinition::Distinguishing => "|2 Pr[b' = b] - 1|",
+            AdvantageDefinition::Winning => "Pr[Verify(crs, x, pi) = 1 and x not in L]",
+            AdvantageDefinition::ExtractionFailure => {
+                "Pr[Verify(crs, x, pi) = 1 and (x, Ext(pi)) not in R]"
+            }
+        };
+        format!(
+            "{} ({:?} {:?}, {:?} adversary, {:?} CRS): Adv = {}",
+            self.name, self.flavor, self.kind, self.adversary, self.crs_model, advantage
+        )
+    }
+}
+
+pub trait GameProtocol {
+    fn setup(&mut self, rng: &mut dyn RngCore) -> Vec<u8>;
+    fn sample_instance(&mut self, rng: &mut dyn RngCore) -> (Vec<u8>, Vec<u8>);
+    fn prove(
+        &mut self,
+        crs: &[u8],
+        statement: &[u8],
+        witness: &[u8],
+        rng: &mut dyn RngCore,
+    ) -> Vec<u8>;
+    fn simulate(&mut self, crs: &[u8], statement: &[u8], rng: &mut dyn RngCore) -> Vec<u8>;
+    fn verify(&self, crs: &[u8], statement: &[u8], proof: &[u8]) -> bool;
+    fn in_language(&self, statement: &[u8]) -> bool;
+    fn relation(&self, statement: &[u8], witness: &[u8]) -> bool;
+    fn extract(&self, crs: &[u8], statement: &[u8], proof: &[u8]) -> Option<Vec<u8>>;
+}
+
+pub trait GameAdversary {
+    fn guess(&mut self, crs: &[u8], statement: &[u8], proof: &[u8]) -> bool;
+    fn forge(&mut self, crs: &[u8]) -> (Vec<u8>, Vec<u8>);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameResult {
+    pub game: SecurityGame,
+    pub trials: usize,
+    pub wins: usize,
+    pub advantage: f64,
+}
+
+#[derive(Default)]
+struct GameExecution {
+    crs: Vec<u8>,
+    statement: Vec<u8>,
+    witness: Vec<u8>,
+    proof: Vec<u8>,
+    challenge_bit: bool,
+    guess: bool,
+    accepted: bool,
+    extracted: Option<Vec<u8>>,
+}
+
+pub struct GameRunner {
+    game: SecurityGame,
+}
+
+impl GameRunner {
+    // The runner hands the adversary the CRS as a string and puts no bound on
+    // its memory, so it refuses games in the quantum-oracle CRS model or
+    // against bounded-storage adversaries rather than label its results with
+    // a model it did not run
+    pub fn new(game: SecurityGame) -> Result<Self, ExperimentError> {
+        if game.crs_model == CrsModel::QuantumOracle {
+            return Err(ExperimentError::InvalidConfig(
+                "the game runner only gives adversaries the CRS as a string",
+            ));
+        }
+        if let AdversaryClass::BoundedStorage { .. } = game.adversary {
+            return Err(ExperimentError::InvalidConfig(
+                "the game runner does not bound adversary storage",
+            ));
+        }
+        Ok(Self { game })
+    }
+
+    pub fn game(&self) -> &SecurityGame {
+        &self.game
+    }
+
+    pub fn run<P: GameProtocol + ?Sized, A: GameAdversary + ?Sized>(
+        &self,
+        protocol: &mut P,
+        adversary: &mut A,
+        trials: usize,
+        rng: &mut dyn RngCore,
+    ) -> GameResult {
+        let mut wins = 0;
+        for _ in 0..trials {
+            if self.run_once(protocol, adversary, rng) {
+                wins += 1;
+            }
+        }
+
+        let rate = if trials == 0 {
+            0.0
+        } else {
+            wins as f64 / trials as f64
+        };
+        let advantage = match self.game.advantage {
+            AdvantageDefinition::Distinguishing => (2.0 * rate - 1.0).abs(),
+            AdvantageDefinition::Winning | AdvantageDefinition::ExtractionFailure => rate,
+        };
+
+        GameResult {
+            game: self.game.clone(),
+            trials,
+            wins,
+            advantage,
+        }
+    }
+
+    // Interprets the game's step list; returns whether the adversary won
+    pub fn run_once<P: GameProtocol + ?Sized, A: GameAdversary + ?Sized>(
+        &self,
+        protocol: &mut P,
+        adversary: &mut A,
+        rng: &mut dyn RngCore,
+    ) -> bool {
+        let mut execution = GameExecution::default();
+
+        for step in &self.game.steps {
+            match step {
+                GameStep::SetupCrs => execution.crs = protocol.setup(rng),
+                GameStep::SampleChallengeBit => execution.challenge_bit = rng.gen(),
+                GameStep::SampleInstance => {
+                    let (statement, witness) = protocol.sample_instance(rng);
+                    execution.statement = statement;
+                    execution.witness = witness;
+                }
+                GameStep::HonestProof => {
+                    if !execution.challenge_bit {
+                        execution.proof = protocol.prove(
+                            &execution.crs,
+                            &execution.statement,
+                            &execution.witness,
+                            rng,
+                        );
+                    }
+                }
+                GameStep::SimulatedProof => {
+                    if execution.challenge_bit {
+                        execution.proof =
+                            protocol.simulate(&execution.crs, &execution.statement, rng);
+                    }
+                }
+                GameStep::AdversaryGuess => {
+                    execution.guess =
+                        adversary.guess(&execution.crs, &execution.statement, &execution.proof);
+                }
+                GameStep::AdversaryForgery => {
+                    let (statement, proof) = adversary.forge(&execution.crs);
+                    execution.statement = statement;
+                    execution.proof = proof;
+                }
+                GameStep::Verify => {
+                    execution.accepted =
+                        protocol.verify(&execution.crs, &execution.statement, &execution.proof);
+                }
+                GameStep::Extract => {
+                    execution.extracted =
+                        protocol.extract(&execution.crs, &execution.statement, &execution.proof);
+                }
+            }
+        }
+
+        match self.game.kind {
+            GameKind::ZeroKnowledge => execution.guess == execution.challenge_bit,
+            GameKind::Soundness => {
+                execution.accepted && !protocol.in_language(&execution.statement)
+            }
+            GameKind::Extractability => {
+                execution.accepted
+                    && !execution
+                        .extracted
+                        .as_ref()
+                        .map_or(false, |w| protocol.relation(&execution.statement, w))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    // Statements are even bytes 2w with witness w. Honest proofs are the
+    // witness itself when the protocol leaks and a random byte otherwise;
+    // an unsound verifier also accepts odd statements.
+    struct Doubling {
+        leaks: bool,
+        sound: bool,
+    }
+
+    impl GameProtocol for Doubling {
+        fn setup(&mut self, rng: &mut dyn RngCore) -> Vec<u8> {
+            rng.gen::<[u8; 16]>().to_vec()
+        }
+
+        fn sample_instance(&mut self, rng: &mut dyn RngCore) -> (Vec<u8>, Vec<u8>) {
+            let witness = rng.gen::<u8>();
+            (vec![witness.wrapping_mul(2)], vec![witness])
+        }
+
+        fn prove(
+            &mut self,
+            _crs: &[u8],
+            _statement: &[u8],
+            witness: &[u8],
+            rng: &mut dyn RngCore,
+        ) -> Vec<u8> {
+            if self.leaks {
+                witness.to_vec()
+            } else {
+                vec![rng.gen()]
+            }
+        }
+
+        fn simulate(&mut self, _crs: &[u8], _statement: &[u8], rng: &mut dyn RngCore) -> Vec<u8> {
+            vec![rng.gen()]
+        }
+
+        fn verify(&self, _crs: &[u8], statement: &[u8], proof: &[u8]) -> bool {
+            proof.len() == 1 && (!self.sound || self.in_language(statement))
+        }
+
+        fn in_language(&self, statement: &[u8]) -> bool {
+            matches!(statement, [x] if x % 2 == 0)
+        }
+
+        fn relation(&self, statement: &[u8], witness: &[u8]) -> bool {
+            matches!((statement, witness), ([x], [w]) if *x == w.wrapping_mul(2))
+        }
+
+        fn extract(&self, _crs: &[u8], _statement: &[u8], proof: &[u8]) -> Option<Vec<u8>> {
+            Some(proof.to_vec())
+        }
+    }
+
+    // Calls a proof simulated when it is not a witness, and forges the odd
+    // statement 1
+    struct WitnessChecker;
+
+    impl GameAdversary for WitnessChecker {
+        fn guess(&mut self, _crs: &[u8], statement: &[u8], proof: &[u8]) -> bool {
+            !matches!((statement, proof), ([x], [w]) if *x == w.wrapping_mul(2))
+        }
+
+        fn forge(&mut self, _crs: &[u8]) -> (Vec<u8>, Vec<u8>) {
+            (vec![1], vec![0])
+        }
+    }
+
+    fn run(game: SecurityGame, leaks: bool, sound: bool) -> GameResult {
+        let mut rng = ChaCha20Rng::seed_from_u64(1025);
+        GameRunner::new(game).unwrap().run(
+            &mut Doubling { leaks, sound },
+            &mut WitnessChecker,
+            400,
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn distinguishes_proofs_that_leak_the_witness() {
+        let game =
+            SecurityGame::zero_knowledge(SecurityFlavor::Statistical, AdversaryClass::Unbounded);
+        assert!(run(game.clone(), true, true).advantage > 0.9);
+        let hiding = run(game, false, true);
+        assert_eq!(hiding.trials, 400);
+        assert!(hiding.advantage < 0.2);
+    }
+
+    #[test]
+    fn forges_only_against_unsound_verifiers() {
+        let adversary = AdversaryClass::ClassicalPolynomial;
+        let soundness = SecurityGame::soundness(SecurityFlavor::Perfect, adversary);
+        assert_eq!(run(soundness.clone(), false, true).wins, 0);
+        assert_eq!(run(soundness, false, false).advantage, 1.0);
+
+        let extractability = SecurityGame::extractability(SecurityFlavor::Perfect, adversary);
+        assert_eq!(run(extractability.clone(), false, true).wins, 0);
+        assert_eq!(run(extractability, false, false).wins, 400);
+    }
+
+    #[test]
+    fn games_round_trip_through_bytes() {
+        let game = SecurityGame::zero_knowledge(
+            SecurityFlavor::Computational,
+            AdversaryClass::QuantumPolynomial,
+        )
+        .with_name("zk-hop-0");
+        assert_eq!(SecurityGame::from_bytes(&game.to_bytes()).unwrap(), game);
+        assert!(game
+            .describe()
+            .starts_with("zk-hop-0 (Computational ZeroKnowledge"));
+        assert!(matches!(
+            SecurityGame::from_bytes(&[0xff; 3]),
+            Err(ExperimentError::MalformedGame)
+        ));
+    }
+
+    #[test]
+    fn rejects_models_the_runner_cannot_honor() {
+        let oracle =
+            SecurityGame::soundness(SecurityFlavor::Statistical, AdversaryClass::Unbounded)
+                .with_crs_model(CrsModel::QuantumOracle);
+        assert!(matches!(
+            GameRunner::new(oracle),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+
+        let bounded = SecurityGame::zero_knowledge(
+            SecurityFlavor::Statistical,
+            AdversaryClass::BoundedStorage {
+                classical_bytes: 64,
+                qubits: 4,
+            },
+        );
+        assert!(matches!(
+            GameRunner::new(bounded),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+    }
+}
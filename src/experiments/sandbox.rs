@@ -0,0 +1,375 @@
+use super::*;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, Stdio};
+
+const MAX_FRAME_BYTES: usize = 16 << 20;
+const ANNOUNCE_PREFIX: &str = "prover-listening ";
+
+// Prover-side half of the sandbox. Implementors own the witness; only proofs
+// produced by `prove` are ever written to the transport.
+pub trait WitnessHolder {
+    fn can_prove(&self, statement: &[u8]) -> bool;
+    fn prove(
+        &mut self,
+        crs: &[u8],
+        statement: &[u8],
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<u8>, ExperimentError>;
+}
+
+// Wraps a GameProtocol together with the witnesses it may use
+pub struct ProtocolWitness<P> {
+    protocol: P,
+    witnesses: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl<P: GameProtocol> ProtocolWitness<P> {
+    pub fn new(protocol: P) -> Self {
+        Self {
+            protocol,
+            witnesses: HashMap::new(),
+        }
+    }
+
+    // Witnesses outside the protocol's relation are dropped
+    pub fn with_witness(mut self, statement: &[u8], witness: &[u8]) -> Self {
+        if self.protocol.relation(statement, witness) {
+            self.witnesses.insert(statement.to_vec(), witness.to_vec());
+        }
+        self
+    }
+}
+
+impl<P: GameProtocol> WitnessHolder for ProtocolWitness<P> {
+    fn can_prove(&self, statement: &[u8]) -> bool {
+        self.witnesses.contains_key(statement)
+    }
+
+    fn prove(
+        &mut self,
+        crs: &[u8],
+        statement: &[u8],
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<u8>, ExperimentError> {
+        let witness = self
+            .witnesses
+            .get(statement)
+            .ok_or_else(|| ExperimentError::CapabilityDenied("no witness for statement".into()))?;
+        Ok(self.protocol.prove(crs, statement, witness, rng))
+    }
+}
+
+// Unforgeable handle minted by the prover process; the host can only spend it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CapabilityToken([u8; 32]);
+
+// Right to request proofs of one statement, optionally for a limited number of uses
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProverCapability {
+    pub token: CapabilityToken,
+    pub statement: Vec<u8>,
+    pub uses: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SandboxRequest {
+    Grant {
+        statement: Vec<u8>,
+        uses: Option<usize>,
+    },
+    Prove {
+        token: CapabilityToken,
+        crs: Vec<u8>,
+    },
+    Revoke {
+        token: CapabilityToken,
+    },
+    Shutdown,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SandboxResponse {
+    Granted(ProverCapability),
+    Proof(Vec<u8>),
+    Revoked,
+    Denied(String),
+}
+
+fn transport_error(error: impl std::fmt::Display) -> ExperimentError {
+    ExperimentError::Transport(error.to_string())
+}
+
+// Length-prefixed bincode frames; returns the raw frame for observation
+fn write_frame<W: Write, T: Serialize>(
+    writer: &mut W,
+    message: &T,
+) -> Result<Vec<u8>, ExperimentError> {
+    let payload = bincode::serialize(message).map_err(transport_error)?;
+    if payload.len() > MAX_FRAME_BYTES {
+        return Err(ExperimentError::Transport("frame too large".into()));
+    }
+    writer
+        .write_all(&(payload.len() as u32).to_be_bytes())
+        .and_then(|_| writer.write_all(&payload))
+        .and_then(|_| writer.flush())
+        .map_err(transport_error)?;
+    Ok(payload)
+}
+
+fn read_frame<R: Read, T: DeserializeOwned>(
+    reader: &mut R,
+) -> Result<(T, Vec<u8>), ExperimentError> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length).map_err(transport_error)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_BYTES {
+        return Err(ExperimentError::Transport("frame too large".into()));
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload).map_err(transport_error)?;
+    let message = bincode::deserialize(&payload).map_err(transport_error)?;
+    Ok((message, payload))
+}
+
+pub struct ProverServer<W> {
+    holder: W,
+    capabilities: HashMap<CapabilityToken, (Vec<u8>, Option<usize>)>,
+}
+
+impl<W: WitnessHolder> ProverServer<W> {
+    pub fn new(holder: W) -> Self {
+        Self {
+            holder,
+            capabilities: HashMap::new(),
+        }
+    }
+
+    fn handle(&mut self, request: SandboxRequest, rng: &mut dyn RngCore) -> SandboxResponse {
+        match request {
+            SandboxRequest::Grant { statement, uses } => {
+                if !self.holder.can_prove(&statement) {
+                    return SandboxResponse::Denied("no witness for statement".into());
+                }
+                let mut token = [0u8; 32];
+                rng.fill_bytes(&mut token);
+                let token = CapabilityToken(token);
+                self.capabilities.insert(token, (statement.clone(), uses));
+                SandboxResponse::Granted(ProverCapability {
+                    token,
+                    statement,
+                    uses,
+                })
+            }
+            SandboxRequest::Prove { token, crs } => {
+                let statement = match self.capabilities.get_mut(&token) {
+                    Some((_, Some(0))) | None => {
+                        return SandboxResponse::Denied("unknown or exhausted capability".into())
+                    }
+                    Some((statement, uses)) => {
+                        if let Some(remaining) = uses {
+                            *remaining -= 1;
+                        }
+                        statement.clone()
+                    }
+                };
+                match self.holder.prove(&crs, &statement, rng) {
+                    Ok(proof) => SandboxResponse::Proof(proof),
+                    Err(error) => SandboxResponse::Denied(error.to_string()),
+                }
+            }
+            SandboxRequest::Revoke { token } => match self.capabilities.remove(&token) {
+                Some(_) => SandboxResponse::Revoked,
+                None => SandboxResponse::Denied("unknown capability".into()),
+            },
+            SandboxRequest::Shutdown => SandboxResponse::Revoked,
+        }
+    }
+
+    // Serves one host until it shuts the session down or disconnects
+    pub fn serve_connection(
+        &mut self,
+        mut stream: TcpStream,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), ExperimentError> {
+        loop {
+            let (request, _) = match read_frame::<_, SandboxRequest>(&mut stream) {
+                Ok(frame) => frame,
+                Err(_) => return Ok(()),
+            };
+            let shutdown = matches!(request, SandboxRequest::Shutdown);
+            let response = self.handle(request, rng);
+            write_frame(&mut stream, &response)?;
+            if shutdown {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn serve(
+        &mut self,
+        listener: TcpListener,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), ExperimentError> {
+        let (stream, _) = listener.accept().map_err(transport_error)?;
+        self.serve_connection(stream, rng)
+    }
+
+    // Entry point for the child process started by SandboxedProver::spawn:
+    // binds an ephemeral loopback port and announces it on stdout.
+    pub fn run_subprocess(mut self) -> Result<(), ExperimentError> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(transport_error)?;
+        let address = listener.local_addr().map_err(transport_error)?;
+        let mut stdout = std::io::stdout();
+        writeln!(stdout, "{}{}", ANNOUNCE_PREFIX, address)
+            .and_then(|_| stdout.flush())
+            .map_err(transport_error)?;
+        self.serve(listener, &mut OsRng)
+    }
+}
+
+// Verifier-host half of the sandbox. Every frame crossing the transport is
+// recorded, so `observed` is exactly what a corrupted host gets to see.
+pub struct SandboxedProver {
+    stream: TcpStream,
+    child: Option<Child>,
+    observed: ProtocolTranscript,
+}
+
+impl SandboxedProver {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<Self, ExperimentError> {
+        let stream = TcpStream::connect(address).map_err(transport_error)?;
+        Ok(Self {
+            stream,
+            child: None,
+            observed: ProtocolTranscript::default(),
+        })
+    }
+
+    // Starts a prover process that calls ProverServer::run_subprocess
+    pub fn spawn(mut command: Command) -> Result<Self, ExperimentError> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(transport_error)?;
+
+        let mut line = String::new();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| ExperimentError::Transport("prover stdout unavailable".into()))?;
+        BufReader::new(stdout)
+            .read_line(&mut line)
+            .map_err(transport_error)?;
+
+        let address = match line.trim().strip_prefix(ANNOUNCE_PREFIX) {
+            Some(address) => address.to_string(),
+            None => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ExperimentError::Transport(format!(
+                    "unexpected prover announcement {:?}",
+                    line.trim()
+                )));
+            }
+        };
+
+        let mut prover = Self::connect(address.as_str())?;
+        prover.child = Some(child);
+        Ok(prover)
+    }
+
+    // Same protocol over loopback, but with the prover on a thread of this
+    // process; useful as a baseline when the isolation itself is not under test
+    pub fn in_thread<W: WitnessHolder + Send + 'static>(
+        holder: W,
+    ) -> Result<Self, ExperimentError> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(transport_error)?;
+        let address = listener.local_addr().map_err(transport_error)?;
+        std::thread::spawn(move || ProverServer::new(holder).serve(listener, &mut OsRng));
+        Self::connect(address)
+    }
+
+    fn call(&mut self, request: &SandboxRequest) -> Result<SandboxResponse, ExperimentError> {
+        let sent = write_frame(&mut self.stream, request)?;
+        self.observed.record("host->prover", sent);
+        let (response, received) = read_frame(&mut self.stream)?;
+        self.observed.record("prover->host", received);
+        Ok(response)
+    }
+
+    pub fn grant(
+        &mut self,
+        statement: &[u8],
+        uses: Option<usize>,
+    ) -> Result<ProverCapability, ExperimentError> {
+        let request = SandboxRequest::Grant {
+            statement: statement.to_vec(),
+            uses,
+        };
+        match self.call(&request)? {
+            SandboxResponse::Granted(capability) => Ok(capability),
+            SandboxResponse::Denied(reason) => Err(ExperimentError::CapabilityDenied(reason)),
+            _ => Err(ExperimentError::Transport(
+                "unexpected response to grant".into(),
+            )),
+        }
+    }
+
+    pub fn prove(
+        &mut self,
+        capability: &ProverCapability,
+        crs: &[u8],
+    ) -> Result<Vec<u8>, ExperimentError> {
+        let request = SandboxRequest::Prove {
+            token: capability.token,
+            crs: crs.to_vec(),
+        };
+        match self.call(&request)? {
+            SandboxResponse::Proof(proof) => Ok(proof),
+            SandboxResponse::Denied(reason) => Err(ExperimentError::CapabilityDenied(reason)),
+            _ => Err(ExperimentError::Transport(
+                "unexpected response to prove".into(),
+            )),
+        }
+    }
+
+    pub fn revoke(&mut self, capability: &ProverCapability) -> Result<(), ExperimentError> {
+        match self.call(&SandboxRequest::Revoke {
+            token: capability.token,
+        })? {
+            SandboxResponse::Revoked => Ok(()),
+            SandboxResponse::Denied(reason) => Err(ExperimentError::CapabilityDenied(reason)),
+            _ => Err(ExperimentError::Transport(
+                "unexpected response to revoke".into(),
+            )),
+        }
+    }
+
+    pub fn observed(&self) -> &ProtocolTranscript {
+        &self.observed
+    }
+
+    pub fn shutdown(mut self) -> Result<ProtocolTranscript, ExperimentError> {
+        self.call(&SandboxRequest::Shutdown)?;
+        if let Some(mut child) = self.child.take() {
+            child.wait().map_err(transport_error)?;
+        }
+        Ok(std::mem::take(&mut self.observed))
+    }
+}
+
+impl Drop for SandboxedProver {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use super::*;
+use rand::RngCore;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Default)]
+pub struct SoakSample {
+    pub accepted: bool,
+    pub challenge: Vec<u8>,
+    pub state_norm: Option<f64>,
+}
+
+pub trait SoakProtocol {
+    // One full prove/verify round of the protocol under test
+    fn run_once(&mut self, rng: &mut dyn RngCore) -> Result<SoakSample, ExperimentError>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoakMetric {
+    AcceptanceRate,
+    // Fraction of set bits across the batch's challenges; 0.5 for uniform challenges
+    ChallengeBitBalance,
+    // Mean of |1 - ||psi||| over samples that report a state norm
+    NormDeviation,
+}
+
+#[derive(Clone, Debug)]
+pub struct SoakConfig {
+    pub duration: Duration,
+    pub max_batches: Option<usize>,
+    pub batch_size: usize,
+    pub warmup_batches: usize,
+    pub ewma_lambda: f64,
+    pub control_limit: f64,
+    pub stop_on_alarm: bool,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4 * 60 * 60),
+            max_batches: None,
+            batch_size: 256,
+            warmup_batches: 20,
+            ewma_lambda: 0.2,
+            control_limit: 3.0,
+            stop_on_alarm: false,
+        }
+    }
+}
+
+impl SoakConfig {
+    pub fn validate(&self) -> Result<(), ExperimentError> {
+        if self.batch_size == 0 {
+            return Err(ExperimentError::InvalidConfig(
+                "soak batch size must be positive",
+            ));
+        }
+        if self.warmup_batches < 2 {
+            return Err(ExperimentError::InvalidConfig(
+                "soak warm-up needs at least two batches",
+            ));
+        }
+        if !(self.ewma_lambda > 0.0 && self.ewma_lambda <= 1.0) {
+            return Err(ExperimentError::InvalidConfig(
+                "EWMA weight must lie in (0, 1]",
+            ));
+        }
+        Ok(())
+    }
+}
+
+// EWMA control chart: the centre line and spread are estimated from the
+// warm-up batches, after which the smoothed statistic must stay within
+// centre +/- L * sigma * sqrt(lambda / (2 - lambda)).
+#[derive(Clone, Debug)]
+pub struct ControlChart {
+    lambda: f64,
+    limit: f64,
+    warmup: Vec<f64>,
+    warmup_len: usize,
+    centre: f64,
+    sigma: f64,
+    ewma: f64,
+}
+
+impl ControlChart {
+    pub fn new(warmup_len: usize, lambda: f64, limit: f64) -> Self {
+        Self {
+            lambda,
+            limit,
+            warmup: Vec::with_capacity(warmup_len),
+            warmup_len,
+            centre: 0.0,
+            sigma: 0.0,
+            ewma: 0.0,
+        }
+    }
+
+    pub fn is_calibrated(&self) -> bool {
+        self.warmup.len() >= self.warmup_len
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        let width = self.limit * self.sigma * (self.lambda / (2.0 - self.lambda)).sqrt();
+        (self.centre - width, self.centre + width)
+    }
+
+    // Returns the EWMA value when it falls outside the control limits
+    pub fn observe(&mut self, value: f64) -> Option<f64> {
+        if !self.is_calibrated() {
+            self.warmup.push(value);
+            if self.is_calibrated() {
+                let n = self.warmup.len() as f64;
+                self.centre = self.warmup.iter().sum::<f64>() / n;
+                let variance = self
+                    .warmup
+                    .iter()
+                    .map(|v| (v - self.centre).powi(2))
+                    .sum::<f64>()
+                    / (n - 1.0);
+                // Floor the spread so a perfectly constant warm-up still tolerates rounding
+                self.sigma = variance.sqrt().max(1e-12);
+                self.ewma = self.centre;
+            }
+            return None;
+        }
+
+        self.ewma = self.lambda * value + (1.0 - self.lambda) * self.ewma;
+        let (lower, upper) = self.bounds();
+        if self.ewma < lower || self.ewma > upper {
+            Some(self.ewma)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DriftAlarm {
+    pub batch: usize,
+    pub metric: SoakMetric,
+    pub ewma: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SoakReport {
+    pub batches: usize,
+    pub samples: usize,
+    pub accepted: usize,
+    pub elapsed: Duration,
+    pub alarms: Vec<DriftAlarm>,
+}
+
+impl SoakReport {
+    pub fn is_stable(&self) -> bool {
+        self.alarms.is_empty()
+    }
+
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.accepted as f64 / self.samples as f64
+        }
+    }
+}
+
+pub struct SoakRunner {
+    config: SoakConfig,
+}
+
+impl SoakRunner {
+    pub fn new(config: SoakConfig) -> Result<Self, ExperimentError> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    pub fn run<P: SoakProtocol + ?Sized>(
+        &self,
+        protocol: &mut P,
+        rng: &mut dyn RngCore,
+    ) -> Result<SoakReport, ExperimentError> {
+        let config = &self.config;
+        let chart = || {
+            ControlChart::new(
+                config.warmup_batches,
+                config.ewma_lambda,
+                config.control_limit,
+            )
+        };
+        let mut charts = [
+            (SoakMetric::AcceptanceRate, chart()),
+            (SoakMetric::ChallengeBitBalance, chart()),
+            (SoakMetric::NormDeviation, chart()),
+        ];
+
+        let start = Instant::now();
+        let mut report = SoakReport::default();
+
+        while start.elapsed() < config.duration
+            && config.max_batches.map_or(true, |max| report.batches < max)
+        {
+            let mut accepted = 0;
+            let mut set_bits = 0u64;
+            let mut total_bits = 0u64;
+            let mut norm_deviation = 0.0;
+            let mut norm_samples = 0;
+
+            for _ in 0..config.batch_size {
+                let sample = protocol.run_once(rng)?;
+                if sample.accepted {
+                    accepted += 1;
+                }
+                set_bits += sample
+                    .challenge
+                    .iter()
+                    .map(|b| b.count_ones() as u64)
+                    .sum::<u64>();
+                total_bits += 8 * sample.challenge.len() as u64;
+                if let Some(norm) = sample.state_norm {
+                    norm_deviation += (1.0 - norm).abs();
+                    norm_samples += 1;
+                }
+            }
+
+            let values = [
+                accepted as f64 / config.batch_size as f64,
+                if total_bits == 0 {
+                    0.5
+                } else {
+                    set_bits as f64 / total_bits as f64
+                },
+                if norm_samples == 0 {
+                    0.0
+                } else {
+                    norm_deviation / norm_samples as f64
+                },
+            ];
+
+            for ((metric, chart), value) in charts.iter_mut().zip(values) {
+                if let Some(ewma) = chart.observe(value) {
+                    let (lower, upper) = chart.bounds();
+                    report.alarms.push(DriftAlarm {
+                        batch: report.batches,
+                        metric: *metric,
+                        ewma,
+                        lower,
+                        upper,
+                    });
+                }
+            }
+
+            report.batches += 1;
+            report.samples += config.batch_size;
+            report.accepted += accepted;
+
+            if config.stop_on_alarm && !report.alarms.is_empty() {
+                break;
+            }
+        }
+
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+}
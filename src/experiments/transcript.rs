@@ -0,0 +1,42 @@
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolTranscript {
+    pub crs: Vec<u8>,
+    pub statement: Vec<u8>,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptMessage {
+    pub label: String,
+    pub payload: Vec<u8>,
+}
+
+impl ProtocolTranscript {
+    pub fn new(crs: Vec<u8>, statement: Vec<u8>) -> Self {
+        Self {
+            crs,
+            statement,
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, label: &str, payload: Vec<u8>) {
+        self.messages.push(TranscriptMessage {
+            label: label.to_string(),
+            payload,
+        });
+    }
+
+    pub fn message(&self, label: &str) -> Option<&[u8]> {
+        self.messages
+            .iter()
+            .find(|m| m.label == label)
+            .map(|m| m.payload.as_slice())
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.crs.len()
+            + self.statement.len()
+            + self.messages.iter().map(|m| m.payload.len()).sum::<usize>()
+    }
+}
@@ -1,20 +1,196 @@
 mod quantum {
+    mod error;
     mod state;
     mod error_correction;
     mod noise;
+    mod qudit;
+    mod circuit;
+    mod transpiler;
+    mod density;
+    mod oracle;
+    mod haar;
+    mod checkpoint;
+    mod clifford;
+    mod channel;
+    mod fusion;
+    mod precision;
+    mod grover;
+    mod pauli;
+    mod diagram;
+    mod lattice;
+    mod gf2;
+    mod logical;
+    mod syndrome_extraction;
+    mod threshold;
+    mod spacetime;
+    mod erasure;
+    mod detector_model;
+    mod code_builder;
+    mod distance;
+    mod lookup_cache;
+    mod encoding;
+    mod transversal;
+    mod kraus;
+    mod readout;
+    mod crosstalk;
+    mod leakage;
+    mod schedule;
+    mod composite;
+    mod calibration;
+    mod trajectory;
+    mod benchmarking;
+    mod bias;
+    mod seeded;
+    mod executor;
+    mod correlation;
+    mod nonmarkovian;
+    mod trace;
+    pub mod unitary;
     
+    pub use error::*;
     pub use state::*;
     pub use error_correction::*;
     pub use noise::*;
+    pub use qudit::*;
+    pub use circuit::*;
+    pub use transpiler::*;
+    pub use density::*;
+    pub use oracle::*;
+    pub use haar::*;
+    pub use checkpoint::*;
+    pub use clifford::*;
+    pub use channel::*;
+    pub use fusion::*;
+    pub use precision::*;
+    pub use grover::*;
+    pub use pauli::*;
+    pub use lattice::*;
+    pub use logical::*;
+    pub use syndrome_extraction::*;
+    pub use threshold::*;
+    pub use spacetime::*;
+    pub use erasure::*;
+    pub use detector_model::*;
+    pub use code_builder::*;
+    pub use distance::*;
+    pub use lookup_cache::*;
+    pub(crate) use encoding::*;
+    pub use kraus::*;
+    pub use readout::*;
+    pub use crosstalk::*;
+    pub use leakage::*;
+    pub use schedule::*;
+    pub(crate) use composite::*;
+    pub use trajectory::*;
+    pub use benchmarking::*;
+    pub use bias::*;
+    pub use seeded::*;
+    pub use executor::*;
+    pub use correlation::*;
+    pub use nonmarkovian::*;
+    pub use trace::*;
+    pub use unitary::{Matrix2, Matrix4, Unitary};
 }
 
 mod crypto {
+    use crate::quantum::*;
+
+    mod error;
     mod snark;
     mod nizk;
+    mod crs_oracle;
+    mod prf;
+    mod prs;
+    mod verification_policy;
+    mod r1cs;
+    mod gadgets;
+    mod hash_gadgets;
+    mod witness;
+    mod foreign_field;
+    mod recursion;
+    mod domain;
+    mod proof_system;
+    mod groth16;
+    mod plonk;
+    mod inner_product;
+    mod bulletproofs;
+    mod polynomial_commitment;
+    mod fri;
+    mod aggregation;
+    mod ceremony;
+    mod encoding;
+    mod msm;
+    mod pedersen;
+    mod fri_snark;
+    mod simulator;
+    mod soundness;
+    mod transcript;
     
+    pub use error::*;
     pub use snark::*;
     pub use nizk::*;
+    pub use crs_oracle::*;
+    pub use prf::*;
+    pub use prs::*;
+    pub use verification_policy::*;
+    pub use r1cs::*;
+    pub use gadgets::*;
+    pub use hash_gadgets::*;
+    pub use witness::*;
+    pub use foreign_field::*;
+    pub use recursion::*;
+    pub(crate) use domain::*;
+    pub use proof_system::*;
+    pub use groth16::*;
+    pub use plonk::*;
+    pub use inner_product::*;
+    pub use bulletproofs::*;
+    pub use polynomial_commitment::*;
+    pub use fri::*;
+    pub use aggregation::*;
+    pub use ceremony::*;
+    pub use encoding::*;
+    pub use msm::*;
+    pub use pedersen::*;
+    pub use fri_snark::*;
+    pub use simulator::*;
+    pub use soundness::*;
+    pub use transcript::*;
+}
+
+mod experiments {
+    use crate::quantum::*;
+    use crate::crypto::*;
+
+    mod error;
+    mod adversary;
+    mod transcript;
+    mod everlasting;
+    mod brute_force;
+    mod config;
+    mod attacks;
+    mod games;
+    mod game_hopping;
+    mod soak;
+    mod sandbox;
+    mod audit;
+
+    pub use error::*;
+    pub use adversary::*;
+    pub use transcript::*;
+    pub use everlasting::*;
+    pub use brute_force::*;
+    pub use config::*;
+    pub use attacks::*;
+    pub use games::*;
+    pub use game_hopping::*;
+    pub use soak::*;
+    pub use sandbox::*;
+    pub use audit::*;
 }
 
+pub mod test_vectors;
+
 pub use quantum::*;
-pub use crypto::*;
\ No newline at end of file
+pub use crypto::*;
+pub use experiments::*;
\ No newline at end of file
@@ -3,7 +3,9 @@ use rand::rngs::OsRng;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let security_parameter = 256;
-    
+    let mut rng = OsRng;
+    let master_key = Blake3Prf::random(&mut rng);
+
     let quantum_channel = QuantumChannel {
         noise_model: NoiseModel {
             decoherence_rate: 0.01,
@@ -16,14 +18,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             recovery_operations: vec![],
         },
         authentication: QuantumAuthentication {
-            key: SecretKey(vec![0u8; 32]),
+            key: SecretKey(master_key.evaluate(b"quantum authentication key", 32)),
             tag: AuthenticationTag(vec![0u8; 32]),
             verification_scheme: VerificationScheme::Clifford,
         },
     };
 
     let protocol = SNIZKProtocol::new(security_parameter, quantum_channel.clone());
-    let mut rng = OsRng;
 
     let crs = (protocol.crs_generator)(&mut rng)?;
     let statement = vec![0u8; security_parameter];
@@ -0,0 +1,233 @@
+use super::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+
+const GOLDEN_SECTION_STEPS: usize = 100;
+
+// Least-squares fit of survival = amplitude * decay^m + offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecayFit {
+    pub amplitude: f64,
+    pub decay: f64,
+    pub offset: f64,
+    pub residual: f64,
+}
+
+impl DecayFit {
+    pub fn survival(&self, length: f64) -> f64 {
+        self.amplitude * self.decay.powf(length) + self.offset
+    }
+}
+
+// Mean survival probability per sequence length and the depolarizing
+// parameter read off its decay
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomizedBenchmarkingResult {
+    pub lengths: Vec<usize>,
+    pub survival: Vec<f64>,
+    pub fit: DecayFit,
+    pub error_per_clifford: f64,
+    pub fidelity: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterleavedBenchmarkingResult {
+    pub reference: RandomizedBenchmarkingResult,
+    pub interleaved: RandomizedBenchmarkingResult,
+    pub gate_error: f64,
+    pub gate_fidelity: f64,
+}
+
+// Randomized benchmarking against a noise model. Each sequence is `m`
+// uniformly random Cliffords followed by the Clifford that inverts them, run
+// from |0...0> as noisy trajectories (gate noise and a noise layer after
+// every physical gate); survival is the probability of returning to
+// |0...0>. Error rates are per Clifford, in the units a device RB run
+// reports.
+#[derive(Clone, Debug)]
+pub struct RandomizedBenchmarking {
+    num_qubits: usize,
+    lengths: Vec<usize>,
+    noise: NoiseModel,
+    sequences: usize,
+    trajectories: usize,
+}
+
+impl RandomizedBenchmarking {
+    pub fn new(num_qubits: usize, lengths: Vec<usize>, noise: NoiseModel) -> Self {
+        Self {
+            num_qubits,
+            lengths,
+            noise,
+            sequences: 30,
+            trajectories: 20,
+        }
+    }
+
+    // Random sequences drawn per length
+    pub fn with_sequences(mut self, sequences: usize) -> Self {
+        self.sequences = sequences;
+        self
+    }
+
+    // Noisy trajectories averaged per sequence
+    pub fn with_trajectories(mut self, trajectories: usize) -> Self {
+        self.trajectories = trajectories;
+        self
+    }
+
+    pub fn run<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<RandomizedBenchmarkingResult, QuantumError> {
+        self.benchmark(None, rng)
+    }
+
+    // Interleaves `gate` after every random Clifford and compares the decay
+    // with a reference run: the gate's error is (d - 1)(1 - p_gate / p_ref) / d
+    pub fn run_interleaved<R: Rng + ?Sized>(
+        &self,
+        gate: &QuantumCircuit,
+        rng: &mut R,
+    ) -> Result<InterleavedBenchmarkingResult, QuantumError> {
+        if gate.num_qubits() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        CliffordTableau::from_circuit(gate)?;
+        let reference = self.benchmark(None, rng)?;
+        let interleaved = self.benchmark(Some(gate), rng)?;
+        let gate_error = self.error_rate(interleaved.fit.decay / reference.fit.decay);
+        Ok(InterleavedBenchmarkingResult {
+            reference,
+            interleaved,
+            gate_error,
+            gate_fidelity: 1.0 - gate_error,
+        })
+    }
+
+    fn error_rate(&self, decay: f64) -> f64 {
+        let dimension = (1usize << self.num_qubits) as f64;
+        (dimension - 1.0) * (1.0 - decay) / dimension
+    }
+
+    fn sequence<R: Rng + ?Sized>(
+        &self,
+        length: usize,
+        interleaved: Option<&QuantumCircuit>,
+        rng: &mut R,
+    ) -> Result<QuantumCircuit, QuantumError> {
+        let mut circuit = QuantumCircuit::new(self.num_qubits);
+        for _ in 0..length {
+            circuit.extend(&random_clifford_circuit(self.num_qubits, rng))?;
+            if let Some(gate) = interleaved {
+                circuit.extend(gate)?;
+            }
+        }
+        let recovery = CliffordTableau::from_circuit(&circuit)?
+            .to_circuit()
+            .inverse()?;
+        circuit.extend(&recovery)?;
+        Ok(circuit)
+    }
+
+    fn benchmark<R: Rng + ?Sized>(
+        &self,
+        interleaved: Option<&QuantumCircuit>,
+        rng: &mut R,
+    ) -> Result<RandomizedBenchmarkingResult, QuantumError> {
+        if self.num_qubits == 0 || self.sequences == 0 || self.trajectories == 0 {
+            return Err(QuantumError::InvalidDimension);
+        }
+        let mut survival = Vec::with_capacity(self.lengths.len());
+        for &length in &self.lengths {
+            let runs = (0..self.sequences)
+                .map(|_| Ok((self.sequence(length, interleaved, rng)?, rng.gen::<u64>())))
+                .collect::<Result<Vec<(QuantumCircuit, u64)>, QuantumError>>()?;
+            let total = runs
+                .into_par_iter()
+                .map(|(circuit, seed)| {
+                    let mut sequence_rng = ChaCha20Rng::seed_from_u64(seed);
+                    let simulator = TrajectorySimulator::new(circuit, self.noise.clone());
+                    let initial = QuantumState::new(self.num_qubits)?;
+                    let mut sum = 0.0;
+                    for _ in 0..self.trajectories {
+                        let (state, _) = simulator.run_trajectory(&initial, &mut sequence_rng)?;
+                        sum += state.probabilities()[0];
+                    }
+                    Ok(sum / self.trajectories as f64)
+                })
+                .collect::<Result<Vec<f64>, QuantumError>>()?
+                .iter()
+                .sum::<f64>();
+            survival.push(total / self.sequences as f64);
+        }
+
+        let lengths: Vec<f64> = self.lengths.iter().map(|&m| m as f64).collect();
+        let fit = fit_exponential_decay(&lengths, &survival)?;
+        let error_per_clifford = self.error_rate(fit.decay);
+        Ok(RandomizedBenchmarkingResult {
+            lengths: self.lengths.clone(),
+            survival,
+            fit,
+            error_per_clifford,
+            fidelity: 1.0 - error_per_clifford,
+        })
+    }
+}
+
+// Fits y = A p^x + B with p in [0, 1]: for fixed p the fit is linear in A and
+// B, and the residual is minimised over p by golden-section search. Needs at
+// least three distinct x.
+pub fn fit_exponential_decay(x: &[f64], y: &[f64]) -> Result<DecayFit, QuantumError> {
+    if x.len() != y.len() {
+        return Err(QuantumError::DimensionMismatch);
+    }
+    let mut distinct = x.to_vec();
+    distinct.sort_by(f64::total_cmp);
+    distinct.dedup();
+    if distinct.len() < 3 {
+        return Err(QuantumError::InsufficientData);
+    }
+
+    let fit_at = |decay: f64| {
+        let basis: Vec<f64> = x.iter().map(|&m| decay.powf(m)).collect();
+        let n = x.len() as f64;
+        let sx: f64 = basis.iter().sum();
+        let sy: f64 = y.iter().sum();
+        let sxx: f64 = basis.iter().map(|b| b * b).sum();
+        let sxy: f64 = basis.iter().zip(y).map(|(b, v)| b * v).sum();
+        let denominator = n * sxx - sx * sx;
+        let amplitude = if denominator.abs() < 1e-12 {
+            0.0
+        } else {
+            (n * sxy - sx * sy) / denominator
+        };
+        let offset = (sy - amplitude * sx) / n;
+        let residual = basis
+            .iter()
+            .zip(y)
+            .map(|(b, v)| (amplitude * b + offset - v).powi(2))
+            .sum();
+        DecayFit {
+            amplitude,
+            decay,
+            offset,
+            residual,
+        }
+    };
+
+    // Ties move towards p = 1, so a flat curve fits as no decay
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..GOLDEN_SECTION_STEPS {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if fit_at(left).residual < fit_at(right).residual {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    Ok(fit_at((low + high) / 2.0))
+}
@@ -0,0 +1,130 @@
+use super::*;
+use rand::Rng;
+
+// Pauli noise of total probability p with Z favoured by bias eta = p_z /
+// (p_x + p_y): p_z = p eta / (eta + 1) and p_x = p_y = p / (2 (eta + 1)).
+// eta = 1/2 is depolarizing noise and eta -> infinity pure dephasing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BiasedNoise {
+    probability: f64,
+    bias: f64,
+}
+
+impl BiasedNoise {
+    pub fn new(probability: f64, bias: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        if bias.is_nan() || bias < 0.0 {
+            return Err(QuantumError::InvalidBias);
+        }
+        Ok(Self { probability, bias })
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    // (p_x, p_y, p_z)
+    pub fn pauli_probabilities(&self) -> (f64, f64, f64) {
+        if self.bias.is_infinite() {
+            return (0.0, 0.0, self.probability);
+        }
+        let transverse = self.probability / (2.0 * (self.bias + 1.0));
+        (
+            transverse,
+            transverse,
+            self.probability * self.bias / (self.bias + 1.0),
+        )
+    }
+
+    pub fn probability_of(&self, pauli: PauliOperator) -> f64 {
+        let (px, py, pz) = self.pauli_probabilities();
+        match pauli {
+            PauliOperator::X => px,
+            PauliOperator::Y => py,
+            PauliOperator::Z => pz,
+        }
+    }
+
+    pub fn channel(&self) -> Result<QuantumChannelOp, QuantumError> {
+        let (px, py, pz) = self.pauli_probabilities();
+        QuantumChannelOp::pauli_channel(
+            1,
+            &[
+                (px, PauliString::single(0, PauliOperator::X)),
+                (py, PauliString::single(0, PauliOperator::Y)),
+                (pz, PauliString::single(0, PauliOperator::Z)),
+            ],
+        )
+    }
+
+    // Independent biased error on each of `num_qubits` qubits
+    pub fn sample_error<R: Rng + ?Sized>(&self, num_qubits: usize, rng: &mut R) -> PauliString {
+        let (px, py, _) = self.pauli_probabilities();
+        let mut error = PauliString::identity(num_qubits);
+        for qubit in 0..num_qubits {
+            let draw = rng.gen::<f64>();
+            if draw < self.probability {
+                let pauli = if draw < px {
+                    PauliOperator::X
+                } else if draw < px + py {
+                    PauliOperator::Y
+                } else {
+                    PauliOperator::Z
+                };
+                error.set(qubit, Some(pauli));
+            }
+        }
+        error
+    }
+}
+
+impl NoiseModel {
+    // Biased Pauli noise on every qubit in each noise layer, after the
+    // depolarizing channel
+    pub fn with_biased_noise(mut self, bias: BiasedNoise) -> Self {
+        self.bias = Some(bias);
+        self
+    }
+
+    pub fn biased_noise(&self) -> Option<BiasedNoise> {
+        self.bias
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Single-qubit faults weighted ln((1 - p) / p) by their biased rates, so
+    // the decoder prefers Z chains over equally long X or Y ones
+    pub fn biased_faults(&self, bias: &BiasedNoise) -> Vec<Fault> {
+        let mut faults = self.spacetime_faults(1);
+        for fault in &mut faults {
+            if let Some((_, pauli)) = fault.correction.iter().next() {
+                fault.weight = log_odds(bias.probability_of(pauli));
+            }
+        }
+        faults
+    }
+
+    pub fn biased_decoder(&self, bias: &BiasedNoise) -> SpacetimeDecoder {
+        SpacetimeDecoder::new(
+            self.biased_faults(bias),
+            self.get_stabilizers().len(),
+            self.distance(),
+        )
+    }
+
+    // Most likely correction under `bias` rather than the minimum-weight one
+    // decode returns
+    pub fn decode_biased(
+        &self,
+        syndrome: &[bool],
+        bias: &BiasedNoise,
+    ) -> Result<PauliString, QuantumError> {
+        self.biased_decoder(bias).decode(syndrome)
+    }
+}
@@ -0,0 +1,142 @@
+use super::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Vendor-style calibration snapshot. Times share one unit (e.g. seconds);
+// error rates are average gate infidelities, treated as depolarizing
+// probabilities.
+//
+// {
+//   "qubits": [{"qubit": 0, "t1": 1.1e-4, "t2": 9.0e-5, "readout_error": 0.02}, ...],
+//   "gates": [{"gate": "sx", "qubits": [0], "error": 2.4e-4, "duration": 3.5e-8},
+//             {"gate": "cx", "qubits": [0, 1], "error": 8.1e-3, "duration": 4.4e-7}, ...],
+//   "coupling_map": [[0, 1], [1, 2]],
+//   "temperature": 0.05,
+//   "crosstalk_error": 1e-4
+// }
+#[derive(Deserialize)]
+struct Calibration {
+    qubits: Vec<QubitCalibration>,
+    #[serde(default)]
+    gates: Vec<GateCalibration>,
+    #[serde(default)]
+    coupling_map: Vec<(usize, usize)>,
+    // Duration of one idle noise layer; defaults per qubit to its longest
+    // single-qubit gate
+    #[serde(default)]
+    layer_time: Option<f64>,
+    #[serde(default)]
+    temperature: f64,
+    #[serde(default)]
+    crosstalk_error: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct QubitCalibration {
+    qubit: usize,
+    t1: f64,
+    t2: f64,
+    // Symmetric readout error, or the two directions separately
+    #[serde(default)]
+    readout_error: Option<f64>,
+    #[serde(default)]
+    prob_meas1_prep0: Option<f64>,
+    #[serde(default)]
+    prob_meas0_prep1: Option<f64>,
+}
+
+// Gates are told apart by the qubits they act on; names are not needed
+#[derive(Deserialize)]
+struct GateCalibration {
+    qubits: Vec<usize>,
+    error: f64,
+    #[serde(default)]
+    duration: Option<f64>,
+}
+
+impl NoiseModel {
+    // Composite model for a calibrated device: per qubit, thermal relaxation
+    // over each idle layer and its own readout confusion; per gate, a
+    // depolarizing error on the qubits it acts on (the worst calibrated gate
+    // when several share those qubits); crosstalk along the coupling map
+    // when a crosstalk error is given. Two-qubit gates must be on coupled
+    // pairs when a coupling map is present.
+    pub fn from_calibration(json: &str) -> Result<NoiseModel, QuantumError> {
+        let calibration: Calibration =
+            serde_json::from_str(json).map_err(|_| QuantumError::InvalidCalibration)?;
+        let coupling = CouplingMap::new(&calibration.coupling_map);
+
+        let mut worst: BTreeMap<Vec<usize>, (f64, f64)> = BTreeMap::new();
+        for gate in &calibration.gates {
+            let mut qubits = gate.qubits.clone();
+            qubits.sort_unstable();
+            qubits.dedup();
+            if qubits.len() != gate.qubits.len() || !(1..=2).contains(&qubits.len()) {
+                return Err(QuantumError::InvalidCalibration);
+            }
+            if qubits.len() == 2
+                && !calibration.coupling_map.is_empty()
+                && !coupling.are_coupled(qubits[0], qubits[1])
+            {
+                return Err(QuantumError::InvalidCalibration);
+            }
+            let entry = worst.entry(qubits).or_insert((0.0, 0.0));
+            entry.0 = entry.0.max(gate.error);
+            entry.1 = entry.1.max(gate.duration.unwrap_or(0.0));
+        }
+
+        let mut model = NoiseModel::noiseless();
+        for qubit in &calibration.qubits {
+            let q = qubit.qubit;
+            let layer_time = calibration
+                .layer_time
+                .or_else(|| worst.get(&vec![q]).map(|&(_, duration)| duration))
+                .filter(|&t| t > 0.0)
+                .ok_or(QuantumError::InvalidCalibration)?;
+            // Published T2 can exceed 2 T1 within calibration error
+            let t2 = qubit.t2.min(2.0 * qubit.t1);
+            if qubit.t1 <= 0.0 || t2 <= 0.0 || calibration.temperature < 0.0 {
+                return Err(QuantumError::InvalidCalibration);
+            }
+            let relaxation = NoiseModel::noiseless().with_thermal_relaxation(
+                qubit.t1,
+                t2,
+                layer_time,
+                calibration.temperature,
+            );
+            model = model.with_qubit_override(q, relaxation);
+
+            let readout = match (
+                qubit.prob_meas1_prep0,
+                qubit.prob_meas0_prep1,
+                qubit.readout_error,
+            ) {
+                (Some(false_positive), Some(false_negative), _) => {
+                    Some(ConfusionMatrix::new(false_positive, false_negative))
+                }
+                (_, _, Some(error)) => Some(ConfusionMatrix::symmetric(error)),
+                _ => None,
+            };
+            if let Some(readout) = readout {
+                let readout = readout.map_err(|_| QuantumError::InvalidCalibration)?;
+                model = model.with_qubit_readout_error(q, readout);
+            }
+        }
+
+        for (qubits, (error, _)) in worst {
+            let channel = match qubits.len() {
+                1 => QuantumChannelOp::depolarizing(error),
+                _ => QuantumChannelOp::two_qubit_depolarizing(error),
+            }
+            .map_err(|_| QuantumError::InvalidCalibration)?;
+            model = model.with_gate_error(qubits, channel)?;
+        }
+
+        if let Some(error) = calibration.crosstalk_error {
+            let crosstalk = Crosstalk::dephasing(coupling, error)
+                .map_err(|_| QuantumError::InvalidCalibration)?;
+            model = model.with_crosstalk(crosstalk);
+        }
+        Ok(model)
+    }
+}
@@ -0,0 +1,177 @@
+use super::*;
+use num_complex::Complex64;
+use rand::Rng;
+
+const MAX_CHOI_INPUT_QUBITS: usize = 4;
+const MAX_CHANNEL_QUBITS: usize = 12;
+
+// Prover-side encoding, transmission noise and verifier-side decoding composed
+// into a single channel on `input_qubits`. Encoding and decoding act on the
+// input register (qubits 0..k) together with `ancilla_qubits` workspace qubits
+// (k..k+a), which start in |0> and are traced out after decoding.
+#[derive(Clone, Debug)]
+pub struct ProtocolChannel {
+    input_qubits: usize,
+    ancilla_qubits: usize,
+    encoding: QuantumCircuit,
+    noise: Option<NoiseModel>,
+    decoding: QuantumCircuit,
+}
+
+impl ProtocolChannel {
+    pub fn new(input_qubits: usize, ancilla_qubits: usize) -> Self {
+        let width = input_qubits + ancilla_qubits;
+        Self {
+            input_qubits,
+            ancilla_qubits,
+            encoding: QuantumCircuit::new(width),
+            noise: None,
+            decoding: QuantumCircuit::new(width),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: QuantumCircuit) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_noise(mut self, noise: NoiseModel) -> Self {
+        self.noise = Some(noise);
+        self
+    }
+
+    pub fn with_decoding(mut self, decoding: QuantumCircuit) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    fn width(&self) -> usize {
+        self.input_qubits + self.ancilla_qubits
+    }
+
+    // One trajectory of the channel; the returned state still holds the ancillas
+    pub fn apply<R: Rng + ?Sized>(
+        &self,
+        input: &QuantumState,
+        rng: &mut R,
+    ) -> Result<QuantumState, QuantumError> {
+        if input.num_qubits != self.input_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        if self.encoding.num_qubits() > self.width() || self.decoding.num_qubits() > self.width() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut state = QuantumState::new(self.width())?;
+        state.amplitudes[..input.amplitudes.len()].copy_from_slice(&input.amplitudes);
+
+        self.encoding.execute(&mut state)?;
+        if let Some(noise) = &self.noise {
+            noise.apply_noise(&mut state, rng)?;
+        }
+        self.decoding.execute(&mut state)?;
+
+        Ok(state)
+    }
+
+    // Monte Carlo estimate of E(|psi><psi|) averaged over noise trajectories
+    fn output_density<R: Rng + ?Sized>(
+        &self,
+        input: &QuantumState,
+        samples: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Complex64>, QuantumError> {
+        let ancillas: Vec<usize> = (self.input_qubits..self.width()).collect();
+        let d = 1 << self.input_qubits;
+        let mut average = vec![Complex64::new(0.0, 0.0); d * d];
+
+        for _ in 0..samples {
+            let output = self.apply(input, rng)?.reduced_density_matrix(&ancillas)?;
+            for (a, e) in average.iter_mut().zip(output.elements.iter()) {
+                *a += e / samples as f64;
+            }
+        }
+
+        Ok(average)
+    }
+
+    // Normalised Choi matrix J = (1/d) sum_{ij} E(|i><j|) (x) |i><j|, with the
+    // channel output on qubits 0..k and the reference copy of the input on
+    // qubits k..2k. Off-diagonal blocks are recovered by polarisation from
+    // the pure inputs |i>, |j>, (|i> + |j>)/sqrt(2) and (|i> + i|j>)/sqrt(2).
+    // Noisy channels are estimated from `samples` trajectories per input.
+    pub fn choi_matrix<R: Rng + ?Sized>(
+        &self,
+        samples: usize,
+        rng: &mut R,
+    ) -> Result<DensityMatrix, QuantumError> {
+        if self.input_qubits > MAX_CHOI_INPUT_QUBITS || self.width() > MAX_CHANNEL_QUBITS {
+            return Err(QuantumError::RegisterTooLarge);
+        }
+
+        let samples = if self.noise.is_some() {
+            samples.max(1)
+        } else {
+            1
+        };
+        let k = self.input_qubits;
+        let d = 1 << k;
+
+        let basis_input = |amplitudes: &[(usize, Complex64)]| {
+            let mut state = QuantumState::new(k)?;
+            state.amplitudes[0] = Complex64::new(0.0, 0.0);
+            for (index, amplitude) in amplitudes {
+                state.amplitudes[*index] = *amplitude;
+            }
+            Ok::<_, QuantumError>(state)
+        };
+        let one = Complex64::new(1.0, 0.0);
+        let h = Complex64::new(0.5f64.sqrt(), 0.0);
+        let ih = Complex64::new(0.0, 0.5f64.sqrt());
+
+        let diagonal = (0..d)
+            .map(|i| self.output_density(&basis_input(&[(i, one)])?, samples, rng))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let dimension = d * d;
+        let mut elements = vec![Complex64::new(0.0, 0.0); dimension * dimension];
+        let mut place_block = |i: usize, j: usize, block: &[Complex64]| {
+            for a in 0..d {
+                for b in 0..d {
+                    let row = a | (i << k);
+                    let column = b | (j << k);
+                    elements[row * dimension + column] = block[a * d + b] / d as f64;
+                }
+            }
+        };
+
+        for i in 0..d {
+            place_block(i, i, &diagonal[i]);
+            for j in (i + 1)..d {
+                let plus = self.output_density(&basis_input(&[(i, h), (j, h)])?, samples, rng)?;
+                let plus_i = self.output_density(&basis_input(&[(i, h), (j, ih)])?, samples, rng)?;
+
+                let correction = Complex64::new(0.5, 0.5);
+                let block: Vec<Complex64> = (0..d * d)
+                    .map(|e| {
+                        plus[e] + Complex64::i() * plus_i[e]
+                            - correction * (diagonal[i][e] + diagonal[j][e])
+                    })
+                    .collect();
+                // E(|j><i|) = E(|i><j|)^dagger
+                let adjoint: Vec<Complex64> = (0..d * d)
+                    .map(|e| block[(e % d) * d + e / d].conj())
+                    .collect();
+
+                place_block(i, j, &block);
+                place_block(j, i, &adjoint);
+            }
+        }
+
+        Ok(DensityMatrix {
+            num_qubits: 2 * k,
+            dimension,
+            elements,
+        })
+    }
+}
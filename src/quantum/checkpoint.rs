@@ -0,0 +1,104 @@
+use super::*;
+use rand::Rng;
+
+#[derive(Clone, Debug)]
+pub struct StateCheckpoint {
+    state: QuantumState,
+}
+
+impl StateCheckpoint {
+    pub fn num_qubits(&self) -> usize {
+        self.state.num_qubits
+    }
+
+    // A fresh state starting from the checkpoint, leaving the checkpoint reusable
+    pub fn branch(&self) -> QuantumState {
+        self.state.clone()
+    }
+}
+
+impl QuantumState {
+    pub fn checkpoint(&self) -> StateCheckpoint {
+        StateCheckpoint {
+            state: self.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, checkpoint: &StateCheckpoint) -> Result<(), QuantumError> {
+        if checkpoint.state.num_qubits != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        self.clone_from(&checkpoint.state);
+        Ok(())
+    }
+}
+
+// Snapshot of a circuit run after the first `position` operations
+#[derive(Clone, Debug)]
+pub struct CircuitCheckpoint {
+    position: usize,
+    state: StateCheckpoint,
+    clbits: Vec<bool>,
+}
+
+impl CircuitCheckpoint {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn state(&self) -> &StateCheckpoint {
+        &self.state
+    }
+
+    pub fn clbits(&self) -> &[bool] {
+        &self.clbits
+    }
+}
+
+impl QuantumCircuit {
+    pub fn run_until<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        position: usize,
+        rng: &mut R,
+    ) -> Result<CircuitCheckpoint, QuantumError> {
+        if position > self.len() {
+            return Err(QuantumError::InvalidCheckpoint);
+        }
+        if state.num_qubits < self.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut clbits = vec![false; self.num_clbits()];
+        self.run_range(state, 0..position, &mut clbits, rng)?;
+
+        Ok(CircuitCheckpoint {
+            position,
+            state: state.checkpoint(),
+            clbits,
+        })
+    }
+
+    // Runs the remaining operations from a checkpoint on a fresh branch, so the
+    // same prefix can be resumed any number of times (e.g. for rewinding)
+    pub fn resume<R: Rng + ?Sized>(
+        &self,
+        checkpoint: &CircuitCheckpoint,
+        rng: &mut R,
+    ) -> Result<(QuantumState, Vec<bool>), QuantumError> {
+        if checkpoint.position > self.len() || checkpoint.clbits.len() != self.num_clbits() {
+            return Err(QuantumError::InvalidCheckpoint);
+        }
+
+        let mut state = checkpoint.state.branch();
+        let mut clbits = checkpoint.clbits.clone();
+        self.run_range(
+            &mut state,
+            checkpoint.position..self.len(),
+            &mut clbits,
+            rng,
+        )?;
+
+        Ok((state, clbits))
+    }
+}
@@ -0,0 +1,423 @@
+use super::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Clone, Debug)]
+pub struct GateInstruction {
+    pub gate: QuantumGate,
+    pub target: usize,
+}
+
+// A condition on a classical register. Indices name classical bits, the
+// ones Measure { clbit } writes, never positions in a state's measurement
+// history, which can differ whenever bits are written out of order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MeasurementRef {
+    // Classical bit i
+    Bit(usize),
+    // XOR of the listed classical bits
+    Parity(Vec<usize>),
+}
+
+impl MeasurementRef {
+    pub fn evaluate(&self, bits: &[bool]) -> Result<bool, QuantumError> {
+        let lookup = |index: &usize| {
+            bits.get(*index)
+                .copied()
+                .ok_or(QuantumError::InvalidClassicalBit)
+        };
+        match self {
+            MeasurementRef::Bit(index) => lookup(index),
+            MeasurementRef::Parity(indices) => indices
+                .iter()
+                .try_fold(false, |parity, index| Ok(parity ^ lookup(index)?)),
+        }
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self {
+            MeasurementRef::Bit(index) => Some(*index),
+            MeasurementRef::Parity(indices) => indices.iter().copied().max(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CircuitOperation {
+    Gate(GateInstruction),
+    Measure {
+        qubit: usize,
+        clbit: usize,
+    },
+    Reset(usize),
+    ClassicallyControlled {
+        condition: MeasurementRef,
+        gate: QuantumGate,
+        target: usize,
+    },
+}
+
+impl CircuitOperation {
+    pub fn is_unitary(&self) -> bool {
+        matches!(self, CircuitOperation::Gate(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QuantumCircuit {
+    num_qubits: usize,
+    num_clbits: usize,
+    operations: Vec<CircuitOperation>,
+}
+
+impl QuantumCircuit {
+    pub fn new(num_qubits: usize) -> Self {
+        Self {
+            num_qubits,
+            num_clbits: 0,
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn num_clbits(&self) -> usize {
+        self.num_clbits
+    }
+
+    pub fn operations(&self) -> &[CircuitOperation] {
+        &self.operations
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &GateInstruction> {
+        self.operations.iter().filter_map(|op| match op {
+            CircuitOperation::Gate(instruction) => Some(instruction),
+            _ => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn is_unitary(&self) -> bool {
+        self.operations.iter().all(|op| op.is_unitary())
+    }
+
+    pub fn add_gate(&mut self, gate: QuantumGate, target: usize) -> &mut Self {
+        self.operations
+            .push(CircuitOperation::Gate(GateInstruction { gate, target }));
+        self
+    }
+
+    pub fn add_measurement(&mut self, qubit: usize, clbit: usize) -> &mut Self {
+        self.num_clbits = self.num_clbits.max(clbit + 1);
+        self.operations
+            .push(CircuitOperation::Measure { qubit, clbit });
+        self
+    }
+
+    pub fn add_reset(&mut self, qubit: usize) -> &mut Self {
+        self.operations.push(CircuitOperation::Reset(qubit));
+        self
+    }
+
+    pub fn add_classically_controlled(
+        &mut self,
+        condition: MeasurementRef,
+        gate: QuantumGate,
+        target: usize,
+    ) -> &mut Self {
+        self.push_operation(CircuitOperation::ClassicallyControlled {
+            condition,
+            gate,
+            target,
+        })
+    }
+
+    pub(crate) fn push_operation(&mut self, operation: CircuitOperation) -> &mut Self {
+        match &operation {
+            CircuitOperation::Measure { clbit, .. } => {
+                self.num_clbits = self.num_clbits.max(clbit + 1);
+            }
+            CircuitOperation::ClassicallyControlled { condition, .. } => {
+                if let Some(index) = condition.max_index() {
+                    self.num_clbits = self.num_clbits.max(index + 1);
+                }
+            }
+            _ => {}
+        }
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn extend(&mut self, other: &QuantumCircuit) -> Result<&mut Self, QuantumError> {
+        if other.num_qubits > self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        self.num_clbits = self.num_clbits.max(other.num_clbits);
+        self.operations.extend(other.operations.iter().cloned());
+        Ok(self)
+    }
+
+    pub fn inverse(&self) -> Result<Self, QuantumError> {
+        let operations = self
+            .operations
+            .iter()
+            .rev()
+            .map(|op| match op {
+                CircuitOperation::Gate(instruction) => {
+                    Ok(CircuitOperation::Gate(GateInstruction {
+                        gate: instruction.gate.dagger(),
+                        target: instruction.target,
+                    }))
+                }
+                _ => Err(QuantumError::NonUnitaryOperation),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            num_qubits: self.num_qubits,
+            num_clbits: self.num_clbits,
+            operations,
+        })
+    }
+
+    // Applies a measurement-free circuit
+    pub fn execute(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        if state.num_qubits < self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        for operation in &self.operations {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    state.apply_gate(instruction.gate.clone(), instruction.target)?
+                }
+                _ => return Err(QuantumError::NonUnitaryOperation),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Applies the circuit including measurements and resets, returning the classical register
+    pub fn run<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<Vec<bool>, QuantumError> {
+        if state.num_qubits < self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut clbits = vec![false; self.num_clbits];
+        self.run_range(state, 0..self.operations.len(), &mut clbits, rng)?;
+
+        Ok(clbits)
+    }
+
+    pub(crate) fn run_range<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        range: Range<usize>,
+        clbits: &mut [bool],
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        for operation in &self.operations[range] {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    state.apply_gate(instruction.gate.clone(), instruction.target)?
+                }
+                CircuitOperation::Measure { qubit, clbit } => {
+                    clbits[*clbit] = state.measure_qubit(*qubit, rng)?;
+                }
+                CircuitOperation::Reset(qubit) => {
+                    state.reset(*qubit, rng)?;
+                }
+                CircuitOperation::ClassicallyControlled {
+                    condition,
+                    gate,
+                    target,
+                } => {
+                    if condition.evaluate(clbits)? {
+                        state.apply_gate(gate.clone(), *target)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Splits off trailing measurements when nothing else follows them, so the
+    // pre-measurement state can be simulated once and sampled repeatedly
+    fn terminal_measurements(&self) -> Option<(usize, Vec<(usize, usize)>)> {
+        let prefix = self
+            .operations
+            .iter()
+            .rposition(|op| !matches!(op, CircuitOperation::Measure { .. }))
+            .map_or(0, |i| i + 1);
+        if !self.operations[..prefix].iter().all(|op| op.is_unitary()) {
+            return None;
+        }
+
+        let measurements = self.operations[prefix..]
+            .iter()
+            .map(|op| match op {
+                CircuitOperation::Measure { qubit, clbit } => (*qubit, *clbit),
+                _ => unreachable!(),
+            })
+            .collect();
+        Some((prefix, measurements))
+    }
+
+    // Executes the circuit `shots` times from `initial`. Measurement-free
+    // circuits are sampled on every qubit; circuits whose measurements are all
+    // terminal reuse a single simulation; anything else runs shots in parallel.
+    pub fn run_shots<R: Rng + ?Sized>(
+        &self,
+        initial: &QuantumState,
+        shots: usize,
+        rng: &mut R,
+    ) -> Result<ShotResults, QuantumError> {
+        if initial.num_qubits < self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut results = ShotResults::new(shots);
+
+        if let Some((prefix, measurements)) = self.terminal_measurements() {
+            let mut state = initial.clone();
+            for operation in &self.operations[..prefix] {
+                if let CircuitOperation::Gate(instruction) = operation {
+                    state.apply_gate(instruction.gate.clone(), instruction.target)?;
+                }
+            }
+
+            for (basis, count) in state.sample_counts(shots, rng) {
+                let bits = if measurements.is_empty() {
+                    (0..state.num_qubits)
+                        .map(|q| (basis >> q) & 1 == 1)
+                        .collect()
+                } else {
+                    let mut clbits = vec![false; self.num_clbits];
+                    for (qubit, clbit) in &measurements {
+                        clbits[*clbit] = (basis >> qubit) & 1 == 1;
+                    }
+                    clbits
+                };
+                *results.counts.entry(bits).or_insert(0) += count;
+            }
+
+            return Ok(results);
+        }
+
+        let seeds: Vec<u64> = (0..shots).map(|_| rng.gen()).collect();
+        let outcomes = seeds
+            .par_iter()
+            .map(|seed| {
+                let mut shot_rng = ChaCha20Rng::seed_from_u64(*seed);
+                let mut state = initial.clone();
+                self.run(&mut state, &mut shot_rng)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for bits in outcomes {
+            *results.counts.entry(bits).or_insert(0) += 1;
+        }
+
+        Ok(results)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShotResults {
+    pub shots: usize,
+    pub counts: HashMap<Vec<bool>, usize>,
+}
+
+impl ShotResults {
+    fn new(shots: usize) -> Self {
+        Self {
+            shots,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn probability(&self, bits: &[bool]) -> f64 {
+        if self.shots == 0 {
+            return 0.0;
+        }
+        self.counts.get(bits).copied().unwrap_or(0) as f64 / self.shots as f64
+    }
+
+    pub fn most_frequent(&self) -> Option<(&[bool], usize)> {
+        self.counts
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(bits, count)| (bits.as_slice(), *count))
+    }
+
+    // Marginal probability that classical bit `index` reads 1
+    pub fn marginal(&self, index: usize) -> f64 {
+        if self.shots == 0 {
+            return 0.0;
+        }
+        let ones: usize = self
+            .counts
+            .iter()
+            .filter(|(bits, _)| bits.get(index).copied().unwrap_or(false))
+            .map(|(_, count)| count)
+            .sum();
+        ones as f64 / self.shots as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Qubit 1 is measured first but into classical bit 0, so the bit a
+    // condition names and the outcome at that place in the history differ
+    #[test]
+    fn conditions_read_classical_bits_not_history_order() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1027);
+        let mut circuit = QuantumCircuit::new(3);
+        circuit
+            .add_gate(QuantumGate::PauliX, 1)
+            .add_measurement(0, 1)
+            .add_measurement(1, 0)
+            .add_classically_controlled(MeasurementRef::Bit(0), QuantumGate::PauliX, 2)
+            .add_measurement(2, 2);
+
+        let mut state = QuantumState::new(3).unwrap();
+        let clbits = circuit.run(&mut state, &mut rng).unwrap();
+        assert_eq!(clbits, vec![true, false, true]);
+        assert_eq!(state.classical_outcomes(), &[false, true, true]);
+
+        let mut state = QuantumState::new(3).unwrap();
+        state.apply_gate(QuantumGate::PauliX, 1).unwrap();
+        state.measure_qubit(0, &mut rng).unwrap();
+        state.measure_qubit(1, &mut rng).unwrap();
+        let clbits = [true, false];
+        let bit = MeasurementRef::Bit(0);
+        let parity = MeasurementRef::Parity(vec![0, 1, 0]);
+        assert!(state
+            .apply_classically_controlled(&bit, &clbits, QuantumGate::PauliX, 2)
+            .unwrap());
+        assert!(!state
+            .apply_classically_controlled(&parity, &clbits, QuantumGate::PauliX, 2)
+            .unwrap());
+        assert!(state.measure_qubit(2, &mut rng).unwrap());
+    }
+}
@@ -0,0 +1,407 @@
+use super::*;
+use rand::Rng;
+use std::f64::consts::PI;
+
+const ANGLE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TableauRow {
+    pub x: Vec<bool>,
+    pub z: Vec<bool>,
+    // true means the Pauli carries a -1 sign
+    pub sign: bool,
+}
+
+impl TableauRow {
+    fn identity(num_qubits: usize) -> Self {
+        Self {
+            x: vec![false; num_qubits],
+            z: vec![false; num_qubits],
+            sign: false,
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        self.x.iter().chain(self.z.iter()).all(|b| !b)
+    }
+
+    // Sign followed by one of I, X, Y, Z per qubit, qubit 0 first
+    pub fn to_pauli_string(&self) -> String {
+        let mut pauli = String::with_capacity(self.x.len() + 1);
+        pauli.push(if self.sign { '-' } else { '+' });
+        for (x, z) in self.x.iter().zip(self.z.iter()) {
+            pauli.push(match (x, z) {
+                (false, false) => 'I',
+                (true, false) => 'X',
+                (true, true) => 'Y',
+                (false, true) => 'Z',
+            });
+        }
+        pauli
+    }
+
+    pub fn to_pauli(&self) -> PauliString {
+        PauliString::from_xz(
+            self.x.clone(),
+            self.z.clone(),
+            if self.sign { 2 } else { 0 },
+        )
+        .expect("tableau rows have matching x and z lengths")
+    }
+
+    // U P U^dagger for a single Clifford gate U, e.g. to push a Pauli frame
+    // through a circuit
+    pub(crate) fn conjugate_by(
+        &mut self,
+        gate: &QuantumGate,
+        target: usize,
+    ) -> Result<(), QuantumError> {
+        for op in CliffordOp::from_gate(gate, target)? {
+            op.conjugate(std::slice::from_mut(self));
+        }
+        Ok(())
+    }
+
+    fn anticommutes(&self, other: &Self) -> bool {
+        (0..self.x.len())
+            .filter(|&j| (self.x[j] & other.z[j]) ^ (self.z[j] & other.x[j]))
+            .count()
+            % 2
+            == 1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CliffordOp {
+    H(usize),
+    S(usize),
+    SDagger(usize),
+    X(usize),
+    Z(usize),
+    Cnot(usize, usize),
+    Swap(usize, usize),
+}
+
+impl CliffordOp {
+    fn inverse(self) -> Self {
+        match self {
+            CliffordOp::S(q) => CliffordOp::SDagger(q),
+            CliffordOp::SDagger(q) => CliffordOp::S(q),
+            op => op,
+        }
+    }
+
+    // Conjugation P -> U P U^dagger, following Aaronson-Gottesman
+    fn conjugate(self, rows: &mut [TableauRow]) {
+        for row in rows {
+            match self {
+                CliffordOp::H(a) => {
+                    row.sign ^= row.x[a] & row.z[a];
+                    std::mem::swap(&mut row.x[a], &mut row.z[a]);
+                }
+                CliffordOp::S(a) => {
+                    row.sign ^= row.x[a] & row.z[a];
+                    row.z[a] ^= row.x[a];
+                }
+                CliffordOp::SDagger(a) => {
+                    row.sign ^= row.x[a] & !row.z[a];
+                    row.z[a] ^= row.x[a];
+                }
+                CliffordOp::X(a) => row.sign ^= row.z[a],
+                CliffordOp::Z(a) => row.sign ^= row.x[a],
+                CliffordOp::Cnot(a, b) => {
+                    row.sign ^= row.x[a] & row.z[b] & !(row.x[b] ^ row.z[a]);
+                    row.x[b] ^= row.x[a];
+                    row.z[a] ^= row.z[b];
+                }
+                CliffordOp::Swap(a, b) => {
+                    row.x.swap(a, b);
+                    row.z.swap(a, b);
+                }
+            }
+        }
+    }
+
+    fn to_gate(self) -> (QuantumGate, usize) {
+        match self {
+            CliffordOp::H(q) => (QuantumGate::Hadamard, q),
+            CliffordOp::S(q) => (QuantumGate::Phase(PI / 2.0), q),
+            CliffordOp::SDagger(q) => (QuantumGate::Phase(-PI / 2.0), q),
+            CliffordOp::X(q) => (QuantumGate::PauliX, q),
+            CliffordOp::Z(q) => (QuantumGate::PauliZ, q),
+            CliffordOp::Cnot(c, t) => (QuantumGate::CNOT(c), t),
+            CliffordOp::Swap(a, b) => (QuantumGate::Swap(a), b),
+        }
+    }
+
+    fn from_gate(gate: &QuantumGate, target: usize) -> Result<Vec<Self>, QuantumError> {
+        let quarter_turns = |phi: f64| {
+            let turns = phi / (PI / 2.0);
+            if (turns - turns.round()).abs() > ANGLE_TOLERANCE {
+                None
+            } else {
+                Some((turns.round() as i64).rem_euclid(4))
+            }
+        };
+
+        let ops = match gate {
+            QuantumGate::Hadamard => vec![CliffordOp::H(target)],
+            QuantumGate::PauliX => vec![CliffordOp::X(target)],
+            QuantumGate::PauliY => vec![CliffordOp::X(target), CliffordOp::Z(target)],
+            QuantumGate::PauliZ => vec![CliffordOp::Z(target)],
+            QuantumGate::Phase(phi) => match quarter_turns(*phi) {
+                Some(0) => vec![],
+                Some(1) => vec![CliffordOp::S(target)],
+                Some(2) => vec![CliffordOp::Z(target)],
+                Some(3) => vec![CliffordOp::SDagger(target)],
+                _ => return Err(QuantumError::NonCliffordGate),
+            },
+            QuantumGate::SqrtX => vec![
+                CliffordOp::H(target),
+                CliffordOp::S(target),
+                CliffordOp::H(target),
+            ],
+            QuantumGate::SqrtXDagger => vec![
+                CliffordOp::H(target),
+                CliffordOp::SDagger(target),
+                CliffordOp::H(target),
+            ],
+            QuantumGate::CNOT(control) => vec![CliffordOp::Cnot(*control, target)],
+            QuantumGate::Swap(other) => vec![CliffordOp::Swap(*other, target)],
+            _ => return Err(QuantumError::NonCliffordGate),
+        };
+        Ok(ops)
+    }
+}
+
+// Canonical Aaronson-Gottesman tableau of a Clifford C: row i is C X_i C^dagger
+// (destabilizers) and row n + i is C Z_i C^dagger (stabilizers), with signs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CliffordTableau {
+    num_qubits: usize,
+    rows: Vec<TableauRow>,
+}
+
+impl CliffordTableau {
+    pub fn identity(num_qubits: usize) -> Self {
+        let mut rows = vec![TableauRow::identity(num_qubits); 2 * num_qubits];
+        for q in 0..num_qubits {
+            rows[q].x[q] = true;
+            rows[num_qubits + q].z[q] = true;
+        }
+        Self { num_qubits, rows }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    // Tableau with the given images of X_i and Z_i, which must satisfy the
+    // canonical commutation relations for the result to be a Clifford
+    pub fn from_rows(
+        destabilizers: Vec<TableauRow>,
+        stabilizers: Vec<TableauRow>,
+    ) -> Result<Self, QuantumError> {
+        let num_qubits = destabilizers.len();
+        let rows: Vec<TableauRow> = destabilizers.into_iter().chain(stabilizers).collect();
+        if rows.len() != 2 * num_qubits
+            || rows
+                .iter()
+                .any(|row| row.x.len() != num_qubits || row.z.len() != num_qubits)
+        {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        for i in 0..rows.len() {
+            for j in i + 1..rows.len() {
+                let paired = i < num_qubits && j == i + num_qubits;
+                if rows[i].anticommutes(&rows[j]) != paired {
+                    return Err(QuantumError::InvalidTableau);
+                }
+            }
+        }
+        Ok(Self { num_qubits, rows })
+    }
+
+    pub fn destabilizer(&self, qubit: usize) -> &TableauRow {
+        &self.rows[qubit]
+    }
+
+    pub fn stabilizer(&self, qubit: usize) -> &TableauRow {
+        &self.rows[self.num_qubits + qubit]
+    }
+
+    // Destabilizers followed by stabilizers, as signed Pauli strings
+    pub fn to_pauli_strings(&self) -> Vec<String> {
+        self.rows.iter().map(TableauRow::to_pauli_string).collect()
+    }
+
+    pub fn from_circuit(circuit: &QuantumCircuit) -> Result<Self, QuantumError> {
+        let mut tableau = Self::identity(circuit.num_qubits());
+        for operation in circuit.operations() {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    for op in CliffordOp::from_gate(&instruction.gate, instruction.target)? {
+                        op.conjugate(&mut tableau.rows);
+                    }
+                }
+                _ => return Err(QuantumError::NonUnitaryOperation),
+            }
+        }
+        Ok(tableau)
+    }
+
+    // Uniformly random element of the n-qubit Clifford group (modulo global
+    // phase), using the sweeping method of van den Berg (2021): for each qubit
+    // draw a random anticommuting Pauli pair on the remaining qubits with
+    // random signs, and take the Clifford mapping (X_i, Z_i) onto that pair.
+    pub fn random<R: Rng + ?Sized>(num_qubits: usize, rng: &mut R) -> Self {
+        let ops = random_clifford_ops(num_qubits, rng);
+        let mut tableau = Self::identity(num_qubits);
+        for op in ops {
+            op.conjugate(&mut tableau.rows);
+        }
+        tableau
+    }
+
+    // Synthesises a circuit by sweeping the tableau back to the identity
+    pub fn to_circuit(&self) -> QuantumCircuit {
+        let mut rows = self.rows.clone();
+        let mut sweep = Vec::new();
+        for qubit in 0..self.num_qubits {
+            let mut pair = [rows[qubit].clone(), rows[self.num_qubits + qubit].clone()];
+            let ops = sweep_pair(&mut pair, qubit);
+            for op in &ops {
+                op.conjugate(&mut rows);
+            }
+            sweep.extend(ops);
+        }
+
+        // sweep * C = identity, so C = sweep^{-1}
+        let ops: Vec<CliffordOp> = sweep.into_iter().rev().map(CliffordOp::inverse).collect();
+        circuit_from_ops(self.num_qubits, &ops)
+    }
+}
+
+pub fn random_clifford_circuit<R: Rng + ?Sized>(num_qubits: usize, rng: &mut R) -> QuantumCircuit {
+    circuit_from_ops(num_qubits, &random_clifford_ops(num_qubits, rng))
+}
+
+fn circuit_from_ops(num_qubits: usize, ops: &[CliffordOp]) -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(num_qubits);
+    for op in ops {
+        let (gate, target) = op.to_gate();
+        circuit.add_gate(gate, target);
+    }
+    circuit
+}
+
+fn random_clifford_ops<R: Rng + ?Sized>(num_qubits: usize, rng: &mut R) -> Vec<CliffordOp> {
+    let mut layers = Vec::with_capacity(num_qubits);
+
+    for qubit in 0..num_qubits {
+        let random_row = |rng: &mut R| {
+            let mut row = TableauRow::identity(num_qubits);
+            for j in qubit..num_qubits {
+                row.x[j] = rng.gen();
+                row.z[j] = rng.gen();
+            }
+            row.sign = rng.gen();
+            row
+        };
+
+        let p = loop {
+            let row = random_row(rng);
+            if !row.is_identity() {
+                break row;
+            }
+        };
+        let q = loop {
+            let row = random_row(rng);
+            if row.anticommutes(&p) {
+                break row;
+            }
+        };
+
+        // The sweep maps (p, q) to (X_i, Z_i); its inverse maps (X_i, Z_i) to (p, q)
+        let mut pair = [p, q];
+        let sweep = sweep_pair(&mut pair, qubit);
+        layers.push(
+            sweep
+                .into_iter()
+                .rev()
+                .map(CliffordOp::inverse)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // C = C_0 C_1 ... C_{n-1}, so C_{n-1} is applied first
+    layers.into_iter().rev().flatten().collect()
+}
+
+// Returns gates on qubits >= `qubit` that conjugate an anticommuting pair
+// (p, q) to (+X_qubit, +Z_qubit), updating the pair in place
+fn sweep_pair(pair: &mut [TableauRow; 2], qubit: usize) -> Vec<CliffordOp> {
+    let n = pair[0].x.len();
+    let mut ops = Vec::new();
+    fn apply(op: CliffordOp, pair: &mut [TableauRow; 2], ops: &mut Vec<CliffordOp>) {
+        op.conjugate(pair);
+        ops.push(op);
+    }
+
+    // Make p X-only, then fold its support onto a single qubit and move it to `qubit`
+    for j in qubit..n {
+        if pair[0].z[j] {
+            let op = if pair[0].x[j] {
+                CliffordOp::S(j)
+            } else {
+                CliffordOp::H(j)
+            };
+            apply(op, pair, &mut ops);
+        }
+    }
+    let mut support: Vec<usize> = (qubit..n).filter(|&j| pair[0].x[j]).collect();
+    while support.len() > 1 {
+        let mut next = Vec::with_capacity(support.len().div_ceil(2));
+        for chunk in support.chunks(2) {
+            if let [a, b] = chunk {
+                apply(CliffordOp::Cnot(*a, *b), pair, &mut ops);
+            }
+            next.push(chunk[0]);
+        }
+        support = next;
+    }
+    if support[0] != qubit {
+        apply(CliffordOp::Swap(support[0], qubit), pair, &mut ops);
+    }
+
+    // p = X_qubit; clear q down to Z_qubit while leaving p untouched
+    let q_is_z = (0..n).all(|j| !pair[1].x[j] && pair[1].z[j] == (j == qubit));
+    if !q_is_z {
+        apply(CliffordOp::H(qubit), pair, &mut ops);
+        for j in qubit..n {
+            if pair[1].z[j] {
+                let op = if pair[1].x[j] {
+                    CliffordOp::S(j)
+                } else {
+                    CliffordOp::H(j)
+                };
+                apply(op, pair, &mut ops);
+            }
+        }
+        for j in (qubit + 1)..n {
+            if pair[1].x[j] {
+                apply(CliffordOp::Cnot(qubit, j), pair, &mut ops);
+            }
+        }
+        apply(CliffordOp::H(qubit), pair, &mut ops);
+    }
+
+    if pair[0].sign {
+        apply(CliffordOp::Z(qubit), pair, &mut ops);
+    }
+    if pair[1].sign {
+        apply(CliffordOp::X(qubit), pair, &mut ops);
+    }
+
+    ops
+}
@@ -0,0 +1,208 @@
+use super::*;
+
+// Stabilizer code from arbitrary generators. build() rejects generators that
+// are not Hermitian, reach past the last qubit, anticommute or are dependent;
+// logical operators are either supplied as X/Z pairs and checked, or derived
+// from the normalizer.
+#[derive(Clone, Debug)]
+pub struct ErrorCorrectionCodeBuilder {
+    num_qubits: usize,
+    stabilizers: Vec<PauliString>,
+    logicals: Vec<(PauliString, PauliString)>,
+    distance: Option<usize>,
+}
+
+impl ErrorCorrectionCodeBuilder {
+    pub fn new(num_qubits: usize) -> Self {
+        Self {
+            num_qubits,
+            stabilizers: Vec::new(),
+            logicals: Vec::new(),
+            distance: None,
+        }
+    }
+
+    pub fn with_stabilizer(mut self, stabilizer: PauliString) -> Self {
+        self.stabilizers.push(stabilizer);
+        self
+    }
+
+    pub fn with_stabilizers<I>(mut self, stabilizers: I) -> Self
+    where
+        I: IntoIterator<Item = PauliString>,
+    {
+        self.stabilizers.extend(stabilizers);
+        self
+    }
+
+    pub fn with_logical_pair(mut self, x: PauliString, z: PauliString) -> Self {
+        self.logicals.push((x, z));
+        self
+    }
+
+    // Checked against compute_distance; without one the computed value is used
+    pub fn with_distance(mut self, distance: usize) -> Self {
+        self.distance = Some(distance);
+        self
+    }
+
+    pub fn build(&self) -> Result<ErrorCorrectionCode, QuantumError> {
+        let n = self.num_qubits;
+        if n == 0 {
+            return Err(QuantumError::InvalidDimension);
+        }
+        for stabilizer in &self.stabilizers {
+            self.check_operator(stabilizer)?;
+            if !stabilizer.is_hermitian() {
+                return Err(QuantumError::NonHermitianStabilizer);
+            }
+        }
+        for (i, a) in self.stabilizers.iter().enumerate() {
+            if self.stabilizers[i + 1..]
+                .iter()
+                .any(|b| !a.commutes_with(b))
+            {
+                return Err(QuantumError::StabilizersDoNotCommute);
+            }
+        }
+
+        let rows: Vec<Vec<bool>> = self
+            .stabilizers
+            .iter()
+            .map(|s| s.to_symplectic(n))
+            .collect();
+        if gf2::rank(&rows) < rows.len() {
+            return Err(QuantumError::DependentStabilizers);
+        }
+        let num_logical = n - rows.len();
+        if num_logical == 0 {
+            return Err(QuantumError::NoLogicalQubits);
+        }
+
+        let logicals = if self.logicals.is_empty() {
+            derive_logicals(&rows, n)
+        } else {
+            self.check_logicals(num_logical)?;
+            self.logicals.clone()
+        };
+        let computed = distance_of(
+            &self.stabilizers,
+            &logicals
+                .iter()
+                .flat_map(|(x, z)| [x.clone(), z.clone()])
+                .collect::<Vec<_>>(),
+            n,
+        );
+        let distance = match (self.distance, computed) {
+            (None, computed) => computed.value(),
+            (Some(d), CodeDistance::Exact(exact)) if d != exact => {
+                return Err(QuantumError::DistanceMismatch)
+            }
+            (Some(d), CodeDistance::UpperBound(bound)) if d > bound => {
+                return Err(QuantumError::DistanceMismatch)
+            }
+            (Some(d), _) => d,
+        };
+
+        let stabilizers = self
+            .stabilizers
+            .iter()
+            .cloned()
+            .map(Stabilizer::from_pauli)
+            .collect();
+        let logical_operators = logicals
+            .iter()
+            .flat_map(|(x, z)| {
+                [
+                    LogicalOperator::new(x.iter().collect(), OperatorType::X),
+                    LogicalOperator::new(z.iter().collect(), OperatorType::Z),
+                ]
+            })
+            .collect();
+        Ok(ErrorCorrectionCode::from_validated_parts(
+            n,
+            distance,
+            stabilizers,
+            logical_operators,
+        ))
+    }
+
+    fn check_operator(&self, pauli: &PauliString) -> Result<(), QuantumError> {
+        match pauli.support().last() {
+            Some(&qubit) if qubit >= self.num_qubits => Err(QuantumError::InvalidQubitIndex),
+            _ => Ok(()),
+        }
+    }
+
+    // Supplied pairs must commute with every stabilizer, and X_i must
+    // anticommute with Z_j exactly when i = j while commuting with every X_j
+    fn check_logicals(&self, num_logical: usize) -> Result<(), QuantumError> {
+        if self.logicals.len() != num_logical {
+            return Err(QuantumError::InvalidLogicalOperator);
+        }
+        for (x, z) in &self.logicals {
+            for logical in [x, z] {
+                self.check_operator(logical)?;
+                if self.stabilizers.iter().any(|s| !s.commutes_with(logical)) {
+                    return Err(QuantumError::InvalidLogicalOperator);
+                }
+            }
+        }
+        for (i, (xi, zi)) in self.logicals.iter().enumerate() {
+            for (j, (xj, zj)) in self.logicals.iter().enumerate() {
+                let paired = xi.commutes_with(zj) != (i == j);
+                if !paired || (i < j && (!xi.commutes_with(xj) || !zi.commutes_with(zj))) {
+                    return Err(QuantumError::InvalidLogicalOperator);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Symplectic Gram-Schmidt on a complement of the stabilizers inside their
+// normalizer: take v, find a partner w with <v, w> = 1, and clear both from
+// every remaining vector
+fn derive_logicals(
+    stabilizers: &[Vec<bool>],
+    num_qubits: usize,
+) -> Vec<(PauliString, PauliString)> {
+    // <s, v> = s_z . v_x + s_x . v_z, so the normalizer is the kernel of (z | x)
+    let swapped: Vec<Vec<bool>> = stabilizers
+        .iter()
+        .map(|row| {
+            let (x, z) = row.split_at(num_qubits);
+            z.iter().chain(x).copied().collect()
+        })
+        .collect();
+    let normalizer = gf2::kernel(&swapped, 2 * num_qubits);
+    let mut remaining = gf2::complement_basis(stabilizers, &normalizer);
+
+    let mut pairs = Vec::new();
+    while let Some(v) = remaining.pop() {
+        let partner = remaining
+            .iter()
+            .position(|w| gf2::symplectic_product(&v, w))
+            .expect("the symplectic form is non-degenerate on the logicals");
+        let w = remaining.swap_remove(partner);
+        for u in &mut remaining {
+            let (with_v, with_w) = (
+                gf2::symplectic_product(u, &v),
+                gf2::symplectic_product(u, &w),
+            );
+            if with_w {
+                gf2::add_assign(u, &v);
+            }
+            if with_v {
+                gf2::add_assign(u, &w);
+            }
+        }
+
+        let pauli = |row: &[bool]| {
+            PauliString::from_xz(row[..num_qubits].to_vec(), row[num_qubits..].to_vec(), 0)
+                .expect("x and z halves have equal length")
+        };
+        pairs.push((pauli(&v), pauli(&w)));
+    }
+    pairs
+}
@@ -0,0 +1,75 @@
+use super::*;
+use rand::Rng;
+
+// Noise models combined into one; each layer of the combination runs before
+// the combined model's own channels
+#[derive(Clone, Debug)]
+pub(crate) enum Composition {
+    // Every model's layer, in order
+    Sequence(Vec<NoiseModel>),
+    // One model's layer, drawn with probability proportional to its weight
+    Mixture(Vec<(f64, NoiseModel)>),
+}
+
+pub(crate) fn choose<'a, R: Rng + ?Sized>(
+    models: &'a [(f64, NoiseModel)],
+    rng: &mut R,
+) -> &'a NoiseModel {
+    let total: f64 = models.iter().map(|(w, _)| w).sum();
+    let mut draw = rng.gen::<f64>() * total;
+    for (weight, model) in models {
+        if draw < *weight {
+            return model;
+        }
+        draw -= weight;
+    }
+    &models[models.len() - 1].1
+}
+
+impl NoiseModel {
+    pub fn noiseless() -> Self {
+        Self::new(0.0, 0.0, 0.0, 0.0)
+    }
+
+    pub fn sequence(models: Vec<NoiseModel>) -> Self {
+        let mut model = Self::noiseless();
+        model.composition = Some(Composition::Sequence(models));
+        model
+    }
+
+    pub fn mixture(models: Vec<(f64, NoiseModel)>) -> Result<Self, QuantumError> {
+        let total: f64 = models.iter().map(|(w, _)| w).sum();
+        if models.iter().any(|(w, _)| *w < 0.0 || !w.is_finite()) || total <= 0.0 {
+            return Err(QuantumError::InvalidProbability);
+        }
+        let mut model = Self::noiseless();
+        model.composition = Some(Composition::Mixture(models));
+        Ok(model)
+    }
+
+    // `qubit` gets the single-qubit channels of `model` in place of every
+    // channel of this model that touches it
+    pub fn with_qubit_override(mut self, qubit: usize, model: NoiseModel) -> Self {
+        self.qubit_overrides.insert(qubit, model);
+        self
+    }
+
+    // Copy of the model with `f` applied to it and to every model nested in
+    // it, innermost first
+    pub(crate) fn map_nested(&self, f: &impl Fn(&NoiseModel) -> NoiseModel) -> NoiseModel {
+        let mut model = self.clone();
+        model.composition = match &self.composition {
+            None => None,
+            Some(Composition::Sequence(models)) => Some(Composition::Sequence(
+                models.iter().map(|m| m.map_nested(f)).collect(),
+            )),
+            Some(Composition::Mixture(models)) => Some(Composition::Mixture(
+                models.iter().map(|(w, m)| (*w, m.map_nested(f))).collect(),
+            )),
+        };
+        for nested in model.qubit_overrides.values_mut() {
+            *nested = nested.map_nested(f);
+        }
+        f(&model)
+    }
+}
@@ -0,0 +1,125 @@
+use super::*;
+use std::fmt;
+use std::sync::Arc;
+
+// Strength of the correlated flip on a pair of qubits, as a function of their
+// coordinates. The built-in kernels depend on the distance d between them and
+// the model's correlation length l.
+#[derive(Clone, Default)]
+pub enum CorrelationKernel {
+    // exp(-d / l)
+    #[default]
+    Exponential,
+    // exp(-d^2 / (2 l^2))
+    Gaussian,
+    // The given strength up to distance l, nothing beyond
+    Cutoff(f64),
+    Custom(Arc<dyn Fn((f64, f64), (f64, f64)) -> f64 + Send + Sync>),
+}
+
+impl fmt::Debug for CorrelationKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorrelationKernel::Exponential => f.write_str("Exponential"),
+            CorrelationKernel::Gaussian => f.write_str("Gaussian"),
+            CorrelationKernel::Cutoff(strength) => f.debug_tuple("Cutoff").field(strength).finish(),
+            CorrelationKernel::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl CorrelationKernel {
+    pub fn custom<F>(kernel: F) -> Self
+    where
+        F: Fn((f64, f64), (f64, f64)) -> f64 + Send + Sync + 'static,
+    {
+        CorrelationKernel::Custom(Arc::new(kernel))
+    }
+
+    // Clamped to [0, 1], since it is used as a probability
+    pub fn evaluate(&self, a: (f64, f64), b: (f64, f64), correlation_length: f64) -> f64 {
+        let distance = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+        let value = match self {
+            CorrelationKernel::Exponential => (-distance / correlation_length).exp(),
+            CorrelationKernel::Gaussian => {
+                (-distance.powi(2) / (2.0 * correlation_length.powi(2))).exp()
+            }
+            CorrelationKernel::Cutoff(strength) => {
+                if distance <= correlation_length {
+                    *strength
+                } else {
+                    0.0
+                }
+            }
+            CorrelationKernel::Custom(kernel) => kernel(a, b),
+        };
+        if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl NoiseModel {
+    // Fixes the correlation of one pair, overriding the kernel
+    pub fn with_spatial_correlation(
+        mut self,
+        i: usize,
+        j: usize,
+        correlation: f64,
+    ) -> Result<Self, QuantumError> {
+        if i == j {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        if !(0.0..=1.0).contains(&correlation) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        self.spatial_correlations
+            .insert((i.min(j), i.max(j)), correlation);
+        Ok(self)
+    }
+
+    pub fn with_correlation_kernel(mut self, kernel: CorrelationKernel) -> Self {
+        self.correlation_kernel = kernel;
+        self
+    }
+
+    // Positions the kernel sees; qubits past the end sit at (q, 0), which is
+    // where every qubit sits by default
+    pub fn with_qubit_coordinates(mut self, coordinates: Vec<(f64, f64)>) -> Self {
+        self.qubit_coordinates = coordinates;
+        self
+    }
+
+    // Grid positions of the data qubits of a rotated surface code
+    pub fn with_surface_code_coordinates(self, layout: &SurfaceCodeLayout) -> Self {
+        let coordinates = (0..layout.num_qubits())
+            .map(|q| {
+                let (row, column) = layout.coordinates(q);
+                (column as f64, row as f64)
+            })
+            .collect();
+        self.with_qubit_coordinates(coordinates)
+    }
+
+    pub fn qubit_coordinates(&self, qubit: usize) -> (f64, f64) {
+        self.qubit_coordinates
+            .get(qubit)
+            .copied()
+            .unwrap_or((qubit as f64, 0.0))
+    }
+
+    pub fn spatial_correlation(&self, i: usize, j: usize) -> f64 {
+        self.spatial_correlations
+            .get(&(i.min(j), i.max(j)))
+            .copied()
+            .unwrap_or_else(|| {
+                self.correlation_kernel.evaluate(
+                    self.qubit_coordinates(i),
+                    self.qubit_coordinates(j),
+                    self.correlation_length,
+                )
+            })
+    }
+}
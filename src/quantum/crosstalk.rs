@@ -0,0 +1,191 @@
+use super::*;
+use rand::Rng;
+use std::collections::HashMap;
+
+// Undirected physical couplings between qubits
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CouplingMap {
+    neighbours: HashMap<usize, Vec<usize>>,
+}
+
+impl CouplingMap {
+    pub fn new(edges: &[(usize, usize)]) -> Self {
+        let mut map = Self::default();
+        for &(a, b) in edges {
+            map.add_edge(a, b);
+        }
+        map
+    }
+
+    // Nearest-neighbour chain 0 - 1 - ... - (n - 1)
+    pub fn linear(num_qubits: usize) -> Self {
+        let edges: Vec<(usize, usize)> = (1..num_qubits).map(|q| (q - 1, q)).collect();
+        Self::new(&edges)
+    }
+
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        if a == b || self.are_coupled(a, b) {
+            return;
+        }
+        self.neighbours.entry(a).or_default().push(b);
+        self.neighbours.entry(b).or_default().push(a);
+    }
+
+    pub fn are_coupled(&self, a: usize, b: usize) -> bool {
+        self.neighbours
+            .get(&a)
+            .map_or(false, |neighbours| neighbours.contains(&b))
+    }
+
+    pub fn neighbours(&self, qubit: usize) -> &[usize] {
+        self.neighbours.get(&qubit).map_or(&[], |n| n.as_slice())
+    }
+
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = self
+            .neighbours
+            .iter()
+            .flat_map(|(&a, neighbours)| neighbours.iter().map(move |&b| (a, b)))
+            .filter(|(a, b)| a < b)
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+}
+
+// Errors a two-qubit gate injects on spectators: every qubit coupled to
+// either of its qubits, other than those two, receives `channel` (or its own
+// override) each time the gate runs
+#[derive(Clone, Debug)]
+pub struct Crosstalk {
+    coupling: CouplingMap,
+    channel: QuantumChannelOp,
+    spectator_channels: HashMap<usize, QuantumChannelOp>,
+}
+
+impl Crosstalk {
+    pub fn new(coupling: CouplingMap, channel: QuantumChannelOp) -> Result<Self, QuantumError> {
+        if channel.num_qubits() != 1 {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        Ok(Self {
+            coupling,
+            channel,
+            spectator_channels: HashMap::new(),
+        })
+    }
+
+    // Spectator errors as Z rotations picked up with probability p, the
+    // usual signature of ZZ coupling
+    pub fn dephasing(coupling: CouplingMap, probability: f64) -> Result<Self, QuantumError> {
+        Self::new(coupling, QuantumChannelOp::phase_flip(probability)?)
+    }
+
+    pub fn with_spectator_channel(
+        mut self,
+        spectator: usize,
+        channel: QuantumChannelOp,
+    ) -> Result<Self, QuantumError> {
+        if channel.num_qubits() != 1 {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        self.spectator_channels.insert(spectator, channel);
+        Ok(self)
+    }
+
+    pub fn coupling(&self) -> &CouplingMap {
+        &self.coupling
+    }
+
+    // Spectators of a gate on `qubits`, in increasing order; single-qubit
+    // gates have none
+    pub fn spectators(&self, qubits: &[usize]) -> Vec<usize> {
+        if qubits.len() < 2 {
+            return Vec::new();
+        }
+        let mut spectators: Vec<usize> = qubits
+            .iter()
+            .flat_map(|&q| self.coupling.neighbours(q).iter().copied())
+            .filter(|q| !qubits.contains(q))
+            .collect();
+        spectators.sort_unstable();
+        spectators.dedup();
+        spectators
+    }
+
+    pub(crate) fn channels(&self, qubits: &[usize]) -> Vec<(QuantumChannelOp, Vec<usize>)> {
+        self.spectators(qubits)
+            .into_iter()
+            .map(|spectator| {
+                let channel = self
+                    .spectator_channels
+                    .get(&spectator)
+                    .unwrap_or(&self.channel);
+                (channel.clone(), vec![spectator])
+            })
+            .collect()
+    }
+}
+
+impl NoiseModel {
+    // Channels triggered by running `gate` on `target`, on a register of
+    // `num_qubits` qubits: the gate errors registered for its qubits, then
+    // crosstalk on the spectators
+    pub fn gate_channels(
+        &self,
+        gate: &QuantumGate,
+        target: usize,
+        num_qubits: usize,
+    ) -> Result<Vec<(QuantumChannelOp, Vec<usize>)>, QuantumError> {
+        let qubits = gate.qubits(target);
+        let mut sorted = qubits.clone();
+        sorted.sort_unstable();
+        let mut channels: Vec<(QuantumChannelOp, Vec<usize>)> = self
+            .gate_errors
+            .iter()
+            .filter(|(on, _)| {
+                let mut on = on.clone();
+                on.sort_unstable();
+                on == sorted
+            })
+            .map(|(on, channel)| (channel.clone(), on.clone()))
+            .collect();
+        if let Some(crosstalk) = self.crosstalk() {
+            channels.extend(crosstalk.channels(&qubits));
+        }
+        if channels
+            .iter()
+            .any(|(_, targets)| targets.iter().any(|&q| q >= num_qubits))
+        {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        Ok(channels)
+    }
+
+    pub fn apply_gate_noise<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        gate: &QuantumGate,
+        target: usize,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.gate_noise(state, gate, target, None, rng)
+    }
+
+    pub(crate) fn gate_noise<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        gate: &QuantumGate,
+        target: usize,
+        mut trace: Option<&mut NoiseTrace>,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        for (channel, targets) in self.gate_channels(gate, target, state.num_qubits)? {
+            let index = channel.unravel(state, &targets, rng)?;
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.record_branch(&channel, &targets, index);
+            }
+        }
+        Ok(())
+    }
+}
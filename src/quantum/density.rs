@@ -0,0 +1,183 @@
+use super::*;
+use num_complex::Complex64;
+
+const PURIFICATION_TOLERANCE: f64 = 1e-12;
+
+#[derive(Clone, Debug)]
+pub struct DensityMatrix {
+    pub(crate) num_qubits: usize,
+    pub(crate) dimension: usize,
+    pub(crate) elements: Vec<Complex64>,
+}
+
+impl DensityMatrix {
+    pub fn new(num_qubits: usize) -> Self {
+        let dimension = 1 << num_qubits;
+        let mut elements = vec![Complex64::new(0.0, 0.0); dimension * dimension];
+        elements[0] = Complex64::new(1.0, 0.0);
+        Self {
+            num_qubits,
+            dimension,
+            elements,
+        }
+    }
+
+    pub fn from_pure_state(state: &QuantumState) -> Self {
+        let dimension = state.amplitudes.len();
+        let mut elements = vec![Complex64::new(0.0, 0.0); dimension * dimension];
+        for (i, a) in state.amplitudes.iter().enumerate() {
+            for (j, b) in state.amplitudes.iter().enumerate() {
+                elements[i * dimension + j] = a * b.conj();
+            }
+        }
+        Self {
+            num_qubits: state.num_qubits,
+            dimension,
+            elements,
+        }
+    }
+
+    pub fn from_ensemble(ensemble: &[(f64, QuantumState)]) -> Result<Self, QuantumError> {
+        let (_, first) = ensemble.first().ok_or(QuantumError::DimensionMismatch)?;
+        let mut result = Self::from_pure_state(first);
+        result
+            .elements
+            .iter_mut()
+            .for_each(|e| *e = Complex64::new(0.0, 0.0));
+
+        let total: f64 = ensemble.iter().map(|(p, _)| p).sum();
+        for (probability, state) in ensemble {
+            if state.num_qubits != result.num_qubits {
+                return Err(QuantumError::DimensionMismatch);
+            }
+            let pure = Self::from_pure_state(state);
+            for (e, p) in result.elements.iter_mut().zip(pure.elements.iter()) {
+                *e += p * (probability / total);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Complex64 {
+        self.elements[row * self.dimension + column]
+    }
+
+    pub fn trace(&self) -> Complex64 {
+        (0..self.dimension).map(|i| self.get(i, i)).sum()
+    }
+
+    pub fn purity(&self) -> f64 {
+        let mut purity = 0.0;
+        for i in 0..self.dimension {
+            for j in 0..self.dimension {
+                purity += (self.get(i, j) * self.get(j, i)).re;
+            }
+        }
+        purity
+    }
+
+    pub fn partial_trace(&self, traced: &[usize]) -> Result<Self, QuantumError> {
+        if traced.iter().any(|&q| q >= self.num_qubits) {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let kept: Vec<usize> = (0..self.num_qubits)
+            .filter(|q| !traced.contains(q))
+            .collect();
+        let traced: Vec<usize> = (0..self.num_qubits)
+            .filter(|q| traced.contains(q))
+            .collect();
+        let kept_dimension = 1 << kept.len();
+
+        let compose = |kept_bits: usize, traced_bits: usize| {
+            let mut index = 0;
+            for (bit, qubit) in kept.iter().enumerate() {
+                index |= ((kept_bits >> bit) & 1) << qubit;
+            }
+            for (bit, qubit) in traced.iter().enumerate() {
+                index |= ((traced_bits >> bit) & 1) << qubit;
+            }
+            index
+        };
+
+        let mut elements = vec![Complex64::new(0.0, 0.0); kept_dimension * kept_dimension];
+        for a in 0..kept_dimension {
+            for b in 0..kept_dimension {
+                let mut sum = Complex64::new(0.0, 0.0);
+                for t in 0..(1 << traced.len()) {
+                    sum += self.get(compose(a, t), compose(b, t));
+                }
+                elements[a * kept_dimension + b] = sum;
+            }
+        }
+
+        Ok(Self {
+            num_qubits: kept.len(),
+            dimension: kept_dimension,
+            elements,
+        })
+    }
+
+    // Returns |psi> on 2n qubits (system on qubits 0..n, purifying register on
+    // n..2n) with Tr_R |psi><psi| = rho. Uses a Cholesky factor rho = L L^dagger,
+    // so |psi> = sum_{i,k} L_ik |i>|k>.
+    pub fn purify(&self) -> Result<QuantumState, QuantumError> {
+        let n = self.dimension;
+        let mut factor = vec![Complex64::new(0.0, 0.0); n * n];
+
+        for j in 0..n {
+            let mut pivot = self.get(j, j).re;
+            for k in 0..j {
+                pivot -= factor[j * n + k].norm_sqr();
+            }
+            if pivot < -PURIFICATION_TOLERANCE {
+                return Err(QuantumError::NotPositiveSemidefinite);
+            }
+            if pivot <= PURIFICATION_TOLERANCE {
+                continue;
+            }
+
+            let diagonal = pivot.sqrt();
+            factor[j * n + j] = Complex64::new(diagonal, 0.0);
+            for i in (j + 1)..n {
+                let mut value = self.get(i, j);
+                for k in 0..j {
+                    value -= factor[i * n + k] * factor[j * n + k].conj();
+                }
+                factor[i * n + j] = value / diagonal;
+            }
+        }
+
+        let mut purification = QuantumState::new(2 * self.num_qubits)?;
+        for i in 0..n {
+            for k in 0..n {
+                purification.amplitudes[i | (k << self.num_qubits)] = factor[i * n + k];
+            }
+        }
+
+        let norm = purification
+            .amplitudes
+            .iter()
+            .map(|a| a.norm_sqr())
+            .sum::<f64>()
+            .sqrt();
+        for amplitude in &mut purification.amplitudes {
+            *amplitude /= norm;
+        }
+        let qubits: Vec<usize> = (0..2 * self.num_qubits).collect();
+        purification.merge_entanglement(&qubits);
+
+        Ok(purification)
+    }
+}
+
+impl QuantumState {
+    pub fn reduced_density_matrix(&self, traced: &[usize]) -> Result<DensityMatrix, QuantumError> {
+        DensityMatrix::from_pure_state(self).partial_trace(traced)
+    }
+}
@@ -0,0 +1,280 @@
+use super::*;
+
+// Circuit-level noise: depolarizing after every gate on the qubits it touches
+// (p / 3 per Pauli for one qubit, p / 15 per Pauli pair for two), and an X flip
+// after every reset and before every measurement with `measurement_error`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircuitNoise {
+    pub gate_error: f64,
+    pub measurement_error: f64,
+}
+
+impl CircuitNoise {
+    pub fn uniform(probability: f64) -> Self {
+        Self {
+            gate_error: probability,
+            measurement_error: probability,
+        }
+    }
+}
+
+// One independent error mechanism: fires `detectors` and leaves `correction`
+// on the data qubits, with the given probability
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectorError {
+    pub probability: f64,
+    pub detectors: Vec<usize>,
+    pub correction: PauliString,
+}
+
+// Detector error model of `rounds` noisy repetitions of a syndrome circuit
+// followed by one ideal round. Per round the detectors are the change of every
+// stabilizer since the previous round, then every flag or cat verification
+// bit, which should read 0; the final layer compares the ideal syndrome with
+// the last noisy one.
+#[derive(Clone, Debug)]
+pub struct DetectorErrorModel {
+    rounds: usize,
+    num_stabilizers: usize,
+    num_flags: usize,
+    errors: Vec<DetectorError>,
+}
+
+// The Pauli frame left by a fault and the measurements it has flipped so far
+struct Propagation {
+    frame: TableauRow,
+    flipped: Vec<bool>,
+}
+
+fn touched_qubits(gate: &QuantumGate, target: usize) -> Vec<usize> {
+    match gate {
+        QuantumGate::CNOT(control) | QuantumGate::Swap(control) => vec![*control, target],
+        _ => vec![target],
+    }
+}
+
+// Every non-identity Pauli on `qubits`, as (x, z) bits per qubit
+fn pauli_combinations(qubits: usize) -> Vec<Vec<(bool, bool)>> {
+    let mut combinations: Vec<Vec<(bool, bool)>> = vec![Vec::new()];
+    for _ in 0..qubits {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|prefix| {
+                [(false, false), (true, false), (true, true), (false, true)]
+                    .into_iter()
+                    .map(move |bits| {
+                        let mut extended = prefix.clone();
+                        extended.push(bits);
+                        extended
+                    })
+            })
+            .collect();
+    }
+    combinations.retain(|bits| bits.iter().any(|&(x, z)| x | z));
+    combinations
+}
+
+impl DetectorErrorModel {
+    pub fn from_syndrome_circuit(
+        code: &ErrorCorrectionCode,
+        extraction: &SyndromeCircuit,
+        rounds: usize,
+        noise: CircuitNoise,
+    ) -> Result<Self, QuantumError> {
+        let mut model = Self {
+            rounds,
+            num_stabilizers: code.get_stabilizers().len(),
+            num_flags: extraction.flag_bits().iter().flatten().count(),
+            errors: Vec::new(),
+        };
+        let operations = extraction.circuit().operations();
+        let num_qubits = extraction.num_qubits();
+        let num_clbits = extraction.circuit().num_clbits();
+        let blank = || Propagation {
+            frame: TableauRow {
+                x: vec![false; num_qubits],
+                z: vec![false; num_qubits],
+                sign: false,
+            },
+            flipped: vec![false; rounds * num_clbits],
+        };
+
+        let mut mechanisms: Vec<(f64, Propagation)> = Vec::new();
+        for round in 0..rounds {
+            for (index, operation) in operations.iter().enumerate() {
+                let mut faults: Vec<(f64, Propagation)> = Vec::new();
+                match operation {
+                    CircuitOperation::Gate(GateInstruction { gate, target }) => {
+                        let qubits = touched_qubits(gate, *target);
+                        let combinations = pauli_combinations(qubits.len());
+                        let probability = noise.gate_error / combinations.len() as f64;
+                        for bits in combinations {
+                            let mut fault = blank();
+                            for (&qubit, &(x, z)) in qubits.iter().zip(&bits) {
+                                fault.frame.x[qubit] = x;
+                                fault.frame.z[qubit] = z;
+                            }
+                            faults.push((probability, fault));
+                        }
+                    }
+                    CircuitOperation::Reset(qubit) => {
+                        let mut fault = blank();
+                        fault.frame.x[*qubit] = true;
+                        faults.push((noise.measurement_error, fault));
+                    }
+                    CircuitOperation::Measure { clbit, .. } => {
+                        let mut fault = blank();
+                        fault.flipped[round * num_clbits + clbit] = true;
+                        faults.push((noise.measurement_error, fault));
+                    }
+                    CircuitOperation::ClassicallyControlled { .. } => {
+                        return Err(QuantumError::NonCliffordGate);
+                    }
+                }
+
+                for (probability, mut fault) in faults {
+                    if probability <= 0.0 {
+                        continue;
+                    }
+                    Self::propagate(operations, round, index + 1, rounds, num_clbits, &mut fault)?;
+                    mechanisms.push((probability, fault));
+                }
+            }
+        }
+
+        // Mechanisms with the same signature are merged: an odd number of them
+        // firing has probability p1 (1 - p2) + p2 (1 - p1)
+        let mut merged: HashMap<(Vec<usize>, PauliString), f64> = HashMap::new();
+        for (probability, fault) in mechanisms {
+            let (detectors, correction) = model.signature(code, extraction, &fault)?;
+            if detectors.is_empty() && correction.is_identity() {
+                continue;
+            }
+            let entry = merged.entry((detectors, correction)).or_insert(0.0);
+            *entry = *entry * (1.0 - probability) + probability * (1.0 - *entry);
+        }
+        model.errors = merged
+            .into_iter()
+            .map(|((detectors, correction), probability)| DetectorError {
+                probability,
+                detectors,
+                correction,
+            })
+            .collect();
+        model.errors.sort_by(|a, b| a.detectors.cmp(&b.detectors));
+
+        Ok(model)
+    }
+
+    // Pushes a fault from just before operation `start` of round `round` to
+    // the end of the last round
+    fn propagate(
+        operations: &[CircuitOperation],
+        round: usize,
+        start: usize,
+        rounds: usize,
+        num_clbits: usize,
+        fault: &mut Propagation,
+    ) -> Result<(), QuantumError> {
+        for r in round..rounds {
+            let first = if r == round { start } else { 0 };
+            for operation in &operations[first..] {
+                match operation {
+                    CircuitOperation::Gate(GateInstruction { gate, target }) => {
+                        fault.frame.conjugate_by(gate, *target)?;
+                    }
+                    CircuitOperation::Measure { qubit, clbit } => {
+                        fault.flipped[r * num_clbits + clbit] ^= fault.frame.x[*qubit];
+                    }
+                    CircuitOperation::Reset(qubit) => {
+                        fault.frame.x[*qubit] = false;
+                        fault.frame.z[*qubit] = false;
+                    }
+                    CircuitOperation::ClassicallyControlled { .. } => {
+                        return Err(QuantumError::NonCliffordGate);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn signature(
+        &self,
+        code: &ErrorCorrectionCode,
+        extraction: &SyndromeCircuit,
+        fault: &Propagation,
+    ) -> Result<(Vec<usize>, PauliString), QuantumError> {
+        let n = extraction.num_data_qubits();
+        let correction =
+            PauliString::from_xz(fault.frame.x[..n].to_vec(), fault.frame.z[..n].to_vec(), 0)?;
+        let num_clbits = extraction.circuit().num_clbits();
+
+        let mut outcomes = Vec::with_capacity(self.rounds);
+        for round in 0..self.rounds {
+            let clbits = &fault.flipped[round * num_clbits..(round + 1) * num_clbits];
+            outcomes.push(extraction.interpret(clbits)?);
+        }
+        let final_syndrome: Vec<bool> = code
+            .get_stabilizers()
+            .iter()
+            .map(|s| !s.pauli().commutes_with(&correction))
+            .collect();
+
+        let events = self.detection_events(extraction, &outcomes, &final_syndrome)?;
+        let detectors = (0..events.len()).filter(|&i| events[i]).collect();
+        Ok((detectors, correction))
+    }
+
+    pub fn num_detectors(&self) -> usize {
+        self.rounds * (self.num_stabilizers + self.num_flags) + self.num_stabilizers
+    }
+
+    pub fn errors(&self) -> &[DetectorError] {
+        &self.errors
+    }
+
+    // Detection events of an actual run: the outcome of each noisy round and
+    // the syndrome of the ideal final round, e.g. from measuring out the data
+    pub fn detection_events(
+        &self,
+        extraction: &SyndromeCircuit,
+        outcomes: &[SyndromeOutcome],
+        final_syndrome: &[bool],
+    ) -> Result<Vec<bool>, QuantumError> {
+        if outcomes.len() != self.rounds || final_syndrome.len() != self.num_stabilizers {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut events = Vec::with_capacity(self.num_detectors());
+        let mut previous = vec![false; self.num_stabilizers];
+        for outcome in outcomes {
+            events.extend(outcome.syndrome.iter().zip(&previous).map(|(&s, &p)| s ^ p));
+            events.extend(
+                extraction
+                    .flag_bits()
+                    .iter()
+                    .zip(&outcome.flags)
+                    .filter(|(bit, _)| bit.is_some())
+                    .map(|(_, &flag)| flag),
+            );
+            previous.clone_from(&outcome.syndrome);
+        }
+        events.extend(final_syndrome.iter().zip(&previous).map(|(&s, &p)| s ^ p));
+        Ok(events)
+    }
+
+    // Weights ln((1 - p) / p), so the cheapest explanation is the most likely
+    pub fn decoder(&self, max_faults: usize) -> SpacetimeDecoder {
+        let faults = self
+            .errors
+            .iter()
+            .map(|error| Fault {
+                detectors: error.detectors.clone(),
+                correction: error.correction.clone(),
+                weight: log_odds(error.probability),
+            })
+            .collect();
+        SpacetimeDecoder::new(faults, self.num_detectors(), max_faults)
+    }
+}
@@ -0,0 +1,300 @@
+use super::*;
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Ascii,
+    Latex,
+}
+
+enum Mark<'a> {
+    Gate(&'a QuantumGate),
+    Conditional(&'a QuantumGate, &'a MeasurementRef),
+    Control,
+    Target,
+    Swap,
+    Measure(usize),
+    Reset,
+}
+
+// One operation's marks per qubit plus the vertical span it occupies
+struct Placement<'a> {
+    marks: Vec<(usize, Mark<'a>)>,
+    span: (usize, usize),
+}
+
+fn place(operation: &CircuitOperation) -> Placement<'_> {
+    let marks = match operation {
+        CircuitOperation::Gate(GateInstruction { gate, target }) => match gate {
+            QuantumGate::CNOT(control) => vec![(*control, Mark::Control), (*target, Mark::Target)],
+            QuantumGate::MultiControlledX(controls) => controls
+                .iter()
+                .map(|&c| (c, Mark::Control))
+                .chain(std::iter::once((*target, Mark::Target)))
+                .collect(),
+            QuantumGate::Swap(other) => vec![(*other, Mark::Swap), (*target, Mark::Swap)],
+            QuantumGate::Unitary2(other, _) => {
+                vec![(*other, Mark::Gate(gate)), (*target, Mark::Gate(gate))]
+            }
+            _ => vec![(*target, Mark::Gate(gate))],
+        },
+        CircuitOperation::Measure { qubit, clbit } => vec![(*qubit, Mark::Measure(*clbit))],
+        CircuitOperation::Reset(qubit) => vec![(*qubit, Mark::Reset)],
+        CircuitOperation::ClassicallyControlled {
+            condition,
+            gate,
+            target,
+        } => vec![(*target, Mark::Conditional(gate, condition))],
+    };
+    let low = marks.iter().map(|(q, _)| *q).min().unwrap_or(0);
+    let high = marks.iter().map(|(q, _)| *q).max().unwrap_or(0);
+    Placement {
+        marks,
+        span: (low, high),
+    }
+}
+
+// Greedy layering: each operation goes in the first column after every
+// column that already touches a qubit within its span
+fn layout(circuit: &QuantumCircuit) -> (usize, Vec<Vec<Placement<'_>>>) {
+    let placements: Vec<Placement> = circuit.operations().iter().map(place).collect();
+    let num_qubits = placements
+        .iter()
+        .map(|p| p.span.1 + 1)
+        .max()
+        .unwrap_or(0)
+        .max(circuit.num_qubits());
+
+    let mut frontier = vec![0usize; num_qubits];
+    let mut columns: Vec<Vec<Placement>> = Vec::new();
+    for placement in placements {
+        let (low, high) = placement.span;
+        let column = frontier[low..=high].iter().copied().max().unwrap_or(0);
+        for slot in &mut frontier[low..=high] {
+            *slot = column + 1;
+        }
+        if columns.len() <= column {
+            columns.resize_with(column + 1, Vec::new);
+        }
+        columns[column].push(placement);
+    }
+    (num_qubits, columns)
+}
+
+// Multiples of pi with small denominators are printed symbolically
+fn format_angle(theta: f64, style: Style) -> String {
+    let pi = if style == Style::Latex { "\\pi" } else { "pi" };
+    for denominator in [1i64, 2, 3, 4, 6, 8] {
+        let numerator = theta / PI * denominator as f64;
+        if (numerator - numerator.round()).abs() < 1e-9 {
+            let numerator = numerator.round() as i64;
+            let sign = if numerator < 0 { "-" } else { "" };
+            let magnitude = match numerator.abs() {
+                0 => return "0".to_string(),
+                1 => pi.to_string(),
+                n => format!("{}{}", n, pi),
+            };
+            return match denominator {
+                1 => format!("{}{}", sign, magnitude),
+                d => format!("{}{}/{}", sign, magnitude, d),
+            };
+        }
+    }
+    format!("{:.3}", theta)
+}
+
+fn gate_label(gate: &QuantumGate, style: Style) -> String {
+    let angle = |theta: f64| format_angle(theta, style);
+    let latex = style == Style::Latex;
+    match gate {
+        QuantumGate::Hadamard => "H".to_string(),
+        QuantumGate::PauliX | QuantumGate::CNOT(_) | QuantumGate::MultiControlledX(_) => {
+            "X".to_string()
+        }
+        QuantumGate::PauliY => "Y".to_string(),
+        QuantumGate::PauliZ => "Z".to_string(),
+        QuantumGate::Phase(phi) => format!("P({})", angle(*phi)),
+        QuantumGate::Rx(theta) if latex => format!("R_x({})", angle(*theta)),
+        QuantumGate::Ry(theta) if latex => format!("R_y({})", angle(*theta)),
+        QuantumGate::Rz(theta) if latex => format!("R_z({})", angle(*theta)),
+        QuantumGate::Rx(theta) => format!("Rx({})", angle(*theta)),
+        QuantumGate::Ry(theta) => format!("Ry({})", angle(*theta)),
+        QuantumGate::Rz(theta) => format!("Rz({})", angle(*theta)),
+        QuantumGate::SqrtX if latex => "\\sqrt{X}".to_string(),
+        QuantumGate::SqrtXDagger if latex => "\\sqrt{X}^\\dagger".to_string(),
+        QuantumGate::SqrtX => "SX".to_string(),
+        QuantumGate::SqrtXDagger => "SXdg".to_string(),
+        QuantumGate::U3(theta, phi, lambda) => format!(
+            "{}({},{},{})",
+            if latex { "U_3" } else { "U3" },
+            angle(*theta),
+            angle(*phi),
+            angle(*lambda)
+        ),
+        QuantumGate::Swap(_) => "SWAP".to_string(),
+        QuantumGate::Unitary1(_) | QuantumGate::Unitary2(..) => "U".to_string(),
+    }
+}
+
+fn condition_label(condition: &MeasurementRef, style: Style) -> String {
+    let bits = match condition {
+        MeasurementRef::Bit(index) => index.to_string(),
+        MeasurementRef::Parity(indices) => indices
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(if style == Style::Latex {
+                "\\oplus "
+            } else {
+                "^"
+            }),
+    };
+    match style {
+        Style::Ascii => format!("if(c{})", bits),
+        Style::Latex => format!("c_{{{}}}", bits),
+    }
+}
+
+fn ascii_mark(mark: &Mark) -> String {
+    match mark {
+        Mark::Gate(gate) => gate_label(gate, Style::Ascii),
+        Mark::Conditional(gate, condition) => format!(
+            "{}.{}",
+            gate_label(gate, Style::Ascii),
+            condition_label(condition, Style::Ascii)
+        ),
+        Mark::Control => "*".to_string(),
+        Mark::Target => "(+)".to_string(),
+        Mark::Swap => "x".to_string(),
+        Mark::Measure(clbit) => format!("M>c{}", clbit),
+        Mark::Reset => "|0>".to_string(),
+    }
+}
+
+fn latex_mark(mark: &Mark, qubit: usize, span: (usize, usize)) -> String {
+    // Vertical wires run from the top-most mark down to the bottom one
+    let offset = if qubit == span.0 && span.1 > span.0 {
+        Some(span.1 - span.0)
+    } else {
+        None
+    };
+    match mark {
+        Mark::Gate(gate) => match offset {
+            Some(d) => format!(
+                "\\gate{{{}}} \\vqw{{{}}}",
+                gate_label(gate, Style::Latex),
+                d
+            ),
+            None => format!("\\gate{{{}}}", gate_label(gate, Style::Latex)),
+        },
+        Mark::Conditional(gate, condition) => format!(
+            "\\gate{{{}^{{{}}}}}",
+            gate_label(gate, Style::Latex),
+            condition_label(condition, Style::Latex)
+        ),
+        Mark::Control => match offset {
+            Some(d) => format!("\\ctrl{{{}}}", d),
+            None => "\\control{}".to_string(),
+        },
+        Mark::Target => match offset {
+            Some(d) => format!("\\targ{{}} \\vqw{{{}}}", d),
+            None => "\\targ{}".to_string(),
+        },
+        Mark::Swap => match offset {
+            Some(d) => format!("\\swap{{{}}}", d),
+            None => "\\targX{}".to_string(),
+        },
+        Mark::Measure(_) => "\\meter{}".to_string(),
+        Mark::Reset => "\\gate{\\ket{0}}".to_string(),
+    }
+}
+
+impl QuantumCircuit {
+    // One wire per qubit, qubit 0 on top; multi-qubit operations are joined by
+    // a vertical bar, controls drawn as `*` and X targets as `(+)`
+    pub fn to_ascii(&self) -> String {
+        let (num_qubits, columns) = layout(self);
+        if num_qubits == 0 {
+            return String::new();
+        }
+        let labels: Vec<String> = (0..num_qubits).map(|q| format!("q{}: ", q)).collect();
+        let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+
+        // Wire rows at even indices, connector rows in between
+        let mut rows: Vec<String> = (0..2 * num_qubits - 1)
+            .map(|row| {
+                if row % 2 == 0 {
+                    format!("{:>width$}-", labels[row / 2], width = label_width)
+                } else {
+                    " ".repeat(label_width + 1)
+                }
+            })
+            .collect();
+
+        for column in &columns {
+            let mut cells: Vec<Option<String>> = vec![None; num_qubits];
+            let mut connected = vec![false; 2 * num_qubits];
+            for placement in column {
+                for (qubit, mark) in &placement.marks {
+                    cells[*qubit] = Some(ascii_mark(mark));
+                }
+                let (low, high) = placement.span;
+                for slot in connected.iter_mut().take(2 * high).skip(2 * low + 1) {
+                    *slot = true;
+                }
+            }
+
+            let width = cells.iter().flatten().map(|c| c.len()).max().unwrap_or(1) + 2;
+            for (row, line) in rows.iter_mut().enumerate() {
+                let cell = if row % 2 == 0 {
+                    match &cells[row / 2] {
+                        Some(text) => format!("{:-^width$}", text, width = width),
+                        None if connected[row] => format!("{:-^width$}", "|", width = width),
+                        None => "-".repeat(width),
+                    }
+                } else if connected[row] {
+                    format!("{:^width$}", "|", width = width)
+                } else {
+                    " ".repeat(width)
+                };
+                line.push_str(&cell);
+                line.push(if row % 2 == 0 { '-' } else { ' ' });
+            }
+        }
+
+        let mut diagram = rows
+            .iter()
+            .map(|row| row.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        diagram.push('\n');
+        diagram
+    }
+
+    // quantikz environment body, ready for \begin{quantikz} ... \end{quantikz}
+    pub fn to_quantikz(&self) -> String {
+        let (num_qubits, columns) = layout(self);
+        let mut rows: Vec<Vec<String>> = (0..num_qubits)
+            .map(|q| vec![format!("\\lstick{{$q_{{{}}}$}}", q)])
+            .collect();
+
+        for column in &columns {
+            let mut cells = vec!["\\qw".to_string(); num_qubits];
+            for placement in column {
+                for (qubit, mark) in &placement.marks {
+                    cells[*qubit] = latex_mark(mark, *qubit, placement.span);
+                }
+            }
+            for (row, cell) in rows.iter_mut().zip(cells) {
+                row.push(cell);
+            }
+        }
+
+        let body = rows
+            .iter()
+            .map(|row| format!("{} & \\qw", row.join(" & ")))
+            .collect::<Vec<_>>()
+            .join(" \\\\\n");
+        format!("\\begin{{quantikz}}\n{}\n\\end{{quantikz}}\n", body)
+    }
+}
@@ -0,0 +1,210 @@
+use super::*;
+use itertools::Itertools;
+
+// Number of candidate supports an exact search will test before settling for
+// an upper bound on the distance
+const DISTANCE_SEARCH_LIMIT: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeDistance {
+    Exact(usize),
+    // Weight of the lightest logical found when exhaustive search was too large
+    UpperBound(usize),
+}
+
+impl CodeDistance {
+    pub fn value(self) -> usize {
+        match self {
+            CodeDistance::Exact(d) | CodeDistance::UpperBound(d) => d,
+        }
+    }
+
+    pub fn is_exact(self) -> bool {
+        matches!(self, CodeDistance::Exact(_))
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Exact while the search over supports of increasing weight fits the
+    // budget, otherwise the lightest logical found by greedy reduction
+    pub fn compute_distance(&self) -> CodeDistance {
+        let stabilizers: Vec<PauliString> = self
+            .get_stabilizers()
+            .iter()
+            .map(|s| s.pauli().clone())
+            .collect();
+        let logicals: Vec<PauliString> = self
+            .logical_operators()
+            .iter()
+            .map(|l| l.pauli().clone())
+            .collect();
+        distance_of(&stabilizers, &logicals, self.num_qubits())
+    }
+
+    // The stored distance must match an exact result, and may not exceed an
+    // upper bound
+    pub fn verify_distance(&self) -> Result<CodeDistance, QuantumError> {
+        let computed = self.compute_distance();
+        let consistent = match computed {
+            CodeDistance::Exact(d) => d == self.distance(),
+            CodeDistance::UpperBound(d) => self.distance() <= d,
+        };
+        if consistent {
+            Ok(computed)
+        } else {
+            Err(QuantumError::DistanceMismatch)
+        }
+    }
+}
+
+// Minimum weight of a Pauli commuting with every stabilizer without being one.
+// CSS codes only need single-type Paulis to be searched.
+pub(crate) fn distance_of(
+    stabilizers: &[PauliString],
+    logicals: &[PauliString],
+    num_qubits: usize,
+) -> CodeDistance {
+    let rows: Vec<Vec<bool>> = stabilizers
+        .iter()
+        .map(|s| s.to_symplectic(num_qubits))
+        .collect();
+    let mut representatives: Vec<Vec<bool>> = logicals
+        .iter()
+        .map(|l| l.to_symplectic(num_qubits))
+        .collect();
+    for pair in logicals.chunks(2) {
+        if let [x, z] = pair {
+            representatives.push(x.multiply(z).to_symplectic(num_qubits));
+        }
+    }
+    let bound = representatives
+        .into_iter()
+        .map(|logical| reduce_greedily(logical, &rows, num_qubits))
+        .min()
+        .unwrap_or(num_qubits);
+
+    let is_pure = |s: &PauliString, pauli: PauliOperator| s.iter().all(|(_, p)| p == pauli);
+    let is_css = stabilizers
+        .iter()
+        .all(|s| is_pure(s, PauliOperator::X) || is_pure(s, PauliOperator::Z));
+    if is_css {
+        let (hx, hz): (Vec<_>, Vec<_>) = stabilizers
+            .iter()
+            .partition(|s| is_pure(s, PauliOperator::X));
+        let hx: Vec<Vec<bool>> = hx
+            .iter()
+            .map(|s| s.to_symplectic(num_qubits)[..num_qubits].to_vec())
+            .collect();
+        let hz: Vec<Vec<bool>> = hz
+            .iter()
+            .map(|s| s.to_symplectic(num_qubits)[num_qubits..].to_vec())
+            .collect();
+        return css_distance(&hx, &hz, num_qubits, bound);
+    }
+
+    let mut budget = DISTANCE_SEARCH_LIMIT;
+    for weight in 1..bound {
+        let candidates = binomial(num_qubits, weight);
+        if candidates > budget {
+            return CodeDistance::UpperBound(bound);
+        }
+        budget -= candidates;
+        for support in (0..num_qubits).combinations(weight) {
+            if has_logical_on(&rows, &support, num_qubits) {
+                return CodeDistance::Exact(weight);
+            }
+        }
+    }
+    CodeDistance::Exact(bound)
+}
+
+// Lightest single-type Pauli that is a non-trivial logical, below `bound`
+pub(crate) fn css_distance(
+    hx: &[Vec<bool>],
+    hz: &[Vec<bool>],
+    num_qubits: usize,
+    bound: usize,
+) -> CodeDistance {
+    let is_logical = |checks: &[Vec<bool>], stabilizers: &[Vec<bool>], v: &[bool]| {
+        checks.iter().all(|row| !gf2::dot(row, v)) && !gf2::in_row_space(stabilizers, v)
+    };
+
+    let mut budget = DISTANCE_SEARCH_LIMIT;
+    for weight in 1..bound {
+        let candidates = binomial(num_qubits, weight);
+        if candidates > budget {
+            return CodeDistance::UpperBound(bound);
+        }
+        budget -= candidates;
+        for support in (0..num_qubits).combinations(weight) {
+            let mut v = vec![false; num_qubits];
+            for q in support {
+                v[q] = true;
+            }
+            if is_logical(hz, hx, &v) || is_logical(hx, hz, &v) {
+                return CodeDistance::Exact(weight);
+            }
+        }
+    }
+    CodeDistance::Exact(bound)
+}
+
+// Whether some Pauli supported on `support` commutes with every stabilizer
+// without being in the group. Such Paulis form a subspace, so it is enough to
+// test a basis of the solutions restricted to the support.
+fn has_logical_on(stabilizers: &[Vec<bool>], support: &[usize], num_qubits: usize) -> bool {
+    let w = support.len();
+    // Unknowns are the x bits then z bits on the support; <s, v> pairs the z
+    // part of s with the x part of v and vice versa
+    let restricted: Vec<Vec<bool>> = stabilizers
+        .iter()
+        .map(|row| {
+            let z = support.iter().map(|&q| row[num_qubits + q]);
+            let x = support.iter().map(|&q| row[q]);
+            z.chain(x).collect()
+        })
+        .collect();
+    gf2::kernel(&restricted, 2 * w).iter().any(|solution| {
+        let mut v = vec![false; 2 * num_qubits];
+        for (i, &q) in support.iter().enumerate() {
+            v[q] = solution[i];
+            v[num_qubits + q] = solution[w + i];
+        }
+        !gf2::in_row_space(stabilizers, &v)
+    })
+}
+
+// Multiplies in stabilizers while any of them lowers the weight; the result
+// is an equivalent logical, so its weight bounds the distance
+fn reduce_greedily(mut logical: Vec<bool>, stabilizers: &[Vec<bool>], num_qubits: usize) -> usize {
+    let mut weight = pauli_weight(&logical, num_qubits);
+    loop {
+        let improvement = stabilizers.iter().find_map(|s| {
+            let mut candidate = logical.clone();
+            gf2::add_assign(&mut candidate, s);
+            let w = pauli_weight(&candidate, num_qubits);
+            (w < weight).then_some((candidate, w))
+        });
+        match improvement {
+            Some((candidate, w)) => {
+                logical = candidate;
+                weight = w;
+            }
+            None => return weight,
+        }
+    }
+}
+
+fn pauli_weight(v: &[bool], num_qubits: usize) -> usize {
+    (0..num_qubits)
+        .filter(|&q| v[q] || v[num_qubits + q])
+        .count()
+}
+
+// Saturates rather than overflowing; only compared against a budget
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k.min(n - k)).fold(1usize, |acc, i| acc.saturating_mul(n - i) / (i + 1))
+}
@@ -0,0 +1,125 @@
+use super::*;
+
+// Below this squared norm a state has no component left in the code space
+const CODE_SPACE_TOLERANCE: f64 = 1e-9;
+
+pub(crate) fn tableau_row(pauli: &PauliString, num_qubits: usize) -> TableauRow {
+    let v = pauli.to_symplectic(num_qubits);
+    TableauRow {
+        x: v[..num_qubits].to_vec(),
+        z: v[num_qubits..].to_vec(),
+        sign: pauli.phase() == 2,
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Clifford U taking X_i, Z_i of logical qubit i < k to its logical
+    // operators and Z_{k + j} to stabilizer j, so that logical qubits on
+    // 0..k with the rest in |0> are mapped into the code space. X_{k + j}
+    // goes to a destabilizer of stabilizer j, chosen to commute with
+    // everything else.
+    pub fn encoding_circuit(&self) -> Result<QuantumCircuit, QuantumError> {
+        let n = self.num_qubits();
+        let k = self.num_logical_qubits();
+
+        // Redundant checks (as from_css can produce) add nothing to the group
+        let mut generators: Vec<Vec<bool>> = Vec::new();
+        let mut stabilizers = Vec::new();
+        for stabilizer in self.get_stabilizers() {
+            let v = stabilizer.pauli().to_symplectic(n);
+            if !gf2::in_row_space(&generators, &v) {
+                generators.push(v);
+                stabilizers.push(tableau_row(stabilizer.pauli(), n));
+            }
+        }
+        if generators.len() + k != n {
+            return Err(QuantumError::InvalidTableau);
+        }
+
+        let logical_x = (0..k)
+            .map(|l| Ok(tableau_row(&self.logical_x(l)?, n)))
+            .collect::<Result<Vec<_>, QuantumError>>()?;
+        let logical_z = (0..k)
+            .map(|l| Ok(tableau_row(&self.logical_z(l)?, n)))
+            .collect::<Result<Vec<_>, QuantumError>>()?;
+
+        // Destabilizer j: <s_i, d> = [i = j] and <l, d> = 0 for every logical,
+        // with <a, b> = a_z . b_x + a_x . b_z; then made to commute pairwise
+        // by adding stabilizers
+        let constraints: Vec<Vec<bool>> = stabilizers
+            .iter()
+            .chain(&logical_x)
+            .chain(&logical_z)
+            .map(|row| row.z.iter().chain(&row.x).copied().collect())
+            .collect();
+        let mut destabilizers: Vec<TableauRow> = Vec::with_capacity(stabilizers.len());
+        for j in 0..stabilizers.len() {
+            let rhs: Vec<bool> = (0..constraints.len()).map(|i| i == j).collect();
+            let v = gf2::solve(&constraints, &rhs, 2 * n).ok_or(QuantumError::InvalidTableau)?;
+            let mut row = TableauRow {
+                x: v[..n].to_vec(),
+                z: v[n..].to_vec(),
+                sign: false,
+            };
+            for (i, previous) in destabilizers.iter().enumerate() {
+                let v = row.x.iter().chain(&row.z).copied().collect::<Vec<_>>();
+                let w = previous
+                    .x
+                    .iter()
+                    .chain(&previous.z)
+                    .copied()
+                    .collect::<Vec<_>>();
+                if gf2::symplectic_product(&v, &w) {
+                    gf2::add_assign(&mut row.x, &stabilizers[i].x);
+                    gf2::add_assign(&mut row.z, &stabilizers[i].z);
+                }
+            }
+            destabilizers.push(row);
+        }
+
+        let tableau = CliffordTableau::from_rows(
+            logical_x.into_iter().chain(destabilizers).collect(),
+            logical_z.into_iter().chain(stabilizers).collect(),
+        )?;
+        Ok(tableau.to_circuit())
+    }
+
+    // k-qubit logical state to the n-qubit codeword
+    pub fn encode(&self, state: &QuantumState) -> Result<QuantumState, QuantumError> {
+        let k = self.num_logical_qubits();
+        if state.num_qubits != k {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let mut encoded = QuantumState::new(self.num_qubits())?;
+        encoded.amplitudes[..1 << k].copy_from_slice(&state.amplitudes);
+        self.encoding_circuit()?.execute(&mut encoded)?;
+        Ok(encoded)
+    }
+
+    // Inverse of encode: undoes the encoding circuit and reads the logical
+    // qubits off 0..k, projecting onto the ancillas being |0>. Fails with
+    // NotInCodeSpace when nothing of the state survives the projection.
+    pub fn decode_logical(&self, state: &QuantumState) -> Result<QuantumState, QuantumError> {
+        let k = self.num_logical_qubits();
+        if state.num_qubits != self.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let mut unencoded = state.clone();
+        self.encoding_circuit()?
+            .inverse()?
+            .execute(&mut unencoded)?;
+
+        let norm: f64 = unencoded.amplitudes[..1 << k]
+            .iter()
+            .map(|a| a.norm_sqr())
+            .sum();
+        if norm < CODE_SPACE_TOLERANCE {
+            return Err(QuantumError::NotInCodeSpace);
+        }
+        let mut logical = QuantumState::new(k)?;
+        for (target, amplitude) in logical.amplitudes.iter_mut().zip(&unencoded.amplitudes) {
+            *target = *amplitude / norm.sqrt();
+        }
+        Ok(logical)
+    }
+}
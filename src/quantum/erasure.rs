@@ -0,0 +1,224 @@
+use super::*;
+use rand::Rng;
+
+// A fault on one qubit: a Pauli, or a heralded erasure whose location is known
+// but whose effect is not
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhysicalError {
+    Pauli(usize, PauliOperator),
+    Erasure(usize),
+}
+
+// An erased qubit is replaced by the maximally mixed state, i.e. suffers I,
+// X, Y or Z uniformly at random. Returns the Pauli actually applied and the
+// erased locations.
+pub fn resolve_errors<R: Rng + ?Sized>(
+    errors: &[PhysicalError],
+    rng: &mut R,
+) -> (PauliString, Vec<usize>) {
+    let mut pauli = PauliString::identity(0);
+    let mut erased = Vec::new();
+    for error in errors {
+        let (qubit, operator) = match *error {
+            PhysicalError::Pauli(qubit, operator) => (qubit, Some(operator)),
+            PhysicalError::Erasure(qubit) => {
+                erased.push(qubit);
+                let operator = match rng.gen_range(0..4) {
+                    0 => None,
+                    1 => Some(PauliOperator::X),
+                    2 => Some(PauliOperator::Y),
+                    _ => Some(PauliOperator::Z),
+                };
+                (qubit, operator)
+            }
+        };
+        if let Some(operator) = operator {
+            pauli = pauli.multiply(&PauliString::single(qubit, operator));
+        }
+    }
+    erased.sort_unstable();
+    erased.dedup();
+    (pauli, erased)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErasureSyndrome {
+    pub syndrome: Vec<bool>,
+    pub erased: Vec<usize>,
+}
+
+impl ErrorCorrectionCode {
+    pub fn erasure_syndrome(&self, error: &PauliString, erased: &[usize]) -> ErasureSyndrome {
+        ErasureSyndrome {
+            syndrome: self
+                .get_stabilizers()
+                .iter()
+                .map(|stabilizer| !stabilizer.pauli().commutes_with(error))
+                .collect(),
+            erased: erased.to_vec(),
+        }
+    }
+
+    // A correction supported on the erasure is maximum likelihood when one
+    // exists: peeling on the surface code, Gaussian elimination otherwise.
+    // Syndromes the erasure cannot explain also involve Pauli errors and go to
+    // the single-round spacetime decoder with erased faults made free.
+    pub fn decode_erasure(&self, syndrome: &ErasureSyndrome) -> Result<PauliString, QuantumError> {
+        if syndrome.syndrome.len() != self.get_stabilizers().len() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        if syndrome.erased.iter().any(|&q| q >= self.num_qubits()) {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let erasure_only = match self.lattice() {
+            Some(CodeLattice::RotatedSurface(layout)) => {
+                peel_surface(layout, &syndrome.syndrome, &syndrome.erased)
+            }
+            _ => self.solve_on_erasure(syndrome),
+        };
+        if let Some(correction) = erasure_only {
+            return Ok(correction);
+        }
+
+        let mut faults = self.spacetime_faults(1);
+        for fault in &mut faults {
+            if fault
+                .correction
+                .support()
+                .iter()
+                .all(|q| syndrome.erased.contains(q))
+            {
+                fault.weight = 0.0;
+            }
+        }
+        SpacetimeDecoder::new(
+            faults,
+            syndrome.syndrome.len(),
+            self.distance() + syndrome.erased.len(),
+        )
+        .decode(&syndrome.syndrome)
+    }
+
+    // Unknowns are the x and z bits of the erased qubits; stabilizer i sees x
+    // where it acts as Z or Y and z where it acts as X or Y
+    fn solve_on_erasure(&self, syndrome: &ErasureSyndrome) -> Option<PauliString> {
+        let erased = &syndrome.erased;
+        let k = erased.len();
+        let rows: Vec<Vec<bool>> = self
+            .get_stabilizers()
+            .iter()
+            .map(|stabilizer| {
+                let mut row = vec![false; 2 * k];
+                for (j, &qubit) in erased.iter().enumerate() {
+                    if let Some(pauli) = stabilizer.pauli().get(qubit) {
+                        row[j] = pauli != PauliOperator::X;
+                        row[k + j] = pauli != PauliOperator::Z;
+                    }
+                }
+                row
+            })
+            .collect();
+
+        let solution = gf2::solve(&rows, &syndrome.syndrome, 2 * k)?;
+        let mut correction = PauliString::identity(0);
+        for (j, &qubit) in erased.iter().enumerate() {
+            let pauli = match (solution[j], solution[k + j]) {
+                (false, false) => continue,
+                (true, false) => PauliOperator::X,
+                (true, true) => PauliOperator::Y,
+                (false, true) => PauliOperator::Z,
+            };
+            correction.set(qubit, Some(pauli));
+        }
+        Some(correction)
+    }
+}
+
+// Delfosse-Zemor peeling on the rotated surface code, X and Z parts separately.
+// Stabilizers are ordered X plaquettes then Z plaquettes, as new_surface_code
+// builds them.
+fn peel_surface(
+    layout: &SurfaceCodeLayout,
+    syndrome: &[bool],
+    erased: &[usize],
+) -> Option<PauliString> {
+    let (x_syndrome, z_syndrome) = syndrome.split_at(layout.x_plaquettes.len());
+    let n = layout.num_qubits();
+    let x_flips = peel(&layout.z_plaquettes, z_syndrome, erased, n)?;
+    let z_flips = peel(&layout.x_plaquettes, x_syndrome, erased, n)?;
+
+    let mut correction = PauliString::identity(0);
+    for qubit in x_flips {
+        correction = correction.multiply(&PauliString::single(qubit, PauliOperator::X));
+    }
+    for qubit in z_flips {
+        correction = correction.multiply(&PauliString::single(qubit, PauliOperator::Z));
+    }
+    Some(correction)
+}
+
+// Qubits are edges between the (at most two) checks containing them, with one
+// virtual boundary vertex standing in for a missing check. A spanning forest
+// of the erased edges, grown from the boundary first, is peeled from its
+// leaves: a leaf whose check is lit puts its edge in the correction and moves
+// the excitation to its parent.
+fn peel(
+    checks: &[Vec<usize>],
+    syndrome: &[bool],
+    erased: &[usize],
+    num_qubits: usize,
+) -> Option<Vec<usize>> {
+    let boundary = checks.len();
+    let mut endpoints = vec![Vec::new(); num_qubits];
+    for (check, qubits) in checks.iter().enumerate() {
+        for &qubit in qubits {
+            endpoints[qubit].push(check);
+        }
+    }
+
+    let mut adjacency = vec![Vec::new(); boundary + 1];
+    for &qubit in erased {
+        let (u, v) = match endpoints[qubit][..] {
+            [u, v] => (u, v),
+            [u] => (u, boundary),
+            _ => continue,
+        };
+        adjacency[u].push((qubit, v));
+        adjacency[v].push((qubit, u));
+    }
+
+    // (vertex, edge to its parent, parent) in discovery order, so every vertex
+    // comes after its parent
+    let mut visited = vec![false; boundary + 1];
+    let mut tree = Vec::new();
+    for root in std::iter::once(boundary).chain(0..boundary) {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut frontier = vec![root];
+        while let Some(vertex) = frontier.pop() {
+            for &(edge, next) in &adjacency[vertex] {
+                if !visited[next] {
+                    visited[next] = true;
+                    tree.push((next, edge, vertex));
+                    frontier.push(next);
+                }
+            }
+        }
+    }
+
+    let mut lit: Vec<bool> = syndrome.to_vec();
+    lit.push(false);
+    let mut correction = Vec::new();
+    for &(vertex, edge, parent) in tree.iter().rev() {
+        if lit[vertex] {
+            correction.push(edge);
+            lit[vertex] = false;
+            lit[parent] ^= true;
+        }
+    }
+
+    lit[..boundary].iter().all(|&l| !l).then_some(correction)
+}
@@ -0,0 +1,81 @@
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum QuantumError {
+    #[error("qubit index out of range")]
+    InvalidQubitIndex,
+    #[error("classical bit index out of range")]
+    InvalidClassicalBit,
+    #[error("dimensions of the operands do not match")]
+    DimensionMismatch,
+    #[error("invalid dimension")]
+    InvalidDimension,
+    #[error("register is too large to simulate")]
+    RegisterTooLarge,
+    #[error("state exceeds the simulator memory limit")]
+    StateTooLarge,
+    #[error("input and output registers overlap")]
+    OverlappingRegisters,
+    #[error("invalid gate")]
+    InvalidGate,
+    #[error("gate is not a Clifford")]
+    NonCliffordGate,
+    #[error("operation is not unitary")]
+    NonUnitaryOperation,
+    #[error("matrix is not unitary")]
+    NotUnitary,
+    #[error("native gate set cannot express the circuit")]
+    UnsupportedGateSet,
+    #[error("invalid probability")]
+    InvalidProbability,
+    #[error("invalid noise parameter")]
+    InvalidNoiseParameter,
+    #[error("invalid noise bias")]
+    InvalidBias,
+    #[error("invalid relaxation time")]
+    InvalidRelaxationTime,
+    #[error("time step is too long for the channel")]
+    InvalidTimeStep,
+    #[error("channel is not trace preserving")]
+    NotTracePreserving,
+    #[error("matrix is not positive semidefinite")]
+    NotPositiveSemidefinite,
+    #[error("mixture noise models cannot be expressed as one channel")]
+    MixedNoiseModel,
+    #[error("invalid device calibration")]
+    InvalidCalibration,
+    #[error("too few distinct data points for a fit")]
+    InsufficientData,
+    #[error("invalid checkpoint")]
+    InvalidCheckpoint,
+    #[error("invalid Pauli string")]
+    InvalidPauliString,
+    #[error("invalid stabilizer tableau")]
+    InvalidTableau,
+    #[error("stabilizer is not Hermitian")]
+    NonHermitianStabilizer,
+    #[error("stabilizers do not commute")]
+    StabilizersDoNotCommute,
+    #[error("stabilizers are not independent")]
+    DependentStabilizers,
+    #[error("code encodes no logical qubits")]
+    NoLogicalQubits,
+    #[error("invalid logical operator")]
+    InvalidLogicalOperator,
+    #[error("invalid code distance")]
+    InvalidCodeDistance,
+    #[error("declared distance does not match the code")]
+    DistanceMismatch,
+    #[error("inner code must encode a single logical qubit")]
+    InvalidConcatenation,
+    #[error("state is not in the code space")]
+    NotInCodeSpace,
+    #[error("syndrome has no known recovery")]
+    UnknownSyndrome,
+    #[error("recovery lookup cache I/O failed")]
+    LookupCacheIo,
+    #[error("malformed recovery lookup cache")]
+    MalformedLookupCache,
+    #[error("recovery lookup cache belongs to another code")]
+    LookupCacheMismatch,
+}
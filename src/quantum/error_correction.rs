@@ -1,16 +1,38 @@
 use super::*;
 use bitvec::prelude::*;
 use itertools::Itertools;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// Lookup-table decoding enumerates every error of weight below the distance,
+// which stops being tractable beyond this
+const MAX_LOOKUP_DISTANCE: usize = 5;
+
+// Which error a repetition code protects against: bit flips are caught by
+// Z_i Z_{i+1} checks, phase flips by X_i X_{i+1} checks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepetitionBasis {
+    BitFlip,
+    PhaseFlip,
+}
 
 #[derive(Clone, Debug)]
 pub struct ErrorCorrectionCode {
+    num_qubits: usize,
     distance: usize,
     stabilizers: Vec<Stabilizer>,
     logical_operators: Vec<LogicalOperator>,
-    recovery_lookup: HashMap<BitVec, RecoveryOperation>,
+    recovery_lookup: OnceLock<HashMap<BitVec, RecoveryOperation>>,
+    lattice: Option<CodeLattice>,
+    concatenation: Option<Box<Concatenation>>,
 }
 
 impl ErrorCorrectionCode {
+    // Steane [[7, 1, 3]] code: X and Z checks from the [7, 4] Hamming code, whose
+    // column for qubit q is q + 1 in binary, so every single-qubit error has its
+    // own syndrome. The logicals are the transversal X^7 and Z^7, equivalent up
+    // to stabilizers to weight-3 operators such as X0 X1 X2.
     pub fn new_steane_code() -> Self {
         let stabilizers = vec![
             Stabilizer::new(vec![
@@ -21,7 +43,13 @@ impl ErrorCorrectionCode {
             ]),
             Stabilizer::new(vec![
                 (1, PauliOperator::X),
+                (2, PauliOperator::X),
+                (5, PauliOperator::X),
+                (6, PauliOperator::X),
+            ]),
+            Stabilizer::new(vec![
                 (3, PauliOperator::X),
+                (4, PauliOperator::X),
                 (5, PauliOperator::X),
                 (6, PauliOperator::X),
             ]),
@@ -33,7 +61,13 @@ impl ErrorCorrectionCode {
             ]),
             Stabilizer::new(vec![
                 (1, PauliOperator::Z),
+                (2, PauliOperator::Z),
+                (5, PauliOperator::Z),
+                (6, PauliOperator::Z),
+            ]),
+            Stabilizer::new(vec![
                 (3, PauliOperator::Z),
+                (4, PauliOperator::Z),
                 (5, PauliOperator::Z),
                 (6, PauliOperator::Z),
             ]),
@@ -41,76 +75,483 @@ impl ErrorCorrectionCode {
 
         let logical_operators = vec![
             LogicalOperator::new(
-                vec![
-                    (0, PauliOperator::X),
-                    (1, PauliOperator::X),
-                    (2, PauliOperator::X),
-                    (3, PauliOperator::X),
-                ],
+                (0..7).map(|q| (q, PauliOperator::X)).collect(),
                 OperatorType::X,
             ),
+            LogicalOperator::new(
+                (0..7).map(|q| (q, PauliOperator::Z)).collect(),
+                OperatorType::Z,
+            ),
+        ];
+
+        Self {
+            num_qubits: 7,
+            distance: 3,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: None,
+        }
+    }
+
+    // Shor [[9, 1, 3]] code: three bit-flip repetition blocks {0,1,2}, {3,4,5},
+    // {6,7,8} concatenated under a phase-flip code, so that
+    // |0_L> = (|000> + |111>)^3 and |1_L> = (|000> - |111>)^3
+    pub fn new_shor_code() -> Self {
+        let mut stabilizers = Vec::new();
+        for block in [0, 3, 6] {
+            for offset in 0..2 {
+                stabilizers.push(Stabilizer::new(vec![
+                    (block + offset, PauliOperator::Z),
+                    (block + offset + 1, PauliOperator::Z),
+                ]));
+            }
+        }
+        for start in [0, 3] {
+            stabilizers.push(Stabilizer::new(
+                (start..start + 6).map(|q| (q, PauliOperator::X)).collect(),
+            ));
+        }
+
+        let logical_operators = vec![
             LogicalOperator::new(
                 vec![
                     (0, PauliOperator::Z),
-                    (1, PauliOperator::Z),
-                    (2, PauliOperator::Z),
                     (3, PauliOperator::Z),
+                    (6, PauliOperator::Z),
+                ],
+                OperatorType::X,
+            ),
+            LogicalOperator::new(
+                vec![
+                    (0, PauliOperator::X),
+                    (1, PauliOperator::X),
+                    (2, PauliOperator::X),
                 ],
                 OperatorType::Z,
             ),
         ];
 
-        let mut code = Self {
+        Self {
+            num_qubits: 9,
             distance: 3,
             stabilizers,
             logical_operators,
-            recovery_lookup: HashMap::new(),
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: None,
+        }
+    }
+
+    // [[d, 1]] repetition code. `distance` is against the protected error type
+    // only; a single error of the other type is already a logical fault.
+    pub fn new_repetition_code(
+        distance: usize,
+        basis: RepetitionBasis,
+    ) -> Result<Self, QuantumError> {
+        if distance < 2 {
+            return Err(QuantumError::InvalidCodeDistance);
+        }
+
+        let (check, flip) = match basis {
+            RepetitionBasis::BitFlip => (PauliOperator::Z, PauliOperator::X),
+            RepetitionBasis::PhaseFlip => (PauliOperator::X, PauliOperator::Z),
+        };
+        let stabilizers = (0..distance - 1)
+            .map(|q| Stabilizer::new(vec![(q, check), (q + 1, check)]))
+            .collect();
+
+        // The logical flip acts on every qubit, the logical phase on any single one
+        let transversal = (0..distance).map(|q| (q, flip)).collect();
+        let logical_operators = match basis {
+            RepetitionBasis::BitFlip => vec![
+                LogicalOperator::new(transversal, OperatorType::X),
+                LogicalOperator::new(vec![(0, check)], OperatorType::Z),
+            ],
+            RepetitionBasis::PhaseFlip => vec![
+                LogicalOperator::new(vec![(0, check)], OperatorType::X),
+                LogicalOperator::new(transversal, OperatorType::Z),
+            ],
+        };
+
+        Ok(Self {
+            num_qubits: distance,
+            distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: None,
+        })
+    }
+
+    // Rotated surface code [[d^2, 1, d]] for odd d >= 3
+    pub fn new_surface_code(distance: usize) -> Result<Self, QuantumError> {
+        let layout = SurfaceCodeLayout::new(distance)?;
+
+        let plaquettes = |checks: &[Vec<usize>], pauli: PauliOperator| {
+            checks
+                .iter()
+                .map(|qubits| Stabilizer::new(qubits.iter().map(|&q| (q, pauli)).collect()))
+                .collect::<Vec<_>>()
         };
+        let mut stabilizers = plaquettes(&layout.x_plaquettes, PauliOperator::X);
+        stabilizers.extend(plaquettes(&layout.z_plaquettes, PauliOperator::Z));
+
+        let logical_operators = vec![
+            LogicalOperator::new(
+                layout.logical_x().into_iter().map(|q| (q, PauliOperator::X)).collect(),
+                OperatorType::X,
+            ),
+            LogicalOperator::new(
+                layout.logical_z().into_iter().map(|q| (q, PauliOperator::Z)).collect(),
+                OperatorType::Z,
+            ),
+        ];
 
-        code.precompute_recovery_operations();
-        code
+        Ok(Self {
+            num_qubits: layout.num_qubits(),
+            distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: Some(CodeLattice::RotatedSurface(layout)),
+            concatenation: None,
+        })
+    }
+
+    // Triangular 6.6.6 color code [[(3d^2 + 1) / 4, 1, d]] for odd d >= 3,
+    // with transversal H, S and CNOT available from its layout
+    pub fn new_color_code(distance: usize) -> Result<Self, QuantumError> {
+        let layout = ColorCodeLayout::new(distance)?;
+
+        let mut stabilizers = Vec::new();
+        for pauli in [PauliOperator::X, PauliOperator::Z] {
+            stabilizers.extend(
+                layout
+                    .faces
+                    .iter()
+                    .map(|face| Stabilizer::new(face.iter().map(|&q| (q, pauli)).collect())),
+            );
+        }
+
+        let support = layout.logical_support();
+        let logical_operators = vec![
+            LogicalOperator::new(
+                support.iter().map(|&q| (q, PauliOperator::X)).collect(),
+                OperatorType::X,
+            ),
+            LogicalOperator::new(
+                support.iter().map(|&q| (q, PauliOperator::Z)).collect(),
+                OperatorType::Z,
+            ),
+        ];
+
+        Ok(Self {
+            num_qubits: layout.num_qubits(),
+            distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: Some(CodeLattice::Color(layout)),
+            concatenation: None,
+        })
+    }
+
+    // CSS code from classical parity checks: rows of `hx` become X stabilizers
+    // and rows of `hz` Z stabilizers, which commute iff hx hz^T = 0. Logical
+    // operators are derived as paired bases of ker(hz) / row(hx) and
+    // ker(hx) / row(hz). The distance is exact for small codes and otherwise
+    // the weight of the lightest logical found, an upper bound.
+    pub fn from_css(hx: &[Vec<bool>], hz: &[Vec<bool>]) -> Result<Self, QuantumError> {
+        let num_qubits = hx.iter().chain(hz).map(|row| row.len()).max().unwrap_or(0);
+        if num_qubits == 0 || hx.iter().chain(hz).any(|row| row.len() != num_qubits) {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        if hx.iter().any(|x| hz.iter().any(|z| gf2::dot(x, z))) {
+            return Err(QuantumError::StabilizersDoNotCommute);
+        }
+
+        let (x_logicals, z_logicals) = css_logicals(hx, hz, num_qubits);
+        if x_logicals.is_empty() {
+            return Err(QuantumError::NoLogicalQubits);
+        }
+        let bound = x_logicals
+            .iter()
+            .chain(&z_logicals)
+            .map(|logical| gf2::weight(logical))
+            .min()
+            .unwrap_or(num_qubits);
+        let distance = css_distance(hx, hz, num_qubits, bound).value();
+
+        let sparse = |row: &[bool], pauli: PauliOperator| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &set)| set)
+                .map(|(q, _)| (q, pauli))
+                .collect::<Vec<_>>()
+        };
+        let stabilizers = hx
+            .iter()
+            .map(|row| Stabilizer::new(sparse(row, PauliOperator::X)))
+            .chain(hz.iter().map(|row| Stabilizer::new(sparse(row, PauliOperator::Z))))
+            .collect();
+        let logical_operators = x_logicals
+            .iter()
+            .zip(&z_logicals)
+            .flat_map(|(x, z)| {
+                [
+                    LogicalOperator::new(sparse(x, PauliOperator::X), OperatorType::X),
+                    LogicalOperator::new(sparse(z, PauliOperator::Z), OperatorType::Z),
+                ]
+            })
+            .collect();
+
+        Ok(Self {
+            num_qubits,
+            distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: None,
+        })
+    }
+
+    // Hypergraph product of classical checks h1 (m1 x n1) and h2 (m2 x n2), on
+    // n1 n2 + m1 m2 qubits: hx = [h1 (x) I | I (x) h2^T], hz = [I (x) h2 | h1^T (x) I]
+    pub fn new_hypergraph_product(
+        h1: &[Vec<bool>],
+        h2: &[Vec<bool>],
+    ) -> Result<Self, QuantumError> {
+        let (m1, n1) = matrix_shape(h1)?;
+        let (m2, n2) = matrix_shape(h2)?;
+
+        let hx = gf2::hstack(
+            &gf2::kron(h1, &gf2::identity(n2)),
+            &gf2::kron(&gf2::identity(m1), &gf2::transpose(h2, n2)),
+        );
+        let hz = gf2::hstack(
+            &gf2::kron(&gf2::identity(n1), h2),
+            &gf2::kron(&gf2::transpose(h1, n1), &gf2::identity(m2)),
+        );
+        Self::from_css(&hx, &hz)
+    }
+
+    // Lifted product over F2[x] / (x^l - 1): entries of `a` and `b` are lists of
+    // exponents of x, each lifted to an l x l circulant. With * the conjugate
+    // transpose, hx = [a (x) I | I (x) b] and hz = [I (x) b* | a* (x) I]; a and b
+    // commute as ring elements, which is what makes hx hz^T vanish.
+    pub fn new_lifted_product(
+        a: &[Vec<Vec<usize>>],
+        b: &[Vec<Vec<usize>>],
+        lift: usize,
+    ) -> Result<Self, QuantumError> {
+        if lift == 0 {
+            return Err(QuantumError::InvalidDimension);
+        }
+        let (ma, na) = matrix_shape(a)?;
+        let (mb, nb) = matrix_shape(b)?;
+
+        let ring_identity = |size: usize| -> Vec<Vec<Vec<usize>>> {
+            (0..size)
+                .map(|i| (0..size).map(|j| if i == j { vec![0] } else { vec![] }).collect())
+                .collect()
+        };
+        let lifted = |m: Vec<Vec<Vec<usize>>>| lift_circulants(&m, lift);
+
+        let hx = gf2::hstack(
+            &lifted(ring_kron(a, &ring_identity(mb), lift)),
+            &lifted(ring_kron(&ring_identity(ma), b, lift)),
+        );
+        let hz = gf2::hstack(
+            &lifted(ring_kron(&ring_identity(na), &ring_adjoint(b, nb, lift), lift)),
+            &lifted(ring_kron(&ring_adjoint(a, na, lift), &ring_identity(nb), lift)),
+        );
+        Self::from_css(&hx, &hz)
+    }
+
+    // Stabilizers and logicals are taken as already validated, e.g. by
+    // ErrorCorrectionCodeBuilder
+    pub(crate) fn from_validated_parts(
+        num_qubits: usize,
+        distance: usize,
+        stabilizers: Vec<Stabilizer>,
+        logical_operators: Vec<LogicalOperator>,
+    ) -> Self {
+        Self {
+            num_qubits,
+            distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: None,
+        }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn distance(&self) -> usize {
+        self.distance
     }
 
     pub fn get_stabilizers(&self) -> &[Stabilizer] {
         &self.stabilizers
     }
 
+    pub fn logical_operators(&self) -> &[LogicalOperator] {
+        &self.logical_operators
+    }
+
+    pub fn lattice(&self) -> Option<&CodeLattice> {
+        self.lattice.as_ref()
+    }
+
+    // Bit i is set when the Pauli error anticommutes with stabilizer i
+    pub fn syndrome_of(&self, error: &[(usize, PauliOperator)]) -> Vec<bool> {
+        let error = PauliString::from_sparse(error);
+        self.stabilizers
+            .iter()
+            .map(|stabilizer| !stabilizer.pauli().commutes_with(&error))
+            .collect()
+    }
+
     pub fn compute_recovery_operation(&self, syndrome: &ErrorSyndrome) -> Result<RecoveryOperation, QuantumError> {
-        let syndrome_bits = syndrome.to_bitvec();
-        self.recovery_lookup
-            .get(&syndrome_bits)
-            .cloned()
-            .ok_or(QuantumError::UnknownSyndrome)
+        self.decode(&syndrome.to_vec()).map(RecoveryOperation::from_pauli)
     }
 
-    fn precompute_recovery_operations(&mut self) {
-        let n = self.stabilizers.len();
-        let all_errors = self.enumerate_likely_errors();
+    // Correction for a syndrome over get_stabilizers(): a table lookup for
+    // plain codes, inner blocks first and then the outer code for concatenated ones.
+    // Syndromes missing from the table are decoded on demand by a minimum-weight
+    // search.
+    pub fn decode(&self, syndrome: &[bool]) -> Result<PauliString, QuantumError> {
+        if syndrome.len() != self.stabilizers.len() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        if let Some(levels) = &self.concatenation {
+            return self.decode_hierarchically(levels, syndrome);
+        }
+        if syndrome.iter().all(|&bit| !bit) {
+            return Ok(PauliString::identity(self.num_qubits));
+        }
 
-        for error in all_errors {
-            let syndrome = self.compute_syndrome_for_error(&error);
-            if !self.recovery_lookup.contains_key(&syndrome) {
-                self.recovery_lookup.insert(syndrome, RecoveryOperation::from_error(&error));
+        let bits: BitVec = syndrome.iter().copied().collect();
+        match self.recovery_table().get(&bits) {
+            Some(recovery) => Ok(recovery.pauli().clone()),
+            None => self.spacetime_decoder(1).decode(syndrome),
+        }
+    }
+
+    fn decode_hierarchically(
+        &self,
+        levels: &Concatenation,
+        syndrome: &[bool],
+    ) -> Result<PauliString, QuantumError> {
+        let block_size = levels.inner.num_qubits;
+        let inner_checks = levels.inner.stabilizers.len();
+
+        let mut correction = PauliString::identity(self.num_qubits);
+        for block in 0..levels.outer.num_qubits {
+            let bits = &syndrome[block * inner_checks..(block + 1) * inner_checks];
+            let local = levels.inner.decode(bits)?;
+            correction = correction.multiply(&local.shifted(block * block_size));
+        }
+
+        // What the inner corrections leave behind is a logical error on each
+        // block, which the outer checks see once their syndrome is updated
+        let start = levels.outer.num_qubits * inner_checks;
+        let outer_syndrome: Vec<bool> = self.stabilizers[start..]
+            .iter()
+            .zip(&syndrome[start..])
+            .map(|(stabilizer, &bit)| bit ^ !stabilizer.pauli().commutes_with(&correction))
+            .collect();
+        let outer = levels.outer.decode(&outer_syndrome)?;
+
+        Ok(correction.multiply(&levels.lift(&outer)?))
+    }
+
+    // Built on first use: read from the lookup cache directory when it holds a
+    // table for this stabilizer set, otherwise computed and written back
+    fn recovery_table(&self) -> &HashMap<BitVec, RecoveryOperation> {
+        self.recovery_lookup.get_or_init(|| {
+            if self.distance > MAX_LOOKUP_DISTANCE {
+                return HashMap::new();
             }
+            let key = self.lookup_cache_key();
+            let cached = lookup_cache_path(&key).and_then(|path| read_lookup(&path, &key).ok());
+            cached.unwrap_or_else(|| {
+                let table = self.precompute_recovery_operations();
+                if let Some(path) = lookup_cache_path(&key) {
+                    // A cache that cannot be written only costs a recomputation
+                    let _ = write_lookup(&path, &key, &table, self.num_qubits);
+                }
+                table
+            })
+        })
+    }
+
+    fn precompute_recovery_operations(&self) -> HashMap<BitVec, RecoveryOperation> {
+        let mut table = HashMap::new();
+        for error in self.enumerate_likely_errors() {
+            let syndrome = self.compute_syndrome_for_error(&error);
+            table
+                .entry(syndrome)
+                .or_insert_with(|| RecoveryOperation::from_pauli(error));
+        }
+        table
+    }
+
+    // Identifies the table: qubit count, distance and stabilizers (signs aside,
+    // since syndromes only record commutation)
+    pub fn lookup_cache_key(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&(self.num_qubits as u64).to_le_bytes());
+        hasher.update(&(self.distance as u64).to_le_bytes());
+        for stabilizer in &self.stabilizers {
+            let bits: Vec<u8> = stabilizer
+                .pauli()
+                .to_symplectic(self.num_qubits)
+                .into_iter()
+                .map(u8::from)
+                .collect();
+            hasher.update(&bits);
         }
+        *hasher.finalize().as_bytes()
     }
 
-    fn enumerate_likely_errors(&self) -> Vec<QuantumError> {
-        let n = self.distance - 1;
+    pub fn save_recovery_lookup(&self, path: impl AsRef<Path>) -> Result<(), QuantumError> {
+        write_lookup(
+            path.as_ref(),
+            &self.lookup_cache_key(),
+            self.recovery_table(),
+            self.num_qubits,
+        )
+    }
+
+    // Fails with LookupCacheMismatch when the file belongs to another code
+    pub fn load_recovery_lookup(&mut self, path: impl AsRef<Path>) -> Result<(), QuantumError> {
+        let table = read_lookup(path.as_ref(), &self.lookup_cache_key())?;
+        self.recovery_lookup = OnceLock::from(table);
+        Ok(())
+    }
+
+    // Every Pauli error of weight 1 to (d - 1) / 2, lightest first, so each
+    // syndrome keeps a minimum-weight correction
+    fn enumerate_likely_errors(&self) -> Vec<PauliString> {
+        let correctable = (self.distance.max(1) - 1) / 2;
         let mut errors = Vec::new();
 
-        for t in 0..=n {
-            for positions in (0..self.stabilizers.len()).combinations(t) {
-                for error_types in (0..3).combinations_with_replacement(t) {
-                    let mut error = QuantumError::new(self.stabilizers.len());
-                    for (pos, err_type) in positions.iter().zip(error_types) {
-                        error.add_pauli(*pos, match err_type {
-                            0 => PauliOperator::X,
-                            1 => PauliOperator::Y,
-                            _ => PauliOperator::Z,
-                        });
-                    }
-                    errors.push(error);
+        for weight in 1..=correctable {
+            for positions in (0..self.num_qubits).combinations(weight) {
+                let choices = [PauliOperator::X, PauliOperator::Y, PauliOperator::Z];
+                for paulis in std::iter::repeat(choices).take(weight).multi_cartesian_product() {
+                    let error: Vec<(usize, PauliOperator)> =
+                        positions.iter().copied().zip(paulis).collect();
+                    errors.push(PauliString::from_sparse(&error));
                 }
             }
         }
@@ -118,31 +559,245 @@ impl ErrorCorrectionCode {
         errors
     }
 
-    fn compute_syndrome_for_error(&self, error: &QuantumError) -> BitVec {
-        let mut syndrome = bitvec![0; self.stabilizers.len()];
-        
-        for (i, stabilizer) in self.stabilizers.iter().enumerate() {
-            let mut parity = false;
-            for (qubit, pauli) in stabilizer.iter() {
-                if let Some(error_pauli) = error.get_pauli(*qubit) {
-                    parity ^= Self::commutes(pauli, error_pauli);
+    fn compute_syndrome_for_error(&self, error: &PauliString) -> BitVec {
+        self.stabilizers
+            .iter()
+            .map(|stabilizer| !stabilizer.pauli().commutes_with(error))
+            .collect()
+    }
+
+    // Each qubit of `outer` is replaced by a block of `inner`, which must encode
+    // a single logical qubit. The distance is at least the product of the two.
+    pub fn concatenate(
+        outer: &ErrorCorrectionCode,
+        inner: &ErrorCorrectionCode,
+    ) -> Result<Self, QuantumError> {
+        if inner.num_logical_qubits() != 1 {
+            return Err(QuantumError::InvalidConcatenation);
+        }
+        let levels = Concatenation {
+            outer: outer.clone(),
+            inner: inner.clone(),
+        };
+
+        let mut stabilizers = Vec::new();
+        for block in 0..outer.num_qubits {
+            let offset = block * inner.num_qubits;
+            stabilizers.extend(
+                inner
+                    .stabilizers
+                    .iter()
+                    .map(|s| Stabilizer::from_pauli(s.pauli().shifted(offset))),
+            );
+        }
+        for stabilizer in &outer.stabilizers {
+            let lifted = levels.lift(stabilizer.pauli())?;
+            stabilizers.push(Stabilizer::new(lifted.iter().collect()));
+        }
+
+        let logical_operators = outer
+            .logical_operators
+            .iter()
+            .map(|op| {
+                let lifted = levels.lift(op.pauli())?;
+                Ok(LogicalOperator::new(lifted.iter().collect(), op.operator_type()))
+            })
+            .collect::<Result<Vec<_>, QuantumError>>()?;
+
+        Ok(Self {
+            num_qubits: outer.num_qubits * inner.num_qubits,
+            distance: outer.distance * inner.distance,
+            stabilizers,
+            logical_operators,
+            recovery_lookup: OnceLock::new(),
+            lattice: None,
+            concatenation: Some(Box::new(levels)),
+        })
+    }
+}
+
+// Concatenated stabilizers list the inner ones block by block, then the outer
+// ones with each Pauli replaced by the matching inner logical
+#[derive(Clone, Debug)]
+struct Concatenation {
+    outer: ErrorCorrectionCode,
+    inner: ErrorCorrectionCode,
+}
+
+impl Concatenation {
+    // Outer qubit b becomes inner block b; signs are dropped, as elsewhere
+    fn lift(&self, pauli: &PauliString) -> Result<PauliString, QuantumError> {
+        let mut lifted = PauliString::identity(0);
+        for (block, operator) in pauli.iter() {
+            let logical = match operator {
+                PauliOperator::X => self.inner.logical_x(block)?,
+                PauliOperator::Z => self.inner.logical_z(block)?,
+                PauliOperator::Y => self
+                    .inner
+                    .logical_x(block)?
+                    .multiply(&self.inner.logical_z(block)?),
+            };
+            lifted = lifted.multiply(&logical);
+        }
+        Ok(lifted)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryOperation {
+    correction: PauliString,
+}
+
+impl RecoveryOperation {
+    pub fn from_pauli(correction: PauliString) -> Self {
+        Self { correction }
+    }
+
+    pub fn pauli(&self) -> &PauliString {
+        &self.correction
+    }
+
+    pub fn apply(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        self.correction.apply(state)
+    }
+}
+
+// Rows and columns of a non-empty rectangular matrix
+fn matrix_shape<T>(rows: &[Vec<T>]) -> Result<(usize, usize), QuantumError> {
+    let num_columns = rows.first().map_or(0, |row| row.len());
+    if num_columns == 0 || rows.iter().any(|row| row.len() != num_columns) {
+        return Err(QuantumError::DimensionMismatch);
+    }
+    Ok((rows.len(), num_columns))
+}
+
+// Product in F2[x] / (x^l - 1) of elements given by their exponents
+fn ring_multiply(p: &[usize], q: &[usize], lift: usize) -> Vec<usize> {
+    let mut coefficients = vec![false; lift];
+    for &i in p {
+        for &j in q {
+            coefficients[(i + j) % lift] ^= true;
+        }
+    }
+    (0..lift).filter(|&k| coefficients[k]).collect()
+}
+
+fn ring_kron(a: &[Vec<Vec<usize>>], b: &[Vec<Vec<usize>>], lift: usize) -> Vec<Vec<Vec<usize>>> {
+    a.iter()
+        .flat_map(|a_row| {
+            b.iter().map(move |b_row| {
+                a_row
+                    .iter()
+                    .flat_map(|x| b_row.iter().map(move |y| ring_multiply(x, y, lift)))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+// Transpose with x -> x^-1 on every entry
+fn ring_adjoint(m: &[Vec<Vec<usize>>], num_columns: usize, lift: usize) -> Vec<Vec<Vec<usize>>> {
+    (0..num_columns)
+        .map(|j| {
+            m.iter()
+                .map(|row| row[j].iter().map(|&e| (lift - e % lift) % lift).collect())
+                .collect()
+        })
+        .collect()
+}
+
+// x^k becomes the cyclic shift e_c -> e_{c + k}
+fn lift_circulants(m: &[Vec<Vec<usize>>], lift: usize) -> Vec<Vec<bool>> {
+    let num_columns = m.first().map_or(0, |row| row.len()) * lift;
+    let mut lifted = vec![vec![false; num_columns]; m.len() * lift];
+    for (i, row) in m.iter().enumerate() {
+        for (j, exponents) in row.iter().enumerate() {
+            for &e in exponents {
+                for c in 0..lift {
+                    lifted[i * lift + (c + e) % lift][j * lift + c] ^= true;
                 }
             }
-            syndrome.set(i, parity);
         }
+    }
+    lifted
+}
+
+// X and Z logical bases with X_i anticommuting with Z_j exactly when i = j
+fn css_logicals(
+    hx: &[Vec<bool>],
+    hz: &[Vec<bool>],
+    num_qubits: usize,
+) -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
+    let mut xs = gf2::complement_basis(hx, &gf2::kernel(hz, num_qubits));
+    let mut zs = gf2::complement_basis(hz, &gf2::kernel(hx, num_qubits));
 
-        syndrome
+    for i in 0..xs.len() {
+        let partner = (i..zs.len())
+            .find(|&j| gf2::dot(&xs[i], &zs[j]))
+            .expect("the logical pairing form is non-degenerate");
+        zs.swap(i, partner);
+        for j in 0..xs.len() {
+            if j == i {
+                continue;
+            }
+            if gf2::dot(&xs[i], &zs[j]) {
+                let pivot = zs[i].clone();
+                gf2::add_assign(&mut zs[j], &pivot);
+            }
+            if gf2::dot(&xs[j], &zs[i]) {
+                let pivot = xs[i].clone();
+                gf2::add_assign(&mut xs[j], &pivot);
+            }
+        }
+    }
+
+    (xs, zs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steane_single_qubit_errors_have_distinct_syndromes() {
+        let code = ErrorCorrectionCode::new_steane_code();
+        assert_eq!(code.get_stabilizers().len(), 6);
+
+        let mut seen = HashMap::new();
+        for qubit in 0..7 {
+            for pauli in [PauliOperator::X, PauliOperator::Y, PauliOperator::Z] {
+                let syndrome = code.syndrome_of(&[(qubit, pauli)]);
+                assert!(
+                    syndrome.iter().any(|&bit| bit),
+                    "{:?} on {} is undetected",
+                    pauli,
+                    qubit
+                );
+                if let Some(previous) = seen.insert(syndrome, (qubit, pauli)) {
+                    panic!("{:?} on {} shares a syndrome with {:?}", pauli, qubit, previous);
+                }
+            }
+        }
+        assert_eq!(seen.len(), 21);
     }
 
-    fn commutes(a: &PauliOperator, b: &PauliOperator) -> bool {
-        match (a, b) {
-            (PauliOperator::X, PauliOperator::Y) => false,
-            (PauliOperator::X, PauliOperator::Z) => false,
-            (PauliOperator::Y, PauliOperator::X) => false,
-            (PauliOperator::Y, PauliOperator::Z) => false,
-            (PauliOperator::Z, PauliOperator::X) => false,
-            (PauliOperator::Z, PauliOperator::Y) => false,
-            _ => true,
+    #[test]
+    fn steane_logicals_commute_with_stabilizers_and_anticommute_with_each_other() {
+        let code = ErrorCorrectionCode::new_steane_code();
+        let logicals = code.logical_operators();
+        assert_eq!(logicals.len(), 2);
+        assert!(logicals.iter().all(|l| l.pauli().weight() == 7));
+        for logical in logicals {
+            assert!(code
+                .get_stabilizers()
+                .iter()
+                .all(|s| s.pauli().commutes_with(logical.pauli())));
         }
+        assert!(!logicals[0].pauli().commutes_with(logicals[1].pauli()));
+
+        // X^7 times the third X check is the weight-3 X0 X1 X2
+        let reduced = logicals[0].pauli().multiply(code.get_stabilizers()[2].pauli());
+        assert_eq!(reduced.support(), vec![0, 1, 2]);
+        assert_eq!(code.compute_distance(), CodeDistance::Exact(3));
     }
-}
\ No newline at end of file
+}
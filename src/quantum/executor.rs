@@ -0,0 +1,235 @@
+use super::*;
+use rand::Rng;
+use std::collections::HashMap;
+
+// Gate families, ignoring angles and which qubits they act on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GateKind {
+    Hadamard,
+    PauliX,
+    PauliY,
+    PauliZ,
+    Phase,
+    Cnot,
+    Rx,
+    Ry,
+    Rz,
+    SqrtX,
+    SqrtXDagger,
+    U3,
+    Swap,
+    MultiControlledX,
+    Unitary1,
+    Unitary2,
+}
+
+impl QuantumGate {
+    pub fn kind(&self) -> GateKind {
+        match self {
+            QuantumGate::Hadamard => GateKind::Hadamard,
+            QuantumGate::PauliX => GateKind::PauliX,
+            QuantumGate::PauliY => GateKind::PauliY,
+            QuantumGate::PauliZ => GateKind::PauliZ,
+            QuantumGate::Phase(_) => GateKind::Phase,
+            QuantumGate::CNOT(_) => GateKind::Cnot,
+            QuantumGate::Rx(_) => GateKind::Rx,
+            QuantumGate::Ry(_) => GateKind::Ry,
+            QuantumGate::Rz(_) => GateKind::Rz,
+            QuantumGate::SqrtX => GateKind::SqrtX,
+            QuantumGate::SqrtXDagger => GateKind::SqrtXDagger,
+            QuantumGate::U3(..) => GateKind::U3,
+            QuantumGate::Swap(_) => GateKind::Swap,
+            QuantumGate::MultiControlledX(_) => GateKind::MultiControlledX,
+            QuantumGate::Unitary1(_) => GateKind::Unitary1,
+            QuantumGate::Unitary2(..) => GateKind::Unitary2,
+        }
+    }
+}
+
+// Runs gates and measurements with their noise attached, so callers cannot
+// forget to interleave it. After every gate: a depolarizing error at the
+// rate set for its kind (two-qubit depolarizing on two-qubit gates,
+// independent per qubit on larger ones), then the model's gate noise. After
+// every operation, gate or measurement: one noise layer of the model at the
+// executor's clock, which then advances by one. Measurements report through
+// the model's readout error.
+#[derive(Clone, Debug)]
+pub struct NoisyExecutor {
+    noise: NoiseModel,
+    gate_errors: HashMap<GateKind, f64>,
+    default_gate_error: f64,
+    pub(crate) colored_dephasing: Option<ColoredDephasing>,
+    pub(crate) trace: Option<NoiseTrace>,
+    time: usize,
+}
+
+impl NoisyExecutor {
+    pub fn new(noise: NoiseModel) -> Self {
+        Self {
+            noise,
+            gate_errors: HashMap::new(),
+            default_gate_error: 0.0,
+            colored_dephasing: None,
+            trace: None,
+            time: 0,
+        }
+    }
+
+    pub fn with_gate_error(
+        mut self,
+        kind: GateKind,
+        probability: f64,
+    ) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        self.gate_errors.insert(kind, probability);
+        Ok(self)
+    }
+
+    // Rate for every kind without its own
+    pub fn with_default_gate_error(mut self, probability: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        self.default_gate_error = probability;
+        Ok(self)
+    }
+
+    pub fn noise(&self) -> &NoiseModel {
+        &self.noise
+    }
+
+    pub fn gate_error(&self, kind: GateKind) -> f64 {
+        self.gate_errors
+            .get(&kind)
+            .copied()
+            .unwrap_or(self.default_gate_error)
+    }
+
+    // Noise layers applied so far
+    pub fn time(&self) -> usize {
+        self.time
+    }
+
+    // Also restarts any colored dephasing and clears the trace, ready for a
+    // new run
+    pub fn reset_clock(&mut self) {
+        self.time = 0;
+        if let Some(dephasing) = &mut self.colored_dephasing {
+            dephasing.reset();
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    fn idle<R: Rng + ?Sized>(
+        &mut self,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.noise
+            .apply_layer_at(state, self.time as f64, self.trace.as_mut(), rng)?;
+        if let Some(dephasing) = &mut self.colored_dephasing {
+            dephasing.apply(state, rng)?;
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.step();
+        }
+        self.time += 1;
+        Ok(())
+    }
+
+    pub fn apply_gate<R: Rng + ?Sized>(
+        &mut self,
+        state: &mut QuantumState,
+        gate: QuantumGate,
+        target: usize,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        state.apply_gate(gate.clone(), target)?;
+        let probability = self.gate_error(gate.kind());
+        if probability > 0.0 {
+            let qubits = gate.qubits(target);
+            let (channel, targets) = if qubits.len() == 2 {
+                (
+                    QuantumChannelOp::two_qubit_depolarizing(probability)?,
+                    vec![qubits],
+                )
+            } else {
+                (
+                    QuantumChannelOp::depolarizing(probability)?,
+                    qubits.into_iter().map(|q| vec![q]).collect(),
+                )
+            };
+            for targets in targets {
+                let index = channel.unravel(state, &targets, rng)?;
+                if let Some(trace) = &mut self.trace {
+                    trace.record_branch(&channel, &targets, index);
+                }
+            }
+        }
+        self.noise
+            .gate_noise(state, &gate, target, self.trace.as_mut(), rng)?;
+        self.idle(state, rng)
+    }
+
+    pub fn measure<R: Rng + ?Sized>(
+        &mut self,
+        state: &mut QuantumState,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> Result<Measurement, QuantumError> {
+        let measurement = state.measure_with_readout(qubits, &self.noise, rng)?;
+        self.idle(state, rng)?;
+        Ok(measurement)
+    }
+
+    pub fn reset<R: Rng + ?Sized>(
+        &mut self,
+        state: &mut QuantumState,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        state.reset(qubit, rng)?;
+        self.idle(state, rng)
+    }
+
+    // Runs `circuit` operation by operation, returning the classical register
+    // as reported (readout errors included); conditions read the reported bits
+    pub fn run<R: Rng + ?Sized>(
+        &mut self,
+        circuit: &QuantumCircuit,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<Vec<bool>, QuantumError> {
+        if state.num_qubits < circuit.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for operation in circuit.operations() {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    self.apply_gate(state, instruction.gate.clone(), instruction.target, rng)?
+                }
+                CircuitOperation::Measure { qubit, clbit } => {
+                    clbits[*clbit] = self.measure(state, &[*qubit], rng)?.reported[0];
+                }
+                CircuitOperation::Reset(qubit) => self.reset(state, *qubit, rng)?,
+                CircuitOperation::ClassicallyControlled {
+                    condition,
+                    gate,
+                    target,
+                } => {
+                    if condition.evaluate(&clbits)? {
+                        self.apply_gate(state, gate.clone(), *target, rng)?;
+                    } else {
+                        self.idle(state, rng)?;
+                    }
+                }
+            }
+        }
+        Ok(clbits)
+    }
+}
@@ -0,0 +1,192 @@
+use super::*;
+use std::collections::HashMap;
+
+const IDENTITY_TOLERANCE: f64 = 1e-12;
+
+struct SingleRun {
+    matrix: Matrix2,
+    gates: Vec<QuantumGate>,
+}
+
+struct PairBlock {
+    high: usize,
+    low: usize,
+    matrix: Matrix4,
+    operations: Vec<CircuitOperation>,
+}
+
+// Merges runs of gates before simulation: consecutive single-qubit gates on a
+// qubit become one Unitary1, and gates confined to a qubit pair between
+// interruptions become one Unitary2. Runs of a single gate are left as-is.
+#[derive(Clone, Debug)]
+pub struct GateFusion {
+    two_qubit: bool,
+}
+
+impl Default for GateFusion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GateFusion {
+    pub fn new() -> Self {
+        Self { two_qubit: true }
+    }
+
+    pub fn single_qubit_only(mut self) -> Self {
+        self.two_qubit = false;
+        self
+    }
+
+    pub fn run(&self, circuit: &QuantumCircuit) -> QuantumCircuit {
+        let fused = Self::fuse_single_qubit(circuit);
+        if self.two_qubit {
+            Self::fuse_two_qubit(&fused)
+        } else {
+            fused
+        }
+    }
+
+    fn touched_qubits(operation: &CircuitOperation) -> Vec<usize> {
+        match operation {
+            CircuitOperation::Gate(instruction) => instruction.gate.qubits(instruction.target),
+            CircuitOperation::Measure { qubit, .. } | CircuitOperation::Reset(qubit) => {
+                vec![*qubit]
+            }
+            CircuitOperation::ClassicallyControlled { gate, target, .. } => gate.qubits(*target),
+        }
+    }
+
+    fn fuse_single_qubit(circuit: &QuantumCircuit) -> QuantumCircuit {
+        let mut output = QuantumCircuit::new(circuit.num_qubits());
+        let mut runs: HashMap<usize, SingleRun> = HashMap::new();
+
+        let flush =
+            |qubit: usize, runs: &mut HashMap<usize, SingleRun>, output: &mut QuantumCircuit| {
+                if let Some(run) = runs.remove(&qubit) {
+                    if run.gates.len() == 1 {
+                        output.add_gate(run.gates[0].clone(), qubit);
+                    } else if unitary::phase_distance2(&run.matrix, &unitary::identity2())
+                        > IDENTITY_TOLERANCE
+                    {
+                        output.add_gate(QuantumGate::Unitary1(run.matrix), qubit);
+                    }
+                }
+            };
+
+        for operation in circuit.operations() {
+            if let CircuitOperation::Gate(instruction) = operation {
+                if let Some(matrix) = instruction.gate.matrix() {
+                    let run = runs.entry(instruction.target).or_insert_with(|| SingleRun {
+                        matrix: unitary::identity2(),
+                        gates: Vec::new(),
+                    });
+                    run.matrix = unitary::mul2(&matrix, &run.matrix);
+                    run.gates.push(instruction.gate.clone());
+                    continue;
+                }
+            }
+
+            for qubit in Self::touched_qubits(operation) {
+                flush(qubit, &mut runs, &mut output);
+            }
+            output.push_operation(operation.clone());
+        }
+
+        let mut remaining: Vec<usize> = runs.keys().copied().collect();
+        remaining.sort_unstable();
+        for qubit in remaining {
+            flush(qubit, &mut runs, &mut output);
+        }
+
+        output
+    }
+
+    fn fuse_two_qubit(circuit: &QuantumCircuit) -> QuantumCircuit {
+        let mut output = QuantumCircuit::new(circuit.num_qubits());
+        let mut blocks: Vec<Option<PairBlock>> = Vec::new();
+        let mut owner: HashMap<usize, usize> = HashMap::new();
+
+        let flush = |index: usize,
+                     blocks: &mut Vec<Option<PairBlock>>,
+                     owner: &mut HashMap<usize, usize>,
+                     output: &mut QuantumCircuit| {
+            if let Some(block) = blocks[index].take() {
+                owner.remove(&block.high);
+                owner.remove(&block.low);
+                if block.operations.len() == 1 {
+                    output.push_operation(block.operations[0].clone());
+                } else {
+                    output.add_gate(QuantumGate::Unitary2(block.high, block.matrix), block.low);
+                }
+            }
+        };
+
+        for operation in circuit.operations() {
+            if let CircuitOperation::Gate(instruction) = operation {
+                let qubits = instruction.gate.qubits(instruction.target);
+                let pair_owner = match qubits.as_slice() {
+                    [q] => owner.get(q).copied(),
+                    [a, b] => owner.get(a).filter(|i| owner.get(b) == Some(*i)).copied(),
+                    _ => None,
+                };
+
+                if let Some(index) = pair_owner {
+                    let block = blocks[index].as_mut().unwrap();
+                    if let Some(matrix) =
+                        instruction
+                            .gate
+                            .two_qubit_matrix(instruction.target, block.high, block.low)
+                    {
+                        block.matrix = unitary::mul4(&matrix, &block.matrix);
+                        block.operations.push(operation.clone());
+                        continue;
+                    }
+                }
+
+                if let [high, low] = qubits.as_slice() {
+                    if let Some(matrix) =
+                        instruction
+                            .gate
+                            .two_qubit_matrix(instruction.target, *high, *low)
+                    {
+                        for qubit in [high, low] {
+                            if let Some(&index) = owner.get(qubit) {
+                                flush(index, &mut blocks, &mut owner, &mut output);
+                            }
+                        }
+                        owner.insert(*high, blocks.len());
+                        owner.insert(*low, blocks.len());
+                        blocks.push(Some(PairBlock {
+                            high: *high,
+                            low: *low,
+                            matrix,
+                            operations: vec![operation.clone()],
+                        }));
+                        continue;
+                    }
+                }
+            }
+
+            for qubit in Self::touched_qubits(operation) {
+                if let Some(&index) = owner.get(&qubit) {
+                    flush(index, &mut blocks, &mut owner, &mut output);
+                }
+            }
+            output.push_operation(operation.clone());
+        }
+
+        for index in 0..blocks.len() {
+            flush(index, &mut blocks, &mut owner, &mut output);
+        }
+
+        output
+    }
+}
+
+impl QuantumCircuit {
+    pub fn fused(&self) -> QuantumCircuit {
+        GateFusion::new().run(self)
+    }
+}
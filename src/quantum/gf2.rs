@@ -0,0 +1,147 @@
+// Dense linear algebra over GF(2) on rows of bools, for parity-check matrices
+// and symplectic stabilizer bookkeeping
+
+pub(crate) fn dot(a: &[bool], b: &[bool]) -> bool {
+    a.iter().zip(b).filter(|(&x, &y)| x & y).count() % 2 == 1
+}
+
+pub(crate) fn add_assign(target: &mut [bool], source: &[bool]) {
+    for (t, &s) in target.iter_mut().zip(source) {
+        *t ^= s;
+    }
+}
+
+// Symplectic form on (x | z) vectors: 1 exactly when the Paulis anticommute
+pub(crate) fn symplectic_product(a: &[bool], b: &[bool]) -> bool {
+    let n = a.len() / 2;
+    dot(&a[..n], &b[n..]) ^ dot(&a[n..], &b[..n])
+}
+
+pub(crate) fn weight(row: &[bool]) -> usize {
+    row.iter().filter(|&&b| b).count()
+}
+
+// Reduced row echelon form; returns the non-zero rows and their pivot columns
+pub(crate) fn row_reduce(rows: &[Vec<bool>]) -> (Vec<Vec<bool>>, Vec<usize>) {
+    let mut rows = rows.to_vec();
+    let num_columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut pivots = Vec::new();
+    let mut rank = 0;
+    for column in 0..num_columns {
+        let Some(pivot) = (rank..rows.len()).find(|&i| rows[i][column]) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != rank && row[column] {
+                add_assign(row, &pivot_row);
+            }
+        }
+        pivots.push(column);
+        rank += 1;
+    }
+    rows.truncate(rank);
+    (rows, pivots)
+}
+
+pub(crate) fn rank(rows: &[Vec<bool>]) -> usize {
+    row_reduce(rows).1.len()
+}
+
+// Basis of { v : rows . v = 0 }, vectors of length `num_columns`
+pub(crate) fn kernel(rows: &[Vec<bool>], num_columns: usize) -> Vec<Vec<bool>> {
+    let (reduced, pivots) = row_reduce(rows);
+    (0..num_columns)
+        .filter(|column| !pivots.contains(column))
+        .map(|free| {
+            let mut v = vec![false; num_columns];
+            v[free] = true;
+            for (row, &pivot) in reduced.iter().zip(&pivots) {
+                v[pivot] = row[free];
+            }
+            v
+        })
+        .collect()
+}
+
+pub(crate) fn in_row_space(rows: &[Vec<bool>], v: &[bool]) -> bool {
+    let mut extended = rows.to_vec();
+    extended.push(v.to_vec());
+    rank(&extended) == rank(rows)
+}
+
+// Vectors of `candidates` that extend `base` to a larger span, greedily, i.e.
+// a basis of span(base + candidates) / span(base)
+pub(crate) fn complement_basis(base: &[Vec<bool>], candidates: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let mut span = base.to_vec();
+    let mut current = rank(&span);
+    let mut chosen = Vec::new();
+    for candidate in candidates {
+        span.push(candidate.clone());
+        let extended = rank(&span);
+        if extended > current {
+            current = extended;
+            chosen.push(candidate.clone());
+        } else {
+            span.pop();
+        }
+    }
+    chosen
+}
+
+pub(crate) fn identity(size: usize) -> Vec<Vec<bool>> {
+    (0..size)
+        .map(|i| (0..size).map(|j| i == j).collect())
+        .collect()
+}
+
+pub(crate) fn transpose(rows: &[Vec<bool>], num_columns: usize) -> Vec<Vec<bool>> {
+    (0..num_columns)
+        .map(|j| rows.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+pub(crate) fn kron(a: &[Vec<bool>], b: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    a.iter()
+        .flat_map(|a_row| {
+            b.iter().map(move |b_row| {
+                a_row
+                    .iter()
+                    .flat_map(|&x| b_row.iter().map(move |&y| x & y))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+// [a | b], row by row
+pub(crate) fn hstack(a: &[Vec<bool>], b: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.iter().chain(y).copied().collect())
+        .collect()
+}
+
+// Some solution of rows . v = rhs, free variables set to zero
+pub(crate) fn solve(rows: &[Vec<bool>], rhs: &[bool], num_columns: usize) -> Option<Vec<bool>> {
+    let augmented: Vec<Vec<bool>> = rows
+        .iter()
+        .zip(rhs)
+        .map(|(row, &b)| {
+            let mut extended = row.clone();
+            extended.resize(num_columns, false);
+            extended.push(b);
+            extended
+        })
+        .collect();
+    let (reduced, pivots) = row_reduce(&augmented);
+    if pivots.contains(&num_columns) {
+        return None;
+    }
+    let mut solution = vec![false; num_columns];
+    for (row, &pivot) in reduced.iter().zip(&pivots) {
+        solution[pivot] = row[num_columns];
+    }
+    Some(solution)
+}
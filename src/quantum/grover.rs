@@ -0,0 +1,250 @@
+use super::*;
+use num_complex::Complex64;
+use rand::Rng;
+use std::cell::Cell;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+// BBHT growth factor for the iteration-count window; any value in (1, 4/3) works
+const BBHT_GROWTH: f64 = 1.2;
+
+fn validate_register(state: &QuantumState, register: &Range<usize>) -> Result<(), QuantumError> {
+    if register.is_empty() || register.end > state.num_qubits {
+        return Err(QuantumError::InvalidQubitIndex);
+    }
+    Ok(())
+}
+
+// Phase oracle |x> -> (-1)^f(x) |x> on a register that counts its invocations,
+// so search adversaries can be charged per superposition query
+pub struct GroverOracle<F> {
+    register: Range<usize>,
+    predicate: F,
+    queries: Cell<usize>,
+}
+
+impl<F: Fn(usize) -> bool> GroverOracle<F> {
+    pub fn new(register: Range<usize>, predicate: F) -> Self {
+        Self {
+            register,
+            predicate,
+            queries: Cell::new(0),
+        }
+    }
+
+    pub fn register(&self) -> &Range<usize> {
+        &self.register
+    }
+
+    pub fn queries(&self) -> usize {
+        self.queries.get()
+    }
+
+    // Classical evaluation, e.g. to check a measured candidate; not charged
+    pub fn is_marked(&self, x: usize) -> bool {
+        (self.predicate)(x)
+    }
+
+    // Exhaustive count of marked inputs, for analysis only; not charged
+    pub fn marked_count(&self) -> usize {
+        (0..1usize << self.register.len())
+            .filter(|&x| (self.predicate)(x))
+            .count()
+    }
+
+    pub fn apply(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        apply_phase_oracle(state, &self.register, &self.predicate)?;
+        self.queries.set(self.queries.get() + 1);
+        Ok(())
+    }
+}
+
+// 2|s><s| - I on the register, |s> being its uniform superposition. Applied
+// independently to every assignment of the qubits outside the register.
+pub fn apply_diffusion(
+    state: &mut QuantumState,
+    register: &Range<usize>,
+) -> Result<(), QuantumError> {
+    validate_register(state, register)?;
+
+    let mask = xor_register(0, register, usize::MAX);
+    let size = 1usize << register.len();
+    for base in 0..state.amplitudes.len() {
+        if base & mask != 0 {
+            continue;
+        }
+        let mean = (0..size)
+            .map(|local| state.amplitudes[xor_register(base, register, local)])
+            .sum::<Complex64>()
+            / size as f64;
+        for local in 0..size {
+            let index = xor_register(base, register, local);
+            state.amplitudes[index] = 2.0 * mean - state.amplitudes[index];
+        }
+    }
+    let qubits: Vec<usize> = register.clone().collect();
+    state.merge_entanglement(&qubits);
+
+    Ok(())
+}
+
+// 2|0><0| - I on the register
+pub fn apply_zero_reflection(
+    state: &mut QuantumState,
+    register: &Range<usize>,
+) -> Result<(), QuantumError> {
+    validate_register(state, register)?;
+
+    for (basis, amplitude) in state.amplitudes.iter_mut().enumerate() {
+        if read_register(basis, register) != 0 {
+            *amplitude = -*amplitude;
+        }
+    }
+    let qubits: Vec<usize> = register.clone().collect();
+    state.merge_entanglement(&qubits);
+
+    Ok(())
+}
+
+// Amplitude amplification for a preparation A with A|0> = |psi>: each
+// iteration applies (2|psi><psi| - I) S_f = A S_0 A^dagger S_f.
+pub struct AmplitudeAmplifier {
+    register: Range<usize>,
+    preparation: QuantumCircuit,
+    unpreparation: QuantumCircuit,
+}
+
+impl AmplitudeAmplifier {
+    pub fn new(register: Range<usize>, preparation: QuantumCircuit) -> Result<Self, QuantumError> {
+        let unpreparation = preparation.inverse()?;
+        Ok(Self {
+            register,
+            preparation,
+            unpreparation,
+        })
+    }
+
+    // Plain Grover search: A = H on every register qubit
+    pub fn grover(register: Range<usize>) -> Self {
+        let mut preparation = QuantumCircuit::new(register.end);
+        for qubit in register.clone() {
+            preparation.add_gate(QuantumGate::Hadamard, qubit);
+        }
+        Self {
+            register,
+            unpreparation: preparation.clone(),
+            preparation,
+        }
+    }
+
+    pub fn prepare(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        self.preparation.execute(state)
+    }
+
+    pub fn iterate<F: Fn(usize) -> bool>(
+        &self,
+        state: &mut QuantumState,
+        oracle: &GroverOracle<F>,
+    ) -> Result<(), QuantumError> {
+        oracle.apply(state)?;
+        self.unpreparation.execute(state)?;
+        apply_zero_reflection(state, &self.register)?;
+        self.preparation.execute(state)
+    }
+
+    // Prepares |psi> on a fresh register and applies `iterations` rounds
+    pub fn run<F: Fn(usize) -> bool>(
+        &self,
+        state: &mut QuantumState,
+        oracle: &GroverOracle<F>,
+        iterations: usize,
+    ) -> Result<(), QuantumError> {
+        self.prepare(state)?;
+        for _ in 0..iterations {
+            self.iterate(state, oracle)?;
+        }
+        Ok(())
+    }
+}
+
+fn rotation_angle(search_space: usize, marked: usize) -> f64 {
+    (marked as f64 / search_space as f64).sqrt().asin()
+}
+
+// Iteration count maximising sin^2((2k + 1) theta), sin^2 theta = M / N
+pub fn optimal_iterations(search_space: usize, marked: usize) -> usize {
+    if marked == 0 || marked >= search_space {
+        return 0;
+    }
+    let theta = rotation_angle(search_space, marked);
+    (PI / (4.0 * theta) - 0.5).round().max(0.0) as usize
+}
+
+pub fn amplification_success_probability(
+    search_space: usize,
+    marked: usize,
+    iterations: usize,
+) -> f64 {
+    if search_space == 0 || marked == 0 {
+        return 0.0;
+    }
+    let theta = rotation_angle(search_space, marked.min(search_space));
+    ((2 * iterations + 1) as f64 * theta).sin().powi(2)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOutcome {
+    pub result: Option<usize>,
+    pub queries: usize,
+    pub rounds: usize,
+}
+
+// Boyer-Brassard-Hoyer-Tapp search, for when the number of marked inputs is
+// unknown. Gives up once `query_budget` superposition queries have been spent.
+pub fn grover_search<F: Fn(usize) -> bool, R: Rng + ?Sized>(
+    num_qubits: usize,
+    predicate: F,
+    query_budget: usize,
+    rng: &mut R,
+) -> Result<SearchOutcome, QuantumError> {
+    if num_qubits == 0 {
+        return Err(QuantumError::InvalidQubitIndex);
+    }
+    let register = 0..num_qubits;
+    let oracle = GroverOracle::new(register.clone(), predicate);
+    let amplifier = AmplitudeAmplifier::grover(register);
+    let max_window = ((1usize << num_qubits) as f64).sqrt();
+
+    let mut window = 1.0f64;
+    let mut rounds = 0;
+    while oracle.queries() < query_budget {
+        rounds += 1;
+        let iterations = rng
+            .gen_range(0..window.ceil() as usize)
+            .min(query_budget - oracle.queries());
+
+        let mut state = QuantumState::new(num_qubits)?;
+        amplifier.run(&mut state, &oracle, iterations)?;
+        let mut candidate = 0;
+        for qubit in 0..num_qubits {
+            if state.measure_qubit(qubit, rng)? {
+                candidate |= 1 << qubit;
+            }
+        }
+
+        if oracle.is_marked(candidate) {
+            return Ok(SearchOutcome {
+                result: Some(candidate),
+                queries: oracle.queries(),
+                rounds,
+            });
+        }
+        window = (window * BBHT_GROWTH).min(max_window);
+    }
+
+    Ok(SearchOutcome {
+        result: None,
+        queries: oracle.queries(),
+        rounds,
+    })
+}
@@ -0,0 +1,111 @@
+use super::*;
+use num_complex::Complex64;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use std::f64::consts::PI;
+
+fn complex_gaussian<R: Rng + ?Sized>(rng: &mut R) -> Complex64 {
+    Complex64::new(rng.sample(StandardNormal), rng.sample(StandardNormal))
+}
+
+// Normalised vector of i.i.d. complex Gaussians is Haar-distributed on the unit sphere
+pub fn haar_random_state<R: Rng + ?Sized>(
+    num_qubits: usize,
+    rng: &mut R,
+) -> Result<QuantumState, QuantumError> {
+    let mut state = QuantumState::new(num_qubits)?;
+    for amplitude in &mut state.amplitudes {
+        *amplitude = complex_gaussian(rng);
+    }
+
+    let norm = state
+        .amplitudes
+        .iter()
+        .map(|a| a.norm_sqr())
+        .sum::<f64>()
+        .sqrt();
+    for amplitude in &mut state.amplitudes {
+        *amplitude /= norm;
+    }
+    let qubits: Vec<usize> = (0..num_qubits).collect();
+    state.merge_entanglement(&qubits);
+
+    Ok(state)
+}
+
+// Gram-Schmidt on the columns of a complex Ginibre matrix. Gram-Schmidt fixes
+// the diagonal of R to be real and positive, which is exactly the phase
+// correction needed for the resulting Q to be Haar-distributed.
+pub fn haar_random_unitary<R: Rng + ?Sized>(num_qubits: usize, rng: &mut R) -> Unitary {
+    let n = 1 << num_qubits;
+    let mut columns: Vec<Vec<Complex64>> = (0..n)
+        .map(|_| (0..n).map(|_| complex_gaussian(rng)).collect())
+        .collect();
+
+    for j in 0..n {
+        for k in 0..j {
+            let projection: Complex64 = (0..n).map(|i| columns[k][i].conj() * columns[j][i]).sum();
+            for i in 0..n {
+                let value = columns[k][i] * projection;
+                columns[j][i] -= value;
+            }
+        }
+        let norm = columns[j].iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+        columns[j].iter_mut().for_each(|a| *a /= norm);
+    }
+
+    let mut elements = vec![Complex64::new(0.0, 0.0); n * n];
+    for (j, column) in columns.iter().enumerate() {
+        for (i, value) in column.iter().enumerate() {
+            elements[i * n + j] = *value;
+        }
+    }
+
+    Unitary {
+        num_qubits,
+        dimension: n,
+        elements,
+    }
+}
+
+// Layers of random single-qubit gates from {H, S, T} followed by CNOTs on a
+// random pairing. Local random circuits of depth O(n t) form approximate
+// unitary t-designs, which is what the PRS baselines need when a full Haar
+// unitary is too expensive.
+pub fn random_clifford_t_circuit<R: Rng + ?Sized>(
+    num_qubits: usize,
+    depth: usize,
+    rng: &mut R,
+) -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(num_qubits);
+    let mut order: Vec<usize> = (0..num_qubits).collect();
+
+    for _ in 0..depth {
+        for qubit in 0..num_qubits {
+            let gate = match rng.gen_range(0..3) {
+                0 => QuantumGate::Hadamard,
+                1 => QuantumGate::Phase(PI / 2.0),
+                _ => QuantumGate::Phase(PI / 4.0),
+            };
+            circuit.add_gate(gate, qubit);
+        }
+
+        order.shuffle(rng);
+        for pair in order.chunks_exact(2) {
+            circuit.add_gate(QuantumGate::CNOT(pair[0]), pair[1]);
+        }
+    }
+
+    circuit
+}
+
+pub fn approximate_design_state<R: Rng + ?Sized>(
+    num_qubits: usize,
+    depth: usize,
+    rng: &mut R,
+) -> Result<QuantumState, QuantumError> {
+    let mut state = QuantumState::new(num_qubits)?;
+    random_clifford_t_circuit(num_qubits, depth, rng).execute(&mut state)?;
+    Ok(state)
+}
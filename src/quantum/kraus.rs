@@ -0,0 +1,568 @@
+use super::*;
+use num_complex::{Complex, Complex64};
+use rand::Rng;
+
+// Tolerance on sum K^dagger K = I, and on recognising Kraus operators that are
+// multiples of unitaries
+const KRAUS_TOLERANCE: f64 = 1e-9;
+
+// Completely positive, trace-preserving map on `num_qubits` qubits, given by
+// Kraus operators: dense 2^k x 2^k matrices, row-major, with qubit i of the
+// channel on bit i of the local index. Density matrices get the exact map
+// sum K rho K^dagger; statevectors get one Kraus operator, sampled with
+// probability ||K psi||^2, and are renormalised.
+#[derive(Clone, Debug)]
+pub struct QuantumChannelOp {
+    num_qubits: usize,
+    kraus: Vec<Vec<Complex64>>,
+    // ||K psi||^2 for every psi, when each K^dagger K is a multiple of the
+    // identity; sampling then needs no pass over the state
+    fixed_weights: Option<Vec<f64>>,
+}
+
+fn zero() -> Complex64 {
+    Complex64::new(0.0, 0.0)
+}
+
+fn identity_matrix(dimension: usize) -> Vec<Complex64> {
+    let mut matrix = vec![zero(); dimension * dimension];
+    for i in 0..dimension {
+        matrix[i * dimension + i] = Complex64::new(1.0, 0.0);
+    }
+    matrix
+}
+
+// P as a matrix on `num_qubits` qubits, including its phase
+fn pauli_matrix(pauli: &PauliString, num_qubits: usize) -> Vec<Complex64> {
+    let d = 1 << num_qubits;
+    let i = Complex64::new(0.0, 1.0);
+    let mut matrix = vec![zero(); d * d];
+    for column in 0..d {
+        let mut row = column;
+        let mut amplitude = i.powi(pauli.phase() as i32);
+        for (qubit, operator) in pauli.iter() {
+            let bit = (column >> qubit) & 1 == 1;
+            match operator {
+                PauliOperator::X => row ^= 1 << qubit,
+                PauliOperator::Z if bit => amplitude = -amplitude,
+                PauliOperator::Z => {}
+                PauliOperator::Y => {
+                    row ^= 1 << qubit;
+                    amplitude *= if bit { -i } else { i };
+                }
+            }
+        }
+        matrix[row * d + column] = amplitude;
+    }
+    matrix
+}
+
+// K^dagger K
+fn gram(kraus: &[Complex64], d: usize) -> Vec<Complex64> {
+    let mut product = vec![zero(); d * d];
+    for i in 0..d {
+        for j in 0..d {
+            product[i * d + j] = (0..d)
+                .map(|r| kraus[r * d + i].conj() * kraus[r * d + j])
+                .sum();
+        }
+    }
+    product
+}
+
+// c when `matrix` is c I
+fn identity_multiple(matrix: &[Complex64], d: usize) -> Option<Complex64> {
+    let c = matrix[0];
+    let is_multiple = (0..d).all(|i| {
+        (0..d).all(|j| {
+            let expected = if i == j { c } else { zero() };
+            (matrix[i * d + j] - expected).norm() <= KRAUS_TOLERANCE
+        })
+    });
+    is_multiple.then_some(c)
+}
+
+impl QuantumChannelOp {
+    pub fn new(num_qubits: usize, kraus: Vec<Vec<Complex64>>) -> Result<Self, QuantumError> {
+        let d = 1 << num_qubits;
+        if num_qubits == 0 || kraus.is_empty() || kraus.iter().any(|k| k.len() != d * d) {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let grams: Vec<Vec<Complex64>> = kraus.iter().map(|k| gram(k, d)).collect();
+        let mut total = vec![zero(); d * d];
+        for g in &grams {
+            for (t, e) in total.iter_mut().zip(g) {
+                *t += e;
+            }
+        }
+        if identity_multiple(&total, d).map_or(true, |c| (c - 1.0).norm() > KRAUS_TOLERANCE) {
+            return Err(QuantumError::NotTracePreserving);
+        }
+
+        let fixed_weights = grams
+            .iter()
+            .map(|g| identity_multiple(g, d).map(|c| c.re))
+            .collect();
+        Ok(Self {
+            num_qubits,
+            kraus,
+            fixed_weights,
+        })
+    }
+
+    pub fn identity(num_qubits: usize) -> Self {
+        Self {
+            num_qubits,
+            kraus: vec![identity_matrix(1 << num_qubits)],
+            fixed_weights: Some(vec![1.0]),
+        }
+    }
+
+    pub fn from_unitary(unitary: &Unitary) -> Result<Self, QuantumError> {
+        Self::new(unitary.num_qubits(), vec![unitary.elements.clone()])
+    }
+
+    // rho -> (1 - sum p) rho + sum p P rho P for Hermitian Paulis P on the
+    // channel's qubits
+    pub fn pauli_channel(
+        num_qubits: usize,
+        errors: &[(f64, PauliString)],
+    ) -> Result<Self, QuantumError> {
+        let total: f64 = errors.iter().map(|(p, _)| p).sum();
+        if errors.iter().any(|(p, _)| !(0.0..=1.0).contains(p)) || total > 1.0 + KRAUS_TOLERANCE {
+            return Err(QuantumError::InvalidProbability);
+        }
+        let d = 1 << num_qubits;
+        let mut kraus = vec![identity_matrix(d)
+            .into_iter()
+            .map(|e| e * (1.0 - total).max(0.0).sqrt())
+            .collect()];
+        for (probability, pauli) in errors {
+            if pauli.support().last().map_or(false, |&q| q >= num_qubits) {
+                return Err(QuantumError::InvalidQubitIndex);
+            }
+            if !pauli.is_hermitian() {
+                return Err(QuantumError::InvalidPauliString);
+            }
+            kraus.push(
+                pauli_matrix(pauli, num_qubits)
+                    .into_iter()
+                    .map(|e| e * probability.sqrt())
+                    .collect(),
+            );
+        }
+        Self::new(num_qubits, kraus)
+    }
+
+    pub fn bit_flip(probability: f64) -> Result<Self, QuantumError> {
+        Self::pauli_channel(
+            1,
+            &[(probability, PauliString::single(0, PauliOperator::X))],
+        )
+    }
+
+    pub fn phase_flip(probability: f64) -> Result<Self, QuantumError> {
+        Self::pauli_channel(
+            1,
+            &[(probability, PauliString::single(0, PauliOperator::Z))],
+        )
+    }
+
+    // An X, Y or Z error with probability p / 3 each
+    pub fn depolarizing(probability: f64) -> Result<Self, QuantumError> {
+        let errors: Vec<(f64, PauliString)> =
+            [PauliOperator::X, PauliOperator::Y, PauliOperator::Z]
+                .into_iter()
+                .map(|p| (probability / 3.0, PauliString::single(0, p)))
+                .collect();
+        Self::pauli_channel(1, &errors)
+    }
+
+    // Each of the 15 non-identity two-qubit Paulis with probability p / 15
+    pub fn two_qubit_depolarizing(probability: f64) -> Result<Self, QuantumError> {
+        let operators = [
+            None,
+            Some(PauliOperator::X),
+            Some(PauliOperator::Y),
+            Some(PauliOperator::Z),
+        ];
+        let mut errors = Vec::new();
+        for a in operators {
+            for b in operators {
+                let mut pauli = PauliString::identity(2);
+                pauli.set(0, a);
+                pauli.set(1, b);
+                if !pauli.is_identity() {
+                    errors.push((probability / 15.0, pauli));
+                }
+            }
+        }
+        Self::pauli_channel(2, &errors)
+    }
+
+    // XX or ZZ on a pair of qubits, each with probability p / 2
+    pub fn correlated_flip(probability: f64) -> Result<Self, QuantumError> {
+        let pair = |p| PauliString::from_sparse(&[(0, p), (1, p)]);
+        Self::pauli_channel(
+            2,
+            &[
+                (probability / 2.0, pair(PauliOperator::X)),
+                (probability / 2.0, pair(PauliOperator::Z)),
+            ],
+        )
+    }
+
+    // Relaxation |1> -> |0> with probability gamma
+    pub fn amplitude_damping(gamma: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&gamma) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        let c = |x: f64| Complex64::new(x, 0.0);
+        Self::new(
+            1,
+            vec![
+                vec![c(1.0), zero(), zero(), c((1.0 - gamma).sqrt())],
+                vec![zero(), c(gamma.sqrt()), zero(), zero()],
+            ],
+        )
+    }
+
+    // Amplitude damping over a gate of `gate_time`, gamma = 1 - exp(-t / T1),
+    // with both times in the same units
+    pub fn t1_relaxation(t1: f64, gate_time: f64) -> Result<Self, QuantumError> {
+        if t1 <= 0.0 || gate_time < 0.0 {
+            return Err(QuantumError::InvalidRelaxationTime);
+        }
+        Self::amplitude_damping(1.0 - (-gate_time / t1).exp())
+    }
+
+    // Loss of coherence without energy exchange: |1><1| survives, while the
+    // off-diagonal terms shrink by sqrt(1 - lambda)
+    pub fn phase_damping(lambda: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&lambda) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        let c = |x: f64| Complex64::new(x, 0.0);
+        Self::new(
+            1,
+            vec![
+                vec![c(1.0), zero(), zero(), c((1.0 - lambda).sqrt())],
+                vec![zero(), zero(), zero(), c(lambda.sqrt())],
+            ],
+        )
+    }
+
+    // Phase damping over a gate of `gate_time`, with coherences decaying as
+    // exp(-t / T2)
+    pub fn t2_dephasing(t2: f64, gate_time: f64) -> Result<Self, QuantumError> {
+        if t2 <= 0.0 || gate_time < 0.0 {
+            return Err(QuantumError::InvalidRelaxationTime);
+        }
+        Self::phase_damping(1.0 - (-2.0 * gate_time / t2).exp())
+    }
+
+    // Relaxation towards the thermal state at `temperature`, given in units
+    // of the qubit splitting (k_B T / hbar omega), followed by the pure
+    // dephasing that brings the coherence decay up to exp(-t / T2). Needs
+    // T2 <= 2 T1; an infinite T1 leaves only dephasing.
+    pub fn thermal_relaxation(
+        t1: f64,
+        t2: f64,
+        gate_time: f64,
+        temperature: f64,
+    ) -> Result<Self, QuantumError> {
+        if t1 <= 0.0 || t2 <= 0.0 || t2 > 2.0 * t1 || gate_time < 0.0 || temperature < 0.0 {
+            return Err(QuantumError::InvalidRelaxationTime);
+        }
+        let gamma = 1.0 - (-gate_time / t1).exp();
+        let excited = if temperature > 0.0 {
+            1.0 / (1.0 + (1.0 / temperature).exp())
+        } else {
+            0.0
+        };
+        let c = |x: f64| Complex64::new(x, 0.0);
+        let (ground, up) = ((1.0 - excited).sqrt(), excited.sqrt());
+        let damping = Self::new(
+            1,
+            vec![
+                vec![c(ground), zero(), zero(), c(ground * (1.0 - gamma).sqrt())],
+                vec![zero(), c(ground * gamma.sqrt()), zero(), zero()],
+                vec![c(up * (1.0 - gamma).sqrt()), zero(), zero(), c(up)],
+                vec![zero(), zero(), c(up * gamma.sqrt()), zero()],
+            ],
+        )?;
+
+        // 1 / T_phi = 1 / T2 - 1 / (2 T1)
+        let dephasing_rate = (1.0 / t2 - 0.5 / t1).max(0.0);
+        let lambda = 1.0 - (-2.0 * gate_time * dephasing_rate).exp();
+        damping.then(&Self::phase_damping(lambda)?)
+    }
+
+    // One step of length dt of the single-qubit Lindblad dissipator with jump
+    // operators L_j: K_j = sqrt(dt) L_j and K_0 = sqrt(I - dt sum L_j^dagger L_j),
+    // which is trace preserving exactly (the coherent part belongs in the
+    // circuit). Fails when dt is too long for K_0 to exist.
+    pub fn lindblad(jumps: &[Matrix2], dt: f64) -> Result<Self, QuantumError> {
+        if dt < 0.0 {
+            return Err(QuantumError::InvalidTimeStep);
+        }
+        let mut remainder = unitary::identity2();
+        for jump in jumps {
+            let decay = unitary::mul2(&unitary::adjoint2(jump), jump);
+            for i in 0..2 {
+                for j in 0..2 {
+                    remainder[i][j] -= decay[i][j] * dt;
+                }
+            }
+        }
+        // sqrt(M) = (M + sqrt(det M) I) / sqrt(tr M + 2 sqrt(det M)) for a
+        // positive semidefinite 2 x 2 matrix
+        let (det, trace) = (unitary::det2(&remainder).re, unitary::trace2(&remainder).re);
+        if det < -KRAUS_TOLERANCE || trace < -KRAUS_TOLERANCE {
+            return Err(QuantumError::InvalidTimeStep);
+        }
+        let s = det.max(0.0).sqrt();
+        let t = (trace + 2.0 * s).max(0.0).sqrt();
+        let mut root = vec![zero(); 4];
+        if t > 0.0 {
+            for i in 0..2 {
+                for j in 0..2 {
+                    let shift = if i == j { s } else { 0.0 };
+                    root[2 * i + j] = (remainder[i][j] + shift) / t;
+                }
+            }
+        }
+
+        let mut kraus = vec![root];
+        for jump in jumps {
+            kraus.push(jump.iter().flatten().map(|e| e * dt.sqrt()).collect());
+        }
+        Self::new(1, kraus)
+    }
+
+    // This channel followed by `next`, with Kraus operators B_j A_i
+    pub fn then(&self, next: &QuantumChannelOp) -> Result<Self, QuantumError> {
+        if next.num_qubits != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let d = 1 << self.num_qubits;
+        let mut kraus = Vec::with_capacity(self.kraus.len() * next.kraus.len());
+        for b in &next.kraus {
+            for a in &self.kraus {
+                let mut product = vec![zero(); d * d];
+                for i in 0..d {
+                    for j in 0..d {
+                        product[i * d + j] = (0..d).map(|k| b[i * d + k] * a[k * d + j]).sum();
+                    }
+                }
+                if product.iter().any(|e| e.norm() > KRAUS_TOLERANCE) {
+                    kraus.push(product);
+                }
+            }
+        }
+        Self::new(self.num_qubits, kraus)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn kraus_operators(&self) -> &[Vec<Complex64>] {
+        &self.kraus
+    }
+
+    // The Pauli, on the channel's own qubits, that Kraus operator `index` is a
+    // nonzero multiple of; None for operators that are not, such as a
+    // damping jump
+    pub fn kraus_pauli(&self, index: usize) -> Option<PauliString> {
+        let kraus = self.kraus.get(index)?;
+        let d = 1 << self.num_qubits;
+        (0..1usize << (2 * self.num_qubits)).find_map(|code| {
+            let operators: Vec<(usize, PauliOperator)> = (0..self.num_qubits)
+                .filter_map(|q| match (code >> (2 * q)) & 3 {
+                    1 => Some((q, PauliOperator::X)),
+                    2 => Some((q, PauliOperator::Y)),
+                    3 => Some((q, PauliOperator::Z)),
+                    _ => None,
+                })
+                .collect();
+            let pauli = PauliString::from_sparse(&operators);
+            let matrix = pauli_matrix(&pauli, self.num_qubits);
+            // P is Hermitian and squares to I, so c = tr(P K) / d
+            let c: Complex64 = (0..d * d)
+                .map(|e| matrix[(e % d) * d + e / d] * kraus[e])
+                .sum::<Complex64>()
+                / d as f64;
+            let is_multiple = c.norm() > KRAUS_TOLERANCE
+                && matrix
+                    .iter()
+                    .zip(kraus)
+                    .all(|(p, k)| (k - c * p).norm() <= KRAUS_TOLERANCE);
+            is_multiple.then_some(pauli)
+        })
+    }
+
+    // Offsets of the 2^k local basis states within a register, and the mask
+    // of the target bits
+    fn layout(
+        &self,
+        targets: &[usize],
+        register: usize,
+    ) -> Result<(Vec<usize>, usize), QuantumError> {
+        if targets.len() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let mut mask = 0;
+        for &q in targets {
+            if q >= register || mask & (1 << q) != 0 {
+                return Err(QuantumError::InvalidQubitIndex);
+            }
+            mask |= 1 << q;
+        }
+        let offsets = (0..1usize << self.num_qubits)
+            .map(|local| {
+                targets
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| (local >> bit) & 1 == 1)
+                    .map(|(_, &q)| 1 << q)
+                    .sum()
+            })
+            .collect();
+        Ok((offsets, mask))
+    }
+
+    // K v for a local vector v
+    fn multiply(&self, index: usize, v: &[Complex64]) -> Vec<Complex64> {
+        let d = v.len();
+        let k = &self.kraus[index];
+        (0..d)
+            .map(|r| (0..d).map(|c| k[r * d + c] * v[c]).sum())
+            .collect()
+    }
+
+    // rho -> sum K rho K^dagger on `targets`
+    pub fn apply_to_density(
+        &self,
+        rho: &mut DensityMatrix,
+        targets: &[usize],
+    ) -> Result<(), QuantumError> {
+        let (offsets, mask) = self.layout(targets, rho.num_qubits)?;
+        let d = offsets.len();
+        let n = rho.dimension;
+        let bases: Vec<usize> = (0..n).filter(|i| i & mask == 0).collect();
+
+        let mut elements = vec![zero(); n * n];
+        for &row in &bases {
+            for &column in &bases {
+                let block: Vec<Complex64> = offsets
+                    .iter()
+                    .flat_map(|a| offsets.iter().map(move |b| (a, b)))
+                    .map(|(a, b)| rho.get(row + a, column + b))
+                    .collect();
+                for k in &self.kraus {
+                    for (i, a) in offsets.iter().enumerate() {
+                        for (j, b) in offsets.iter().enumerate() {
+                            let mut sum = zero();
+                            for p in 0..d {
+                                for q in 0..d {
+                                    sum += k[i * d + p] * block[p * d + q] * k[j * d + q].conj();
+                                }
+                            }
+                            elements[(row + a) * n + column + b] += sum;
+                        }
+                    }
+                }
+            }
+        }
+        rho.elements = elements;
+        Ok(())
+    }
+
+    // One trajectory of the channel; returns the index of the Kraus operator
+    // that was applied
+    pub fn unravel<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        targets: &[usize],
+        rng: &mut R,
+    ) -> Result<usize, QuantumError> {
+        let chosen =
+            self.unravel_amplitudes(&mut state.amplitudes, state.num_qubits, targets, rng)?;
+        if targets.len() > 1 {
+            state.merge_entanglement(targets);
+        }
+        Ok(chosen)
+    }
+
+    pub fn unravel_vector<T: Precision, R: Rng + ?Sized>(
+        &self,
+        state: &mut StateVector<T>,
+        targets: &[usize],
+        rng: &mut R,
+    ) -> Result<usize, QuantumError> {
+        self.unravel_amplitudes(&mut state.amplitudes, state.num_qubits, targets, rng)
+    }
+
+    fn unravel_amplitudes<T: Precision, R: Rng + ?Sized>(
+        &self,
+        amplitudes: &mut [Complex<T>],
+        register: usize,
+        targets: &[usize],
+        rng: &mut R,
+    ) -> Result<usize, QuantumError> {
+        let (offsets, mask) = self.layout(targets, register)?;
+        let bases: Vec<usize> = (0..amplitudes.len()).filter(|i| i & mask == 0).collect();
+        let local = |amplitudes: &[Complex<T>], base: usize| -> Vec<Complex64> {
+            offsets
+                .iter()
+                .map(|o| widen(amplitudes[base + o]))
+                .collect()
+        };
+
+        let weights = match &self.fixed_weights {
+            Some(weights) => weights.clone(),
+            None => (0..self.kraus.len())
+                .map(|index| {
+                    bases
+                        .iter()
+                        .map(|&base| {
+                            self.multiply(index, &local(amplitudes, base))
+                                .iter()
+                                .map(|a| a.norm_sqr())
+                                .sum::<f64>()
+                        })
+                        .sum()
+                })
+                .collect(),
+        };
+        let total: f64 = weights.iter().sum();
+        let mut draw = rng.gen::<f64>() * total;
+        let chosen = weights
+            .iter()
+            .position(|&w| {
+                if draw < w {
+                    true
+                } else {
+                    draw -= w;
+                    false
+                }
+            })
+            .or_else(|| weights.iter().rposition(|&w| w > 0.0))
+            .ok_or(QuantumError::NotTracePreserving)?;
+
+        let d = offsets.len();
+        if identity_multiple(&self.kraus[chosen], d).is_some() {
+            return Ok(chosen);
+        }
+        let scale = 1.0 / weights[chosen].sqrt();
+        for &base in &bases {
+            let image = self.multiply(chosen, &local(amplitudes, base));
+            for (o, a) in offsets.iter().zip(image) {
+                amplitudes[base + o] = narrow(a * scale);
+            }
+        }
+        Ok(chosen)
+    }
+}
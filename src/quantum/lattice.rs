@@ -0,0 +1,233 @@
+use super::*;
+use std::f64::consts::FRAC_PI_2;
+
+// Geometric structure behind a topological code, kept alongside its stabilizers
+#[derive(Clone, Debug)]
+pub enum CodeLattice {
+    RotatedSurface(SurfaceCodeLayout),
+    Color(ColorCodeLayout),
+}
+
+// Rotated surface code on a d x d grid of data qubits, qubit r * d + c at row r,
+// column c. Plaquette (i, j), 0 <= i, j <= d, sits at the corner shared by data
+// qubits (i - 1, j - 1), (i - 1, j), (i, j - 1) and (i, j) and is X-type when
+// i + j is even. Weight-two X checks line the top and bottom boundaries and
+// weight-two Z checks the left and right ones, giving d^2 - 1 stabilizers.
+#[derive(Clone, Debug)]
+pub struct SurfaceCodeLayout {
+    pub distance: usize,
+    pub x_plaquettes: Vec<Vec<usize>>,
+    pub z_plaquettes: Vec<Vec<usize>>,
+}
+
+impl SurfaceCodeLayout {
+    pub fn new(distance: usize) -> Result<Self, QuantumError> {
+        if distance < 3 || distance % 2 == 0 {
+            return Err(QuantumError::InvalidCodeDistance);
+        }
+
+        let d = distance;
+        let mut x_plaquettes = Vec::new();
+        let mut z_plaquettes = Vec::new();
+        for i in 0..=d {
+            for j in 0..=d {
+                let x_type = (i + j) % 2 == 0;
+                let bulk = (1..d).contains(&i) && (1..d).contains(&j);
+                let top_or_bottom = (i == 0 || i == d) && (1..d).contains(&j);
+                let left_or_right = (j == 0 || j == d) && (1..d).contains(&i);
+
+                let qubits: Vec<usize> = [(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)]
+                    .iter()
+                    .filter(|&&(r, c)| (1..=d).contains(&r) && (1..=d).contains(&c))
+                    .map(|&(r, c)| (r - 1) * d + (c - 1))
+                    .collect();
+
+                if x_type && (bulk || top_or_bottom) {
+                    x_plaquettes.push(qubits);
+                } else if !x_type && (bulk || left_or_right) {
+                    z_plaquettes.push(qubits);
+                }
+            }
+        }
+
+        Ok(Self {
+            distance,
+            x_plaquettes,
+            z_plaquettes,
+        })
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.distance * self.distance
+    }
+
+    pub fn qubit(&self, row: usize, column: usize) -> usize {
+        row * self.distance + column
+    }
+
+    pub fn coordinates(&self, qubit: usize) -> (usize, usize) {
+        (qubit / self.distance, qubit % self.distance)
+    }
+
+    // Logical X runs down the left column, between the two X-check boundaries
+    pub fn logical_x(&self) -> Vec<usize> {
+        (0..self.distance).map(|row| self.qubit(row, 0)).collect()
+    }
+
+    // Logical Z runs along the top row, between the two Z-check boundaries
+    pub fn logical_z(&self) -> Vec<usize> {
+        (0..self.distance)
+            .map(|column| self.qubit(0, column))
+            .collect()
+    }
+
+    pub fn x_boundaries(&self) -> [Vec<usize>; 2] {
+        let last = self.distance - 1;
+        [
+            (0..self.distance).map(|c| self.qubit(0, c)).collect(),
+            (0..self.distance).map(|c| self.qubit(last, c)).collect(),
+        ]
+    }
+
+    pub fn z_boundaries(&self) -> [Vec<usize>; 2] {
+        let last = self.distance - 1;
+        [
+            (0..self.distance).map(|r| self.qubit(r, 0)).collect(),
+            (0..self.distance).map(|r| self.qubit(r, last)).collect(),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FaceColor {
+    Red,
+    Green,
+    Blue,
+}
+
+// Triangular 6.6.6 color code of odd distance d, n = (3d^2 + 1) / 4. Points
+// (r, c) with 0 <= c <= r <= 3(d - 1) / 2 form a triangular patch of the
+// triangular lattice; those with r + c = 1 (mod 3) are face centres and the
+// rest are data qubits, numbered row by row. Each face touches the qubits among
+// its six neighbours, so bulk faces are hexagons and boundary faces squares.
+#[derive(Clone, Debug)]
+pub struct ColorCodeLayout {
+    pub distance: usize,
+    pub sites: Vec<(usize, usize)>,
+    pub faces: Vec<Vec<usize>>,
+    pub face_colors: Vec<FaceColor>,
+}
+
+impl ColorCodeLayout {
+    pub fn new(distance: usize) -> Result<Self, QuantumError> {
+        if distance < 3 || distance % 2 == 0 {
+            return Err(QuantumError::InvalidCodeDistance);
+        }
+
+        let rows = 3 * (distance - 1) / 2 + 1;
+        let is_face = |r: usize, c: usize| (r + c) % 3 == 1;
+        let sites: Vec<(usize, usize)> = (0..rows)
+            .flat_map(|r| (0..=r).map(move |c| (r, c)))
+            .filter(|&(r, c)| !is_face(r, c))
+            .collect();
+        let index: HashMap<(usize, usize), usize> =
+            sites.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+
+        let mut faces = Vec::new();
+        let mut face_colors = Vec::new();
+        for r in 0..rows {
+            for c in (0..=r).filter(|&c| is_face(r, c)) {
+                let neighbours = [
+                    (r.wrapping_sub(1), c.wrapping_sub(1)),
+                    (r.wrapping_sub(1), c),
+                    (r, c + 1),
+                    (r + 1, c + 1),
+                    (r + 1, c),
+                    (r, c.wrapping_sub(1)),
+                ];
+                faces.push(
+                    neighbours
+                        .iter()
+                        .filter_map(|p| index.get(p).copied())
+                        .collect(),
+                );
+                // Neighbouring faces differ by (1, 2), (2, 1) or (1, -1), so the
+                // row alone 3-colours them
+                face_colors.push(match r % 3 {
+                    0 => FaceColor::Red,
+                    1 => FaceColor::Green,
+                    _ => FaceColor::Blue,
+                });
+            }
+        }
+
+        Ok(Self {
+            distance,
+            sites,
+            faces,
+            face_colors,
+        })
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.sites.len()
+    }
+
+    pub fn faces_of_color(&self, color: FaceColor) -> impl Iterator<Item = &Vec<usize>> + '_ {
+        self.faces
+            .iter()
+            .zip(&self.face_colors)
+            .filter(move |(_, &c)| c == color)
+            .map(|(face, _)| face)
+    }
+
+    // The code is self-dual, so logical X and Z share this support: the left
+    // edge of the triangle, d qubits long
+    pub fn logical_support(&self) -> Vec<usize> {
+        (0..self.num_qubits())
+            .filter(|&q| self.sites[q].1 == 0)
+            .collect()
+    }
+
+    // The honeycomb of data qubits is bipartite and every face has as many
+    // qubits on either side
+    pub fn sublattice(&self, qubit: usize) -> bool {
+        let (r, c) = self.sites[qubit];
+        (r + c) % 3 == 0
+    }
+
+    pub fn transversal_hadamard(&self) -> QuantumCircuit {
+        let mut circuit = QuantumCircuit::new(self.num_qubits());
+        for qubit in 0..self.num_qubits() {
+            circuit.add_gate(QuantumGate::Hadamard, qubit);
+        }
+        circuit
+    }
+
+    // S on one sublattice and S^dagger on the other. A bare S^n would map the
+    // weight-six X checks to -XZ, but the alternating pattern fixes every
+    // stabilizer and, with one more qubit of the first kind on the logical
+    // edge, implements logical S.
+    pub fn transversal_phase(&self) -> QuantumCircuit {
+        let mut circuit = QuantumCircuit::new(self.num_qubits());
+        for qubit in 0..self.num_qubits() {
+            let angle = if self.sublattice(qubit) {
+                FRAC_PI_2
+            } else {
+                -FRAC_PI_2
+            };
+            circuit.add_gate(QuantumGate::Phase(angle), qubit);
+        }
+        circuit
+    }
+
+    // Logical CNOT between two blocks: qubits 0..n control n..2n pairwise
+    pub fn transversal_cnot(&self) -> QuantumCircuit {
+        let n = self.num_qubits();
+        let mut circuit = QuantumCircuit::new(2 * n);
+        for qubit in 0..n {
+            circuit.add_gate(QuantumGate::CNOT(qubit), qubit + n);
+        }
+        circuit
+    }
+}
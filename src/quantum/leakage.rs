@@ -0,0 +1,150 @@
+use super::*;
+use num_complex::Complex64;
+use rand::Rng;
+
+// Per-layer probabilities of a qubit leaving the computational subspace for
+// a third level (|2> of a transmon) and of returning from it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LeakageRates {
+    pub leakage: f64,
+    pub seepage: f64,
+}
+
+impl LeakageRates {
+    pub fn new(leakage: f64, seepage: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&leakage) || !(0.0..=1.0).contains(&seepage) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        Ok(Self { leakage, seepage })
+    }
+}
+
+// Projective measurement without a recorded outcome: what remains of the
+// other qubits once a qubit's state is lost to the leaked level
+fn dephase_completely() -> QuantumChannelOp {
+    let (zero, one) = (Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0));
+    QuantumChannelOp::new(
+        1,
+        vec![vec![one, zero, zero, zero], vec![zero, zero, zero, one]],
+    )
+    .expect("projectors onto |0> and |1> sum to the identity")
+}
+
+fn depolarize_completely() -> QuantumChannelOp {
+    let errors: Vec<(f64, PauliString)> = [PauliOperator::X, PauliOperator::Y, PauliOperator::Z]
+        .into_iter()
+        .map(|p| (0.25, PauliString::single(0, p)))
+        .collect();
+    QuantumChannelOp::pauli_channel(1, &errors).expect("probabilities sum to 3/4")
+}
+
+// A leaked qubit keeps a placeholder basis state in the statevector, so
+// gates still act on it while it is out; what matters is that its quantum
+// information is gone and that it comes back maximally mixed. Leaked qubits
+// read out as 1.
+impl QuantumState {
+    pub fn leaked_qubits(&self) -> &[usize] {
+        &self.leaked
+    }
+
+    pub fn is_leaked(&self, qubit: usize) -> bool {
+        self.leaked.contains(&qubit)
+    }
+
+    pub fn leak<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> Result<(), QuantumError> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        if self.is_leaked(qubit) {
+            return Ok(());
+        }
+        dephase_completely().unravel(self, &[qubit], rng)?;
+        self.leaked.push(qubit);
+        self.leaked.sort_unstable();
+        Ok(())
+    }
+
+    pub fn seep<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> Result<(), QuantumError> {
+        if !self.is_leaked(qubit) {
+            return Ok(());
+        }
+        depolarize_completely().unravel(self, &[qubit], rng)?;
+        self.leaked.retain(|&q| q != qubit);
+        Ok(())
+    }
+
+    // Leakage reduction: returns every leaked qubit to the computational
+    // subspace, maximally mixed, so a decoder that treated them as erasures
+    // can correct them. Returns the qubits that had leaked.
+    pub fn reset_leaked<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<Vec<usize>, QuantumError> {
+        let leaked = self.leaked.clone();
+        for &qubit in &leaked {
+            self.seep(qubit, rng)?;
+        }
+        Ok(leaked)
+    }
+}
+
+impl NoiseModel {
+    // Leakage transitions of one noise layer: each qubit leaks or seeps back
+    // with its rates. Only QuantumState tracks leaked qubits, so the other
+    // representations ignore leakage.
+    pub fn apply_leakage<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.leakage_layer(state, None, rng)
+    }
+
+    pub(crate) fn leakage_layer<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        mut trace: Option<&mut NoiseTrace>,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        for qubit in 0..state.num_qubits {
+            let Some(rates) = self.leakage_rates(qubit) else {
+                continue;
+            };
+            let error = if state.is_leaked(qubit) {
+                if rng.gen::<f64>() >= rates.seepage {
+                    continue;
+                }
+                state.seep(qubit, rng)?;
+                InjectedError::Seepage
+            } else {
+                if rng.gen::<f64>() >= rates.leakage {
+                    continue;
+                }
+                state.leak(qubit, rng)?;
+                InjectedError::Leakage
+            };
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.record(vec![qubit], error);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Leaked qubits are heralded (e.g. by leakage detection or a leakage
+    // reduction unit), so they are decoded as erasures
+    pub fn decode_with_leakage(
+        &self,
+        syndrome: &[bool],
+        leaked: &[usize],
+    ) -> Result<PauliString, QuantumError> {
+        if leaked.is_empty() {
+            return self.decode(syndrome);
+        }
+        self.decode_erasure(&ErasureSyndrome {
+            syndrome: syndrome.to_vec(),
+            erased: leaked.to_vec(),
+        })
+    }
+}
@@ -0,0 +1,210 @@
+use super::*;
+use num_complex::Complex64;
+use std::f64::consts::FRAC_1_SQRT_2;
+
+// Gates on logical qubits of a register of code blocks. Logical qubit l is
+// logical index l % k of block l / k, block b occupying physical qubits
+// b n .. (b + 1) n.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogicalGate {
+    X(usize),
+    Z(usize),
+    Hadamard(usize),
+    // S = diag(1, i)
+    Phase(usize),
+    CNOT(usize, usize),
+}
+
+impl ErrorCorrectionCode {
+    pub fn num_logical_qubits(&self) -> usize {
+        self.logical_operators()
+            .iter()
+            .filter(|op| op.operator_type() == OperatorType::X)
+            .count()
+    }
+
+    fn logical_pauli(
+        &self,
+        operator_type: OperatorType,
+        logical: usize,
+    ) -> Result<PauliString, QuantumError> {
+        let k = self.num_logical_qubits();
+        if k == 0 {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        let (block, index) = (logical / k, logical % k);
+        let operator = self
+            .logical_operators()
+            .iter()
+            .filter(|op| op.operator_type() == operator_type)
+            .nth(index)
+            .ok_or(QuantumError::InvalidQubitIndex)?;
+
+        Ok(operator.pauli().shifted(block * self.num_qubits()))
+    }
+
+    pub fn logical_x(&self, logical: usize) -> Result<PauliString, QuantumError> {
+        self.logical_pauli(OperatorType::X, logical)
+    }
+
+    pub fn logical_z(&self, logical: usize) -> Result<PauliString, QuantumError> {
+        self.logical_pauli(OperatorType::Z, logical)
+    }
+
+    pub fn is_css(&self) -> bool {
+        self.get_stabilizers().iter().all(|stabilizer| {
+            let mut paulis = stabilizer.pauli().iter().map(|(_, p)| p);
+            match paulis.next() {
+                Some(first) => first != PauliOperator::Y && paulis.all(|p| p == first),
+                None => true,
+            }
+        })
+    }
+
+    fn in_stabilizer_group(&self, pauli: &PauliString) -> bool {
+        let n = self.num_qubits();
+        let rows: Vec<Vec<bool>> = self
+            .get_stabilizers()
+            .iter()
+            .map(|stabilizer| stabilizer.pauli().to_symplectic(n))
+            .collect();
+        gf2::in_row_space(&rows, &pauli.to_symplectic(n))
+    }
+
+    // X <-> Z on every qubit, up to sign
+    fn hadamard_image(pauli: &PauliString) -> PauliString {
+        let swapped: Vec<(usize, PauliOperator)> = pauli
+            .iter()
+            .map(|(q, p)| {
+                let image = match p {
+                    PauliOperator::X => PauliOperator::Z,
+                    PauliOperator::Y => PauliOperator::Y,
+                    PauliOperator::Z => PauliOperator::X,
+                };
+                (q, image)
+            })
+            .collect();
+        PauliString::from_sparse(&swapped)
+    }
+
+    // H on every qubit is a logical H when the code is CSS, H^n fixes the
+    // stabilizer group and swaps logical X and Z modulo stabilizers. Only
+    // single-logical-qubit codes qualify, since H^n acts on all k at once.
+    pub fn has_transversal_hadamard(&self) -> bool {
+        if self.num_logical_qubits() != 1 || !self.is_css() {
+            return false;
+        }
+        let (Ok(x), Ok(z)) = (self.logical_x(0), self.logical_z(0)) else {
+            return false;
+        };
+        self.get_stabilizers()
+            .iter()
+            .all(|s| self.in_stabilizer_group(&Self::hadamard_image(s.pauli())))
+            && self.in_stabilizer_group(&Self::hadamard_image(&x).multiply(&z))
+            && self.in_stabilizer_group(&Self::hadamard_image(&z).multiply(&x))
+    }
+
+    // Pairwise CNOTs between two blocks are a logical CNOT for any CSS code
+    pub fn has_transversal_cnot(&self) -> bool {
+        self.num_logical_qubits() == 1 && self.is_css()
+    }
+
+    // Transversal when the code allows it; otherwise the gate is applied
+    // exactly, but not fault-tolerantly, through its expansion in logical
+    // Paulis, H = (X + Z) / sqrt 2, S = ((1 + i) I + (1 - i) Z) / 2 and
+    // CNOT = (I + Z_c + X_t - Z_c X_t) / 2
+    pub fn apply_logical_gate(
+        &self,
+        state: &mut QuantumState,
+        gate: LogicalGate,
+    ) -> Result<(), QuantumError> {
+        let n = self.num_qubits();
+        let k = self.num_logical_qubits();
+        let highest = match gate {
+            LogicalGate::X(l)
+            | LogicalGate::Z(l)
+            | LogicalGate::Hadamard(l)
+            | LogicalGate::Phase(l) => l,
+            LogicalGate::CNOT(control, target) if control != target => control.max(target),
+            LogicalGate::CNOT(..) => return Err(QuantumError::InvalidQubitIndex),
+        };
+        if k == 0 || (highest / k + 1) * n > state.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        match gate {
+            LogicalGate::X(l) => self.logical_x(l)?.apply(state),
+            LogicalGate::Z(l) => self.logical_z(l)?.apply(state),
+            LogicalGate::Hadamard(l) if self.has_transversal_hadamard() => {
+                for qubit in l * n..(l + 1) * n {
+                    state.apply_gate(QuantumGate::Hadamard, qubit)?;
+                }
+                Ok(())
+            }
+            LogicalGate::CNOT(control, target) if self.has_transversal_cnot() => {
+                for offset in 0..n {
+                    state
+                        .apply_gate(QuantumGate::CNOT(control * n + offset), target * n + offset)?;
+                }
+                Ok(())
+            }
+            LogicalGate::Phase(l) => match self.transversal_implementation(gate)? {
+                Some(instructions) => {
+                    for GateInstruction { gate, target } in instructions {
+                        state.apply_gate(gate, target)?;
+                    }
+                    Ok(())
+                }
+                None => apply_pauli_sum(
+                    state,
+                    &[
+                        (Complex64::new(0.5, 0.5), PauliString::identity(0)),
+                        (Complex64::new(0.5, -0.5), self.logical_z(l)?),
+                    ],
+                ),
+            },
+            LogicalGate::Hadamard(l) => apply_pauli_sum(
+                state,
+                &[
+                    (Complex64::from(FRAC_1_SQRT_2), self.logical_x(l)?),
+                    (Complex64::from(FRAC_1_SQRT_2), self.logical_z(l)?),
+                ],
+            ),
+            LogicalGate::CNOT(control, target) => {
+                let z = self.logical_z(control)?;
+                let x = self.logical_x(target)?;
+                apply_pauli_sum(
+                    state,
+                    &[
+                        (Complex64::from(0.5), PauliString::identity(0)),
+                        (Complex64::from(0.5), z.clone()),
+                        (Complex64::from(0.5), x.clone()),
+                        (Complex64::from(-0.5), z.multiply(&x)),
+                    ],
+                )
+            }
+        }
+    }
+}
+
+// state <- sum_i c_i P_i state; the caller guarantees the sum is unitary
+fn apply_pauli_sum(
+    state: &mut QuantumState,
+    terms: &[(Complex64, PauliString)],
+) -> Result<(), QuantumError> {
+    let mut result = vec![Complex64::new(0.0, 0.0); state.amplitudes.len()];
+    let mut support = Vec::new();
+    for (coefficient, pauli) in terms {
+        let mut image = state.clone();
+        pauli.apply(&mut image)?;
+        for (r, a) in result.iter_mut().zip(&image.amplitudes) {
+            *r += a * coefficient;
+        }
+        support.extend(pauli.support());
+    }
+    state.amplitudes = result;
+    support.sort_unstable();
+    support.dedup();
+    state.merge_entanglement(&support);
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use super::*;
+use bitvec::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+static LOOKUP_CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// Directory where codes read and write their recovery tables, one file per
+// stabilizer set; None (the default) keeps tables in memory only
+pub fn set_lookup_cache_dir(dir: Option<PathBuf>) {
+    *LOOKUP_CACHE_DIR.write().unwrap_or_else(|e| e.into_inner()) = dir;
+}
+
+pub fn lookup_cache_dir() -> Option<PathBuf> {
+    LOOKUP_CACHE_DIR
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+pub(crate) fn lookup_cache_path(key: &[u8; 32]) -> Option<PathBuf> {
+    let name: String = key.iter().map(|byte| format!("{:02x}", byte)).collect();
+    lookup_cache_dir().map(|dir| dir.join(format!("recovery-{}.bin", name)))
+}
+
+// Syndromes and corrections in symplectic form, tagged with the key of the
+// code they were computed for
+#[derive(Serialize, Deserialize)]
+struct StoredLookup {
+    key: [u8; 32],
+    num_qubits: usize,
+    entries: Vec<(Vec<bool>, Vec<bool>)>,
+}
+
+pub(crate) fn write_lookup(
+    path: &Path,
+    key: &[u8; 32],
+    table: &HashMap<BitVec, RecoveryOperation>,
+    num_qubits: usize,
+) -> Result<(), QuantumError> {
+    let stored = StoredLookup {
+        key: *key,
+        num_qubits,
+        entries: table
+            .iter()
+            .map(|(syndrome, recovery)| {
+                (
+                    syndrome.iter().by_vals().collect(),
+                    recovery.pauli().to_symplectic(num_qubits),
+                )
+            })
+            .collect(),
+    };
+    let bytes = bincode::serialize(&stored).map_err(|_| QuantumError::LookupCacheIo)?;
+    fs::write(path, bytes).map_err(|_| QuantumError::LookupCacheIo)
+}
+
+pub(crate) fn read_lookup(
+    path: &Path,
+    key: &[u8; 32],
+) -> Result<HashMap<BitVec, RecoveryOperation>, QuantumError> {
+    let bytes = fs::read(path).map_err(|_| QuantumError::LookupCacheIo)?;
+    let stored: StoredLookup =
+        bincode::deserialize(&bytes).map_err(|_| QuantumError::MalformedLookupCache)?;
+    if &stored.key != key {
+        return Err(QuantumError::LookupCacheMismatch);
+    }
+
+    let n = stored.num_qubits;
+    stored
+        .entries
+        .into_iter()
+        .map(|(syndrome, correction)| {
+            if correction.len() != 2 * n {
+                return Err(QuantumError::MalformedLookupCache);
+            }
+            let pauli =
+                PauliString::from_xz(correction[..n].to_vec(), correction[n..].to_vec(), 0)?;
+            Ok((
+                syndrome.into_iter().collect(),
+                RecoveryOperation::from_pauli(pauli),
+            ))
+        })
+        .collect()
+}
@@ -1,13 +1,38 @@
 use super::*;
-use rand_distr::{Distribution, Normal, Uniform};
+use num_complex::Complex64;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+// Thermal relaxation of every qubit over one noise layer of `gate_time`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Relaxation {
+    t1: f64,
+    t2: f64,
+    gate_time: f64,
+    temperature: f64,
+}
 
 #[derive(Clone, Debug)]
 pub struct NoiseModel {
-    decoherence_rate: f64,
-    depolarizing_probability: f64,
-    thermal_noise_strength: f64,
-    correlation_length: f64,
-    spatial_correlations: HashMap<(usize, usize), f64>,
+    pub(crate) decoherence_rate: f64,
+    pub(crate) depolarizing_probability: f64,
+    pub(crate) thermal_noise_strength: f64,
+    pub(crate) correlation_length: f64,
+    pub(crate) spatial_correlations: HashMap<(usize, usize), f64>,
+    pub(crate) correlation_kernel: CorrelationKernel,
+    pub(crate) qubit_coordinates: Vec<(f64, f64)>,
+    relaxation: Option<Relaxation>,
+    readout: ConfusionMatrix,
+    readout_overrides: HashMap<usize, ConfusionMatrix>,
+    crosstalk: Option<Crosstalk>,
+    pub(crate) gate_errors: Vec<(Vec<usize>, QuantumChannelOp)>,
+    leakage: Option<LeakageRates>,
+    leakage_overrides: HashMap<usize, LeakageRates>,
+    pub(crate) schedules: Vec<(NoiseParameter, NoiseSchedule)>,
+    pub(crate) bias: Option<BiasedNoise>,
+    custom_channels: Vec<(QuantumChannelOp, Vec<usize>)>,
+    pub(crate) composition: Option<Composition>,
+    pub(crate) qubit_overrides: BTreeMap<usize, NoiseModel>,
 }
 
 impl NoiseModel {
@@ -23,104 +48,300 @@ impl NoiseModel {
             thermal_noise_strength,
             correlation_length,
             spatial_correlations: HashMap::new(),
+            correlation_kernel: CorrelationKernel::default(),
+            qubit_coordinates: Vec::new(),
+            relaxation: None,
+            readout: ConfusionMatrix::ideal(),
+            readout_overrides: HashMap::new(),
+            crosstalk: None,
+            gate_errors: Vec::new(),
+            leakage: None,
+            leakage_overrides: HashMap::new(),
+            schedules: Vec::new(),
+            bias: None,
+            custom_channels: Vec::new(),
+            composition: None,
+            qubit_overrides: BTreeMap::new(),
         }
     }
 
-    pub fn apply_noise(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
-        self.apply_decoherence(state)?;
-        self.apply_depolarizing_noise(state)?;
-        self.apply_thermal_noise(state)?;
-        self.apply_correlated_noise(state)?;
-        Ok(())
+    // Energy relaxation over each noise layer, one layer lasting `gate_time`.
+    // Unlike decoherence_rate, which only dephases, this drives |1> to |0>.
+    pub fn with_amplitude_damping(self, t1: f64, gate_time: f64) -> Self {
+        self.with_thermal_relaxation(t1, 2.0 * t1, gate_time, 0.0)
+    }
+
+    pub fn with_phase_damping(self, t2: f64, gate_time: f64) -> Self {
+        self.with_thermal_relaxation(f64::INFINITY, t2, gate_time, 0.0)
     }
 
-    fn apply_decoherence(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
-        let mut rng = rand::thread_rng();
-        let uniform = Uniform::new(0.0, 1.0);
+    // T1, T2 and gate time in the same units, as published in device
+    // calibrations; temperature in units of the qubit splitting
+    pub fn with_thermal_relaxation(
+        mut self,
+        t1: f64,
+        t2: f64,
+        gate_time: f64,
+        temperature: f64,
+    ) -> Self {
+        self.relaxation = Some(Relaxation {
+            t1,
+            t2,
+            gate_time,
+            temperature,
+        });
+        self
+    }
 
-        for i in 0..state.num_qubits {
-            if uniform.sample(&mut rng) < self.decoherence_rate {
-                state.apply_gate(QuantumGate::PauliZ, i)?;
-            }
+    // Readout error of every qubit without its own confusion matrix
+    pub fn with_readout_error(mut self, readout: ConfusionMatrix) -> Self {
+        self.readout = readout;
+        self
+    }
+
+    pub fn with_qubit_readout_error(mut self, qubit: usize, readout: ConfusionMatrix) -> Self {
+        self.readout_overrides.insert(qubit, readout);
+        self
+    }
+
+    pub fn readout_error(&self, qubit: usize) -> ConfusionMatrix {
+        self.readout_overrides
+            .get(&qubit)
+            .copied()
+            .unwrap_or(self.readout)
+    }
+
+    // Gate-triggered errors on spectator qubits, injected by
+    // apply_gate_noise rather than by the idle noise layer
+    pub fn with_crosstalk(mut self, crosstalk: Crosstalk) -> Self {
+        self.crosstalk = Some(crosstalk);
+        self
+    }
+
+    pub fn crosstalk(&self) -> Option<&Crosstalk> {
+        self.crosstalk.as_ref()
+    }
+
+    // `channel` on `qubits` after every gate acting on exactly those qubits,
+    // in any order; also injected by apply_gate_noise
+    pub fn with_gate_error(
+        mut self,
+        qubits: Vec<usize>,
+        channel: QuantumChannelOp,
+    ) -> Result<Self, QuantumError> {
+        if channel.num_qubits() != qubits.len() {
+            return Err(QuantumError::DimensionMismatch);
         }
+        self.gate_errors.push((qubits, channel));
+        Ok(self)
+    }
 
-        Ok(())
+    pub fn with_leakage(mut self, rates: LeakageRates) -> Self {
+        self.leakage = Some(rates);
+        self
     }
 
-    fn apply_depolarizing_noise(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
-        let mut rng = rand::thread_rng();
-        let uniform = Uniform::new(0.0, 1.0);
+    pub fn with_qubit_leakage(mut self, qubit: usize, rates: LeakageRates) -> Self {
+        self.leakage_overrides.insert(qubit, rates);
+        self
+    }
+
+    pub fn leakage_rates(&self, qubit: usize) -> Option<LeakageRates> {
+        self.leakage_overrides.get(&qubit).copied().or(self.leakage)
+    }
 
-        for i in 0..state.num_qubits {
-            if uniform.sample(&mut rng) < self.depolarizing_probability {
-                match uniform.sample(&mut rng) {
-                    x if x < 1.0/3.0 => state.apply_gate(QuantumGate::PauliX, i)?,
-                    x if x < 2.0/3.0 => state.apply_gate(QuantumGate::PauliY, i)?,
-                    _ => state.apply_gate(QuantumGate::PauliZ, i)?,
+    // Lets `parameter` drift over circuit time; apply_noise_at and
+    // at_time read the schedule, apply_noise keeps the static value
+    pub fn with_schedule(mut self, parameter: NoiseParameter, schedule: NoiseSchedule) -> Self {
+        self.schedules.retain(|(p, _)| *p != parameter);
+        self.schedules.push((parameter, schedule));
+        self
+    }
+
+    // Applied on `targets` after the built-in channels, in the order added
+    pub fn with_channel(mut self, channel: QuantumChannelOp, targets: Vec<usize>) -> Self {
+        self.custom_channels.push((channel, targets));
+        self
+    }
+
+    // One noise layer on a register of `num_qubits` qubits, as channels and
+    // the qubits they act on: the layers of sequenced models, then this
+    // model's own. Mixtures pick a layer at random and have no fixed list.
+    pub fn channels(
+        &self,
+        num_qubits: usize,
+    ) -> Result<Vec<(QuantumChannelOp, Vec<usize>)>, QuantumError> {
+        let mut channels = match &self.composition {
+            None => Vec::new(),
+            Some(Composition::Sequence(models)) => {
+                let mut channels = Vec::new();
+                for model in models {
+                    channels.extend(model.channels(num_qubits)?);
                 }
+                channels
             }
-        }
-
-        Ok(())
+            Some(Composition::Mixture(_)) => return Err(QuantumError::MixedNoiseModel),
+        };
+        channels.extend(self.layer_channels(num_qubits)?);
+        Ok(channels)
     }
 
-    fn apply_thermal_noise(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
-        let mut rng = rand::thread_rng();
-        let normal = Normal::new(0.0, self.thermal_noise_strength).unwrap();
+    // Relaxation, dephasing, depolarizing, biased and thermal noise per qubit,
+    // correlated flips per pair, then the custom channels; channels touching
+    // an overridden qubit give way to the single-qubit channels its override
+    // puts on it
+    fn layer_channels(
+        &self,
+        num_qubits: usize,
+    ) -> Result<Vec<(QuantumChannelOp, Vec<usize>)>, QuantumError> {
+        let mut channels = Vec::new();
+        let mut per_qubit = |channel: QuantumChannelOp| {
+            for q in 0..num_qubits {
+                channels.push((channel.clone(), vec![q]));
+            }
+        };
+        if let Some(r) = self.relaxation {
+            per_qubit(QuantumChannelOp::thermal_relaxation(
+                r.t1,
+                r.t2,
+                r.gate_time,
+                r.temperature,
+            )?);
+        }
+        if self.decoherence_rate > 0.0 {
+            per_qubit(QuantumChannelOp::phase_flip(self.decoherence_rate)?);
+        }
+        if self.depolarizing_probability > 0.0 {
+            per_qubit(QuantumChannelOp::depolarizing(
+                self.depolarizing_probability,
+            )?);
+        }
+        if let Some(bias) = self.bias.filter(|b| b.probability() > 0.0) {
+            per_qubit(bias.channel()?);
+        }
+        // Gaussian kicks of standard deviation sigma on every amplitude
+        // component, followed by renormalisation, mix a qubit towards I / 2
+        // with weight 4 sigma^2 / (1 + 4 sigma^2) on average
+        if self.thermal_noise_strength > 0.0 {
+            let variance = 4.0 * self.thermal_noise_strength.powi(2);
+            let mixing = variance / (1.0 + variance);
+            per_qubit(QuantumChannelOp::depolarizing(0.75 * mixing)?);
+        }
+
+        for i in 0..num_qubits {
+            for j in (i + 1)..num_qubits {
+                let correlation = self.spatial_correlation(i, j);
+                if correlation > 0.0 {
+                    channels.push((QuantumChannelOp::correlated_flip(correlation)?, vec![i, j]));
+                }
+            }
+        }
 
-        for i in 0..state.amplitudes.len() {
-            let noise = Complex64::new(
-                normal.sample(&mut rng),
-                normal.sample(&mut rng)
-            );
-            state.amplitudes[i] += noise;
+        for (channel, targets) in &self.custom_channels {
+            if targets.iter().any(|&q| q >= num_qubits) {
+                return Err(QuantumError::InvalidQubitIndex);
+            }
+            channels.push((channel.clone(), targets.clone()));
         }
 
-        // Renormalize the state
-        let norm = state.amplitudes.iter()
-            .map(|x| x.norm_sqr())
-            .sum::<f64>()
-            .sqrt();
-        
-        for amplitude in &mut state.amplitudes {
-            *amplitude /= norm;
+        if !self.qubit_overrides.is_empty() {
+            channels.retain(|(_, targets)| {
+                targets
+                    .iter()
+                    .all(|q| !self.qubit_overrides.contains_key(q))
+            });
+            for (&qubit, model) in self.qubit_overrides.range(..num_qubits) {
+                channels.extend(
+                    model
+                        .channels(num_qubits)?
+                        .into_iter()
+                        .filter(|(_, targets)| targets == &[qubit]),
+                );
+            }
         }
+        Ok(channels)
+    }
 
-        Ok(())
+    pub fn apply_noise<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.apply_layer(state, None, rng)
     }
 
-    fn apply_correlated_noise(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
-        let mut rng = rand::thread_rng();
-        let uniform = Uniform::new(0.0, 1.0);
-
-        for i in 0..state.num_qubits {
-            for j in (i+1)..state.num_qubits {
-                let correlation = self.get_spatial_correlation(i, j);
-                if uniform.sample(&mut rng) < correlation {
-                    // Apply correlated errors
-                    match uniform.sample(&mut rng) {
-                        x if x < 0.5 => {
-                            state.apply_gate(QuantumGate::PauliX, i)?;
-                            state.apply_gate(QuantumGate::PauliX, j)?;
-                        },
-                        _ => {
-                            state.apply_gate(QuantumGate::PauliZ, i)?;
-                            state.apply_gate(QuantumGate::PauliZ, j)?;
-                        }
-                    }
+    pub(crate) fn apply_layer<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        mut trace: Option<&mut NoiseTrace>,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        match &self.composition {
+            None => {}
+            Some(Composition::Sequence(models)) => {
+                for model in models {
+                    model.apply_layer(state, trace.as_deref_mut(), rng)?;
                 }
             }
+            Some(Composition::Mixture(models)) => {
+                choose(models, rng).apply_layer(state, trace.as_deref_mut(), rng)?
+            }
         }
+        for (channel, targets) in self.layer_channels(state.num_qubits)? {
+            let index = channel.unravel(state, &targets, rng)?;
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.record_branch(&channel, &targets, index);
+            }
+        }
+        self.leakage_layer(state, trace, rng)
+    }
 
+    // Same channels as apply_noise, on a reduced-precision state vector
+    pub fn apply_noise_to<T: Precision, R: Rng + ?Sized>(
+        &self,
+        state: &mut StateVector<T>,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        match &self.composition {
+            None => {}
+            Some(Composition::Sequence(models)) => {
+                for model in models {
+                    model.apply_noise_to(state, rng)?;
+                }
+            }
+            Some(Composition::Mixture(models)) => choose(models, rng).apply_noise_to(state, rng)?,
+        }
+        for (channel, targets) in self.layer_channels(state.num_qubits)? {
+            channel.unravel_vector(state, &targets, rng)?;
+        }
         Ok(())
     }
 
-    fn get_spatial_correlation(&self, i: usize, j: usize) -> f64 {
-        let key = if i < j { (i, j) } else { (j, i) };
-        *self.spatial_correlations.get(&key).unwrap_or_else(|| {
-            let distance = (i as f64 - j as f64).abs();
-            let correlation = (-distance / self.correlation_length).exp();
-            &correlation
-        })
+    // The exact average of apply_noise over its randomness
+    pub fn apply_noise_to_density(&self, rho: &mut DensityMatrix) -> Result<(), QuantumError> {
+        match &self.composition {
+            None => {}
+            Some(Composition::Sequence(models)) => {
+                for model in models {
+                    model.apply_noise_to_density(rho)?;
+                }
+            }
+            Some(Composition::Mixture(models)) => {
+                let total: f64 = models.iter().map(|(w, _)| w).sum();
+                let mut mixed = vec![Complex64::new(0.0, 0.0); rho.elements.len()];
+                for (weight, model) in models {
+                    let mut branch = rho.clone();
+                    model.apply_noise_to_density(&mut branch)?;
+                    for (m, e) in mixed.iter_mut().zip(&branch.elements) {
+                        *m += e * (weight / total);
+                    }
+                }
+                rho.elements = mixed;
+            }
+        }
+        for (channel, targets) in self.layer_channels(rho.num_qubits)? {
+            channel.apply_to_density(rho, &targets)?;
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
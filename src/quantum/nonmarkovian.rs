@@ -0,0 +1,95 @@
+use super::*;
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+// Dephasing by a fluctuating frequency offset on each qubit, an
+// Ornstein-Uhlenbeck process of stationary standard deviation `sigma` and
+// correlation time `tau` (both per noise layer). Each layer rotates every
+// qubit by Rz of its current offset, so errors in nearby layers are
+// correlated; tau -> 0 recovers uncorrelated dephasing and tau -> infinity
+// quasi-static detuning. The offsets carry over between layers, so one
+// process should follow one run and be reset between runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColoredDephasing {
+    sigma: f64,
+    correlation_time: f64,
+    offsets: Vec<f64>,
+}
+
+impl ColoredDephasing {
+    pub fn new(sigma: f64, correlation_time: f64) -> Result<Self, QuantumError> {
+        if sigma.is_nan() || sigma < 0.0 || correlation_time.is_nan() || correlation_time <= 0.0 {
+            return Err(QuantumError::InvalidNoiseParameter);
+        }
+        Ok(Self {
+            sigma,
+            correlation_time,
+            offsets: Vec::new(),
+        })
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    pub fn correlation_time(&self) -> f64 {
+        self.correlation_time
+    }
+
+    // Correlation between the offsets `lag` layers apart
+    pub fn autocorrelation(&self, lag: f64) -> f64 {
+        (-lag.abs() / self.correlation_time).exp()
+    }
+
+    pub fn offsets(&self) -> &[f64] {
+        &self.offsets
+    }
+
+    // Forgets the offsets; the next layer draws them afresh
+    pub fn reset(&mut self) {
+        self.offsets.clear();
+    }
+
+    // Exact OU update over one layer; qubits seen for the first time start
+    // from the stationary distribution
+    fn advance<R: Rng + ?Sized>(&mut self, num_qubits: usize, rng: &mut R) {
+        let decay = (-1.0 / self.correlation_time).exp();
+        let kick = self.sigma * (1.0 - decay * decay).sqrt();
+        for offset in &mut self.offsets {
+            *offset = *offset * decay + kick * rng.sample::<f64, _>(StandardNormal);
+        }
+        while self.offsets.len() < num_qubits {
+            self.offsets
+                .push(self.sigma * rng.sample::<f64, _>(StandardNormal));
+        }
+    }
+
+    pub fn apply<R: Rng + ?Sized>(
+        &mut self,
+        state: &mut QuantumState,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.advance(state.num_qubits, rng);
+        for qubit in 0..state.num_qubits {
+            let offset = self.offsets[qubit];
+            if offset != 0.0 {
+                state.apply_gate(QuantumGate::Rz(offset), qubit)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The Markovian model with the same single-layer dephasing: a phase flip
+    // with probability (1 - exp(-sigma^2 / 2)) / 2
+    pub fn markovian_equivalent(&self) -> Result<QuantumChannelOp, QuantumError> {
+        QuantumChannelOp::phase_flip((1.0 - (-self.sigma.powi(2) / 2.0).exp()) / 2.0)
+    }
+}
+
+impl NoisyExecutor {
+    // Colored dephasing after every noise layer, in addition to the model
+    pub fn with_colored_dephasing(mut self, dephasing: ColoredDephasing) -> Self {
+        self.colored_dephasing = Some(dephasing);
+        self
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+use num_complex::Complex64;
+use std::ops::Range;
+
+pub(crate) fn read_register(basis: usize, register: &Range<usize>) -> usize {
+    register
+        .clone()
+        .enumerate()
+        .fold(0, |acc, (bit, qubit)| acc | (((basis >> qubit) & 1) << bit))
+}
+
+pub(crate) fn xor_register(basis: usize, register: &Range<usize>, value: usize) -> usize {
+    let mut result = basis;
+    for (bit, qubit) in register.clone().enumerate() {
+        if (value >> bit) & 1 == 1 {
+            result ^= 1 << qubit;
+        }
+    }
+    result
+}
+
+fn validate_registers(
+    state: &QuantumState,
+    input: &Range<usize>,
+    output: &Range<usize>,
+) -> Result<(), QuantumError> {
+    if input.end > state.num_qubits || output.end > state.num_qubits {
+        return Err(QuantumError::InvalidQubitIndex);
+    }
+    if input.start < output.end && output.start < input.end {
+        return Err(QuantumError::OverlappingRegisters);
+    }
+    Ok(())
+}
+
+// Compiles a classical function into the standard oracle |x>|y> -> |x>|y xor f(x)>
+pub fn apply_xor_oracle<F: Fn(usize) -> usize>(
+    state: &mut QuantumState,
+    input: &Range<usize>,
+    output: &Range<usize>,
+    f: F,
+) -> Result<(), QuantumError> {
+    validate_registers(state, input, output)?;
+
+    let mut amplitudes = vec![Complex64::new(0.0, 0.0); state.amplitudes.len()];
+    for (basis, amplitude) in state.amplitudes.iter().enumerate() {
+        let x = read_register(basis, input);
+        let mask = (1usize << output.len()) - 1;
+        amplitudes[xor_register(basis, output, f(x) & mask)] = *amplitude;
+    }
+    state.amplitudes = amplitudes;
+    let qubits: Vec<usize> = input.clone().chain(output.clone()).collect();
+    state.merge_entanglement(&qubits);
+
+    Ok(())
+}
+
+// |x> -> (-1)^f(x) |x>
+pub fn apply_phase_oracle<F: Fn(usize) -> bool>(
+    state: &mut QuantumState,
+    input: &Range<usize>,
+    f: F,
+) -> Result<(), QuantumError> {
+    if input.end > state.num_qubits {
+        return Err(QuantumError::InvalidQubitIndex);
+    }
+
+    for (basis, amplitude) in state.amplitudes.iter_mut().enumerate() {
+        if f(read_register(basis, input)) {
+            *amplitude = -*amplitude;
+        }
+    }
+    let qubits: Vec<usize> = input.clone().collect();
+    state.merge_entanglement(&qubits);
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use super::*;
+use num_complex::Complex64;
+use std::fmt;
+use std::str::FromStr;
+
+impl PauliOperator {
+    fn to_xz(self) -> (bool, bool) {
+        match self {
+            PauliOperator::X => (true, false),
+            PauliOperator::Y => (true, true),
+            PauliOperator::Z => (false, true),
+        }
+    }
+
+    fn from_xz(x: bool, z: bool) -> Option<Self> {
+        match (x, z) {
+            (false, false) => None,
+            (true, false) => Some(PauliOperator::X),
+            (true, true) => Some(PauliOperator::Y),
+            (false, true) => Some(PauliOperator::Z),
+        }
+    }
+
+    pub fn commutes_with(self, other: PauliOperator) -> bool {
+        self == other
+    }
+
+    pub fn gate(self) -> QuantumGate {
+        match self {
+            PauliOperator::X => QuantumGate::PauliX,
+            PauliOperator::Y => QuantumGate::PauliY,
+            PauliOperator::Z => QuantumGate::PauliZ,
+        }
+    }
+}
+
+// Exponent of i picked up by the single-qubit product P1 P2 (Aaronson-Gottesman g)
+fn product_phase(x1: bool, z1: bool, x2: bool, z2: bool) -> i32 {
+    let (x2, z2) = (x2 as i32, z2 as i32);
+    match (x1, z1) {
+        (false, false) => 0,
+        (true, true) => z2 - x2,
+        (true, false) => z2 * (2 * x2 - 1),
+        (false, true) => x2 * (1 - 2 * z2),
+    }
+}
+
+// i^phase times a tensor product of I, X, Y, Z in symplectic form, Y being
+// (x, z) = (1, 1). Strings of different lengths are padded with identities.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PauliString {
+    x: Vec<bool>,
+    z: Vec<bool>,
+    phase: u8,
+}
+
+impl PauliString {
+    pub fn identity(num_qubits: usize) -> Self {
+        Self {
+            x: vec![false; num_qubits],
+            z: vec![false; num_qubits],
+            phase: 0,
+        }
+    }
+
+    pub fn from_sparse(operators: &[(usize, PauliOperator)]) -> Self {
+        let num_qubits = operators.iter().map(|(q, _)| q + 1).max().unwrap_or(0);
+        let mut pauli = Self::identity(num_qubits);
+        for &(qubit, operator) in operators {
+            pauli = pauli.multiply(&Self::single(qubit, operator));
+        }
+        pauli
+    }
+
+    pub fn single(qubit: usize, operator: PauliOperator) -> Self {
+        let mut pauli = Self::identity(qubit + 1);
+        let (x, z) = operator.to_xz();
+        pauli.x[qubit] = x;
+        pauli.z[qubit] = z;
+        pauli
+    }
+
+    pub fn from_xz(x: Vec<bool>, z: Vec<bool>, phase: u8) -> Result<Self, QuantumError> {
+        if x.len() != z.len() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        Ok(Self {
+            x,
+            z,
+            phase: phase % 4,
+        })
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.x.len()
+    }
+
+    // The string is i^phase P, with phase in 0..4
+    pub fn phase(&self) -> u8 {
+        self.phase
+    }
+
+    pub fn is_hermitian(&self) -> bool {
+        self.phase % 2 == 0
+    }
+
+    pub fn get(&self, qubit: usize) -> Option<PauliOperator> {
+        match (self.x.get(qubit), self.z.get(qubit)) {
+            (Some(&x), Some(&z)) => PauliOperator::from_xz(x, z),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, qubit: usize, operator: Option<PauliOperator>) {
+        self.pad(qubit + 1);
+        let (x, z) = operator.map_or((false, false), PauliOperator::to_xz);
+        self.x[qubit] = x;
+        self.z[qubit] = z;
+    }
+
+    // Non-identity factors, in qubit order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, PauliOperator)> + '_ {
+        (0..self.num_qubits()).filter_map(move |q| self.get(q).map(|p| (q, p)))
+    }
+
+    pub fn support(&self) -> Vec<usize> {
+        self.iter().map(|(qubit, _)| qubit).collect()
+    }
+
+    pub fn weight(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.weight() == 0
+    }
+
+    // The same operator on qubits q + offset
+    pub fn shifted(&self, offset: usize) -> PauliString {
+        let mut shifted = Self::identity(self.num_qubits() + offset);
+        shifted.x[offset..].copy_from_slice(&self.x);
+        shifted.z[offset..].copy_from_slice(&self.z);
+        shifted.phase = self.phase;
+        shifted
+    }
+
+    // x bits then z bits, each padded with identities to `num_qubits`
+    pub(crate) fn to_symplectic(&self, num_qubits: usize) -> Vec<bool> {
+        let mut padded = self.clone();
+        padded.pad(num_qubits);
+        padded.x.iter().chain(&padded.z).copied().collect()
+    }
+
+    fn pad(&mut self, num_qubits: usize) {
+        if self.x.len() < num_qubits {
+            self.x.resize(num_qubits, false);
+            self.z.resize(num_qubits, false);
+        }
+    }
+
+    // self * other, with the phase tracked exactly
+    pub fn multiply(&self, other: &PauliString) -> PauliString {
+        let mut product = self.clone();
+        product.pad(other.num_qubits());
+        let mut exponent = self.phase as i32 + other.phase as i32;
+        for q in 0..other.num_qubits() {
+            exponent += product_phase(product.x[q], product.z[q], other.x[q], other.z[q]);
+            product.x[q] ^= other.x[q];
+            product.z[q] ^= other.z[q];
+        }
+        product.phase = exponent.rem_euclid(4) as u8;
+        product
+    }
+
+    pub fn commutes_with(&self, other: &PauliString) -> bool {
+        let overlap = self.num_qubits().min(other.num_qubits());
+        (0..overlap)
+            .filter(|&q| (self.x[q] & other.z[q]) ^ (self.z[q] & other.x[q]))
+            .count()
+            % 2
+            == 0
+    }
+
+    pub fn adjoint(&self) -> PauliString {
+        let mut adjoint = self.clone();
+        adjoint.phase = (4 - self.phase) % 4;
+        adjoint
+    }
+
+    // Applies i^phase P to the state, one single-qubit Pauli gate per factor
+    pub fn apply(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        for (qubit, operator) in self.iter() {
+            state.apply_gate(operator.gate(), qubit)?;
+        }
+        if self.phase != 0 {
+            let factor = Complex64::i().powu(self.phase as u32);
+            for amplitude in &mut state.amplitudes {
+                *amplitude *= factor;
+            }
+        }
+        Ok(())
+    }
+
+    // <psi| P |psi>; real for Hermitian strings
+    pub fn expectation(&self, state: &QuantumState) -> Result<Complex64, QuantumError> {
+        if self.support().iter().any(|&q| q >= state.num_qubits) {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        let mut image = state.clone();
+        self.apply(&mut image)?;
+        Ok(state
+            .amplitudes
+            .iter()
+            .zip(&image.amplitudes)
+            .map(|(a, b)| a.conj() * b)
+            .sum())
+    }
+}
+
+impl fmt::Display for PauliString {
+    // Phase prefix (+, -, +i, -i) then one of I, X, Y, Z per qubit, qubit 0 first
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(["+", "+i", "-", "-i"][self.phase as usize])?;
+        for q in 0..self.num_qubits() {
+            let letter = match self.get(q) {
+                None => 'I',
+                Some(PauliOperator::X) => 'X',
+                Some(PauliOperator::Y) => 'Y',
+                Some(PauliOperator::Z) => 'Z',
+            };
+            write!(f, "{}", letter)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PauliString {
+    type Err = QuantumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (phase, letters) = [("+i", 1), ("-i", 3), ("+", 0), ("-", 2)]
+            .iter()
+            .find_map(|(prefix, phase)| s.strip_prefix(prefix).map(|rest| (*phase, rest)))
+            .unwrap_or((0, s));
+
+        let mut pauli = Self::identity(letters.len());
+        pauli.phase = phase;
+        for (qubit, letter) in letters.chars().enumerate() {
+            let operator = match letter {
+                'I' => None,
+                'X' => Some(PauliOperator::X),
+                'Y' => Some(PauliOperator::Y),
+                'Z' => Some(PauliOperator::Z),
+                _ => return Err(QuantumError::InvalidPauliString),
+            };
+            pauli.set(qubit, operator);
+        }
+        Ok(pauli)
+    }
+}
@@ -0,0 +1,284 @@
+use super::*;
+use num_complex::{Complex, Complex64};
+use num_traits::Float;
+use rand::Rng;
+use rayon::prelude::*;
+use std::fmt::Debug;
+
+// Amplitude component type of a StateVector. f32 halves memory and roughly
+// doubles throughput; accumulations (norms, probabilities) are still done in f64.
+pub trait Precision: Float + Send + Sync + Debug + 'static {
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Precision for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Precision for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+pub type StateVector32 = StateVector<f32>;
+pub type StateVector64 = StateVector<f64>;
+
+pub(crate) fn narrow<T: Precision>(value: Complex64) -> Complex<T> {
+    Complex::new(T::from_f64(value.re), T::from_f64(value.im))
+}
+
+pub(crate) fn widen<T: Precision>(value: Complex<T>) -> Complex64 {
+    Complex64::new(value.re.to_f64(), value.im.to_f64())
+}
+
+pub fn estimate_memory_for<T: Precision>(num_qubits: usize) -> Option<usize> {
+    estimate_memory(num_qubits)
+        .map(|bytes| bytes / std::mem::size_of::<Complex64>() * std::mem::size_of::<Complex<T>>())
+}
+
+// Bare statevector generic over amplitude precision, for sweeps that only need
+// gates, Pauli noise and measurement. Convert to QuantumState for everything else.
+#[derive(Clone, Debug)]
+pub struct StateVector<T: Precision> {
+    pub(crate) amplitudes: Vec<Complex<T>>,
+    pub(crate) num_qubits: usize,
+}
+
+impl<T: Precision> StateVector<T> {
+    pub fn new(num_qubits: usize) -> Result<Self, QuantumError> {
+        match estimate_memory_for::<T>(num_qubits) {
+            Some(bytes) if bytes <= state_memory_limit() => {}
+            _ => return Err(QuantumError::StateTooLarge),
+        }
+        let mut amplitudes = vec![Complex::new(T::zero(), T::zero()); 1 << num_qubits];
+        amplitudes[0] = Complex::new(T::one(), T::zero());
+        Ok(Self {
+            amplitudes,
+            num_qubits,
+        })
+    }
+
+    pub fn from_state(state: &QuantumState) -> Self {
+        Self {
+            amplitudes: state.amplitudes.iter().map(|&a| narrow(a)).collect(),
+            num_qubits: state.num_qubits,
+        }
+    }
+
+    pub fn to_state(&self) -> Result<QuantumState, QuantumError> {
+        let mut state = QuantumState::new(self.num_qubits)?;
+        for (target, &amplitude) in state.amplitudes.iter_mut().zip(&self.amplitudes) {
+            *target = widen(amplitude);
+        }
+        Ok(state)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn amplitudes(&self) -> &[Complex<T>] {
+        &self.amplitudes
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.amplitudes
+            .par_iter()
+            .map(|a| widen(*a).norm_sqr())
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    // Rounding drift accumulates faster in f32; long sweeps should renormalise
+    // periodically
+    pub fn renormalize(&mut self) {
+        let norm = self.norm();
+        if norm > 0.0 {
+            let scale = T::from_f64(1.0 / norm);
+            self.amplitudes.par_iter_mut().for_each(|a| *a = *a * scale);
+        }
+    }
+
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes
+            .iter()
+            .map(|a| widen(*a).norm_sqr())
+            .collect()
+    }
+
+    // |<other|self>|^2 against a full-precision reference
+    pub fn fidelity(&self, reference: &QuantumState) -> Result<f64, QuantumError> {
+        if reference.num_qubits != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let overlap: Complex64 = reference
+            .amplitudes
+            .iter()
+            .zip(&self.amplitudes)
+            .map(|(r, &a)| r.conj() * widen(a))
+            .sum();
+        Ok(overlap.norm_sqr())
+    }
+
+    pub fn apply_gate(&mut self, gate: QuantumGate, target: usize) -> Result<(), QuantumError> {
+        if target >= self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        match gate {
+            QuantumGate::CNOT(control) => self.apply_multi_controlled_x(&[control], target),
+            QuantumGate::MultiControlledX(controls) => {
+                self.apply_multi_controlled_x(&controls, target)
+            }
+            QuantumGate::Swap(other) => {
+                if other >= self.num_qubits || other == target {
+                    return Err(QuantumError::InvalidQubitIndex);
+                }
+                let (a, b) = (1 << other, 1 << target);
+                for i in 0..self.amplitudes.len() {
+                    if i & a != 0 && i & b == 0 {
+                        self.amplitudes.swap(i, (i & !a) | b);
+                    }
+                }
+                Ok(())
+            }
+            QuantumGate::Unitary2(other, matrix) => {
+                self.apply_two_qubit_unitary(other, target, &matrix)
+            }
+            gate => {
+                let matrix = gate.matrix().ok_or(QuantumError::InvalidGate)?;
+                self.apply_single_qubit_unitary(target, &matrix);
+                Ok(())
+            }
+        }
+    }
+
+    fn apply_single_qubit_unitary(&mut self, target: usize, matrix: &Matrix2) {
+        let m = [
+            [narrow::<T>(matrix[0][0]), narrow(matrix[0][1])],
+            [narrow(matrix[1][0]), narrow(matrix[1][1])],
+        ];
+        let stride = 1 << target;
+        self.amplitudes
+            .par_chunks_mut(2 * stride)
+            .for_each(|block| {
+                let (zeros, ones) = block.split_at_mut(stride);
+                for (a, b) in zeros.iter_mut().zip(ones.iter_mut()) {
+                    let (x, y) = (*a, *b);
+                    *a = m[0][0] * x + m[0][1] * y;
+                    *b = m[1][0] * x + m[1][1] * y;
+                }
+            });
+    }
+
+    fn apply_two_qubit_unitary(
+        &mut self,
+        other: usize,
+        target: usize,
+        matrix: &Matrix4,
+    ) -> Result<(), QuantumError> {
+        if other >= self.num_qubits || other == target {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let m = matrix.map(|row| row.map(narrow::<T>));
+        let (high, low) = (1 << other, 1 << target);
+        for i in 0..self.amplitudes.len() {
+            if i & (high | low) == 0 {
+                let indices = [i, i | low, i | high, i | high | low];
+                let input = indices.map(|index| self.amplitudes[index]);
+                for (row, &index) in indices.iter().enumerate() {
+                    self.amplitudes[index] = (0..4)
+                        .map(|col| m[row][col] * input[col])
+                        .fold(Complex::new(T::zero(), T::zero()), |sum, term| sum + term);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_multi_controlled_x(
+        &mut self,
+        controls: &[usize],
+        target: usize,
+    ) -> Result<(), QuantumError> {
+        if controls
+            .iter()
+            .any(|&c| c >= self.num_qubits || c == target)
+        {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let control_mask = controls.iter().fold(0, |mask, &c| mask | (1 << c));
+        let target_mask = 1 << target;
+        for i in 0..self.amplitudes.len() {
+            if i & control_mask == control_mask && i & target_mask == 0 {
+                self.amplitudes.swap(i, i | target_mask);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Unitary circuits only, as with QuantumCircuit::execute
+    pub fn execute(&mut self, circuit: &QuantumCircuit) -> Result<(), QuantumError> {
+        if self.num_qubits < circuit.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        for operation in circuit.operations() {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    self.apply_gate(instruction.gate.clone(), instruction.target)?
+                }
+                _ => return Err(QuantumError::NonUnitaryOperation),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn measure_qubit<R: Rng + ?Sized>(
+        &mut self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<bool, QuantumError> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let mask = 1 << qubit;
+        let p_one: f64 = self
+            .amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i & mask != 0)
+            .map(|(_, a)| widen(*a).norm_sqr())
+            .sum();
+        let outcome = rng.gen::<f64>() < p_one;
+
+        let scale = T::from_f64(1.0 / if outcome { p_one } else { 1.0 - p_one }.sqrt());
+        for (i, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if (i & mask != 0) == outcome {
+                *amplitude = *amplitude * scale;
+            } else {
+                *amplitude = Complex::new(T::zero(), T::zero());
+            }
+        }
+
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,123 @@
+use super::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Readout of one qubit as P(report 1 | state 0) and P(report 0 | state 1)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfusionMatrix {
+    pub false_positive: f64,
+    pub false_negative: f64,
+}
+
+impl ConfusionMatrix {
+    pub fn new(false_positive: f64, false_negative: f64) -> Result<Self, QuantumError> {
+        if !(0.0..=1.0).contains(&false_positive) || !(0.0..=1.0).contains(&false_negative) {
+            return Err(QuantumError::InvalidProbability);
+        }
+        Ok(Self {
+            false_positive,
+            false_negative,
+        })
+    }
+
+    pub fn symmetric(flip_probability: f64) -> Result<Self, QuantumError> {
+        Self::new(flip_probability, flip_probability)
+    }
+
+    pub fn ideal() -> Self {
+        Self {
+            false_positive: 0.0,
+            false_negative: 0.0,
+        }
+    }
+
+    // P(report `reported` | state `actual`)
+    pub fn probability(&self, actual: bool, reported: bool) -> f64 {
+        let flip = if actual {
+            self.false_negative
+        } else {
+            self.false_positive
+        };
+        if actual == reported {
+            1.0 - flip
+        } else {
+            flip
+        }
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, actual: bool, rng: &mut R) -> bool {
+        actual ^ (rng.gen::<f64>() < self.probability(actual, !actual))
+    }
+}
+
+// Outcome of measuring `qubits` in the computational basis: the values the
+// state collapsed to, and the values the readout reported
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub qubits: Vec<usize>,
+    pub ideal: Vec<bool>,
+    pub reported: Vec<bool>,
+}
+
+impl Measurement {
+    pub fn ideal(qubits: Vec<usize>, outcomes: Vec<bool>) -> Self {
+        Self {
+            qubits,
+            reported: outcomes.clone(),
+            ideal: outcomes,
+        }
+    }
+
+    pub fn has_readout_error(&self) -> bool {
+        self.ideal != self.reported
+    }
+
+    // Qubits whose reported value differs from the collapsed state
+    pub fn misread_qubits(&self) -> Vec<usize> {
+        self.qubits
+            .iter()
+            .zip(self.ideal.iter().zip(&self.reported))
+            .filter(|(_, (ideal, reported))| ideal != reported)
+            .map(|(&q, _)| q)
+            .collect()
+    }
+}
+
+impl QuantumState {
+    // Measures `qubits` one after another and passes each outcome through
+    // the readout error of `noise`; the result is kept in the measurement
+    // history, and classical_outcomes keeps the ideal values. Leaked qubits
+    // are not measured and read as 1.
+    pub fn measure_with_readout<R: Rng + ?Sized>(
+        &mut self,
+        qubits: &[usize],
+        noise: &NoiseModel,
+        rng: &mut R,
+    ) -> Result<Measurement, QuantumError> {
+        let mut ideal = Vec::with_capacity(qubits.len());
+        for &qubit in qubits {
+            if self.is_leaked(qubit) {
+                ideal.push(true);
+            } else {
+                ideal.push(self.measure_qubit(qubit, rng)?);
+            }
+        }
+        let reported = qubits
+            .iter()
+            .zip(&ideal)
+            .map(|(&q, &outcome)| noise.readout_error(q).sample(outcome, rng))
+            .collect();
+
+        let measurement = Measurement {
+            qubits: qubits.to_vec(),
+            ideal,
+            reported,
+        };
+        self.measurement_history.push(measurement.clone());
+        Ok(measurement)
+    }
+
+    pub fn measurement_history(&self) -> &[Measurement] {
+        &self.measurement_history
+    }
+}
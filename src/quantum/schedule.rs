@@ -0,0 +1,136 @@
+use super::*;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NoiseParameter {
+    Decoherence,
+    Depolarizing,
+    Thermal,
+    CorrelationLength,
+}
+
+// Value of a noise parameter over circuit time, counted in noise layers from
+// the start of a run (one layer per circuit operation)
+#[derive(Clone)]
+pub enum NoiseSchedule {
+    // (start, value) steps in increasing start order; before the first step
+    // the model's static value applies
+    Piecewise(Vec<(f64, f64)>),
+    // Interpolates between (time, value) points, constant outside them
+    Linear(Vec<(f64, f64)>),
+    Custom(Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+
+impl fmt::Debug for NoiseSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoiseSchedule::Piecewise(steps) => f.debug_tuple("Piecewise").field(steps).finish(),
+            NoiseSchedule::Linear(points) => f.debug_tuple("Linear").field(points).finish(),
+            NoiseSchedule::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl NoiseSchedule {
+    pub fn piecewise(mut steps: Vec<(f64, f64)>) -> Self {
+        steps.sort_by(|a, b| a.0.total_cmp(&b.0));
+        NoiseSchedule::Piecewise(steps)
+    }
+
+    pub fn linear(mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        NoiseSchedule::Linear(points)
+    }
+
+    pub fn custom<F>(schedule: F) -> Self
+    where
+        F: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        NoiseSchedule::Custom(Arc::new(schedule))
+    }
+
+    // `on` for the first `on_time` of every `period`, `off` for the rest
+    pub fn duty_cycle(period: f64, on_time: f64, on: f64, off: f64) -> Self {
+        Self::custom(move |time| {
+            if time.rem_euclid(period) < on_time {
+                on
+            } else {
+                off
+            }
+        })
+    }
+
+    pub fn value_at(&self, time: f64, default: f64) -> f64 {
+        match self {
+            NoiseSchedule::Piecewise(steps) => steps
+                .iter()
+                .take_while(|(start, _)| *start <= time)
+                .last()
+                .map_or(default, |&(_, value)| value),
+            NoiseSchedule::Linear(points) => {
+                let after = points.partition_point(|&(t, _)| t <= time);
+                match (after.checked_sub(1).map(|i| points[i]), points.get(after)) {
+                    (None, None) => default,
+                    (Some((_, value)), None) | (None, Some(&(_, value))) => value,
+                    (Some((t0, v0)), Some(&(t1, v1))) => v0 + (v1 - v0) * (time - t0) / (t1 - t0),
+                }
+            }
+            NoiseSchedule::Custom(schedule) => schedule(time),
+        }
+    }
+}
+
+impl NoiseModel {
+    // The static model in force at `time`, nested models included
+    pub fn at_time(&self, time: f64) -> NoiseModel {
+        self.map_nested(&|model| {
+            let mut model = model.clone();
+            for (parameter, schedule) in std::mem::take(&mut model.schedules) {
+                let value = match parameter {
+                    NoiseParameter::Decoherence => &mut model.decoherence_rate,
+                    NoiseParameter::Depolarizing => &mut model.depolarizing_probability,
+                    NoiseParameter::Thermal => &mut model.thermal_noise_strength,
+                    NoiseParameter::CorrelationLength => &mut model.correlation_length,
+                };
+                *value = schedule.value_at(time, *value);
+            }
+            model
+        })
+    }
+
+    fn is_static(&self) -> bool {
+        let nested_static = match &self.composition {
+            None => true,
+            Some(Composition::Sequence(models)) => models.iter().all(|m| m.is_static()),
+            Some(Composition::Mixture(models)) => models.iter().all(|(_, m)| m.is_static()),
+        };
+        self.schedules.is_empty()
+            && nested_static
+            && self.qubit_overrides.values().all(|m| m.is_static())
+    }
+
+    pub fn apply_noise_at<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        time: f64,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.apply_layer_at(state, time, None, rng)
+    }
+
+    pub(crate) fn apply_layer_at<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        time: f64,
+        trace: Option<&mut NoiseTrace>,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        if self.is_static() {
+            self.apply_layer(state, trace, rng)
+        } else {
+            self.at_time(time).apply_layer(state, trace, rng)
+        }
+    }
+}
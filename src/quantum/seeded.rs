@@ -0,0 +1,85 @@
+use super::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+// A noise model bundled with its own ChaCha20 generator, so that a noisy
+// experiment is fixed by one seed and replays exactly. Every noise method
+// also takes any `&mut impl Rng` (including `&mut dyn RngCore`) directly;
+// this is for callers that would rather not thread a generator through.
+#[derive(Clone, Debug)]
+pub struct SeededNoiseModel {
+    model: NoiseModel,
+    seed: u64,
+    rng: ChaCha20Rng,
+}
+
+impl SeededNoiseModel {
+    pub fn new(model: NoiseModel, seed: u64) -> Self {
+        Self {
+            model,
+            seed,
+            rng: ChaCha20Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn model(&self) -> &NoiseModel {
+        &self.model
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut ChaCha20Rng {
+        &mut self.rng
+    }
+
+    // Restarts the stream, replaying every draw made since the seed was set
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha20Rng::seed_from_u64(seed);
+    }
+
+    pub fn apply_noise(&mut self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        self.model.apply_noise(state, &mut self.rng)
+    }
+
+    pub fn apply_noise_at(
+        &mut self,
+        state: &mut QuantumState,
+        time: f64,
+    ) -> Result<(), QuantumError> {
+        self.model.apply_noise_at(state, time, &mut self.rng)
+    }
+
+    pub fn apply_noise_to<T: Precision>(
+        &mut self,
+        state: &mut StateVector<T>,
+    ) -> Result<(), QuantumError> {
+        self.model.apply_noise_to(state, &mut self.rng)
+    }
+
+    pub fn apply_gate_noise(
+        &mut self,
+        state: &mut QuantumState,
+        gate: &QuantumGate,
+        target: usize,
+    ) -> Result<(), QuantumError> {
+        self.model
+            .apply_gate_noise(state, gate, target, &mut self.rng)
+    }
+
+    pub fn measure_with_readout(
+        &mut self,
+        state: &mut QuantumState,
+        qubits: &[usize],
+    ) -> Result<Measurement, QuantumError> {
+        state.measure_with_readout(qubits, &self.model, &mut self.rng)
+    }
+}
+
+impl NoiseModel {
+    pub fn seeded(self, seed: u64) -> SeededNoiseModel {
+        SeededNoiseModel::new(self, seed)
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+
+// Syndromes from repeated rounds of stabilizer measurement, oldest first. The
+// last round is taken to be reliable, as when it is inferred from a final
+// transversal readout of the data qubits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyndromeHistory {
+    rounds: Vec<Vec<bool>>,
+}
+
+impl SyndromeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_round(&mut self, syndrome: Vec<bool>) {
+        self.rounds.push(syndrome);
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self.rounds.len()
+    }
+
+    pub fn rounds(&self) -> &[Vec<bool>] {
+        &self.rounds
+    }
+
+    // Detector (r, i) fires when stabilizer i changed between rounds r - 1 and
+    // r, round -1 being all zeros; flattened as r * m + i
+    pub fn detection_events(&self) -> Vec<bool> {
+        let mut previous: Vec<bool> = Vec::new();
+        let mut events = Vec::new();
+        for round in &self.rounds {
+            previous.resize(round.len(), false);
+            events.extend(
+                round
+                    .iter()
+                    .zip(&previous)
+                    .map(|(&now, &before)| now ^ before),
+            );
+            previous.clone_from(round);
+        }
+        events
+    }
+}
+
+// A fault mechanism for decoding: the detectors it flips, the Pauli it leaves
+// on the data qubits, and its cost, e.g. ln((1 - p) / p) for probability p
+#[derive(Clone, Debug)]
+pub struct Fault {
+    pub detectors: Vec<usize>,
+    pub correction: PauliString,
+    pub weight: f64,
+}
+
+// Finds the cheapest set of at most `max_faults` faults explaining the fired
+// detectors. Each step picks the first unexplained detector and branches over
+// the faults touching it, pruning branches already costlier than the best.
+#[derive(Clone, Debug)]
+pub struct SpacetimeDecoder {
+    faults: Vec<Fault>,
+    by_detector: Vec<Vec<usize>>,
+    max_faults: usize,
+}
+
+impl SpacetimeDecoder {
+    pub fn new(faults: Vec<Fault>, num_detectors: usize, max_faults: usize) -> Self {
+        let mut by_detector = vec![Vec::new(); num_detectors];
+        for (index, fault) in faults.iter().enumerate() {
+            for &detector in &fault.detectors {
+                if detector < num_detectors {
+                    by_detector[detector].push(index);
+                }
+            }
+        }
+        Self {
+            faults,
+            by_detector,
+            max_faults,
+        }
+    }
+
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    pub fn num_detectors(&self) -> usize {
+        self.by_detector.len()
+    }
+
+    pub fn decode(&self, detectors: &[bool]) -> Result<PauliString, QuantumError> {
+        if detectors.len() != self.num_detectors() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut remaining = detectors.to_vec();
+        let mut chosen = Vec::new();
+        let mut best: Option<(f64, Vec<usize>)> = None;
+        self.search(&mut remaining, 0.0, &mut chosen, &mut best);
+
+        let (_, faults) = best.ok_or(QuantumError::UnknownSyndrome)?;
+        Ok(faults
+            .iter()
+            .fold(PauliString::identity(0), |correction, &index| {
+                correction.multiply(&self.faults[index].correction)
+            }))
+    }
+
+    fn toggle(&self, remaining: &mut [bool], fault: usize) {
+        for &detector in &self.faults[fault].detectors {
+            remaining[detector] ^= true;
+        }
+    }
+
+    fn search(
+        &self,
+        remaining: &mut [bool],
+        cost: f64,
+        chosen: &mut Vec<usize>,
+        best: &mut Option<(f64, Vec<usize>)>,
+    ) {
+        let Some(first) = remaining.iter().position(|&fired| fired) else {
+            if best.as_ref().is_none_or(|(b, _)| cost < *b) {
+                *best = Some((cost, chosen.clone()));
+            }
+            return;
+        };
+        if chosen.len() == self.max_faults {
+            return;
+        }
+
+        for &fault in &self.by_detector[first] {
+            let next = cost + self.faults[fault].weight;
+            if best.as_ref().is_some_and(|(b, _)| next >= *b) {
+                continue;
+            }
+            self.toggle(remaining, fault);
+            chosen.push(fault);
+            self.search(remaining, next, chosen, best);
+            chosen.pop();
+            self.toggle(remaining, fault);
+        }
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Phenomenological faults over `rounds` rounds, all of unit weight: a
+    // Pauli on a data qubit just before round r fires detectors in round r
+    // only, and a flipped outcome of stabilizer i in round r < rounds - 1
+    // fires (r, i) and (r + 1, i)
+    pub fn spacetime_faults(&self, rounds: usize) -> Vec<Fault> {
+        let m = self.get_stabilizers().len();
+        let mut faults = Vec::new();
+        for round in 0..rounds {
+            for qubit in 0..self.num_qubits() {
+                for pauli in [PauliOperator::X, PauliOperator::Y, PauliOperator::Z] {
+                    let correction = PauliString::single(qubit, pauli);
+                    let detectors = self
+                        .get_stabilizers()
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, s)| !s.pauli().commutes_with(&correction))
+                        .map(|(i, _)| round * m + i)
+                        .collect();
+                    faults.push(Fault {
+                        detectors,
+                        correction,
+                        weight: 1.0,
+                    });
+                }
+            }
+            if round + 1 < rounds {
+                for i in 0..m {
+                    faults.push(Fault {
+                        detectors: vec![round * m + i, (round + 1) * m + i],
+                        correction: PauliString::identity(0),
+                        weight: 1.0,
+                    });
+                }
+            }
+        }
+        faults
+    }
+
+    // Up to d faults are searched, comfortably more than the (d - 1) / 2 a
+    // distance-d code can always correct
+    pub fn spacetime_decoder(&self, rounds: usize) -> SpacetimeDecoder {
+        SpacetimeDecoder::new(
+            self.spacetime_faults(rounds),
+            rounds * self.get_stabilizers().len(),
+            self.distance(),
+        )
+    }
+
+    // Phenomenological faults weighted by their rates: each data Pauli has
+    // probability data_error / 3, and stabilizer outcomes in round r are wrong
+    // with probability measurement_errors[r]. Unlike spacetime_faults, every
+    // round may be faulty, so a flip in the last round fires only (r, i).
+    pub fn phenomenological_faults(
+        &self,
+        data_error: f64,
+        measurement_errors: &[f64],
+    ) -> Vec<Fault> {
+        let m = self.get_stabilizers().len();
+        let rounds = measurement_errors.len();
+        let mut faults = self.spacetime_faults(rounds);
+        for fault in &mut faults {
+            fault.weight = if fault.correction.is_identity() {
+                log_odds(measurement_errors[fault.detectors[0] / m])
+            } else {
+                log_odds(data_error / 3.0)
+            };
+        }
+        if let Some(&last) = measurement_errors.last() {
+            faults.extend((0..m).map(|i| Fault {
+                detectors: vec![(rounds - 1) * m + i],
+                correction: PauliString::identity(0),
+                weight: log_odds(last),
+            }));
+        }
+        faults
+    }
+
+    // Decodes noisy syndromes from repeated measure_syndrome calls, oldest
+    // first, trusting each as far as its recorded measurement error
+    pub fn decode_noisy_syndromes(
+        &self,
+        syndromes: &[ErrorSyndrome],
+        data_error: f64,
+    ) -> Result<PauliString, QuantumError> {
+        let m = self.get_stabilizers().len();
+        let mut history = SyndromeHistory::new();
+        for syndrome in syndromes {
+            let bits = syndrome.to_vec();
+            if bits.len() != m {
+                return Err(QuantumError::DimensionMismatch);
+            }
+            history.push_round(bits);
+        }
+        let measurement_errors: Vec<f64> = syndromes
+            .iter()
+            .map(ErrorSyndrome::measurement_error)
+            .collect();
+
+        SpacetimeDecoder::new(
+            self.phenomenological_faults(data_error, &measurement_errors),
+            syndromes.len() * m,
+            self.distance() + syndromes.len(),
+        )
+        .decode(&history.detection_events())
+    }
+
+    // Net data correction for the whole history, decoded at once rather than
+    // round by round
+    pub fn decode_history(&self, history: &SyndromeHistory) -> Result<PauliString, QuantumError> {
+        let m = self.get_stabilizers().len();
+        if history.rounds().iter().any(|round| round.len() != m) {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        self.spacetime_decoder(history.num_rounds())
+            .decode(&history.detection_events())
+    }
+}
+
+// Cost ln((1 - p) / p) of a fault with probability p, kept finite and
+// non-negative
+pub(crate) fn log_odds(probability: f64) -> f64 {
+    let p = probability.clamp(1e-12, 0.5);
+    ((1.0 - p) / p).ln()
+}
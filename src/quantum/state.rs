@@ -1,31 +1,141 @@
 use super::*;
-use std::ops::{Add, Mul};
+use std::ops::{Add, Mul, Range};
+use bitvec::prelude::*;
 use num_complex::Complex64;
+use rand::Rng;
 use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const BROADCAST_TILE_QUBITS: usize = 12;
+const DEFAULT_STATE_MEMORY_LIMIT: usize = 16 << 30;
+
+static STATE_MEMORY_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_STATE_MEMORY_LIMIT);
+
+// Bytes needed for the amplitude vector of an n-qubit state, or None on overflow
+pub fn estimate_memory(num_qubits: usize) -> Option<usize> {
+    1usize
+        .checked_shl(num_qubits as u32)
+        .filter(|&dimension| dimension.trailing_zeros() as usize == num_qubits)
+        .and_then(|dimension| dimension.checked_mul(std::mem::size_of::<Complex64>()))
+}
+
+pub fn state_memory_limit() -> usize {
+    STATE_MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+// Caps the amplitude memory QuantumState::new will allocate (default 16 GiB)
+pub fn set_state_memory_limit(bytes: usize) {
+    STATE_MEMORY_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub fn max_simulable_qubits() -> usize {
+    (0..usize::BITS as usize)
+        .take_while(|&n| estimate_memory(n).map_or(false, |bytes| bytes <= state_memory_limit()))
+        .last()
+        .unwrap_or(0)
+}
+
+// Applies a 2x2 matrix to every amplitude pair (i, i + stride) with bit `stride` clear
+fn butterfly(amplitudes: &mut [Complex64], stride: usize, matrix: &Matrix2) {
+    for i in 0..amplitudes.len() {
+        if i & stride == 0 {
+            let a = amplitudes[i];
+            let b = amplitudes[i | stride];
+            amplitudes[i] = matrix[0][0] * a + matrix[0][1] * b;
+            amplitudes[i | stride] = matrix[1][0] * a + matrix[1][1] * b;
+        }
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QuantumState {
     pub(crate) amplitudes: Vec<Complex64>,
     pub(crate) num_qubits: usize,
-    pub(crate) basis_states: Vec<BasisState>,
     pub(crate) entanglement_map: HashMap<usize, Vec<usize>>,
     pub(crate) measurement_history: Vec<Measurement>,
+    pub(crate) classical_outcomes: Vec<bool>,
     pub(crate) error_syndrome: Option<ErrorSyndrome>,
+    #[serde(default)]
+    pub(crate) leaked: Vec<usize>,
 }
 
 impl QuantumState {
-    pub fn new(num_qubits: usize) -> Self {
+    // |0...0>, failing fast instead of attempting an allocation beyond the
+    // memory limit
+    pub fn new(num_qubits: usize) -> Result<Self, QuantumError> {
+        match estimate_memory(num_qubits) {
+            Some(bytes) if bytes <= state_memory_limit() => {}
+            _ => return Err(QuantumError::StateTooLarge),
+        }
         let mut amplitudes = vec![Complex64::new(0.0, 0.0); 1 << num_qubits];
         amplitudes[0] = Complex64::new(1.0, 0.0);
         
-        Self {
+        Ok(Self {
             amplitudes,
             num_qubits,
-            basis_states: vec![BasisState::new(num_qubits)],
             entanglement_map: HashMap::new(),
             measurement_history: Vec::new(),
+            classical_outcomes: Vec::new(),
             error_syndrome: None,
+            leaked: Vec::new(),
+        })
+    }
+
+    // (|00> + |11>) / sqrt(2)
+    pub fn bell_pair() -> Result<Self, QuantumError> {
+        Self::epr_pairs(1)
+    }
+
+    // k Bell pairs on 2k qubits, pairing qubit 2i with qubit 2i + 1
+    pub fn epr_pairs(k: usize) -> Result<Self, QuantumError> {
+        let mut state = Self::new(2 * k)?;
+        let amplitude = Complex64::new(0.5f64.sqrt().powi(k as i32), 0.0);
+        state.amplitudes[0] = Complex64::new(0.0, 0.0);
+        for pattern in 0..(1usize << k) {
+            let mut index = 0;
+            for pair in 0..k {
+                if (pattern >> pair) & 1 == 1 {
+                    index |= 0b11 << (2 * pair);
+                }
+            }
+            state.amplitudes[index] = amplitude;
+        }
+        for pair in 0..k {
+            state.merge_entanglement(&[2 * pair, 2 * pair + 1]);
+        }
+        Ok(state)
+    }
+
+    // (|0...0> + |1...1>) / sqrt(2)
+    pub fn ghz(num_qubits: usize) -> Result<Self, QuantumError> {
+        if num_qubits == 0 {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        let mut state = Self::new(num_qubits)?;
+        let amplitude = Complex64::new(0.5f64.sqrt(), 0.0);
+        state.amplitudes[0] = amplitude;
+        state.amplitudes[(1 << num_qubits) - 1] = amplitude;
+        let qubits: Vec<usize> = (0..num_qubits).collect();
+        state.merge_entanglement(&qubits);
+        Ok(state)
+    }
+
+    // Uniform superposition of all weight-one basis states
+    pub fn w_state(num_qubits: usize) -> Result<Self, QuantumError> {
+        if num_qubits == 0 {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        let mut state = Self::new(num_qubits)?;
+        let amplitude = Complex64::new(1.0 / (num_qubits as f64).sqrt(), 0.0);
+        state.amplitudes[0] = Complex64::new(0.0, 0.0);
+        for qubit in 0..num_qubits {
+            state.amplitudes[1 << qubit] = amplitude;
         }
+        let qubits: Vec<usize> = (0..num_qubits).collect();
+        state.merge_entanglement(&qubits);
+        Ok(state)
     }
 
     pub fn apply_gate(&mut self, gate: QuantumGate, target: usize) -> Result<(), QuantumError> {
@@ -39,28 +149,401 @@ impl QuantumState {
             QuantumGate::PauliY => self.apply_pauli_y(target),
             QuantumGate::PauliZ => self.apply_pauli_z(target),
             QuantumGate::Phase(phi) => self.apply_phase(target, phi),
-            QuantumGate::CNOT(control) => self.apply_cnot(control, target),
+            QuantumGate::CNOT(control) => {
+                self.apply_cnot(control, target)?;
+                self.merge_entanglement(&[control, target]);
+                Ok(())
+            }
+            QuantumGate::Rx(_)
+            | QuantumGate::Ry(_)
+            | QuantumGate::Rz(_)
+            | QuantumGate::SqrtX
+            | QuantumGate::SqrtXDagger
+            | QuantumGate::U3(..)
+            | QuantumGate::Unitary1(_) => {
+                let matrix = gate.matrix().ok_or(QuantumError::InvalidGate)?;
+                self.apply_single_qubit_unitary(target, &matrix)
+            }
+            QuantumGate::Swap(other) => {
+                self.apply_swap(other, target)?;
+                self.swap_entanglement(other, target);
+                Ok(())
+            }
+            QuantumGate::MultiControlledX(controls) => {
+                self.apply_multi_controlled_x(&controls, target)?;
+                let mut qubits = controls;
+                qubits.push(target);
+                self.merge_entanglement(&qubits);
+                Ok(())
+            }
+            QuantumGate::Unitary2(other, matrix) => {
+                self.apply_two_qubit_unitary(other, target, &matrix)?;
+                self.merge_entanglement(&[other, target]);
+                Ok(())
+            }
+        }
+    }
+
+    // The entanglement map is conservative: qubits that have interacted through
+    // a multi-qubit gate share a cluster until they are measured or reset.
+    pub fn is_entangled(&self, a: usize, b: usize) -> bool {
+        a != b
+            && self
+                .entanglement_map
+                .get(&a)
+                .map_or(false, |cluster| cluster.contains(&b))
+    }
+
+    pub fn entangled_cluster(&self, qubit: usize) -> Vec<usize> {
+        self.entanglement_map
+            .get(&qubit)
+            .cloned()
+            .unwrap_or_else(|| vec![qubit])
+    }
+
+    // Partition of all qubits into clusters that can be treated as tensor factors
+    pub fn entanglement_clusters(&self) -> Vec<Vec<usize>> {
+        let mut clusters: Vec<Vec<usize>> = Vec::new();
+        for qubit in 0..self.num_qubits {
+            let cluster = self.entangled_cluster(qubit);
+            if cluster[0] == qubit {
+                clusters.push(cluster);
+            }
+        }
+        clusters
+    }
+
+    pub(crate) fn merge_entanglement(&mut self, qubits: &[usize]) {
+        let mut cluster: Vec<usize> = qubits
+            .iter()
+            .flat_map(|&q| self.entangled_cluster(q))
+            .collect();
+        cluster.sort_unstable();
+        cluster.dedup();
+
+        for &qubit in &cluster {
+            self.entanglement_map.insert(qubit, cluster.clone());
+        }
+    }
+
+    fn isolate_qubit(&mut self, qubit: usize) {
+        if let Some(cluster) = self.entanglement_map.remove(&qubit) {
+            let rest: Vec<usize> = cluster.into_iter().filter(|&q| q != qubit).collect();
+            for &other in &rest {
+                if rest.len() > 1 {
+                    self.entanglement_map.insert(other, rest.clone());
+                } else {
+                    self.entanglement_map.remove(&other);
+                }
+            }
+        }
+    }
+
+    // A SWAP exchanges which cluster each qubit belongs to rather than merging them
+    fn swap_entanglement(&mut self, a: usize, b: usize) {
+        let relabel = |q: usize| {
+            if q == a {
+                b
+            } else if q == b {
+                a
+            } else {
+                q
+            }
+        };
+
+        let map = std::mem::take(&mut self.entanglement_map);
+        for (qubit, cluster) in map {
+            let mut cluster: Vec<usize> = cluster.into_iter().map(relabel).collect();
+            cluster.sort_unstable();
+            self.entanglement_map.insert(relabel(qubit), cluster);
+        }
+    }
+
+    pub(crate) fn apply_single_qubit_unitary(
+        &mut self,
+        target: usize,
+        matrix: &Matrix2,
+    ) -> Result<(), QuantumError> {
+        if target >= self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let mask = 1 << target;
+        for i in 0..self.amplitudes.len() {
+            if i & mask == 0 {
+                let a = self.amplitudes[i];
+                let b = self.amplitudes[i | mask];
+                self.amplitudes[i] = matrix[0][0] * a + matrix[0][1] * b;
+                self.amplitudes[i | mask] = matrix[1][0] * a + matrix[1][1] * b;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_gate_all(&mut self, gate: QuantumGate) -> Result<(), QuantumError> {
+        self.apply_gate_range(gate, 0..self.num_qubits)
+    }
+
+    // Applies a single-qubit gate to every qubit in `range`. Qubits below
+    // BROADCAST_TILE_QUBITS are handled tile by tile so each tile is swept once
+    // while it is in cache; the remaining qubits are applied together in a
+    // second pass that gathers their 2^h strided amplitudes per base index.
+    pub fn apply_gate_range(
+        &mut self,
+        gate: QuantumGate,
+        range: Range<usize>,
+    ) -> Result<(), QuantumError> {
+        if range.end > self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        let matrix = gate.matrix().ok_or(QuantumError::InvalidGate)?;
+
+        let tile_qubits = BROADCAST_TILE_QUBITS.min(self.num_qubits);
+        let low: Vec<usize> = range.clone().filter(|&q| q < tile_qubits).collect();
+        let high: Vec<usize> = range.filter(|&q| q >= tile_qubits).collect();
+
+        if !low.is_empty() {
+            self.amplitudes
+                .par_chunks_mut(1 << tile_qubits)
+                .for_each(|tile| {
+                    for &qubit in &low {
+                        butterfly(tile, 1 << qubit, &matrix);
+                    }
+                });
+        }
+
+        if !high.is_empty() {
+            let high_mask = high.iter().fold(0, |mask, &q| mask | (1 << q));
+            let mut buffer = vec![Complex64::new(0.0, 0.0); 1 << high.len()];
+            for base in 0..self.amplitudes.len() {
+                if base & high_mask != 0 {
+                    continue;
+                }
+                let index = |local: usize| {
+                    high.iter()
+                        .enumerate()
+                        .fold(base, |i, (bit, &q)| i | (((local >> bit) & 1) << q))
+                };
+                for (local, slot) in buffer.iter_mut().enumerate() {
+                    *slot = self.amplitudes[index(local)];
+                }
+                for bit in 0..high.len() {
+                    butterfly(&mut buffer, 1 << bit, &matrix);
+                }
+                for (local, value) in buffer.iter().enumerate() {
+                    self.amplitudes[index(local)] = *value;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_two_qubit_unitary(
+        &mut self,
+        other: usize,
+        target: usize,
+        matrix: &Matrix4,
+    ) -> Result<(), QuantumError> {
+        if other >= self.num_qubits || other == target {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let (high, low) = (1 << other, 1 << target);
+        for i in 0..self.amplitudes.len() {
+            if i & (high | low) == 0 {
+                let indices = [i, i | low, i | high, i | high | low];
+                let input = indices.map(|index| self.amplitudes[index]);
+                for (row, &index) in indices.iter().enumerate() {
+                    self.amplitudes[index] = (0..4).map(|col| matrix[row][col] * input[col]).sum();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_swap(&mut self, other: usize, target: usize) -> Result<(), QuantumError> {
+        if other >= self.num_qubits || other == target {
+            return Err(QuantumError::InvalidQubitIndex);
         }
+
+        let (a, b) = (1 << other, 1 << target);
+        for i in 0..self.amplitudes.len() {
+            if i & a != 0 && i & b == 0 {
+                self.amplitudes.swap(i, (i & !a) | b);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_multi_controlled_x(
+        &mut self,
+        controls: &[usize],
+        target: usize,
+    ) -> Result<(), QuantumError> {
+        if controls.iter().any(|&c| c >= self.num_qubits || c == target) {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        let control_mask = controls.iter().fold(0, |mask, &c| mask | (1 << c));
+        let target_mask = 1 << target;
+        for i in 0..self.amplitudes.len() {
+            if i & control_mask == control_mask && i & target_mask == 0 {
+                self.amplitudes.swap(i, i | target_mask);
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn measure(&mut self, basis: MeasurementBasis) -> Result<Measurement, QuantumError> {
-        let mut rng = rand::thread_rng();
+    pub fn measure<R: Rng + ?Sized>(
+        &mut self,
+        basis: MeasurementBasis,
+        rng: &mut R,
+    ) -> Result<Measurement, QuantumError> {
         let distribution = Normal::new(0.0, 1.0).unwrap();
         
         let measurement = match basis {
-            MeasurementBasis::Computational => self.measure_computational(&mut rng),
-            MeasurementBasis::Bell => self.measure_bell(&mut rng),
-            MeasurementBasis::Magic => self.measure_magic(&mut rng, &distribution),
+            MeasurementBasis::Computational => self.measure_computational(rng),
+            MeasurementBasis::Bell => self.measure_bell(rng),
+            MeasurementBasis::Magic => self.measure_magic(rng, &distribution),
+            MeasurementBasis::Arbitrary(unitary) => {
+                let outcome = self.measure_in_basis(&unitary, rng)?;
+                let qubits: Vec<usize> = (0..unitary.num_qubits()).collect();
+                let outcomes = qubits.iter().map(|q| (outcome >> q) & 1 == 1).collect();
+                Ok(Measurement::ideal(qubits, outcomes))
+            }
         }?;
 
         self.measurement_history.push(measurement.clone());
         Ok(measurement)
     }
 
+    // Measures the lowest `unitary.num_qubits()` qubits in the basis {U|k>},
+    // returning k and leaving those qubits in U|k>
+    pub fn measure_in_basis<R: Rng + ?Sized>(
+        &mut self,
+        unitary: &Unitary,
+        rng: &mut R,
+    ) -> Result<usize, QuantumError> {
+        self.apply_unitary(&unitary.adjoint())?;
+        let mut outcome = 0;
+        for qubit in 0..unitary.num_qubits() {
+            if self.measure_qubit(qubit, rng)? {
+                outcome |= 1 << qubit;
+            }
+        }
+        self.apply_unitary(unitary)?;
+        Ok(outcome)
+    }
+
+    pub fn measure_qubit<R: Rng + ?Sized>(
+        &mut self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<bool, QuantumError> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+
+        // Both weights are summed, since leakage, noise or resets can leave
+        // the state unnormalized
+        let mask = 1 << qubit;
+        let (mut p_zero, mut p_one) = (0.0, 0.0);
+        for (i, amplitude) in self.amplitudes.iter().enumerate() {
+            if i & mask != 0 {
+                p_one += amplitude.norm_sqr();
+            } else {
+                p_zero += amplitude.norm_sqr();
+            }
+        }
+        let total = p_zero + p_one;
+        if total <= 0.0 {
+            return Err(QuantumError::InvalidProbability);
+        }
+        let outcome = rng.gen::<f64>() * total < p_one;
+
+        let norm = if outcome { p_one } else { p_zero }.sqrt();
+        for (i, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if (i & mask != 0) == outcome {
+                *amplitude /= norm;
+            } else {
+                *amplitude = Complex64::new(0.0, 0.0);
+            }
+        }
+
+        self.isolate_qubit(qubit);
+        self.classical_outcomes.push(outcome);
+        Ok(outcome)
+    }
+
+    // Outcomes of measure_qubit/reset calls, in the order they happened
+    pub fn classical_outcomes(&self) -> &[bool] {
+        &self.classical_outcomes
+    }
+
+    // Applies the gate if the condition holds on `clbits`, a classical
+    // register as QuantumCircuit::run fills it. Conditioning on this state's
+    // own outcomes means passing classical_outcomes(), where a bit's index is
+    // the order it was measured in.
+    pub fn apply_classically_controlled(
+        &mut self,
+        condition: &MeasurementRef,
+        clbits: &[bool],
+        gate: QuantumGate,
+        target: usize,
+    ) -> Result<bool, QuantumError> {
+        let fire = condition.evaluate(clbits)?;
+        if fire {
+            self.apply_gate(gate, target)?;
+        }
+        Ok(fire)
+    }
+
+    // Measures the qubit and flips it back to |0> if the outcome was 1
+    pub fn reset<R: Rng + ?Sized>(
+        &mut self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<bool, QuantumError> {
+        let outcome = self.measure_qubit(qubit, rng)?;
+        if outcome {
+            self.apply_gate(QuantumGate::PauliX, qubit)?;
+        }
+        Ok(outcome)
+    }
+
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes.iter().map(|a| a.norm_sqr()).collect()
+    }
+
+    pub fn sample_counts<R: Rng + ?Sized>(&self, shots: usize, rng: &mut R) -> HashMap<usize, usize> {
+        let mut cumulative = Vec::with_capacity(self.amplitudes.len());
+        let mut total = 0.0;
+        for p in self.probabilities() {
+            total += p;
+            cumulative.push(total);
+        }
+
+        let mut counts = HashMap::new();
+        for _ in 0..shots {
+            let r = rng.gen::<f64>() * total;
+            let outcome = cumulative
+                .partition_point(|&c| c <= r)
+                .min(cumulative.len() - 1);
+            *counts.entry(outcome).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
     pub fn apply_error_correction(&mut self, code: ErrorCorrectionCode) -> Result<(), QuantumError> {
         let syndrome = self.compute_error_syndrome(&code)?;
         let correction = code.compute_recovery_operation(&syndrome)?;
-        self.apply_recovery_operation(correction)
+        correction.apply(self)
     }
 
     fn compute_error_syndrome(&self, code: &ErrorCorrectionCode) -> Result<ErrorSyndrome, QuantumError> {
@@ -75,18 +558,33 @@ impl QuantumState {
         Ok(syndrome)
     }
 
-    fn measure_stabilizer(&self, stabilizer: &Stabilizer) -> Result<bool, QuantumError> {
-        let mut state = self.clone();
-        for (qubit, pauli) in stabilizer.iter() {
-            match pauli {
-                PauliOperator::X => state.apply_gate(QuantumGate::PauliX, *qubit)?,
-                PauliOperator::Y => state.apply_gate(QuantumGate::PauliY, *qubit)?,
-                PauliOperator::Z => state.apply_gate(QuantumGate::PauliZ, *qubit)?,
+    // Phenomenological syndrome measurement: the ideal syndrome with each bit
+    // reported wrongly with probability `flip_probability`, as from a faulty
+    // ancilla or readout
+    pub fn measure_syndrome<R: Rng + ?Sized>(
+        &self,
+        code: &ErrorCorrectionCode,
+        flip_probability: f64,
+        rng: &mut R,
+    ) -> Result<ErrorSyndrome, QuantumError> {
+        let mut syndrome = self
+            .compute_error_syndrome(code)?
+            .with_measurement_error(flip_probability);
+        for i in 0..code.get_stabilizers().len() {
+            if rng.gen::<f64>() < flip_probability {
+                syndrome.flip_bit(i);
             }
         }
+        Ok(syndrome)
+    }
+
+    // Set when the state lies in the -1 eigenspace of the stabilizer
+    fn measure_stabilizer(&self, stabilizer: &Stabilizer) -> Result<bool, QuantumError> {
+        let mut state = self.clone();
+        stabilizer.pauli().apply(&mut state)?;
         
         let overlap = state.compute_overlap(self)?;
-        Ok(overlap.re > 0.0)
+        Ok(overlap.re < 0.0)
     }
 
     fn compute_overlap(&self, other: &Self) -> Result<Complex64, QuantumError> {
@@ -103,20 +601,54 @@ impl QuantumState {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ErrorSyndrome {
     bits: BitVec,
     size: usize,
+    // Probability that each reported bit is the wrong outcome, and the bits
+    // that actually were flipped when measuring
+    measurement_error: f64,
+    flips: BitVec,
 }
 
 impl ErrorSyndrome {
     pub fn new(size: usize) -> Self {
         Self {
-            bits: BitVec::from_elem(size, false),
+            bits: BitVec::repeat(false, size),
             size,
+            measurement_error: 0.0,
+            flips: BitVec::repeat(false, size),
         }
     }
 
+    pub fn with_measurement_error(mut self, probability: f64) -> Self {
+        self.measurement_error = probability;
+        self
+    }
+
+    pub fn measurement_error(&self) -> f64 {
+        self.measurement_error
+    }
+
+    // Reports the bit as the opposite of what was measured
+    pub fn flip_bit(&mut self, index: usize) {
+        if index < self.size {
+            let bit = self.bits[index];
+            self.bits.set(index, !bit);
+            let flipped = self.flips[index];
+            self.flips.set(index, !flipped);
+        }
+    }
+
+    pub fn flipped_bits(&self) -> Vec<bool> {
+        self.flips.iter().by_vals().collect()
+    }
+
+    // The syndrome as it would have been measured without readout faults
+    pub fn ideal(&self) -> Vec<bool> {
+        (0..self.size).map(|i| self.bits[i] ^ self.flips[i]).collect()
+    }
+
     pub fn set_bit(&mut self, index: usize, value: bool) {
         if index < self.size {
             self.bits.set(index, value);
@@ -132,26 +664,76 @@ impl ErrorSyndrome {
     }
 
     pub fn to_vec(&self) -> Vec<bool> {
-        self.bits.to_vec()
+        self.bits.iter().by_vals().collect()
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Stabilizer {
-    operators: Vec<(usize, PauliOperator)>,
+    operator: PauliString,
 }
 
 impl Stabilizer {
     pub fn new(operators: Vec<(usize, PauliOperator)>) -> Self {
-        Self { operators }
+        Self::from_pauli(PauliString::from_sparse(&operators))
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &(usize, PauliOperator)> {
-        self.operators.iter()
+    pub fn from_pauli(operator: PauliString) -> Self {
+        Self { operator }
     }
+
+    pub fn pauli(&self) -> &PauliString {
+        &self.operator
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, PauliOperator)> + '_ {
+        self.operator.iter()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperatorType {
+    X,
+    Z,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogicalOperator {
+    operator: PauliString,
+    operator_type: OperatorType,
+}
+
+impl LogicalOperator {
+    pub fn new(operators: Vec<(usize, PauliOperator)>, operator_type: OperatorType) -> Self {
+        Self {
+            operator: PauliString::from_sparse(&operators),
+            operator_type,
+        }
+    }
+
+    pub fn pauli(&self) -> &PauliString {
+        &self.operator
+    }
+
+    pub fn operator_type(&self) -> OperatorType {
+        self.operator_type
+    }
+
+    pub fn apply(&self, state: &mut QuantumState) -> Result<(), QuantumError> {
+        self.operator.apply(state)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum MeasurementBasis {
+    Computational,
+    Bell,
+    Magic,
+    // Measurement in the basis {U|k>} on the lowest qubits
+    Arbitrary(Unitary),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PauliOperator {
     X,
     Y,
@@ -166,4 +748,16 @@ pub enum QuantumGate {
     PauliZ,
     Phase(f64),
     CNOT(usize),
+    Rx(f64),
+    Ry(f64),
+    Rz(f64),
+    SqrtX,
+    SqrtXDagger,
+    U3(f64, f64, f64),
+    Swap(usize),
+    MultiControlledX(Vec<usize>),
+    // Fused single-qubit unitary
+    Unitary1(Matrix2),
+    // Fused two-qubit unitary on (other, target), basis index 2 * b_other + b_target
+    Unitary2(usize, Matrix4),
 }
\ No newline at end of file
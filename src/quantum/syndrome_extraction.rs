@@ -0,0 +1,257 @@
+use super::*;
+use rand::Rng;
+use std::f64::consts::FRAC_PI_2;
+
+// How each stabilizer is measured. Bare uses one ancilla, so a single fault on
+// it can spread to several data qubits; Shor couples every data qubit to its
+// own qubit of a verified cat state; Flag adds one qubit that catches the
+// ancilla faults that would spread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyndromeExtraction {
+    Bare,
+    Shor,
+    Flag,
+}
+
+// Ancilla-based measurement of every stabilizer of a code. Data qubits are
+// 0..n and the ancillas follow them, reset and reused from one stabilizer to
+// the next.
+#[derive(Clone, Debug)]
+pub struct SyndromeCircuit {
+    scheme: SyndromeExtraction,
+    num_data_qubits: usize,
+    circuit: QuantumCircuit,
+    syndrome: Vec<MeasurementRef>,
+    flags: Vec<Option<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyndromeOutcome {
+    pub syndrome: Vec<bool>,
+    // Flag raised (Flag) or cat-state verification failed (Shor), per stabilizer
+    pub flags: Vec<bool>,
+    pub clbits: Vec<bool>,
+}
+
+impl SyndromeOutcome {
+    pub fn is_flagged(&self) -> bool {
+        self.flags.iter().any(|&flag| flag)
+    }
+}
+
+// Pauli on `target` controlled by `control`: CNOT, conjugated by H for Z and
+// by S for Y
+fn add_controlled_pauli(
+    circuit: &mut QuantumCircuit,
+    control: usize,
+    target: usize,
+    pauli: PauliOperator,
+) {
+    let (before, after) = match pauli {
+        PauliOperator::X => (None, None),
+        PauliOperator::Y => (
+            Some(QuantumGate::Phase(-FRAC_PI_2)),
+            Some(QuantumGate::Phase(FRAC_PI_2)),
+        ),
+        PauliOperator::Z => (Some(QuantumGate::Hadamard), Some(QuantumGate::Hadamard)),
+    };
+    if let Some(gate) = before {
+        circuit.add_gate(gate, target);
+    }
+    circuit.add_gate(QuantumGate::CNOT(control), target);
+    if let Some(gate) = after {
+        circuit.add_gate(gate, target);
+    }
+}
+
+impl ErrorCorrectionCode {
+    pub fn syndrome_circuit(&self, scheme: SyndromeExtraction) -> SyndromeCircuit {
+        let n = self.num_qubits();
+        let max_weight = self
+            .get_stabilizers()
+            .iter()
+            .map(|s| s.pauli().weight())
+            .max()
+            .unwrap_or(0);
+        let num_ancillas = match scheme {
+            SyndromeExtraction::Bare => 1,
+            SyndromeExtraction::Shor => max_weight + 1,
+            SyndromeExtraction::Flag => 2,
+        };
+
+        let mut circuit = QuantumCircuit::new(n + num_ancillas);
+        let mut syndrome = Vec::new();
+        let mut flags = Vec::new();
+        let mut next_clbit = 0;
+        let mut measure = |circuit: &mut QuantumCircuit, qubit: usize| {
+            circuit.add_measurement(qubit, next_clbit);
+            next_clbit += 1;
+            next_clbit - 1
+        };
+
+        for stabilizer in self.get_stabilizers() {
+            let support: Vec<(usize, PauliOperator)> = stabilizer.iter().collect();
+            for ancilla in n..n + num_ancillas {
+                circuit.add_reset(ancilla);
+            }
+
+            match scheme {
+                SyndromeExtraction::Bare => {
+                    let ancilla = n;
+                    circuit.add_gate(QuantumGate::Hadamard, ancilla);
+                    for &(qubit, pauli) in &support {
+                        add_controlled_pauli(&mut circuit, ancilla, qubit, pauli);
+                    }
+                    circuit.add_gate(QuantumGate::Hadamard, ancilla);
+                    syndrome.push(MeasurementRef::Bit(measure(&mut circuit, ancilla)));
+                    flags.push(None);
+                }
+                SyndromeExtraction::Shor => {
+                    // Cat state on n..n + w, its end-to-end parity checked on the
+                    // last ancilla before any data qubit is touched
+                    let cat: Vec<usize> = (n..n + support.len()).collect();
+                    let verifier = n + max_weight;
+                    if let Some(&first) = cat.first() {
+                        circuit.add_gate(QuantumGate::Hadamard, first);
+                        for pair in cat.windows(2) {
+                            circuit.add_gate(QuantumGate::CNOT(pair[0]), pair[1]);
+                        }
+                        circuit.add_gate(QuantumGate::CNOT(first), verifier);
+                        circuit.add_gate(QuantumGate::CNOT(cat[cat.len() - 1]), verifier);
+                    }
+                    flags.push(Some(measure(&mut circuit, verifier)));
+
+                    for (&ancilla, &(qubit, pauli)) in cat.iter().zip(&support) {
+                        add_controlled_pauli(&mut circuit, ancilla, qubit, pauli);
+                    }
+                    let bits = cat
+                        .iter()
+                        .map(|&ancilla| {
+                            circuit.add_gate(QuantumGate::Hadamard, ancilla);
+                            measure(&mut circuit, ancilla)
+                        })
+                        .collect();
+                    syndrome.push(MeasurementRef::Parity(bits));
+                }
+                SyndromeExtraction::Flag => {
+                    // The two ancilla-to-flag CNOTs cancel unless an X fault hits
+                    // the ancilla between them, where it would spread to two or
+                    // more data qubits
+                    let (ancilla, flag) = (n, n + 1);
+                    circuit.add_gate(QuantumGate::Hadamard, ancilla);
+                    for (i, &(qubit, pauli)) in support.iter().enumerate() {
+                        if i == 1 {
+                            circuit.add_gate(QuantumGate::CNOT(ancilla), flag);
+                        }
+                        if i + 1 == support.len() && i > 0 {
+                            circuit.add_gate(QuantumGate::CNOT(ancilla), flag);
+                        }
+                        add_controlled_pauli(&mut circuit, ancilla, qubit, pauli);
+                    }
+                    circuit.add_gate(QuantumGate::Hadamard, ancilla);
+                    syndrome.push(MeasurementRef::Bit(measure(&mut circuit, ancilla)));
+                    flags.push(Some(measure(&mut circuit, flag)));
+                }
+            }
+        }
+
+        SyndromeCircuit {
+            scheme,
+            num_data_qubits: n,
+            circuit,
+            syndrome,
+            flags,
+        }
+    }
+}
+
+impl SyndromeCircuit {
+    pub fn scheme(&self) -> SyndromeExtraction {
+        self.scheme
+    }
+
+    pub fn num_data_qubits(&self) -> usize {
+        self.num_data_qubits
+    }
+
+    // Data plus ancilla qubits the state must have
+    pub fn num_qubits(&self) -> usize {
+        self.circuit.num_qubits()
+    }
+
+    pub fn circuit(&self) -> &QuantumCircuit {
+        &self.circuit
+    }
+
+    // Classical bits (or parities of them) holding each stabilizer's outcome
+    pub fn syndrome_bits(&self) -> &[MeasurementRef] {
+        &self.syndrome
+    }
+
+    // Classical bit of each stabilizer's flag or cat verification, if any
+    pub fn flag_bits(&self) -> &[Option<usize>] {
+        &self.flags
+    }
+
+    pub fn interpret(&self, clbits: &[bool]) -> Result<SyndromeOutcome, QuantumError> {
+        let syndrome = self
+            .syndrome
+            .iter()
+            .map(|bit| bit.evaluate(clbits))
+            .collect::<Result<Vec<_>, _>>()?;
+        let flags = self
+            .flags
+            .iter()
+            .map(|flag| match flag {
+                Some(index) => MeasurementRef::Bit(*index).evaluate(clbits),
+                None => Ok(false),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SyndromeOutcome {
+            syndrome,
+            flags,
+            clbits: clbits.to_vec(),
+        })
+    }
+
+    // Runs the circuit operation by operation, applying `noise` to the state
+    // after each one (and its crosstalk after each gate), so faults can land
+    // anywhere in the extraction
+    pub fn run<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        noise: Option<&NoiseModel>,
+        rng: &mut R,
+    ) -> Result<SyndromeOutcome, QuantumError> {
+        self.run_at(state, noise, 0.0, rng)
+    }
+
+    // As run, with the noise layer after operation i taken at circuit time
+    // start + i, for noise models with schedules
+    pub fn run_at<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        noise: Option<&NoiseModel>,
+        start: f64,
+        rng: &mut R,
+    ) -> Result<SyndromeOutcome, QuantumError> {
+        if state.num_qubits < self.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let mut clbits = vec![false; self.circuit.num_clbits()];
+        for index in 0..self.circuit.len() {
+            self.circuit
+                .run_range(state, index..index + 1, &mut clbits, rng)?;
+            if let Some(noise) = noise {
+                if let CircuitOperation::Gate(GateInstruction { gate, target }) =
+                    &self.circuit.operations()[index]
+                {
+                    noise.apply_gate_noise(state, gate, *target, rng)?;
+                }
+                noise.apply_noise_at(state, start + index as f64, rng)?;
+            }
+        }
+        self.interpret(&clbits)
+    }
+}
@@ -0,0 +1,287 @@
+use super::*;
+use num_complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+
+// z for a two-sided 95% interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+// Failure rate over `shots` trials with a 95% Wilson score interval, which
+// stays inside [0, 1] and is sensible even with no failures observed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogicalErrorEstimate {
+    pub failures: usize,
+    pub shots: usize,
+    pub rate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl LogicalErrorEstimate {
+    pub fn from_counts(failures: usize, shots: usize) -> Self {
+        if shots == 0 {
+            return Self {
+                failures,
+                shots,
+                rate: 0.0,
+                lower: 0.0,
+                upper: 1.0,
+            };
+        }
+        let n = shots as f64;
+        let p = failures as f64 / n;
+        let z2 = CONFIDENCE_Z * CONFIDENCE_Z;
+        let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half_width =
+            CONFIDENCE_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        Self {
+            failures,
+            shots,
+            rate: p,
+            lower: (centre - half_width).max(0.0),
+            upper: (centre + half_width).min(1.0),
+        }
+    }
+}
+
+// Independent depolarizing error on each of `num_qubits` qubits: X, Y or Z
+// each with probability p / 3
+pub fn sample_depolarizing_error<R: Rng + ?Sized>(
+    num_qubits: usize,
+    probability: f64,
+    rng: &mut R,
+) -> PauliString {
+    let mut error = PauliString::identity(num_qubits);
+    for qubit in 0..num_qubits {
+        if rng.gen::<f64>() < probability {
+            let pauli = match rng.gen_range(0..3) {
+                0 => PauliOperator::X,
+                1 => PauliOperator::Y,
+                _ => PauliOperator::Z,
+            };
+            error.set(qubit, Some(pauli));
+        }
+    }
+    error
+}
+
+impl ErrorCorrectionCode {
+    // A decoded error is a logical failure when the residual anticommutes
+    // with some logical operator, or when the decoder has no correction
+    pub fn is_logical_failure(&self, error: &PauliString) -> bool {
+        let syndrome: Vec<bool> = self
+            .get_stabilizers()
+            .iter()
+            .map(|stabilizer| !stabilizer.pauli().commutes_with(error))
+            .collect();
+        match self.decode(&syndrome) {
+            Ok(correction) => {
+                let residual = error.multiply(&correction);
+                self.logical_operators()
+                    .iter()
+                    .any(|logical| !logical.pauli().commutes_with(&residual))
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+// Memory experiment on the state vector. A code state is prepared with one
+// ideal round of bare syndrome extraction, then `rounds` rounds run with
+// `noise_model` applied after every operation (its schedules timed from the
+// first noisy round), then one ideal round whose syndrome is trusted. The
+// whole history is decoded at once, and a shot fails when the corrected state
+// keeps less than half its overlap with the prepared one or the decoder finds
+// no correction.
+pub fn estimate_logical_error_rate<R: Rng + ?Sized>(
+    code: &ErrorCorrectionCode,
+    noise_model: &NoiseModel,
+    rounds: usize,
+    shots: usize,
+    rng: &mut R,
+) -> Result<LogicalErrorEstimate, QuantumError> {
+    let extraction = code.syndrome_circuit(SyndromeExtraction::Bare);
+    let seeds: Vec<u64> = (0..shots).map(|_| rng.gen()).collect();
+    let outcomes = seeds
+        .par_iter()
+        .map(|&seed| {
+            let mut shot_rng = ChaCha20Rng::seed_from_u64(seed);
+            memory_shot(code, &extraction, noise_model, rounds, &mut shot_rng)
+        })
+        .collect::<Result<Vec<bool>, QuantumError>>()?;
+    let failures = outcomes.into_iter().filter(|&failed| failed).count();
+    Ok(LogicalErrorEstimate::from_counts(failures, shots))
+}
+
+fn memory_shot<R: Rng + ?Sized>(
+    code: &ErrorCorrectionCode,
+    extraction: &SyndromeCircuit,
+    noise_model: &NoiseModel,
+    rounds: usize,
+    rng: &mut R,
+) -> Result<bool, QuantumError> {
+    let mut state = QuantumState::new(extraction.num_qubits())?;
+    let initial = extraction.run(&mut state, None, rng)?;
+    pauli_with_syndrome(code, &initial.syndrome)?.apply(&mut state)?;
+    reset_ancillas(&mut state, extraction, rng)?;
+    let prepared = state.clone();
+
+    let mut history = SyndromeHistory::new();
+    for round in 0..rounds {
+        let start = (round * extraction.circuit().len()) as f64;
+        let outcome = extraction.run_at(&mut state, Some(noise_model), start, rng)?;
+        history.push_round(outcome.syndrome);
+    }
+    history.push_round(extraction.run(&mut state, None, rng)?.syndrome);
+
+    let correction = match code.decode_history(&history) {
+        Ok(correction) => correction,
+        Err(QuantumError::UnknownSyndrome) => return Ok(true),
+        Err(error) => return Err(error),
+    };
+    correction.apply(&mut state)?;
+    reset_ancillas(&mut state, extraction, rng)?;
+
+    let overlap: Complex64 = prepared
+        .amplitudes
+        .iter()
+        .zip(&state.amplitudes)
+        .map(|(a, b)| a.conj() * b)
+        .sum();
+    Ok(overlap.norm_sqr() < 0.5)
+}
+
+fn reset_ancillas<R: Rng + ?Sized>(
+    state: &mut QuantumState,
+    extraction: &SyndromeCircuit,
+    rng: &mut R,
+) -> Result<(), QuantumError> {
+    for qubit in extraction.num_data_qubits()..extraction.num_qubits() {
+        state.reset(qubit, rng)?;
+    }
+    Ok(())
+}
+
+// Some Pauli with the given syndrome, which moves a state with that syndrome
+// back into the code space: ω(s_i, v) = syndrome_i, solved over GF(2)
+fn pauli_with_syndrome(
+    code: &ErrorCorrectionCode,
+    syndrome: &[bool],
+) -> Result<PauliString, QuantumError> {
+    let n = code.num_qubits();
+    let rows: Vec<Vec<bool>> = code
+        .get_stabilizers()
+        .iter()
+        .map(|stabilizer| {
+            let row = stabilizer.pauli().to_symplectic(n);
+            row[n..].iter().chain(&row[..n]).copied().collect()
+        })
+        .collect();
+    let v = gf2::solve(&rows, syndrome, 2 * n).ok_or(QuantumError::UnknownSyndrome)?;
+    PauliString::from_xz(v[..n].to_vec(), v[n..].to_vec(), 0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThresholdPoint {
+    pub distance: usize,
+    pub physical_error_rate: f64,
+    pub estimate: LogicalErrorEstimate,
+}
+
+// Code-capacity Monte Carlo sweep: depolarizing errors on the data qubits,
+// perfect syndromes, decoded with the code's own decoder
+#[derive(Clone, Debug)]
+pub struct ThresholdSweep {
+    distances: Vec<usize>,
+    error_rates: Vec<f64>,
+    shots: usize,
+}
+
+impl ThresholdSweep {
+    pub fn new(distances: Vec<usize>, error_rates: Vec<f64>) -> Self {
+        Self {
+            distances,
+            error_rates,
+            shots: 10_000,
+        }
+    }
+
+    pub fn with_shots(mut self, shots: usize) -> Self {
+        self.shots = shots;
+        self
+    }
+
+    // `family` builds the code for each distance, e.g.
+    // ErrorCorrectionCode::new_surface_code. Shots run in parallel, each with
+    // its own RNG seeded from `rng`, so a seeded sweep is reproducible.
+    pub fn run<F, R>(&self, family: F, rng: &mut R) -> Result<ThresholdResults, QuantumError>
+    where
+        F: Fn(usize) -> Result<ErrorCorrectionCode, QuantumError>,
+        R: Rng + ?Sized,
+    {
+        let mut points = Vec::new();
+        for &distance in &self.distances {
+            let code = family(distance)?;
+            for &physical_error_rate in &self.error_rates {
+                let seeds: Vec<u64> = (0..self.shots).map(|_| rng.gen()).collect();
+                let failures = seeds
+                    .par_iter()
+                    .filter(|&&seed| {
+                        let mut shot_rng = ChaCha20Rng::seed_from_u64(seed);
+                        let error = sample_depolarizing_error(
+                            code.num_qubits(),
+                            physical_error_rate,
+                            &mut shot_rng,
+                        );
+                        code.is_logical_failure(&error)
+                    })
+                    .count();
+                points.push(ThresholdPoint {
+                    distance,
+                    physical_error_rate,
+                    estimate: LogicalErrorEstimate::from_counts(failures, self.shots),
+                });
+            }
+        }
+        Ok(ThresholdResults { points })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ThresholdResults {
+    pub points: Vec<ThresholdPoint>,
+}
+
+impl ThresholdResults {
+    pub fn curve(&self, distance: usize) -> Vec<&ThresholdPoint> {
+        let mut curve: Vec<&ThresholdPoint> = self
+            .points
+            .iter()
+            .filter(|point| point.distance == distance)
+            .collect();
+        curve.sort_by(|a, b| a.physical_error_rate.total_cmp(&b.physical_error_rate));
+        curve
+    }
+
+    // Physical error rate where the two largest distances' curves cross,
+    // linearly interpolated; None when they do not cross within the sweep
+    pub fn estimated_threshold(&self) -> Option<f64> {
+        let mut distances: Vec<usize> = self.points.iter().map(|p| p.distance).collect();
+        distances.sort_unstable();
+        distances.dedup();
+        let (&large, rest) = distances.split_last()?;
+        let &small = rest.last()?;
+
+        let (small, large) = (self.curve(small), self.curve(large));
+        let gaps: Vec<(f64, f64)> = small
+            .iter()
+            .zip(&large)
+            .map(|(s, l)| (s.physical_error_rate, l.estimate.rate - s.estimate.rate))
+            .collect();
+        gaps.windows(2).find_map(|pair| {
+            let ((p0, g0), (p1, g1)) = (pair[0], pair[1]);
+            (g0 < 0.0 && g1 >= 0.0).then(|| p0 + (p1 - p0) * -g0 / (g1 - g0))
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use super::*;
+use rand::Rng;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InjectedError {
+    // A Pauli error, on register qubits
+    Pauli(PauliString),
+    // A branch of a channel that is not a Pauli, by its Kraus index, e.g. the
+    // jump of amplitude damping
+    Kraus(usize),
+    Leakage,
+    Seepage,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseEvent {
+    pub timestep: usize,
+    pub qubits: Vec<usize>,
+    pub error: InjectedError,
+}
+
+// Errors actually injected during a run, for comparing against what a
+// decoder inferred. Branches that leave the state alone are not recorded:
+// identity Paulis, and Kraus index 0, which every built-in channel uses for
+// its no-error branch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoiseTrace {
+    timestep: usize,
+    events: Vec<NoiseEvent>,
+}
+
+impl NoiseTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Timestep new events are recorded at
+    pub fn timestep(&self) -> usize {
+        self.timestep
+    }
+
+    pub fn step(&mut self) {
+        self.timestep += 1;
+    }
+
+    pub fn events(&self) -> &[NoiseEvent] {
+        &self.events
+    }
+
+    pub fn events_at(&self, timestep: usize) -> impl Iterator<Item = &NoiseEvent> + '_ {
+        self.events.iter().filter(move |e| e.timestep == timestep)
+    }
+
+    pub fn events_on(&self, qubit: usize) -> impl Iterator<Item = &NoiseEvent> + '_ {
+        self.events
+            .iter()
+            .filter(move |e| e.qubits.contains(&qubit))
+    }
+
+    pub fn record(&mut self, qubits: Vec<usize>, error: InjectedError) {
+        self.events.push(NoiseEvent {
+            timestep: self.timestep,
+            qubits,
+            error,
+        });
+    }
+
+    // Records branch `index` of `channel` on `targets`
+    pub(crate) fn record_branch(
+        &mut self,
+        channel: &QuantumChannelOp,
+        targets: &[usize],
+        index: usize,
+    ) {
+        let error = match channel.kraus_pauli(index) {
+            Some(pauli) if pauli.is_identity() => return,
+            Some(pauli) => {
+                let operators: Vec<(usize, PauliOperator)> =
+                    pauli.iter().map(|(q, op)| (targets[q], op)).collect();
+                InjectedError::Pauli(PauliString::from_sparse(&operators))
+            }
+            None if index == 0 => return,
+            None => InjectedError::Kraus(index),
+        };
+        self.record(targets.to_vec(), error);
+    }
+
+    // Product of every Pauli error injected, up to phase; the other events
+    // have no Pauli equivalent and are left out
+    pub fn net_pauli(&self) -> PauliString {
+        self.events
+            .iter()
+            .filter_map(|e| match &e.error {
+                InjectedError::Pauli(pauli) => Some(pauli),
+                _ => None,
+            })
+            .fold(PauliString::identity(0), |net, pauli| net.multiply(pauli))
+    }
+
+    // What is left after `correction`; a decoding failure shows up as a
+    // residual outside the stabilizer group
+    pub fn residual(&self, correction: &PauliString) -> PauliString {
+        self.net_pauli().multiply(correction)
+    }
+
+    // True when every injected error was a Pauli, so net_pauli is the whole
+    // story
+    pub fn is_pauli_only(&self) -> bool {
+        self.events
+            .iter()
+            .all(|e| matches!(e.error, InjectedError::Pauli(_)))
+    }
+
+    pub fn clear(&mut self) {
+        self.timestep = 0;
+        self.events.clear();
+    }
+}
+
+impl NoiseModel {
+    // apply_noise, recording into `trace` at its current timestep
+    pub fn apply_noise_traced<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        trace: &mut NoiseTrace,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.apply_layer(state, Some(trace), rng)
+    }
+
+    pub fn apply_noise_at_traced<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        time: f64,
+        trace: &mut NoiseTrace,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.apply_layer_at(state, time, Some(trace), rng)
+    }
+
+    pub fn apply_gate_noise_traced<R: Rng + ?Sized>(
+        &self,
+        state: &mut QuantumState,
+        gate: &QuantumGate,
+        target: usize,
+        trace: &mut NoiseTrace,
+        rng: &mut R,
+    ) -> Result<(), QuantumError> {
+        self.gate_noise(state, gate, target, Some(trace), rng)
+    }
+}
+
+impl NoisyExecutor {
+    // Records every injected error, one timestep per noise layer; colored
+    // dephasing is a continuous rotation and is not recorded
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(NoiseTrace::new());
+        self
+    }
+
+    pub fn trace(&self) -> Option<&NoiseTrace> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<NoiseTrace> {
+        self.trace.as_mut().map(std::mem::take)
+    }
+}
@@ -0,0 +1,137 @@
+use super::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+
+// Mean of each observable over the trajectories, with its standard error
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrajectoryEstimate {
+    pub means: Vec<f64>,
+    pub standard_errors: Vec<f64>,
+    pub trajectories: usize,
+}
+
+// Monte Carlo wavefunction simulation of a noisy circuit. Each trajectory
+// runs the circuit on a pure state and unravels every channel of the noise
+// model: gate noise after each gate, then a noise layer after each operation
+// at circuit time equal to its index. Averages over trajectories converge to
+// the density-matrix result at statevector memory cost.
+#[derive(Clone, Debug)]
+pub struct TrajectorySimulator {
+    circuit: QuantumCircuit,
+    noise: NoiseModel,
+    trajectories: usize,
+}
+
+impl TrajectorySimulator {
+    pub fn new(circuit: QuantumCircuit, noise: NoiseModel) -> Self {
+        Self {
+            circuit,
+            noise,
+            trajectories: 1000,
+        }
+    }
+
+    pub fn with_trajectories(mut self, trajectories: usize) -> Self {
+        self.trajectories = trajectories;
+        self
+    }
+
+    // One trajectory from `initial`: the final state and the classical register
+    pub fn run_trajectory<R: Rng + ?Sized>(
+        &self,
+        initial: &QuantumState,
+        rng: &mut R,
+    ) -> Result<(QuantumState, Vec<bool>), QuantumError> {
+        if initial.num_qubits < self.circuit.num_qubits() {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let mut state = initial.clone();
+        let mut clbits = vec![false; self.circuit.num_clbits()];
+        for (index, operation) in self.circuit.operations().iter().enumerate() {
+            self.circuit
+                .run_range(&mut state, index..index + 1, &mut clbits, rng)?;
+            if let CircuitOperation::Gate(GateInstruction { gate, target }) = operation {
+                self.noise
+                    .apply_gate_noise(&mut state, gate, *target, rng)?;
+            }
+            self.noise.apply_noise_at(&mut state, index as f64, rng)?;
+        }
+        Ok((state, clbits))
+    }
+
+    // Runs `f` on the end of every trajectory, in parallel, each trajectory
+    // with its own generator seeded from `rng`
+    fn map_trajectories<T, R, F>(
+        &self,
+        initial: &QuantumState,
+        rng: &mut R,
+        f: F,
+    ) -> Result<Vec<T>, QuantumError>
+    where
+        T: Send,
+        R: Rng + ?Sized,
+        F: Fn(QuantumState, Vec<bool>) -> Result<T, QuantumError> + Sync,
+    {
+        if self.trajectories == 0 {
+            return Err(QuantumError::InvalidDimension);
+        }
+        let seeds: Vec<u64> = (0..self.trajectories).map(|_| rng.gen()).collect();
+        seeds
+            .par_iter()
+            .map(|&seed| {
+                let mut trajectory_rng = ChaCha20Rng::seed_from_u64(seed);
+                let (state, clbits) = self.run_trajectory(initial, &mut trajectory_rng)?;
+                f(state, clbits)
+            })
+            .collect()
+    }
+
+    // <P> for each Hermitian Pauli observable, averaged over trajectories
+    pub fn expectation_values<R: Rng + ?Sized>(
+        &self,
+        initial: &QuantumState,
+        observables: &[PauliString],
+        rng: &mut R,
+    ) -> Result<TrajectoryEstimate, QuantumError> {
+        if observables.iter().any(|p| !p.is_hermitian()) {
+            return Err(QuantumError::InvalidPauliString);
+        }
+        let samples = self.map_trajectories(initial, rng, |state, _| {
+            observables
+                .iter()
+                .map(|p| Ok(p.expectation(&state)?.re))
+                .collect::<Result<Vec<f64>, QuantumError>>()
+        })?;
+
+        let n = samples.len() as f64;
+        let mut means = Vec::with_capacity(observables.len());
+        let mut standard_errors = Vec::with_capacity(observables.len());
+        for i in 0..observables.len() {
+            let mean = samples.iter().map(|s| s[i]).sum::<f64>() / n;
+            let variance = if samples.len() > 1 {
+                samples.iter().map(|s| (s[i] - mean).powi(2)).sum::<f64>() / (n - 1.0)
+            } else {
+                0.0
+            };
+            means.push(mean);
+            standard_errors.push((variance / n).sqrt());
+        }
+        Ok(TrajectoryEstimate {
+            means,
+            standard_errors,
+            trajectories: samples.len(),
+        })
+    }
+
+    // Average of the final pure states; only for registers small enough to
+    // hold a density matrix
+    pub fn average_density<R: Rng + ?Sized>(
+        &self,
+        initial: &QuantumState,
+        rng: &mut R,
+    ) -> Result<DensityMatrix, QuantumError> {
+        let ensemble = self.map_trajectories(initial, rng, |state, _| Ok((1.0, state)))?;
+        DensityMatrix::from_ensemble(&ensemble)
+    }
+}
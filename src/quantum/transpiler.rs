@@ -0,0 +1,461 @@
+use super::*;
+use num_complex::Complex64;
+use std::collections::HashSet;
+use std::f64::consts::{FRAC_PI_4, PI};
+
+const ANGLE_TOLERANCE: f64 = 1e-10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NativeGate {
+    CNOT,
+    Rz,
+    SqrtX,
+    Hadamard,
+    T,
+}
+
+#[derive(Clone, Debug)]
+pub struct NativeGateSet {
+    gates: HashSet<NativeGate>,
+}
+
+impl NativeGateSet {
+    pub fn new(gates: &[NativeGate]) -> Self {
+        Self {
+            gates: gates.iter().copied().collect(),
+        }
+    }
+
+    // {CNOT, Rz, sqrt-X}, the usual superconducting-hardware basis
+    pub fn rotation_basis() -> Self {
+        Self::new(&[NativeGate::CNOT, NativeGate::Rz, NativeGate::SqrtX])
+    }
+
+    pub fn clifford_t() -> Self {
+        Self::new(&[NativeGate::CNOT, NativeGate::Hadamard, NativeGate::T])
+    }
+
+    pub fn contains(&self, gate: NativeGate) -> bool {
+        self.gates.contains(&gate)
+    }
+
+    fn supports_rotations(&self) -> bool {
+        self.contains(NativeGate::Rz) && self.contains(NativeGate::SqrtX)
+    }
+
+    fn supports_clifford_t(&self) -> bool {
+        self.contains(NativeGate::Hadamard) && self.contains(NativeGate::T)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiscreteGate {
+    H,
+    T,
+    TDagger,
+}
+
+impl DiscreteGate {
+    fn matrix(&self) -> Matrix2 {
+        match self {
+            DiscreteGate::H => QuantumGate::Hadamard.matrix().unwrap(),
+            DiscreteGate::T => unitary::phase(FRAC_PI_4),
+            DiscreteGate::TDagger => unitary::phase(-FRAC_PI_4),
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        match self {
+            DiscreteGate::H => DiscreteGate::H,
+            DiscreteGate::T => DiscreteGate::TDagger,
+            DiscreteGate::TDagger => DiscreteGate::T,
+        }
+    }
+
+    fn cancels(&self, next: &Self) -> bool {
+        self.inverse() == *next
+    }
+}
+
+type Word = Vec<DiscreteGate>;
+
+enum LoweredGate {
+    Single(usize, Matrix2),
+    Cnot(usize, usize),
+    NonUnitary(CircuitOperation),
+}
+
+pub struct Transpiler {
+    native: NativeGateSet,
+    approximation_depth: usize,
+    base_sequence_length: usize,
+    base_net: Vec<(Matrix2, Word)>,
+}
+
+impl Transpiler {
+    pub fn new(native: NativeGateSet) -> Result<Self, QuantumError> {
+        if !native.contains(NativeGate::CNOT)
+            || !(native.supports_rotations() || native.supports_clifford_t())
+        {
+            return Err(QuantumError::UnsupportedGateSet);
+        }
+
+        let mut transpiler = Self {
+            native,
+            approximation_depth: 2,
+            base_sequence_length: 12,
+            base_net: Vec::new(),
+        };
+        transpiler.build_base_net();
+        Ok(transpiler)
+    }
+
+    pub fn with_approximation_depth(mut self, depth: usize) -> Self {
+        self.approximation_depth = depth;
+        self
+    }
+
+    pub fn with_base_sequence_length(mut self, length: usize) -> Self {
+        self.base_sequence_length = length;
+        self.build_base_net();
+        self
+    }
+
+    pub fn transpile(&self, circuit: &QuantumCircuit) -> Result<QuantumCircuit, QuantumError> {
+        let mut lowered = Vec::new();
+        for operation in circuit.operations() {
+            match operation {
+                CircuitOperation::Gate(instruction) => {
+                    self.lower(&instruction.gate, instruction.target, &mut lowered)?
+                }
+                CircuitOperation::ClassicallyControlled {
+                    condition,
+                    gate,
+                    target,
+                } => {
+                    // Lower the gate on its own and guard every native gate with the same condition
+                    let mut single = QuantumCircuit::new(circuit.num_qubits());
+                    single.add_gate(gate.clone(), *target);
+                    for native in self.transpile(&single)?.instructions() {
+                        lowered.push(LoweredGate::NonUnitary(
+                            CircuitOperation::ClassicallyControlled {
+                                condition: condition.clone(),
+                                gate: native.gate.clone(),
+                                target: native.target,
+                            },
+                        ));
+                    }
+                }
+                other => lowered.push(LoweredGate::NonUnitary(other.clone())),
+            }
+        }
+
+        let mut output = QuantumCircuit::new(circuit.num_qubits());
+        for gate in lowered {
+            match gate {
+                LoweredGate::Cnot(control, target) => {
+                    output.add_gate(QuantumGate::CNOT(control), target);
+                }
+                LoweredGate::Single(target, matrix) => {
+                    self.emit_single(target, &matrix, &mut output);
+                }
+                LoweredGate::NonUnitary(operation) => {
+                    output.push_operation(operation);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    // Approximation error of the discrete Clifford+T sequence for a single-qubit unitary
+    pub fn approximation_error(&self, matrix: &Matrix2) -> f64 {
+        if self.native.supports_rotations() {
+            return 0.0;
+        }
+        let (approximation, _) = self.solovay_kitaev(
+            &unitary::to_special_unitary(matrix),
+            self.approximation_depth,
+        );
+        unitary::phase_distance2(matrix, &approximation)
+    }
+
+    fn lower(
+        &self,
+        gate: &QuantumGate,
+        target: usize,
+        out: &mut Vec<LoweredGate>,
+    ) -> Result<(), QuantumError> {
+        match gate {
+            QuantumGate::CNOT(control) => out.push(LoweredGate::Cnot(*control, target)),
+            QuantumGate::Swap(other) => {
+                out.push(LoweredGate::Cnot(*other, target));
+                out.push(LoweredGate::Cnot(target, *other));
+                out.push(LoweredGate::Cnot(*other, target));
+            }
+            QuantumGate::MultiControlledX(controls) => {
+                let x = QuantumGate::PauliX.matrix().unwrap();
+                self.lower_controlled(controls, target, &x, out);
+            }
+            single => {
+                let matrix = single.matrix().ok_or(QuantumError::InvalidGate)?;
+                out.push(LoweredGate::Single(target, matrix));
+            }
+        }
+        Ok(())
+    }
+
+    // Barenco et al. Lemma 7.5: C^n(U) from C(V), C^{n-1}(X) and C^{n-1}(V) with V^2 = U
+    fn lower_controlled(
+        &self,
+        controls: &[usize],
+        target: usize,
+        u: &Matrix2,
+        out: &mut Vec<LoweredGate>,
+    ) {
+        match controls.split_last() {
+            None => out.push(LoweredGate::Single(target, *u)),
+            Some((control, [])) => self.lower_single_controlled(*control, target, u, out),
+            Some((last, rest)) => {
+                let x = QuantumGate::PauliX.matrix().unwrap();
+                let v = unitary::sqrt2(u);
+                self.lower_single_controlled(*last, target, &v, out);
+                self.lower_controlled(rest, *last, &x, out);
+                self.lower_single_controlled(*last, target, &unitary::adjoint2(&v), out);
+                self.lower_controlled(rest, *last, &x, out);
+                self.lower_controlled(rest, target, &v, out);
+            }
+        }
+    }
+
+    // ABC decomposition: C(U) = Phase(alpha)_c . A X B X C
+    fn lower_single_controlled(
+        &self,
+        control: usize,
+        target: usize,
+        u: &Matrix2,
+        out: &mut Vec<LoweredGate>,
+    ) {
+        let (alpha, beta, gamma, delta) = unitary::zyz_decompose(u);
+        let a = unitary::mul2(&unitary::rz(beta), &unitary::ry(gamma / 2.0));
+        let b = unitary::mul2(
+            &unitary::ry(-gamma / 2.0),
+            &unitary::rz(-(delta + beta) / 2.0),
+        );
+        let c = unitary::rz((delta - beta) / 2.0);
+
+        out.push(LoweredGate::Single(target, c));
+        out.push(LoweredGate::Cnot(control, target));
+        out.push(LoweredGate::Single(target, b));
+        out.push(LoweredGate::Cnot(control, target));
+        out.push(LoweredGate::Single(target, a));
+        out.push(LoweredGate::Single(control, unitary::phase(alpha)));
+    }
+
+    fn emit_single(&self, target: usize, matrix: &Matrix2, output: &mut QuantumCircuit) {
+        if unitary::phase_distance2(matrix, &unitary::identity2()) < ANGLE_TOLERANCE {
+            return;
+        }
+
+        if self.native.supports_rotations() {
+            // U3(theta, phi, lambda) = Rz(phi + pi) SX Rz(theta + pi) SX Rz(lambda) up to phase
+            let (_, phi, theta, lambda) = unitary::zyz_decompose(matrix);
+            if theta.abs() < ANGLE_TOLERANCE {
+                Self::emit_rz(target, phi + lambda, output);
+            } else {
+                Self::emit_rz(target, lambda, output);
+                output.add_gate(QuantumGate::SqrtX, target);
+                Self::emit_rz(target, theta + PI, output);
+                output.add_gate(QuantumGate::SqrtX, target);
+                Self::emit_rz(target, phi + PI, output);
+            }
+        } else {
+            let (_, word) = self.solovay_kitaev(
+                &unitary::to_special_unitary(matrix),
+                self.approximation_depth,
+            );
+            for gate in word {
+                let native = match gate {
+                    DiscreteGate::H => QuantumGate::Hadamard,
+                    DiscreteGate::T => QuantumGate::Phase(FRAC_PI_4),
+                    DiscreteGate::TDagger => QuantumGate::Phase(-FRAC_PI_4),
+                };
+                output.add_gate(native, target);
+            }
+        }
+    }
+
+    fn emit_rz(target: usize, theta: f64, output: &mut QuantumCircuit) {
+        let theta = unitary::normalize_angle(theta);
+        if theta.abs() > ANGLE_TOLERANCE {
+            output.add_gate(QuantumGate::Rz(theta), target);
+        }
+    }
+
+    fn build_base_net(&mut self) {
+        self.base_net.clear();
+        if self.native.supports_rotations() {
+            return;
+        }
+
+        let mut frontier = vec![(unitary::identity2(), Word::new())];
+        self.base_net.push((unitary::identity2(), Word::new()));
+
+        for _ in 0..self.base_sequence_length {
+            let mut next = Vec::new();
+            for (matrix, word) in &frontier {
+                for gate in [DiscreteGate::H, DiscreteGate::T, DiscreteGate::TDagger] {
+                    if word.last().map_or(false, |last| last.cancels(&gate)) {
+                        continue;
+                    }
+                    let mut extended = word.clone();
+                    extended.push(gate);
+                    next.push((unitary::mul2(&gate.matrix(), matrix), extended));
+                }
+            }
+            self.base_net.extend(next.iter().cloned());
+            frontier = next;
+        }
+    }
+
+    fn basic_approximation(&self, u: &Matrix2) -> (Matrix2, Word) {
+        self.base_net
+            .iter()
+            .min_by(|a, b| {
+                unitary::phase_distance2(u, &a.0)
+                    .partial_cmp(&unitary::phase_distance2(u, &b.0))
+                    .unwrap()
+            })
+            .cloned()
+            .unwrap_or((unitary::identity2(), Word::new()))
+    }
+
+    fn solovay_kitaev(&self, u: &Matrix2, depth: usize) -> (Matrix2, Word) {
+        if depth == 0 {
+            return self.basic_approximation(u);
+        }
+
+        let (previous, previous_word) = self.solovay_kitaev(u, depth - 1);
+        let delta = unitary::mul2(u, &unitary::adjoint2(&previous));
+        let (v, w) = Self::balanced_commutator(&delta);
+        let (v_approx, v_word) = self.solovay_kitaev(&v, depth - 1);
+        let (w_approx, w_word) = self.solovay_kitaev(&w, depth - 1);
+
+        let commutator = unitary::mul2(
+            &unitary::mul2(&v_approx, &w_approx),
+            &unitary::mul2(&unitary::adjoint2(&v_approx), &unitary::adjoint2(&w_approx)),
+        );
+        let matrix = unitary::mul2(&commutator, &previous);
+
+        // Time order is the reverse of the matrix product
+        let mut word = previous_word;
+        word.extend(Self::inverse_word(&w_word));
+        word.extend(Self::inverse_word(&v_word));
+        word.extend(w_word);
+        word.extend(v_word);
+
+        (matrix, word)
+    }
+
+    fn inverse_word(word: &[DiscreteGate]) -> Word {
+        word.iter().rev().map(|g| g.inverse()).collect()
+    }
+
+    // Finds V, W with V W V^dagger W^dagger = delta (Dawson-Nielsen balanced commutator)
+    fn balanced_commutator(delta: &Matrix2) -> (Matrix2, Matrix2) {
+        let (theta, axis) = Self::rotation_parameters(delta);
+        let s = (theta / 2.0).sin();
+        let x = ((1.0 - (1.0 - s * s).max(0.0).sqrt()) / 2.0).sqrt();
+        let phi = 2.0 * x.sqrt().min(1.0).asin();
+
+        let v = unitary::rx(phi);
+        let w = unitary::ry(phi);
+        let commutator = unitary::mul2(
+            &unitary::mul2(&v, &w),
+            &unitary::mul2(&unitary::adjoint2(&v), &unitary::adjoint2(&w)),
+        );
+        let (_, commutator_axis) = Self::rotation_parameters(&commutator);
+
+        let similarity = Self::rotation_between(&commutator_axis, &axis);
+        let conjugate = |m: &Matrix2| {
+            unitary::mul2(
+                &unitary::mul2(&similarity, m),
+                &unitary::adjoint2(&similarity),
+            )
+        };
+
+        (conjugate(&v), conjugate(&w))
+    }
+
+    // Returns (theta, n) with u = cos(theta/2) I - i sin(theta/2) n.sigma, up to sign
+    fn rotation_parameters(u: &Matrix2) -> (f64, [f64; 3]) {
+        let mut su = unitary::to_special_unitary(u);
+        if unitary::trace2(&su).re < 0.0 {
+            su = unitary::scale2(&su, Complex64::new(-1.0, 0.0));
+        }
+
+        let cos_half = (unitary::trace2(&su).re / 2.0).clamp(-1.0, 1.0);
+        let theta = 2.0 * cos_half.acos();
+        let v = [-su[0][1].im, -su[0][1].re, -su[0][0].im];
+        let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+
+        if norm < ANGLE_TOLERANCE {
+            (0.0, [0.0, 0.0, 1.0])
+        } else {
+            (theta, [v[0] / norm, v[1] / norm, v[2] / norm])
+        }
+    }
+
+    fn axis_rotation(axis: &[f64; 3], angle: f64) -> Matrix2 {
+        let c = (angle / 2.0).cos();
+        let s = (angle / 2.0).sin();
+        [
+            [
+                Complex64::new(c, -s * axis[2]),
+                Complex64::new(-s * axis[1], -s * axis[0]),
+            ],
+            [
+                Complex64::new(s * axis[1], -s * axis[0]),
+                Complex64::new(c, s * axis[2]),
+            ],
+        ]
+    }
+
+    fn rotation_between(from: &[f64; 3], to: &[f64; 3]) -> Matrix2 {
+        let cross = [
+            from[1] * to[2] - from[2] * to[1],
+            from[2] * to[0] - from[0] * to[2],
+            from[0] * to[1] - from[1] * to[0],
+        ];
+        let dot = from[0] * to[0] + from[1] * to[1] + from[2] * to[2];
+        let sin = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+
+        if sin < ANGLE_TOLERANCE {
+            if dot > 0.0 {
+                return unitary::identity2();
+            }
+            // Antiparallel: rotate by pi around any orthogonal axis
+            let helper = if from[0].abs() < 0.9 {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            let orthogonal = [
+                from[1] * helper[2] - from[2] * helper[1],
+                from[2] * helper[0] - from[0] * helper[2],
+                from[0] * helper[1] - from[1] * helper[0],
+            ];
+            let norm =
+                (orthogonal[0].powi(2) + orthogonal[1].powi(2) + orthogonal[2].powi(2)).sqrt();
+            let axis = [
+                orthogonal[0] / norm,
+                orthogonal[1] / norm,
+                orthogonal[2] / norm,
+            ];
+            return Self::axis_rotation(&axis, PI);
+        }
+
+        let axis = [cross[0] / sin, cross[1] / sin, cross[2] / sin];
+        Self::axis_rotation(&axis, sin.atan2(dot))
+    }
+}
@@ -0,0 +1,251 @@
+use super::*;
+use std::f64::consts::FRAC_PI_2;
+
+// Representatives of the single-qubit Cliffords modulo Paulis, as gate
+// sequences; uniform layers of these are the candidates for single-block gates
+fn clifford_cosets() -> Vec<Vec<QuantumGate>> {
+    let (h, s) = (QuantumGate::Hadamard, QuantumGate::Phase(FRAC_PI_2));
+    vec![
+        vec![],
+        vec![h.clone()],
+        vec![s.clone()],
+        vec![h.clone(), s.clone()],
+        vec![s.clone(), h.clone()],
+        vec![h.clone(), s, h],
+    ]
+}
+
+// The blocks a logical gate touches, with the gate rewritten on local logical
+// indices (block position p holding local logicals p k .. (p + 1) k)
+fn localize(gate: LogicalGate, k: usize) -> (Vec<usize>, LogicalGate) {
+    match gate {
+        LogicalGate::X(l) => (vec![l / k], LogicalGate::X(l % k)),
+        LogicalGate::Z(l) => (vec![l / k], LogicalGate::Z(l % k)),
+        LogicalGate::Hadamard(l) => (vec![l / k], LogicalGate::Hadamard(l % k)),
+        LogicalGate::Phase(l) => (vec![l / k], LogicalGate::Phase(l % k)),
+        LogicalGate::CNOT(c, t) if c / k == t / k => (vec![c / k], LogicalGate::CNOT(c % k, t % k)),
+        LogicalGate::CNOT(c, t) => (vec![c / k, t / k], LogicalGate::CNOT(c % k, k + t % k)),
+    }
+}
+
+impl ErrorCorrectionCode {
+    // Per-qubit gates implementing `gate` on a register of code blocks, when
+    // some transversal circuit does: a uniform layer of one single-qubit
+    // Clifford (pairwise CNOTs between blocks for a CNOT), followed by Paulis
+    // that repair the signs of the stabilizers and logicals. Every candidate
+    // is checked against the whole stabilizer group and every logical of the
+    // blocks involved, so logicals the gate should leave alone are verified
+    // too. None when no candidate works.
+    pub fn transversal_implementation(
+        &self,
+        gate: LogicalGate,
+    ) -> Result<Option<Vec<GateInstruction>>, QuantumError> {
+        let n = self.num_qubits();
+        let k = self.num_logical_qubits();
+        if k == 0 {
+            return Err(QuantumError::InvalidQubitIndex);
+        }
+        if let LogicalGate::CNOT(c, t) = gate {
+            if c == t {
+                return Err(QuantumError::InvalidQubitIndex);
+            }
+        }
+
+        let (blocks, local) = localize(gate, k);
+        let place = |instructions: Vec<GateInstruction>| -> Vec<GateInstruction> {
+            instructions
+                .into_iter()
+                .map(|GateInstruction { gate, target }| {
+                    let shift = |q: usize| blocks[q / n] * n + q % n;
+                    let gate = match gate {
+                        QuantumGate::CNOT(control) => QuantumGate::CNOT(shift(control)),
+                        other => other,
+                    };
+                    GateInstruction {
+                        gate,
+                        target: shift(target),
+                    }
+                })
+                .collect()
+        };
+
+        // Logical Paulis are transversal in any stabilizer code
+        let pauli = match local {
+            LogicalGate::X(l) => Some(self.logical_x(l)?),
+            LogicalGate::Z(l) => Some(self.logical_z(l)?),
+            _ => None,
+        };
+        if let Some(pauli) = pauli {
+            return Ok(Some(place(pauli_layer(&pauli))));
+        }
+
+        let candidates: Vec<Vec<GateInstruction>> = match local {
+            LogicalGate::CNOT(_, _) if blocks.len() == 2 => vec![(0..n)
+                .map(|q| GateInstruction {
+                    gate: QuantumGate::CNOT(q),
+                    target: n + q,
+                })
+                .collect()],
+            LogicalGate::CNOT(_, _) => Vec::new(),
+            _ => clifford_cosets()
+                .into_iter()
+                .map(|sequence| {
+                    (0..n)
+                        .flat_map(|q| {
+                            sequence.iter().map(move |gate| GateInstruction {
+                                gate: gate.clone(),
+                                target: q,
+                            })
+                        })
+                        .collect()
+                })
+                .collect(),
+        };
+
+        let register = TransversalCheck::new(self, blocks.len(), local)?;
+        for candidate in candidates {
+            if let Some(fix) = register.sign_fix(&candidate)? {
+                let mut instructions = candidate;
+                instructions.extend(pauli_layer(&fix));
+                return Ok(Some(place(instructions)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn is_transversal(&self, gate: LogicalGate) -> Result<bool, QuantumError> {
+        Ok(self.transversal_implementation(gate)?.is_some())
+    }
+}
+
+fn pauli_layer(pauli: &PauliString) -> Vec<GateInstruction> {
+    pauli
+        .iter()
+        .map(|(target, operator)| GateInstruction {
+            gate: operator.gate(),
+            target,
+        })
+        .collect()
+}
+
+// Stabilizers of a few blocks side by side, and what the gate should do to
+// each of their logical operators
+struct TransversalCheck {
+    num_qubits: usize,
+    stabilizers: Vec<PauliString>,
+    generators: Vec<Vec<bool>>,
+    // (logical, its image under the ideal gate)
+    logicals: Vec<(PauliString, PauliString)>,
+}
+
+impl TransversalCheck {
+    fn new(
+        code: &ErrorCorrectionCode,
+        num_blocks: usize,
+        gate: LogicalGate,
+    ) -> Result<Self, QuantumError> {
+        let n = code.num_qubits();
+        let num_qubits = num_blocks * n;
+        let stabilizers: Vec<PauliString> = (0..num_blocks)
+            .flat_map(|block| {
+                code.get_stabilizers()
+                    .iter()
+                    .map(move |s| s.pauli().shifted(block * n))
+            })
+            .collect();
+        let generators = stabilizers
+            .iter()
+            .map(|s| s.to_symplectic(num_qubits))
+            .collect();
+
+        let mut logicals = Vec::new();
+        for l in 0..num_blocks * code.num_logical_qubits() {
+            let (x, z) = (code.logical_x(l)?, code.logical_z(l)?);
+            let (x_image, z_image) = match gate {
+                LogicalGate::Hadamard(t) if t == l => (z.clone(), x.clone()),
+                // S X S^dagger = Y = i X Z
+                LogicalGate::Phase(t) if t == l => {
+                    let i = PauliString::from_xz(Vec::new(), Vec::new(), 1)?;
+                    (x.multiply(&z).multiply(&i), z.clone())
+                }
+                LogicalGate::CNOT(c, t) if c == l => (x.multiply(&code.logical_x(t)?), z.clone()),
+                LogicalGate::CNOT(c, t) if t == l => (x.clone(), z.multiply(&code.logical_z(c)?)),
+                _ => (x.clone(), z.clone()),
+            };
+            logicals.push((x, x_image));
+            logicals.push((z, z_image));
+        }
+
+        Ok(Self {
+            num_qubits,
+            stabilizers,
+            generators,
+            logicals,
+        })
+    }
+
+    // U Q U^dagger for a Hermitian Pauli Q
+    fn image(
+        &self,
+        pauli: &PauliString,
+        circuit: &[GateInstruction],
+    ) -> Result<PauliString, QuantumError> {
+        let mut row = tableau_row(pauli, self.num_qubits);
+        for GateInstruction { gate, target } in circuit {
+            row.conjugate_by(gate, *target)?;
+        }
+        Ok(row.to_pauli())
+    }
+
+    // Some(sign) when `pauli` is `target` times a stabilizer up to that sign
+    // (true meaning -1), None when it is not
+    fn relative_sign(&self, pauli: &PauliString, target: &PauliString) -> Option<bool> {
+        let quotient = pauli.multiply(target);
+        let columns = gf2::transpose(&self.generators, 2 * self.num_qubits);
+        let coefficients = gf2::solve(
+            &columns,
+            &quotient.to_symplectic(self.num_qubits),
+            self.generators.len(),
+        )?;
+        let element = self
+            .stabilizers
+            .iter()
+            .zip(&coefficients)
+            .filter(|(_, &used)| used)
+            .fold(PauliString::identity(0), |product, (s, _)| {
+                product.multiply(s)
+            });
+        match (quotient.phase() + 4 - element.phase()) % 4 {
+            0 => Some(false),
+            2 => Some(true),
+            _ => None,
+        }
+    }
+
+    // The Pauli P such that P U implements the gate, if U does up to signs:
+    // P flips exactly the images whose sign is wrong
+    fn sign_fix(&self, circuit: &[GateInstruction]) -> Result<Option<PauliString>, QuantumError> {
+        let n = self.num_qubits;
+        let mut rows = Vec::new();
+        let mut flips = Vec::new();
+        let requirements = self
+            .stabilizers
+            .iter()
+            .map(|s| (s, s))
+            .chain(self.logicals.iter().map(|(l, target)| (l, target)));
+        for (pauli, target) in requirements {
+            let image = self.image(pauli, circuit)?;
+            let Some(flip) = self.relative_sign(&image, target) else {
+                return Ok(None);
+            };
+            // <P, image> = P_x . image_z + P_z . image_x
+            let v = image.to_symplectic(n);
+            rows.push(v[n..].iter().chain(&v[..n]).copied().collect());
+            flips.push(flip);
+        }
+
+        Ok(gf2::solve(&rows, &flips, 2 * n)
+            .map(|v| PauliString::from_xz(v[..n].to_vec(), v[n..].to_vec(), 0))
+            .transpose()?)
+    }
+}
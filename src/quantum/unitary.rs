@@ -0,0 +1,447 @@
+use super::*;
+use num_complex::Complex64;
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+pub type Matrix2 = [[Complex64; 2]; 2];
+pub type Matrix4 = [[Complex64; 4]; 4];
+
+const EPSILON: f64 = 1e-12;
+
+pub fn identity2() -> Matrix2 {
+    let one = Complex64::new(1.0, 0.0);
+    let zero = Complex64::new(0.0, 0.0);
+    [[one, zero], [zero, one]]
+}
+
+pub fn mul2(a: &Matrix2, b: &Matrix2) -> Matrix2 {
+    let mut result = [[Complex64::new(0.0, 0.0); 2]; 2];
+    for i in 0..2 {
+        for j in 0..2 {
+            result[i][j] = a[i][0] * b[0][j] + a[i][1] * b[1][j];
+        }
+    }
+    result
+}
+
+pub fn adjoint2(a: &Matrix2) -> Matrix2 {
+    [
+        [a[0][0].conj(), a[1][0].conj()],
+        [a[0][1].conj(), a[1][1].conj()],
+    ]
+}
+
+pub fn scale2(a: &Matrix2, factor: Complex64) -> Matrix2 {
+    [
+        [a[0][0] * factor, a[0][1] * factor],
+        [a[1][0] * factor, a[1][1] * factor],
+    ]
+}
+
+pub fn det2(a: &Matrix2) -> Complex64 {
+    a[0][0] * a[1][1] - a[0][1] * a[1][0]
+}
+
+pub fn trace2(a: &Matrix2) -> Complex64 {
+    a[0][0] + a[1][1]
+}
+
+// Operator distance that ignores global phase
+pub fn phase_distance2(a: &Matrix2, b: &Matrix2) -> f64 {
+    let overlap = trace2(&mul2(&adjoint2(a), b)).norm() / 2.0;
+    (1.0 - overlap.min(1.0)).max(0.0).sqrt()
+}
+
+pub fn to_special_unitary(a: &Matrix2) -> Matrix2 {
+    scale2(a, det2(a).sqrt().inv())
+}
+
+pub fn identity4() -> Matrix4 {
+    let mut result = [[Complex64::new(0.0, 0.0); 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        row[i] = Complex64::new(1.0, 0.0);
+    }
+    result
+}
+
+pub fn mul4(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut result = [[Complex64::new(0.0, 0.0); 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            result[i][j] = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+pub fn adjoint4(a: &Matrix4) -> Matrix4 {
+    let mut result = [[Complex64::new(0.0, 0.0); 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            result[i][j] = a[j][i].conj();
+        }
+    }
+    result
+}
+
+// high (x) low, with `high` acting on bit 1 of the two-qubit basis index
+pub fn kron2(high: &Matrix2, low: &Matrix2) -> Matrix4 {
+    let mut result = [[Complex64::new(0.0, 0.0); 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            result[i][j] = high[i >> 1][j >> 1] * low[i & 1][j & 1];
+        }
+    }
+    result
+}
+
+// Exchanges the roles of the two qubits in a two-qubit matrix
+pub fn swap_qubits4(a: &Matrix4) -> Matrix4 {
+    let permute = |i: usize| ((i & 1) << 1) | (i >> 1);
+    let mut result = [[Complex64::new(0.0, 0.0); 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            result[permute(i)][permute(j)] = a[i][j];
+        }
+    }
+    result
+}
+
+pub fn rx(theta: f64) -> Matrix2 {
+    let c = Complex64::new((theta / 2.0).cos(), 0.0);
+    let s = Complex64::new(0.0, -(theta / 2.0).sin());
+    [[c, s], [s, c]]
+}
+
+pub fn ry(theta: f64) -> Matrix2 {
+    let c = Complex64::new((theta / 2.0).cos(), 0.0);
+    let s = Complex64::new((theta / 2.0).sin(), 0.0);
+    [[c, -s], [s, c]]
+}
+
+pub fn rz(theta: f64) -> Matrix2 {
+    [
+        [
+            Complex64::from_polar(1.0, -theta / 2.0),
+            Complex64::new(0.0, 0.0),
+        ],
+        [
+            Complex64::new(0.0, 0.0),
+            Complex64::from_polar(1.0, theta / 2.0),
+        ],
+    ]
+}
+
+pub fn phase(phi: f64) -> Matrix2 {
+    [
+        [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+        [Complex64::new(0.0, 0.0), Complex64::from_polar(1.0, phi)],
+    ]
+}
+
+pub fn u3(theta: f64, phi: f64, lambda: f64) -> Matrix2 {
+    let c = (theta / 2.0).cos();
+    let s = (theta / 2.0).sin();
+    [
+        [Complex64::new(c, 0.0), -Complex64::from_polar(s, lambda)],
+        [
+            Complex64::from_polar(s, phi),
+            Complex64::from_polar(c, phi + lambda),
+        ],
+    ]
+}
+
+// Returns (alpha, beta, gamma, delta) with U = e^{i alpha} Rz(beta) Ry(gamma) Rz(delta)
+pub fn zyz_decompose(u: &Matrix2) -> (f64, f64, f64, f64) {
+    let alpha = det2(u).arg() / 2.0;
+    let v = scale2(u, Complex64::from_polar(1.0, -alpha));
+
+    let gamma = 2.0 * v[1][0].norm().atan2(v[0][0].norm());
+    let (sum, difference) = if v[1][0].norm() < EPSILON {
+        (2.0 * v[1][1].arg(), 0.0)
+    } else if v[0][0].norm() < EPSILON {
+        (0.0, 2.0 * v[1][0].arg())
+    } else {
+        (2.0 * v[1][1].arg(), 2.0 * v[1][0].arg())
+    };
+
+    let beta = (sum + difference) / 2.0;
+    let delta = (sum - difference) / 2.0;
+    (alpha, beta, gamma, delta)
+}
+
+// Principal square root of a 2x2 unitary
+pub fn sqrt2(u: &Matrix2) -> Matrix2 {
+    let root_det = det2(u).sqrt();
+    let candidates = [root_det, -root_det];
+
+    for s in candidates {
+        let t = (trace2(u) + s * 2.0).sqrt();
+        if t.norm() > EPSILON {
+            let shifted = [[u[0][0] + s, u[0][1]], [u[1][0], u[1][1] + s]];
+            return scale2(&shifted, t.inv());
+        }
+    }
+
+    identity2()
+}
+
+impl QuantumGate {
+    pub fn matrix(&self) -> Option<Matrix2> {
+        let zero = Complex64::new(0.0, 0.0);
+        let one = Complex64::new(1.0, 0.0);
+        let h = Complex64::new(FRAC_1_SQRT_2, 0.0);
+
+        match self {
+            QuantumGate::Hadamard => Some([[h, h], [h, -h]]),
+            QuantumGate::PauliX => Some([[zero, one], [one, zero]]),
+            QuantumGate::PauliY => Some([
+                [zero, Complex64::new(0.0, -1.0)],
+                [Complex64::new(0.0, 1.0), zero],
+            ]),
+            QuantumGate::PauliZ => Some([[one, zero], [zero, -one]]),
+            QuantumGate::Phase(phi) => Some(phase(*phi)),
+            QuantumGate::Rx(theta) => Some(rx(*theta)),
+            QuantumGate::Ry(theta) => Some(ry(*theta)),
+            QuantumGate::Rz(theta) => Some(rz(*theta)),
+            QuantumGate::SqrtX => {
+                let a = Complex64::new(0.5, 0.5);
+                let b = Complex64::new(0.5, -0.5);
+                Some([[a, b], [b, a]])
+            }
+            QuantumGate::SqrtXDagger => {
+                let a = Complex64::new(0.5, -0.5);
+                let b = Complex64::new(0.5, 0.5);
+                Some([[a, b], [b, a]])
+            }
+            QuantumGate::U3(theta, phi, lambda) => Some(u3(*theta, *phi, *lambda)),
+            QuantumGate::Unitary1(matrix) => Some(*matrix),
+            _ => None,
+        }
+    }
+
+    pub fn dagger(&self) -> QuantumGate {
+        match self {
+            QuantumGate::Phase(phi) => QuantumGate::Phase(-phi),
+            QuantumGate::Rx(theta) => QuantumGate::Rx(-theta),
+            QuantumGate::Ry(theta) => QuantumGate::Ry(-theta),
+            QuantumGate::Rz(theta) => QuantumGate::Rz(-theta),
+            QuantumGate::SqrtX => QuantumGate::SqrtXDagger,
+            QuantumGate::SqrtXDagger => QuantumGate::SqrtX,
+            QuantumGate::U3(theta, phi, lambda) => QuantumGate::U3(-theta, -lambda, -phi),
+            QuantumGate::Unitary1(matrix) => QuantumGate::Unitary1(adjoint2(matrix)),
+            QuantumGate::Unitary2(other, matrix) => QuantumGate::Unitary2(*other, adjoint4(matrix)),
+            // Self-inverse
+            QuantumGate::Hadamard
+            | QuantumGate::PauliX
+            | QuantumGate::PauliY
+            | QuantumGate::PauliZ
+            | QuantumGate::CNOT(_)
+            | QuantumGate::Swap(_)
+            | QuantumGate::MultiControlledX(_) => self.clone(),
+        }
+    }
+
+    pub fn is_single_qubit(&self) -> bool {
+        self.matrix().is_some()
+    }
+
+    // Matrix on the pair (high, low) in the basis 2 * b_high + b_low, for gates
+    // acting only on those two qubits
+    pub fn two_qubit_matrix(&self, target: usize, high: usize, low: usize) -> Option<Matrix4> {
+        let zero = Complex64::new(0.0, 0.0);
+        let one = Complex64::new(1.0, 0.0);
+
+        let on_pair =
+            |other: usize| (other, target) == (high, low) || (other, target) == (low, high);
+        let orient = |matrix: Matrix4, other: usize| {
+            if other == high {
+                matrix
+            } else {
+                swap_qubits4(&matrix)
+            }
+        };
+
+        match self {
+            QuantumGate::CNOT(control) if on_pair(*control) => {
+                let mut cnot = identity4();
+                cnot[2] = [zero, zero, zero, one];
+                cnot[3] = [zero, zero, one, zero];
+                Some(orient(cnot, *control))
+            }
+            QuantumGate::Swap(other) if on_pair(*other) => {
+                let mut swap = identity4();
+                swap[1] = [zero, zero, one, zero];
+                swap[2] = [zero, one, zero, zero];
+                Some(swap)
+            }
+            QuantumGate::Unitary2(other, matrix) if on_pair(*other) => {
+                Some(orient(*matrix, *other))
+            }
+            gate => {
+                let matrix = gate.matrix()?;
+                if target == high {
+                    Some(kron2(&matrix, &identity2()))
+                } else if target == low {
+                    Some(kron2(&identity2(), &matrix))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn qubits(&self, target: usize) -> Vec<usize> {
+        match self {
+            QuantumGate::CNOT(control)
+            | QuantumGate::Swap(control)
+            | QuantumGate::Unitary2(control, _) => vec![*control, target],
+            QuantumGate::MultiControlledX(controls) => {
+                let mut qubits = controls.clone();
+                qubits.push(target);
+                qubits
+            }
+            _ => vec![target],
+        }
+    }
+}
+
+pub(crate) fn normalize_angle(theta: f64) -> f64 {
+    let wrapped = theta.rem_euclid(2.0 * PI);
+    if wrapped > PI {
+        wrapped - 2.0 * PI
+    } else {
+        wrapped
+    }
+}
+
+// Dense 2^n x 2^n unitary, row-major; column j is U|j>
+#[derive(Clone, Debug)]
+pub struct Unitary {
+    pub(crate) num_qubits: usize,
+    pub(crate) dimension: usize,
+    pub(crate) elements: Vec<Complex64>,
+}
+
+impl Unitary {
+    pub fn identity(num_qubits: usize) -> Self {
+        let dimension = 1 << num_qubits;
+        let mut elements = vec![Complex64::new(0.0, 0.0); dimension * dimension];
+        for i in 0..dimension {
+            elements[i * dimension + i] = Complex64::new(1.0, 0.0);
+        }
+        Self {
+            num_qubits,
+            dimension,
+            elements,
+        }
+    }
+
+    pub fn from_elements(
+        num_qubits: usize,
+        elements: Vec<Complex64>,
+    ) -> Result<Self, QuantumError> {
+        let dimension = 1 << num_qubits;
+        if elements.len() != dimension * dimension {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let unitary = Self {
+            num_qubits,
+            dimension,
+            elements,
+        };
+        if !unitary.is_unitary(1e-9) {
+            return Err(QuantumError::NotUnitary);
+        }
+        Ok(unitary)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Complex64 {
+        self.elements[row * self.dimension + column]
+    }
+
+    pub fn adjoint(&self) -> Self {
+        let n = self.dimension;
+        let mut elements = vec![Complex64::new(0.0, 0.0); n * n];
+        for i in 0..n {
+            for j in 0..n {
+                elements[j * n + i] = self.elements[i * n + j].conj();
+            }
+        }
+        Self {
+            num_qubits: self.num_qubits,
+            dimension: n,
+            elements,
+        }
+    }
+
+    pub fn compose(&self, other: &Self) -> Result<Self, QuantumError> {
+        if self.dimension != other.dimension {
+            return Err(QuantumError::DimensionMismatch);
+        }
+        let n = self.dimension;
+        let mut elements = vec![Complex64::new(0.0, 0.0); n * n];
+        for i in 0..n {
+            for k in 0..n {
+                let a = self.elements[i * n + k];
+                if a.norm_sqr() == 0.0 {
+                    continue;
+                }
+                for j in 0..n {
+                    elements[i * n + j] += a * other.elements[k * n + j];
+                }
+            }
+        }
+        Ok(Self {
+            num_qubits: self.num_qubits,
+            dimension: n,
+            elements,
+        })
+    }
+
+    pub fn is_unitary(&self, tolerance: f64) -> bool {
+        let n = self.dimension;
+        for i in 0..n {
+            for j in 0..n {
+                let mut inner = Complex64::new(0.0, 0.0);
+                for k in 0..n {
+                    inner += self.elements[k * n + i].conj() * self.elements[k * n + j];
+                }
+                let expected = if i == j { 1.0 } else { 0.0 };
+                if (inner - expected).norm() > tolerance {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl QuantumState {
+    // Applies a unitary acting on the lowest `unitary.num_qubits` qubits
+    pub fn apply_unitary(&mut self, unitary: &Unitary) -> Result<(), QuantumError> {
+        if unitary.num_qubits > self.num_qubits {
+            return Err(QuantumError::DimensionMismatch);
+        }
+
+        let n = unitary.dimension;
+        let mut amplitudes = vec![Complex64::new(0.0, 0.0); self.amplitudes.len()];
+        for (basis, amplitude) in self.amplitudes.iter().enumerate() {
+            if amplitude.norm_sqr() == 0.0 {
+                continue;
+            }
+            let (low, high) = (basis & (n - 1), basis & !(n - 1));
+            for row in 0..n {
+                amplitudes[high | row] += unitary.elements[row * n + low] * amplitude;
+            }
+        }
+        self.amplitudes = amplitudes;
+        let qubits: Vec<usize> = (0..unitary.num_qubits).collect();
+        self.merge_entanglement(&qubits);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+// Frozen reference vectors for cross-validating independent implementations.
+// Byte strings are lowercase hex; Ristretto points are compressed encodings and
+// scalars are little-endian canonical encodings.
+
+use crate::*;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use std::f64::consts::PI;
+
+pub struct HashToCurveVector {
+    pub input: &'static [u8],
+    pub point: &'static str,
+}
+
+pub const HASH_TO_CURVE: &[HashToCurveVector] = &[
+    HashToCurveVector {
+        input: b"",
+        point: "52686eed5d41e68e22922246e6c1d1fa4d042cbcc98d93d8a75ed890f9da6624",
+    },
+    HashToCurveVector {
+        input: b"abc",
+        point: "a0bc8c78afe7c337ad8fadeb819223b0dfc39c780994fae05a3175c051c6470a",
+    },
+    HashToCurveVector {
+        input: b"quantum-nizk",
+        point: "f6f46233b34ef0460bdcf8f9a488f30064628ba89965d7fc56ebefd4df4d7f3e",
+    },
+    HashToCurveVector {
+        input: b"statement-0001",
+        point: "74c803c44758a2e93dbe48e5945343a3457981e7d23ca274a8f41bd5a2780447",
+    },
+];
+
+// SNARK verifier transcript challenges, with no auxiliary points
+pub struct TranscriptChallengeVector {
+    pub statement: &'static [u8],
+    pub commitment: &'static str,
+    pub challenge: &'static str,
+}
+
+pub const TRANSCRIPT_CHALLENGES: &[TranscriptChallengeVector] = &[
+    TranscriptChallengeVector {
+        statement: b"statement-0",
+        commitment: "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+        challenge: "1406d893176e611252469fe29c1d6e78447ae33b402f86e5f4962c7f1afe4908",
+    },
+    TranscriptChallengeVector {
+        statement: b"statement-1",
+        commitment: "c0431e233239a850a811dbff2e208073b1530299f128808d28356df0b3932727",
+        challenge: "8a0dcdc2be17bdcdc573ae91f720f6a3dcc0b39ffaa547844597f1e3b36dcb0a",
+    },
+];
+
+// Syndromes of ErrorCorrectionCode::new_steane_code(), in stabilizer order
+pub struct SteaneSyndromeVector {
+    pub error: &'static [(usize, PauliOperator)],
+    pub syndrome: &'static [bool],
+}
+
+pub const STEANE_SYNDROMES: &[SteaneSyndromeVector] = &[
+    SteaneSyndromeVector {
+        error: &[],
+        syndrome: &[false, false, false, false, false, false],
+    },
+    SteaneSyndromeVector {
+        error: &[(0, PauliOperator::X)],
+        syndrome: &[false, false, false, true, false, false],
+    },
+    SteaneSyndromeVector {
+        error: &[(3, PauliOperator::Z)],
+        syndrome: &[false, false, true, false, false, false],
+    },
+    SteaneSyndromeVector {
+        error: &[(6, PauliOperator::Y)],
+        syndrome: &[true, true, true, true, true, true],
+    },
+    SteaneSyndromeVector {
+        error: &[(0, PauliOperator::X), (1, PauliOperator::X)],
+        syndrome: &[false, false, false, true, true, false],
+    },
+    SteaneSyndromeVector {
+        error: &[(2, PauliOperator::Y), (5, PauliOperator::Z)],
+        syndrome: &[true, false, true, true, true, false],
+    },
+];
+
+pub struct CliffordTableauVector {
+    pub name: &'static str,
+    pub circuit: fn() -> QuantumCircuit,
+    pub tableau: &'static [&'static str],
+}
+
+fn bell_circuit() -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(2);
+    circuit
+        .add_gate(QuantumGate::Hadamard, 0)
+        .add_gate(QuantumGate::CNOT(0), 1);
+    circuit
+}
+
+fn phase_hadamard_circuit() -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(1);
+    circuit
+        .add_gate(QuantumGate::Phase(PI / 2.0), 0)
+        .add_gate(QuantumGate::Hadamard, 0);
+    circuit
+}
+
+fn ghz3_circuit() -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(3);
+    circuit
+        .add_gate(QuantumGate::Hadamard, 0)
+        .add_gate(QuantumGate::CNOT(0), 1)
+        .add_gate(QuantumGate::CNOT(1), 2);
+    circuit
+}
+
+fn mixed3_circuit() -> QuantumCircuit {
+    let mut circuit = QuantumCircuit::new(3);
+    circuit
+        .add_gate(QuantumGate::Hadamard, 0)
+        .add_gate(QuantumGate::Phase(PI / 2.0), 1)
+        .add_gate(QuantumGate::CNOT(0), 2)
+        .add_gate(QuantumGate::Swap(1), 2)
+        .add_gate(QuantumGate::PauliZ, 0)
+        .add_gate(QuantumGate::Phase(-PI / 2.0), 1)
+        .add_gate(QuantumGate::PauliX, 2)
+        .add_gate(QuantumGate::CNOT(2), 1);
+    circuit
+}
+
+// Destabilizers then stabilizers, as produced by CliffordTableau::to_pauli_strings
+pub const CLIFFORD_TABLEAUS: &[CliffordTableauVector] = &[
+    CliffordTableauVector {
+        name: "bell",
+        circuit: bell_circuit,
+        tableau: &["+ZI", "+IX", "+XX", "+ZZ"],
+    },
+    CliffordTableauVector {
+        name: "phase-hadamard",
+        circuit: phase_hadamard_circuit,
+        tableau: &["-Y", "+X"],
+    },
+    CliffordTableauVector {
+        name: "ghz3",
+        circuit: ghz3_circuit,
+        tableau: &["+ZII", "+IXX", "+IIX", "+XXX", "+ZZI", "+IZZ"],
+    },
+    CliffordTableauVector {
+        name: "mixed3",
+        circuit: mixed3_circuit,
+        tableau: &["+ZII", "-IXY", "-IYZ", "+XYZ", "-IIZ", "+ZZZ"],
+    },
+];
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+// Regenerates every vector with this crate, returning a description of each mismatch
+pub fn verify_all() -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    for vector in HASH_TO_CURVE {
+        let point = to_hex(hash_to_curve(vector.input).compress().as_bytes());
+        if point != vector.point {
+            mismatches.push(format!("hash_to_curve({:?}) = {}", vector.input, point));
+        }
+    }
+
+    for vector in TRANSCRIPT_CHALLENGES {
+        let commitment = from_hex(vector.commitment)
+            .and_then(|bytes| CompressedRistretto::from_slice(&bytes).ok());
+        match commitment {
+            Some(commitment) => {
+                let challenge =
+                    to_hex(snark_challenge(vector.statement, &commitment, &[]).as_bytes());
+                if challenge != vector.challenge {
+                    mismatches.push(format!(
+                        "snark_challenge({:?}) = {}",
+                        vector.statement, challenge
+                    ));
+                }
+            }
+            None => mismatches.push(format!("malformed commitment for {:?}", vector.statement)),
+        }
+    }
+
+    let steane = ErrorCorrectionCode::new_steane_code();
+    for vector in STEANE_SYNDROMES {
+        let syndrome = steane.syndrome_of(vector.error);
+        if syndrome != vector.syndrome {
+            mismatches.push(format!(
+                "steane syndrome of {:?} = {:?}",
+                vector.error, syndrome
+            ));
+        }
+    }
+
+    for vector in CLIFFORD_TABLEAUS {
+        match CliffordTableau::from_circuit(&(vector.circuit)()) {
+            Ok(tableau) => {
+                let rows = tableau.to_pauli_strings();
+                if rows != vector.tableau {
+                    mismatches.push(format!("tableau {} = {:?}", vector.name, rows));
+                }
+            }
+            Err(error) => mismatches.push(format!("tableau {}: {:?}", vector.name, error)),
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
+
+    #[test]
+    fn basepoint_is_first_commitment() {
+        assert_eq!(
+            TRANSCRIPT_CHALLENGES[0].commitment,
+            to_hex(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes())
+        );
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        for vector in HASH_TO_CURVE {
+            assert_eq!(to_hex(&from_hex(vector.point).unwrap()), vector.point);
+        }
+    }
+
+    #[test]
+    fn frozen_vectors_match() {
+        assert_eq!(verify_all(), Vec::<String>::new());
+    }
+}